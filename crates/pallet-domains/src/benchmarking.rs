@@ -7,8 +7,8 @@ use crate::block_tree::{prune_receipt, BlockTreeNode};
 use crate::bundle_storage_fund::refund_storage_fee;
 use crate::domain_registry::DomainConfig;
 use crate::staking::{
-    do_convert_previous_epoch_deposits, do_reward_operators, do_slash_operators, OperatorConfig,
-    OperatorStatus,
+    do_convert_previous_epoch_deposits, do_reward_operators, do_slash_operators,
+    note_slash_evidence, DeferredSlash, OperatorConfig, OperatorStatus,
 };
 use crate::staking_epoch::{
     do_finalize_domain_current_epoch, do_finalize_domain_epoch_staking,
@@ -23,19 +23,34 @@ use frame_benchmarking::v2::*;
 use frame_support::assert_ok;
 use frame_support::traits::fungible::Mutate;
 use frame_support::traits::Hooks;
-use frame_support::weights::Weight;
 use frame_system::{Pallet as System, RawOrigin};
 use sp_core::crypto::UncheckedFrom;
+use sp_domains::inherents::{
+    RejectedBundle, RejectedBundleReason, RejectedBundleRecord, MAX_REJECTED_BUNDLES_PER_BLOCK,
+};
 use sp_domains::{
-    dummy_opaque_bundle, ConfirmedDomainBlock, DomainId, ExecutionReceipt, OperatorAllowList,
-    OperatorId, OperatorPublicKey, RuntimeType,
+    dummy_opaque_bundle, BadReceiptMismatch, ConfirmedDomainBlock, DomainId, ExecutionReceipt,
+    OperatorAllowList, OperatorId, OperatorPublicKey, PermissionedActionAllowedBy, RuntimeType,
+    SealedBundleHeader, MAX_OPERATOR_NAME_LENGTH, MAX_OPERATOR_WEBSITE_LENGTH,
 };
 use sp_domains_fraud_proof::fraud_proof::FraudProof;
-use sp_runtime::traits::{CheckedAdd, One, Zero};
+use sp_runtime::traits::{CheckedAdd, Hash, One, Zero};
+use sp_runtime::RuntimeAppPublic;
 use sp_std::collections::btree_set::BTreeSet;
 
 const SEED: u32 = 0;
 
+/// Upper bound of the bundles per block used in the benchmark component ranges.
+///
+/// The range bounds are evaluated when the benchmark is set up, `MaxBundlesPerBlock` is capped
+/// (saturating min) by this value to keep the setup of the worst case within a reasonable size,
+/// the weight functions are still called with `MaxBundlesPerBlock` by the pallet.
+const MAX_BUNDLES_PER_BLOCK_BENCHMARK_BOUND: u32 = 100;
+
+fn max_bundles_per_block<T: Config>() -> u32 {
+    T::MaxBundlesPerBlock::get().min(MAX_BUNDLES_PER_BLOCK_BENCHMARK_BOUND)
+}
+
 #[benchmarks]
 mod benchmarks {
     use super::*;
@@ -82,6 +97,7 @@ mod benchmarks {
                     Default::default(),
                 );
                 let receipt_hash = receipt.hash::<DomainHashingFor<T>>();
+                HeadDomainNumber::<T>::set(domain_id, domain_block_number);
                 HeadReceiptNumber::<T>::set(domain_id, receipt_block_number);
                 BlockTree::<T>::insert(domain_id, receipt_block_number, receipt_hash);
                 BlockTreeNodes::<T>::insert(
@@ -176,7 +192,7 @@ mod benchmarks {
         let fraud_proof = FraudProof::dummy_fraud_proof(domain_id, target_receipt_hash.unwrap());
 
         #[extrinsic_call]
-        submit_fraud_proof(RawOrigin::None, Box::new(fraud_proof));
+        submit_fraud_proof(RawOrigin::None, Box::new(fraud_proof), None);
 
         assert_eq!(Domains::<T>::head_receipt_number(domain_id), 0u32.into());
         assert_eq!(
@@ -185,9 +201,29 @@ mod benchmarks {
         );
     }
 
+    /// Benchmark the verification of the MMR proof of the consensus block that a fraud proof
+    /// carries when the consensus block hash is no longer available in the state
+    #[benchmark]
+    fn verify_consensus_block_mmr_proof() {
+        let (consensus_block_number, consensus_block_proof) =
+            T::MmrProofVerifier::consensus_block_mmr_proof_for_benchmarks()
+                .expect("MMR proof of the consensus block must be available for benchmarking");
+        let consensus_block_hash;
+
+        #[block]
+        {
+            consensus_block_hash = Domains::<T>::verify_consensus_block_mmr_proof(
+                consensus_block_number,
+                consensus_block_proof,
+            );
+        }
+
+        assert!(consensus_block_hash.is_ok());
+    }
+
     /// Benchmark prune bad ER and slash the submitter based on the number of submitter
     #[benchmark]
-    fn handle_bad_receipt(n: Linear<1, MAX_BUNLDE_PER_BLOCK>) {
+    fn handle_bad_receipt(n: Linear<1, { max_bundles_per_block::<T>() }>) {
         let minimum_nominator_stake = T::MinNominatorStake::get();
         let domain_id = register_domain::<T>();
         let mut operator_ids = Vec::new();
@@ -224,6 +260,18 @@ mod benchmarks {
                 .expect("prune bad receipt should success")
                 .expect("block tree node must exist");
 
+            note_slash_evidence::<T>(
+                &block_tree_node.operator_ids,
+                SlashEvidence {
+                    receipt_hash,
+                    domain_block_number: receipt_number,
+                    consensus_block_number: block_tree_node
+                        .execution_receipt
+                        .consensus_block_number,
+                    fraud_proof_hash: None,
+                    mismatch: BadReceiptMismatch::Unknown,
+                },
+            );
             do_slash_operators::<T>(
                 block_tree_node.operator_ids.into_iter(),
                 SlashedReason::BadExecutionReceipt(receipt_hash),
@@ -240,12 +288,88 @@ mod benchmarks {
         assert!(BlockTree::<T>::get(domain_id, receipt_number).is_none());
     }
 
+    /// Benchmark the lazy prune of a bad ER's descendant and the slash of its submitters in the
+    /// subsequent `submit_bundle` that brings the reverted head back to its domain block number,
+    /// based on the number of submitter
+    #[benchmark]
+    fn lazy_prune_bad_receipt(n: Linear<1, { max_bundles_per_block::<T>() }>) {
+        let minimum_nominator_stake = T::MinNominatorStake::get();
+        let domain_id = register_domain::<T>();
+        let mut operator_ids = Vec::new();
+        for i in 0..n {
+            let (_, operator_id) =
+                register_operator_with_seed::<T>(domain_id, i + 1, minimum_nominator_stake);
+            operator_ids.push(operator_id);
+        }
+        do_finalize_domain_current_epoch::<T>(domain_id)
+            .expect("finalize domain staking should success");
+
+        // Construct the descendant of a bad ER that is reverted by fraud proof, the bad ER at #1 is
+        // already pruned by the fraud proof and the head is brought back to #1 by a new ER
+        let head_receipt_number: DomainBlockNumberFor<T> = 1u32.into();
+        let stale_receipt_number = head_receipt_number + One::one();
+        let stale_receipt = ExecutionReceipt::dummy::<DomainHashingFor<T>>(
+            2u32.into(),
+            frame_system::Pallet::<T>::block_hash::<BlockNumberFor<T>>(2u32.into()),
+            stale_receipt_number,
+            Default::default(),
+        );
+        let stale_receipt_hash = stale_receipt.hash::<DomainHashingFor<T>>();
+        HeadDomainNumber::<T>::set(domain_id, stale_receipt_number);
+        HeadReceiptNumber::<T>::set(domain_id, head_receipt_number);
+        BlockTree::<T>::insert(domain_id, stale_receipt_number, stale_receipt_hash);
+        BlockTreeNodes::<T>::insert(
+            stale_receipt_hash,
+            BlockTreeNode {
+                execution_receipt: stale_receipt,
+                operator_ids: operator_ids.clone(),
+            },
+        );
+        for operator_id in operator_ids {
+            LatestSubmittedER::<T>::insert((domain_id, operator_id), stale_receipt_number);
+        }
+
+        #[block]
+        {
+            let block_tree_node = prune_receipt::<T>(domain_id, stale_receipt_number)
+                .expect("prune stale receipt should success")
+                .expect("block tree node must exist");
+
+            note_slash_evidence::<T>(
+                &block_tree_node.operator_ids,
+                SlashEvidence {
+                    receipt_hash: stale_receipt_hash,
+                    domain_block_number: stale_receipt_number,
+                    consensus_block_number: block_tree_node
+                        .execution_receipt
+                        .consensus_block_number,
+                    fraud_proof_hash: None,
+                    mismatch: BadReceiptMismatch::Unknown,
+                },
+            );
+            do_slash_operators::<T>(
+                block_tree_node.operator_ids.into_iter(),
+                SlashedReason::BadExecutionReceipt(stale_receipt_hash),
+            )
+            .expect("slash operator should success");
+        }
+
+        assert_eq!(
+            PendingSlashes::<T>::get(domain_id)
+                .expect("pedning slash must exist")
+                .len(),
+            n as usize
+        );
+        assert!(BlockTree::<T>::get(domain_id, stale_receipt_number).is_none());
+        assert!(BlockTreeNodes::<T>::get(stale_receipt_hash).is_none());
+    }
+
     /// Benchmark confirm domain block based on the number of valid and invalid bundles have submitted
     /// in this block
     #[benchmark]
     fn confirm_domain_block(
-        n: Linear<1, MAX_BUNLDE_PER_BLOCK>,
-        s: Linear<0, MAX_BUNLDE_PER_BLOCK>,
+        n: Linear<1, { max_bundles_per_block::<T>() }>,
+        s: Linear<0, { max_bundles_per_block::<T>() }>,
     ) {
         let minimum_nominator_stake = T::MinNominatorStake::get();
         let operator_rewards =
@@ -272,7 +396,7 @@ mod benchmarks {
         #[allow(clippy::unnecessary_to_owned)]
         #[block]
         {
-            refund_storage_fee::<T>(
+            let storage_fee_refunds = refund_storage_fee::<T>(
                 total_storage_fee,
                 operator_ids
                     .iter()
@@ -282,13 +406,25 @@ mod benchmarks {
             )
             .expect("refund storage fee should success");
 
-            do_reward_operators::<T>(
+            let distributed_rewards = do_reward_operators::<T>(
                 domain_id,
                 operator_ids[..n as usize].to_vec().into_iter(),
                 operator_rewards,
             )
             .expect("reward operator should success");
 
+            if let Some(stake_summary) = DomainStakingSummary::<T>::get(domain_id) {
+                Domains::<T>::note_domain_fees(
+                    domain_id,
+                    stake_summary.current_epoch_index,
+                    DomainFees {
+                        operator_rewards: distributed_rewards,
+                        storage_fee_refunds,
+                        operator_tax: Zero::zero(),
+                    },
+                );
+            }
+
             do_slash_operators::<T>(
                 operator_ids[n as usize..].to_vec().into_iter(),
                 SlashedReason::InvalidBundle(1u32.into()),
@@ -312,7 +448,7 @@ mod benchmarks {
     /// Benchmark `operator_take_reward_tax_and_stake` based on the number of operator who has reward
     /// in the current epoch
     #[benchmark]
-    fn operator_reward_tax_and_restake(n: Linear<1, MAX_BUNLDE_PER_BLOCK>) {
+    fn operator_reward_tax_and_restake(n: Linear<1, { max_bundles_per_block::<T>() }>) {
         let minimum_nominator_stake = T::MinNominatorStake::get();
         let operator_rewards =
             T::Currency::minimum_balance().saturating_mul(BalanceOf::<T>::from(1000u32));
@@ -359,15 +495,16 @@ mod benchmarks {
     // nominator that has slashed in the current epoch
     #[benchmark]
     fn finalize_slashed_operators(
-        n: Linear<1, { MAX_BUNLDE_PER_BLOCK * T::MaxNominators::get() }>,
+        n: Linear<1, { max_bundles_per_block::<T>() * T::MaxNominators::get() }>,
     ) {
         let minimum_nominator_stake = T::MinNominatorStake::get();
         let domain_id = register_domain::<T>();
 
-        let (operator_count, nominator_per_operator) = if n <= MAX_BUNLDE_PER_BLOCK {
+        let max_bundles_per_block = max_bundles_per_block::<T>();
+        let (operator_count, nominator_per_operator) = if n <= max_bundles_per_block {
             (n, 1)
         } else {
-            (MAX_BUNLDE_PER_BLOCK, n.div_ceil(MAX_BUNLDE_PER_BLOCK))
+            (max_bundles_per_block, n.div_ceil(max_bundles_per_block))
         };
 
         let mut operator_ids = Vec::new();
@@ -389,7 +526,10 @@ mod benchmarks {
             // Minus one since the operator owner is already a nominator
             for j in 1..nominator_per_operator {
                 let nominator = account("nominator", i as u32, j);
-                T::Currency::set_balance(&nominator, minimum_nominator_stake * 2u32.into());
+                T::Currency::set_balance(
+                    &nominator,
+                    minimum_nominator_stake * 2u32.into() + T::NominationKeepAlive::get(),
+                );
                 assert_ok!(Domains::<T>::nominate_operator(
                     RawOrigin::Signed(nominator).into(),
                     *operator_id,
@@ -402,11 +542,16 @@ mod benchmarks {
 
         // Slash operator
         do_slash_operators::<T>(
-            operator_ids.into_iter(),
+            operator_ids.clone().into_iter(),
             SlashedReason::InvalidBundle(1u32.into()),
         )
         .expect("slash operator should success");
 
+        // Skip the `SlashDeferDuration` so the slashes are finalized right away
+        for operator_id in operator_ids {
+            DeferredSlashes::<T>::remove(operator_id);
+        }
+
         assert_eq!(
             PendingSlashes::<T>::get(domain_id)
                 .expect("pedning slash must exist")
@@ -416,7 +561,8 @@ mod benchmarks {
 
         #[block]
         {
-            do_finalize_slashed_operators::<T>(domain_id).expect("finalize slash should success");
+            do_finalize_slashed_operators::<T>(domain_id, u32::MAX)
+                .expect("finalize slash should success");
         }
 
         assert!(PendingSlashes::<T>::get(domain_id).is_none());
@@ -448,7 +594,10 @@ mod benchmarks {
 
         for (i, operator_id) in operator_ids.iter().enumerate().take(p as usize) {
             let nominator = account("nominator", i as u32, SEED);
-            T::Currency::set_balance(&nominator, minimum_nominator_stake * 2u32.into());
+            T::Currency::set_balance(
+                &nominator,
+                minimum_nominator_stake * 2u32.into() + T::NominationKeepAlive::get(),
+            );
             assert_ok!(Domains::<T>::nominate_operator(
                 RawOrigin::Signed(nominator).into(),
                 *operator_id,
@@ -515,7 +664,7 @@ mod benchmarks {
         });
 
         #[extrinsic_call]
-        _(RawOrigin::Root, runtime_id, genesis_storage.clone());
+        _(RawOrigin::Root, runtime_id, genesis_storage.clone(), None);
 
         let scheduled_at = frame_system::Pallet::<T>::current_block_number()
             .checked_add(&T::DomainRuntimeUpgradeDelay::get())
@@ -523,6 +672,10 @@ mod benchmarks {
         let scheduled_upgrade = ScheduledRuntimeUpgrades::<T>::get(scheduled_at, runtime_id)
             .expect("scheduled upgrade must exist");
         assert_eq!(scheduled_upgrade.version.spec_version, 1);
+        assert_eq!(
+            PendingRuntimeUpgrades::<T>::get(runtime_id),
+            Some(scheduled_at)
+        );
     }
 
     #[benchmark]
@@ -538,12 +691,13 @@ mod benchmarks {
         let domain_config = DomainConfig {
             domain_name: "evm-domain".to_owned(),
             runtime_id,
-            max_block_size: 1024,
-            max_block_weight: Weight::from_parts(1, 0),
+            max_block_size: T::MaxDomainBlockSize::get(),
+            max_block_weight: T::MaxDomainBlockWeight::get(),
             bundle_slot_probability: (1, 1),
             target_bundles_per_block: 10,
             operator_allow_list: OperatorAllowList::Anyone,
             initial_balances: Default::default(),
+            initial_contract_creation_allow_list: None,
         };
 
         #[extrinsic_call]
@@ -564,7 +718,9 @@ mod benchmarks {
         let operator_account = account("operator", 1, SEED);
         T::Currency::set_balance(
             &operator_account,
-            T::MinOperatorStake::get() + T::MinNominatorStake::get(),
+            T::MinOperatorStake::get()
+                + T::MinNominatorStake::get()
+                + T::NominationKeepAlive::get(),
         );
 
         let domain_id = register_domain::<T>();
@@ -606,7 +762,9 @@ mod benchmarks {
         let minimum_nominator_stake = T::MinNominatorStake::get();
         T::Currency::set_balance(
             &nominator,
-            minimum_nominator_stake * 2u32.into() + T::MinNominatorStake::get(),
+            minimum_nominator_stake * 2u32.into()
+                + T::MinNominatorStake::get()
+                + T::NominationKeepAlive::get(),
         );
 
         let domain_id = register_domain::<T>();
@@ -676,6 +834,36 @@ mod benchmarks {
         );
     }
 
+    #[benchmark]
+    fn pause_operator() {
+        let domain_id = register_domain::<T>();
+        let (operator_owner, operator_id) =
+            register_helper_operator::<T>(domain_id, T::MinNominatorStake::get());
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(operator_owner), operator_id);
+
+        let operator = Operators::<T>::get(operator_id).expect("operator must exist");
+        assert!(operator.paused);
+    }
+
+    #[benchmark]
+    fn resume_operator() {
+        let domain_id = register_domain::<T>();
+        let (operator_owner, operator_id) =
+            register_helper_operator::<T>(domain_id, T::MinNominatorStake::get());
+        assert_ok!(Domains::<T>::pause_operator(
+            RawOrigin::Signed(operator_owner.clone()).into(),
+            operator_id,
+        ));
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(operator_owner), operator_id);
+
+        let operator = Operators::<T>::get(operator_id).expect("operator must exist");
+        assert!(!operator.paused);
+    }
+
     /// Benchmark `withdraw_stake` extrinsic with the worst possible conditions:
     /// - There is a pending withdrawal and a pending deposit from the previous epoch that
     ///   need to convert into balance/share
@@ -687,7 +875,9 @@ mod benchmarks {
         let withdraw_amount = T::MinOperatorStake::get();
         T::Currency::set_balance(
             &nominator,
-            withdraw_amount * 4u32.into() + T::MinNominatorStake::get(),
+            withdraw_amount * 4u32.into()
+                + T::MinNominatorStake::get()
+                + T::NominationKeepAlive::get(),
         );
 
         let domain_id = register_domain::<T>();
@@ -727,12 +917,16 @@ mod benchmarks {
 
     /// Benchmark `unlock_funds` extrinsic with the worst possible conditions:
     /// - Unlock a full withdrawal which also remove the deposit storage for the nominator
+    /// - Transfer the unlocked funds to a new beneficiary account
     #[benchmark]
     fn unlock_funds() {
         let nominator = account("nominator", 1, SEED);
         let minimum_nominator_stake = T::MinNominatorStake::get();
         let staking_amount = T::MinOperatorStake::get();
-        T::Currency::set_balance(&nominator, staking_amount + T::MinNominatorStake::get());
+        T::Currency::set_balance(
+            &nominator,
+            staking_amount + T::MinNominatorStake::get() + T::NominationKeepAlive::get(),
+        );
 
         let domain_id = register_domain::<T>();
         let (_, operator_id) = register_helper_operator::<T>(domain_id, minimum_nominator_stake);
@@ -776,11 +970,18 @@ mod benchmarks {
             },
         );
 
+        let beneficiary: T::AccountId = account("beneficiary", 1, SEED);
+
         #[extrinsic_call]
-        _(RawOrigin::Signed(nominator.clone()), operator_id);
+        _(
+            RawOrigin::Signed(nominator.clone()),
+            operator_id,
+            Some(beneficiary.clone()),
+        );
 
         assert!(Withdrawals::<T>::get(operator_id, nominator.clone()).is_none());
         assert!(Deposits::<T>::get(operator_id, nominator).is_none());
+        assert!(!T::Currency::balance(&beneficiary).is_zero());
     }
 
     /// Benchmark `unlock_operator` extrinsic based on the number of nominator of the unlocked operator
@@ -802,7 +1003,9 @@ mod benchmarks {
             let nominator = account("nominator", i, SEED);
             T::Currency::set_balance(
                 &nominator,
-                T::MinNominatorStake::get() + T::Currency::minimum_balance(),
+                T::MinNominatorStake::get()
+                    + T::Currency::minimum_balance()
+                    + T::NominationKeepAlive::get(),
             );
             assert_ok!(Domains::<T>::nominate_operator(
                 RawOrigin::Signed(nominator).into(),
@@ -871,6 +1074,195 @@ mod benchmarks {
         assert_eq!(domain_obj.domain_config.operator_allow_list, new_allow_list);
     }
 
+    /// Benchmark `update_contract_creation_allow_list` extrinsic with the worst possible
+    /// conditions:
+    /// - The allow list contains `n` accounts, which is stored twice
+    #[benchmark]
+    fn update_contract_creation_allow_list(
+        n: Linear<0, { T::MaxContractCreationAllowListLength::get() }>,
+    ) {
+        let domain_id = register_domain::<T>();
+        let domain_owner = DomainRegistry::<T>::get(domain_id)
+            .expect("domain object must exist")
+            .owner_account_id;
+        let new_allow_list = PermissionedActionAllowedBy::Accounts(
+            (0..n)
+                .map(|i| {
+                    let mut account = [0u8; 20];
+                    account[..4].copy_from_slice(&i.to_le_bytes());
+                    EthereumAccountId::from(account)
+                })
+                .collect(),
+        );
+
+        #[extrinsic_call]
+        _(
+            RawOrigin::Signed(domain_owner),
+            domain_id,
+            new_allow_list.clone(),
+        );
+
+        assert_eq!(
+            EvmDomainContractCreationAllowedBy::<T>::get(domain_id),
+            Some(new_allow_list.clone())
+        );
+        assert_eq!(
+            EvmDomainContractCreationAllowListUpdate::<T>::get(domain_id),
+            Some(new_allow_list)
+        );
+    }
+
+    /// Benchmark `report_rejected_bundles` extrinsic with the worst possible conditions:
+    /// - Each of the `n` bundles is submitted by a different operator and its signature verified
+    /// - Each bundle is rejected with `ConflictingReceipt` and its receipt is recorded as a
+    ///   competing receipt
+    /// - The rejected bundle records of each operator are full so the oldest record is dropped
+    #[benchmark]
+    fn report_rejected_bundles(n: Linear<0, MAX_REJECTED_BUNDLES_PER_BLOCK>) {
+        let domain_id = register_domain::<T>();
+        let max_records = T::MaxRejectedBundleRecords::get() as usize;
+
+        let mut rejected_bundles = Vec::new();
+        let mut operator_ids = Vec::new();
+        for i in 0..n {
+            let signing_key = OperatorPublicKey::generate_pair(None);
+            let (_, operator_id) = register_operator_with_signing_key::<T>(
+                domain_id,
+                i + 1,
+                signing_key.clone(),
+                T::MinNominatorStake::get(),
+            );
+            OperatorRejectedBundles::<T>::insert(
+                operator_id,
+                vec![
+                    RejectedBundleRecord {
+                        consensus_block_number: Zero::zero(),
+                        reason: RejectedBundleReason::ConflictingReceipt,
+                    };
+                    max_records
+                ],
+            );
+
+            // A receipt of the genesis domain block that conflicts with the genesis receipt
+            let receipt = ExecutionReceipt::dummy::<DomainHashingFor<T>>(
+                Zero::zero(),
+                T::Hashing::hash_of(&i),
+                Zero::zero(),
+                Default::default(),
+            );
+            let header = dummy_opaque_bundle(domain_id, operator_id, receipt)
+                .sealed_header
+                .header;
+            let signature = signing_key
+                .sign(&header.hash())
+                .expect("the signing key is generated in the keystore");
+            rejected_bundles.push(RejectedBundle {
+                encoded_sealed_header: SealedBundleHeader::new(header, signature).encode(),
+                reason: RejectedBundleReason::ConflictingReceipt,
+            });
+            operator_ids.push(operator_id);
+        }
+        let rejected_bundles =
+            RejectedBundles::try_from(rejected_bundles).expect("at most the maximum bundles");
+
+        #[extrinsic_call]
+        _(RawOrigin::None, rejected_bundles);
+
+        assert!(RejectedBundlesReported::<T>::get());
+        for operator_id in operator_ids {
+            assert_eq!(RejectedBundleCount::<T>::get(operator_id), 1);
+            assert_eq!(
+                OperatorRejectedBundles::<T>::get(operator_id).len(),
+                max_records
+            );
+        }
+    }
+
+    /// Benchmark `cancel_pending_slash` extrinsic with the worst possible conditions:
+    /// - The operator was registered before the slash so it is added back to the next operator set
+    #[benchmark]
+    fn cancel_pending_slash() {
+        let domain_id = register_domain::<T>();
+        let (_, operator_id) =
+            register_helper_operator::<T>(domain_id, T::MinNominatorStake::get());
+        do_finalize_domain_current_epoch::<T>(domain_id)
+            .expect("finalize domain staking should success");
+
+        do_slash_operators::<T>(vec![operator_id], SlashedReason::InvalidBundle(1u32.into()))
+            .expect("slash operator should success");
+
+        // Defer the slash regardless of the `SlashDeferDuration` of the runtime
+        let current_epoch_index = DomainStakingSummary::<T>::get(domain_id)
+            .expect("staking summary must exist")
+            .current_epoch_index;
+        DeferredSlashes::<T>::insert(
+            operator_id,
+            DeferredSlash {
+                finalize_at_epoch: current_epoch_index + 1,
+                previous_status: OperatorStatus::Registered,
+            },
+        );
+
+        #[extrinsic_call]
+        _(RawOrigin::Root, domain_id, operator_id);
+
+        assert!(PendingSlashes::<T>::get(domain_id).is_none());
+        assert!(DeferredSlashes::<T>::get(operator_id).is_none());
+        let operator = Operators::<T>::get(operator_id).expect("operator must exist");
+        assert_eq!(
+            *operator.status::<T>(operator_id),
+            OperatorStatus::Registered
+        );
+    }
+
+    /// Benchmark `set_operator_metadata` extrinsic with the worst possible conditions:
+    /// - The metadata is set for the first time so the deposit is held
+    /// - Both the name and the website are of the maximum length
+    #[benchmark]
+    fn set_operator_metadata() {
+        let domain_id = register_domain::<T>();
+        let (operator_owner, operator_id) =
+            register_helper_operator::<T>(domain_id, T::MinNominatorStake::get());
+        T::Currency::set_balance(
+            &operator_owner,
+            T::Currency::balance(&operator_owner) + T::OperatorMetadataDeposit::get(),
+        );
+
+        #[extrinsic_call]
+        _(
+            RawOrigin::Signed(operator_owner),
+            operator_id,
+            vec![b'a'; MAX_OPERATOR_NAME_LENGTH as usize],
+            Some(vec![b'a'; MAX_OPERATOR_WEBSITE_LENGTH as usize]),
+        );
+
+        assert!(RegisteredOperatorMetadata::<T>::contains_key(operator_id));
+    }
+
+    /// Benchmark `clear_operator_metadata` extrinsic, the deposit held for the metadata is
+    /// released
+    #[benchmark]
+    fn clear_operator_metadata() {
+        let domain_id = register_domain::<T>();
+        let (operator_owner, operator_id) =
+            register_helper_operator::<T>(domain_id, T::MinNominatorStake::get());
+        T::Currency::set_balance(
+            &operator_owner,
+            T::Currency::balance(&operator_owner) + T::OperatorMetadataDeposit::get(),
+        );
+        assert_ok!(Domains::<T>::set_operator_metadata(
+            RawOrigin::Signed(operator_owner.clone()).into(),
+            operator_id,
+            vec![b'a'; MAX_OPERATOR_NAME_LENGTH as usize],
+            Some(vec![b'a'; MAX_OPERATOR_WEBSITE_LENGTH as usize]),
+        ));
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(operator_owner), operator_id);
+
+        assert!(!RegisteredOperatorMetadata::<T>::contains_key(operator_id));
+    }
+
     fn register_runtime<T: Config>() -> RuntimeId {
         let genesis_storage = include_bytes!("../res/evm-domain-genesis-storage").to_vec();
         let runtime_id = NextRuntimeId::<T>::get();
@@ -899,12 +1291,13 @@ mod benchmarks {
         let domain_config = DomainConfig {
             domain_name: "evm-domain".to_owned(),
             runtime_id,
-            max_block_size: 1024,
-            max_block_weight: Weight::from_parts(1, 0),
+            max_block_size: T::MaxDomainBlockSize::get(),
+            max_block_weight: T::MaxDomainBlockWeight::get(),
             bundle_slot_probability: (1, 1),
             target_bundles_per_block: 10,
             operator_allow_list: OperatorAllowList::Anyone,
             initial_balances: Default::default(),
+            initial_contract_creation_allow_list: None,
         };
 
         assert_ok!(Domains::<T>::instantiate_domain(
@@ -931,20 +1324,36 @@ mod benchmarks {
         operator_seed: u32,
         minimum_nominator_stake: BalanceOf<T>,
     ) -> (T::AccountId, OperatorId) {
-        let operator_account = account("operator", operator_seed, SEED);
-        T::Currency::set_balance(
-            &operator_account,
-            T::MinOperatorStake::get() + T::MinNominatorStake::get(),
-        );
-
         let key = {
             let mut k = [0u8; 32];
             (k[..4]).copy_from_slice(&operator_seed.to_be_bytes()[..]);
             k
         };
+        register_operator_with_signing_key::<T>(
+            domain_id,
+            operator_seed,
+            OperatorPublicKey::unchecked_from(key),
+            minimum_nominator_stake,
+        )
+    }
+
+    fn register_operator_with_signing_key<T: Config>(
+        domain_id: DomainId,
+        operator_seed: u32,
+        signing_key: OperatorPublicKey,
+        minimum_nominator_stake: BalanceOf<T>,
+    ) -> (T::AccountId, OperatorId) {
+        let operator_account = account("operator", operator_seed, SEED);
+        T::Currency::set_balance(
+            &operator_account,
+            T::MinOperatorStake::get()
+                + T::MinNominatorStake::get()
+                + T::NominationKeepAlive::get(),
+        );
+
         let operator_id = NextOperatorId::<T>::get();
         let operator_config = OperatorConfig {
-            signing_key: OperatorPublicKey::unchecked_from(key),
+            signing_key,
             minimum_nominator_stake,
             nomination_tax: Default::default(),
         };
@@ -4,15 +4,17 @@
 extern crate alloc;
 
 use crate::{
-    BalanceOf, BlockTree, BlockTreeNodeFor, BlockTreeNodes, Config, ConsensusBlockHash,
-    DomainBlockNumberFor, DomainHashingFor, ExecutionInbox, ExecutionReceiptOf,
-    HeadReceiptExtended, HeadReceiptNumber, InboxedBundleAuthor, LatestConfirmedDomainBlock,
+    BalanceOf, BlockTree, BlockTreeNodeFor, BlockTreeNodes, CompetingReceipts, Config,
+    ConfirmedDomainStateRoots, ConsensusBlockHash, DomainBlockNumberFor, DomainHashingFor,
+    ExecutionInbox, ExecutionReceiptOf, HeadDomainNumber, HeadReceiptExtended,
+    HeadReceiptExtendedAt, HeadReceiptNumber, InboxedBundleAuthor, LatestConfirmedDomainBlock,
     LatestSubmittedER, Pallet, ReceiptHashFor,
 };
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
 use codec::{Decode, Encode};
 use frame_support::{ensure, PalletError};
+use frame_system::pallet_prelude::BlockNumberFor;
 use scale_info::TypeInfo;
 use sp_core::Get;
 use sp_domains::merkle_tree::MerkleTree;
@@ -41,11 +43,16 @@ pub enum Error {
     InvalidTraceRoot,
     InvalidExecutionTrace,
     UnavailableConsensusBlockHash,
+    PrunedConsensusBlockHash,
     InvalidStateRoot,
     BalanceOverflow,
     DomainTransfersTracking,
     InvalidDomainTransfers,
     OverwritingER,
+    ReceiptExceedsHeadDomainNumber,
+    HeadReceiptExceedsHeadDomainNumber,
+    FutureConsensusBlock,
+    InboxCountMismatch,
 }
 
 #[derive(TypeInfo, Debug, Encode, Decode, Clone, PartialEq, Eq)]
@@ -158,6 +165,144 @@ pub(crate) fn execution_receipt_type<T: Config>(
     }
 }
 
+/// Returns `true` if the receipt tries to create a new branch at the head receipt while the head
+/// receipt is accepted in the current consensus block, i.e. the receipt conflicts with the receipt
+/// of a sibling bundle that is included earlier in the same block.
+///
+/// When validating a transaction for the next block, the head receipt accepted in the parent
+/// block is also taken into account, so the bundles dropped by the block author for such conflict
+/// are reported with the same reason.
+pub(crate) fn is_conflicting_receipt<T: Config>(
+    domain_id: DomainId,
+    execution_receipt: &ExecutionReceiptOf<T>,
+) -> bool {
+    let head_receipt_number = HeadReceiptNumber::<T>::get(domain_id);
+    if head_receipt_number.is_zero() || execution_receipt.domain_block_number != head_receipt_number
+    {
+        return false;
+    }
+
+    let current_block_number = frame_system::Pallet::<T>::current_block_number();
+    let head_receipt_extended_recently = HeadReceiptExtended::<T>::get(domain_id)
+        || HeadReceiptExtendedAt::<T>::get(domain_id).saturating_add(One::one())
+            == current_block_number;
+
+    head_receipt_extended_recently
+        && execution_receipt_type::<T>(domain_id, execution_receipt)
+            == ReceiptType::Rejected(RejectedReceiptType::NewBranch)
+}
+
+/// Returns `false` if the receipt can't be a receipt of `domain_id`.
+///
+/// Receipts don't carry a domain id, a receipt that may become or confirm the head receipt of the
+/// domain must be chained to the block tree of the domain: its parent receipt, or the receipt
+/// itself for the genesis receipt, must be in the block tree. Other receipts are left to
+/// `verify_execution_receipt`, which rejects them with more specific errors.
+pub(crate) fn is_receipt_of_domain<T: Config>(
+    domain_id: DomainId,
+    execution_receipt: &ExecutionReceiptOf<T>,
+) -> bool {
+    let receipt_number = execution_receipt.domain_block_number;
+    let head_receipt_number = HeadReceiptNumber::<T>::get(domain_id);
+    if receipt_number < head_receipt_number
+        || receipt_number > head_receipt_number.saturating_add(One::one())
+    {
+        return true;
+    }
+
+    match receipt_number.checked_sub(&One::one()) {
+        Some(parent_number) => {
+            // The parent receipt is already pruned from the block tree
+            let latest_confirmed_domain_block_number =
+                Pallet::<T>::latest_confirmed_domain_block_number(domain_id);
+            if !latest_confirmed_domain_block_number.is_zero()
+                && parent_number <= latest_confirmed_domain_block_number
+            {
+                return true;
+            }
+
+            does_receipt_exists::<T>(
+                domain_id,
+                parent_number,
+                execution_receipt.parent_domain_block_receipt_hash,
+            )
+        }
+        None => does_receipt_exists::<T>(
+            domain_id,
+            receipt_number,
+            execution_receipt.hash::<DomainHashingFor<T>>(),
+        ),
+    }
+}
+
+/// Returns `true` if the gap between `HeadDomainNumber` and `HeadReceiptNumber` exceeds
+/// `MaxReceiptGap` and the given receipt does not extend the head receipt.
+pub(crate) fn is_lagging_receipt<T: Config>(
+    domain_id: DomainId,
+    execution_receipt: &ExecutionReceiptOf<T>,
+) -> bool {
+    let receipt_gap = HeadDomainNumber::<T>::get(domain_id)
+        .saturating_sub(HeadReceiptNumber::<T>::get(domain_id));
+    receipt_gap > T::MaxReceiptGap::get()
+        && execution_receipt_type::<T>(domain_id, execution_receipt)
+            != ReceiptType::Accepted(AcceptedReceiptType::NewHead)
+}
+
+/// Returns the hash of the consensus block `consensus_number` that a receipt of `domain_id` must
+/// be derived from.
+///
+/// Errors with `PrunedConsensusBlockHash` if the consensus block is older than the oldest
+/// unconfirmed receipt of the domain, and with `UnavailableConsensusBlockHash` if the hash is
+/// otherwise unknown.
+pub(crate) fn expected_consensus_hash_at<T: Config>(
+    domain_id: DomainId,
+    consensus_number: BlockNumberFor<T>,
+) -> Result<T::Hash, Error> {
+    if let Some(hash) = ConsensusBlockHash::<T>::get(domain_id, consensus_number) {
+        return Ok(hash);
+    }
+
+    // The `initialize_block` of non-system pallets is skipped in the `validate_transaction`,
+    // thus the hash of best block, which is recorded in the this pallet's `on_initialize` hook,
+    // is unavailable at this point.
+    let current_block_number = frame_system::Pallet::<T>::current_block_number();
+    if current_block_number.checked_sub(&One::one()) == Some(consensus_number) {
+        return Ok(frame_system::Pallet::<T>::parent_hash());
+    }
+
+    // The genesis block hash is never pruned by `frame_system`
+    if consensus_number.is_zero() {
+        return Ok(frame_system::Pallet::<T>::block_hash(consensus_number));
+    }
+
+    // `ConsensusBlockHash` is pruned along with the receipt that points to it, so any consensus
+    // block before the one of the oldest unconfirmed receipt is too old to verify against.
+    let oldest_consensus_number = Pallet::<T>::oldest_unconfirmed_receipt_number(domain_id)
+        .and_then(|number| BlockTree::<T>::get(domain_id, number))
+        .and_then(BlockTreeNodes::<T>::get)
+        .map(|node| node.execution_receipt.consensus_block_number);
+    if let Some(oldest_consensus_number) = oldest_consensus_number {
+        ensure!(
+            consensus_number >= oldest_consensus_number,
+            Error::PrunedConsensusBlockHash
+        );
+    }
+
+    Err(Error::UnavailableConsensusBlockHash)
+}
+
+/// Ensures the consensus block `consensus_number` that a receipt claims to be derived from is
+/// already produced, i.e. not beyond the current consensus block.
+pub(crate) fn ensure_consensus_block_not_in_future<T: Config>(
+    consensus_number: BlockNumberFor<T>,
+) -> Result<(), Error> {
+    ensure!(
+        consensus_number <= frame_system::Pallet::<T>::current_block_number(),
+        Error::FutureConsensusBlock
+    );
+    Ok(())
+}
+
 /// Verify the execution receipt
 pub(crate) fn verify_execution_receipt<T: Config>(
     domain_id: DomainId,
@@ -175,6 +320,10 @@ pub(crate) fn verify_execution_receipt<T: Config>(
         ..
     } = execution_receipt;
 
+    // Checked first since the consensus block hash lookup of a receipt derived from a future
+    // consensus block fails with different errors depending on what is in the storage.
+    ensure_consensus_block_not_in_future::<T>(*consensus_block_number)?;
+
     // Checking if the incoming ER is expected regarding to its `domain_block_number` or freshness
     if let ReceiptType::Rejected(rejected_receipt_type) =
         execution_receipt_type::<T>(domain_id, execution_receipt)
@@ -182,6 +331,18 @@ pub(crate) fn verify_execution_receipt<T: Config>(
         return Err(rejected_receipt_type.into());
     }
 
+    // A receipt can only be derived from a domain block that is already produced, and the head
+    // receipt must never go beyond the best domain block.
+    let head_domain_number = HeadDomainNumber::<T>::get(domain_id);
+    ensure!(
+        HeadReceiptNumber::<T>::get(domain_id) <= head_domain_number,
+        Error::HeadReceiptExceedsHeadDomainNumber
+    );
+    ensure!(
+        *domain_block_number <= head_domain_number,
+        Error::ReceiptExceedsHeadDomainNumber
+    );
+
     // The genesis receipt is generated and added to the block tree by the runtime upon domain
     // instantiation thus it is unchallengeable, we can safely skip other checks as long as we
     // can ensure it is always be the same.
@@ -204,31 +365,22 @@ pub(crate) fn verify_execution_receipt<T: Config>(
 
     // Check if the ER is derived from the correct consensus block in the current chain
     let excepted_consensus_block_hash =
-        match ConsensusBlockHash::<T>::get(domain_id, consensus_block_number) {
-            Some(hash) => hash,
-            // The `initialize_block` of non-system pallets is skipped in the `validate_transaction`,
-            // thus the hash of best block, which is recorded in the this pallet's `on_initialize` hook,
-            // is unavailable at this point.
-            None => {
-                let parent_block_number =
-                    frame_system::Pallet::<T>::current_block_number() - One::one();
-                if *consensus_block_number == parent_block_number {
-                    frame_system::Pallet::<T>::parent_hash()
-                } else {
-                    return Err(Error::UnavailableConsensusBlockHash);
-                }
-            }
-        };
+        expected_consensus_hash_at::<T>(domain_id, *consensus_block_number)?;
     ensure!(
         *consensus_block_hash == excepted_consensus_block_hash,
         Error::BuiltOnUnknownConsensusBlock
     );
 
-    // Check if the ER is derived from the expected inboxed bundles of the consensus block
-    let bundles_extrinsics_roots: Vec<_> =
-        inboxed_bundles.iter().map(|b| b.extrinsics_root).collect();
+    // Check if the ER is derived from the expected inboxed bundles of the consensus block, the
+    // bundles must be in the same order as they are included in the consensus block
     let execution_inbox =
         ExecutionInbox::<T>::get((domain_id, domain_block_number, consensus_block_number));
+    ensure!(
+        inboxed_bundles.len() == execution_inbox.len(),
+        Error::InboxCountMismatch
+    );
+    let bundles_extrinsics_roots: Vec<_> =
+        inboxed_bundles.iter().map(|b| b.extrinsics_root).collect();
     let expected_extrinsics_roots: Vec<_> =
         execution_inbox.iter().map(|b| b.extrinsics_root).collect();
     ensure!(
@@ -306,6 +458,10 @@ pub(crate) fn process_execution_receipt<T: Config>(
             // Update the head receipt number
             HeadReceiptNumber::<T>::insert(domain_id, receipt_block_number);
             HeadReceiptExtended::<T>::insert(domain_id, true);
+            HeadReceiptExtendedAt::<T>::insert(
+                domain_id,
+                frame_system::Pallet::<T>::current_block_number(),
+            );
 
             // Prune expired domain block
             if let Some(to_prune) =
@@ -368,6 +524,13 @@ pub(crate) fn process_execution_receipt<T: Config>(
                 update_domain_transfers::<T>(domain_id, &execution_receipt.transfers, block_fees)
                     .map_err(|_| Error::DomainTransfersTracking)?;
 
+                note_confirmed_domain_state_root::<T>(
+                    domain_id,
+                    to_prune,
+                    execution_receipt.domain_block_hash,
+                    execution_receipt.final_state_root,
+                );
+
                 LatestConfirmedDomainBlock::<T>::insert(
                     domain_id,
                     ConfirmedDomainBlock {
@@ -507,6 +670,13 @@ pub(crate) fn import_genesis_receipt<T: Config>(
         },
     );
 
+    note_confirmed_domain_state_root::<T>(
+        domain_id,
+        domain_block_number,
+        genesis_receipt.domain_block_hash,
+        genesis_receipt.final_state_root,
+    );
+
     let block_tree_node = BlockTreeNode {
         execution_receipt: genesis_receipt,
         operator_ids: sp_std::vec![],
@@ -516,6 +686,24 @@ pub(crate) fn import_genesis_receipt<T: Config>(
     BlockTreeNodes::<T>::insert(er_hash, block_tree_node);
 }
 
+/// Record the state root of the confirmed domain block and remove the state root that falls out
+/// of the retained confirmed history, which is `BlockTreePruningDepth` blocks.
+fn note_confirmed_domain_state_root<T: Config>(
+    domain_id: DomainId,
+    domain_block_number: DomainBlockNumberFor<T>,
+    domain_block_hash: T::DomainHash,
+    state_root: T::DomainHash,
+) {
+    ConfirmedDomainStateRoots::<T>::insert(
+        domain_id,
+        domain_block_number,
+        (domain_block_hash, state_root),
+    );
+    if let Some(to_remove) = domain_block_number.checked_sub(&T::BlockTreePruningDepth::get()) {
+        ConfirmedDomainStateRoots::<T>::remove(domain_id, to_remove);
+    }
+}
+
 pub(crate) fn prune_receipt<T: Config>(
     domain_id: DomainId,
     receipt_number: DomainBlockNumberFor<T>,
@@ -526,6 +714,7 @@ pub(crate) fn prune_receipt<T: Config>(
     };
     let block_tree_node =
         BlockTreeNodes::<T>::take(receipt_hash).ok_or(Error::MissingDomainBlock)?;
+    CompetingReceipts::<T>::remove(receipt_hash);
 
     // If the pruned ER is the operator's `latest_submitted_er` for this domain, it means either:
     //
@@ -558,7 +747,9 @@ mod tests {
     use frame_support::dispatch::RawOrigin;
     use frame_support::{assert_err, assert_ok};
     use sp_core::H256;
-    use sp_domains::{BundleDigest, InboxedBundle, InvalidBundleType};
+    use sp_domains::{BundleDigest, DomainStateRoot, InboxedBundle, InvalidBundleType};
+
+    type Domains = crate::Pallet<Test>;
 
     #[test]
     fn test_genesis_receipt() {
@@ -908,6 +1099,96 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_execution_inbox_order() {
+        let creator = 0u128;
+        let operator_id1 = 1u64;
+        let operator_id2 = 2u64;
+        let mut ext = new_test_ext_with_extensions();
+        ext.execute_with(|| {
+            let domain_id = register_genesis_domain(creator, vec![operator_id1, operator_id2]);
+            let mut receipt = extend_block_tree_from_zero(domain_id, operator_id1, 3);
+
+            // Submit bundles in a specific order across two consensus blocks
+            let mut last_extrinsics_roots = vec![];
+            for block_number in 3..=4u64 {
+                if block_number != 3 {
+                    run_to_block::<Test>(block_number, receipt.consensus_block_hash);
+                }
+
+                let mut extrinsics_roots = vec![];
+                for operator_id in [operator_id2, operator_id1, operator_id2] {
+                    let extrinsics_root = H256::random();
+                    let bundle = create_dummy_bundle_with_receipts(
+                        domain_id,
+                        operator_id,
+                        extrinsics_root,
+                        receipt.clone(),
+                    );
+                    assert_ok!(crate::Pallet::<Test>::submit_bundle(
+                        RawOrigin::None.into(),
+                        bundle,
+                    ));
+                    extrinsics_roots.push(extrinsics_root);
+                }
+
+                // The digests are recorded in the inclusion order
+                let inbox =
+                    Domains::execution_inbox_digests(domain_id, block_number as u32, block_number);
+                assert_eq!(
+                    inbox.iter().map(|d| d.extrinsics_root).collect::<Vec<_>>(),
+                    extrinsics_roots
+                );
+                assert_eq!(Domains::current_inbox(domain_id), inbox);
+
+                let head_receipt_number = HeadReceiptNumber::<Test>::get(domain_id);
+                let parent_node =
+                    get_block_tree_node_at::<Test>(domain_id, head_receipt_number).unwrap();
+                receipt = create_dummy_receipt(
+                    block_number,
+                    H256::random(),
+                    parent_node
+                        .execution_receipt
+                        .hash::<DomainHashingFor<Test>>(),
+                    extrinsics_roots.clone(),
+                );
+                last_extrinsics_roots = extrinsics_roots;
+            }
+            run_to_block::<Test>(5, receipt.consensus_block_hash);
+            assert_ok!(verify_execution_receipt::<Test>(domain_id, &receipt));
+
+            // Receipt missing one of the inboxed bundles
+            let mut missing_bundle_receipt = receipt.clone();
+            missing_bundle_receipt.inboxed_bundles.pop();
+            assert_err!(
+                verify_execution_receipt::<Test>(domain_id, &missing_bundle_receipt),
+                Error::InboxCountMismatch
+            );
+
+            // Receipt with an extra bundle
+            let mut extra_bundle_receipt = receipt.clone();
+            extra_bundle_receipt
+                .inboxed_bundles
+                .push(InboxedBundle::dummy(H256::random()));
+            assert_err!(
+                verify_execution_receipt::<Test>(domain_id, &extra_bundle_receipt),
+                Error::InboxCountMismatch
+            );
+
+            // Receipt with the inboxed bundles reordered
+            let mut reordered_receipt = receipt;
+            last_extrinsics_roots.swap(0, 1);
+            reordered_receipt.inboxed_bundles = last_extrinsics_roots
+                .into_iter()
+                .map(InboxedBundle::dummy)
+                .collect();
+            assert_err!(
+                verify_execution_receipt::<Test>(domain_id, &reordered_receipt),
+                Error::InvalidExtrinsicsRoots
+            );
+        });
+    }
+
     #[test]
     fn test_invalid_receipt() {
         let creator = 0u128;
@@ -996,6 +1277,107 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_future_consensus_block_receipt() {
+        let creator = 0u128;
+        let operator_id = 1u64;
+        let mut ext = new_test_ext_with_extensions();
+        ext.execute_with(|| {
+            let domain_id = register_genesis_domain(creator, vec![operator_id]);
+            let next_receipt = extend_block_tree_from_zero(domain_id, operator_id, 3);
+            let current_block_number = frame_system::Pallet::<Test>::current_block_number();
+            assert_ok!(verify_execution_receipt::<Test>(domain_id, &next_receipt));
+
+            // Receipt derived from the current consensus block
+            assert_ok!(ensure_consensus_block_not_in_future::<Test>(
+                current_block_number
+            ));
+            let mut current_receipt = next_receipt.clone();
+            current_receipt.consensus_block_number = current_block_number;
+            assert_ne!(
+                verify_execution_receipt::<Test>(domain_id, &current_receipt),
+                Err(Error::FutureConsensusBlock)
+            );
+
+            // Receipt derived from the next consensus block
+            let mut one_ahead_receipt = next_receipt.clone();
+            one_ahead_receipt.consensus_block_number = current_block_number + 1;
+            assert_err!(
+                verify_execution_receipt::<Test>(domain_id, &one_ahead_receipt),
+                Error::FutureConsensusBlock
+            );
+
+            // Receipt derived from a far future consensus block, rejected before any other check
+            let mut far_ahead_receipt = next_receipt;
+            far_ahead_receipt.consensus_block_number = u64::MAX;
+            far_ahead_receipt.domain_block_number = u32::MAX;
+            assert_err!(
+                verify_execution_receipt::<Test>(domain_id, &far_ahead_receipt),
+                Error::FutureConsensusBlock
+            );
+        });
+    }
+
+    #[test]
+    fn test_expected_consensus_hash_at() {
+        let creator = 0u128;
+        let operator_id = 1u64;
+        let block_tree_pruning_depth = <Test as Config>::BlockTreePruningDepth::get();
+        let mut ext = new_test_ext_with_extensions();
+        ext.execute_with(|| {
+            let domain_id = register_genesis_domain(creator, vec![operator_id]);
+            let next_receipt =
+                extend_block_tree_from_zero(domain_id, operator_id, block_tree_pruning_depth + 3);
+            let current_block_number = frame_system::Pallet::<Test>::current_block_number();
+            let parent_number = current_block_number - 1;
+            assert_eq!(next_receipt.consensus_block_number, parent_number);
+
+            // Consensus block hash recorded in `ConsensusBlockHash`
+            let recorded_number = parent_number - 1;
+            assert_eq!(
+                expected_consensus_hash_at::<Test>(domain_id, recorded_number),
+                Ok(frame_system::Pallet::<Test>::block_hash(recorded_number))
+            );
+
+            // The parent block hash is not recorded yet as `on_initialize` is skipped in
+            // `validate_transaction`
+            ConsensusBlockHash::<Test>::remove(domain_id, parent_number);
+            assert_eq!(
+                expected_consensus_hash_at::<Test>(domain_id, parent_number),
+                Ok(frame_system::Pallet::<Test>::parent_hash())
+            );
+            assert_ok!(verify_execution_receipt::<Test>(domain_id, &next_receipt));
+
+            // Receipt built on the parent block of a different fork
+            let mut wrong_fork_receipt = next_receipt.clone();
+            wrong_fork_receipt.consensus_block_hash = H256::random();
+            assert_err!(
+                verify_execution_receipt::<Test>(domain_id, &wrong_fork_receipt),
+                Error::BuiltOnUnknownConsensusBlock
+            );
+
+            // The genesis block hash is always available
+            assert_eq!(
+                expected_consensus_hash_at::<Test>(domain_id, 0),
+                Ok(frame_system::Pallet::<Test>::block_hash(0))
+            );
+
+            // The consensus block of the confirmed receipt is pruned
+            assert!(Domains::latest_confirmed_domain_block_number(domain_id) >= 1);
+            assert!(ConsensusBlockHash::<Test>::get(domain_id, 1).is_none());
+            assert_eq!(
+                expected_consensus_hash_at::<Test>(domain_id, 1),
+                Err(Error::PrunedConsensusBlockHash)
+            );
+
+            // Consensus block that is not produced yet
+            assert_eq!(
+                expected_consensus_hash_at::<Test>(domain_id, current_block_number + 1),
+                Err(Error::UnavailableConsensusBlockHash)
+            );
+        });
+    }
+
     #[test]
     fn test_invalid_trace_root_receipt() {
         let creator = 0u128;
@@ -1148,4 +1530,177 @@ mod tests {
             );
         });
     }
+
+    #[test]
+    fn test_receipt_exceeds_head_domain_number() {
+        let creator = 0u128;
+        let operator_id = 1u64;
+        let mut ext = new_test_ext_with_extensions();
+        ext.execute_with(|| {
+            let domain_id = register_genesis_domain(creator, vec![operator_id]);
+            let next_receipt = extend_block_tree_from_zero(domain_id, operator_id, 3);
+            let head_receipt_number = HeadReceiptNumber::<Test>::get(domain_id);
+            let head_domain_number = HeadDomainNumber::<Test>::get(domain_id);
+            assert_eq!(next_receipt.domain_block_number, head_domain_number);
+            assert_ok!(verify_execution_receipt::<Test>(domain_id, &next_receipt));
+
+            // Receipt of a domain block that is not produced yet
+            HeadDomainNumber::<Test>::insert(domain_id, head_receipt_number);
+            assert_eq!(
+                execution_receipt_type::<Test>(domain_id, &next_receipt),
+                ReceiptType::Accepted(AcceptedReceiptType::NewHead)
+            );
+            assert_err!(
+                verify_execution_receipt::<Test>(domain_id, &next_receipt),
+                Error::ReceiptExceedsHeadDomainNumber
+            );
+
+            // Head receipt is beyond the best domain block
+            HeadDomainNumber::<Test>::insert(domain_id, head_receipt_number - 1);
+            assert_err!(
+                verify_execution_receipt::<Test>(domain_id, &next_receipt),
+                Error::HeadReceiptExceedsHeadDomainNumber
+            );
+
+            HeadDomainNumber::<Test>::insert(domain_id, head_domain_number);
+            assert_ok!(verify_execution_receipt::<Test>(domain_id, &next_receipt));
+        });
+    }
+
+    #[test]
+    fn test_lagging_receipt() {
+        let creator = 0u128;
+        let operator_id = 1u64;
+        let max_receipt_gap = <Test as Config>::MaxReceiptGap::get();
+        let mut ext = new_test_ext_with_extensions();
+        ext.execute_with(|| {
+            let domain_id = register_genesis_domain(creator, vec![operator_id]);
+            let next_receipt = extend_block_tree_from_zero(domain_id, operator_id, 3);
+            let head_receipt_number = HeadReceiptNumber::<Test>::get(domain_id);
+            let head_receipt = get_block_tree_node_at::<Test>(domain_id, head_receipt_number)
+                .unwrap()
+                .execution_receipt;
+
+            // Within the max receipt gap
+            HeadDomainNumber::<Test>::insert(domain_id, head_receipt_number + max_receipt_gap);
+            assert!(!is_lagging_receipt::<Test>(domain_id, &next_receipt));
+            assert!(!is_lagging_receipt::<Test>(domain_id, &head_receipt));
+
+            // Beyond the max receipt gap, only the head-extending receipt is not lagging
+            HeadDomainNumber::<Test>::insert(domain_id, head_receipt_number + max_receipt_gap + 1);
+            assert!(!is_lagging_receipt::<Test>(domain_id, &next_receipt));
+            assert!(is_lagging_receipt::<Test>(domain_id, &head_receipt));
+        });
+    }
+
+    #[test]
+    fn test_domain_state_root() {
+        let creator = 0u128;
+        let operator_id = 1u64;
+        let block_tree_pruning_depth = <Test as Config>::BlockTreePruningDepth::get();
+
+        let mut ext = new_test_ext_with_extensions();
+        ext.execute_with(|| {
+            let domain_id = register_genesis_domain(creator, vec![operator_id]);
+
+            let mut receipt = get_block_tree_node_at::<Test>(domain_id, 0)
+                .unwrap()
+                .execution_receipt;
+            // The receipt of domain block `n` is at index `n`
+            let mut receipts = vec![receipt.clone()];
+            for block_number in 1..=(block_tree_pruning_depth as u64 * 3 + 3) {
+                // Finilize parent block and initialize block at `block_number`
+                run_to_block::<Test>(block_number, receipt.consensus_block_hash);
+
+                // Submit a bundle with the receipt of the last block
+                let bundle_extrinsics_root = H256::random();
+                let bundle = create_dummy_bundle_with_receipts(
+                    domain_id,
+                    operator_id,
+                    bundle_extrinsics_root,
+                    receipt,
+                );
+                assert_ok!(crate::Pallet::<Test>::submit_bundle(
+                    RawOrigin::None.into(),
+                    bundle,
+                ));
+
+                let head_receipt_number = HeadReceiptNumber::<Test>::get(domain_id);
+                let confirmed_number = Domains::latest_confirmed_domain_block_number(domain_id);
+
+                // The head receipt is unconfirmed unless it is the genesis receipt
+                let head_receipt = &receipts[head_receipt_number as usize];
+                let expected = if head_receipt_number == confirmed_number {
+                    DomainStateRoot::Confirmed(head_receipt.final_state_root)
+                } else {
+                    DomainStateRoot::Unconfirmed(head_receipt.final_state_root)
+                };
+                assert_eq!(
+                    Domains::domain_state_root(
+                        domain_id,
+                        head_receipt_number,
+                        head_receipt.domain_block_hash
+                    ),
+                    Some(expected)
+                );
+                assert!(
+                    Domains::domain_state_root(domain_id, head_receipt_number, H256::random())
+                        .is_none()
+                );
+                assert!(Domains::domain_state_root(
+                    domain_id,
+                    head_receipt_number + 1,
+                    H256::random()
+                )
+                .is_none());
+
+                // The confirmed receipt is removed from the block tree but its state root is still
+                // available
+                let confirmed_receipt = &receipts[confirmed_number as usize];
+                assert_eq!(
+                    Domains::domain_state_root(
+                        domain_id,
+                        confirmed_number,
+                        confirmed_receipt.domain_block_hash
+                    ),
+                    Some(DomainStateRoot::Confirmed(
+                        confirmed_receipt.final_state_root
+                    ))
+                );
+
+                // Only the last `BlockTreePruningDepth` confirmed state roots are kept
+                if let Some(pruned_number) = confirmed_number.checked_sub(block_tree_pruning_depth)
+                {
+                    let oldest_retained_receipt = &receipts[pruned_number as usize + 1];
+                    assert_eq!(
+                        Domains::domain_state_root(
+                            domain_id,
+                            pruned_number + 1,
+                            oldest_retained_receipt.domain_block_hash
+                        ),
+                        Some(DomainStateRoot::Confirmed(
+                            oldest_retained_receipt.final_state_root
+                        ))
+                    );
+                    let pruned_receipt = &receipts[pruned_number as usize];
+                    assert!(Domains::domain_state_root(
+                        domain_id,
+                        pruned_number,
+                        pruned_receipt.domain_block_hash
+                    )
+                    .is_none());
+                }
+
+                // Construct a `NewHead` receipt of the just submitted bundle, which will be included
+                // in the next bundle
+                receipt = create_dummy_receipt(
+                    block_number,
+                    H256::random(),
+                    BlockTree::<Test>::get(domain_id, head_receipt_number).unwrap(),
+                    vec![bundle_extrinsics_root],
+                );
+                receipts.push(receipt.clone());
+            }
+        });
+    }
 }
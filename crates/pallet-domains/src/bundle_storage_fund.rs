@@ -9,10 +9,10 @@ use frame_support::traits::Get;
 use frame_support::PalletError;
 use scale_info::TypeInfo;
 use sp_domains::OperatorId;
-use sp_runtime::traits::{AccountIdConversion, CheckedSub, Zero};
-use sp_runtime::Perbill;
+use sp_runtime::traits::{AccountIdConversion, CheckedSub, Saturating, Zero};
+use sp_runtime::{Perbill, SaturatedConversion};
 use sp_std::collections::btree_map::BTreeMap;
-use subspace_runtime_primitives::StorageFee;
+use subspace_runtime_primitives::{Balance, StorageFee};
 
 /// The proportion of staking fund reserved for the bundle storage fee
 pub const STORAGE_FEE_RESERVE: Perbill = Perbill::from_percent(20);
@@ -21,11 +21,29 @@ pub const STORAGE_FEE_RESERVE: Perbill = Perbill::from_percent(20);
 #[derive(TypeInfo, Encode, Decode, PalletError, Debug, PartialEq)]
 pub enum Error {
     BundleStorageFeePayment,
+    /// The bundle storage fund is empty.
+    BundleStorageFundEmpty,
+    /// The balance of the bundle storage fund is less than the bundle storage fee.
+    InsufficientBundleStorageFund {
+        #[codec(skip)]
+        required: Balance,
+        #[codec(skip)]
+        available: Balance,
+    },
+    /// The bundle storage fund has enough balance but paying the bundle storage fee would kill
+    /// the fund account, which must be kept alive.
+    BundleStorageFeeWouldKillAccount {
+        #[codec(skip)]
+        required: Balance,
+        #[codec(skip)]
+        available: Balance,
+    },
     BalanceUnderflow,
     MintBalance,
     FailToDeposit,
     WithdrawAndHold,
     BalanceTransfer,
+    ReleaseWithdrawnStorageFee,
 }
 
 /// The type of system account being created.
@@ -63,7 +81,68 @@ pub fn storage_fund_account<T: Config>(id: OperatorId) -> T::AccountId {
     T::PalletId::get().into_sub_account_truncating((AccountType::StorageFund, id))
 }
 
+fn bundle_storage_fee<T: Config>(bundle_size: u32) -> BalanceOf<T> {
+    T::StorageFee::transaction_byte_fee() * bundle_size.into()
+}
+
+/// Return the balance of the operator's bundle storage fund that can be used to pay the bundle
+/// storage fee, using the same balance constraints as the `burn_from` in
+/// `charge_bundle_storage_fee`.
+fn payable_storage_fund_balance<T: Config>(operator_id: OperatorId) -> BalanceOf<T> {
+    T::Currency::reducible_balance(
+        &storage_fund_account::<T>(operator_id),
+        Preservation::Expendable,
+        Fortitude::Polite,
+    )
+}
+
+/// Check if the operator's bundle storage fund can afford the bundle storage fee without
+/// mutating any balance, used when validating the bundle in the transaction pool.
+///
+/// Return the bundle storage fee if affordable.
+pub fn ensure_bundle_storage_fee_affordable<T: Config>(
+    operator_id: OperatorId,
+    bundle_size: u32,
+) -> Result<BalanceOf<T>, Error> {
+    let storage_fee = bundle_storage_fee::<T>(bundle_size);
+    let payable_balance = payable_storage_fund_balance::<T>(operator_id);
+    if payable_balance >= storage_fee {
+        return Ok(storage_fee);
+    }
+
+    let total_balance = T::Currency::balance(&storage_fund_account::<T>(operator_id));
+    let err = if total_balance.is_zero() {
+        Error::BundleStorageFundEmpty
+    } else if total_balance < storage_fee {
+        Error::InsufficientBundleStorageFund {
+            required: storage_fee.saturated_into(),
+            available: total_balance.saturated_into(),
+        }
+    } else {
+        Error::BundleStorageFeeWouldKillAccount {
+            required: storage_fee.saturated_into(),
+            available: payable_balance.saturated_into(),
+        }
+    };
+    Err(err)
+}
+
+/// Return how much the operator's bundle storage fund is short of to pay the bundle storage fee,
+/// `None` if the fee is affordable.
+pub fn storage_fee_shortfall<T: Config>(
+    operator_id: OperatorId,
+    bundle_size: u32,
+) -> Option<BalanceOf<T>> {
+    let storage_fee = bundle_storage_fee::<T>(bundle_size);
+    storage_fee
+        .checked_sub(&payable_storage_fund_balance::<T>(operator_id))
+        .filter(|shortfall| !shortfall.is_zero())
+}
+
 /// Charge the bundle storage fee from the operator's bundle storage fund
+///
+/// NOTE: this must only be called when the bundle is included in a block (i.e. in `pre_dispatch`)
+/// so that the fund balance decreases only for bundles that are included in a block.
 pub fn charge_bundle_storage_fee<T: Config>(
     operator_id: OperatorId,
     bundle_size: u32,
@@ -73,7 +152,7 @@ pub fn charge_bundle_storage_fee<T: Config>(
     }
 
     let storage_fund_acc = storage_fund_account::<T>(operator_id);
-    let storage_fee = T::StorageFee::transaction_byte_fee() * bundle_size.into();
+    let storage_fee = ensure_bundle_storage_fee_affordable::<T>(operator_id, bundle_size)?;
 
     T::Currency::burn_from(
         &storage_fund_acc,
@@ -96,9 +175,9 @@ pub fn charge_bundle_storage_fee<T: Config>(
 pub fn refund_storage_fee<T: Config>(
     total_storage_fee: BalanceOf<T>,
     paid_bundle_storage_fees: BTreeMap<OperatorId, u32>,
-) -> Result<(), Error> {
+) -> Result<BalanceOf<T>, Error> {
     if total_storage_fee.is_zero() {
-        return Ok(());
+        return Ok(Zero::zero());
     }
 
     let total_paid_storage = paid_bundle_storage_fees.values().sum::<u32>();
@@ -117,6 +196,11 @@ pub fn refund_storage_fee<T: Config>(
         let storage_fund_acc = storage_fund_account::<T>(operator_id);
         T::Currency::mint_into(&storage_fund_acc, refund_amount).map_err(|_| Error::MintBalance)?;
 
+        Pallet::<T>::deposit_event(Event::StorageFeeRefunded {
+            operator_id,
+            amount: refund_amount,
+        });
+
         remaining_fee = remaining_fee
             .checked_sub(&refund_amount)
             .ok_or(Error::BalanceUnderflow)?;
@@ -128,7 +212,7 @@ pub fn refund_storage_fee<T: Config>(
             .map_err(|_| Error::MintBalance)?;
     }
 
-    Ok(())
+    Ok(total_storage_fee.saturating_sub(remaining_fee))
 }
 
 /// Split the new deposit into 2 parts: the staking deposit and the the storage fee deposit,
@@ -191,6 +275,49 @@ pub fn withdraw_and_hold<T: Config>(
     .map_err(|_| Error::WithdrawAndHold)
 }
 
+/// Refund the given `refund_amount` of balance from the bundle storage fund to the contributor
+/// `dest_account` as free balance, the refund is skipped and left in the bundle storage fund if it
+/// is below the existential deposit.
+///
+/// Any storage fee that was already withdrawn from the bundle storage fund and is still held on
+/// the `dest_account` is released back to the contributor as well.
+///
+/// Return the amount actually refunded.
+pub fn refund_to_contributor<T: Config>(
+    operator_id: OperatorId,
+    dest_account: &T::AccountId,
+    refund_amount: BalanceOf<T>,
+) -> Result<BalanceOf<T>, Error> {
+    let storage_fund_hold_id = T::HoldIdentifier::storage_fund_withdrawal(operator_id);
+    let withdrawn_amount =
+        T::Currency::release_all(&storage_fund_hold_id, dest_account, Precision::BestEffort)
+            .map_err(|_| Error::ReleaseWithdrawnStorageFee)?;
+
+    let refund_amount = if refund_amount < T::Currency::minimum_balance() {
+        Zero::zero()
+    } else {
+        let storage_fund_acc = storage_fund_account::<T>(operator_id);
+        T::Currency::transfer(
+            &storage_fund_acc,
+            dest_account,
+            refund_amount,
+            Preservation::Expendable,
+        )
+        .map_err(|_| Error::BalanceTransfer)?
+    };
+
+    let total_refunded = refund_amount.saturating_add(withdrawn_amount);
+    if !total_refunded.is_zero() {
+        Pallet::<T>::deposit_event(Event::StorageFundRefunded {
+            operator_id,
+            nominator_id: dest_account.clone(),
+            amount: total_refunded,
+        });
+    }
+
+    Ok(total_refunded)
+}
+
 /// Return the total balance of the bundle storage fund the given `operator_id`
 pub fn total_balance<T: Config>(operator_id: OperatorId) -> BalanceOf<T> {
     let storage_fund_acc = storage_fund_account::<T>(operator_id);
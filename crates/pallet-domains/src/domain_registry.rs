@@ -4,19 +4,22 @@
 extern crate alloc;
 
 use crate::block_tree::import_genesis_receipt;
-use crate::pallet::{DomainStakingSummary, NextEVMChainId};
+use crate::pallet::{
+    DomainStakingSummary, EvmDomainContractCreationAllowListUpdate,
+    EvmDomainContractCreationAllowedBy, NextEVMChainId, OwnedDomains,
+};
 use crate::runtime_registry::DomainRuntimeInfo;
 use crate::staking::StakingSummary;
 use crate::{
     BalanceOf, Config, DomainHashingFor, DomainRegistry, ExecutionReceiptOf, HoldIdentifier,
-    NextDomainId, RuntimeRegistry,
+    NextDomainId, RuntimeRegistry, RuntimeSummaries,
 };
 #[cfg(not(feature = "std"))]
 use alloc::string::String;
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
 use codec::{Decode, Encode};
-use domain_runtime_primitives::MultiAccountId;
+use domain_runtime_primitives::{EthereumAccountId, MultiAccountId};
 use frame_support::traits::fungible::{Inspect, Mutate, MutateHold};
 use frame_support::traits::tokens::{Fortitude, Precision, Preservation};
 use frame_support::weights::Weight;
@@ -24,12 +27,14 @@ use frame_support::{ensure, PalletError};
 use frame_system::pallet_prelude::*;
 use scale_info::TypeInfo;
 use sp_core::Get;
+use sp_domains::storage::RawGenesis;
 use sp_domains::{
     derive_domain_block_hash, DomainBundleLimit, DomainId, DomainsDigestItem,
-    DomainsTransfersTracker, OperatorAllowList, RuntimeId, RuntimeType,
+    DomainsTransfersTracker, OperatorAllowList, PermissionedActionAllowedBy, RuntimeId,
+    RuntimeType,
 };
-use sp_runtime::traits::{CheckedAdd, Zero};
-use sp_runtime::DigestItem;
+use sp_runtime::traits::{CheckedAdd, Hash, Zero};
+use sp_runtime::{DigestItem, StateVersion};
 use sp_std::collections::btree_map::BTreeMap;
 use sp_std::collections::btree_set::BTreeSet;
 
@@ -56,6 +61,12 @@ pub enum Error {
     DuplicateInitialAccounts,
     FailedToGenerateRawGenesis(crate::runtime_registry::Error),
     BundleLimitCalculationOverflow,
+    ExceedMaxBundlesPerBlock,
+    BundleLimitTooSmall,
+    ContractCreationAllowListNotSupported,
+    MaxContractCreationAllowListLength,
+    TooManyDomains,
+    GenesisRuntimeCodeMismatch,
 }
 
 #[derive(TypeInfo, Debug, Encode, Decode, Clone, PartialEq, Eq)]
@@ -77,6 +88,10 @@ pub struct DomainConfig<AccountId: Ord, Balance> {
     pub operator_allow_list: OperatorAllowList<AccountId>,
     // Initial balances for Domain.
     pub initial_balances: Vec<(MultiAccountId, Balance)>,
+    /// Accounts allowed to create contracts at the domain genesis, only applicable to the EVM
+    /// domain, `None` means anyone can create contracts.
+    pub initial_contract_creation_allow_list:
+        Option<PermissionedActionAllowedBy<EthereumAccountId>>,
 }
 
 impl<AccountId, Balance> DomainConfig<AccountId, Balance>
@@ -181,8 +196,32 @@ pub(crate) fn can_instantiate_domain<T: Config>(
         Error::InvalidSlotProbability
     );
 
-    // Ensure the bundle limit can be calculated successfully
-    let _ = domain_config.calculate_bundle_limit::<T>()?;
+    // The expected number of bundles per consensus block, i.e. `bundle_slot_probability / ConsensusSlotProbability`,
+    // must not exceed `MaxBundlesPerBlock` since the weight of `submit_bundle` and `submit_fraud_proof` are
+    // capped with `MaxBundlesPerBlock`.
+    let (consensus_numerator, consensus_denominator) = T::ConsensusSlotProbability::get();
+    ensure!(
+        u128::from(numerator).saturating_mul(consensus_denominator.into())
+            <= u128::from(T::MaxBundlesPerBlock::get())
+                .saturating_mul(denominator.into())
+                .saturating_mul(consensus_numerator.into()),
+        Error::ExceedMaxBundlesPerBlock
+    );
+
+    // Ensure the bundle limit can be calculated successfully and is not too small to fit any
+    // extrinsic, otherwise every bundle of the domain would be rejected
+    let bundle_limit = domain_config.calculate_bundle_limit::<T>()?;
+    ensure!(
+        bundle_limit.max_bundle_size >= T::MinBundleSize::get()
+            && bundle_limit.max_bundle_weight.ref_time() >= T::MinBundleWeight::get().ref_time(),
+        Error::BundleLimitTooSmall
+    );
+
+    ensure!(
+        (OwnedDomains::<T>::decode_len(owner_account_id).unwrap_or_default() as u32)
+            < T::MaxDomainsPerOwner::get(),
+        Error::TooManyDomains
+    );
 
     ensure!(
         T::Currency::reducible_balance(owner_account_id, Preservation::Protect, Fortitude::Polite)
@@ -192,6 +231,23 @@ pub(crate) fn can_instantiate_domain<T: Config>(
 
     domain_config.check_initial_balances::<T>()?;
 
+    // The contract creation allow list is only supported by the EVM domain
+    if let Some(contract_creation_allow_list) = &domain_config.initial_contract_creation_allow_list
+    {
+        ensure!(
+            contract_creation_allow_list.accounts_len() as u32
+                <= T::MaxContractCreationAllowListLength::get(),
+            Error::MaxContractCreationAllowListLength
+        );
+        let runtime_type = RuntimeRegistry::<T>::get(domain_config.runtime_id)
+            .map(|runtime_obj| runtime_obj.runtime_type)
+            .ok_or(Error::RuntimeNotFound)?;
+        ensure!(
+            matches!(runtime_type, RuntimeType::Evm),
+            Error::ContractCreationAllowListNotSupported
+        );
+    }
+
     Ok(())
 }
 
@@ -241,25 +297,30 @@ pub(crate) fn do_instantiate_domain<T: Config>(
                 domain_runtime_info,
                 total_issuance,
                 domain_config.initial_balances.clone(),
+                domain_config.initial_contract_creation_allow_list.clone(),
             )
             .map_err(Error::FailedToGenerateRawGenesis)?;
-        let state_root = raw_genesis.state_root::<DomainHashingFor<T>>(state_version);
-        let genesis_block_hash = derive_domain_block_hash::<T::DomainHeader>(
-            Zero::zero(),
-            sp_domains::EMPTY_EXTRINSIC_ROOT.into(),
-            state_root,
-            Default::default(),
-            Default::default(),
+
+        // The domain client executes the runtime code of the genesis state, ensure it is the code
+        // registered for the runtime, whose hash is tracked separately from the raw genesis
+        let genesis_code_hash = raw_genesis.get_runtime_code().map(T::Hashing::hash);
+        let registered_code_hash =
+            RuntimeSummaries::<T>::get(domain_config.runtime_id).map(|summary| summary.code_hash);
+        ensure!(
+            genesis_code_hash.is_some() && genesis_code_hash == registered_code_hash,
+            Error::GenesisRuntimeCodeMismatch
         );
 
-        ExecutionReceiptOf::<T>::genesis(
-            state_root,
-            sp_domains::EMPTY_EXTRINSIC_ROOT.into(),
-            genesis_block_hash,
-        )
+        derive_genesis_receipt::<T>(&raw_genesis, state_version)
     };
     let genesis_receipt_hash = genesis_receipt.hash::<DomainHashingFor<T>>();
 
+    if let Some(contract_creation_allow_list) =
+        domain_config.initial_contract_creation_allow_list.clone()
+    {
+        EvmDomainContractCreationAllowedBy::<T>::insert(domain_id, contract_creation_allow_list);
+    }
+
     let domain_obj = DomainObject {
         owner_account_id: owner_account_id.clone(),
         created_at,
@@ -268,6 +329,9 @@ pub(crate) fn do_instantiate_domain<T: Config>(
         domain_runtime_info,
     };
     DomainRegistry::<T>::insert(domain_id, domain_obj);
+    OwnedDomains::<T>::mutate(&owner_account_id, |owned_domains| {
+        owned_domains.insert(domain_id);
+    });
 
     let next_domain_id = domain_id.checked_add(&1.into()).ok_or(Error::MaxDomainId)?;
     NextDomainId::<T>::set(next_domain_id);
@@ -298,6 +362,27 @@ pub(crate) fn do_instantiate_domain<T: Config>(
     Ok(domain_id)
 }
 
+/// Derives the genesis receipt of a domain from its raw genesis storage.
+pub(crate) fn derive_genesis_receipt<T: Config>(
+    raw_genesis: &RawGenesis,
+    state_version: StateVersion,
+) -> ExecutionReceiptOf<T> {
+    let state_root = raw_genesis.state_root::<DomainHashingFor<T>>(state_version);
+    let genesis_block_hash = derive_domain_block_hash::<T::DomainHeader>(
+        Zero::zero(),
+        sp_domains::EMPTY_EXTRINSIC_ROOT.into(),
+        state_root,
+        Default::default(),
+        Default::default(),
+    );
+
+    ExecutionReceiptOf::<T>::genesis(
+        state_root,
+        sp_domains::EMPTY_EXTRINSIC_ROOT.into(),
+        genesis_block_hash,
+    )
+}
+
 pub(crate) fn do_update_domain_allow_list<T: Config>(
     domain_owner: T::AccountId,
     domain_id: DomainId,
@@ -315,6 +400,40 @@ pub(crate) fn do_update_domain_allow_list<T: Config>(
     })
 }
 
+pub(crate) fn do_update_contract_creation_allow_list<T: Config>(
+    domain_owner: T::AccountId,
+    domain_id: DomainId,
+    updated_contract_creation_allow_list: PermissionedActionAllowedBy<EthereumAccountId>,
+) -> Result<(), Error> {
+    let domain_obj = DomainRegistry::<T>::get(domain_id).ok_or(Error::DomainNotFound)?;
+    ensure!(
+        domain_obj.owner_account_id == domain_owner,
+        Error::NotDomainOwner
+    );
+    ensure!(
+        matches!(
+            domain_obj.domain_runtime_info,
+            DomainRuntimeInfo::EVM { .. }
+        ),
+        Error::ContractCreationAllowListNotSupported
+    );
+    ensure!(
+        updated_contract_creation_allow_list.accounts_len() as u32
+            <= T::MaxContractCreationAllowListLength::get(),
+        Error::MaxContractCreationAllowListLength
+    );
+
+    EvmDomainContractCreationAllowedBy::<T>::insert(
+        domain_id,
+        updated_contract_creation_allow_list.clone(),
+    );
+    EvmDomainContractCreationAllowListUpdate::<T>::insert(
+        domain_id,
+        updated_contract_creation_allow_list,
+    );
+    Ok(())
+}
+
 // See https://forum.subspace.network/t/on-bundle-weight-limits-sum/2277 for more details
 // about the formula
 pub(crate) fn calculate_max_bundle_weight_and_size(
@@ -346,14 +465,17 @@ pub(crate) fn calculate_max_bundle_weight_and_size(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::runtime_registry::RuntimeObject;
-    use crate::tests::{new_test_ext, Test};
+    use crate::runtime_registry::{insert_runtime_object, RuntimeObject};
+    use crate::tests::{new_test_ext, MinBundleSize, MinBundleWeight, RuntimeOrigin, Test};
     use domain_runtime_primitives::{AccountId20, AccountId20Converter};
-    use frame_support::traits::Currency;
+    use frame_support::traits::{BuildGenesisConfig, Currency};
     use frame_support::{assert_err, assert_ok};
     use hex_literal::hex;
+    use sp_core::crypto::UncheckedFrom;
     use sp_domains::storage::RawGenesis;
+    use sp_domains::{GenesisDomain, OperatorPublicKey};
     use sp_runtime::traits::Convert;
+    use sp_runtime::Percent;
     use sp_std::vec;
     use sp_version::RuntimeVersion;
     use subspace_runtime_primitives::SSC;
@@ -374,6 +496,7 @@ mod tests {
             target_bundles_per_block: 0,
             operator_allow_list: OperatorAllowList::Anyone,
             initial_balances: Default::default(),
+            initial_contract_creation_allow_list: None,
         };
 
         let mut ext = new_test_ext();
@@ -394,13 +517,13 @@ mod tests {
                 Err(Error::RuntimeNotFound)
             );
             // Register runtime id
-            RuntimeRegistry::<Test>::insert(
+            insert_runtime_object::<Test>(
                 domain_config.runtime_id,
                 RuntimeObject {
                     runtime_name: "evm".to_owned(),
                     runtime_type: Default::default(),
                     runtime_upgrades: 0,
-                    hash: Default::default(),
+                    hash: <Test as frame_system::Config>::Hashing::hash(&[1, 2, 3, 4]),
                     raw_genesis: RawGenesis::dummy(vec![1, 2, 3, 4]),
                     version: RuntimeVersion {
                         spec_name: "test".into(),
@@ -466,6 +589,33 @@ mod tests {
             // Recorrect `bundle_slot_probability`
             domain_config.bundle_slot_probability = (1, 1);
 
+            // Failed to instantiate domain due to the bundle limit is too small, there are 6
+            // expected bundles per consensus block with the `bundle_slot_probability` of (1, 1)
+            // and the `ConsensusSlotProbability` of (1, 6)
+            let expected_bundles_per_block = 6;
+            assert_eq!(
+                do_instantiate_domain::<Test>(domain_config.clone(), creator, created_at),
+                Err(Error::BundleLimitTooSmall)
+            );
+            domain_config.max_block_size = MinBundleSize::get() * expected_bundles_per_block - 1;
+            domain_config.max_block_weight =
+                MinBundleWeight::get() * expected_bundles_per_block as u64;
+            assert_eq!(
+                do_instantiate_domain::<Test>(domain_config.clone(), creator, created_at),
+                Err(Error::BundleLimitTooSmall)
+            );
+            domain_config.max_block_size = MinBundleSize::get() * expected_bundles_per_block;
+            domain_config.max_block_weight = MinBundleWeight::get()
+                * expected_bundles_per_block as u64
+                - Weight::from_parts(1, 0);
+            assert_eq!(
+                do_instantiate_domain::<Test>(domain_config.clone(), creator, created_at),
+                Err(Error::BundleLimitTooSmall)
+            );
+            // Recorrect `max_block_weight`, the bundle limit is just enough now
+            domain_config.max_block_weight =
+                MinBundleWeight::get() * expected_bundles_per_block as u64;
+
             // Failed to instantiate domain due to creator don't have enough fund
             assert_eq!(
                 do_instantiate_domain::<Test>(domain_config.clone(), creator, created_at),
@@ -520,25 +670,26 @@ mod tests {
         let mut domain_config = DomainConfig {
             domain_name: "evm-domain".to_owned(),
             runtime_id: 0,
-            max_block_size: 10,
-            max_block_weight: Weight::from_parts(1, 0),
+            max_block_size: 1024,
+            max_block_weight: Weight::from_parts(1024, 0),
             bundle_slot_probability: (1, 1),
             target_bundles_per_block: 1,
             operator_allow_list: OperatorAllowList::Anyone,
             initial_balances: vec![(MultiAccountId::Raw(vec![0, 1, 2, 3, 4, 5]), 1_000_000 * SSC)],
+            initial_contract_creation_allow_list: None,
         };
 
         let mut ext = new_test_ext();
         ext.execute_with(|| {
             assert_eq!(NextDomainId::<Test>::get(), 0.into());
             // Register runtime id
-            RuntimeRegistry::<Test>::insert(
+            insert_runtime_object::<Test>(
                 domain_config.runtime_id,
                 RuntimeObject {
                     runtime_name: "evm".to_owned(),
                     runtime_type: Default::default(),
                     runtime_upgrades: 0,
-                    hash: Default::default(),
+                    hash: <Test as frame_system::Config>::Hashing::hash(&[1, 2, 3, 4]),
                     raw_genesis: RawGenesis::dummy(vec![1, 2, 3, 4]),
                     version: RuntimeVersion {
                         spec_name: "test".into(),
@@ -674,4 +825,335 @@ mod tests {
             assert_eq!(domain_obj.domain_config, domain_config);
         });
     }
+
+    #[test]
+    fn test_domain_instantiation_evm_contract_creation_allow_list() {
+        let creator = 1u128;
+        let created_at = 0u64;
+        let allowed_account = AccountId20::from(hex!("f24FF3a9CF04c71Dbc94D0b566f7A27B94566cac"));
+        let initial_allow_list = PermissionedActionAllowedBy::Accounts(vec![allowed_account]);
+        let domain_config = DomainConfig {
+            domain_name: "evm-domain".to_owned(),
+            runtime_id: 0,
+            max_block_size: 1024,
+            max_block_weight: Weight::from_parts(1024, 0),
+            bundle_slot_probability: (1, 1),
+            target_bundles_per_block: 1,
+            operator_allow_list: OperatorAllowList::Anyone,
+            initial_balances: Default::default(),
+            initial_contract_creation_allow_list: Some(initial_allow_list.clone()),
+        };
+
+        let mut ext = new_test_ext();
+        ext.execute_with(|| {
+            insert_runtime_object::<Test>(
+                domain_config.runtime_id,
+                RuntimeObject {
+                    runtime_name: "evm".to_owned(),
+                    runtime_type: Default::default(),
+                    runtime_upgrades: 0,
+                    hash: <Test as frame_system::Config>::Hashing::hash(&[1, 2, 3, 4]),
+                    raw_genesis: RawGenesis::dummy(vec![1, 2, 3, 4]),
+                    version: RuntimeVersion {
+                        spec_name: "test".into(),
+                        spec_version: 1,
+                        impl_version: 1,
+                        transaction_version: 1,
+                        ..Default::default()
+                    },
+                    created_at: Default::default(),
+                    updated_at: Default::default(),
+                },
+            );
+            Balances::make_free_balance_be(
+                &creator,
+                <Test as Config>::DomainInstantiationDeposit::get()
+                    + <Test as pallet_balances::Config>::ExistentialDeposit::get(),
+            );
+
+            let domain_id =
+                do_instantiate_domain::<Test>(domain_config, creator, created_at).unwrap();
+            assert_eq!(
+                crate::Pallet::<Test>::evm_domain_contract_creation_allowed_by(domain_id),
+                Some(initial_allow_list.clone())
+            );
+
+            // The initial allow list is injected into the domain raw genesis
+            let (domain_instance_data, _) =
+                crate::Pallet::<Test>::domain_instance_data(domain_id).unwrap();
+            let genesis_storage = domain_instance_data.raw_genesis.into_storage();
+            assert_eq!(
+                genesis_storage
+                    .top
+                    .get(&sp_domains::evm_contract_creation_allowed_by_storage_key().0),
+                Some(&initial_allow_list.encode())
+            );
+
+            // Only the domain owner can update the allow list
+            let updated_allow_list = PermissionedActionAllowedBy::Anyone;
+            assert_err!(
+                do_update_contract_creation_allow_list::<Test>(
+                    2u128,
+                    domain_id,
+                    updated_allow_list.clone()
+                ),
+                Error::NotDomainOwner
+            );
+            assert_err!(
+                do_update_contract_creation_allow_list::<Test>(
+                    creator,
+                    DomainId::new(1),
+                    updated_allow_list.clone()
+                ),
+                Error::DomainNotFound
+            );
+
+            // The allow list can not exceed `MaxContractCreationAllowListLength`
+            let max_len = <Test as Config>::MaxContractCreationAllowListLength::get();
+            let too_long_allow_list =
+                PermissionedActionAllowedBy::Accounts(vec![allowed_account; max_len as usize + 1]);
+            assert_err!(
+                do_update_contract_creation_allow_list::<Test>(
+                    creator,
+                    domain_id,
+                    too_long_allow_list
+                ),
+                Error::MaxContractCreationAllowListLength
+            );
+            assert_ok!(do_update_contract_creation_allow_list::<Test>(
+                creator,
+                domain_id,
+                PermissionedActionAllowedBy::Accounts(vec![allowed_account; max_len as usize])
+            ));
+
+            assert_ok!(crate::Pallet::<Test>::update_contract_creation_allow_list(
+                RuntimeOrigin::signed(creator),
+                domain_id,
+                updated_allow_list.clone()
+            ));
+            assert_eq!(
+                crate::Pallet::<Test>::evm_domain_contract_creation_allowed_by(domain_id),
+                Some(updated_allow_list)
+            );
+        });
+    }
+
+    #[test]
+    fn test_domain_instantiation_max_domains_per_owner() {
+        let creator = 1u128;
+        let created_at = 0u64;
+        let expected_bundles_per_block = 6;
+        let domain_config = DomainConfig {
+            domain_name: "evm-domain".to_owned(),
+            runtime_id: 0,
+            max_block_size: MinBundleSize::get() * expected_bundles_per_block,
+            max_block_weight: MinBundleWeight::get() * expected_bundles_per_block as u64,
+            bundle_slot_probability: (1, 1),
+            target_bundles_per_block: 1,
+            operator_allow_list: OperatorAllowList::Anyone,
+            initial_balances: Default::default(),
+            initial_contract_creation_allow_list: None,
+        };
+
+        let mut ext = new_test_ext();
+        ext.execute_with(|| {
+            insert_runtime_object::<Test>(
+                domain_config.runtime_id,
+                RuntimeObject {
+                    runtime_name: "evm".to_owned(),
+                    runtime_type: Default::default(),
+                    runtime_upgrades: 0,
+                    hash: <Test as frame_system::Config>::Hashing::hash(&[1, 2, 3, 4]),
+                    raw_genesis: RawGenesis::dummy(vec![1, 2, 3, 4]),
+                    version: RuntimeVersion {
+                        spec_name: "test".into(),
+                        spec_version: 1,
+                        impl_version: 1,
+                        transaction_version: 1,
+                        ..Default::default()
+                    },
+                    created_at: Default::default(),
+                    updated_at: Default::default(),
+                },
+            );
+
+            let max_domains = <Test as Config>::MaxDomainsPerOwner::get();
+            Balances::make_free_balance_be(
+                &creator,
+                <Test as Config>::DomainInstantiationDeposit::get() * (max_domains + 1) as u128
+                    + <Test as pallet_balances::Config>::ExistentialDeposit::get(),
+            );
+
+            let mut owned_domains = vec![];
+            for _ in 0..max_domains {
+                let domain_id =
+                    do_instantiate_domain::<Test>(domain_config.clone(), creator, created_at)
+                        .unwrap();
+                owned_domains.push(domain_id);
+            }
+            assert_eq!(
+                crate::Pallet::<Test>::domains_owned_by(creator),
+                owned_domains
+            );
+
+            // The creator still has enough fund but already owns the max number of domains
+            assert_eq!(
+                do_instantiate_domain::<Test>(domain_config.clone(), creator, created_at),
+                Err(Error::TooManyDomains)
+            );
+
+            // The cap is per owner
+            let other_creator = 2u128;
+            Balances::make_free_balance_be(
+                &other_creator,
+                <Test as Config>::DomainInstantiationDeposit::get()
+                    + <Test as pallet_balances::Config>::ExistentialDeposit::get(),
+            );
+            let domain_id =
+                do_instantiate_domain::<Test>(domain_config, other_creator, created_at).unwrap();
+            assert_eq!(
+                crate::Pallet::<Test>::domains_owned_by(other_creator),
+                vec![domain_id]
+            );
+            assert_eq!(
+                crate::Pallet::<Test>::domains_owned_by(creator),
+                owned_domains
+            );
+        });
+    }
+
+    #[test]
+    fn test_domain_instantiation_genesis_runtime_code_mismatch() {
+        let creator = 1u128;
+        let created_at = 0u64;
+        let expected_bundles_per_block = 6;
+        let domain_config = DomainConfig {
+            domain_name: "evm-domain".to_owned(),
+            runtime_id: 0,
+            max_block_size: MinBundleSize::get() * expected_bundles_per_block,
+            max_block_weight: MinBundleWeight::get() * expected_bundles_per_block as u64,
+            bundle_slot_probability: (1, 1),
+            target_bundles_per_block: 1,
+            operator_allow_list: OperatorAllowList::Anyone,
+            initial_balances: Default::default(),
+            initial_contract_creation_allow_list: None,
+        };
+
+        let mut ext = new_test_ext();
+        ext.execute_with(|| {
+            let runtime_obj = RuntimeObject {
+                runtime_name: "evm".to_owned(),
+                runtime_type: Default::default(),
+                runtime_upgrades: 0,
+                hash: <Test as frame_system::Config>::Hashing::hash(&[1, 2, 3, 4]),
+                raw_genesis: RawGenesis::dummy(vec![1, 2, 3, 4]),
+                version: RuntimeVersion {
+                    spec_name: "test".into(),
+                    spec_version: 1,
+                    impl_version: 1,
+                    transaction_version: 1,
+                    ..Default::default()
+                },
+                created_at: Default::default(),
+                updated_at: Default::default(),
+            };
+            let summary = runtime_obj.summary(domain_config.runtime_id);
+            insert_runtime_object::<Test>(domain_config.runtime_id, runtime_obj);
+
+            Balances::make_free_balance_be(
+                &creator,
+                <Test as Config>::DomainInstantiationDeposit::get()
+                    + <Test as pallet_balances::Config>::ExistentialDeposit::get(),
+            );
+
+            // The raw genesis embeds different code than the registered one
+            RuntimeSummaries::<Test>::mutate(domain_config.runtime_id, |maybe_summary| {
+                maybe_summary.as_mut().unwrap().code_hash =
+                    <Test as frame_system::Config>::Hashing::hash(&[5, 6, 7, 8]);
+            });
+            assert_eq!(
+                do_instantiate_domain::<Test>(domain_config.clone(), creator, created_at),
+                Err(Error::GenesisRuntimeCodeMismatch)
+            );
+
+            // The registered code hash is unknown
+            RuntimeSummaries::<Test>::remove(domain_config.runtime_id);
+            assert_eq!(
+                do_instantiate_domain::<Test>(domain_config.clone(), creator, created_at),
+                Err(Error::GenesisRuntimeCodeMismatch)
+            );
+
+            RuntimeSummaries::<Test>::insert(domain_config.runtime_id, summary);
+            let domain_id =
+                do_instantiate_domain::<Test>(domain_config, creator, created_at).unwrap();
+            assert!(crate::Pallet::<Test>::genesis_execution_receipt(domain_id).is_some());
+        });
+    }
+
+    #[test]
+    fn test_genesis_domain_counts_towards_max_domains_per_owner() {
+        let creator = 1u128;
+        let created_at = 0u64;
+        let expected_bundles_per_block = 6;
+
+        let mut ext = new_test_ext();
+        ext.execute_with(|| {
+            Balances::make_free_balance_be(&creator, 1000 * SSC);
+
+            crate::GenesisConfig::<Test> {
+                genesis_domain: Some(GenesisDomain {
+                    runtime_name: "evm".to_owned(),
+                    runtime_type: Default::default(),
+                    runtime_version: RuntimeVersion {
+                        spec_name: "test".into(),
+                        spec_version: 1,
+                        impl_version: 1,
+                        transaction_version: 1,
+                        ..Default::default()
+                    },
+                    raw_genesis_storage: RawGenesis::dummy(vec![1, 2, 3, 4]).encode(),
+                    owner_account_id: creator,
+                    domain_name: "evm-domain".to_owned(),
+                    max_block_size: MinBundleSize::get() * expected_bundles_per_block,
+                    max_block_weight: MinBundleWeight::get() * expected_bundles_per_block as u64,
+                    bundle_slot_probability: (1, 1),
+                    target_bundles_per_block: 1,
+                    operator_allow_list: OperatorAllowList::Anyone,
+                    signing_key: OperatorPublicKey::unchecked_from([1u8; 32]),
+                    minimum_nominator_stake: SSC,
+                    nomination_tax: Percent::zero(),
+                    initial_balances: Default::default(),
+                }),
+                permissioned_action_allowed_by: None,
+            }
+            .build();
+
+            let genesis_domain_id = DomainId::new(0);
+            assert_eq!(
+                crate::Pallet::<Test>::domains_owned_by(creator),
+                vec![genesis_domain_id]
+            );
+
+            // The genesis domain takes one of the owner's slots
+            let domain_config = DomainRegistry::<Test>::get(genesis_domain_id)
+                .unwrap()
+                .domain_config;
+            let max_domains = <Test as Config>::MaxDomainsPerOwner::get();
+            for _ in 1..max_domains {
+                assert_ok!(do_instantiate_domain::<Test>(
+                    domain_config.clone(),
+                    creator,
+                    created_at
+                ));
+            }
+            assert_eq!(
+                crate::Pallet::<Test>::domains_owned_by(creator).len() as u32,
+                max_domains
+            );
+            assert_eq!(
+                do_instantiate_domain::<Test>(domain_config, creator, created_at),
+                Err(Error::TooManyDomains)
+            );
+        });
+    }
 }
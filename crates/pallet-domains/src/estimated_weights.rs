@@ -0,0 +1,232 @@
+//! Hand-estimated weights for pallet_domains.
+//!
+//! THE WEIGHTS IN THIS FILE ARE NOT BENCHMARKED, they are estimated by hand from the storage
+//! accesses of the call and the benchmarked weight of the closest call in `weights.rs`.
+//!
+//! Each of these calls has a benchmark in `benchmarking.rs`, once `weights.rs` is regenerated
+//! with the benchmark CLI the regenerated weight must be used and the estimate here removed.
+
+use crate::weights::SubstrateWeight;
+use frame_support::traits::Get;
+use frame_support::weights::constants::ParityDbWeight;
+use frame_support::weights::{RuntimeDbWeight, Weight};
+
+/// Hand-estimated weight functions needed for pallet_domains.
+pub trait EstimatedWeightInfo {
+    fn lazy_prune_bad_receipt(n: u32) -> Weight;
+    fn pause_operator() -> Weight;
+    fn resume_operator() -> Weight;
+    fn cancel_pending_slash() -> Weight;
+    fn set_operator_metadata() -> Weight;
+    fn clear_operator_metadata() -> Weight;
+    fn verify_consensus_block_mmr_proof() -> Weight;
+    fn update_contract_creation_allow_list(n: u32) -> Weight;
+    fn report_rejected_bundles(n: u32) -> Weight;
+}
+
+/// Estimated weight of `lazy_prune_bad_receipt`, based on the weight of `handle_bad_receipt`
+/// plus the lookup of the `LatestSubmittedER` and the slash of each submitter.
+///
+/// Storage: `Domains::BlockTree` (r:1 w:1)
+/// Storage: `Domains::BlockTreeNodes` (r:1 w:1)
+/// Storage: `Domains::LatestSubmittedER` (r:n w:0)
+/// Storage: `Domains::OperatorSlashEvidence` (r:n w:n)
+/// Storage: `Domains::Operators` (r:n w:n)
+/// Storage: `Domains::PendingSlashes` (r:1 w:1)
+/// Storage: `Domains::DomainStakingSummary` (r:1 w:1)
+/// Storage: `Domains::PendingOperatorSwitches` (r:1 w:1)
+fn lazy_prune_bad_receipt(db_weight: RuntimeDbWeight, n: u32) -> Weight {
+    Weight::from_parts(38_000_000, 4559)
+        .saturating_add(Weight::from_parts(20_416_712, 0).saturating_mul(n.into()))
+        .saturating_add(db_weight.reads(5_u64))
+        .saturating_add(db_weight.reads((3_u64).saturating_mul(n.into())))
+        .saturating_add(db_weight.writes(5_u64))
+        .saturating_add(db_weight.writes((2_u64).saturating_mul(n.into())))
+        .saturating_add(Weight::from_parts(0, 2683).saturating_mul(n.into()))
+}
+
+/// Estimated weight of `pause_operator`, based on the weight of `deregister_operator` without
+/// the update of the domain staking summary.
+///
+/// Storage: `Domains::OperatorIdOwner` (r:1 w:0)
+/// Storage: `Domains::Operators` (r:1 w:1)
+/// Storage: `Domains::PendingSlashes` (r:1 w:0)
+fn pause_operator(db_weight: RuntimeDbWeight) -> Weight {
+    Weight::from_parts(19_000_000, 4277)
+        .saturating_add(db_weight.reads(3_u64))
+        .saturating_add(db_weight.writes(1_u64))
+}
+
+/// Estimated weight of `resume_operator`, based on the weight of `deregister_operator` without
+/// the update of the domain staking summary.
+///
+/// Storage: `Domains::OperatorIdOwner` (r:1 w:0)
+/// Storage: `Domains::Operators` (r:1 w:1)
+/// Storage: `Domains::PendingSlashes` (r:1 w:0)
+fn resume_operator(db_weight: RuntimeDbWeight) -> Weight {
+    Weight::from_parts(19_000_000, 4278)
+        .saturating_add(db_weight.reads(3_u64))
+        .saturating_add(db_weight.writes(1_u64))
+}
+
+/// Estimated weight of `cancel_pending_slash`, based on the storage accesses of the call, it
+/// only removes the operator from the pending slashes and re-adds it to the next operator set.
+///
+/// Storage: `Domains::PendingSlashes` (r:1 w:1)
+/// Storage: `Domains::DeferredSlashes` (r:1 w:1)
+/// Storage: `Domains::Operators` (r:1 w:1)
+/// Storage: `Domains::DomainStakingSummary` (r:1 w:1)
+fn cancel_pending_slash(db_weight: RuntimeDbWeight) -> Weight {
+    Weight::from_parts(31_000_000, 4487)
+        .saturating_add(db_weight.reads(4_u64))
+        .saturating_add(db_weight.writes(4_u64))
+}
+
+/// Estimated weight of `set_operator_metadata`, based on the storage accesses of the call, the
+/// metadata deposit is held or released on the operator owner account.
+///
+/// Storage: `Domains::OperatorIdOwner` (r:1 w:0)
+/// Storage: `Domains::Operators` (r:1 w:0)
+/// Storage: `Domains::PendingSlashes` (r:1 w:0)
+/// Storage: `Domains::RegisteredOperatorMetadata` (r:1 w:1)
+/// Storage: `Balances::Holds` (r:1 w:1)
+/// Storage: `System::Account` (r:1 w:1)
+fn set_operator_metadata(db_weight: RuntimeDbWeight) -> Weight {
+    Weight::from_parts(55_000_000, 6215)
+        .saturating_add(db_weight.reads(6_u64))
+        .saturating_add(db_weight.writes(3_u64))
+}
+
+/// Estimated weight of `clear_operator_metadata`, based on the storage accesses of the call, the
+/// metadata deposit is released to the operator owner account.
+///
+/// Storage: `Domains::OperatorIdOwner` (r:1 w:0)
+/// Storage: `Domains::RegisteredOperatorMetadata` (r:1 w:1)
+/// Storage: `Balances::Holds` (r:1 w:1)
+/// Storage: `System::Account` (r:1 w:1)
+fn clear_operator_metadata(db_weight: RuntimeDbWeight) -> Weight {
+    Weight::from_parts(48_000_000, 6215)
+        .saturating_add(db_weight.reads(4_u64))
+        .saturating_add(db_weight.writes(3_u64))
+}
+
+/// Estimated weight of `verify_consensus_block_mmr_proof`, based on the storage accesses of the
+/// MMR proof verification against the consensus chain MMR.
+///
+/// Storage: `Mmr::NumberOfLeaves` (r:1 w:0)
+/// Storage: `Mmr::Nodes` (r:1 w:0)
+fn verify_consensus_block_mmr_proof(db_weight: RuntimeDbWeight) -> Weight {
+    Weight::from_parts(15_000_000, 3505).saturating_add(db_weight.reads(2_u64))
+}
+
+/// Estimated weight of `update_contract_creation_allow_list`, based on the weight of
+/// `update_domain_operator_allow_list` plus the encoding of the `n` accounts of the allow list,
+/// which is stored twice.
+///
+/// Storage: `Domains::DomainRegistry` (r:1 w:0)
+/// Storage: `Domains::EvmDomainContractCreationAllowedBy` (r:0 w:1)
+/// Storage: `Domains::EvmDomainContractCreationAllowListUpdate` (r:0 w:1)
+fn update_contract_creation_allow_list(db_weight: RuntimeDbWeight, n: u32) -> Weight {
+    Weight::from_parts(16_000_000, 3897)
+        .saturating_add(Weight::from_parts(40_000, 0).saturating_mul(n.into()))
+        .saturating_add(db_weight.reads(1_u64))
+        .saturating_add(db_weight.writes(2_u64))
+}
+
+/// Estimated weight of `report_rejected_bundles`, based on the storage accesses of the call for
+/// each of the `n` reported bundles plus the verification of its bundle header signature, which
+/// is a single sr25519 signature verification.
+///
+/// Storage: `Domains::RejectedBundlesReported` (r:0 w:1)
+/// Storage: `Domains::Operators` (r:n w:0)
+/// Storage: `Domains::LastRejectedBundleSlot` (r:n w:n)
+/// Storage: `Domains::InboxedBundleAuthor` (r:n w:0)
+/// Storage: `Domains::BlockTree` (r:n w:0)
+/// Storage: `Domains::BlockTreeNodes` (r:n w:0)
+/// Storage: `Domains::CompetingReceipts` (r:n w:n)
+/// Storage: `Domains::RejectedBundleCount` (r:n w:n)
+/// Storage: `Domains::OperatorRejectedBundles` (r:n w:n)
+fn report_rejected_bundles(db_weight: RuntimeDbWeight, n: u32) -> Weight {
+    Weight::from_parts(5_000_000, 0)
+        .saturating_add(Weight::from_parts(70_000_000, 4_800).saturating_mul(n.into()))
+        .saturating_add(db_weight.reads((8_u64).saturating_mul(n.into())))
+        .saturating_add(db_weight.writes(1_u64))
+        .saturating_add(db_weight.writes((4_u64).saturating_mul(n.into())))
+}
+
+impl<T: frame_system::Config> EstimatedWeightInfo for SubstrateWeight<T> {
+    fn lazy_prune_bad_receipt(n: u32) -> Weight {
+        lazy_prune_bad_receipt(T::DbWeight::get(), n)
+    }
+
+    fn pause_operator() -> Weight {
+        pause_operator(T::DbWeight::get())
+    }
+
+    fn resume_operator() -> Weight {
+        resume_operator(T::DbWeight::get())
+    }
+
+    fn cancel_pending_slash() -> Weight {
+        cancel_pending_slash(T::DbWeight::get())
+    }
+
+    fn set_operator_metadata() -> Weight {
+        set_operator_metadata(T::DbWeight::get())
+    }
+
+    fn clear_operator_metadata() -> Weight {
+        clear_operator_metadata(T::DbWeight::get())
+    }
+
+    fn verify_consensus_block_mmr_proof() -> Weight {
+        verify_consensus_block_mmr_proof(T::DbWeight::get())
+    }
+
+    fn update_contract_creation_allow_list(n: u32) -> Weight {
+        update_contract_creation_allow_list(T::DbWeight::get(), n)
+    }
+
+    fn report_rejected_bundles(n: u32) -> Weight {
+        report_rejected_bundles(T::DbWeight::get(), n)
+    }
+}
+
+// For backwards compatibility and tests
+impl EstimatedWeightInfo for () {
+    fn lazy_prune_bad_receipt(n: u32) -> Weight {
+        lazy_prune_bad_receipt(ParityDbWeight::get(), n)
+    }
+
+    fn pause_operator() -> Weight {
+        pause_operator(ParityDbWeight::get())
+    }
+
+    fn resume_operator() -> Weight {
+        resume_operator(ParityDbWeight::get())
+    }
+
+    fn cancel_pending_slash() -> Weight {
+        cancel_pending_slash(ParityDbWeight::get())
+    }
+
+    fn set_operator_metadata() -> Weight {
+        set_operator_metadata(ParityDbWeight::get())
+    }
+
+    fn clear_operator_metadata() -> Weight {
+        clear_operator_metadata(ParityDbWeight::get())
+    }
+
+    fn verify_consensus_block_mmr_proof() -> Weight {
+        verify_consensus_block_mmr_proof(ParityDbWeight::get())
+    }
+
+    fn update_contract_creation_allow_list(n: u32) -> Weight {
+        update_contract_creation_allow_list(ParityDbWeight::get(), n)
+    }
+
+    fn report_rejected_bundles(n: u32) -> Weight {
+        report_rejected_bundles(ParityDbWeight::get(), n)
+    }
+}
@@ -27,6 +27,8 @@ mod tests;
 pub mod block_tree;
 mod bundle_storage_fund;
 pub mod domain_registry;
+pub mod estimated_weights;
+pub mod migrations;
 pub mod runtime_registry;
 mod staking;
 mod staking_epoch;
@@ -34,11 +36,15 @@ pub mod weights;
 
 extern crate alloc;
 
-use crate::block_tree::verify_execution_receipt;
-use crate::bundle_storage_fund::storage_fund_account;
+use crate::block_tree::{
+    ensure_consensus_block_not_in_future, expected_consensus_hash_at, is_conflicting_receipt,
+    is_lagging_receipt, is_receipt_of_domain, verify_execution_receipt, Error as BlockTreeError,
+};
+use crate::bundle_storage_fund::{storage_fee_shortfall, storage_fund_account};
 use crate::domain_registry::Error as DomainRegistryError;
-use crate::staking::OperatorStatus;
-use crate::staking_epoch::EpochTransitionResult;
+use crate::estimated_weights::EstimatedWeightInfo;
+use crate::staking::{settle_nominator_tax, DomainEpoch, OperatorEpochSummary, OperatorStatus};
+use crate::staking_epoch::{do_finalize_domain_current_epoch, EpochTransitionResult};
 use crate::weights::WeightInfo;
 #[cfg(not(feature = "std"))]
 use alloc::boxed::Box;
@@ -46,8 +52,10 @@ use alloc::collections::btree_map::BTreeMap;
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
 use codec::{Decode, Encode};
+use domain_runtime_primitives::EthereumAccountId;
 use frame_support::ensure;
 use frame_support::pallet_prelude::StorageVersion;
+use frame_support::storage::with_storage_layer;
 use frame_support::traits::fungible::{Inspect, InspectHold};
 use frame_support::traits::tokens::{Fortitude, Preservation};
 use frame_support::traits::{Get, Randomness as RandomnessT};
@@ -59,11 +67,18 @@ use scale_info::TypeInfo;
 use sp_consensus_subspace::consensus::is_proof_of_time_valid;
 use sp_consensus_subspace::WrappedPotOutput;
 use sp_core::H256;
-use sp_domains::bundle_producer_election::BundleProducerElectionParams;
+use sp_domains::bundle_producer_election::{
+    calculate_threshold, BundleProducerElectionParams, EpochElectionParams,
+};
+use sp_domains::inherents::{
+    RejectedBundle, RejectedBundleReason, RejectedBundleRecord, RejectedBundles,
+};
 use sp_domains::{
-    DomainBlockLimit, DomainBundleLimit, DomainId, DomainInstanceData, ExecutionReceipt,
-    OpaqueBundle, OperatorId, OperatorPublicKey, RuntimeId,
-    DOMAIN_EXTRINSICS_SHUFFLING_SEED_SUBJECT, EMPTY_EXTRINSIC_ROOT,
+    BundleDigest, DomainBlockLimit, DomainBundleLimit, DomainFees, DomainId, DomainInstanceData,
+    DomainStateRoot, EffectiveDomainParams, EpochIndex, ExecutionReceipt, OpaqueBundle, OperatorId,
+    OperatorMetadata, OperatorPublicKey, OperatorSignature, RuntimeId, RuntimeSummary,
+    SealedBundleHeader, SlashEvidence, DOMAIN_EXTRINSICS_SHUFFLING_SEED_SUBJECT,
+    EMPTY_EXTRINSIC_ROOT,
 };
 use sp_domains_fraud_proof::fraud_proof::{
     FraudProof, InvalidBlockFeesProof, InvalidDomainBlockHashProof,
@@ -74,9 +89,13 @@ use sp_domains_fraud_proof::verification::{
     verify_invalid_domain_extrinsics_root_fraud_proof, verify_invalid_state_transition_fraud_proof,
     verify_invalid_transfers_fraud_proof, verify_valid_bundle_fraud_proof,
 };
+use sp_domains_fraud_proof::ConsensusBlockMmrProofVerifier;
+use sp_messenger::messages::ConsensusChainMmrLeafProof;
 use sp_runtime::traits::{BlockNumberProvider, CheckedSub, Hash, Header, One, Zero};
 use sp_runtime::transaction_validity::TransactionPriority;
-use sp_runtime::{RuntimeAppPublic, SaturatedConversion, Saturating};
+use sp_runtime::{
+    DispatchError, Perbill, Perquintill, RuntimeAppPublic, SaturatedConversion, Saturating,
+};
 pub use staking::OperatorConfig;
 use subspace_core_primitives::{BlockHash, PotOutput, SlotNumber, U256};
 use subspace_runtime_primitives::Balance;
@@ -93,6 +112,7 @@ pub trait HoldIdentifier<T: Config> {
     fn staking_staked(operator_id: OperatorId) -> FungibleHoldId<T>;
     fn domain_instantiation_id(domain_id: DomainId) -> FungibleHoldId<T>;
     fn storage_fund_withdrawal(operator_id: OperatorId) -> FungibleHoldId<T>;
+    fn operator_metadata_deposit(operator_id: OperatorId) -> FungibleHoldId<T>;
 }
 
 pub trait BlockSlot<T: frame_system::Config> {
@@ -103,6 +123,29 @@ pub trait BlockSlot<T: frame_system::Config> {
     fn slot_produced_after(to_check: sp_consensus_slots::Slot) -> Option<BlockNumberFor<T>>;
 }
 
+/// Verifier of the signatures of the bundles submitted by the operators
+pub trait BundleSignatureVerifier {
+    /// Returns whether `signature` of the bundle header with `pre_hash` is made with `signing_key`
+    fn verify_bundle_signature<M: AsRef<[u8]>>(
+        signing_key: &OperatorPublicKey,
+        pre_hash: &M,
+        signature: &OperatorSignature,
+    ) -> bool;
+}
+
+/// Verifies the bundle signatures with the operator signing keys
+pub struct DefaultBundleSignatureVerifier;
+
+impl BundleSignatureVerifier for DefaultBundleSignatureVerifier {
+    fn verify_bundle_signature<M: AsRef<[u8]>>(
+        signing_key: &OperatorPublicKey,
+        pre_hash: &M,
+        signature: &OperatorSignature,
+    ) -> bool {
+        signing_key.verify(pre_hash, signature)
+    }
+}
+
 pub type ExecutionReceiptOf<T> = ExecutionReceipt<
     BlockNumberFor<T>,
     <T as frame_system::Config>::Hash,
@@ -118,6 +161,13 @@ pub type OpaqueBundleOf<T> = OpaqueBundle<
     BalanceOf<T>,
 >;
 
+pub type SealedBundleHeaderOf<T> = SealedBundleHeader<
+    BlockNumberFor<T>,
+    <T as frame_system::Config>::Hash,
+    <T as Config>::DomainHeader,
+    BalanceOf<T>,
+>;
+
 /// Parameters used to verify proof of election.
 #[derive(TypeInfo, Debug, Encode, Decode, Clone, PartialEq, Eq)]
 pub(crate) struct ElectionVerificationParams<Balance> {
@@ -125,6 +175,25 @@ pub(crate) struct ElectionVerificationParams<Balance> {
     total_domain_stake: Balance,
 }
 
+/// The epoch transition of a domain that is not completed upon the confirmation of the epoch
+/// boundary block, it is retried with an exponential backoff.
+#[derive(TypeInfo, Debug, Encode, Decode, Clone, PartialEq, Eq)]
+pub(crate) struct PendingEpochTransitionInfo<DomainNumber, ConsensusNumber> {
+    /// The epoch boundary domain block.
+    domain_block_number: DomainNumber,
+    /// The number of failed attempts to complete the transition.
+    failed_attempts: u32,
+    /// The consensus block from which the transition is retried in `on_initialize`.
+    retry_at: ConsensusNumber,
+}
+
+/// The maximum backoff, in consensus blocks, between the retries of a failed epoch transition.
+const MAX_EPOCH_TRANSITION_RETRY_BACKOFF: u32 = 1024;
+
+/// The portion of the block weight that can be used to retry the pending epoch transitions in
+/// `on_initialize`.
+const EPOCH_TRANSITION_RETRY_WEIGHT_RATIO: Perbill = Perbill::from_percent(25);
+
 #[derive(Debug, Decode, Encode, TypeInfo, PartialEq, Eq, Clone)]
 pub(crate) enum FraudProofTag {
     BadER(DomainId),
@@ -135,6 +204,9 @@ pub type DomainBlockNumberFor<T> = <<T as Config>::DomainHeader as Header>::Numb
 pub type DomainHashingFor<T> = <<T as Config>::DomainHeader as Header>::Hashing;
 pub type ReceiptHashFor<T> = <<T as Config>::DomainHeader as Header>::Hash;
 
+pub type SlashEvidenceOf<T> =
+    SlashEvidence<DomainBlockNumberFor<T>, BlockNumberFor<T>, ReceiptHashFor<T>>;
+
 pub type BlockTreeNodeFor<T> = crate::block_tree::BlockTreeNode<
     BlockNumberFor<T>,
     <T as frame_system::Config>::Hash,
@@ -143,14 +215,12 @@ pub type BlockTreeNodeFor<T> = crate::block_tree::BlockTreeNode<
     BalanceOf<T>,
 >;
 
-/// The current storage version.
-const STORAGE_VERSION: StorageVersion = StorageVersion::new(0);
+/// The MMR proof of a consensus block that a fraud proof may carry.
+pub type ConsensusBlockMmrProofOf<T> =
+    ConsensusChainMmrLeafProof<<T as frame_system::Config>::Hash, <T as Config>::MmrHash>;
 
-/// The number of bundle of a particular domain to be included in the block is probabilistic
-/// and based on the consensus chain slot probability and domain bundle slot probability, usually
-/// the value is 6 on average, smaller/bigger value with less probability, we hypocritically use
-/// 100 as the maximum number of bundle per block for benchmarking.
-const MAX_BUNLDE_PER_BLOCK: u32 = 100;
+/// The current storage version.
+const STORAGE_VERSION: StorageVersion = StorageVersion::new(6);
 
 #[frame_support::pallet]
 mod pallet {
@@ -163,38 +233,51 @@ mod pallet {
     use crate::block_tree::{prune_receipt, AcceptedReceiptType};
     #[cfg(not(feature = "runtime-benchmarks"))]
     use crate::bundle_storage_fund::refund_storage_fee;
-    use crate::bundle_storage_fund::{charge_bundle_storage_fee, Error as BundleStorageFundError};
+    use crate::bundle_storage_fund::{
+        charge_bundle_storage_fee, ensure_bundle_storage_fee_affordable,
+        Error as BundleStorageFundError,
+    };
     use crate::domain_registry::{
-        do_instantiate_domain, do_update_domain_allow_list, DomainConfig, DomainObject,
-        Error as DomainRegistryError,
+        do_instantiate_domain, do_update_contract_creation_allow_list, do_update_domain_allow_list,
+        DomainConfig, DomainObject, Error as DomainRegistryError,
     };
+    use crate::estimated_weights::EstimatedWeightInfo;
     use crate::runtime_registry::{
         do_register_runtime, do_schedule_runtime_upgrade, do_upgrade_runtimes,
         register_runtime_at_genesis, Error as RuntimeRegistryError, RuntimeObject,
         ScheduledRuntimeUpgrade,
     };
+    #[cfg(feature = "try-runtime")]
+    use crate::staking::check_staking_invariants;
     #[cfg(not(feature = "runtime-benchmarks"))]
     use crate::staking::do_reward_operators;
+    #[cfg(not(feature = "runtime-benchmarks"))]
+    use crate::staking::note_slash_evidence;
     use crate::staking::{
-        do_deregister_operator, do_nominate_operator, do_register_operator, do_slash_operators,
-        do_unlock_funds, do_unlock_operator, do_withdraw_stake, Deposit, DomainEpoch,
-        Error as StakingError, Operator, OperatorConfig, SharePrice, StakingSummary, Withdrawal,
+        do_cancel_pending_slash, do_clear_operator_metadata, do_deregister_operator,
+        do_nominate_operator, do_register_operator, do_set_operator_metadata,
+        do_set_operator_paused, do_slash_operators, do_unlock_funds, do_unlock_operator,
+        do_withdraw_stake, prune_expired_slash_evidence, DeferredSlash, Deposit, DomainEpoch,
+        Error as StakingError, NominatorTax, Operator, OperatorConfig, OperatorEpochSummary,
+        OperatorMetadataEntry, SharePrice, StakingSummary, UnlockOutcome, Withdrawal,
+    };
+    use crate::staking_epoch::{
+        do_finalize_domain_current_epoch, do_finalize_slashed_operators, Error as StakingEpochError,
     };
-    use crate::staking_epoch::{do_finalize_domain_current_epoch, Error as StakingEpochError};
     use crate::weights::WeightInfo;
     #[cfg(not(feature = "runtime-benchmarks"))]
     use crate::DomainHashingFor;
     use crate::{
-        BalanceOf, BlockSlot, BlockTreeNodeFor, DomainBlockNumberFor, ElectionVerificationParams,
-        HoldIdentifier, NominatorId, OpaqueBundleOf, ReceiptHashFor, MAX_BUNLDE_PER_BLOCK,
-        STORAGE_VERSION,
+        BalanceOf, BlockSlot, BlockTreeNodeFor, ConsensusBlockMmrProofOf, DomainBlockNumberFor,
+        ElectionVerificationParams, HoldIdentifier, NominatorId, OpaqueBundleOf,
+        PendingEpochTransitionInfo, ReceiptHashFor, SlashEvidenceOf, STORAGE_VERSION,
     };
     #[cfg(not(feature = "std"))]
     use alloc::string::String;
     #[cfg(not(feature = "std"))]
     use alloc::vec::Vec;
     use codec::FullCodec;
-    use domain_runtime_primitives::EVMChainId;
+    use domain_runtime_primitives::{EVMChainId, EthereumAccountId};
     use frame_support::pallet_prelude::*;
     use frame_support::traits::fungible::{InspectHold, Mutate, MutateHold};
     use frame_support::traits::Randomness as RandomnessT;
@@ -204,18 +287,24 @@ mod pallet {
     use sp_consensus_slots::Slot;
     use sp_core::H256;
     use sp_domains::bundle_producer_election::ProofOfElectionError;
+    use sp_domains::inherents::{
+        InherentType, RejectedBundleReason, RejectedBundleRecord, RejectedBundles,
+        INHERENT_IDENTIFIER,
+    };
+    #[cfg(not(feature = "runtime-benchmarks"))]
+    use sp_domains::{BadReceiptMismatch, SlashEvidence};
     use sp_domains::{
-        BundleDigest, ConfirmedDomainBlock, DomainBundleSubmitted, DomainId,
+        BundleDigest, ConfirmedDomainBlock, DomainBundleLimit, DomainBundleSubmitted, DomainId,
         DomainsTransfersTracker, EpochIndex, GenesisDomain, OperatorAllowList, OperatorId,
         OperatorPublicKey, RuntimeId, RuntimeType,
     };
     use sp_domains_fraud_proof::fraud_proof::FraudProof;
-    use sp_domains_fraud_proof::InvalidTransactionCode;
+    use sp_domains_fraud_proof::{ConsensusBlockMmrProofVerifier, InvalidTransactionCode};
     use sp_runtime::traits::{
         AtLeast32BitUnsigned, BlockNumberProvider, CheckEqual, CheckedAdd, Header as HeaderT,
         MaybeDisplay, One, SimpleBitOps, Zero,
     };
-    use sp_runtime::Saturating;
+    use sp_runtime::{FixedU128, Saturating};
     use sp_std::boxed::Box;
     use sp_std::collections::btree_set::BTreeSet;
     use sp_std::fmt::Debug;
@@ -251,6 +340,17 @@ mod pallet {
         /// The domain header type.
         type DomainHeader: HeaderT<Hash = Self::DomainHash>;
 
+        /// Hash type of the consensus chain MMR.
+        type MmrHash: Parameter + Member + Default + Clone;
+
+        /// Verifier of the MMR proof of the consensus block that a fraud proof may carry, used
+        /// when the consensus block hash is no longer available in the state.
+        type MmrProofVerifier: ConsensusBlockMmrProofVerifier<
+            BlockNumberFor<Self>,
+            Self::Hash,
+            Self::MmrHash,
+        >;
+
         /// Same with `pallet_subspace::Config::ConfirmationDepthK`.
         #[pallet::constant]
         type ConfirmationDepthK: Get<BlockNumberFor<Self>>;
@@ -259,6 +359,14 @@ mod pallet {
         #[pallet::constant]
         type DomainRuntimeUpgradeDelay: Get<BlockNumberFor<Self>>;
 
+        /// Minimum delay governance may choose when scheduling a domain runtime upgrade.
+        #[pallet::constant]
+        type MinRuntimeUpgradeDelay: Get<BlockNumberFor<Self>>;
+
+        /// Maximum delay governance may choose when scheduling a domain runtime upgrade.
+        #[pallet::constant]
+        type MaxRuntimeUpgradeDelay: Get<BlockNumberFor<Self>>;
+
         /// Currency type used by the domains for staking and other currency related stuff.
         type Currency: Mutate<Self::AccountId>
             + InspectHold<Self::AccountId>
@@ -284,6 +392,11 @@ mod pallet {
         #[pallet::constant]
         type BlockTreePruningDepth: Get<DomainBlockNumberFor<Self>>;
 
+        /// The maximum gap between `HeadDomainNumber` and `HeadReceiptNumber`, beyond which bundles
+        /// that do not carry a head-extending receipt are deprioritized so the receipts can catch up.
+        #[pallet::constant]
+        type MaxReceiptGap: Get<DomainBlockNumberFor<Self>>;
+
         /// Consensus chain slot probability.
         #[pallet::constant]
         type ConsensusSlotProbability: Get<(u64, u64)>;
@@ -300,16 +413,35 @@ mod pallet {
         #[pallet::constant]
         type MaxBundlesPerBlock: Get<u32>;
 
+        /// The maximum number of extrinsics in a bundle, regardless of the bundle limit of the
+        /// domain.
+        #[pallet::constant]
+        type MaxBundleExtrinsics: Get<u32>;
+
+        /// The minimum bundle size limit of the domain, the domain can't be instantiated if its
+        /// bundle size limit is below this value.
+        #[pallet::constant]
+        type MinBundleSize: Get<u32>;
+
+        /// The minimum bundle weight limit of the domain, the domain can't be instantiated if its
+        /// bundle weight limit is below this value.
+        #[pallet::constant]
+        type MinBundleWeight: Get<Weight>;
+
         /// The maximum domain name length limit for all domain.
         #[pallet::constant]
         type MaxDomainNameLength: Get<u32>;
 
+        /// The maximum number of domains a single account can own.
+        #[pallet::constant]
+        type MaxDomainsPerOwner: Get<u32>;
+
         /// The amount of fund to be locked up for the domain instance creator.
         #[pallet::constant]
         type DomainInstantiationDeposit: Get<BalanceOf<Self>>;
 
         /// Weight information for extrinsics in this pallet.
-        type WeightInfo: WeightInfo;
+        type WeightInfo: WeightInfo + EstimatedWeightInfo;
 
         /// Initial domain tx range value.
         #[pallet::constant]
@@ -327,6 +459,12 @@ mod pallet {
         #[pallet::constant]
         type MinNominatorStake: Get<BalanceOf<Self>>;
 
+        /// Minimum free balance, on top of the existential deposit, that must be left in the
+        /// account after a nomination or operator registration is held, so the account can still
+        /// pay the fee of future staking transactions (e.g. `withdraw_stake`).
+        #[pallet::constant]
+        type NominationKeepAlive: Get<BalanceOf<Self>>;
+
         /// Minimum number of blocks after which any finalized withdrawals are released to nominators.
         #[pallet::constant]
         type StakeWithdrawalLockingPeriod: Get<DomainBlockNumberFor<Self>>;
@@ -347,6 +485,54 @@ mod pallet {
         #[pallet::constant]
         type MaxNominators: Get<u32>;
 
+        /// Deposit held from the operator owner for storing the operator metadata.
+        #[pallet::constant]
+        type OperatorMetadataDeposit: Get<BalanceOf<Self>>;
+
+        /// The maximum number of slash evidence kept for given operator, the oldest evidence is
+        /// dropped when exceeded.
+        #[pallet::constant]
+        type MaxSlashEvidence: Get<u32>;
+
+        /// The number of consensus blocks the slash evidence of an operator is kept after the
+        /// slashed operator is removed.
+        #[pallet::constant]
+        type SlashEvidenceRetentionPeriod: Get<BlockNumberFor<Self>>;
+
+        /// The number of domain epochs the finalization of a slash is deferred, during which the
+        /// slash can be cancelled by root through `cancel_pending_slash`. Zero means the slash is
+        /// finalized at the end of the current epoch.
+        #[pallet::constant]
+        type SlashDeferDuration: Get<EpochIndex>;
+
+        /// The maximum number of rejected bundle records kept for given operator, the oldest record
+        /// is dropped when exceeded.
+        #[pallet::constant]
+        type MaxRejectedBundleRecords: Get<u32>;
+
+        /// The number of the latest epochs whose fees are kept for every domain, see
+        /// `DomainEpochFees`.
+        #[pallet::constant]
+        type DomainFeeHistoryDepth: Get<u32>;
+
+        /// The number of the latest epochs whose summary is kept for every operator, see
+        /// `EpochSummary`.
+        #[pallet::constant]
+        type OperatorEpochSummaryHistoryDepth: Get<u32>;
+
+        /// The number of consensus blocks that the head receipt of a domain is not extended after
+        /// which the domain is considered stalled, the overdue maintenance of a stalled domain can
+        /// be performed by anyone through `maintain_domain`.
+        #[pallet::constant]
+        type DomainStallPeriod: Get<BlockNumberFor<Self>>;
+
+        /// The number of consensus blocks that the head receipt of a domain is not extended after
+        /// which the domain is considered frozen, the stake of a frozen domain is unlocked on the
+        /// consensus clock since the confirmed domain block it is usually locked against doesn't
+        /// advance, see `StakeWithdrawalLockingPeriod`.
+        #[pallet::constant]
+        type DomainFreezePeriod: Get<BlockNumberFor<Self>>;
+
         /// Randomness source.
         type Randomness: RandomnessT<Self::Hash, BlockNumberFor<Self>>;
 
@@ -369,12 +555,20 @@ mod pallet {
         /// Minimum balance for each initial domain account
         type MinInitialDomainAccountBalance: Get<BalanceOf<Self>>;
 
+        /// Upper limit for the number of accounts in the contract creation allow list of an EVM
+        /// domain
+        #[pallet::constant]
+        type MaxContractCreationAllowListLength: Get<u32>;
+
         /// How many block a bundle should still consider as valid after produced
         #[pallet::constant]
         type BundleLongevity: Get<u32>;
 
         /// Post hook to notify accepted domain bundles in previous block.
         type DomainBundleSubmitted: DomainBundleSubmitted;
+
+        /// The verifier of the bundle signatures.
+        type BundleSignatureVerifier: BundleSignatureVerifier;
     }
 
     #[pallet::pallet]
@@ -414,6 +608,12 @@ mod pallet {
     pub(super) type RuntimeRegistry<T: Config> =
         StorageMap<_, Identity, RuntimeId, RuntimeObject<BlockNumberFor<T>, T::Hash>, OptionQuery>;
 
+    /// The summary of the runtimes in `RuntimeRegistry`, kept separately so the runtime metadata
+    /// can be read without decoding the raw genesis of the runtime.
+    #[pallet::storage]
+    pub(super) type RuntimeSummaries<T: Config> =
+        StorageMap<_, Identity, RuntimeId, RuntimeSummary<BlockNumberFor<T>, T::Hash>, OptionQuery>;
+
     #[pallet::storage]
     pub(super) type ScheduledRuntimeUpgrades<T: Config> = StorageDoubleMap<
         _,
@@ -421,10 +621,16 @@ mod pallet {
         BlockNumberFor<T>,
         Identity,
         RuntimeId,
-        ScheduledRuntimeUpgrade<T::Hash>,
+        ScheduledRuntimeUpgrade<BlockNumberFor<T>, T::Hash>,
         OptionQuery,
     >;
 
+    /// The block number at which the pending upgrade of the runtime in `ScheduledRuntimeUpgrades`
+    /// is applied, a runtime can only have one upgrade pending at a time.
+    #[pallet::storage]
+    pub(super) type PendingRuntimeUpgrades<T: Config> =
+        StorageMap<_, Identity, RuntimeId, BlockNumberFor<T>, OptionQuery>;
+
     #[pallet::storage]
     pub(super) type NextOperatorId<T> = StorageValue<_, OperatorId, ValueQuery>;
 
@@ -432,6 +638,16 @@ mod pallet {
     pub(super) type OperatorIdOwner<T: Config> =
         StorageMap<_, Identity, OperatorId, T::AccountId, OptionQuery>;
 
+    /// The metadata registered by the operator owner along with the deposit held for it.
+    #[pallet::storage]
+    pub(super) type RegisteredOperatorMetadata<T: Config> = StorageMap<
+        _,
+        Identity,
+        OperatorId,
+        OperatorMetadataEntry<T::AccountId, BalanceOf<T>>,
+        OptionQuery,
+    >;
+
     /// Indexes operator signing key against OperatorId.
     #[pallet::storage]
     #[pallet::getter(fn operator_signing_key)]
@@ -449,7 +665,7 @@ mod pallet {
         _,
         Identity,
         OperatorId,
-        Operator<BalanceOf<T>, T::Share, DomainBlockNumberFor<T>>,
+        Operator<BalanceOf<T>, T::Share, DomainBlockNumberFor<T>, BlockNumberFor<T>>,
         OptionQuery,
     >;
 
@@ -465,6 +681,45 @@ mod pallet {
     pub type OperatorEpochSharePrice<T: Config> =
         StorageDoubleMap<_, Identity, OperatorId, Identity, DomainEpoch, SharePrice, OptionQuery>;
 
+    /// The summary of the staking changes finalized for the operator at the end of each epoch of
+    /// its domain, only the last `OperatorEpochSummaryHistoryDepth` epochs are kept.
+    #[pallet::storage]
+    pub(super) type EpochSummary<T: Config> = StorageDoubleMap<
+        _,
+        Identity,
+        OperatorId,
+        Identity,
+        DomainEpoch,
+        OperatorEpochSummary<BalanceOf<T>, T::Share>,
+        OptionQuery,
+    >;
+
+    /// The operator tax accumulated per share of the operator pool over all the epochs.
+    #[pallet::storage]
+    pub(super) type OperatorTaxPerShare<T: Config> =
+        StorageMap<_, Identity, OperatorId, FixedU128, ValueQuery>;
+
+    /// `OperatorTaxPerShare` at the end of Domain epoch, noted along with the epoch share price.
+    #[pallet::storage]
+    pub(super) type OperatorEpochTaxPerShare<T: Config> =
+        StorageDoubleMap<_, Identity, OperatorId, Identity, DomainEpoch, FixedU128, OptionQuery>;
+
+    /// The operator tax paid by each nominator of the operator.
+    ///
+    /// The entry is settled lazily whenever the shares of the nominator change, the tax paid since
+    /// then is derived from `OperatorTaxPerShare`, see `Pallet::nominator_tax_paid`. The entry is
+    /// removed once the nominator fully exits the operator pool.
+    #[pallet::storage]
+    pub(super) type TaxPaid<T: Config> = StorageDoubleMap<
+        _,
+        Identity,
+        OperatorId,
+        Identity,
+        NominatorId<T>,
+        NominatorTax<BalanceOf<T>, T::Share>,
+        ValueQuery,
+    >;
+
     /// List of all deposits for given Operator.
     #[pallet::storage]
     pub(super) type Deposits<T: Config> = StorageDoubleMap<
@@ -485,7 +740,7 @@ mod pallet {
         OperatorId,
         Identity,
         NominatorId<T>,
-        Withdrawal<BalanceOf<T>, T::Share, DomainBlockNumberFor<T>>,
+        Withdrawal<BalanceOf<T>, T::Share, DomainBlockNumberFor<T>, BlockNumberFor<T>>,
         OptionQuery,
     >;
 
@@ -501,17 +756,103 @@ mod pallet {
 
     /// A list operators who were slashed during the current epoch associated with the domain.
     /// When the epoch for a given domain is complete, operator total stake is moved to treasury and
-    /// then deleted.
+    /// then deleted, unless the slash is still deferred, see `DeferredSlashes`.
     #[pallet::storage]
     pub(super) type PendingSlashes<T: Config> =
         StorageMap<_, Identity, DomainId, BTreeSet<OperatorId>, OptionQuery>;
 
+    /// The operators in `PendingSlashes` whose slash is deferred by `SlashDeferDuration` epochs,
+    /// the entry is removed when the slash is finalized or cancelled.
+    #[pallet::storage]
+    pub(super) type DeferredSlashes<T: Config> = StorageMap<
+        _,
+        Identity,
+        OperatorId,
+        DeferredSlash<DomainBlockNumberFor<T>, BlockNumberFor<T>>,
+        OptionQuery,
+    >;
+
+    /// Evidence of the bad ERs that the operator is slashed for, at most `MaxSlashEvidence` of
+    /// the latest evidence are kept and they are removed `SlashEvidenceRetentionPeriod` blocks
+    /// after the slashed operator is removed.
+    #[pallet::storage]
+    pub(super) type OperatorSlashEvidence<T: Config> = StorageMap<
+        _,
+        Identity,
+        OperatorId,
+        BoundedVec<SlashEvidenceOf<T>, T::MaxSlashEvidence>,
+        ValueQuery,
+    >;
+
+    /// The operators whose slash evidence is removed at the given consensus block.
+    #[pallet::storage]
+    pub(super) type SlashEvidenceExpiry<T: Config> =
+        StorageDoubleMap<_, Identity, BlockNumberFor<T>, Identity, OperatorId, (), OptionQuery>;
+
+    /// The total number of bundles of the operator that are reported as rejected by the block
+    /// authors, it is removed along with the operator.
+    #[pallet::storage]
+    pub(super) type RejectedBundleCount<T: Config> =
+        StorageMap<_, Identity, OperatorId, u32, ValueQuery>;
+
+    /// The rejected bundles of the operator, at most `MaxRejectedBundleRecords` of the latest
+    /// records are kept and they are removed along with the operator.
+    #[pallet::storage]
+    pub(super) type OperatorRejectedBundles<T: Config> = StorageMap<
+        _,
+        Identity,
+        OperatorId,
+        Vec<RejectedBundleRecord<BlockNumberFor<T>>>,
+        ValueQuery,
+    >;
+
+    /// The slot of the latest bundle of the operator that is reported as rejected, the bundles of
+    /// the same or an earlier slot are not recorded again. It is removed along with the operator.
+    #[pallet::storage]
+    pub(super) type LastRejectedBundleSlot<T: Config> =
+        StorageMap<_, Identity, OperatorId, u64, OptionQuery>;
+
+    /// Whether the rejected bundles are reported in the current block, the report is accepted at
+    /// most once per block and the flag is removed in `on_finalize`.
+    #[pallet::storage]
+    pub(super) type RejectedBundlesReported<T: Config> = StorageValue<_, bool, ValueQuery>;
+
+    /// The receipts of the rejected bundles that conflict with a receipt accepted earlier in the
+    /// same consensus block, keyed by the hash of the accepted receipt and reported by the block
+    /// authors along with the bundle headers signed by the operators. Only one of the competing
+    /// receipts can be honest, the entry is removed when the accepted receipt is pruned from the
+    /// block tree.
+    #[pallet::storage]
+    pub(super) type CompetingReceipts<T: Config> = StorageMap<
+        _,
+        Identity,
+        ReceiptHashFor<T>,
+        BoundedVec<(OperatorId, ReceiptHashFor<T>), T::MaxBundlesPerBlock>,
+        ValueQuery,
+    >;
+
     /// The pending staking operation count of the current epoch, it should not larger than
     /// `MaxPendingStakingOperation` and will be resetted to 0 upon epoch transition.
     #[pallet::storage]
     pub(super) type PendingStakingOperationCount<T: Config> =
         StorageMap<_, Identity, DomainId, u32, ValueQuery>;
 
+    /// The epoch transition of the domain whose epoch boundary block is confirmed but the
+    /// transition is not completed yet, the transition will be retried in the following blocks.
+    #[pallet::storage]
+    pub(super) type PendingEpochTransition<T: Config> = StorageMap<
+        _,
+        Identity,
+        DomainId,
+        PendingEpochTransitionInfo<DomainBlockNumberFor<T>, BlockNumberFor<T>>,
+        OptionQuery,
+    >;
+
+    /// The domain whose pending epoch transition is retried last in `on_initialize`, the retries
+    /// of the next block continue from the domain after it.
+    #[pallet::storage]
+    pub(super) type LastRetriedEpochTransition<T> = StorageValue<_, DomainId, OptionQuery>;
+
     /// Stores the next domain id.
     #[pallet::storage]
     pub(super) type NextDomainId<T> = StorageValue<_, DomainId, ValueQuery>;
@@ -526,6 +867,11 @@ mod pallet {
         OptionQuery,
     >;
 
+    /// The domains owned by each account, used to enforce `MaxDomainsPerOwner`.
+    #[pallet::storage]
+    pub(super) type OwnedDomains<T: Config> =
+        StorageMap<_, Identity, T::AccountId, BTreeSet<DomainId>, ValueQuery>;
+
     /// The domain block tree, map (`domain_id`, `domain_block_number`) to the hash of ER,
     /// which can be used get the block tree node in `BlockTreeNodes`
     #[pallet::storage]
@@ -556,6 +902,11 @@ mod pallet {
     pub(super) type HeadReceiptExtended<T: Config> =
         StorageMap<_, Identity, DomainId, bool, ValueQuery>;
 
+    /// The consensus block number at which the head receipt of the domain is last extended
+    #[pallet::storage]
+    pub(super) type HeadReceiptExtendedAt<T: Config> =
+        StorageMap<_, Identity, DomainId, BlockNumberFor<T>, ValueQuery>;
+
     /// The consensus block hash used to verify ER,
     /// only store the consensus block hash for a domain
     /// if that consensus block contains bundle of the domain, the hash will be pruned when the ER
@@ -573,6 +924,9 @@ mod pallet {
     ///
     /// 1. Ensure subsequent ERs of that domain block include all pre-validated extrinsic bundles
     /// 2. Index the `InboxedBundleAuthor` and pruned its value when the corresponding `ExecutionInbox` is pruned
+    ///
+    /// NOTE: the digests are stored in the order the bundles are included in the consensus block,
+    /// both the domain block building and the `InvalidExtrinsicsRoot` fraud proof rely on it.
     #[pallet::storage]
     pub type ExecutionInbox<T: Config> = StorageNMap<
         _,
@@ -609,6 +963,41 @@ mod pallet {
     pub(super) type LastEpochStakingDistribution<T: Config> =
         StorageMap<_, Identity, DomainId, ElectionVerificationParams<BalanceOf<T>>, OptionQuery>;
 
+    /// The stake distribution of the past epochs of a given domain, written at each epoch transition
+    /// and used to verify the proof of election of the bundles produced in these epochs.
+    ///
+    /// Only the last `election_params_history_depth` epochs are kept, the full operators map is stored
+    /// since the number of operators of a domain is small.
+    #[pallet::storage]
+    pub(super) type ElectionParamsHistory<T: Config> = StorageDoubleMap<
+        _,
+        Identity,
+        DomainId,
+        Identity,
+        EpochIndex,
+        ElectionVerificationParams<BalanceOf<T>>,
+        OptionQuery,
+    >;
+
+    /// The fees collected by the domain since its instantiation, accumulated whenever a domain
+    /// block is confirmed and the operators are rewarded.
+    #[pallet::storage]
+    pub(super) type DomainFeeTotals<T: Config> =
+        StorageMap<_, Identity, DomainId, DomainFees<BalanceOf<T>>, ValueQuery>;
+
+    /// The fees collected by the domain in each epoch, only the last `DomainFeeHistoryDepth` epochs
+    /// are kept.
+    #[pallet::storage]
+    pub(super) type DomainEpochFees<T: Config> = StorageDoubleMap<
+        _,
+        Identity,
+        DomainId,
+        Identity,
+        EpochIndex,
+        DomainFees<BalanceOf<T>>,
+        OptionQuery,
+    >;
+
     /// Storage to hold all the domain's latest confirmed block.
     #[pallet::storage]
     pub(super) type LatestConfirmedDomainBlock<T: Config> = StorageMap<
@@ -619,6 +1008,21 @@ mod pallet {
         OptionQuery,
     >;
 
+    /// The block hash and state root of the recently confirmed domain blocks, used to serve the state
+    /// root to light clients after the receipt is pruned from the block tree.
+    ///
+    /// Only the last `BlockTreePruningDepth` confirmed domain blocks are kept.
+    #[pallet::storage]
+    pub(super) type ConfirmedDomainStateRoots<T: Config> = StorageDoubleMap<
+        _,
+        Identity,
+        DomainId,
+        Identity,
+        DomainBlockNumberFor<T>,
+        (T::DomainHash, T::DomainHash),
+        OptionQuery,
+    >;
+
     /// The latest ER submitted by the operator for a given domain. It is used to determine if the operator
     /// has submitted bad ER and is pending to slash.
     ///
@@ -635,6 +1039,31 @@ mod pallet {
     pub(super) type PermissionedActionAllowedBy<T: Config> =
         StorageValue<_, sp_domains::PermissionedActionAllowedBy<T::AccountId>, OptionQuery>;
 
+    /// Storage for the current contract creation allow list of the EVM domains, the domain
+    /// runtime reads it through the runtime API during block building.
+    ///
+    /// A domain without entry allows anyone to create contracts.
+    #[pallet::storage]
+    pub(super) type EvmDomainContractCreationAllowedBy<T: Config> = StorageMap<
+        _,
+        Identity,
+        DomainId,
+        sp_domains::PermissionedActionAllowedBy<EthereumAccountId>,
+        OptionQuery,
+    >;
+
+    /// Storage for the contract creation allow list updates of the EVM domains that are not yet
+    /// delivered to the domain, the update is delivered through an inherent of the next domain
+    /// block and removed once a bundle of the domain is submitted.
+    #[pallet::storage]
+    pub(super) type EvmDomainContractCreationAllowListUpdate<T: Config> = StorageMap<
+        _,
+        Identity,
+        DomainId,
+        sp_domains::PermissionedActionAllowedBy<EthereumAccountId>,
+        OptionQuery,
+    >;
+
     #[derive(TypeInfo, Encode, Decode, PalletError, Debug, PartialEq)]
     pub enum BundleError {
         /// Can not find the operator for given operator id.
@@ -669,6 +1098,15 @@ mod pallet {
         UnableToCalculateBundleLimit,
         /// Bundle weight exceeds the max bundle weight limit
         BundleTooHeavy,
+        /// Operator paused bundle production.
+        OperatorPaused,
+        /// Bundle contains more extrinsics than `MaxBundleExtrinsics`.
+        TooManyExtrinsics,
+        /// The execution receipt conflicts with the receipt accepted for the same domain block
+        /// in the current consensus block.
+        ConflictingReceipt,
+        /// The execution receipt is not chained to the block tree of the domain of the bundle.
+        ReceiptDomainMismatch,
     }
 
     #[derive(TypeInfo, Encode, Decode, PalletError, Debug, PartialEq)]
@@ -704,6 +1142,10 @@ mod pallet {
         BadBundleEquivocationFraudProof,
         /// The bad receipt already reported by a previous fraud proof
         BadReceiptAlreadyReported,
+        /// The bad receipt is not derived from a consensus block known to the current chain.
+        BadReceiptConsensusBlock(BlockTreeError),
+        /// The MMR proof of the consensus block that the bad receipt is derived from is invalid.
+        InvalidConsensusBlockMmrProof,
     }
 
     impl<T> From<FraudProofError> for Error<T> {
@@ -751,6 +1193,64 @@ mod pallet {
         }
     }
 
+    impl From<&BundleError> for InvalidTransactionCode {
+        fn from(err: &BundleError) -> Self {
+            match err {
+                BundleError::DuplicatedBundle => Self::DuplicatedBundle,
+                BundleError::InvalidProofOfTime => Self::BundleProofOfTime,
+                BundleError::SlotInThePast => Self::BundleSlotInThePast,
+                BundleError::SlotInTheFuture => Self::BundleSlotInTheFuture,
+                BundleError::BadBundleSignature | BundleError::BadVrfSignature => {
+                    Self::BundleSignature
+                }
+                BundleError::InvalidOperatorId
+                | BundleError::BadOperator
+                | BundleError::OperatorPaused => Self::BundleOperator,
+                BundleError::ThresholdUnsatisfied => Self::BundleThresholdUnsatisfied,
+                BundleError::BundleTooLarge
+                | BundleError::BundleTooHeavy
+                | BundleError::TooManyExtrinsics
+                | BundleError::UnableToCalculateBundleLimit => Self::BundleLimit,
+                BundleError::Receipt(BlockTreeError::StaleReceipt) => Self::StaleReceipt,
+                BundleError::Receipt(BlockTreeError::InFutureReceipt) => Self::InFutureReceipt,
+                BundleError::Receipt(_) | BundleError::ReceiptDomainMismatch => {
+                    Self::ExecutionReceipt
+                }
+                BundleError::ConflictingReceipt => Self::ConflictingReceipt,
+                BundleError::InvalidDomainId
+                | BundleError::StaleBundle
+                | BundleError::InvalidExtrinsicRoot => Self::Bundle,
+            }
+        }
+    }
+
+    impl From<&FraudProofError> for InvalidTransactionCode {
+        fn from(err: &FraudProofError) -> Self {
+            match err {
+                FraudProofError::BadReceiptNotFound => Self::FraudProofBadReceiptNotFound,
+                FraudProofError::BadReceiptAlreadyReported => {
+                    Self::FraudProofBadReceiptAlreadyReported
+                }
+                _ => Self::FraudProof,
+            }
+        }
+    }
+
+    impl From<&BundleStorageFundError> for InvalidTransactionCode {
+        fn from(err: &BundleStorageFundError) -> Self {
+            match err {
+                BundleStorageFundError::BundleStorageFundEmpty => Self::BundleStorageFundEmpty,
+                BundleStorageFundError::InsufficientBundleStorageFund { .. } => {
+                    Self::InsufficientBundleStorageFund
+                }
+                BundleStorageFundError::BundleStorageFeeWouldKillAccount { .. } => {
+                    Self::BundleStorageFeeWouldKillAccount
+                }
+                _ => Self::BundleStorageFeePayment,
+            }
+        }
+    }
+
     impl<T> From<BundleStorageFundError> for Error<T> {
         fn from(err: BundleStorageFundError) -> Self {
             Error::BundleStorageFund(err)
@@ -804,6 +1304,7 @@ mod pallet {
         DomainRuntimeUpgradeScheduled {
             runtime_id: RuntimeId,
             scheduled_at: BlockNumberFor<T>,
+            delay: BlockNumberFor<T>,
         },
         DomainRuntimeUpgraded {
             runtime_id: RuntimeId,
@@ -811,6 +1312,7 @@ mod pallet {
         OperatorRegistered {
             operator_id: OperatorId,
             domain_id: DomainId,
+            storage_fund_account: T::AccountId,
         },
         OperatorNominated {
             operator_id: OperatorId,
@@ -818,6 +1320,7 @@ mod pallet {
         },
         DomainInstantiated {
             domain_id: DomainId,
+            bundle_limit: DomainBundleLimit,
         },
         OperatorSwitchedDomain {
             old_domain_id: DomainId,
@@ -826,9 +1329,27 @@ mod pallet {
         OperatorDeregistered {
             operator_id: OperatorId,
         },
+        OperatorPaused {
+            operator_id: OperatorId,
+        },
+        OperatorResumed {
+            operator_id: OperatorId,
+        },
+        OperatorMetadataSet {
+            operator_id: OperatorId,
+        },
+        OperatorMetadataCleared {
+            operator_id: OperatorId,
+        },
         OperatorUnlocked {
             operator_id: OperatorId,
         },
+        /// The deregistered operator of a frozen domain is unlocked on the consensus clock at
+        /// the given consensus block.
+        OperatorUnlockScheduled {
+            operator_id: OperatorId,
+            unlock_at: BlockNumberFor<T>,
+        },
         WithdrewStake {
             operator_id: OperatorId,
             nominator_id: NominatorId<T>,
@@ -837,6 +1358,14 @@ mod pallet {
             operator_id: OperatorId,
             nominator_id: NominatorId<T>,
             amount: BalanceOf<T>,
+            beneficiary: Option<T::AccountId>,
+        },
+        /// The withdrawals of the nominator from an operator of a frozen domain are unlocked on
+        /// the consensus clock at the given consensus block.
+        FundsUnlockScheduled {
+            operator_id: OperatorId,
+            nominator_id: NominatorId<T>,
+            unlock_at: BlockNumberFor<T>,
         },
         PreferredOperator {
             operator_id: OperatorId,
@@ -850,6 +1379,15 @@ mod pallet {
             operator_id: OperatorId,
             tax: BalanceOf<T>,
         },
+        OperatorEpochFinalized {
+            operator_id: OperatorId,
+            epoch_index: EpochIndex,
+            new_total_stake: BalanceOf<T>,
+            new_total_shares: T::Share,
+            share_price: SharePrice,
+            deposits_converted: BalanceOf<T>,
+            withdrawals_finalized: BalanceOf<T>,
+        },
         DomainEpochCompleted {
             domain_id: DomainId,
             completed_epoch_index: EpochIndex,
@@ -858,6 +1396,15 @@ mod pallet {
             domain_id: DomainId,
             completed_epoch_index: EpochIndex,
         },
+        DomainEpochTransitionDeferred {
+            domain_id: DomainId,
+            domain_block_number: DomainBlockNumberFor<T>,
+        },
+        DomainMaintained {
+            domain_id: DomainId,
+            finalized_slashed_operators: u32,
+            completed_epoch_index: Option<EpochIndex>,
+        },
         FraudProofProcessed {
             domain_id: DomainId,
             new_head_receipt_number: Option<DomainBlockNumberFor<T>>,
@@ -865,15 +1412,35 @@ mod pallet {
         DomainOperatorAllowListUpdated {
             domain_id: DomainId,
         },
+        EvmDomainContractCreationAllowListUpdated {
+            domain_id: DomainId,
+        },
         OperatorSlashed {
             operator_id: OperatorId,
             reason: SlashedReason<DomainBlockNumberFor<T>, ReceiptHashFor<T>>,
         },
+        PendingSlashCancelled {
+            domain_id: DomainId,
+            operator_id: OperatorId,
+        },
         StorageFeeDeposited {
             operator_id: OperatorId,
             nominator_id: NominatorId<T>,
             amount: BalanceOf<T>,
         },
+        StorageFundRefunded {
+            operator_id: OperatorId,
+            nominator_id: NominatorId<T>,
+            amount: BalanceOf<T>,
+        },
+        StorageFeeRefunded {
+            operator_id: OperatorId,
+            amount: BalanceOf<T>,
+        },
+        BundleRejected {
+            operator_id: OperatorId,
+            reason: RejectedBundleReason,
+        },
     }
 
     /// Per-domain state for tx range calculation.
@@ -944,14 +1511,29 @@ mod pallet {
                             prune_receipt::<T>(domain_id, receipt_block_number)
                                 .map_err(Error::<T>::from)?
                         {
-                            actual_weight =
-                                actual_weight.saturating_add(T::WeightInfo::handle_bad_receipt(
+                            actual_weight = actual_weight.saturating_add(
+                                T::WeightInfo::lazy_prune_bad_receipt(
                                     block_tree_node.operator_ids.len() as u32,
-                                ));
+                                ),
+                            );
 
                             let bad_receipt_hash = block_tree_node
                                 .execution_receipt
                                 .hash::<DomainHashingFor<T>>();
+                            note_slash_evidence::<T>(
+                                &block_tree_node.operator_ids,
+                                SlashEvidence {
+                                    receipt_hash: bad_receipt_hash,
+                                    domain_block_number: block_tree_node
+                                        .execution_receipt
+                                        .domain_block_number,
+                                    consensus_block_number: block_tree_node
+                                        .execution_receipt
+                                        .consensus_block_number,
+                                    fraud_proof_hash: None,
+                                    mismatch: BadReceiptMismatch::Unknown,
+                                },
+                            );
                             do_slash_operators::<T>(
                                 block_tree_node.operator_ids.into_iter(),
                                 SlashedReason::BadExecutionReceipt(bad_receipt_hash),
@@ -982,40 +1564,69 @@ mod pallet {
                                 confirmed_block_info.invalid_bundle_authors.len() as u32,
                             ));
 
-                        refund_storage_fee::<T>(
+                        let storage_fee_refunds = refund_storage_fee::<T>(
                             confirmed_block_info.total_storage_fee,
                             confirmed_block_info.paid_bundle_storage_fees,
                         )
                         .map_err(Error::<T>::from)?;
 
-                        do_reward_operators::<T>(
+                        let operator_rewards = do_reward_operators::<T>(
                             domain_id,
                             confirmed_block_info.operator_ids.into_iter(),
                             confirmed_block_info.rewards,
                         )
                         .map_err(Error::<T>::from)?;
 
-                        do_slash_operators::<T>(
-                            confirmed_block_info.invalid_bundle_authors.into_iter(),
-                            SlashedReason::InvalidBundle(confirmed_block_info.domain_block_number),
+                        if let Some(stake_summary) = DomainStakingSummary::<T>::get(domain_id) {
+                            Self::note_domain_fees(
+                                domain_id,
+                                stake_summary.current_epoch_index,
+                                DomainFees {
+                                    operator_rewards,
+                                    storage_fee_refunds,
+                                    operator_tax: Zero::zero(),
+                                },
+                            );
+                        }
+
+                        do_slash_operators::<T>(
+                            confirmed_block_info.invalid_bundle_authors.into_iter(),
+                            SlashedReason::InvalidBundle(confirmed_block_info.domain_block_number),
                         )
                         .map_err(Error::<T>::from)?;
 
                         if confirmed_block_info.domain_block_number % T::StakeEpochDuration::get()
                             == Zero::zero()
                         {
-                            let epoch_transition_res =
-                                do_finalize_domain_current_epoch::<T>(domain_id)
-                                    .map_err(Error::<T>::from)?;
+                            // The transition of the previous epoch must be completed first as
+                            // the epochs are transitioned in order, the pending transition is
+                            // never overwritten
+                            if PendingEpochTransition::<T>::contains_key(domain_id) {
+                                let (epoch_transition_weight, _) =
+                                    Self::process_pending_epoch_transition(domain_id);
+                                actual_weight =
+                                    actual_weight.saturating_add(epoch_transition_weight);
+                                ensure!(
+                                    !PendingEpochTransition::<T>::contains_key(domain_id),
+                                    Error::<T>::StakingEpoch(
+                                        StakingEpochError::PendingEpochTransition
+                                    )
+                                );
+                            }
 
-                            Self::deposit_event(Event::DomainEpochCompleted {
+                            // Record the transition as pending first so it will be retried later
+                            // if it can't be completed here.
+                            PendingEpochTransition::<T>::insert(
                                 domain_id,
-                                completed_epoch_index: epoch_transition_res.completed_epoch_index,
-                            });
-
-                            actual_weight = actual_weight.saturating_add(
-                                Self::actual_epoch_transition_weight(epoch_transition_res),
+                                PendingEpochTransitionInfo {
+                                    domain_block_number: confirmed_block_info.domain_block_number,
+                                    failed_attempts: 0,
+                                    retry_at: Zero::zero(),
+                                },
                             );
+                            let (epoch_transition_weight, _) =
+                                Self::process_pending_epoch_transition(domain_id);
+                            actual_weight = actual_weight.saturating_add(epoch_transition_weight);
                         }
                     }
                 }
@@ -1031,11 +1642,19 @@ mod pallet {
                 HeadDomainNumber::<T>::set(domain_id, next_number);
             }
 
-            // Put the `extrinsics_root` to the inbox of the current under building domain block
+            // Put the `extrinsics_root` to the inbox of the current under building domain block,
+            // the digest must be appended right after the digests of the bundles accepted earlier
+            // in this consensus block to keep the inclusion order
             let head_domain_number = HeadDomainNumber::<T>::get(domain_id);
             let consensus_block_number = frame_system::Pallet::<T>::current_block_number();
+            let inbox_key = (domain_id, head_domain_number, consensus_block_number);
+            ensure!(
+                ExecutionInbox::<T>::decode_len(inbox_key).unwrap_or_default()
+                    == SuccessfulBundles::<T>::decode_len(domain_id).unwrap_or_default(),
+                Error::<T>::from(BlockTreeError::InboxCountMismatch)
+            );
             ExecutionInbox::<T>::append(
-                (domain_id, head_domain_number, consensus_block_number),
+                inbox_key,
                 BundleDigest {
                     header_hash: bundle_header_hash,
                     extrinsics_root,
@@ -1057,23 +1676,30 @@ mod pallet {
             Ok(Some(actual_weight.min(Self::max_submit_bundle_weight())).into())
         }
 
+        /// Submits a fraud proof of a bad receipt.
+        ///
+        /// `maybe_consensus_block_proof` is the MMR proof of the consensus block that the bad
+        /// receipt is derived from, it is verified in `validate_unsigned` and `pre_dispatch` in
+        /// place of the `ConsensusBlockHash` entry when the entry is no longer available (i.e.
+        /// pruned) and its verification is accounted in the weight if it is provided.
         #[pallet::call_index(1)]
         #[pallet::weight((
-            T::WeightInfo::submit_fraud_proof().saturating_add(
-                T::WeightInfo::handle_bad_receipt(MAX_BUNLDE_PER_BLOCK)
-            ),
+            Pallet::<T>::submit_fraud_proof_weight(maybe_consensus_block_proof.is_some())
+                .saturating_add(T::WeightInfo::handle_bad_receipt(T::MaxBundlesPerBlock::get())),
             DispatchClass::Operational,
             Pays::No
         ))]
         pub fn submit_fraud_proof(
             origin: OriginFor<T>,
             fraud_proof: Box<FraudProof<BlockNumberFor<T>, T::Hash, T::DomainHeader>>,
+            maybe_consensus_block_proof: Option<ConsensusBlockMmrProofOf<T>>,
         ) -> DispatchResultWithPostInfo {
             ensure_none(origin)?;
 
             log::trace!(target: "runtime::domains", "Processing fraud proof: {fraud_proof:?}");
             let domain_id = fraud_proof.domain_id();
-            let mut actual_weight = T::WeightInfo::submit_fraud_proof();
+            let mut actual_weight =
+                Self::submit_fraud_proof_weight(maybe_consensus_block_proof.is_some());
 
             if let Some(bad_receipt_hash) = fraud_proof.targeted_bad_receipt_hash() {
                 let head_receipt_number = HeadReceiptNumber::<T>::get(domain_id);
@@ -1103,9 +1729,24 @@ mod pallet {
 
                     actual_weight =
                         actual_weight.saturating_add(T::WeightInfo::handle_bad_receipt(
-                            (block_tree_node.operator_ids.len() as u32).min(MAX_BUNLDE_PER_BLOCK),
+                            (block_tree_node.operator_ids.len() as u32)
+                                .min(T::MaxBundlesPerBlock::get()),
                         ));
 
+                    note_slash_evidence::<T>(
+                        &block_tree_node.operator_ids,
+                        SlashEvidence {
+                            receipt_hash: bad_receipt_hash,
+                            domain_block_number: bad_receipt_number,
+                            consensus_block_number: block_tree_node
+                                .execution_receipt
+                                .consensus_block_number,
+                            fraud_proof_hash: Some(fraud_proof.hash()),
+                            mismatch: fraud_proof
+                                .bad_receipt_mismatch()
+                                .unwrap_or(BadReceiptMismatch::Unknown),
+                        },
+                    );
                     do_slash_operators::<T>(
                         block_tree_node.operator_ids.into_iter(),
                         SlashedReason::BadExecutionReceipt(bad_receipt_hash),
@@ -1179,17 +1820,23 @@ mod pallet {
             origin: OriginFor<T>,
             runtime_id: RuntimeId,
             raw_genesis_storage: Vec<u8>,
+            delay: Option<BlockNumberFor<T>>,
         ) -> DispatchResult {
             ensure_root(origin)?;
 
             let block_number = frame_system::Pallet::<T>::current_block_number();
-            let scheduled_at =
-                do_schedule_runtime_upgrade::<T>(runtime_id, raw_genesis_storage, block_number)
-                    .map_err(Error::<T>::from)?;
+            let (scheduled_at, delay) = do_schedule_runtime_upgrade::<T>(
+                runtime_id,
+                raw_genesis_storage,
+                block_number,
+                delay,
+            )
+            .map_err(Error::<T>::from)?;
 
             Self::deposit_event(Event::DomainRuntimeUpgradeScheduled {
                 runtime_id,
                 scheduled_at,
+                delay,
             });
 
             Ok(())
@@ -1206,12 +1853,13 @@ mod pallet {
             let owner = ensure_signed(origin)?;
 
             let (operator_id, current_epoch_index) =
-                do_register_operator::<T>(owner, domain_id, amount, config)
+                do_register_operator::<T>(owner, domain_id, amount, config, false)
                     .map_err(Error::<T>::from)?;
 
             Self::deposit_event(Event::OperatorRegistered {
                 operator_id,
                 domain_id,
+                storage_fund_account: storage_fund_account::<T>(operator_id),
             });
 
             // if the domain's current epoch is 0,
@@ -1232,7 +1880,7 @@ mod pallet {
         ) -> DispatchResult {
             let nominator_id = ensure_signed(origin)?;
 
-            do_nominate_operator::<T>(operator_id, nominator_id.clone(), amount)
+            do_nominate_operator::<T>(operator_id, nominator_id.clone(), amount, false)
                 .map_err(Error::<T>::from)?;
 
             Self::deposit_event(Event::OperatorNominated {
@@ -1258,11 +1906,17 @@ mod pallet {
             );
 
             let created_at = frame_system::Pallet::<T>::current_block_number();
+            let bundle_limit = domain_config
+                .calculate_bundle_limit::<T>()
+                .map_err(Error::<T>::from)?;
 
             let domain_id = do_instantiate_domain::<T>(domain_config, who, created_at)
                 .map_err(Error::<T>::from)?;
 
-            Self::deposit_event(Event::DomainInstantiated { domain_id });
+            Self::deposit_event(Event::DomainInstantiated {
+                domain_id,
+                bundle_limit,
+            });
 
             Ok(())
         }
@@ -1304,22 +1958,47 @@ mod pallet {
         /// Unlocks the first withdrawal given the unlocking period is complete.
         /// Even if rest of the withdrawals are out of unlocking period, nominator
         /// should call this extrinsic to unlock each withdrawal
+        ///
+        /// The unlocked funds are transferred to the `beneficiary` if specified, the whole call
+        /// fails if the `beneficiary` can't receive the funds (e.g. below existential deposit).
+        ///
+        /// If the domain is frozen, the first call schedules the withdrawals on the consensus
+        /// clock instead, they are unlocked by calling this extrinsic again once it is reached.
         #[pallet::call_index(10)]
         #[pallet::weight(T::WeightInfo::unlock_funds())]
-        pub fn unlock_funds(origin: OriginFor<T>, operator_id: OperatorId) -> DispatchResult {
+        pub fn unlock_funds(
+            origin: OriginFor<T>,
+            operator_id: OperatorId,
+            beneficiary: Option<T::AccountId>,
+        ) -> DispatchResult {
             let nominator_id = ensure_signed(origin)?;
-            let unlocked_funds = do_unlock_funds::<T>(operator_id, nominator_id.clone())
-                .map_err(crate::pallet::Error::<T>::from)?;
-            Self::deposit_event(Event::FundsUnlocked {
-                operator_id,
-                nominator_id,
-                amount: unlocked_funds,
-            });
+            match do_unlock_funds::<T>(operator_id, nominator_id.clone(), beneficiary.clone())
+                .map_err(crate::pallet::Error::<T>::from)?
+            {
+                UnlockOutcome::Unlocked(unlocked_funds) => {
+                    Self::deposit_event(Event::FundsUnlocked {
+                        operator_id,
+                        nominator_id,
+                        amount: unlocked_funds,
+                        beneficiary,
+                    });
+                }
+                UnlockOutcome::Scheduled(unlock_at) => {
+                    Self::deposit_event(Event::FundsUnlockScheduled {
+                        operator_id,
+                        nominator_id,
+                        unlock_at,
+                    });
+                }
+            }
             Ok(())
         }
 
         /// Unlocks the operator given the unlocking period is complete.
         /// Anyone can initiate the operator unlock.
+        ///
+        /// If the domain is frozen, the first call schedules the operator on the consensus clock
+        /// instead, it is unlocked by calling this extrinsic again once it is reached.
         #[pallet::call_index(11)]
         #[pallet::weight(T::WeightInfo::unlock_operator(T::MaxNominators::get()))]
         pub fn unlock_operator(
@@ -1328,10 +2007,21 @@ mod pallet {
         ) -> DispatchResultWithPostInfo {
             ensure_signed(origin)?;
 
-            let nominator_count =
-                do_unlock_operator::<T>(operator_id).map_err(crate::pallet::Error::<T>::from)?;
-
-            Self::deposit_event(Event::OperatorUnlocked { operator_id });
+            let nominator_count = match do_unlock_operator::<T>(operator_id)
+                .map_err(crate::pallet::Error::<T>::from)?
+            {
+                UnlockOutcome::Unlocked(nominator_count) => {
+                    Self::deposit_event(Event::OperatorUnlocked { operator_id });
+                    nominator_count
+                }
+                UnlockOutcome::Scheduled(unlock_at) => {
+                    Self::deposit_event(Event::OperatorUnlockScheduled {
+                        operator_id,
+                        unlock_at,
+                    });
+                    0
+                }
+            };
 
             Ok(Some(T::WeightInfo::unlock_operator(
                 (nominator_count as u32).min(T::MaxNominators::get()),
@@ -1360,7 +2050,10 @@ mod pallet {
             Ok(())
         }
 
-        /// Force staking epoch transition for a given domain
+        /// Force staking epoch transition for a given domain.
+        ///
+        /// This is an emergency override, an epoch transition that is skipped upon the
+        /// confirmation of the epoch boundary block is retried automatically.
         #[pallet::call_index(13)]
         #[pallet::weight(Pallet::<T>::max_staking_epoch_transition())]
         pub fn force_staking_epoch_transition(
@@ -1371,6 +2064,7 @@ mod pallet {
 
             let epoch_transition_res =
                 do_finalize_domain_current_epoch::<T>(domain_id).map_err(Error::<T>::from)?;
+            PendingEpochTransition::<T>::remove(domain_id);
 
             Self::deposit_event(Event::ForceDomainEpochTransition {
                 domain_id,
@@ -1395,6 +2089,228 @@ mod pallet {
             PermissionedActionAllowedBy::<T>::put(permissioned_action_allowed_by);
             Ok(())
         }
+
+        /// Perform the overdue maintenance of a domain:
+        /// - Complete the epoch transition that is skipped upon the confirmation of the epoch
+        ///   boundary block.
+        /// - Finalize the pending slashes of a stalled domain, i.e. a domain whose head receipt
+        ///   is not extended for `DomainStallPeriod` consensus blocks, thus the maintenance that
+        ///   usually happens lazily upon new bundles can not happen.
+        ///
+        /// This can be called by anyone, the fee is waived if any maintenance is done, otherwise
+        /// this is a no-op and the fee is charged.
+        #[pallet::call_index(15)]
+        #[pallet::weight(Pallet::<T>::max_maintain_domain_weight())]
+        pub fn maintain_domain(
+            origin: OriginFor<T>,
+            domain_id: DomainId,
+        ) -> DispatchResultWithPostInfo {
+            ensure_signed(origin)?;
+
+            ensure!(
+                DomainRegistry::<T>::contains_key(domain_id),
+                Error::<T>::DomainRegistry(DomainRegistryError::DomainNotFound)
+            );
+
+            let (mut actual_weight, completed_epoch_index) =
+                Self::process_pending_epoch_transition(domain_id);
+            actual_weight = actual_weight.saturating_add(T::DbWeight::get().reads(3));
+
+            // At most `MaxBundlesPerBlock` slashed operators are finalized per call to bound the
+            // weight, the rest are finalized by the following calls. The deferred slashes are not
+            // overdue and are kept pending.
+            let mut finalized_slashed_operators = 0;
+            if PendingSlashes::<T>::contains_key(domain_id) && Self::is_domain_stalled(domain_id) {
+                let (finalized_operator_count, slashed_nominator_count) =
+                    do_finalize_slashed_operators::<T>(domain_id, T::MaxBundlesPerBlock::get())
+                        .map_err(|err| Error::<T>::from(StakingEpochError::SlashOperator(err)))?;
+                finalized_slashed_operators = finalized_operator_count;
+                actual_weight = actual_weight.saturating_add(
+                    T::WeightInfo::finalize_slashed_operators(slashed_nominator_count),
+                );
+            }
+
+            // Nothing is overdue
+            if completed_epoch_index.is_none() && finalized_slashed_operators.is_zero() {
+                return Ok(Some(actual_weight).into());
+            }
+
+            Self::deposit_event(Event::DomainMaintained {
+                domain_id,
+                finalized_slashed_operators,
+                completed_epoch_index,
+            });
+
+            Ok((Some(actual_weight), Pays::No).into())
+        }
+
+        /// Extrinsic to update the contract creation allow list of an EVM domain, can only be
+        /// called by the domain owner.
+        #[pallet::call_index(16)]
+        #[pallet::weight(T::WeightInfo::update_contract_creation_allow_list(
+            contract_creation_allow_list.accounts_len() as u32,
+        ))]
+        pub fn update_contract_creation_allow_list(
+            origin: OriginFor<T>,
+            domain_id: DomainId,
+            contract_creation_allow_list: sp_domains::PermissionedActionAllowedBy<
+                EthereumAccountId,
+            >,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            do_update_contract_creation_allow_list::<T>(
+                who,
+                domain_id,
+                contract_creation_allow_list,
+            )
+            .map_err(Error::<T>::from)?;
+            Self::deposit_event(Event::EvmDomainContractCreationAllowListUpdated { domain_id });
+            Ok(())
+        }
+
+        /// Same as `nominate_operator` but allows the caller to explicitly opt-out of the
+        /// `NominationKeepAlive` free balance buffer check by setting `allow_death` to `true`.
+        #[pallet::call_index(17)]
+        #[pallet::weight(T::WeightInfo::nominate_operator())]
+        pub fn nominate_operator_with_allow_death(
+            origin: OriginFor<T>,
+            operator_id: OperatorId,
+            amount: BalanceOf<T>,
+            allow_death: bool,
+        ) -> DispatchResult {
+            let nominator_id = ensure_signed(origin)?;
+
+            do_nominate_operator::<T>(operator_id, nominator_id.clone(), amount, allow_death)
+                .map_err(Error::<T>::from)?;
+
+            Self::deposit_event(Event::OperatorNominated {
+                operator_id,
+                nominator_id,
+            });
+
+            Ok(())
+        }
+
+        /// Report the bundles dropped by the block author while building its previous blocks.
+        ///
+        /// This is an inherent extrinsic that is accepted at most once per block. Every bundle is
+        /// reported with its header signed by the operator and the bundles that can't be verified
+        /// are skipped. Besides the records for the operators, the receipts of the bundles
+        /// rejected with `ConflictingReceipt` are recorded in `CompetingReceipts`.
+        #[pallet::call_index(18)]
+        #[pallet::weight((
+            T::WeightInfo::report_rejected_bundles(rejected_bundles.len() as u32),
+            DispatchClass::Mandatory,
+            Pays::No
+        ))]
+        pub fn report_rejected_bundles(
+            origin: OriginFor<T>,
+            rejected_bundles: RejectedBundles,
+        ) -> DispatchResult {
+            ensure_none(origin)?;
+
+            RejectedBundlesReported::<T>::put(true);
+            Self::do_report_rejected_bundles(rejected_bundles);
+
+            Ok(())
+        }
+
+        /// Pause bundle production of the operator without deregistering it, can only be called
+        /// by the operator owner.
+        ///
+        /// The operator is not elected starting from the next epoch of its domain and its bundles
+        /// are rejected right away, while deposits and withdrawals keep working.
+        #[pallet::call_index(19)]
+        #[pallet::weight(T::WeightInfo::pause_operator())]
+        pub fn pause_operator(origin: OriginFor<T>, operator_id: OperatorId) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            do_set_operator_paused::<T>(who, operator_id, true).map_err(Error::<T>::from)?;
+
+            Self::deposit_event(Event::OperatorPaused { operator_id });
+
+            Ok(())
+        }
+
+        /// Resume bundle production of the paused operator, can only be called by the operator
+        /// owner.
+        ///
+        /// The operator is elected again starting from the next epoch of its domain.
+        #[pallet::call_index(20)]
+        #[pallet::weight(T::WeightInfo::resume_operator())]
+        pub fn resume_operator(origin: OriginFor<T>, operator_id: OperatorId) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            do_set_operator_paused::<T>(who, operator_id, false).map_err(Error::<T>::from)?;
+
+            Self::deposit_event(Event::OperatorResumed { operator_id });
+
+            Ok(())
+        }
+
+        /// Cancel the deferred slash of the operator, can only be called by root.
+        ///
+        /// Used when the slash is determined to be invalid (e.g. due to a bug in the fraud proof
+        /// verification) before it is finalized, the operator is restored to the status before
+        /// the slash and, if registered, is elected again starting from the next epoch.
+        #[pallet::call_index(21)]
+        #[pallet::weight(T::WeightInfo::cancel_pending_slash())]
+        pub fn cancel_pending_slash(
+            origin: OriginFor<T>,
+            domain_id: DomainId,
+            operator_id: OperatorId,
+        ) -> DispatchResult {
+            ensure_root(origin)?;
+
+            do_cancel_pending_slash::<T>(domain_id, operator_id).map_err(Error::<T>::from)?;
+
+            Self::deposit_event(Event::PendingSlashCancelled {
+                domain_id,
+                operator_id,
+            });
+
+            Ok(())
+        }
+
+        /// Set or update the display name and website of the operator, can only be called by the
+        /// operator owner.
+        ///
+        /// `OperatorMetadataDeposit` is held from the owner when the metadata is first set and
+        /// released when it is cleared or the operator is removed.
+        #[pallet::call_index(22)]
+        #[pallet::weight(T::WeightInfo::set_operator_metadata())]
+        pub fn set_operator_metadata(
+            origin: OriginFor<T>,
+            operator_id: OperatorId,
+            name: Vec<u8>,
+            website: Option<Vec<u8>>,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            do_set_operator_metadata::<T>(who, operator_id, name, website)
+                .map_err(Error::<T>::from)?;
+
+            Self::deposit_event(Event::OperatorMetadataSet { operator_id });
+
+            Ok(())
+        }
+
+        /// Clear the metadata of the operator and release its deposit, can only be called by the
+        /// operator owner.
+        #[pallet::call_index(23)]
+        #[pallet::weight(T::WeightInfo::clear_operator_metadata())]
+        pub fn clear_operator_metadata(
+            origin: OriginFor<T>,
+            operator_id: OperatorId,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            do_clear_operator_metadata::<T>(who, operator_id).map_err(Error::<T>::from)?;
+
+            Self::deposit_event(Event::OperatorMetadataCleared { operator_id });
+
+            Ok(())
+        }
     }
 
     #[pallet::genesis_config]
@@ -1442,6 +2358,7 @@ mod pallet {
                     target_bundles_per_block: genesis_domain.target_bundles_per_block,
                     operator_allow_list: genesis_domain.operator_allow_list,
                     initial_balances: genesis_domain.initial_balances,
+                    initial_contract_creation_allow_list: None,
                 };
                 let domain_owner = genesis_domain.owner_account_id;
                 let domain_id =
@@ -1455,8 +2372,16 @@ mod pallet {
                     nomination_tax: genesis_domain.nomination_tax,
                 };
                 let operator_stake = T::MinOperatorStake::get();
-                do_register_operator::<T>(domain_owner, domain_id, operator_stake, operator_config)
-                    .expect("Genesis operator registration must succeed");
+                // The genesis operator does not submit any transaction to register so there is no
+                // need to keep the free balance buffer.
+                do_register_operator::<T>(
+                    domain_owner,
+                    domain_id,
+                    operator_stake,
+                    operator_config,
+                    true,
+                )
+                .expect("Genesis operator registration must succeed");
 
                 do_finalize_domain_current_epoch::<T>(domain_id)
                     .expect("Genesis epoch must succeed");
@@ -1471,6 +2396,8 @@ mod pallet {
             // Do scheduled domain runtime upgrade
             do_upgrade_runtimes::<T>(block_number);
 
+            let pruned_slash_evidence = prune_expired_slash_evidence::<T>(block_number);
+
             // Store the hash of the parent consensus block for domain that have bundles submitted
             // in that consensus block
             let parent_number = block_number - One::one();
@@ -1478,16 +2405,142 @@ mod pallet {
             for (domain_id, _) in SuccessfulBundles::<T>::drain() {
                 ConsensusBlockHash::<T>::insert(domain_id, parent_number, parent_hash);
                 T::DomainBundleSubmitted::domain_bundle_submitted(domain_id);
+                EvmDomainContractCreationAllowListUpdate::<T>::remove(domain_id);
             }
 
             let _ = SuccessfulFraudProofs::<T>::clear(u32::MAX, None);
 
-            Weight::zero()
+            let epoch_transition_weight = Self::retry_pending_epoch_transitions(block_number);
+
+            epoch_transition_weight.saturating_add(T::DbWeight::get().reads_writes(
+                pruned_slash_evidence.saturating_add(1),
+                pruned_slash_evidence.saturating_mul(2),
+            ))
         }
 
         fn on_finalize(_: BlockNumberFor<T>) {
             let _ = LastEpochStakingDistribution::<T>::clear(u32::MAX, None);
             let _ = HeadReceiptExtended::<T>::clear(u32::MAX, None);
+            RejectedBundlesReported::<T>::kill();
+        }
+
+        fn integrity_test() {
+            // Every bundle may prune a reverted bad receipt or confirm a domain block, and reward
+            // and slash operators at their bounds. The pending staking operations are finalized
+            // for the whole domain and bounded separately by `MaxPendingStakingOperation`, so they
+            // are not counted for every bundle.
+            let max_bundle_weight = T::WeightInfo::submit_bundle()
+                .saturating_add(
+                    T::WeightInfo::lazy_prune_bad_receipt(T::MaxNominators::get()).max(
+                        T::WeightInfo::confirm_domain_block(
+                            T::MaxBundlesPerBlock::get(),
+                            T::MaxBundlesPerBlock::get(),
+                        ),
+                    ),
+                )
+                .saturating_add(T::WeightInfo::operator_reward_tax_and_restake(
+                    T::MaxBundlesPerBlock::get(),
+                ))
+                .saturating_add(T::WeightInfo::finalize_slashed_operators(
+                    T::MaxNominators::get(),
+                ));
+            let max_bundles_weight =
+                max_bundle_weight.saturating_mul(T::MaxBundlesPerBlock::get().into());
+            assert!(
+                max_bundles_weight
+                    .all_lte(<T as frame_system::Config>::BlockWeights::get().max_block),
+                "`MaxBundlesPerBlock` bundles must fit in the consensus block weight limit"
+            );
+
+            // `can_instantiate_domain` requires `1 ≤ target_bundles_per_block ≤ MaxBundlesPerBlock`.
+            assert!(
+                !T::MaxBundlesPerBlock::get().is_zero(),
+                "`MaxBundlesPerBlock` must be greater than 0"
+            );
+
+            // The operator is the first nominator of its own pool, see `do_register_operator`.
+            assert!(
+                T::MinNominatorStake::get() <= T::MinOperatorStake::get(),
+                "`MinNominatorStake` must not be greater than `MinOperatorStake`"
+            );
+
+            // A withdrawal must stay locked until all the ERs submitted by the operator before the
+            // withdrawal are out of the challenge period, otherwise the withdrawn stake can escape
+            // the slashing in `do_slash_operators`.
+            assert!(
+                T::StakeWithdrawalLockingPeriod::get() >= T::BlockTreePruningDepth::get(),
+                "`StakeWithdrawalLockingPeriod` must not be less than `BlockTreePruningDepth`"
+            );
+
+            // A frozen domain is also stalled, so its overdue maintenance can be performed before
+            // the stake is unlocked on the consensus clock.
+            assert!(
+                T::DomainFreezePeriod::get() >= T::DomainStallPeriod::get(),
+                "`DomainFreezePeriod` must not be less than `DomainStallPeriod`"
+            );
+
+            // The consensus block used to verify the proof-of-time of a bundle must not be re-orged
+            // out while the bundle is still valid, see `check_slot_and_proof_of_time`.
+            let bundle_longevity: BlockNumberFor<T> = T::BundleLongevity::get().into();
+            assert!(
+                bundle_longevity < T::ConfirmationDepthK::get(),
+                "`BundleLongevity` must be less than `ConfirmationDepthK`"
+            );
+
+            // The epoch transition in `submit_bundle` is triggered by
+            // `domain_block_number % StakeEpochDuration`.
+            assert!(
+                !T::StakeEpochDuration::get().is_zero(),
+                "`StakeEpochDuration` must be greater than 0"
+            );
+
+            // The initial tx range is `U256::MAX / InitialDomainTxRange`.
+            assert!(
+                T::InitialDomainTxRange::get() != 0,
+                "`InitialDomainTxRange` must be greater than 0"
+            );
+
+            // `calculate_max_bundle_weight_and_size` divides by the consensus slot probability.
+            let (numerator, denominator) = T::ConsensusSlotProbability::get();
+            assert!(
+                numerator != 0 && denominator != 0 && numerator <= denominator,
+                "`ConsensusSlotProbability` must be `> 0` and `≤ 1`"
+            );
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn try_state(_: BlockNumberFor<T>) -> Result<(), sp_runtime::TryRuntimeError> {
+            check_staking_invariants::<T>().map_err(Into::into)
+        }
+    }
+
+    #[pallet::inherent]
+    impl<T: Config> ProvideInherent for Pallet<T> {
+        type Call = Call<T>;
+        type Error = frame_support::inherent::MakeFatalError<()>;
+        const INHERENT_IDENTIFIER: InherentIdentifier = INHERENT_IDENTIFIER;
+
+        fn create_inherent(data: &InherentData) -> Option<Self::Call> {
+            let inherent_data = data
+                .get_data::<InherentType>(&INHERENT_IDENTIFIER)
+                .expect("Domains inherent data not correctly encoded")?;
+
+            let rejected_bundles = inherent_data.rejected_bundles;
+            if rejected_bundles.is_empty() {
+                None
+            } else {
+                Some(Call::report_rejected_bundles { rejected_bundles })
+            }
+        }
+
+        fn is_inherent_required(_data: &InherentData) -> Result<Option<Self::Error>, Self::Error> {
+            // The rejected bundles are only observed by the block author, other nodes can't tell
+            // whether the report is missing.
+            Ok(None)
+        }
+
+        fn is_inherent(call: &Self::Call) -> bool {
+            matches!(call, Call::report_rejected_bundles { .. })
         }
     }
 
@@ -1497,17 +2550,34 @@ mod pallet {
         fn pre_dispatch(call: &Self::Call) -> Result<(), TransactionValidityError> {
             match call {
                 Call::submit_bundle { opaque_bundle } => Self::validate_bundle(opaque_bundle, true)
-                    .map_err(|_| InvalidTransaction::Call.into())
+                    .map_err(|e| InvalidTransactionCode::from(&e).into())
+                    // The only place that charges the bundle storage fee, `pre_dispatch` is called
+                    // exactly once for every bundle that is included in a block, regardless of
+                    // whether the bundle comes from the transaction pool or not.
                     .and_then(|_| {
                         charge_bundle_storage_fee::<T>(
                             opaque_bundle.operator_id(),
                             opaque_bundle.size(),
                         )
-                        .map_err(|_| InvalidTransaction::Call.into())
+                        .map_err(|e| InvalidTransactionCode::from(&e).into())
                     }),
-                Call::submit_fraud_proof { fraud_proof } => Self::validate_fraud_proof(fraud_proof)
+                Call::submit_fraud_proof {
+                    fraud_proof,
+                    maybe_consensus_block_proof,
+                } => Self::validate_fraud_proof(fraud_proof, maybe_consensus_block_proof)
                     .map(|_| ())
-                    .map_err(|_| InvalidTransaction::Call.into()),
+                    .map_err(|e| InvalidTransactionCode::from(&e).into()),
+                // The rejected bundles can't be checked against the local inherent data since they
+                // are only observed by the block author, the size of the report is bounded by the
+                // call and it is accepted at most once per block. The inherent never enters the
+                // transaction pool since `validate_unsigned` rejects it.
+                Call::report_rejected_bundles { rejected_bundles } => {
+                    if RejectedBundlesReported::<T>::get() || rejected_bundles.is_empty() {
+                        Err(InvalidTransaction::Call.into())
+                    } else {
+                        Ok(())
+                    }
+                }
                 _ => Err(InvalidTransaction::Call.into()),
             }
         }
@@ -1523,7 +2593,10 @@ mod pallet {
                             | BundleError::Receipt(BlockTreeError::StaleReceipt)
                             | BundleError::Receipt(BlockTreeError::NewBranchReceipt)
                             | BundleError::Receipt(BlockTreeError::UnavailableConsensusBlockHash)
+                            | BundleError::Receipt(BlockTreeError::PrunedConsensusBlockHash)
+                            | BundleError::Receipt(BlockTreeError::FutureConsensusBlock)
                             | BundleError::Receipt(BlockTreeError::BuiltOnUnknownConsensusBlock)
+                            | BundleError::ConflictingReceipt
                             | BundleError::DuplicatedBundle
                             | BundleError::SlotInThePast
                             | BundleError::SlotInTheFuture
@@ -1542,14 +2615,13 @@ mod pallet {
                                 );
                             }
                         }
-                        if let BundleError::Receipt(_) = e {
-                            return InvalidTransactionCode::ExecutionReceipt.into();
-                        } else {
-                            return InvalidTransactionCode::Bundle.into();
-                        }
+                        return InvalidTransactionCode::from(&e).into();
                     }
 
-                    if let Err(e) = charge_bundle_storage_fee::<T>(
+                    // Only check if the storage fee is affordable here, the fee is charged in
+                    // `pre_dispatch` so the bundle storage fund balance decreases only for bundles
+                    // that are included in a block.
+                    if let Err(e) = ensure_bundle_storage_fee_affordable::<T>(
                         opaque_bundle.operator_id(),
                         opaque_bundle.size(),
                     ) {
@@ -1559,13 +2631,23 @@ mod pallet {
                             opaque_bundle.operator_id(),
                             opaque_bundle.domain_id(),
                         );
-                        return InvalidTransactionCode::BundleStorageFeePayment.into();
+                        return InvalidTransactionCode::from(&e).into();
                     }
 
+                    // Bundle have a bit higher priority than normal extrinsic but must less than
+                    // fraud proof, bundle that carries a lagging receipt have the same priority as
+                    // normal extrinsic so bundles that extend the head receipt can catch up.
+                    let priority = if is_lagging_receipt::<T>(
+                        opaque_bundle.domain_id(),
+                        opaque_bundle.receipt(),
+                    ) {
+                        0
+                    } else {
+                        1
+                    };
+
                     ValidTransaction::with_tag_prefix("SubspaceSubmitBundle")
-                        // Bundle have a bit higher priority than normal extrinsic but must less than
-                        // fraud proof
-                        .priority(1)
+                        .priority(priority)
                         .longevity(T::ConfirmationDepthK::get().try_into().unwrap_or_else(|_| {
                             panic!("Block number always fits in TransactionLongevity; qed")
                         }))
@@ -1573,14 +2655,20 @@ mod pallet {
                         .propagate(true)
                         .build()
                 }
-                Call::submit_fraud_proof { fraud_proof } => {
-                    let (tag, priority) = match Self::validate_fraud_proof(fraud_proof) {
+                Call::submit_fraud_proof {
+                    fraud_proof,
+                    maybe_consensus_block_proof,
+                } => {
+                    let (tag, priority) = match Self::validate_fraud_proof(
+                        fraud_proof,
+                        maybe_consensus_block_proof,
+                    ) {
                         Err(e) => {
                             log::warn!(
                                 target: "runtime::domains",
                                 "Bad fraud proof {fraud_proof:?}, error: {e:?}",
                             );
-                            return InvalidTransactionCode::FraudProof.into();
+                            return InvalidTransactionCode::from(&e).into();
                         }
                         Ok(tp) => tp,
                     };
@@ -1618,6 +2706,46 @@ impl<T: Config> Pallet<T> {
         Some(HeadDomainNumber::<T>::get(domain_id))
     }
 
+    /// Returns the summary of the given domain runtime.
+    pub fn runtime_summary(
+        runtime_id: RuntimeId,
+    ) -> Option<RuntimeSummary<BlockNumberFor<T>, T::Hash>> {
+        RuntimeSummaries::<T>::get(runtime_id)
+    }
+
+    /// Returns the summaries of all the registered domain runtimes, ordered by runtime id.
+    pub fn runtime_summaries() -> Vec<RuntimeSummary<BlockNumberFor<T>, T::Hash>> {
+        (0..NextRuntimeId::<T>::get())
+            .filter_map(Self::runtime_summary)
+            .collect()
+    }
+
+    /// Returns the domains owned by the given account, ordered by domain id.
+    pub fn domains_owned_by(owner: T::AccountId) -> Vec<DomainId> {
+        OwnedDomains::<T>::get(owner).into_iter().collect()
+    }
+
+    /// Returns the summary of the staking changes finalized for the operator at the end of the
+    /// given epoch of the given domain, `None` if nothing changed in the epoch or it is not within
+    /// the last `OperatorEpochSummaryHistoryDepth` epochs.
+    pub fn operator_epoch_summary(
+        operator_id: OperatorId,
+        domain_id: DomainId,
+        epoch_index: EpochIndex,
+    ) -> Option<OperatorEpochSummary<BalanceOf<T>, T::Share>> {
+        EpochSummary::<T>::get(operator_id, DomainEpoch::from((domain_id, epoch_index)))
+    }
+
+    /// Returns the operator tax paid by the nominator to the operator so far.
+    pub fn nominator_tax_paid(
+        operator_id: OperatorId,
+        nominator_id: NominatorId<T>,
+    ) -> BalanceOf<T> {
+        let tax = TaxPaid::<T>::get(operator_id, nominator_id.clone());
+        let maybe_deposit = Deposits::<T>::get(operator_id, nominator_id);
+        settle_nominator_tax::<T>(operator_id, tax, maybe_deposit.as_ref()).paid
+    }
+
     pub fn runtime_id(domain_id: DomainId) -> Option<RuntimeId> {
         DomainRegistry::<T>::get(domain_id)
             .map(|domain_object| domain_object.domain_config.runtime_id)
@@ -1636,6 +2764,9 @@ impl<T: Config> Pallet<T> {
                 domain_obj.domain_runtime_info,
                 total_issuance,
                 domain_obj.domain_config.initial_balances,
+                domain_obj
+                    .domain_config
+                    .initial_contract_creation_allow_list,
             )
             .ok()?;
         Some((
@@ -1648,9 +2779,15 @@ impl<T: Config> Pallet<T> {
     }
 
     pub fn genesis_state_root(domain_id: DomainId) -> Option<H256> {
+        Self::genesis_execution_receipt(domain_id).map(|receipt| receipt.final_state_root.into())
+    }
+
+    /// Returns the genesis receipt of the domain as stored in the block tree, `None` if the domain
+    /// doesn't exist or the genesis receipt is already pruned.
+    pub fn genesis_execution_receipt(domain_id: DomainId) -> Option<ExecutionReceiptOf<T>> {
         BlockTree::<T>::get(domain_id, DomainBlockNumberFor::<T>::zero())
             .and_then(BlockTreeNodes::<T>::get)
-            .map(|block| block.execution_receipt.final_state_root.into())
+            .map(|block| block.execution_receipt)
     }
 
     /// Returns the tx range for the domain.
@@ -1681,6 +2818,161 @@ impl<T: Config> Pallet<T> {
             .map(|operator| (operator.signing_key, operator.current_total_stake))
     }
 
+    /// Returns the bundle producer election params of the given epoch of the domain.
+    ///
+    /// The params of the current epoch are read from the domain stake summary while the params of
+    /// the past epochs are read from the `ElectionParamsHistory`.
+    pub fn bundle_producer_election_params_at(
+        domain_id: DomainId,
+        epoch_index: EpochIndex,
+    ) -> Option<EpochElectionParams<BalanceOf<T>>> {
+        let bundle_slot_probability = DomainRegistry::<T>::get(domain_id)?
+            .domain_config
+            .bundle_slot_probability;
+        let stake_summary = DomainStakingSummary::<T>::get(domain_id)?;
+        let ElectionVerificationParams {
+            operators,
+            total_domain_stake,
+        } = if epoch_index == stake_summary.current_epoch_index {
+            ElectionVerificationParams {
+                operators: stake_summary.current_operators,
+                total_domain_stake: stake_summary.current_total_stake,
+            }
+        } else {
+            ElectionParamsHistory::<T>::get(domain_id, epoch_index)?
+        };
+
+        Some(EpochElectionParams {
+            params: BundleProducerElectionParams {
+                total_domain_stake,
+                bundle_slot_probability,
+            },
+            operator_stakes: operators,
+        })
+    }
+
+    /// Returns the fees collected by the domain since its instantiation.
+    pub fn domain_fee_totals(domain_id: DomainId) -> DomainFees<BalanceOf<T>> {
+        DomainFeeTotals::<T>::get(domain_id)
+    }
+
+    /// Returns the fees collected by the domain in the given epoch, `None` if the epoch is not
+    /// within the last `DomainFeeHistoryDepth` epochs or the domain collected nothing in it.
+    pub fn domain_epoch_fees(
+        domain_id: DomainId,
+        epoch_index: EpochIndex,
+    ) -> Option<DomainFees<BalanceOf<T>>> {
+        DomainEpochFees::<T>::get(domain_id, epoch_index)
+    }
+
+    /// Add `fees` to the fee totals of the domain and to the fees of the given epoch.
+    pub(crate) fn note_domain_fees(
+        domain_id: DomainId,
+        epoch_index: EpochIndex,
+        fees: DomainFees<BalanceOf<T>>,
+    ) {
+        Self::mutate_domain_fees(domain_id, epoch_index, |domain_fees| {
+            domain_fees.saturating_accrue(fees)
+        });
+    }
+
+    /// Move `operator_tax` taken from the rewards of the given epoch from the operator rewards to
+    /// the operator tax of the domain, such that the tax is not counted twice.
+    pub(crate) fn note_domain_operator_tax(
+        domain_id: DomainId,
+        epoch_index: EpochIndex,
+        operator_tax: BalanceOf<T>,
+    ) {
+        Self::mutate_domain_fees(domain_id, epoch_index, |domain_fees| {
+            domain_fees.operator_rewards.saturating_reduce(operator_tax);
+            domain_fees.operator_tax.saturating_accrue(operator_tax);
+        });
+    }
+
+    /// Apply `f` to the fee totals of the domain and to the fees of the given epoch, the latter
+    /// are not kept if `DomainFeeHistoryDepth` is zero.
+    fn mutate_domain_fees<F>(domain_id: DomainId, epoch_index: EpochIndex, f: F)
+    where
+        F: Fn(&mut DomainFees<BalanceOf<T>>),
+    {
+        DomainFeeTotals::<T>::mutate(domain_id, &f);
+
+        if T::DomainFeeHistoryDepth::get().is_zero() {
+            return;
+        }
+        DomainEpochFees::<T>::mutate(domain_id, epoch_index, |maybe_epoch_fees| {
+            f(maybe_epoch_fees.get_or_insert_with(Default::default))
+        });
+    }
+
+    /// Remove the fees of all the epochs of the domain that fall out of the
+    /// `DomainFeeHistoryDepth` window once the domain moves to `current_epoch_index`, such that
+    /// the history stays bounded even if the depth is lowered.
+    pub(crate) fn prune_domain_epoch_fees(domain_id: DomainId, current_epoch_index: EpochIndex) {
+        let history_depth = T::DomainFeeHistoryDepth::get();
+        let expired_epochs = DomainEpochFees::<T>::iter_key_prefix(domain_id)
+            .filter(|epoch_index| epoch_index.saturating_add(history_depth) <= current_epoch_index)
+            .collect::<Vec<_>>();
+        for expired_epoch in expired_epochs {
+            DomainEpochFees::<T>::remove(domain_id, expired_epoch);
+        }
+    }
+
+    /// Returns the number of past epochs whose election params are kept in `ElectionParamsHistory`,
+    /// which covers the challenge period (i.e. `BlockTreePruningDepth`) plus one epoch as margin.
+    pub fn election_params_history_depth() -> EpochIndex {
+        let challenge_period: u32 = T::BlockTreePruningDepth::get().saturated_into();
+        let epoch_duration: u32 = T::StakeEpochDuration::get().saturated_into::<u32>().max(1);
+        challenge_period.div_ceil(epoch_duration).saturating_add(1)
+    }
+
+    /// Returns the threshold of the bundle producer election for the given operator, `None` if
+    /// the operator is unknown or not active in the current epoch.
+    ///
+    /// The operator wins the election if its vrf output is strictly less than the threshold, a
+    /// threshold of zero means the operator never wins. The threshold is calculated with the same
+    /// stake distribution and math used to verify the proof of election in `validate_bundle`.
+    pub fn operator_election_threshold(
+        domain_id: DomainId,
+        operator_id: OperatorId,
+    ) -> Option<U256> {
+        Self::calculate_operator_election_threshold(domain_id, operator_id).map(U256::from)
+    }
+
+    /// Returns the expected number of bundles produced by the given operator per slot, i.e. the
+    /// probability of the operator winning the election in a slot, `None` if the operator is
+    /// unknown or not active in the current epoch.
+    pub fn operator_expected_bundles_per_slot(
+        domain_id: DomainId,
+        operator_id: OperatorId,
+    ) -> Option<Perquintill> {
+        let threshold = Self::calculate_operator_election_threshold(domain_id, operator_id)?;
+        Some(Perquintill::from_rational(threshold, u128::MAX))
+    }
+
+    /// Returns the threshold that the vrf output of the proof of election of the given operator
+    /// must be below.
+    fn calculate_operator_election_threshold(
+        domain_id: DomainId,
+        operator_id: OperatorId,
+    ) -> Option<u128> {
+        let bundle_slot_probability = DomainRegistry::<T>::get(domain_id)?
+            .domain_config
+            .bundle_slot_probability;
+        let (operator_stake, total_domain_stake) =
+            Self::fetch_operator_stake_info(domain_id, &operator_id).ok()?;
+
+        if total_domain_stake.is_zero() {
+            return None;
+        }
+
+        Some(calculate_threshold(
+            operator_stake.saturated_into(),
+            total_domain_stake.saturated_into(),
+            bundle_slot_probability,
+        ))
+    }
+
     fn check_bundle_duplication(opaque_bundle: &OpaqueBundleOf<T>) -> Result<(), BundleError> {
         // NOTE: it is important to use the hash that not incliude the signature, otherwise
         // the malicious operator may update its `signing_key` (this may support in the future)
@@ -1693,6 +2985,22 @@ impl<T: Config> Pallet<T> {
         Ok(())
     }
 
+    fn check_bundle_signature(
+        signing_key: &OperatorPublicKey,
+        opaque_bundle: &OpaqueBundleOf<T>,
+    ) -> Result<(), BundleError> {
+        let sealed_header = &opaque_bundle.sealed_header;
+        ensure!(
+            T::BundleSignatureVerifier::verify_bundle_signature(
+                signing_key,
+                &sealed_header.pre_hash(),
+                &sealed_header.signature,
+            ),
+            BundleError::BadBundleSignature
+        );
+        Ok(())
+    }
+
     fn check_extrinsics_root(opaque_bundle: &OpaqueBundleOf<T>) -> Result<(), BundleError> {
         let expected_extrinsics_root = <T::DomainHeader as Header>::Hashing::ordered_trie_root(
             opaque_bundle
@@ -1772,14 +3080,35 @@ impl<T: Config> Pallet<T> {
         Ok(())
     }
 
+    /// Validate the bundle.
+    ///
+    /// NOTE: the checks are ordered by cost, the structural checks of the bundle and the checks
+    /// that only read storage come first, the signature, proof of time, extrinsics root and proof
+    /// of election are verified only after all of them passed. This is required to avoid burning
+    /// CPU on garbage bundles sent by malicious peers, new checks must keep this order.
     fn validate_bundle(
         opaque_bundle: &OpaqueBundleOf<T>,
         pre_dispatch: bool,
     ) -> Result<(), BundleError> {
+        // The domain id of the bundle is the one in the proof of election, it is covered by the
+        // signature and verified by the proof of election. Receipts don't carry a domain id, the
+        // receipt is checked to be chained to the block tree of this domain below.
         let domain_id = opaque_bundle.domain_id();
         let operator_id = opaque_bundle.operator_id();
         let sealed_header = &opaque_bundle.sealed_header;
 
+        // Check the hard limits before the limits of the domain are calculated, the extrinsic
+        // count is checked first since computing the body size iterates over all the extrinsics
+        ensure!(
+            opaque_bundle.extrinsics.len() <= T::MaxBundleExtrinsics::get() as usize,
+            BundleError::TooManyExtrinsics
+        );
+        let body_size = opaque_bundle.body_size();
+        ensure!(
+            body_size <= T::MaxDomainBlockSize::get(),
+            BundleError::BundleTooLarge
+        );
+
         let operator = Operators::<T>::get(operator_id).ok_or(BundleError::InvalidOperatorId)?;
 
         ensure!(
@@ -1788,14 +3117,7 @@ impl<T: Config> Pallet<T> {
             BundleError::BadOperator
         );
 
-        if !operator
-            .signing_key
-            .verify(&sealed_header.pre_hash(), &sealed_header.signature)
-        {
-            return Err(BundleError::BadBundleSignature);
-        }
-
-        Self::check_bundle_duplication(opaque_bundle)?;
+        ensure!(!operator.paused, BundleError::OperatorPaused);
 
         let domain_config = DomainRegistry::<T>::get(domain_id)
             .ok_or(BundleError::InvalidDomainId)?
@@ -1806,7 +3128,7 @@ impl<T: Config> Pallet<T> {
             .map_err(|_| BundleError::UnableToCalculateBundleLimit)?;
 
         ensure!(
-            opaque_bundle.body_size() <= domain_bundle_limit.max_bundle_size,
+            body_size <= domain_bundle_limit.max_bundle_size,
             BundleError::BundleTooLarge
         );
 
@@ -1817,18 +3139,31 @@ impl<T: Config> Pallet<T> {
             BundleError::BundleTooHeavy
         );
 
-        Self::check_extrinsics_root(opaque_bundle)?;
+        Self::check_bundle_duplication(opaque_bundle)?;
+
+        let receipt = &sealed_header.header.receipt;
+        ensure_consensus_block_not_in_future::<T>(receipt.consensus_block_number)
+            .map_err(BundleError::Receipt)?;
+        ensure!(
+            is_receipt_of_domain::<T>(domain_id, receipt),
+            BundleError::ReceiptDomainMismatch
+        );
+
+        // Expensive checks
+        Self::check_bundle_signature(&operator.signing_key, opaque_bundle)?;
 
-        let proof_of_election = &sealed_header.header.proof_of_election;
         let (operator_stake, total_domain_stake) =
             Self::fetch_operator_stake_info(domain_id, &operator_id)?;
 
+        let proof_of_election = &sealed_header.header.proof_of_election;
         Self::check_slot_and_proof_of_time(
             proof_of_election.slot_number,
             proof_of_election.proof_of_time,
             pre_dispatch,
         )?;
 
+        Self::check_extrinsics_root(opaque_bundle)?;
+
         sp_domains::bundle_producer_election::check_proof_of_election(
             &operator.signing_key,
             domain_config.bundle_slot_probability,
@@ -1837,14 +3172,48 @@ impl<T: Config> Pallet<T> {
             total_domain_stake.saturated_into(),
         )?;
 
-        let receipt = &sealed_header.header.receipt;
+        // Checked before the receipt verification which rejects the same receipt as a new branch,
+        // so the operator knows it has to rebase its receipt on the one accepted in this block.
+        ensure!(
+            !is_conflicting_receipt::<T>(domain_id, receipt),
+            BundleError::ConflictingReceipt
+        );
+
         verify_execution_receipt::<T>(domain_id, receipt).map_err(BundleError::Receipt)?;
 
         Ok(())
     }
 
+    /// Weight of `submit_fraud_proof` without handling the bad receipt, the verification of the
+    /// MMR proof of the consensus block is included if the proof is provided.
+    fn submit_fraud_proof_weight(has_consensus_block_proof: bool) -> Weight {
+        let weight = T::WeightInfo::submit_fraud_proof();
+        if has_consensus_block_proof {
+            weight.saturating_add(T::WeightInfo::verify_consensus_block_mmr_proof())
+        } else {
+            weight
+        }
+    }
+
+    /// Verifies the MMR proof of the consensus block `consensus_number` against the current
+    /// consensus chain MMR and returns the hash of the consensus block.
+    fn verify_consensus_block_mmr_proof(
+        consensus_number: BlockNumberFor<T>,
+        consensus_block_proof: ConsensusBlockMmrProofOf<T>,
+    ) -> Result<T::Hash, FraudProofError> {
+        let (leaf_number, leaf_hash) =
+            T::MmrProofVerifier::verify_proof_and_extract_consensus_block(consensus_block_proof)
+                .ok_or(FraudProofError::InvalidConsensusBlockMmrProof)?;
+        ensure!(
+            leaf_number == consensus_number,
+            FraudProofError::InvalidConsensusBlockMmrProof
+        );
+        Ok(leaf_hash)
+    }
+
     fn validate_fraud_proof(
         fraud_proof: &FraudProof<BlockNumberFor<T>, T::Hash, T::DomainHeader>,
+        maybe_consensus_block_proof: &Option<ConsensusBlockMmrProofOf<T>>,
     ) -> Result<(FraudProofTag, TransactionPriority), FraudProofError> {
         let tag_and_priority = if let Some(bad_receipt_hash) =
             fraud_proof.targeted_bad_receipt_hash()
@@ -1867,6 +3236,28 @@ impl<T: Config> Pallet<T> {
                 FraudProofError::BadReceiptAlreadyReported,
             );
 
+            let expected_consensus_block_hash = expected_consensus_hash_at::<T>(
+                fraud_proof.domain_id(),
+                bad_receipt.consensus_block_number,
+            )
+            .or_else(|err| {
+                // The consensus block hash is no longer available in the state, fall back to the
+                // MMR proof of the consensus block if any
+                let consensus_block_proof = maybe_consensus_block_proof
+                    .clone()
+                    .ok_or(FraudProofError::BadReceiptConsensusBlock(err))?;
+                Self::verify_consensus_block_mmr_proof(
+                    bad_receipt.consensus_block_number,
+                    consensus_block_proof,
+                )
+            })?;
+            ensure!(
+                bad_receipt.consensus_block_hash == expected_consensus_block_hash,
+                FraudProofError::BadReceiptConsensusBlock(
+                    BlockTreeError::BuiltOnUnknownConsensusBlock
+                ),
+            );
+
             match fraud_proof {
                 FraudProof::InvalidBlockFees(InvalidBlockFeesProof { storage_proof, .. }) => {
                     verify_invalid_block_fees_fraud_proof::<
@@ -2153,6 +3544,24 @@ impl<T: Config> Pallet<T> {
         HeadReceiptNumber::<T>::get(domain_id)
     }
 
+    /// Returns the number of receipts left in the block tree above the head receipt after a fraud
+    /// proof reverted the head, each of them is pruned (and its submitters slashed) lazily by the
+    /// `submit_bundle` that brings the head back to its domain block number.
+    pub fn revert_depth(domain_id: DomainId) -> DomainBlockNumberFor<T> {
+        let mut revert_depth = Zero::zero();
+        let mut receipt_number = HeadReceiptNumber::<T>::get(domain_id).saturating_add(One::one());
+        // Right after the revert, the bad receipt itself is already pruned by the fraud proof thus
+        // its descendants start one block above the head receipt
+        if !BlockTree::<T>::contains_key(domain_id, receipt_number) {
+            receipt_number = receipt_number.saturating_add(One::one());
+        }
+        while BlockTree::<T>::contains_key(domain_id, receipt_number) {
+            revert_depth = revert_depth.saturating_add(One::one());
+            receipt_number = receipt_number.saturating_add(One::one());
+        }
+        revert_depth
+    }
+
     /// Returns the block number of the oldest existing unconfirmed execution receipt, return `None`
     /// means there is no unconfirmed ER exist or submitted yet.
     pub fn oldest_unconfirmed_receipt_number(
@@ -2190,6 +3599,50 @@ impl<T: Config> Pallet<T> {
             .map(|block| (block.block_number, block.block_hash))
     }
 
+    /// Returns the state root of the given domain block, the domain block can be either within the
+    /// retained confirmed history or still in the block tree waiting for confirmation.
+    pub fn domain_state_root(
+        domain_id: DomainId,
+        domain_block_number: DomainBlockNumberFor<T>,
+        domain_block_hash: T::DomainHash,
+    ) -> Option<DomainStateRoot> {
+        if let Some((block_hash, state_root)) =
+            ConfirmedDomainStateRoots::<T>::get(domain_id, domain_block_number)
+        {
+            return (block_hash == domain_block_hash)
+                .then(|| DomainStateRoot::Confirmed(state_root.into()));
+        }
+
+        BlockTree::<T>::get(domain_id, domain_block_number)
+            .and_then(BlockTreeNodes::<T>::get)
+            .filter(|node| node.execution_receipt.domain_block_hash == domain_block_hash)
+            .map(|node| {
+                DomainStateRoot::Unconfirmed(node.execution_receipt.final_state_root.into())
+            })
+    }
+
+    /// Returns the digests of the bundles inboxed for the given domain block in the given consensus
+    /// block, these are the same digests used to verify the `InvalidExtrinsicsRoot` fraud proof.
+    ///
+    /// The digests are returned in the order the bundles are included in the consensus block.
+    pub fn execution_inbox_digests(
+        domain_id: DomainId,
+        domain_block_number: DomainBlockNumberFor<T>,
+        consensus_block_number: BlockNumberFor<T>,
+    ) -> Vec<BundleDigest<T::DomainHash>> {
+        ExecutionInbox::<T>::get((domain_id, domain_block_number, consensus_block_number))
+    }
+
+    /// Returns the digests of the bundles inboxed for the domain block under construction in the
+    /// current consensus block, empty if there is no bundle submitted in the current block yet.
+    pub fn current_inbox(domain_id: DomainId) -> Vec<BundleDigest<T::DomainHash>> {
+        Self::execution_inbox_digests(
+            domain_id,
+            HeadDomainNumber::<T>::get(domain_id),
+            frame_system::Pallet::<T>::current_block_number(),
+        )
+    }
+
     /// Returns the domain block limit of the given domain.
     pub fn domain_block_limit(domain_id: DomainId) -> Option<DomainBlockLimit> {
         DomainRegistry::<T>::get(domain_id).map(|domain_obj| DomainBlockLimit {
@@ -2212,6 +3665,30 @@ impl<T: Config> Pallet<T> {
         Ok(Some(bundle_limit))
     }
 
+    /// Returns the limits and parameters that are in effect for the given domain.
+    ///
+    /// Every value is resolved through the same functions and config items used by bundle and
+    /// receipt validation, any per-domain override of these must be applied here as well.
+    pub fn effective_domain_params(
+        domain_id: DomainId,
+    ) -> Option<EffectiveDomainParams<DomainBlockNumberFor<T>>> {
+        let block_limit = Self::domain_block_limit(domain_id)?;
+        let bundle_limit = Self::domain_bundle_limit(domain_id).ok().flatten()?;
+        let bundle_slot_probability = DomainRegistry::<T>::get(domain_id)?
+            .domain_config
+            .bundle_slot_probability;
+
+        Some(EffectiveDomainParams {
+            block_limit,
+            bundle_limit,
+            bundle_longevity: T::BundleLongevity::get(),
+            epoch_duration: T::StakeEpochDuration::get(),
+            pruning_depth: T::BlockTreePruningDepth::get(),
+            bundle_slot_probability,
+            tx_range: Self::domain_tx_range(domain_id),
+        })
+    }
+
     /// Returns if there are any ERs in the challenge period that have non empty extrinsics.
     /// Note that Genesis ER is also considered special and hence non empty
     pub fn non_empty_er_exists(domain_id: DomainId) -> bool {
@@ -2295,17 +3772,20 @@ impl<T: Config> Pallet<T> {
     pub fn max_submit_bundle_weight() -> Weight {
         T::WeightInfo::submit_bundle()
             .saturating_add(
-                // NOTE: within `submit_bundle`, only one of (or none) `handle_bad_receipt` and
-                // `confirm_domain_block` can happen, thus we use the `max` of them
-                T::WeightInfo::handle_bad_receipt(T::MaxNominators::get()).max(
-                    T::WeightInfo::confirm_domain_block(MAX_BUNLDE_PER_BLOCK, MAX_BUNLDE_PER_BLOCK),
+                // NOTE: within `submit_bundle`, only one of (or none) the lazy prune of a reverted
+                // bad receipt and `confirm_domain_block` can happen, thus we use the `max` of them
+                T::WeightInfo::lazy_prune_bad_receipt(T::MaxNominators::get()).max(
+                    T::WeightInfo::confirm_domain_block(
+                        T::MaxBundlesPerBlock::get(),
+                        T::MaxBundlesPerBlock::get(),
+                    ),
                 ),
             )
             .saturating_add(Self::max_staking_epoch_transition())
     }
 
     pub fn max_staking_epoch_transition() -> Weight {
-        T::WeightInfo::operator_reward_tax_and_restake(MAX_BUNLDE_PER_BLOCK)
+        T::WeightInfo::operator_reward_tax_and_restake(T::MaxBundlesPerBlock::get())
             .saturating_add(T::WeightInfo::finalize_slashed_operators(
                 // FIXME: the actual value should be `N * T::MaxNominators` where `N` is the number of
                 // submitter of the bad ER, which is probabilistically bounded by `bundle_slot_probability`
@@ -2318,6 +3798,143 @@ impl<T: Config> Pallet<T> {
             ))
     }
 
+    /// The worst case weight of `maintain_domain`, where at most `MaxBundlesPerBlock` slashed
+    /// operators are finalized, each with `MaxNominators` nominators.
+    pub fn max_maintain_domain_weight() -> Weight {
+        T::DbWeight::get()
+            .reads(4)
+            .saturating_add(Self::max_staking_epoch_transition())
+            .saturating_add(T::WeightInfo::finalize_slashed_operators(
+                T::MaxBundlesPerBlock::get().saturating_mul(T::MaxNominators::get()),
+            ))
+    }
+
+    /// Retry the pending epoch transitions that are due at `block_number`, round-robin starting
+    /// from the domain after the one retried last.
+    ///
+    /// At least one transition is retried, more are retried as long as the worst case weight of
+    /// another transition fits in `EPOCH_TRANSITION_RETRY_WEIGHT_RATIO` of the block weight, the
+    /// rest are retried in the following blocks or via `maintain_domain`.
+    fn retry_pending_epoch_transitions(block_number: BlockNumberFor<T>) -> Weight {
+        let weight_limit = EPOCH_TRANSITION_RETRY_WEIGHT_RATIO
+            * <T as frame_system::Config>::BlockWeights::get().max_block;
+        let max_epoch_transition_weight = Self::max_staking_epoch_transition();
+
+        let mut pending_transition_count = 0u64;
+        let mut due_domain_ids: Vec<DomainId> = PendingEpochTransition::<T>::iter()
+            .filter_map(|(domain_id, pending_transition)| {
+                pending_transition_count += 1;
+                (pending_transition.retry_at <= block_number).then_some(domain_id)
+            })
+            .collect();
+        let mut weight = T::DbWeight::get().reads(pending_transition_count.saturating_add(1));
+        if due_domain_ids.is_empty() {
+            return weight;
+        }
+
+        due_domain_ids.sort();
+        if let Some(last_retried) = LastRetriedEpochTransition::<T>::get() {
+            let start = due_domain_ids
+                .iter()
+                .position(|domain_id| *domain_id > last_retried)
+                .unwrap_or(0);
+            due_domain_ids.rotate_left(start);
+        }
+
+        for (retried, domain_id) in due_domain_ids.into_iter().enumerate() {
+            if retried > 0
+                && weight
+                    .saturating_add(max_epoch_transition_weight)
+                    .any_gt(weight_limit)
+            {
+                break;
+            }
+
+            let (epoch_transition_weight, _) = Self::process_pending_epoch_transition(domain_id);
+            LastRetriedEpochTransition::<T>::put(domain_id);
+            weight = weight
+                .saturating_add(epoch_transition_weight)
+                .saturating_add(T::DbWeight::get().writes(1));
+        }
+
+        weight
+    }
+
+    /// Try to complete the pending epoch transition of the domain, the transition is left
+    /// pending if it fails and is retried in `on_initialize` after an exponential backoff.
+    ///
+    /// Returns the consumed weight and the completed epoch index if the transition is done.
+    fn process_pending_epoch_transition(domain_id: DomainId) -> (Weight, Option<EpochIndex>) {
+        let Some(mut pending_transition) = PendingEpochTransition::<T>::get(domain_id) else {
+            return (T::DbWeight::get().reads(1), None);
+        };
+
+        // Discard the partial changes of a failed transition
+        let res = with_storage_layer(|| {
+            do_finalize_domain_current_epoch::<T>(domain_id)
+                .map_err(|err| DispatchError::from(Error::<T>::from(err)))
+        });
+        match res {
+            Ok(epoch_transition_res) => {
+                PendingEpochTransition::<T>::remove(domain_id);
+                let completed_epoch_index = epoch_transition_res.completed_epoch_index;
+                Self::deposit_event(Event::DomainEpochCompleted {
+                    domain_id,
+                    completed_epoch_index,
+                });
+                let weight = T::DbWeight::get()
+                    .reads_writes(1, 1)
+                    .saturating_add(Self::actual_epoch_transition_weight(epoch_transition_res));
+                (weight, Some(completed_epoch_index))
+            }
+            Err(err) => {
+                log::warn!(
+                    target: "runtime::domains",
+                    "Epoch transition of domain {domain_id:?} deferred: {err:?}",
+                );
+                // Only the first failure is reported, the following retries are backed off
+                if pending_transition.failed_attempts.is_zero() {
+                    Self::deposit_event(Event::DomainEpochTransitionDeferred {
+                        domain_id,
+                        domain_block_number: pending_transition.domain_block_number,
+                    });
+                }
+
+                let backoff = 2u32
+                    .saturating_pow(pending_transition.failed_attempts)
+                    .min(MAX_EPOCH_TRANSITION_RETRY_BACKOFF);
+                pending_transition.failed_attempts =
+                    pending_transition.failed_attempts.saturating_add(1);
+                pending_transition.retry_at = frame_system::Pallet::<T>::current_block_number()
+                    .saturating_add(backoff.into());
+                PendingEpochTransition::<T>::insert(domain_id, pending_transition);
+
+                let weight = T::DbWeight::get()
+                    .reads_writes(1, 1)
+                    .saturating_add(Self::max_staking_epoch_transition());
+                (weight, None)
+            }
+        }
+    }
+
+    /// Returns whether the head receipt of the domain is not extended for `DomainStallPeriod`.
+    pub fn is_domain_stalled(domain_id: DomainId) -> bool {
+        let current_block_number = frame_system::Pallet::<T>::current_block_number();
+        current_block_number.saturating_sub(HeadReceiptExtendedAt::<T>::get(domain_id))
+            >= T::DomainStallPeriod::get()
+    }
+
+    /// Returns the consensus block at which the domain became frozen, i.e. `DomainFreezePeriod`
+    /// consensus blocks after its head receipt is last extended or after it is instantiated if the
+    /// head receipt is never extended, `None` if the domain is not frozen.
+    pub fn domain_frozen_at(domain_id: DomainId) -> Option<BlockNumberFor<T>> {
+        let created_at = DomainRegistry::<T>::get(domain_id)?.created_at;
+        let frozen_at = HeadReceiptExtendedAt::<T>::get(domain_id)
+            .max(created_at)
+            .saturating_add(T::DomainFreezePeriod::get());
+        (frozen_at <= frame_system::Pallet::<T>::current_block_number()).then_some(frozen_at)
+    }
+
     fn actual_epoch_transition_weight(epoch_transition_res: EpochTransitionResult) -> Weight {
         let EpochTransitionResult {
             rewarded_operator_count,
@@ -2335,10 +3952,163 @@ impl<T: Config> Pallet<T> {
             ))
     }
 
+    /// Returns the bundle storage fund account of the operator.
+    pub fn storage_fund_account(operator_id: OperatorId) -> T::AccountId {
+        storage_fund_account::<T>(operator_id)
+    }
+
+    /// Returns how much the operator's bundle storage fund is short of to pay the storage fee of
+    /// a bundle of `bundle_size`, `None` if the fee is affordable.
+    pub fn storage_fee_shortfall(
+        operator_id: OperatorId,
+        bundle_size: u32,
+    ) -> Option<BalanceOf<T>> {
+        storage_fee_shortfall::<T>(operator_id, bundle_size)
+    }
+
     pub fn storage_fund_account_balance(operator_id: OperatorId) -> BalanceOf<T> {
         let storage_fund_acc = storage_fund_account::<T>(operator_id);
         T::Currency::reducible_balance(&storage_fund_acc, Preservation::Preserve, Fortitude::Polite)
     }
+
+    /// Returns the metadata registered by the owner of the operator.
+    pub fn operator_metadata(operator_id: OperatorId) -> Option<OperatorMetadata> {
+        RegisteredOperatorMetadata::<T>::get(operator_id).map(|entry| entry.metadata)
+    }
+
+    /// Returns the evidence of the bad ERs that the operator is slashed for, oldest first.
+    pub fn slash_evidence(operator_id: OperatorId) -> Vec<SlashEvidenceOf<T>> {
+        OperatorSlashEvidence::<T>::get(operator_id).into_inner()
+    }
+
+    /// Returns the total number of rejected bundles of the operator along with the latest
+    /// rejected bundles, oldest first.
+    pub fn rejected_bundles(
+        operator_id: OperatorId,
+    ) -> (u32, Vec<RejectedBundleRecord<BlockNumberFor<T>>>) {
+        (
+            RejectedBundleCount::<T>::get(operator_id),
+            OperatorRejectedBundles::<T>::get(operator_id),
+        )
+    }
+
+    /// Record the bundles rejected by the block author of the current block, only the latest
+    /// `MaxRejectedBundleRecords` records are kept for every operator.
+    ///
+    /// A bundle is skipped unless its header is signed by an existing operator, it is not
+    /// included in a block and it is built on a later slot than the last reported bundle of the
+    /// operator, so the block author can't record bundles that the operator never submitted or
+    /// report the same bundle repeatedly.
+    fn do_report_rejected_bundles(rejected_bundles: RejectedBundles) {
+        let max_records = T::MaxRejectedBundleRecords::get() as usize;
+        let consensus_block_number = frame_system::Pallet::<T>::current_block_number();
+        for RejectedBundle {
+            encoded_sealed_header,
+            reason,
+        } in rejected_bundles
+        {
+            let Ok(sealed_header) =
+                SealedBundleHeaderOf::<T>::decode(&mut encoded_sealed_header.as_slice())
+            else {
+                continue;
+            };
+            let proof_of_election = &sealed_header.header.proof_of_election;
+            let operator_id = proof_of_election.operator_id;
+            let Some(operator) = Operators::<T>::get(operator_id) else {
+                continue;
+            };
+            if LastRejectedBundleSlot::<T>::get(operator_id)
+                .is_some_and(|last_slot| proof_of_election.slot_number <= last_slot)
+            {
+                continue;
+            }
+            let bundle_header_hash = sealed_header.pre_hash();
+            if InboxedBundleAuthor::<T>::contains_key(bundle_header_hash)
+                || !T::BundleSignatureVerifier::verify_bundle_signature(
+                    &operator.signing_key,
+                    &bundle_header_hash,
+                    &sealed_header.signature,
+                )
+            {
+                continue;
+            }
+            LastRejectedBundleSlot::<T>::insert(operator_id, proof_of_election.slot_number);
+
+            if reason == RejectedBundleReason::ConflictingReceipt {
+                Self::note_competing_receipt(operator_id, &sealed_header);
+            }
+
+            RejectedBundleCount::<T>::mutate(operator_id, |count| *count = count.saturating_add(1));
+            if !max_records.is_zero() {
+                OperatorRejectedBundles::<T>::mutate(operator_id, |records| {
+                    records.push(RejectedBundleRecord {
+                        consensus_block_number,
+                        reason,
+                    });
+                    if records.len() > max_records {
+                        let to_drop = records.len() - max_records;
+                        records.drain(..to_drop);
+                    }
+                });
+            }
+
+            Self::deposit_event(Event::BundleRejected {
+                operator_id,
+                reason,
+            });
+        }
+    }
+
+    /// Records the conflicting receipt of a rejected bundle whose header is verified to be signed
+    /// by the operator.
+    ///
+    /// The report is ignored unless the receipt conflicts with the receipt in the block tree for
+    /// the same domain block.
+    fn note_competing_receipt(operator_id: OperatorId, sealed_header: &SealedBundleHeaderOf<T>) {
+        let proof_of_election = &sealed_header.header.proof_of_election;
+        let receipt = &sealed_header.header.receipt;
+        let Some(accepted_receipt_hash) =
+            BlockTree::<T>::get(proof_of_election.domain_id, receipt.domain_block_number)
+        else {
+            return;
+        };
+        let receipt_hash = receipt.hash::<DomainHashingFor<T>>();
+        if receipt_hash == accepted_receipt_hash || BlockTreeNodes::<T>::contains_key(receipt_hash)
+        {
+            return;
+        }
+
+        CompetingReceipts::<T>::mutate(accepted_receipt_hash, |competing_receipts| {
+            if !competing_receipts
+                .iter()
+                .any(|(_, competing_receipt_hash)| *competing_receipt_hash == receipt_hash)
+            {
+                // At most `MaxBundlesPerBlock` bundles can conflict with the accepted receipt
+                // in one block, the later reports are dropped if the bound is reached anyway.
+                let _ = competing_receipts.try_push((operator_id, receipt_hash));
+            }
+        });
+    }
+
+    /// Returns the receipts that are reported to conflict with the accepted receipt
+    /// `accepted_receipt_hash`, along with the operators who submitted them.
+    pub fn competing_receipts(
+        accepted_receipt_hash: ReceiptHashFor<T>,
+    ) -> Vec<(OperatorId, ReceiptHashFor<T>)> {
+        CompetingReceipts::<T>::get(accepted_receipt_hash).into_inner()
+    }
+
+    pub fn evm_domain_contract_creation_allowed_by(
+        domain_id: DomainId,
+    ) -> Option<sp_domains::PermissionedActionAllowedBy<EthereumAccountId>> {
+        EvmDomainContractCreationAllowedBy::<T>::get(domain_id)
+    }
+
+    pub fn evm_domain_contract_creation_allow_list_update(
+        domain_id: DomainId,
+    ) -> Option<sp_domains::PermissionedActionAllowedBy<EthereumAccountId>> {
+        EvmDomainContractCreationAllowListUpdate::<T>::get(domain_id)
+    }
 }
 
 impl<T: Config> sp_domains::DomainOwner<T::AccountId> for Pallet<T> {
@@ -2378,9 +4148,11 @@ where
     /// Submits an unsigned extrinsic [`Call::submit_fraud_proof`].
     pub fn submit_fraud_proof_unsigned(
         fraud_proof: FraudProof<BlockNumberFor<T>, T::Hash, T::DomainHeader>,
+        maybe_consensus_block_proof: Option<ConsensusBlockMmrProofOf<T>>,
     ) {
         let call = Call::submit_fraud_proof {
             fraud_proof: Box::new(fraud_proof),
+            maybe_consensus_block_proof,
         };
 
         match SubmitTransaction::<T, Call<T>>::submit_unsigned_transaction(call.into()) {
@@ -0,0 +1,907 @@
+//! Migrations of the domains pallet storage.
+//!
+//! Each migration only runs when the on-chain storage version matches the version it migrates
+//! from and bumps the on-chain storage version when done, migrations must be applied in order.
+
+use crate::pallet::{
+    DeferredSlashes, DomainRegistry, Operators, OwnedDomains, PendingRuntimeUpgrades,
+    RuntimeRegistry, RuntimeSummaries, ScheduledRuntimeUpgrades, Withdrawals,
+};
+use crate::{Config, Pallet};
+use core::marker::PhantomData;
+use frame_support::traits::{Get, GetStorageVersion, OnRuntimeUpgrade, StorageVersion};
+use frame_support::weights::Weight;
+
+/// Migrates the `DomainRegistry` to the `DomainConfig` with the initial contract creation allow
+/// list.
+pub struct MigrateToV1<T>(PhantomData<T>);
+
+impl<T: Config> OnRuntimeUpgrade for MigrateToV1<T> {
+    fn on_runtime_upgrade() -> Weight {
+        if Pallet::<T>::on_chain_storage_version() != 0 {
+            return T::DbWeight::get().reads(1);
+        }
+
+        let migrated = domain_registry_v0::migrate::<T>();
+        StorageVersion::new(1).put::<Pallet<T>>();
+
+        T::DbWeight::get().reads_writes(migrated.saturating_add(1), migrated.saturating_add(1))
+    }
+}
+
+/// Stores the `RuntimeSummaries` of the domain runtimes registered before the summaries were
+/// introduced.
+pub struct MigrateToV2<T>(PhantomData<T>);
+
+impl<T: Config> OnRuntimeUpgrade for MigrateToV2<T> {
+    fn on_runtime_upgrade() -> Weight {
+        if Pallet::<T>::on_chain_storage_version() != 1 {
+            return T::DbWeight::get().reads(1);
+        }
+
+        let mut reads = 0u64;
+        let mut migrated = 0u64;
+        for (runtime_id, runtime_obj) in RuntimeRegistry::<T>::iter() {
+            reads += 2;
+            if !RuntimeSummaries::<T>::contains_key(runtime_id) {
+                migrated += 1;
+                RuntimeSummaries::<T>::insert(runtime_id, runtime_obj.summary(runtime_id));
+            }
+        }
+        StorageVersion::new(2).put::<Pallet<T>>();
+
+        T::DbWeight::get().reads_writes(reads.saturating_add(1), migrated.saturating_add(1))
+    }
+}
+
+/// Adds the delay to the `ScheduledRuntimeUpgrades` and indexes them in `PendingRuntimeUpgrades`.
+pub struct MigrateToV3<T>(PhantomData<T>);
+
+impl<T: Config> OnRuntimeUpgrade for MigrateToV3<T> {
+    fn on_runtime_upgrade() -> Weight {
+        if Pallet::<T>::on_chain_storage_version() != 2 {
+            return T::DbWeight::get().reads(1);
+        }
+
+        let migrated = scheduled_runtime_upgrade_v0::migrate::<T>();
+        StorageVersion::new(3).put::<Pallet<T>>();
+
+        // Each upgrade is translated and its pending upgrade index is read and written
+        T::DbWeight::get().reads_writes(
+            migrated.saturating_mul(2).saturating_add(1),
+            migrated.saturating_mul(2).saturating_add(1),
+        )
+    }
+}
+
+/// Indexes the domains instantiated before `OwnedDomains` was introduced, including the genesis
+/// domain, by their owner.
+pub struct MigrateToV4<T>(PhantomData<T>);
+
+impl<T: Config> OnRuntimeUpgrade for MigrateToV4<T> {
+    fn on_runtime_upgrade() -> Weight {
+        if Pallet::<T>::on_chain_storage_version() != 3 {
+            return T::DbWeight::get().reads(1);
+        }
+
+        let mut migrated = 0u64;
+        for (domain_id, domain_obj) in DomainRegistry::<T>::iter() {
+            migrated += 1;
+            OwnedDomains::<T>::mutate(domain_obj.owner_account_id, |owned_domains| {
+                owned_domains.insert(domain_id);
+            });
+        }
+        StorageVersion::new(4).put::<Pallet<T>>();
+
+        // Each domain is read and its owner's domains are read and written
+        T::DbWeight::get().reads_writes(
+            migrated.saturating_mul(2).saturating_add(1),
+            migrated.saturating_add(1),
+        )
+    }
+}
+
+/// Adds the `paused` flag, which is not set for any of the existing operators, to the
+/// `Operators`.
+pub struct MigrateToV5<T>(PhantomData<T>);
+
+impl<T: Config> OnRuntimeUpgrade for MigrateToV5<T> {
+    fn on_runtime_upgrade() -> Weight {
+        if Pallet::<T>::on_chain_storage_version() != 4 {
+            return T::DbWeight::get().reads(1);
+        }
+
+        let migrated = operator_v4::migrate::<T>();
+        StorageVersion::new(5).put::<Pallet<T>>();
+
+        T::DbWeight::get().reads_writes(migrated.saturating_add(1), migrated.saturating_add(1))
+    }
+}
+
+/// Adds the unlock schedule on the consensus clock, which is not recorded for any of the existing
+/// deregistered operators and withdrawals, to the `Operators`, `DeferredSlashes` and `Withdrawals`.
+pub struct MigrateToV6<T>(PhantomData<T>);
+
+impl<T: Config> OnRuntimeUpgrade for MigrateToV6<T> {
+    fn on_runtime_upgrade() -> Weight {
+        if Pallet::<T>::on_chain_storage_version() != 5 {
+            return T::DbWeight::get().reads(1);
+        }
+
+        let migrated = staking_v5::migrate::<T>();
+        StorageVersion::new(6).put::<Pallet<T>>();
+
+        T::DbWeight::get().reads_writes(migrated.saturating_add(1), migrated.saturating_add(1))
+    }
+}
+
+mod domain_registry_v0 {
+    use super::DomainRegistry;
+    use crate::domain_registry::{DomainConfig, DomainObject};
+    use crate::runtime_registry::DomainRuntimeInfo;
+    use crate::Config;
+    #[cfg(not(feature = "std"))]
+    use alloc::string::String;
+    #[cfg(not(feature = "std"))]
+    use alloc::vec::Vec;
+    use codec::Decode;
+    use domain_runtime_primitives::MultiAccountId;
+    use frame_support::weights::Weight;
+    use sp_domains::{OperatorAllowList, RuntimeId};
+
+    #[derive(Decode)]
+    #[cfg_attr(test, derive(codec::Encode))]
+    pub(super) struct DomainConfigV0<AccountId: Ord, Balance> {
+        pub(super) domain_name: String,
+        pub(super) runtime_id: RuntimeId,
+        pub(super) max_block_size: u32,
+        pub(super) max_block_weight: Weight,
+        pub(super) bundle_slot_probability: (u64, u64),
+        pub(super) target_bundles_per_block: u32,
+        pub(super) operator_allow_list: OperatorAllowList<AccountId>,
+        pub(super) initial_balances: Vec<(MultiAccountId, Balance)>,
+    }
+
+    #[derive(Decode)]
+    #[cfg_attr(test, derive(codec::Encode))]
+    pub(super) struct DomainObjectV0<Number, ReceiptHash, AccountId: Ord, Balance> {
+        pub(super) owner_account_id: AccountId,
+        pub(super) created_at: Number,
+        pub(super) genesis_receipt_hash: ReceiptHash,
+        pub(super) domain_config: DomainConfigV0<AccountId, Balance>,
+        pub(super) domain_runtime_info: DomainRuntimeInfo,
+    }
+
+    /// Adds an empty initial contract creation allow list to all the existing domains, returns
+    /// the number of migrated domains.
+    pub(super) fn migrate<T: Config>() -> u64 {
+        let mut migrated = 0u64;
+        DomainRegistry::<T>::translate::<DomainObjectV0<_, _, _, _>, _>(|_, domain_obj| {
+            migrated += 1;
+            let DomainObjectV0 {
+                owner_account_id,
+                created_at,
+                genesis_receipt_hash,
+                domain_config,
+                domain_runtime_info,
+            } = domain_obj;
+            Some(DomainObject {
+                owner_account_id,
+                created_at,
+                genesis_receipt_hash,
+                domain_config: DomainConfig {
+                    domain_name: domain_config.domain_name,
+                    runtime_id: domain_config.runtime_id,
+                    max_block_size: domain_config.max_block_size,
+                    max_block_weight: domain_config.max_block_weight,
+                    bundle_slot_probability: domain_config.bundle_slot_probability,
+                    target_bundles_per_block: domain_config.target_bundles_per_block,
+                    operator_allow_list: domain_config.operator_allow_list,
+                    initial_balances: domain_config.initial_balances,
+                    initial_contract_creation_allow_list: None,
+                },
+                domain_runtime_info,
+            })
+        });
+        migrated
+    }
+}
+
+mod scheduled_runtime_upgrade_v0 {
+    use super::{PendingRuntimeUpgrades, ScheduledRuntimeUpgrades};
+    use crate::runtime_registry::ScheduledRuntimeUpgrade;
+    use crate::Config;
+    use codec::Decode;
+    use sp_core::Get;
+    use sp_domains::storage::RawGenesis;
+    use sp_version::RuntimeVersion;
+
+    #[derive(Decode)]
+    #[cfg_attr(test, derive(codec::Encode))]
+    pub(super) struct ScheduledRuntimeUpgradeV0<Hash> {
+        pub(super) raw_genesis: RawGenesis,
+        pub(super) version: RuntimeVersion,
+        pub(super) hash: Hash,
+    }
+
+    /// Adds the default `DomainRuntimeUpgradeDelay`, that all the existing upgrades are scheduled
+    /// with, to the scheduled upgrades and records the latest upgrade of each runtime as its
+    /// pending upgrade, returns the number of migrated upgrades.
+    pub(super) fn migrate<T: Config>() -> u64 {
+        let mut migrated = 0u64;
+        ScheduledRuntimeUpgrades::<T>::translate::<ScheduledRuntimeUpgradeV0<_>, _>(
+            |scheduled_at, runtime_id, scheduled_upgrade| {
+                migrated += 1;
+                PendingRuntimeUpgrades::<T>::mutate(runtime_id, |maybe_pending_at| {
+                    if maybe_pending_at.map_or(true, |pending_at| pending_at < scheduled_at) {
+                        *maybe_pending_at = Some(scheduled_at);
+                    }
+                });
+
+                let ScheduledRuntimeUpgradeV0 {
+                    raw_genesis,
+                    version,
+                    hash,
+                } = scheduled_upgrade;
+                Some(ScheduledRuntimeUpgrade {
+                    raw_genesis,
+                    version,
+                    hash,
+                    delay: T::DomainRuntimeUpgradeDelay::get(),
+                })
+            },
+        );
+        migrated
+    }
+}
+
+mod operator_v4 {
+    use super::Operators;
+    use crate::staking::DomainEpoch;
+    use crate::{BalanceOf, Config, DomainBlockNumberFor};
+    #[cfg(not(feature = "std"))]
+    use alloc::vec::Vec;
+    use codec::{Decode, Encode};
+    use frame_support::storage::unhashed;
+    use sp_domains::{DomainId, OperatorPublicKey};
+    use sp_runtime::Percent;
+
+    #[derive(Encode, Decode)]
+    pub(super) struct OperatorDeregisteredInfoV4<DomainBlockNumber> {
+        pub(super) domain_epoch: DomainEpoch,
+        pub(super) unlock_at_confirmed_domain_block_number: DomainBlockNumber,
+    }
+
+    #[derive(Encode, Decode)]
+    pub(super) enum OperatorStatusV4<DomainBlockNumber> {
+        Registered,
+        Deregistered(OperatorDeregisteredInfoV4<DomainBlockNumber>),
+        Slashed,
+        PendingSlash,
+    }
+
+    #[derive(Decode)]
+    #[cfg_attr(test, derive(codec::Encode))]
+    pub(super) struct OperatorV4<Balance, Share, DomainBlockNumber> {
+        pub(super) signing_key: OperatorPublicKey,
+        pub(super) current_domain_id: DomainId,
+        pub(super) next_domain_id: DomainId,
+        pub(super) minimum_nominator_stake: Balance,
+        pub(super) nomination_tax: Percent,
+        pub(super) current_total_stake: Balance,
+        pub(super) current_epoch_rewards: Balance,
+        pub(super) current_total_shares: Share,
+        pub(super) status: OperatorStatusV4<DomainBlockNumber>,
+        pub(super) deposits_in_epoch: Balance,
+        pub(super) withdrawals_in_epoch: Share,
+        pub(super) total_storage_fee_deposit: Balance,
+    }
+
+    #[derive(Encode, Decode)]
+    pub(super) struct OperatorV5<Balance, Share, DomainBlockNumber> {
+        pub(super) signing_key: OperatorPublicKey,
+        pub(super) current_domain_id: DomainId,
+        pub(super) next_domain_id: DomainId,
+        pub(super) minimum_nominator_stake: Balance,
+        pub(super) nomination_tax: Percent,
+        pub(super) current_total_stake: Balance,
+        pub(super) current_epoch_rewards: Balance,
+        pub(super) current_total_shares: Share,
+        pub(super) status: OperatorStatusV4<DomainBlockNumber>,
+        pub(super) deposits_in_epoch: Balance,
+        pub(super) withdrawals_in_epoch: Share,
+        pub(super) total_storage_fee_deposit: Balance,
+        pub(super) paused: bool,
+    }
+
+    /// Adds `paused: false` to all the operators, returns the number of migrated operators.
+    ///
+    /// The operators are written in the `OperatorV5` layout, which is migrated to the current
+    /// `Operator` by the following migration, so they are rewritten in place instead of being
+    /// translated.
+    pub(super) fn migrate<T: Config>() -> u64 {
+        let operator_keys: Vec<_> = Operators::<T>::iter_keys()
+            .map(Operators::<T>::hashed_key_for)
+            .collect();
+        let mut migrated = 0u64;
+        for operator_key in operator_keys {
+            let Some(operator) = unhashed::get::<
+                OperatorV4<BalanceOf<T>, T::Share, DomainBlockNumberFor<T>>,
+            >(&operator_key) else {
+                continue;
+            };
+            migrated += 1;
+            let OperatorV4 {
+                signing_key,
+                current_domain_id,
+                next_domain_id,
+                minimum_nominator_stake,
+                nomination_tax,
+                current_total_stake,
+                current_epoch_rewards,
+                current_total_shares,
+                status,
+                deposits_in_epoch,
+                withdrawals_in_epoch,
+                total_storage_fee_deposit,
+            } = operator;
+            unhashed::put(
+                &operator_key,
+                &OperatorV5 {
+                    signing_key,
+                    current_domain_id,
+                    next_domain_id,
+                    minimum_nominator_stake,
+                    nomination_tax,
+                    current_total_stake,
+                    current_epoch_rewards,
+                    current_total_shares,
+                    status,
+                    deposits_in_epoch,
+                    withdrawals_in_epoch,
+                    total_storage_fee_deposit,
+                    paused: false,
+                },
+            );
+        }
+        migrated
+    }
+}
+
+mod staking_v5 {
+    use super::operator_v4::{OperatorDeregisteredInfoV4, OperatorStatusV4, OperatorV5};
+    use super::{DeferredSlashes, Operators, Withdrawals};
+    use crate::staking::{
+        DeferredSlash, Operator, OperatorDeregisteredInfo, OperatorStatus, Withdrawal,
+        WithdrawalInBalance, WithdrawalInShares,
+    };
+    use crate::{Config, DomainBlockNumberFor};
+    use codec::{Decode, Encode};
+    use frame_system::pallet_prelude::BlockNumberFor;
+    use sp_domains::{DomainId, EpochIndex};
+    use sp_std::collections::vec_deque::VecDeque;
+
+    impl<DomainBlockNumber, ConsensusBlockNumber> From<OperatorStatusV4<DomainBlockNumber>>
+        for OperatorStatus<DomainBlockNumber, ConsensusBlockNumber>
+    {
+        fn from(status: OperatorStatusV4<DomainBlockNumber>) -> Self {
+            match status {
+                OperatorStatusV4::Registered => OperatorStatus::Registered,
+                OperatorStatusV4::Deregistered(OperatorDeregisteredInfoV4 {
+                    domain_epoch,
+                    unlock_at_confirmed_domain_block_number,
+                }) => OperatorStatus::Deregistered(OperatorDeregisteredInfo {
+                    domain_epoch,
+                    unlock_at_confirmed_domain_block_number,
+                    unlock_at_consensus_block_number: None,
+                }),
+                OperatorStatusV4::Slashed => OperatorStatus::Slashed,
+                OperatorStatusV4::PendingSlash => OperatorStatus::PendingSlash,
+            }
+        }
+    }
+
+    #[derive(Decode)]
+    #[cfg_attr(test, derive(codec::Encode))]
+    pub(super) struct DeferredSlashV5<DomainBlockNumber> {
+        pub(super) finalize_at_epoch: EpochIndex,
+        pub(super) previous_status: OperatorStatusV4<DomainBlockNumber>,
+    }
+
+    #[derive(Decode)]
+    #[cfg_attr(test, derive(codec::Encode))]
+    pub(super) struct WithdrawalInBalanceV5<DomainBlockNumber, Balance> {
+        pub(super) domain_id: DomainId,
+        pub(super) unlock_at_confirmed_domain_block_number: DomainBlockNumber,
+        pub(super) amount_to_unlock: Balance,
+        pub(super) storage_fee_refund: Balance,
+    }
+
+    #[derive(Decode)]
+    #[cfg_attr(test, derive(codec::Encode))]
+    pub(super) struct WithdrawalV5<Balance, Share, DomainBlockNumber> {
+        pub(super) total_withdrawal_amount: Balance,
+        pub(super) withdrawals: VecDeque<WithdrawalInBalanceV5<DomainBlockNumber, Balance>>,
+        pub(super) withdrawal_in_shares:
+            Option<WithdrawalInShares<DomainBlockNumber, Share, Balance>>,
+    }
+
+    /// Adds an empty unlock schedule on the consensus clock to the deregistered info of all the
+    /// operators, including the status kept by the deferred slashes, and to all the withdrawals,
+    /// returns the number of migrated entries.
+    pub(super) fn migrate<T: Config>() -> u64 {
+        let mut migrated = 0u64;
+        Operators::<T>::translate::<OperatorV5<_, _, _>, _>(|_, operator| {
+            migrated += 1;
+            let OperatorV5 {
+                signing_key,
+                current_domain_id,
+                next_domain_id,
+                minimum_nominator_stake,
+                nomination_tax,
+                current_total_stake,
+                current_epoch_rewards,
+                current_total_shares,
+                status,
+                deposits_in_epoch,
+                withdrawals_in_epoch,
+                total_storage_fee_deposit,
+                paused,
+            } = operator;
+            // The status of the `Operator` is private, so the operator is decoded from the
+            // encoding of its fields, which is the same as the encoding of the struct
+            let status: OperatorStatus<DomainBlockNumberFor<T>, BlockNumberFor<T>> = status.into();
+            Operator::decode(
+                &mut (
+                    signing_key,
+                    current_domain_id,
+                    next_domain_id,
+                    minimum_nominator_stake,
+                    nomination_tax,
+                    current_total_stake,
+                    current_epoch_rewards,
+                    current_total_shares,
+                    status,
+                    deposits_in_epoch,
+                    withdrawals_in_epoch,
+                    total_storage_fee_deposit,
+                    paused,
+                )
+                    .encode()
+                    .as_slice(),
+            )
+            .ok()
+        });
+        DeferredSlashes::<T>::translate::<DeferredSlashV5<_>, _>(|_, deferred_slash| {
+            migrated += 1;
+            Some(DeferredSlash {
+                finalize_at_epoch: deferred_slash.finalize_at_epoch,
+                previous_status: deferred_slash.previous_status.into(),
+            })
+        });
+        Withdrawals::<T>::translate::<WithdrawalV5<_, _, _>, _>(|_, _, withdrawal| {
+            migrated += 1;
+            Some(Withdrawal {
+                total_withdrawal_amount: withdrawal.total_withdrawal_amount,
+                withdrawals: withdrawal
+                    .withdrawals
+                    .into_iter()
+                    .map(|withdrawal_in_balance| WithdrawalInBalance {
+                        domain_id: withdrawal_in_balance.domain_id,
+                        unlock_at_confirmed_domain_block_number: withdrawal_in_balance
+                            .unlock_at_confirmed_domain_block_number,
+                        unlock_at_consensus_block_number: None,
+                        amount_to_unlock: withdrawal_in_balance.amount_to_unlock,
+                        storage_fee_refund: withdrawal_in_balance.storage_fee_refund,
+                    })
+                    .collect(),
+                withdrawal_in_shares: withdrawal.withdrawal_in_shares,
+            })
+        });
+        migrated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::domain_registry_v0::{DomainConfigV0, DomainObjectV0};
+    use super::operator_v4::{OperatorDeregisteredInfoV4, OperatorStatusV4, OperatorV4};
+    use super::scheduled_runtime_upgrade_v0::ScheduledRuntimeUpgradeV0;
+    use super::staking_v5::{DeferredSlashV5, WithdrawalInBalanceV5, WithdrawalV5};
+    use super::{MigrateToV1, MigrateToV2, MigrateToV3, MigrateToV4, MigrateToV5, MigrateToV6};
+    use crate::domain_registry::{DomainConfig, DomainObject};
+    use crate::pallet::{
+        DeferredSlashes, DomainRegistry, Operators, OwnedDomains, PendingRuntimeUpgrades,
+        RuntimeRegistry, RuntimeSummaries, ScheduledRuntimeUpgrades, Withdrawals,
+    };
+    use crate::runtime_registry::{RuntimeObject, ScheduledRuntimeUpgrade};
+    use crate::staking::{
+        DeferredSlash, Operator, OperatorDeregisteredInfo, OperatorStatus, Withdrawal,
+        WithdrawalInBalance, WithdrawalInShares,
+    };
+    use crate::tests::{new_test_ext, DomainRuntimeUpgradeDelay, Test};
+    use crate::{DomainBlockNumberFor, Pallet};
+    use codec::Encode;
+    use frame_support::traits::{GetStorageVersion, OnRuntimeUpgrade, StorageVersion};
+    use frame_support::weights::Weight;
+    use sp_core::{Pair, H256, U256};
+    use sp_domains::storage::RawGenesis;
+    use sp_domains::{DomainId, OperatorAllowList, OperatorPair, RuntimeType};
+    use sp_version::RuntimeVersion;
+
+    #[test]
+    fn test_migrate_domain_registry_to_v1() {
+        new_test_ext().execute_with(|| {
+            let domain_id = DomainId::new(0);
+            let domain_obj_v0 = DomainObjectV0 {
+                owner_account_id: 1u128,
+                created_at: 10u64,
+                genesis_receipt_hash: H256::zero(),
+                domain_config: DomainConfigV0 {
+                    domain_name: "evm-domain".to_owned(),
+                    runtime_id: 0,
+                    max_block_size: 1024,
+                    max_block_weight: Weight::from_parts(1, 0),
+                    bundle_slot_probability: (1, 1),
+                    target_bundles_per_block: 1,
+                    operator_allow_list: OperatorAllowList::Anyone,
+                    initial_balances: vec![],
+                },
+                domain_runtime_info: Default::default(),
+            };
+            frame_support::storage::unhashed::put_raw(
+                &DomainRegistry::<Test>::hashed_key_for(domain_id),
+                &domain_obj_v0.encode(),
+            );
+            StorageVersion::new(0).put::<Pallet<Test>>();
+
+            MigrateToV1::<Test>::on_runtime_upgrade();
+
+            assert_eq!(Pallet::<Test>::on_chain_storage_version(), 1);
+            assert_eq!(
+                DomainRegistry::<Test>::get(domain_id),
+                Some(DomainObject {
+                    owner_account_id: 1u128,
+                    created_at: 10u64,
+                    genesis_receipt_hash: H256::zero(),
+                    domain_config: DomainConfig {
+                        domain_name: "evm-domain".to_owned(),
+                        runtime_id: 0,
+                        max_block_size: 1024,
+                        max_block_weight: Weight::from_parts(1, 0),
+                        bundle_slot_probability: (1, 1),
+                        target_bundles_per_block: 1,
+                        operator_allow_list: OperatorAllowList::Anyone,
+                        initial_balances: vec![],
+                        initial_contract_creation_allow_list: None,
+                    },
+                    domain_runtime_info: Default::default(),
+                })
+            );
+
+            // The migration is a no-op once the storage is migrated
+            DomainRegistry::<Test>::remove(domain_id);
+            MigrateToV1::<Test>::on_runtime_upgrade();
+            assert_eq!(DomainRegistry::<Test>::get(domain_id), None);
+        });
+    }
+
+    #[test]
+    fn test_migrate_runtime_summaries_to_v2() {
+        new_test_ext().execute_with(|| {
+            let runtime_obj = RuntimeObject {
+                runtime_name: "evm".to_owned(),
+                runtime_type: RuntimeType::Evm,
+                runtime_upgrades: 1,
+                hash: H256::random(),
+                raw_genesis: RawGenesis::dummy(vec![1, 2, 3, 4]),
+                version: RuntimeVersion {
+                    spec_name: "test".into(),
+                    spec_version: 2,
+                    ..Default::default()
+                },
+                created_at: 1u64,
+                updated_at: 10u64,
+            };
+            RuntimeRegistry::<Test>::insert(0, runtime_obj.clone());
+            RuntimeRegistry::<Test>::insert(1, runtime_obj.clone());
+            let mut runtime_1_summary = runtime_obj.summary(1);
+            runtime_1_summary.runtime_name = "stored".to_owned();
+            RuntimeSummaries::<Test>::insert(1, runtime_1_summary.clone());
+            StorageVersion::new(1).put::<Pallet<Test>>();
+
+            MigrateToV2::<Test>::on_runtime_upgrade();
+
+            assert_eq!(Pallet::<Test>::on_chain_storage_version(), 2);
+            assert_eq!(
+                RuntimeSummaries::<Test>::get(0),
+                Some(runtime_obj.summary(0))
+            );
+            // Existing summary is kept
+            assert_eq!(RuntimeSummaries::<Test>::get(1), Some(runtime_1_summary));
+
+            // The migration is a no-op once the storage is migrated
+            RuntimeSummaries::<Test>::remove(0);
+            MigrateToV2::<Test>::on_runtime_upgrade();
+            assert_eq!(RuntimeSummaries::<Test>::get(0), None);
+        });
+    }
+
+    #[test]
+    fn test_migrate_scheduled_runtime_upgrades_to_v3() {
+        new_test_ext().execute_with(|| {
+            let version = RuntimeVersion {
+                spec_name: "test".into(),
+                spec_version: 2,
+                ..Default::default()
+            };
+            let hash = H256::random();
+            // Runtime 0 has two upgrades pending, runtime 1 has one
+            for (scheduled_at, runtime_id) in [(20u64, 0), (10u64, 0), (10u64, 1)] {
+                frame_support::storage::unhashed::put_raw(
+                    &ScheduledRuntimeUpgrades::<Test>::hashed_key_for(scheduled_at, runtime_id),
+                    &ScheduledRuntimeUpgradeV0 {
+                        raw_genesis: RawGenesis::dummy(vec![1, 2, 3, 4]),
+                        version: version.clone(),
+                        hash,
+                    }
+                    .encode(),
+                );
+            }
+            StorageVersion::new(2).put::<Pallet<Test>>();
+
+            MigrateToV3::<Test>::on_runtime_upgrade();
+
+            assert_eq!(Pallet::<Test>::on_chain_storage_version(), 3);
+            for (scheduled_at, runtime_id) in [(20u64, 0), (10u64, 0), (10u64, 1)] {
+                assert_eq!(
+                    ScheduledRuntimeUpgrades::<Test>::get(scheduled_at, runtime_id),
+                    Some(ScheduledRuntimeUpgrade {
+                        raw_genesis: RawGenesis::dummy(vec![1, 2, 3, 4]),
+                        version: version.clone(),
+                        hash,
+                        delay: DomainRuntimeUpgradeDelay::get(),
+                    })
+                );
+            }
+            // The latest upgrade is recorded as pending until it is applied
+            assert_eq!(PendingRuntimeUpgrades::<Test>::get(0), Some(20));
+            assert_eq!(PendingRuntimeUpgrades::<Test>::get(1), Some(10));
+
+            // The migration is a no-op once the storage is migrated
+            PendingRuntimeUpgrades::<Test>::remove(0);
+            MigrateToV3::<Test>::on_runtime_upgrade();
+            assert_eq!(PendingRuntimeUpgrades::<Test>::get(0), None);
+        });
+    }
+
+    #[test]
+    fn test_migrate_owned_domains_to_v4() {
+        new_test_ext().execute_with(|| {
+            let domain_obj = |owner_account_id| DomainObject {
+                owner_account_id,
+                created_at: 0u64,
+                genesis_receipt_hash: H256::zero(),
+                domain_config: DomainConfig {
+                    domain_name: "evm-domain".to_owned(),
+                    runtime_id: 0,
+                    max_block_size: 1024,
+                    max_block_weight: Weight::from_parts(1, 0),
+                    bundle_slot_probability: (1, 1),
+                    target_bundles_per_block: 1,
+                    operator_allow_list: OperatorAllowList::Anyone,
+                    initial_balances: vec![],
+                    initial_contract_creation_allow_list: None,
+                },
+                domain_runtime_info: Default::default(),
+            };
+            // Domain 0 is the genesis domain, which is owned by the same account as domain 2
+            DomainRegistry::<Test>::insert(DomainId::new(0), domain_obj(1u128));
+            DomainRegistry::<Test>::insert(DomainId::new(1), domain_obj(2u128));
+            DomainRegistry::<Test>::insert(DomainId::new(2), domain_obj(1u128));
+            StorageVersion::new(3).put::<Pallet<Test>>();
+
+            MigrateToV4::<Test>::on_runtime_upgrade();
+
+            assert_eq!(Pallet::<Test>::on_chain_storage_version(), 4);
+            assert_eq!(
+                Pallet::<Test>::domains_owned_by(1u128),
+                vec![DomainId::new(0), DomainId::new(2)]
+            );
+            assert_eq!(
+                Pallet::<Test>::domains_owned_by(2u128),
+                vec![DomainId::new(1)]
+            );
+
+            // The migration is a no-op once the storage is migrated
+            OwnedDomains::<Test>::remove(2u128);
+            MigrateToV4::<Test>::on_runtime_upgrade();
+            assert!(Pallet::<Test>::domains_owned_by(2u128).is_empty());
+        });
+    }
+
+    #[test]
+    fn test_migrate_operators_to_v5() {
+        new_test_ext().execute_with(|| {
+            let domain_id = DomainId::new(0);
+            let operator_v4 = |status| OperatorV4 {
+                signing_key: OperatorPair::from_seed(&U256::from(0u32).into()).public(),
+                current_domain_id: domain_id,
+                next_domain_id: domain_id,
+                minimum_nominator_stake: 10u128,
+                nomination_tax: Default::default(),
+                current_total_stake: 100u128,
+                current_epoch_rewards: 5u128,
+                current_total_shares: 100u128,
+                status,
+                deposits_in_epoch: 0u128,
+                withdrawals_in_epoch: 0u128,
+                total_storage_fee_deposit: 20u128,
+            };
+            let operators = [
+                (
+                    0u64,
+                    operator_v4(OperatorStatusV4::<DomainBlockNumberFor<Test>>::Registered),
+                ),
+                (
+                    1,
+                    operator_v4(OperatorStatusV4::Deregistered(OperatorDeregisteredInfoV4 {
+                        domain_epoch: (domain_id, 1).into(),
+                        unlock_at_confirmed_domain_block_number: 120,
+                    })),
+                ),
+            ];
+            for (operator_id, operator) in &operators {
+                frame_support::storage::unhashed::put_raw(
+                    &Operators::<Test>::hashed_key_for(operator_id),
+                    &operator.encode(),
+                );
+            }
+            StorageVersion::new(4).put::<Pallet<Test>>();
+
+            MigrateToV5::<Test>::on_runtime_upgrade();
+
+            // The operators are not paused, the flag is appended to the end of the operator
+            assert_eq!(Pallet::<Test>::on_chain_storage_version(), 5);
+            for (operator_id, operator) in &operators {
+                assert_eq!(
+                    frame_support::storage::unhashed::get_raw(&Operators::<Test>::hashed_key_for(
+                        operator_id
+                    )),
+                    Some([operator.encode(), false.encode()].concat())
+                );
+            }
+
+            // The migration is a no-op once the storage is migrated
+            MigrateToV5::<Test>::on_runtime_upgrade();
+            assert_eq!(
+                frame_support::storage::unhashed::get_raw(&Operators::<Test>::hashed_key_for(0u64)),
+                Some([operators[0].1.encode(), false.encode()].concat())
+            );
+        });
+    }
+
+    #[test]
+    fn test_migrate_staking_to_v6() {
+        new_test_ext().execute_with(|| {
+            let domain_id = DomainId::new(0);
+            let (operator_id, nominator_id) = (1u64, 2u128);
+            let signing_key = OperatorPair::from_seed(&U256::from(0u32).into()).public();
+            let unlock_at: DomainBlockNumberFor<Test> = 120;
+            let deregistered_v4 = || {
+                OperatorStatusV4::Deregistered(OperatorDeregisteredInfoV4 {
+                    domain_epoch: (domain_id, 1).into(),
+                    unlock_at_confirmed_domain_block_number: unlock_at,
+                })
+            };
+            let deregistered = OperatorStatus::Deregistered(OperatorDeregisteredInfo {
+                domain_epoch: (domain_id, 1).into(),
+                unlock_at_confirmed_domain_block_number: unlock_at,
+                unlock_at_consensus_block_number: None,
+            });
+            let withdrawal_in_shares = WithdrawalInShares {
+                domain_epoch: (domain_id, 2).into(),
+                unlock_at_confirmed_domain_block_number: unlock_at + 10,
+                shares: 5u128,
+                storage_fee_refund: 1u128,
+            };
+
+            // Both the operators and the withdrawals are in the layout before any of the staking
+            // migrations, the registered operator is encoded without its trailing `paused` flag,
+            // which is the only difference of the registered operator from that layout
+            let registered_operator_id = 0u64;
+            let mut registered_operator = Operator::dummy(domain_id, signing_key.clone(), 10);
+            registered_operator.current_total_stake = 50;
+            let registered_operator_encoded = registered_operator.encode();
+            frame_support::storage::unhashed::put_raw(
+                &Operators::<Test>::hashed_key_for(registered_operator_id),
+                &registered_operator_encoded[..registered_operator_encoded.len() - 1],
+            );
+            frame_support::storage::unhashed::put_raw(
+                &Operators::<Test>::hashed_key_for(operator_id),
+                &OperatorV4 {
+                    signing_key: signing_key.clone(),
+                    current_domain_id: domain_id,
+                    next_domain_id: domain_id,
+                    minimum_nominator_stake: 10u128,
+                    nomination_tax: Default::default(),
+                    current_total_stake: 100u128,
+                    current_epoch_rewards: 0u128,
+                    current_total_shares: 100u128,
+                    status: deregistered_v4(),
+                    deposits_in_epoch: 0u128,
+                    withdrawals_in_epoch: 0u128,
+                    total_storage_fee_deposit: 20u128,
+                }
+                .encode(),
+            );
+            frame_support::storage::unhashed::put_raw(
+                &DeferredSlashes::<Test>::hashed_key_for(operator_id),
+                &DeferredSlashV5 {
+                    finalize_at_epoch: 3,
+                    previous_status: deregistered_v4(),
+                }
+                .encode(),
+            );
+            frame_support::storage::unhashed::put_raw(
+                &Withdrawals::<Test>::hashed_key_for(operator_id, nominator_id),
+                &WithdrawalV5 {
+                    total_withdrawal_amount: 40u128,
+                    withdrawals: vec![WithdrawalInBalanceV5 {
+                        domain_id,
+                        unlock_at_confirmed_domain_block_number: unlock_at,
+                        amount_to_unlock: 40u128,
+                        storage_fee_refund: 10u128,
+                    }]
+                    .into(),
+                    withdrawal_in_shares: Some(withdrawal_in_shares.clone()),
+                }
+                .encode(),
+            );
+            StorageVersion::new(4).put::<Pallet<Test>>();
+
+            MigrateToV5::<Test>::on_runtime_upgrade();
+            MigrateToV6::<Test>::on_runtime_upgrade();
+
+            assert_eq!(Pallet::<Test>::on_chain_storage_version(), 6);
+            let mut operator = Operator::dummy(domain_id, signing_key, 10);
+            operator.current_total_stake = 100;
+            operator.current_total_shares = 100;
+            operator.total_storage_fee_deposit = 20;
+            operator.update_status(deregistered.clone());
+            assert_eq!(Operators::<Test>::get(operator_id), Some(operator));
+            assert_eq!(
+                Operators::<Test>::get(registered_operator_id),
+                Some(registered_operator)
+            );
+            assert_eq!(
+                DeferredSlashes::<Test>::get(operator_id),
+                Some(DeferredSlash {
+                    finalize_at_epoch: 3,
+                    previous_status: deregistered,
+                })
+            );
+            assert_eq!(
+                Withdrawals::<Test>::get(operator_id, nominator_id),
+                Some(Withdrawal {
+                    total_withdrawal_amount: 40,
+                    withdrawals: vec![WithdrawalInBalance {
+                        domain_id,
+                        unlock_at_confirmed_domain_block_number: unlock_at,
+                        unlock_at_consensus_block_number: None,
+                        amount_to_unlock: 40,
+                        storage_fee_refund: 10,
+                    }]
+                    .into(),
+                    withdrawal_in_shares: Some(withdrawal_in_shares),
+                })
+            );
+
+            // The migration is a no-op once the storage is migrated
+            DeferredSlashes::<Test>::remove(operator_id);
+            MigrateToV6::<Test>::on_runtime_upgrade();
+            assert_eq!(DeferredSlashes::<Test>::get(operator_id), None);
+            assert!(Operators::<Test>::get(operator_id).is_some());
+        });
+    }
+}
@@ -3,21 +3,29 @@
 #[cfg(not(feature = "std"))]
 extern crate alloc;
 
-use crate::pallet::{NextRuntimeId, RuntimeRegistry, ScheduledRuntimeUpgrades};
+use crate::pallet::{
+    NextRuntimeId, PendingRuntimeUpgrades, RuntimeRegistry, RuntimeSummaries,
+    ScheduledRuntimeUpgrades,
+};
 use crate::{BalanceOf, Config, Event};
 #[cfg(not(feature = "std"))]
 use alloc::string::String;
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
 use codec::{Decode, Encode};
-use domain_runtime_primitives::{AccountId20, EVMChainId, MultiAccountId, TryConvertBack};
-use frame_support::PalletError;
+use domain_runtime_primitives::{
+    AccountId20, EVMChainId, EthereumAccountId, MultiAccountId, TryConvertBack,
+};
+use frame_support::{ensure, PalletError};
 use frame_system::pallet_prelude::*;
 use frame_system::AccountInfo;
 use scale_info::TypeInfo;
 use sp_core::Hasher;
 use sp_domains::storage::{RawGenesis, StorageData, StorageKey};
-use sp_domains::{DomainId, DomainsDigestItem, RuntimeId, RuntimeType};
+use sp_domains::{
+    DomainId, DomainsDigestItem, PermissionedActionAllowedBy, RuntimeId, RuntimeSummary,
+    RuntimeType,
+};
 use sp_runtime::traits::{CheckedAdd, Get, Zero};
 use sp_runtime::DigestItem;
 use sp_std::vec;
@@ -36,6 +44,7 @@ pub enum Error {
     FailedToDecodeRawGenesis,
     RuntimeCodeNotFoundInRawGenesis,
     InvalidAccountIdType,
+    InvalidRuntimeUpgradeDelay,
 }
 
 #[derive(TypeInfo, Debug, Encode, Decode, Clone, PartialEq, Eq)]
@@ -89,6 +98,22 @@ fn derive_initial_balances_storages<T: Config, AccountId: Encode>(
     initial_storages
 }
 
+impl<Number: Clone, Hash: Clone> RuntimeObject<Number, Hash> {
+    /// Returns the summary of the runtime object.
+    pub fn summary(&self, runtime_id: RuntimeId) -> RuntimeSummary<Number, Hash> {
+        RuntimeSummary {
+            runtime_id,
+            runtime_name: self.runtime_name.clone(),
+            runtime_type: self.runtime_type.clone(),
+            version: self.version.clone(),
+            code_hash: self.hash.clone(),
+            runtime_upgrades: self.runtime_upgrades,
+            created_at: self.created_at.clone(),
+            updated_at: self.updated_at.clone(),
+        }
+    }
+}
+
 impl<Number, Hash> RuntimeObject<Number, Hash> {
     // Return a complete raw genesis with runtime code and domain id set properly
     pub fn into_complete_raw_genesis<T: Config>(
@@ -97,6 +122,9 @@ impl<Number, Hash> RuntimeObject<Number, Hash> {
         domain_runtime_info: DomainRuntimeInfo,
         total_issuance: BalanceOf<T>,
         initial_balances: Vec<(MultiAccountId, BalanceOf<T>)>,
+        initial_contract_creation_allow_list: Option<
+            PermissionedActionAllowedBy<EthereumAccountId>,
+        >,
     ) -> Result<RawGenesis, Error> {
         let RuntimeObject {
             mut raw_genesis, ..
@@ -122,6 +150,9 @@ impl<Number, Hash> RuntimeObject<Number, Hash> {
                     total_issuance,
                     initial_balances,
                 ));
+                if let Some(contract_creation_allow_list) = initial_contract_creation_allow_list {
+                    raw_genesis.set_evm_contract_creation_allowed_by(&contract_creation_allow_list);
+                }
             }
         }
 
@@ -130,10 +161,12 @@ impl<Number, Hash> RuntimeObject<Number, Hash> {
 }
 
 #[derive(TypeInfo, Debug, Encode, Decode, Clone, PartialEq, Eq)]
-pub struct ScheduledRuntimeUpgrade<Hash> {
+pub struct ScheduledRuntimeUpgrade<Number, Hash> {
     pub raw_genesis: RawGenesis,
     pub version: RuntimeVersion,
     pub hash: Hash,
+    /// Effective delay, in blocks, between scheduling and applying the upgrade.
+    pub delay: Number,
 }
 
 /// Extracts the runtime version of the provided code.
@@ -180,7 +213,7 @@ pub(crate) fn do_register_runtime<T: Config>(
     let runtime_hash = T::Hashing::hash(code);
     let runtime_id = NextRuntimeId::<T>::get();
 
-    RuntimeRegistry::<T>::insert(
+    insert_runtime_object::<T>(
         runtime_id,
         RuntimeObject {
             runtime_name,
@@ -219,7 +252,7 @@ pub(crate) fn register_runtime_at_genesis<T: Config>(
     let runtime_hash = T::Hashing::hash(code);
     let runtime_id = NextRuntimeId::<T>::get();
 
-    RuntimeRegistry::<T>::insert(
+    insert_runtime_object::<T>(
         runtime_id,
         RuntimeObject {
             runtime_name,
@@ -239,13 +272,45 @@ pub(crate) fn register_runtime_at_genesis<T: Config>(
     Ok(runtime_id)
 }
 
-/// Schedules a runtime upgrade after `DomainRuntimeUpgradeDelay` from current block number.
+/// Insert the runtime object into `RuntimeRegistry` along with its summary.
+pub(crate) fn insert_runtime_object<T: Config>(
+    runtime_id: RuntimeId,
+    runtime_obj: RuntimeObject<BlockNumberFor<T>, T::Hash>,
+) {
+    RuntimeSummaries::<T>::insert(runtime_id, runtime_obj.summary(runtime_id));
+    RuntimeRegistry::<T>::insert(runtime_id, runtime_obj);
+}
+
+/// Schedules a runtime upgrade after `delay` blocks from current block number, falling back to
+/// `DomainRuntimeUpgradeDelay` when no delay is given.
+///
+/// Returns the block number the upgrade is scheduled at along with the effective delay.
+///
+/// Fails if an upgrade of the runtime is already pending, the pending upgrade has to be applied
+/// before another one can be scheduled.
 pub(crate) fn do_schedule_runtime_upgrade<T: Config>(
     runtime_id: RuntimeId,
     raw_genesis_storage: Vec<u8>,
     current_block_number: BlockNumberFor<T>,
-) -> Result<BlockNumberFor<T>, Error> {
+    delay: Option<BlockNumberFor<T>>,
+) -> Result<(BlockNumberFor<T>, BlockNumberFor<T>), Error> {
+    let delay = match delay {
+        Some(delay) => {
+            ensure!(
+                delay >= T::MinRuntimeUpgradeDelay::get()
+                    && delay <= T::MaxRuntimeUpgradeDelay::get(),
+                Error::InvalidRuntimeUpgradeDelay
+            );
+            delay
+        }
+        None => T::DomainRuntimeUpgradeDelay::get(),
+    };
+
     let runtime_obj = RuntimeRegistry::<T>::get(runtime_id).ok_or(Error::MissingRuntimeObject)?;
+    ensure!(
+        !PendingRuntimeUpgrades::<T>::contains_key(runtime_id),
+        Error::RuntimeUpgradeAlreadyScheduled
+    );
 
     let new_raw_genesis: RawGenesis = Decode::decode(&mut raw_genesis_storage.as_slice())
         .map_err(|_| Error::FailedToDecodeRawGenesis)?;
@@ -260,18 +325,28 @@ pub(crate) fn do_schedule_runtime_upgrade<T: Config>(
         raw_genesis: new_raw_genesis,
         version: new_runtime_version,
         hash: new_runtime_hash,
+        delay,
     };
     let scheduled_at = current_block_number
-        .checked_add(&T::DomainRuntimeUpgradeDelay::get())
+        .checked_add(&delay)
         .ok_or(Error::MaxScheduledBlockNumber)?;
 
     ScheduledRuntimeUpgrades::<T>::insert(scheduled_at, runtime_id, scheduled_upgrade);
+    PendingRuntimeUpgrades::<T>::insert(runtime_id, scheduled_at);
 
-    Ok(scheduled_at)
+    Ok((scheduled_at, delay))
 }
 
 pub(crate) fn do_upgrade_runtimes<T: Config>(at: BlockNumberFor<T>) {
     for (runtime_id, scheduled_update) in ScheduledRuntimeUpgrades::<T>::drain_prefix(at) {
+        // Only clear the pending upgrade if it is this one, upgrades scheduled before a runtime
+        // could only have one pending upgrade may still be pending at a later block
+        PendingRuntimeUpgrades::<T>::mutate_exists(runtime_id, |maybe_scheduled_at| {
+            if *maybe_scheduled_at == Some(at) {
+                *maybe_scheduled_at = None;
+            }
+        });
+
         RuntimeRegistry::<T>::mutate(runtime_id, |maybe_runtime_object| {
             let runtime_obj = maybe_runtime_object
                 .as_mut()
@@ -282,6 +357,8 @@ pub(crate) fn do_upgrade_runtimes<T: Config>(at: BlockNumberFor<T>) {
             runtime_obj.hash = scheduled_update.hash;
             runtime_obj.runtime_upgrades = runtime_obj.runtime_upgrades.saturating_add(1);
             runtime_obj.updated_at = at;
+
+            RuntimeSummaries::<T>::insert(runtime_id, runtime_obj.summary(runtime_id));
         });
 
         // deposit digest log for light clients
@@ -296,18 +373,21 @@ pub(crate) fn do_upgrade_runtimes<T: Config>(at: BlockNumberFor<T>) {
 
 #[cfg(test)]
 mod tests {
-    use crate::pallet::{NextRuntimeId, RuntimeRegistry, ScheduledRuntimeUpgrades};
+    use crate::pallet::{
+        NextRuntimeId, PendingRuntimeUpgrades, RuntimeRegistry, ScheduledRuntimeUpgrades,
+    };
     use crate::runtime_registry::{Error as RuntimeRegistryError, RuntimeObject};
     use crate::tests::{
-        new_test_ext, DomainRuntimeUpgradeDelay, Domains, ReadRuntimeVersion, System, Test,
+        new_test_ext, DomainRuntimeUpgradeDelay, Domains, MaxRuntimeUpgradeDelay,
+        MinRuntimeUpgradeDelay, ReadRuntimeVersion, RuntimeEvent, System, Test,
     };
-    use crate::Error;
+    use crate::{Error, Event};
     use codec::Encode;
     use frame_support::assert_ok;
     use frame_support::dispatch::RawOrigin;
     use frame_support::traits::OnInitialize;
     use sp_domains::storage::RawGenesis;
-    use sp_domains::{DomainsDigestItem, RuntimeId, RuntimeType};
+    use sp_domains::{DomainsDigestItem, RuntimeId, RuntimeSummary, RuntimeType};
     use sp_runtime::traits::BlockNumberProvider;
     use sp_runtime::{Digest, DispatchError};
     use sp_version::RuntimeVersion;
@@ -411,6 +491,7 @@ mod tests {
                     RawOrigin::Root.into(),
                     0,
                     RawGenesis::dummy(vec![6, 7, 8, 9]).encode(),
+                    None,
                 );
 
                 assert_eq!(res, expected.map_err(DispatchError::from))
@@ -523,6 +604,7 @@ mod tests {
                 RawOrigin::Root.into(),
                 0,
                 RawGenesis::dummy(vec![6, 7, 8, 9]).encode(),
+                None,
             );
             assert_ok!(res);
 
@@ -531,11 +613,30 @@ mod tests {
                 .checked_add(DomainRuntimeUpgradeDelay::get())
                 .unwrap();
 
+            assert_eq!(
+                PendingRuntimeUpgrades::<Test>::get(0),
+                Some(scheduled_block_number)
+            );
+
+            // Another upgrade can't be scheduled while the upgrade is pending
+            assert_eq!(
+                crate::Pallet::<Test>::upgrade_domain_runtime(
+                    RawOrigin::Root.into(),
+                    0,
+                    RawGenesis::dummy(vec![6, 7, 8, 9]).encode(),
+                    Some(DomainRuntimeUpgradeDelay::get() + 1),
+                ),
+                Err(DispatchError::from(Error::<Test>::RuntimeRegistry(
+                    RuntimeRegistryError::RuntimeUpgradeAlreadyScheduled
+                )))
+            );
+
             go_to_block(scheduled_block_number);
             assert_eq!(
                 ScheduledRuntimeUpgrades::<Test>::get(scheduled_block_number, 0),
                 None
             );
+            assert_eq!(PendingRuntimeUpgrades::<Test>::get(0), None);
 
             let runtime_obj = RuntimeRegistry::<Test>::get(0).unwrap();
             assert_eq!(runtime_obj.version, version);
@@ -544,4 +645,172 @@ mod tests {
             assert_eq!(Some(0), fetch_upgraded_runtime_from_digest(digest))
         });
     }
+
+    #[test]
+    fn schedule_domain_runtime_upgrade_with_delay() {
+        let mut ext = new_test_ext();
+        let mut version = RuntimeVersion {
+            spec_name: "test".into(),
+            spec_version: 1,
+            impl_version: 1,
+            transaction_version: 1,
+            ..Default::default()
+        };
+
+        ext.execute_with(|| {
+            RuntimeRegistry::<Test>::insert(
+                0,
+                RuntimeObject {
+                    runtime_name: "evm".to_owned(),
+                    runtime_type: Default::default(),
+                    runtime_upgrades: 0,
+                    hash: Default::default(),
+                    raw_genesis: RawGenesis::dummy(vec![1, 2, 3, 4]),
+                    version: version.clone(),
+                    created_at: Default::default(),
+                    updated_at: Default::default(),
+                },
+            );
+
+            NextRuntimeId::<Test>::set(1);
+        });
+
+        version.spec_version = 2;
+        ext.register_extension(sp_core::traits::ReadRuntimeVersionExt::new(
+            ReadRuntimeVersion(version.encode()),
+        ));
+
+        let test_data = vec![
+            (
+                Some(MinRuntimeUpgradeDelay::get() - 1),
+                Err(RuntimeRegistryError::InvalidRuntimeUpgradeDelay),
+            ),
+            (
+                Some(MaxRuntimeUpgradeDelay::get() + 1),
+                Err(RuntimeRegistryError::InvalidRuntimeUpgradeDelay),
+            ),
+            (
+                Some(MinRuntimeUpgradeDelay::get()),
+                Ok(MinRuntimeUpgradeDelay::get()),
+            ),
+            (None, Ok(DomainRuntimeUpgradeDelay::get())),
+        ];
+
+        for (delay, expected) in test_data.into_iter() {
+            ext.execute_with(|| {
+                System::set_block_number(100u64);
+                let res = crate::Pallet::<Test>::upgrade_domain_runtime(
+                    RawOrigin::Root.into(),
+                    0,
+                    RawGenesis::dummy(vec![6, 7, 8, 9]).encode(),
+                    delay,
+                );
+
+                match expected {
+                    Err(err) => {
+                        assert_eq!(
+                            res,
+                            Err(DispatchError::from(Error::<Test>::RuntimeRegistry(err)))
+                        );
+                    }
+                    Ok(expected_delay) => {
+                        assert_ok!(res);
+
+                        let scheduled_at = 100u64 + expected_delay;
+                        let scheduled_upgrade =
+                            ScheduledRuntimeUpgrades::<Test>::get(scheduled_at, 0).unwrap();
+                        assert_eq!(scheduled_upgrade.version, version);
+                        assert_eq!(scheduled_upgrade.delay, expected_delay);
+                        System::assert_last_event(RuntimeEvent::Domains(
+                            Event::DomainRuntimeUpgradeScheduled {
+                                runtime_id: 0,
+                                scheduled_at,
+                                delay: expected_delay,
+                            },
+                        ));
+
+                        assert_eq!(PendingRuntimeUpgrades::<Test>::get(0), Some(scheduled_at));
+
+                        // Drop the pending upgrade so the next one can be scheduled
+                        ScheduledRuntimeUpgrades::<Test>::remove(scheduled_at, 0);
+                        PendingRuntimeUpgrades::<Test>::remove(0);
+                    }
+                }
+            })
+        }
+    }
+
+    #[test]
+    fn runtime_summaries_follow_upgrades() {
+        let mut ext = new_test_ext();
+        let mut version = RuntimeVersion {
+            spec_name: "test".into(),
+            spec_version: 1,
+            impl_version: 1,
+            transaction_version: 1,
+            ..Default::default()
+        };
+        ext.register_extension(sp_core::traits::ReadRuntimeVersionExt::new(
+            ReadRuntimeVersion(version.encode()),
+        ));
+
+        ext.execute_with(|| {
+            assert_ok!(crate::Pallet::<Test>::register_domain_runtime(
+                RawOrigin::Root.into(),
+                "evm".to_owned(),
+                RuntimeType::Evm,
+                RawGenesis::dummy(vec![1, 2, 3, 4]).encode(),
+            ));
+            let runtime_obj = RuntimeRegistry::<Test>::get(0).unwrap();
+            assert_eq!(
+                Domains::runtime_summaries(),
+                vec![RuntimeSummary {
+                    runtime_id: 0,
+                    runtime_name: "evm".to_owned(),
+                    runtime_type: RuntimeType::Evm,
+                    version: version.clone(),
+                    code_hash: runtime_obj.hash,
+                    runtime_upgrades: 0,
+                    created_at: runtime_obj.created_at,
+                    updated_at: runtime_obj.created_at,
+                }]
+            );
+        });
+
+        version.spec_version = 2;
+        ext.register_extension(sp_core::traits::ReadRuntimeVersionExt::new(
+            ReadRuntimeVersion(version.encode()),
+        ));
+
+        ext.execute_with(|| {
+            assert_ok!(crate::Pallet::<Test>::upgrade_domain_runtime(
+                RawOrigin::Root.into(),
+                0,
+                RawGenesis::dummy(vec![6, 7, 8, 9]).encode(),
+                None,
+            ));
+            let scheduled_block_number = frame_system::Pallet::<Test>::current_block_number()
+                .checked_add(DomainRuntimeUpgradeDelay::get())
+                .unwrap();
+            go_to_block(scheduled_block_number);
+            let runtime_obj = RuntimeRegistry::<Test>::get(0).unwrap();
+
+            // The summary is read without decoding the runtime object
+            frame_support::storage::unhashed::put_raw(
+                &RuntimeRegistry::<Test>::hashed_key_for(0),
+                &[0xff; 4],
+            );
+            assert!(RuntimeRegistry::<Test>::get(0).is_none());
+
+            let summary = Domains::runtime_summary(0).unwrap();
+            assert_eq!(summary.version, version);
+            assert_eq!(summary.code_hash, runtime_obj.hash);
+            assert_eq!(summary.runtime_upgrades, 1);
+            assert_eq!(summary.updated_at, scheduled_block_number);
+            assert_ne!(summary.updated_at, summary.created_at);
+
+            assert_eq!(Domains::runtime_summaries(), vec![summary]);
+            assert_eq!(Domains::runtime_summary(1), None);
+        });
+    }
 }
@@ -5,24 +5,33 @@ extern crate alloc;
 
 use crate::bundle_storage_fund::{self, deposit_reserve_for_storage_fund};
 use crate::pallet::{
-    Deposits, DomainRegistry, DomainStakingSummary, LatestSubmittedER, NextOperatorId,
-    NominatorCount, OperatorIdOwner, OperatorSigningKey, Operators, PendingOperatorSwitches,
-    PendingSlashes, PendingStakingOperationCount, Withdrawals,
+    DeferredSlashes, Deposits, DomainRegistry, DomainStakingSummary, EpochSummary,
+    HeadReceiptNumber, LastRejectedBundleSlot, LatestSubmittedER, NextOperatorId, NominatorCount,
+    OperatorIdOwner, OperatorRejectedBundles, OperatorSigningKey, OperatorSlashEvidence, Operators,
+    PendingOperatorSwitches, PendingSlashes, PendingStakingOperationCount,
+    RegisteredOperatorMetadata, RejectedBundleCount, SlashEvidenceExpiry, Withdrawals,
 };
 use crate::staking_epoch::mint_funds;
 use crate::{
     BalanceOf, Config, DomainBlockNumberFor, Event, HoldIdentifier, NominatorId,
-    OperatorEpochSharePrice, Pallet, ReceiptHashFor, SlashedReason,
+    OperatorEpochSharePrice, OperatorEpochTaxPerShare, OperatorTaxPerShare, Pallet, ReceiptHashFor,
+    SlashEvidenceOf, SlashedReason, TaxPaid,
 };
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
 use codec::{Decode, Encode};
-use frame_support::traits::fungible::{Inspect, InspectHold, MutateHold};
-use frame_support::traits::tokens::{Fortitude, Precision, Preservation};
+use frame_support::traits::fungible::{Inspect, InspectHold, Mutate, MutateHold};
+use frame_support::traits::tokens::{Fortitude, Precision, Preservation, Provenance};
 use frame_support::{ensure, PalletError};
+use frame_system::pallet_prelude::BlockNumberFor;
 use scale_info::TypeInfo;
 use sp_core::Get;
-use sp_domains::{DomainId, EpochIndex, OperatorId, OperatorPublicKey, ZERO_OPERATOR_SIGNING_KEY};
-use sp_runtime::traits::{CheckedAdd, CheckedSub, One, Zero};
-use sp_runtime::{Perbill, Percent, Saturating};
+use sp_domains::{
+    DomainId, EpochIndex, OperatorId, OperatorMetadata, OperatorPublicKey,
+    ZERO_OPERATOR_SIGNING_KEY,
+};
+use sp_runtime::traits::{BlockNumberProvider, CheckedAdd, CheckedSub, One, Zero};
+use sp_runtime::{FixedPointNumber, FixedU128, Perbill, Percent, SaturatedConversion, Saturating};
 use sp_std::collections::btree_map::BTreeMap;
 use sp_std::collections::btree_set::BTreeSet;
 use sp_std::collections::vec_deque::VecDeque;
@@ -115,21 +124,25 @@ impl<Balance: Copy + CheckedAdd> PendingDeposit<Balance> {
 
 /// A nominator's withdrawal from a given operator pool.
 #[derive(TypeInfo, Debug, Encode, Decode, Clone, PartialEq, Eq, Default)]
-pub(crate) struct Withdrawal<Balance, Share, DomainBlockNumber> {
+pub(crate) struct Withdrawal<Balance, Share, DomainBlockNumber, ConsensusBlockNumber> {
     /// Total withdrawal amount requested by the nominator that are in unlocking state excluding withdrawal
     /// in shares and the storage fee
     pub(crate) total_withdrawal_amount: Balance,
     /// Individual withdrawal amounts with their unlocking block for a given domain
-    pub(crate) withdrawals: VecDeque<WithdrawalInBalance<DomainBlockNumber, Balance>>,
+    pub(crate) withdrawals:
+        VecDeque<WithdrawalInBalance<DomainBlockNumber, ConsensusBlockNumber, Balance>>,
     /// Withdrawal that was initiated by nominator and not converted to balance due to
     /// unfinished domain epoch.
     pub(crate) withdrawal_in_shares: Option<WithdrawalInShares<DomainBlockNumber, Share, Balance>>,
 }
 
 #[derive(TypeInfo, Debug, Encode, Decode, Clone, PartialEq, Eq)]
-pub(crate) struct WithdrawalInBalance<DomainBlockNumber, Balance> {
+pub(crate) struct WithdrawalInBalance<DomainBlockNumber, ConsensusBlockNumber, Balance> {
     pub(crate) domain_id: DomainId,
     pub(crate) unlock_at_confirmed_domain_block_number: DomainBlockNumber,
+    /// The alternate unlock schedule on the consensus clock, recorded once the nominator tries to
+    /// unlock the withdrawal while its domain is frozen and kept even if the domain resumes later.
+    pub(crate) unlock_at_consensus_block_number: Option<ConsensusBlockNumber>,
     pub(crate) amount_to_unlock: Balance,
     pub(crate) storage_fee_refund: Balance,
 }
@@ -142,36 +155,87 @@ pub(crate) struct WithdrawalInShares<DomainBlockNumber, Share, Balance> {
     pub(crate) storage_fee_refund: Balance,
 }
 
+/// Summary of the staking changes of an operator finalized at the end of a domain epoch.
+#[derive(TypeInfo, Debug, Encode, Decode, Clone, PartialEq, Eq)]
+pub struct OperatorEpochSummary<Balance, Share> {
+    /// Total stake of the operator pool after the epoch is finalized.
+    pub total_stake: Balance,
+    /// Total shares of the operator pool after the epoch is finalized.
+    pub total_shares: Share,
+    /// Share price of the operator pool at the end of the epoch.
+    pub share_price: SharePrice,
+    /// Total deposits of the epoch converted into shares.
+    pub deposits_converted: Balance,
+    /// Total stake of the shares withdrawn in the epoch.
+    pub withdrawals_finalized: Balance,
+}
+
+/// The operator tax paid by a nominator, the tax of each epoch is attributed to the nominators
+/// pro-rata to the shares they held during the epoch.
+#[derive(TypeInfo, Debug, Encode, Decode, Clone, PartialEq, Eq, Default)]
+pub(crate) struct NominatorTax<Balance, Share> {
+    /// Tax paid by the nominator up to `tax_per_share`.
+    pub(crate) paid: Balance,
+    /// The accumulated tax per share of the operator when `paid` was last settled.
+    pub(crate) tax_per_share: FixedU128,
+    /// Shares withdrawn in the given epoch, which still bear the tax of the epoch, along with the
+    /// accumulated tax per share of the operator when they were withdrawn.
+    pub(crate) withdrawn: Option<(DomainEpoch, Share, FixedU128)>,
+}
+
 #[derive(TypeInfo, Debug, Encode, Decode, Clone, PartialEq, Eq)]
-pub struct OperatorDeregisteredInfo<DomainBlockNumber> {
+pub struct OperatorDeregisteredInfo<DomainBlockNumber, ConsensusBlockNumber> {
     pub domain_epoch: DomainEpoch,
     pub unlock_at_confirmed_domain_block_number: DomainBlockNumber,
+    /// The alternate unlock schedule on the consensus clock, recorded once the operator is
+    /// deregistered or unlocked while its domain is frozen. The operator can be unlocked at this
+    /// consensus block even if the domain resumes later.
+    pub unlock_at_consensus_block_number: Option<ConsensusBlockNumber>,
 }
 
-impl<DomainBlockNumber> From<(DomainId, EpochIndex, DomainBlockNumber)>
-    for OperatorDeregisteredInfo<DomainBlockNumber>
+impl<DomainBlockNumber, ConsensusBlockNumber> From<(DomainId, EpochIndex, DomainBlockNumber)>
+    for OperatorDeregisteredInfo<DomainBlockNumber, ConsensusBlockNumber>
 {
     fn from(value: (DomainId, EpochIndex, DomainBlockNumber)) -> Self {
         OperatorDeregisteredInfo {
             domain_epoch: (value.0, value.1).into(),
             unlock_at_confirmed_domain_block_number: value.2,
+            unlock_at_consensus_block_number: None,
         }
     }
 }
 
+/// Type that represents a slash whose finalization is deferred by `SlashDeferDuration` epochs.
+#[derive(TypeInfo, Debug, Encode, Decode, Clone, PartialEq, Eq)]
+pub struct DeferredSlash<DomainBlockNumber, ConsensusBlockNumber> {
+    /// The slash is finalized at the end of this epoch of the operator's domain.
+    pub finalize_at_epoch: EpochIndex,
+    /// The operator status before the slash, restored if the slash is cancelled.
+    pub previous_status: OperatorStatus<DomainBlockNumber, ConsensusBlockNumber>,
+}
+
+/// Type that represents the metadata of an operator along with the deposit held for storing it.
+#[derive(TypeInfo, Debug, Encode, Decode, Clone, PartialEq, Eq)]
+pub struct OperatorMetadataEntry<AccountId, Balance> {
+    pub metadata: OperatorMetadata,
+    /// Account the deposit is held from, released to it once the metadata is removed.
+    pub depositor: AccountId,
+    pub deposit: Balance,
+}
+
 /// Type that represents an operator status.
 #[derive(TypeInfo, Debug, Encode, Decode, Clone, PartialEq, Eq)]
-pub enum OperatorStatus<DomainBlockNumber> {
+pub enum OperatorStatus<DomainBlockNumber, ConsensusBlockNumber> {
     Registered,
     /// De-registered at given domain epoch.
-    Deregistered(OperatorDeregisteredInfo<DomainBlockNumber>),
+    Deregistered(OperatorDeregisteredInfo<DomainBlockNumber, ConsensusBlockNumber>),
     Slashed,
     PendingSlash,
 }
 
 /// Type that represents an operator details.
 #[derive(TypeInfo, Debug, Encode, Decode, Clone, PartialEq, Eq)]
-pub struct Operator<Balance, Share, DomainBlockNumber> {
+pub struct Operator<Balance, Share, DomainBlockNumber, ConsensusBlockNumber> {
     pub signing_key: OperatorPublicKey,
     pub current_domain_id: DomainId,
     pub next_domain_id: DomainId,
@@ -187,17 +251,27 @@ pub struct Operator<Balance, Share, DomainBlockNumber> {
     /// not assigned to this field directlt, thus MUST use the `status()` method to query the status
     /// instead.
     /// TODO: update the filed to `_status` to avoid accidental access in next network reset
-    status: OperatorStatus<DomainBlockNumber>,
+    status: OperatorStatus<DomainBlockNumber, ConsensusBlockNumber>,
     /// Total deposits during the previous epoch
     pub deposits_in_epoch: Balance,
     /// Total withdrew shares during the previous epoch
     pub withdrawals_in_epoch: Share,
     /// Total balance deposited to the bundle storage fund
     pub total_storage_fee_deposit: Balance,
+    /// Whether the operator voluntarily paused bundle production.
+    ///
+    /// Paused operator is not elected starting from the next epoch of its domain until resumed,
+    /// while deposits and withdrawals keep being processed as usual.
+    pub paused: bool,
 }
 
-impl<Balance, Share, DomainBlockNumber> Operator<Balance, Share, DomainBlockNumber> {
-    pub fn status<T: Config>(&self, operator_id: OperatorId) -> &OperatorStatus<DomainBlockNumber> {
+impl<Balance, Share, DomainBlockNumber, ConsensusBlockNumber>
+    Operator<Balance, Share, DomainBlockNumber, ConsensusBlockNumber>
+{
+    pub fn status<T: Config>(
+        &self,
+        operator_id: OperatorId,
+    ) -> &OperatorStatus<DomainBlockNumber, ConsensusBlockNumber> {
         if matches!(self.status, OperatorStatus::Slashed) {
             &OperatorStatus::Slashed
         } else if Pallet::<T>::is_operator_pending_to_slash(self.current_domain_id, operator_id) {
@@ -207,13 +281,18 @@ impl<Balance, Share, DomainBlockNumber> Operator<Balance, Share, DomainBlockNumb
         }
     }
 
-    pub fn update_status(&mut self, new_status: OperatorStatus<DomainBlockNumber>) {
+    pub fn update_status(
+        &mut self,
+        new_status: OperatorStatus<DomainBlockNumber, ConsensusBlockNumber>,
+    ) {
         self.status = new_status;
     }
 }
 
 #[cfg(test)]
-impl<Balance: Zero, Share: Zero, DomainBlockNumber> Operator<Balance, Share, DomainBlockNumber> {
+impl<Balance: Zero, Share: Zero, DomainBlockNumber, ConsensusBlockNumber>
+    Operator<Balance, Share, DomainBlockNumber, ConsensusBlockNumber>
+{
     pub(crate) fn dummy(
         domain_id: DomainId,
         signing_key: OperatorPublicKey,
@@ -232,6 +311,7 @@ impl<Balance: Zero, Share: Zero, DomainBlockNumber> Operator<Balance, Share, Dom
             deposits_in_epoch: Zero::zero(),
             withdrawals_in_epoch: Zero::zero(),
             total_storage_fee_deposit: Zero::zero(),
+            paused: false,
         }
     }
 }
@@ -293,6 +373,15 @@ pub enum Error {
     OperatorNotDeregistered,
     BundleStorageFund(bundle_storage_fund::Error),
     UnconfirmedER,
+    InsufficientFreeBalanceBuffer,
+    OperatorAlreadyPaused,
+    OperatorNotPaused,
+    SlashNotDeferred,
+    TransferToBeneficiary,
+    EmptyOperatorName,
+    OperatorNameTooLong,
+    OperatorWebsiteTooLong,
+    MissingOperatorMetadata,
 }
 
 // Increase `PendingStakingOperationCount` by one and check if the `MaxPendingStakingOperation`
@@ -315,6 +404,7 @@ pub(crate) fn do_register_operator<T: Config>(
     domain_id: DomainId,
     amount: BalanceOf<T>,
     config: OperatorConfig<BalanceOf<T>>,
+    allow_death: bool,
 ) -> Result<(OperatorId, EpochIndex), Error> {
     note_pending_staking_operation::<T>(domain_id)?;
 
@@ -355,6 +445,8 @@ pub(crate) fn do_register_operator<T: Config>(
             Error::MinimumOperatorStake
         );
 
+        ensure_free_balance_buffer::<T>(&operator_owner, amount, allow_death)?;
+
         let new_deposit =
             deposit_reserve_for_storage_fund::<T>(operator_id, &operator_owner, amount)
                 .map_err(Error::BundleStorageFund)?;
@@ -385,6 +477,7 @@ pub(crate) fn do_register_operator<T: Config>(
             deposits_in_epoch: new_deposit.staking,
             withdrawals_in_epoch: Zero::zero(),
             total_storage_fee_deposit: new_deposit.storage_fee_deposit,
+            paused: false,
         };
         Operators::<T>::insert(operator_id, operator);
         OperatorSigningKey::<T>::insert(signing_key, operator_id);
@@ -423,8 +516,11 @@ pub(crate) fn do_calculate_previous_epoch_deposit_shares_and_add_new_deposit<T:
     current_domain_epoch: DomainEpoch,
     new_deposit: NewDeposit<BalanceOf<T>>,
 ) -> Result<DepositInfo<BalanceOf<T>>, Error> {
-    Deposits::<T>::try_mutate(operator_id, nominator_id, |maybe_deposit| {
+    Deposits::<T>::try_mutate(operator_id, nominator_id.clone(), |maybe_deposit| {
         let mut deposit = maybe_deposit.take().unwrap_or_default();
+        TaxPaid::<T>::mutate(operator_id, nominator_id, |tax| {
+            *tax = settle_nominator_tax::<T>(operator_id, tax.clone(), Some(&deposit))
+        });
         do_convert_previous_epoch_deposits::<T>(operator_id, &mut deposit)?;
 
         // add or create new pending deposit
@@ -511,7 +607,7 @@ pub(crate) fn do_convert_previous_epoch_deposits<T: Config>(
 /// If there is no share price available, this will be no-op
 pub(crate) fn do_convert_previous_epoch_withdrawal<T: Config>(
     operator_id: OperatorId,
-    withdrawal: &mut Withdrawal<BalanceOf<T>, T::Share, DomainBlockNumberFor<T>>,
+    withdrawal: &mut Withdrawal<BalanceOf<T>, T::Share, DomainBlockNumberFor<T>, BlockNumberFor<T>>,
 ) -> Result<(), Error> {
     let epoch_share_price = match withdrawal
         .withdrawal_in_shares
@@ -539,6 +635,7 @@ pub(crate) fn do_convert_previous_epoch_withdrawal<T: Config>(
         let withdraw_in_balance = WithdrawalInBalance {
             domain_id,
             unlock_at_confirmed_domain_block_number,
+            unlock_at_consensus_block_number: None,
             amount_to_unlock: withdrawal_amount,
             storage_fee_refund,
         };
@@ -548,10 +645,82 @@ pub(crate) fn do_convert_previous_epoch_withdrawal<T: Config>(
     Ok(())
 }
 
+/// Returns the tax borne by `shares` while the accumulated tax per share of the operator grew
+/// from `from` to `to`.
+fn tax_of_shares<T: Config>(shares: T::Share, from: FixedU128, to: FixedU128) -> BalanceOf<T> {
+    to.saturating_sub(from)
+        .saturating_mul_int(shares.saturated_into::<u128>())
+        .saturated_into()
+}
+
+/// Settles the operator tax paid by the nominator since `tax` was last settled.
+///
+/// The pending deposit is accounted as converted to shares if its epoch share price is available,
+/// so this must be followed by `do_convert_previous_epoch_deposits` whenever the result is stored.
+pub(crate) fn settle_nominator_tax<T: Config>(
+    operator_id: OperatorId,
+    tax: NominatorTax<BalanceOf<T>, T::Share>,
+    maybe_deposit: Option<&Deposit<T::Share, BalanceOf<T>>>,
+) -> NominatorTax<BalanceOf<T>, T::Share> {
+    let NominatorTax {
+        mut paid,
+        tax_per_share: last_tax_per_share,
+        mut withdrawn,
+    } = tax;
+    let tax_per_share = OperatorTaxPerShare::<T>::get(operator_id);
+
+    if let Some(deposit) = maybe_deposit {
+        paid = paid.saturating_add(tax_of_shares::<T>(
+            deposit.known.shares,
+            last_tax_per_share,
+            tax_per_share,
+        ));
+
+        // the shares of the pending deposit bear the tax since the end of its epoch
+        if let Some(pending_deposit) = deposit.pending
+            && let Some(share_price) = OperatorEpochSharePrice::<T>::get(
+                operator_id,
+                pending_deposit.effective_domain_epoch,
+            )
+        {
+            let deposit_tax_per_share = OperatorEpochTaxPerShare::<T>::get(
+                operator_id,
+                pending_deposit.effective_domain_epoch,
+            )
+            .unwrap_or(tax_per_share);
+            paid = paid.saturating_add(tax_of_shares::<T>(
+                share_price.stake_to_shares::<T>(pending_deposit.amount),
+                deposit_tax_per_share,
+                tax_per_share,
+            ));
+        }
+    }
+
+    // the withdrawn shares bear the tax until the end of the epoch they are withdrawn in
+    if let Some((domain_epoch, shares, withdrawn_tax_per_share)) = withdrawn
+        && let Some(epoch_tax_per_share) =
+            OperatorEpochTaxPerShare::<T>::get(operator_id, domain_epoch)
+    {
+        paid = paid.saturating_add(tax_of_shares::<T>(
+            shares,
+            withdrawn_tax_per_share,
+            epoch_tax_per_share,
+        ));
+        withdrawn = None;
+    }
+
+    NominatorTax {
+        paid,
+        tax_per_share,
+        withdrawn,
+    }
+}
+
 pub(crate) fn do_nominate_operator<T: Config>(
     operator_id: OperatorId,
     nominator_id: T::AccountId,
     amount: BalanceOf<T>,
+    allow_death: bool,
 ) -> Result<(), Error> {
     Operators::<T>::try_mutate(operator_id, |maybe_operator| {
         let operator = maybe_operator.as_mut().ok_or(Error::UnknownOperator)?;
@@ -569,6 +738,8 @@ pub(crate) fn do_nominate_operator<T: Config>(
         let domain_stake_summary = DomainStakingSummary::<T>::get(operator.current_domain_id)
             .ok_or(Error::DomainNotInitialized)?;
 
+        ensure_free_balance_buffer::<T>(&nominator_id, amount, allow_death)?;
+
         // Reserve for the bundle storage fund
         let new_deposit = deposit_reserve_for_storage_fund::<T>(operator_id, &nominator_id, amount)
             .map_err(Error::BundleStorageFund)?;
@@ -627,6 +798,30 @@ pub(crate) fn do_nominate_operator<T: Config>(
     })
 }
 
+/// Ensure `who` still has at least `NominationKeepAlive` of free balance left, on top of the
+/// existential deposit, after `amount` is deposited, unless `allow_death` is set.
+fn ensure_free_balance_buffer<T: Config>(
+    who: &T::AccountId,
+    amount: BalanceOf<T>,
+    allow_death: bool,
+) -> Result<(), Error> {
+    if allow_death {
+        return Ok(());
+    }
+
+    let reducible_balance =
+        T::Currency::reducible_balance(who, Preservation::Preserve, Fortitude::Polite);
+    let free_balance_left = reducible_balance
+        .checked_sub(&amount)
+        .ok_or(Error::InsufficientBalance)?;
+    ensure!(
+        free_balance_left >= T::NominationKeepAlive::get(),
+        Error::InsufficientFreeBalanceBuffer
+    );
+
+    Ok(())
+}
+
 pub(crate) fn hold_deposit<T: Config>(
     who: &T::AccountId,
     operator_id: OperatorId,
@@ -749,12 +944,19 @@ pub(crate) fn do_deregister_operator<T: Config>(
                 let unlock_operator_at_domain_block_number = latest_confirmed_domain_block_number
                     .checked_add(&T::StakeWithdrawalLockingPeriod::get())
                     .ok_or(Error::BlockNumberOverflow)?;
-                let operator_deregister_info = (
-                    operator.current_domain_id,
-                    stake_summary.current_epoch_index,
-                    unlock_operator_at_domain_block_number,
-                )
-                    .into();
+
+                let unlock_operator_at_consensus_block_number =
+                    consensus_clock_unlock_at::<T>(operator.current_domain_id)?;
+
+                let operator_deregister_info = OperatorDeregisteredInfo {
+                    domain_epoch: (
+                        operator.current_domain_id,
+                        stake_summary.current_epoch_index,
+                    )
+                        .into(),
+                    unlock_at_confirmed_domain_block_number: unlock_operator_at_domain_block_number,
+                    unlock_at_consensus_block_number: unlock_operator_at_consensus_block_number,
+                };
 
                 operator.update_status(OperatorStatus::Deregistered(operator_deregister_info));
 
@@ -765,6 +967,156 @@ pub(crate) fn do_deregister_operator<T: Config>(
     })
 }
 
+/// Converts the number of domain blocks to the same number of consensus blocks.
+fn to_consensus_block_count<T: Config>(
+    domain_block_count: DomainBlockNumberFor<T>,
+) -> BlockNumberFor<T> {
+    domain_block_count.saturated_into::<u32>().into()
+}
+
+/// Returns the consensus block at which the stake locked on the domain is unlocked on the
+/// consensus clock, `None` if the domain is not frozen.
+///
+/// The confirmed domain block of a frozen domain doesn't advance, so `StakeWithdrawalLockingPeriod`
+/// is counted in consensus blocks instead. The pending ERs of the domain can still be challenged
+/// until they are confirmed, so the stake stays locked for at least their remaining challenge
+/// period. The result is recorded once, the caller must not re-evaluate it later.
+fn consensus_clock_unlock_at<T: Config>(
+    domain_id: DomainId,
+) -> Result<Option<BlockNumberFor<T>>, Error> {
+    if Pallet::<T>::domain_frozen_at(domain_id).is_none() {
+        return Ok(None);
+    }
+
+    let remaining_challenge_period = HeadReceiptNumber::<T>::get(domain_id)
+        .saturating_add(T::BlockTreePruningDepth::get())
+        .saturating_sub(Pallet::<T>::latest_confirmed_domain_block_number(domain_id));
+    let locking_period = T::StakeWithdrawalLockingPeriod::get().max(remaining_challenge_period);
+    frame_system::Pallet::<T>::current_block_number()
+        .checked_add(&to_consensus_block_count::<T>(locking_period))
+        .map(Some)
+        .ok_or(Error::BlockNumberOverflow)
+}
+
+/// The outcome of unlocking a withdrawal or a deregistered operator.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum UnlockOutcome<Unlocked, ConsensusBlockNumber> {
+    /// The stake is unlocked.
+    Unlocked(Unlocked),
+    /// The stake is still locked on the frozen domain, it is unlocked on the consensus clock at
+    /// the given consensus block instead.
+    Scheduled(ConsensusBlockNumber),
+}
+
+/// Pause or resume bundle production of the operator, the change of the election takes effect at
+/// the next epoch transition of its domain, see [`Operator::paused`].
+pub(crate) fn do_set_operator_paused<T: Config>(
+    operator_owner: T::AccountId,
+    operator_id: OperatorId,
+    paused: bool,
+) -> Result<(), Error> {
+    ensure!(
+        OperatorIdOwner::<T>::get(operator_id) == Some(operator_owner),
+        Error::NotOperatorOwner
+    );
+
+    Operators::<T>::try_mutate(operator_id, |maybe_operator| {
+        let operator = maybe_operator.as_mut().ok_or(Error::UnknownOperator)?;
+
+        ensure!(
+            *operator.status::<T>(operator_id) == OperatorStatus::Registered,
+            Error::OperatorNotRegistered
+        );
+
+        if operator.paused == paused {
+            return Err(if paused {
+                Error::OperatorAlreadyPaused
+            } else {
+                Error::OperatorNotPaused
+            });
+        }
+
+        operator.paused = paused;
+        Ok(())
+    })
+}
+
+pub(crate) fn do_set_operator_metadata<T: Config>(
+    operator_owner: T::AccountId,
+    operator_id: OperatorId,
+    name: Vec<u8>,
+    website: Option<Vec<u8>>,
+) -> Result<(), Error> {
+    ensure!(
+        OperatorIdOwner::<T>::get(operator_id).as_ref() == Some(&operator_owner),
+        Error::NotOperatorOwner
+    );
+
+    let operator = Operators::<T>::get(operator_id).ok_or(Error::UnknownOperator)?;
+    ensure!(
+        *operator.status::<T>(operator_id) == OperatorStatus::Registered,
+        Error::OperatorNotRegistered
+    );
+
+    ensure!(!name.is_empty(), Error::EmptyOperatorName);
+    let metadata = OperatorMetadata {
+        name: name.try_into().map_err(|_| Error::OperatorNameTooLong)?,
+        website: website
+            .map(TryInto::try_into)
+            .transpose()
+            .map_err(|_| Error::OperatorWebsiteTooLong)?,
+    };
+
+    RegisteredOperatorMetadata::<T>::try_mutate(operator_id, |maybe_entry| {
+        match maybe_entry {
+            // the deposit is held per entry, updating the metadata does not hold more
+            Some(entry) => entry.metadata = metadata,
+            None => {
+                let deposit = T::OperatorMetadataDeposit::get();
+                let hold_id = T::HoldIdentifier::operator_metadata_deposit(operator_id);
+                T::Currency::hold(&hold_id, &operator_owner, deposit)
+                    .map_err(|_| Error::BalanceFreeze)?;
+
+                *maybe_entry = Some(OperatorMetadataEntry {
+                    metadata,
+                    depositor: operator_owner,
+                    deposit,
+                });
+            }
+        }
+
+        Ok(())
+    })
+}
+
+pub(crate) fn do_clear_operator_metadata<T: Config>(
+    operator_owner: T::AccountId,
+    operator_id: OperatorId,
+) -> Result<(), Error> {
+    ensure!(
+        OperatorIdOwner::<T>::get(operator_id) == Some(operator_owner),
+        Error::NotOperatorOwner
+    );
+
+    ensure!(
+        RegisteredOperatorMetadata::<T>::contains_key(operator_id),
+        Error::MissingOperatorMetadata
+    );
+
+    remove_operator_metadata::<T>(operator_id)
+}
+
+/// Removes the metadata of the operator, if any, and releases the deposit held for it.
+pub(crate) fn remove_operator_metadata<T: Config>(operator_id: OperatorId) -> Result<(), Error> {
+    if let Some(entry) = RegisteredOperatorMetadata::<T>::take(operator_id) {
+        let hold_id = T::HoldIdentifier::operator_metadata_deposit(operator_id);
+        T::Currency::release(&hold_id, &entry.depositor, entry.deposit, Precision::Exact)
+            .map_err(|_| Error::RemoveLock)?;
+    }
+
+    Ok(())
+}
+
 pub(crate) fn do_withdraw_stake<T: Config>(
     operator_id: OperatorId,
     nominator_id: NominatorId<T>,
@@ -795,6 +1147,9 @@ pub(crate) fn do_withdraw_stake<T: Config>(
 
         Deposits::<T>::try_mutate(operator_id, nominator_id.clone(), |maybe_deposit| {
             let deposit = maybe_deposit.as_mut().ok_or(Error::InsufficientShares)?;
+            TaxPaid::<T>::mutate(operator_id, nominator_id.clone(), |tax| {
+                *tax = settle_nominator_tax::<T>(operator_id, tax.clone(), Some(deposit))
+            });
             do_convert_previous_epoch_deposits::<T>(operator_id, deposit)?;
             Ok(())
         })?;
@@ -907,6 +1262,23 @@ pub(crate) fn do_withdraw_stake<T: Config>(
                 .ok_or(Error::ShareOverflow)?;
 
             deposit.known.shares = remaining_shares;
+
+            // the withdrawn shares still bear the tax of the current epoch
+            TaxPaid::<T>::mutate(operator_id, nominator_id.clone(), |tax| {
+                tax.withdrawn = Some(match tax.withdrawn {
+                    Some((domain_epoch, shares, withdrawn_tax_per_share))
+                        if domain_epoch == domain_current_epoch =>
+                    {
+                        (
+                            domain_epoch,
+                            shares.saturating_add(shares_withdrew),
+                            withdrawn_tax_per_share,
+                        )
+                    }
+                    _ => (domain_current_epoch, shares_withdrew, tax.tax_per_share),
+                });
+            });
+
             if remaining_shares.is_zero() {
                 if let Some(pending_deposit) = deposit.pending {
                     // if there is a pending deposit, then ensure
@@ -965,11 +1337,18 @@ pub(crate) fn do_withdraw_stake<T: Config>(
     })
 }
 
-/// Unlocks any withdraws that are ready to be unlocked.
+/// Unlocks the first withdrawal of the nominator, the unlocked funds are transferred to the
+/// `beneficiary` if specified, otherwise they are kept in the nominator account.
+///
+/// If the withdrawal is not unlocked yet while the domain is frozen, the withdrawals of the
+/// nominator are scheduled on the consensus clock instead, see [`consensus_clock_unlock_at`].
+/// Withdrawals still held in shares can't be unlocked this way, as they are only converted to
+/// balance when an epoch completes.
 pub(crate) fn do_unlock_funds<T: Config>(
     operator_id: OperatorId,
     nominator_id: NominatorId<T>,
-) -> Result<BalanceOf<T>, Error> {
+    beneficiary: Option<T::AccountId>,
+) -> Result<UnlockOutcome<BalanceOf<T>, BlockNumberFor<T>>, Error> {
     let operator = Operators::<T>::get(operator_id).ok_or(Error::UnknownOperator)?;
     ensure!(
         *operator.status::<T>(operator_id) == OperatorStatus::Registered,
@@ -979,22 +1358,63 @@ pub(crate) fn do_unlock_funds<T: Config>(
     Withdrawals::<T>::try_mutate_exists(operator_id, nominator_id.clone(), |maybe_withdrawal| {
         let withdrawal = maybe_withdrawal.as_mut().ok_or(Error::MissingWithdrawal)?;
         do_convert_previous_epoch_withdrawal::<T>(operator_id, withdrawal)?;
+        let (domain_id, unlock_at_confirmed_domain_block_number, unlock_at_consensus_block_number) =
+            withdrawal
+                .withdrawals
+                .front()
+                .map(|withdrawal_in_balance| {
+                    (
+                        withdrawal_in_balance.domain_id,
+                        withdrawal_in_balance.unlock_at_confirmed_domain_block_number,
+                        withdrawal_in_balance.unlock_at_consensus_block_number,
+                    )
+                })
+                .ok_or(Error::MissingWithdrawal)?;
+
+        let latest_confirmed_block_number =
+            Pallet::<T>::latest_confirmed_domain_block_number(domain_id);
+        let current_block_number = frame_system::Pallet::<T>::current_block_number();
+        if unlock_at_confirmed_domain_block_number > latest_confirmed_block_number
+            && !unlock_at_consensus_block_number
+                .is_some_and(|unlock_at| unlock_at <= current_block_number)
+        {
+            // The withdrawals are scheduled on the consensus clock only once, the schedule is
+            // checked as is afterwards
+            let unlock_at = match unlock_at_consensus_block_number {
+                Some(_) => None,
+                None => consensus_clock_unlock_at::<T>(domain_id)?,
+            }
+            .ok_or(Error::UnlockPeriodNotComplete)?;
+            for withdrawal_in_balance in withdrawal.withdrawals.iter_mut() {
+                if withdrawal_in_balance.domain_id == domain_id {
+                    withdrawal_in_balance
+                        .unlock_at_consensus_block_number
+                        .get_or_insert(unlock_at);
+                }
+            }
+            return Ok(UnlockOutcome::Scheduled(unlock_at));
+        }
+
         let WithdrawalInBalance {
-            domain_id,
-            unlock_at_confirmed_domain_block_number,
             amount_to_unlock,
             storage_fee_refund,
+            ..
         } = withdrawal
             .withdrawals
             .pop_front()
             .ok_or(Error::MissingWithdrawal)?;
 
-        let latest_confirmed_block_number =
-            Pallet::<T>::latest_confirmed_domain_block_number(domain_id);
-        ensure!(
-            unlock_at_confirmed_domain_block_number <= latest_confirmed_block_number,
-            Error::UnlockPeriodNotComplete
-        );
+        // the beneficiary must be able to receive the unlocked funds, this is checked before any
+        // fund is released so the nominator can just retry without the beneficiary
+        let beneficiary = beneficiary.filter(|beneficiary| *beneficiary != nominator_id);
+        let amount_to_transfer = amount_to_unlock
+            .checked_add(&storage_fee_refund)
+            .ok_or(Error::BalanceOverflow)?;
+        if let Some(beneficiary) = &beneficiary {
+            T::Currency::can_deposit(beneficiary, amount_to_transfer, Provenance::Extant)
+                .into_result()
+                .map_err(|_| Error::TransferToBeneficiary)?;
+        }
 
         // deduct the amount unlocked from total
         withdrawal.total_withdrawal_amount = withdrawal
@@ -1035,46 +1455,90 @@ pub(crate) fn do_unlock_funds<T: Config>(
         )
         .map_err(|_| Error::RemoveLock)?;
 
+        // Transfer the released stake and storage fee refund to the beneficiary
+        if let Some(beneficiary) = beneficiary {
+            T::Currency::transfer(
+                &nominator_id,
+                &beneficiary,
+                amount_to_transfer,
+                Preservation::Expendable,
+            )
+            .map_err(|_| Error::TransferToBeneficiary)?;
+        }
+
         // if there are no withdrawals, then delete the storage as well
         if withdrawal.withdrawals.is_empty() && withdrawal.withdrawal_in_shares.is_none() {
             *maybe_withdrawal = None;
             // if there is no deposit or pending deposits, then clean up the deposit state as well
-            Deposits::<T>::mutate_exists(operator_id, nominator_id, |maybe_deposit| {
+            // along with the tax paid since the nominator fully exited
+            Deposits::<T>::mutate_exists(operator_id, nominator_id.clone(), |maybe_deposit| {
                 if let Some(deposit) = maybe_deposit
                     && deposit.known.shares.is_zero()
                     && deposit.pending.is_none()
                 {
-                    *maybe_deposit = None
+                    *maybe_deposit = None;
+                    TaxPaid::<T>::remove(operator_id, nominator_id);
                 }
             });
         }
 
-        Ok(amount_to_unlock)
+        Ok(UnlockOutcome::Unlocked(amount_to_unlock))
     })
 }
 
+/// Removes the operator tax accounting of the operator along with the tax paid by its nominators.
+pub(crate) fn remove_operator_tax<T: Config>(operator_id: OperatorId) {
+    OperatorTaxPerShare::<T>::remove(operator_id);
+    let _ = OperatorEpochTaxPerShare::<T>::clear_prefix(operator_id, u32::MAX, None);
+    let _ = TaxPaid::<T>::clear_prefix(operator_id, u32::MAX, None);
+}
+
 /// Unlocks an already de-registered operator given unlock wait period is complete.
 ///
+/// If the operator is not unlocked yet while its domain is frozen, the operator is scheduled on
+/// the consensus clock instead, see [`consensus_clock_unlock_at`].
+///
 /// Return the number of nominator processed
-pub(crate) fn do_unlock_operator<T: Config>(operator_id: OperatorId) -> Result<usize, Error> {
+pub(crate) fn do_unlock_operator<T: Config>(
+    operator_id: OperatorId,
+) -> Result<UnlockOutcome<usize, BlockNumberFor<T>>, Error> {
     Operators::<T>::try_mutate_exists(operator_id, |maybe_operator| {
         // take the operator so this operator info is removed once we unlock the operator.
-        let operator = maybe_operator.take().ok_or(Error::UnknownOperator)?;
+        let mut operator = maybe_operator.take().ok_or(Error::UnknownOperator)?;
         let OperatorDeregisteredInfo {
             domain_epoch,
             unlock_at_confirmed_domain_block_number,
+            unlock_at_consensus_block_number,
         } = match operator.status::<T>(operator_id) {
-            OperatorStatus::Deregistered(operator_deregistered_info) => operator_deregistered_info,
+            OperatorStatus::Deregistered(operator_deregistered_info) => {
+                operator_deregistered_info.clone()
+            }
             _ => return Err(Error::OperatorNotDeregistered),
         };
 
         let (domain_id, _) = domain_epoch.deconstruct();
         let latest_confirmed_block_number =
             Pallet::<T>::latest_confirmed_domain_block_number(domain_id);
-        ensure!(
-            *unlock_at_confirmed_domain_block_number <= latest_confirmed_block_number,
-            Error::UnlockPeriodNotComplete
-        );
+        let current_block_number = frame_system::Pallet::<T>::current_block_number();
+        if unlock_at_confirmed_domain_block_number > latest_confirmed_block_number
+            && !unlock_at_consensus_block_number
+                .is_some_and(|unlock_at| unlock_at <= current_block_number)
+        {
+            // The operator deregistered before its domain is frozen is scheduled on the consensus
+            // clock only once, the schedule is checked as is afterwards
+            let unlock_at = match unlock_at_consensus_block_number {
+                Some(_) => None,
+                None => consensus_clock_unlock_at::<T>(domain_id)?,
+            }
+            .ok_or(Error::UnlockPeriodNotComplete)?;
+            operator.update_status(OperatorStatus::Deregistered(OperatorDeregisteredInfo {
+                domain_epoch,
+                unlock_at_confirmed_domain_block_number,
+                unlock_at_consensus_block_number: Some(unlock_at),
+            }));
+            *maybe_operator = Some(operator);
+            return Ok(UnlockOutcome::Scheduled(unlock_at));
+        }
 
         let total_shares = operator.current_total_shares;
         let mut total_stake = operator
@@ -1186,25 +1650,44 @@ pub(crate) fn do_unlock_operator<T: Config>(operator_id: OperatorId) -> Result<u
         // remove OperatorOwner Details
         OperatorIdOwner::<T>::remove(operator_id);
 
+        // remove the operator metadata and release its deposit
+        remove_operator_metadata::<T>(operator_id)?;
+
+        // remove any slash evidence of the operator
+        OperatorSlashEvidence::<T>::remove(operator_id);
+
+        // remove the rejected bundle records of the operator
+        RejectedBundleCount::<T>::remove(operator_id);
+        OperatorRejectedBundles::<T>::remove(operator_id);
+        LastRejectedBundleSlot::<T>::remove(operator_id);
+
         // remove operator signing key
         OperatorSigningKey::<T>::remove(operator.signing_key.clone());
 
         // remove operator epoch share prices
         let _ = OperatorEpochSharePrice::<T>::clear_prefix(operator_id, u32::MAX, None);
 
+        // remove the operator tax paid by the nominators
+        remove_operator_tax::<T>(operator_id);
+
+        // remove operator epoch summaries
+        let _ = EpochSummary::<T>::clear_prefix(operator_id, u32::MAX, None);
+
         // remove nominator count for this operator.
         NominatorCount::<T>::remove(operator_id);
 
-        Ok(nominator_count)
+        Ok(UnlockOutcome::Unlocked(nominator_count))
     })
 }
 
-/// Distribute the reward to the operators equally and drop any dust to treasury.
+/// Distribute the reward to the operators equally and drop any dust to treasury, returns the
+/// reward distributed to the operators.
 pub(crate) fn do_reward_operators<T: Config>(
     domain_id: DomainId,
     operators: IntoIter<OperatorId>,
     mut rewards: BalanceOf<T>,
-) -> Result<(), Error> {
+) -> Result<BalanceOf<T>, Error> {
+    let total_rewards = rewards;
     DomainStakingSummary::<T>::mutate(domain_id, |maybe_stake_summary| {
         let stake_summary = maybe_stake_summary
             .as_mut()
@@ -1234,7 +1717,9 @@ pub(crate) fn do_reward_operators<T: Config>(
                 .ok_or(Error::BalanceUnderflow)?;
         }
 
-        mint_funds::<T>(&T::TreasuryAccount::get(), rewards)
+        mint_funds::<T>(&T::TreasuryAccount::get(), rewards)?;
+
+        Ok(total_rewards.saturating_sub(rewards))
     })
 }
 
@@ -1267,6 +1752,20 @@ pub(crate) fn do_slash_operators<T: Config>(
                         .as_mut()
                         .ok_or(Error::DomainNotInitialized)?;
 
+                    // defer the finalization of the slash if configured, so it can be cancelled
+                    let slash_defer_duration = T::SlashDeferDuration::get();
+                    if !slash_defer_duration.is_zero() {
+                        DeferredSlashes::<T>::insert(
+                            operator_id,
+                            DeferredSlash {
+                                finalize_at_epoch: stake_summary
+                                    .current_epoch_index
+                                    .saturating_add(slash_defer_duration),
+                                previous_status: operator.status.clone(),
+                            },
+                        );
+                    }
+
                     // slash and remove operator from next epoch set
                     operator.update_status(OperatorStatus::Slashed);
                     stake_summary.next_operators.remove(operator_id);
@@ -1296,85 +1795,319 @@ pub(crate) fn do_slash_operators<T: Config>(
     Ok(())
 }
 
-#[cfg(test)]
-pub(crate) mod tests {
-    use crate::domain_registry::{DomainConfig, DomainObject};
-    use crate::pallet::{
-        Config, Deposits, DomainRegistry, DomainStakingSummary, LatestConfirmedDomainBlock,
-        NextOperatorId, NominatorCount, OperatorIdOwner, Operators, PendingSlashes, Withdrawals,
-    };
-    use crate::staking::{
-        do_convert_previous_epoch_withdrawal, do_nominate_operator, do_reward_operators,
-        do_slash_operators, do_unlock_funds, do_withdraw_stake, Error as StakingError, Operator,
-        OperatorConfig, OperatorStatus, StakingSummary,
+/// Returns whether the finalization of the operator's slash is still deferred at the current epoch
+/// of the domain.
+pub(crate) fn is_slash_deferred<T: Config>(domain_id: DomainId, operator_id: OperatorId) -> bool {
+    let current_epoch_index = match DomainStakingSummary::<T>::get(domain_id) {
+        Some(stake_summary) => stake_summary.current_epoch_index,
+        None => return false,
     };
-    use crate::staking_epoch::do_finalize_domain_current_epoch;
-    use crate::tests::{new_test_ext, ExistentialDeposit, RuntimeOrigin, Test};
-    use crate::{bundle_storage_fund, BalanceOf, Error, NominatorId, SlashedReason};
-    use frame_support::traits::fungible::Mutate;
-    use frame_support::traits::Currency;
-    use frame_support::weights::Weight;
-    use frame_support::{assert_err, assert_ok};
-    use sp_core::{Pair, U256};
-    use sp_domains::{
-        ConfirmedDomainBlock, DomainId, OperatorAllowList, OperatorId, OperatorPair,
-        OperatorPublicKey, ZERO_OPERATOR_SIGNING_KEY,
-    };
-    use sp_runtime::traits::Zero;
-    use sp_runtime::{PerThing, Perbill};
-    use std::collections::{BTreeMap, BTreeSet};
-    use std::vec;
-    use subspace_runtime_primitives::SSC;
+    DeferredSlashes::<T>::get(operator_id)
+        .map(|deferred_slash| deferred_slash.finalize_at_epoch > current_epoch_index)
+        .unwrap_or(false)
+}
 
-    type Balances = pallet_balances::Pallet<Test>;
-    type Domains = crate::Pallet<Test>;
+/// Cancels the deferred slash of the operator, the operator is restored to the status it had
+/// before the slash and, if registered, is elected again starting from the next epoch of its
+/// domain.
+///
+/// NOTE: the pending operator switch dropped by the slash is not restored.
+pub(crate) fn do_cancel_pending_slash<T: Config>(
+    domain_id: DomainId,
+    operator_id: OperatorId,
+) -> Result<(), Error> {
+    let mut pending_slashes = PendingSlashes::<T>::get(domain_id).unwrap_or_default();
+    ensure!(
+        pending_slashes.remove(&operator_id),
+        Error::SlashNotDeferred
+    );
+    let deferred_slash = DeferredSlashes::<T>::take(operator_id).ok_or(Error::SlashNotDeferred)?;
 
-    const STORAGE_FEE_RESERVE: Perbill = Perbill::from_percent(20);
+    Operators::<T>::try_mutate(operator_id, |maybe_operator| {
+        let operator = maybe_operator.as_mut().ok_or(Error::UnknownOperator)?;
 
-    pub(crate) fn register_operator(
-        domain_id: DomainId,
-        operator_account: <Test as frame_system::Config>::AccountId,
-        operator_free_balance: BalanceOf<Test>,
-        operator_stake: BalanceOf<Test>,
-        minimum_nominator_stake: BalanceOf<Test>,
-        signing_key: OperatorPublicKey,
-        mut nominators: BTreeMap<NominatorId<Test>, (BalanceOf<Test>, BalanceOf<Test>)>,
-    ) -> (OperatorId, OperatorConfig<BalanceOf<Test>>) {
-        nominators.insert(operator_account, (operator_free_balance, operator_stake));
-        for nominator in &nominators {
-            Balances::set_balance(nominator.0, nominator.1 .0);
-            assert_eq!(Balances::usable_balance(nominator.0), nominator.1 .0);
+        if deferred_slash.previous_status == OperatorStatus::Registered {
+            DomainStakingSummary::<T>::try_mutate(domain_id, |maybe_domain_stake_summary| {
+                let stake_summary = maybe_domain_stake_summary
+                    .as_mut()
+                    .ok_or(Error::DomainNotInitialized)?;
+                stake_summary.next_operators.insert(operator_id);
+                Ok::<(), Error>(())
+            })?;
         }
-        nominators.remove(&operator_account);
 
-        if !DomainRegistry::<Test>::contains_key(domain_id) {
-            let domain_config = DomainConfig {
-                domain_name: String::from_utf8(vec![0; 1024]).unwrap(),
-                runtime_id: 0,
-                max_block_size: u32::MAX,
-                max_block_weight: Weight::MAX,
-                bundle_slot_probability: (0, 0),
-                target_bundles_per_block: 0,
-                operator_allow_list: OperatorAllowList::Anyone,
-                initial_balances: Default::default(),
-            };
+        operator.next_domain_id = operator.current_domain_id;
+        operator.update_status(deferred_slash.previous_status);
+        Ok::<(), Error>(())
+    })?;
 
-            let domain_obj = DomainObject {
-                owner_account_id: 0,
-                created_at: 0,
-                genesis_receipt_hash: Default::default(),
-                domain_config,
-                domain_runtime_info: Default::default(),
-            };
+    if pending_slashes.is_empty() {
+        PendingSlashes::<T>::remove(domain_id);
+    } else {
+        PendingSlashes::<T>::insert(domain_id, pending_slashes);
+    }
 
-            DomainRegistry::<Test>::insert(domain_id, domain_obj);
+    Ok(())
+}
+
+/// Records the evidence of the bad ER that the operators are slashed for, the oldest evidence
+/// is dropped if there are more than `MaxSlashEvidence` evidence for the operator.
+///
+/// Operators that are already removed are skipped since their evidence would never be cleaned up.
+pub(crate) fn note_slash_evidence<T: Config>(
+    operator_ids: impl AsRef<[OperatorId]>,
+    evidence: SlashEvidenceOf<T>,
+) {
+    if T::MaxSlashEvidence::get().is_zero() {
+        return;
+    }
+
+    for operator_id in operator_ids.as_ref() {
+        if !Operators::<T>::contains_key(operator_id) {
+            continue;
         }
 
-        if !DomainStakingSummary::<Test>::contains_key(domain_id) {
-            DomainStakingSummary::<Test>::insert(
-                domain_id,
-                StakingSummary {
-                    current_epoch_index: 0,
+        OperatorSlashEvidence::<T>::mutate(operator_id, |evidences| {
+            // Push to the end and drop the oldest evidence from the front if the bound is reached
+            let _ = evidences.force_insert_keep_right(evidences.len(), evidence.clone());
+        });
+    }
+}
+
+/// Schedules the removal of the slash evidence of the removed slashed operator after
+/// `SlashEvidenceRetentionPeriod` blocks.
+pub(crate) fn schedule_slash_evidence_expiry<T: Config>(operator_id: OperatorId) {
+    if !OperatorSlashEvidence::<T>::contains_key(operator_id) {
+        return;
+    }
+
+    let expire_at = frame_system::Pallet::<T>::current_block_number()
+        .saturating_add(T::SlashEvidenceRetentionPeriod::get());
+    SlashEvidenceExpiry::<T>::insert(expire_at, operator_id, ());
+}
+
+/// Removes the slash evidence that expires at the given block, returns the number of operators
+/// whose slash evidence is removed.
+pub(crate) fn prune_expired_slash_evidence<T: Config>(at: BlockNumberFor<T>) -> u64 {
+    let mut pruned = 0;
+    for (operator_id, _) in SlashEvidenceExpiry::<T>::drain_prefix(at) {
+        // The operator id is never reused, so the evidence can't belong to a new operator
+        OperatorSlashEvidence::<T>::remove(operator_id);
+        pruned += 1;
+    }
+    pruned
+}
+
+/// Checks the staking invariants of all the operators and domains, returns a distinct error for
+/// each violated invariant.
+///
+/// Conversions between shares and stake round down, so the share total and the held balance of a
+/// pool may be off by one unit for every deposit or withdrawal converted with an epoch share
+/// price.
+#[cfg(any(feature = "try-runtime", test))]
+pub(crate) fn check_staking_invariants<T: Config>() -> Result<(), &'static str> {
+    for (operator_id, operator) in Operators::<T>::iter() {
+        let current_epoch = DomainStakingSummary::<T>::get(operator.current_domain_id)
+            .map(|summary| {
+                DomainEpoch::from((operator.current_domain_id, summary.current_epoch_index))
+            })
+            .ok_or("operator's domain has no staking summary")?;
+        let maybe_owner = OperatorIdOwner::<T>::get(operator_id);
+        // the pools of deregistered and slashed operators are not updated by epoch transitions
+        let is_registered = *operator.status::<T>(operator_id) == OperatorStatus::Registered;
+
+        let mut deposit_shares = T::Share::zero();
+        let mut converted_deposits = 0u32;
+        let mut nominator_count = 0u32;
+        let mut held = BalanceOf::<T>::zero();
+        let staked_hold_id = T::HoldIdentifier::staking_staked(operator_id);
+        for (nominator_id, deposit) in Deposits::<T>::iter_prefix(operator_id) {
+            deposit_shares = deposit_shares.saturating_add(deposit.known.shares);
+            if let Some(pending) = deposit.pending {
+                if let Some(share_price) =
+                    OperatorEpochSharePrice::<T>::get(operator_id, pending.effective_domain_epoch)
+                {
+                    deposit_shares = deposit_shares
+                        .saturating_add(share_price.stake_to_shares::<T>(pending.amount));
+                    converted_deposits += 1;
+                }
+            }
+
+            let is_nominating = !deposit.known.shares.is_zero() || deposit.pending.is_some();
+            if is_nominating && maybe_owner.as_ref() != Some(&nominator_id) {
+                nominator_count += 1;
+            }
+
+            held =
+                held.saturating_add(T::Currency::balance_on_hold(&staked_hold_id, &nominator_id));
+        }
+
+        let mut converted_withdrawals = 0u32;
+        let mut pending_withdrawal_amount = BalanceOf::<T>::zero();
+        for (nominator_id, withdrawal) in Withdrawals::<T>::iter_prefix(operator_id) {
+            pending_withdrawal_amount =
+                pending_withdrawal_amount.saturating_add(withdrawal.total_withdrawal_amount);
+            converted_withdrawals += withdrawal.withdrawals.len() as u32;
+
+            if let Some(withdrawal_in_shares) = withdrawal.withdrawal_in_shares {
+                match OperatorEpochSharePrice::<T>::get(
+                    operator_id,
+                    withdrawal_in_shares.domain_epoch,
+                ) {
+                    // withdrew shares are deducted from the pool at the end of the epoch
+                    Some(share_price) => {
+                        pending_withdrawal_amount = pending_withdrawal_amount.saturating_add(
+                            share_price.shares_to_stake::<T>(withdrawal_in_shares.shares),
+                        );
+                        converted_withdrawals += 1;
+                    }
+                    None if withdrawal_in_shares.domain_epoch == current_epoch => {
+                        deposit_shares = deposit_shares.saturating_add(withdrawal_in_shares.shares);
+                    }
+                    None if is_registered => {
+                        return Err("withdrawal has no epoch share price to convert with")
+                    }
+                    None => {}
+                }
+            }
+
+            // the nominator may have withdrawn all its deposit but not unlocked it yet
+            if !Deposits::<T>::contains_key(operator_id, &nominator_id) {
+                held = held
+                    .saturating_add(T::Currency::balance_on_hold(&staked_hold_id, &nominator_id));
+            }
+        }
+
+        if NominatorCount::<T>::get(operator_id) != nominator_count {
+            return Err("nominator count mismatches the number of nominator deposits");
+        }
+
+        if !is_registered {
+            continue;
+        }
+
+        let total_shares = operator.current_total_shares;
+        if deposit_shares > total_shares
+            || total_shares - deposit_shares > T::Share::from(converted_deposits)
+        {
+            return Err("operator total shares mismatch the sum of deposit shares");
+        }
+
+        // the pool stake also includes the rewards, which are minted instead of held
+        let max_held = operator
+            .current_total_stake
+            .saturating_add(operator.deposits_in_epoch)
+            .saturating_add(pending_withdrawal_amount)
+            .saturating_add(BalanceOf::<T>::from(converted_withdrawals));
+        if held < operator.deposits_in_epoch || held > max_held {
+            return Err("staked held balance mismatches the recorded stake and pending deposits");
+        }
+    }
+
+    for (_, stake_summary) in DomainStakingSummary::<T>::iter() {
+        let operators_stake = stake_summary
+            .current_operators
+            .values()
+            .fold(BalanceOf::<T>::zero(), |total, stake| {
+                total.saturating_add(*stake)
+            });
+        if operators_stake != stake_summary.current_total_stake {
+            return Err("domain total stake mismatches the sum of current operator stakes");
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::domain_registry::{DomainConfig, DomainObject};
+    use crate::pallet::{
+        Config, DeferredSlashes, Deposits, DomainRegistry, DomainStakingSummary,
+        HeadReceiptExtendedAt, HeadReceiptNumber, LatestConfirmedDomainBlock, NextOperatorId,
+        NominatorCount, OperatorIdOwner, Operators, PendingSlashes, RegisteredOperatorMetadata,
+        Withdrawals,
+    };
+    use crate::staking::{
+        check_staking_invariants, do_convert_previous_epoch_withdrawal, do_nominate_operator,
+        do_reward_operators, do_slash_operators, do_unlock_funds, do_withdraw_stake,
+        Error as StakingError, Operator, OperatorConfig, OperatorDeregisteredInfo, OperatorStatus,
+        StakingSummary,
+    };
+    use crate::staking_epoch::do_finalize_domain_current_epoch;
+    use crate::tests::{
+        new_test_ext, BlockTreePruningDepth, DomainFreezePeriod, ExistentialDeposit,
+        HoldIdentifier, NominationKeepAlive, OperatorMetadataDeposit, RuntimeEvent, RuntimeOrigin,
+        SlashDeferDuration, StakeWithdrawalLockingPeriod, Test,
+    };
+    use crate::{bundle_storage_fund, BalanceOf, Error, Event, NominatorId, SlashedReason};
+    use frame_support::traits::fungible::{InspectHold, Mutate};
+    use frame_support::traits::{Currency, Get};
+    use frame_support::weights::Weight;
+    use frame_support::{assert_err, assert_ok};
+    use sp_core::{Pair, U256};
+    use sp_domains::{
+        ConfirmedDomainBlock, DomainId, DomainsHoldIdentifier, OperatorAllowList, OperatorId,
+        OperatorPair, OperatorPublicKey, MAX_OPERATOR_NAME_LENGTH, MAX_OPERATOR_WEBSITE_LENGTH,
+        ZERO_OPERATOR_SIGNING_KEY,
+    };
+    use sp_runtime::traits::Zero;
+    use sp_runtime::{DispatchError, PerThing, Perbill};
+    use std::collections::{BTreeMap, BTreeSet};
+    use std::vec;
+    use subspace_runtime_primitives::SSC;
+
+    type Balances = pallet_balances::Pallet<Test>;
+    type Domains = crate::Pallet<Test>;
+    type System = frame_system::Pallet<Test>;
+
+    const STORAGE_FEE_RESERVE: Perbill = Perbill::from_percent(20);
+
+    pub(crate) fn register_operator(
+        domain_id: DomainId,
+        operator_account: <Test as frame_system::Config>::AccountId,
+        operator_free_balance: BalanceOf<Test>,
+        operator_stake: BalanceOf<Test>,
+        minimum_nominator_stake: BalanceOf<Test>,
+        signing_key: OperatorPublicKey,
+        mut nominators: BTreeMap<NominatorId<Test>, (BalanceOf<Test>, BalanceOf<Test>)>,
+    ) -> (OperatorId, OperatorConfig<BalanceOf<Test>>) {
+        nominators.insert(operator_account, (operator_free_balance, operator_stake));
+        for nominator in &nominators {
+            Balances::set_balance(nominator.0, nominator.1 .0);
+            assert_eq!(Balances::usable_balance(nominator.0), nominator.1 .0);
+        }
+        nominators.remove(&operator_account);
+
+        if !DomainRegistry::<Test>::contains_key(domain_id) {
+            let domain_config = DomainConfig {
+                domain_name: String::from_utf8(vec![0; 1024]).unwrap(),
+                runtime_id: 0,
+                max_block_size: u32::MAX,
+                max_block_weight: Weight::MAX,
+                bundle_slot_probability: (0, 0),
+                target_bundles_per_block: 0,
+                operator_allow_list: OperatorAllowList::Anyone,
+                initial_balances: Default::default(),
+                initial_contract_creation_allow_list: None,
+            };
+
+            let domain_obj = DomainObject {
+                owner_account_id: 0,
+                created_at: 0,
+                genesis_receipt_hash: Default::default(),
+                domain_config,
+                domain_runtime_info: Default::default(),
+            };
+
+            DomainRegistry::<Test>::insert(domain_id, domain_obj);
+        }
+
+        if !DomainStakingSummary::<Test>::contains_key(domain_id) {
+            DomainStakingSummary::<Test>::insert(
+                domain_id,
+                StakingSummary {
+                    current_epoch_index: 0,
                     current_total_stake: 0,
                     current_operators: BTreeMap::new(),
                     next_operators: BTreeSet::new(),
@@ -1484,6 +2217,7 @@ pub(crate) mod tests {
 
         let mut ext = new_test_ext();
         ext.execute_with(|| {
+            System::set_block_number(1);
             let (operator_id, mut operator_config) = register_operator(
                 domain_id,
                 operator_account,
@@ -1495,6 +2229,13 @@ pub(crate) mod tests {
             );
 
             assert_eq!(NextOperatorId::<Test>::get(), 1);
+            System::assert_has_event(RuntimeEvent::Domains(Event::OperatorRegistered {
+                operator_id,
+                domain_id,
+                storage_fund_account: bundle_storage_fund::storage_fund_account::<Test>(
+                    operator_id,
+                ),
+            }));
             // operator_id should be 0 and be registered
             assert_eq!(
                 OperatorIdOwner::<Test>::get(operator_id).unwrap(),
@@ -1515,6 +2256,7 @@ pub(crate) mod tests {
                     deposits_in_epoch: 0,
                     withdrawals_in_epoch: 0,
                     total_storage_fee_deposit: operator_storage_fee_deposit,
+                    paused: false,
                 }
             );
 
@@ -1857,51 +2599,982 @@ pub(crate) mod tests {
                 )
             );
 
-            // domain switch will not work since the operator is frozen
-            let new_domain_id = DomainId::new(1);
-            let domain_config = DomainConfig {
-                domain_name: String::from_utf8(vec![0; 1024]).unwrap(),
-                runtime_id: 0,
-                max_block_size: u32::MAX,
-                max_block_weight: Weight::MAX,
-                bundle_slot_probability: (0, 0),
-                target_bundles_per_block: 0,
-                operator_allow_list: OperatorAllowList::Anyone,
-                initial_balances: Default::default(),
-            };
+            // domain switch will not work since the operator is frozen
+            let new_domain_id = DomainId::new(1);
+            let domain_config = DomainConfig {
+                domain_name: String::from_utf8(vec![0; 1024]).unwrap(),
+                runtime_id: 0,
+                max_block_size: u32::MAX,
+                max_block_weight: Weight::MAX,
+                bundle_slot_probability: (0, 0),
+                target_bundles_per_block: 0,
+                operator_allow_list: OperatorAllowList::Anyone,
+                initial_balances: Default::default(),
+                initial_contract_creation_allow_list: None,
+            };
+
+            let domain_obj = DomainObject {
+                owner_account_id: 0,
+                created_at: 0,
+                genesis_receipt_hash: Default::default(),
+                domain_config,
+                domain_runtime_info: Default::default(),
+            };
+
+            DomainRegistry::<Test>::insert(new_domain_id, domain_obj);
+            DomainStakingSummary::<Test>::insert(
+                new_domain_id,
+                StakingSummary {
+                    current_epoch_index: 0,
+                    current_total_stake: 0,
+                    current_operators: BTreeMap::new(),
+                    next_operators: BTreeSet::new(),
+                    current_epoch_rewards: BTreeMap::new(),
+                },
+            );
+
+            // nominations will not work since the is frozen
+            let nominator_account = 100;
+            let nominator_stake = 100 * SSC;
+            let res = Domains::nominate_operator(
+                RuntimeOrigin::signed(nominator_account),
+                operator_id,
+                nominator_stake,
+            );
+            assert_err!(
+                res,
+                Error::<Test>::Staking(crate::staking::Error::OperatorNotRegistered)
+            );
+        });
+    }
+
+    #[test]
+    fn operator_pause_and_resume() {
+        let domain_id = DomainId::new(0);
+        let operator_account = 1;
+        let operator_free_balance = 250 * SSC;
+        let operator_stake = 200 * SSC;
+        let pair = OperatorPair::from_seed(&U256::from(0u32).into());
+
+        let nominator_account = 2;
+        let nominator_free_balance = 150 * SSC;
+        let nominator_stake = 100 * SSC;
+
+        let mut ext = new_test_ext();
+        ext.execute_with(|| {
+            let (operator_id, _) = register_operator(
+                domain_id,
+                operator_account,
+                operator_free_balance,
+                operator_stake,
+                10 * SSC,
+                pair.public(),
+                BTreeMap::new(),
+            );
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+
+            let init_total_stake = STORAGE_FEE_RESERVE.left_from_one() * operator_stake;
+            let domain_stake_summary = DomainStakingSummary::<Test>::get(domain_id).unwrap();
+            assert_eq!(
+                domain_stake_summary.current_operators.get(&operator_id),
+                Some(&init_total_stake)
+            );
+
+            // Only the owner can pause or resume the operator
+            assert_err!(
+                Domains::pause_operator(RuntimeOrigin::signed(nominator_account), operator_id),
+                Error::<Test>::Staking(StakingError::NotOperatorOwner)
+            );
+            assert_err!(
+                Domains::resume_operator(RuntimeOrigin::signed(operator_account), operator_id),
+                Error::<Test>::Staking(StakingError::OperatorNotPaused)
+            );
+            assert_ok!(Domains::pause_operator(
+                RuntimeOrigin::signed(operator_account),
+                operator_id
+            ));
+            assert_err!(
+                Domains::pause_operator(RuntimeOrigin::signed(operator_account), operator_id),
+                Error::<Test>::Staking(StakingError::OperatorAlreadyPaused)
+            );
+            assert!(Operators::<Test>::get(operator_id).unwrap().paused);
+
+            // Operator remains elected until the end of the current epoch
+            let domain_stake_summary = DomainStakingSummary::<Test>::get(domain_id).unwrap();
+            assert!(domain_stake_summary
+                .current_operators
+                .contains_key(&operator_id));
+
+            // Deposits keep working while the operator is paused
+            Balances::set_balance(&nominator_account, nominator_free_balance);
+            assert_ok!(Domains::nominate_operator(
+                RuntimeOrigin::signed(nominator_account),
+                operator_id,
+                nominator_stake,
+            ));
+
+            // Paused operator is not elected in the next epoch, but its staking is finalized
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+            let total_stake =
+                init_total_stake + STORAGE_FEE_RESERVE.left_from_one() * nominator_stake;
+            let operator = Operators::<Test>::get(operator_id).unwrap();
+            assert_eq!(operator.current_total_stake, total_stake);
+            let domain_stake_summary = DomainStakingSummary::<Test>::get(domain_id).unwrap();
+            assert!(domain_stake_summary.next_operators.contains(&operator_id));
+            assert!(!domain_stake_summary
+                .current_operators
+                .contains_key(&operator_id));
+            assert_eq!(domain_stake_summary.current_total_stake, 0);
+
+            // Withdrawals keep working while the operator is paused
+            let withdrawn_shares = 8 * SSC;
+            assert_ok!(Domains::withdraw_stake(
+                RuntimeOrigin::signed(nominator_account),
+                operator_id,
+                withdrawn_shares,
+            ));
+
+            // Resumed operator is elected again in the next epoch with the stake it has by then
+            assert_ok!(Domains::resume_operator(
+                RuntimeOrigin::signed(operator_account),
+                operator_id
+            ));
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+            let operator = Operators::<Test>::get(operator_id).unwrap();
+            assert!(!operator.paused);
+            assert_eq!(operator.current_total_stake, total_stake - withdrawn_shares);
+            let domain_stake_summary = DomainStakingSummary::<Test>::get(domain_id).unwrap();
+            assert_eq!(
+                domain_stake_summary.current_operators.get(&operator_id),
+                Some(&operator.current_total_stake)
+            );
+            assert_eq!(
+                domain_stake_summary.current_total_stake,
+                operator.current_total_stake
+            );
+        });
+    }
+
+    #[test]
+    fn slash_paused_operator() {
+        let domain_id = DomainId::new(0);
+        let operator_account = 1;
+        let operator_free_balance = 250 * SSC;
+        let operator_stake = 200 * SSC;
+        let pair = OperatorPair::from_seed(&U256::from(0u32).into());
+
+        let mut ext = new_test_ext();
+        ext.execute_with(|| {
+            let (operator_id, _) = register_operator(
+                domain_id,
+                operator_account,
+                operator_free_balance,
+                operator_stake,
+                10 * SSC,
+                pair.public(),
+                BTreeMap::new(),
+            );
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+
+            assert_ok!(Domains::pause_operator(
+                RuntimeOrigin::signed(operator_account),
+                operator_id
+            ));
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+
+            // Bad ER submitted before the pause is still slashed
+            do_slash_operators::<Test>(
+                vec![operator_id],
+                SlashedReason::BadExecutionReceipt(Default::default()),
+            )
+            .unwrap();
+            let operator = Operators::<Test>::get(operator_id).unwrap();
+            assert_eq!(
+                *operator.status::<Test>(operator_id),
+                OperatorStatus::Slashed
+            );
+            assert!(PendingSlashes::<Test>::get(domain_id)
+                .unwrap()
+                .contains(&operator_id));
+
+            // Slashed operator can't be resumed
+            assert_err!(
+                Domains::resume_operator(RuntimeOrigin::signed(operator_account), operator_id),
+                Error::<Test>::Staking(StakingError::OperatorNotRegistered)
+            );
+
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+            assert_eq!(Operators::<Test>::get(operator_id), None);
+            assert_eq!(
+                Balances::total_balance(&operator_account),
+                operator_free_balance - operator_stake + STORAGE_FEE_RESERVE * operator_stake
+            );
+        });
+    }
+
+    fn operator_metadata_held(operator_id: OperatorId, who: NominatorId<Test>) -> BalanceOf<Test> {
+        Balances::balance_on_hold(
+            &HoldIdentifier::Domains(DomainsHoldIdentifier::OperatorMetadata(operator_id)),
+            &who,
+        )
+    }
+
+    #[test]
+    fn operator_metadata_deposit_lifecycle() {
+        let domain_id = DomainId::new(0);
+        let operator_account = 1;
+        let operator_free_balance = 250 * SSC;
+        let operator_stake = 200 * SSC;
+        let pair = OperatorPair::from_seed(&U256::from(0u32).into());
+
+        let mut ext = new_test_ext();
+        ext.execute_with(|| {
+            let (operator_id, _) = register_operator(
+                domain_id,
+                operator_account,
+                operator_free_balance,
+                operator_stake,
+                10 * SSC,
+                pair.public(),
+                BTreeMap::new(),
+            );
+            let deposit = OperatorMetadataDeposit::get();
+
+            // Only the owner can set or clear the metadata
+            assert_err!(
+                Domains::set_operator_metadata(
+                    RuntimeOrigin::signed(2),
+                    operator_id,
+                    b"operator".to_vec(),
+                    None,
+                ),
+                Error::<Test>::Staking(StakingError::NotOperatorOwner)
+            );
+            assert_err!(
+                Domains::clear_operator_metadata(
+                    RuntimeOrigin::signed(operator_account),
+                    operator_id
+                ),
+                Error::<Test>::Staking(StakingError::MissingOperatorMetadata)
+            );
+
+            // The deposit is held when the metadata is first set
+            assert_ok!(Domains::set_operator_metadata(
+                RuntimeOrigin::signed(operator_account),
+                operator_id,
+                b"operator".to_vec(),
+                None,
+            ));
+            assert_eq!(
+                operator_metadata_held(operator_id, operator_account),
+                deposit
+            );
+            let metadata = Domains::operator_metadata(operator_id).unwrap();
+            assert_eq!(metadata.name.to_vec(), b"operator".to_vec());
+            assert_eq!(metadata.website, None);
+
+            // Updating the metadata doesn't hold more
+            assert_ok!(Domains::set_operator_metadata(
+                RuntimeOrigin::signed(operator_account),
+                operator_id,
+                b"renamed".to_vec(),
+                Some(b"https://operator.example".to_vec()),
+            ));
+            assert_eq!(
+                operator_metadata_held(operator_id, operator_account),
+                deposit
+            );
+            let metadata = Domains::operator_metadata(operator_id).unwrap();
+            assert_eq!(metadata.name.to_vec(), b"renamed".to_vec());
+            assert_eq!(
+                metadata.website.map(|website| website.to_vec()),
+                Some(b"https://operator.example".to_vec())
+            );
+
+            // Clearing the metadata releases the deposit
+            assert_ok!(Domains::clear_operator_metadata(
+                RuntimeOrigin::signed(operator_account),
+                operator_id
+            ));
+            assert_eq!(operator_metadata_held(operator_id, operator_account), 0);
+            assert_eq!(Domains::operator_metadata(operator_id), None);
+
+            // The metadata is removed once the operator is unlocked
+            assert_ok!(Domains::set_operator_metadata(
+                RuntimeOrigin::signed(operator_account),
+                operator_id,
+                b"operator".to_vec(),
+                None,
+            ));
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+            assert_ok!(Domains::deregister_operator(
+                RuntimeOrigin::signed(operator_account),
+                operator_id
+            ));
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+
+            // staking withdrawal is `StakeWithdrawalLockingPeriod` blocks
+            LatestConfirmedDomainBlock::<Test>::insert(
+                domain_id,
+                ConfirmedDomainBlock {
+                    block_number: StakeWithdrawalLockingPeriod::get(),
+                    block_hash: Default::default(),
+                    parent_block_receipt_hash: Default::default(),
+                    state_root: Default::default(),
+                    extrinsics_root: Default::default(),
+                },
+            );
+            assert_ok!(Domains::unlock_operator(
+                RuntimeOrigin::signed(operator_account),
+                operator_id
+            ));
+            assert!(!RegisteredOperatorMetadata::<Test>::contains_key(
+                operator_id
+            ));
+            assert_eq!(operator_metadata_held(operator_id, operator_account), 0);
+        });
+    }
+
+    #[test]
+    fn operator_metadata_removed_on_slash() {
+        let domain_id = DomainId::new(0);
+        let operator_account = 1;
+        let operator_free_balance = 250 * SSC;
+        let operator_stake = 200 * SSC;
+        let pair = OperatorPair::from_seed(&U256::from(0u32).into());
+
+        let mut ext = new_test_ext();
+        ext.execute_with(|| {
+            let (operator_id, _) = register_operator(
+                domain_id,
+                operator_account,
+                operator_free_balance,
+                operator_stake,
+                10 * SSC,
+                pair.public(),
+                BTreeMap::new(),
+            );
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+
+            assert_ok!(Domains::set_operator_metadata(
+                RuntimeOrigin::signed(operator_account),
+                operator_id,
+                b"operator".to_vec(),
+                None,
+            ));
+
+            do_slash_operators::<Test>(
+                vec![operator_id],
+                SlashedReason::BadExecutionReceipt(Default::default()),
+            )
+            .unwrap();
+
+            // The metadata is kept until the slash is finalized
+            assert!(Domains::operator_metadata(operator_id).is_some());
+
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+            assert_eq!(Operators::<Test>::get(operator_id), None);
+            assert_eq!(Domains::operator_metadata(operator_id), None);
+            assert_eq!(operator_metadata_held(operator_id, operator_account), 0);
+        });
+    }
+
+    #[test]
+    fn operator_metadata_length_bounds() {
+        let domain_id = DomainId::new(0);
+        let operator_account = 1;
+        let operator_free_balance = 250 * SSC;
+        let operator_stake = 200 * SSC;
+        let pair = OperatorPair::from_seed(&U256::from(0u32).into());
+
+        let mut ext = new_test_ext();
+        ext.execute_with(|| {
+            let (operator_id, _) = register_operator(
+                domain_id,
+                operator_account,
+                operator_free_balance,
+                operator_stake,
+                10 * SSC,
+                pair.public(),
+                BTreeMap::new(),
+            );
+            let max_name = vec![b'a'; MAX_OPERATOR_NAME_LENGTH as usize];
+            let max_website = vec![b'a'; MAX_OPERATOR_WEBSITE_LENGTH as usize];
+
+            assert_err!(
+                Domains::set_operator_metadata(
+                    RuntimeOrigin::signed(operator_account),
+                    operator_id,
+                    vec![],
+                    None,
+                ),
+                Error::<Test>::Staking(StakingError::EmptyOperatorName)
+            );
+            assert_err!(
+                Domains::set_operator_metadata(
+                    RuntimeOrigin::signed(operator_account),
+                    operator_id,
+                    [max_name.clone(), vec![b'a']].concat(),
+                    None,
+                ),
+                Error::<Test>::Staking(StakingError::OperatorNameTooLong)
+            );
+            assert_err!(
+                Domains::set_operator_metadata(
+                    RuntimeOrigin::signed(operator_account),
+                    operator_id,
+                    max_name.clone(),
+                    Some([max_website.clone(), vec![b'a']].concat()),
+                ),
+                Error::<Test>::Staking(StakingError::OperatorWebsiteTooLong)
+            );
+            assert_eq!(operator_metadata_held(operator_id, operator_account), 0);
+
+            assert_ok!(Domains::set_operator_metadata(
+                RuntimeOrigin::signed(operator_account),
+                operator_id,
+                max_name.clone(),
+                Some(max_website.clone()),
+            ));
+            let metadata = Domains::operator_metadata(operator_id).unwrap();
+            assert_eq!(metadata.name.to_vec(), max_name);
+            assert_eq!(
+                metadata.website.map(|website| website.to_vec()),
+                Some(max_website)
+            );
+        });
+    }
+
+    #[test]
+    fn staking_invariants() {
+        let domain_id = DomainId::new(0);
+        let operator_account = 1;
+        let operator_free_balance = 250 * SSC;
+        let operator_stake = 200 * SSC;
+        let pair = OperatorPair::from_seed(&U256::from(0u32).into());
+
+        let nominator_account = 2;
+        let nominator_free_balance = 150 * SSC;
+        let nominator_stake = 100 * SSC;
+
+        let mut ext = new_test_ext();
+        ext.execute_with(|| {
+            let (operator_id, _) = register_operator(
+                domain_id,
+                operator_account,
+                operator_free_balance,
+                operator_stake,
+                10 * SSC,
+                pair.public(),
+                BTreeMap::from_iter(vec![(
+                    nominator_account,
+                    (nominator_free_balance, nominator_stake),
+                )]),
+            );
+            assert_ok!(check_staking_invariants::<Test>());
+
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+            assert_ok!(check_staking_invariants::<Test>());
+
+            assert_ok!(Domains::withdraw_stake(
+                RuntimeOrigin::signed(nominator_account),
+                operator_id,
+                10 * SSC,
+            ));
+            assert_ok!(check_staking_invariants::<Test>());
+
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+            assert_ok!(check_staking_invariants::<Test>());
+
+            // Corrupt each invariant in turn
+            let nominator_count = NominatorCount::<Test>::get(operator_id);
+            NominatorCount::<Test>::insert(operator_id, nominator_count + 1);
+            assert_err!(
+                check_staking_invariants::<Test>(),
+                "nominator count mismatches the number of nominator deposits"
+            );
+            NominatorCount::<Test>::insert(operator_id, nominator_count);
+
+            let operator = Operators::<Test>::get(operator_id).unwrap();
+            Operators::<Test>::mutate(operator_id, |maybe_operator| {
+                maybe_operator.as_mut().unwrap().current_total_shares += SSC;
+            });
+            assert_err!(
+                check_staking_invariants::<Test>(),
+                "operator total shares mismatch the sum of deposit shares"
+            );
+            Operators::<Test>::insert(operator_id, operator.clone());
+            Operators::<Test>::mutate(operator_id, |maybe_operator| {
+                maybe_operator.as_mut().unwrap().current_total_stake = 0;
+            });
+            assert_err!(
+                check_staking_invariants::<Test>(),
+                "staked held balance mismatches the recorded stake and pending deposits"
+            );
+            Operators::<Test>::insert(operator_id, operator);
+
+            let withdrawal = Withdrawals::<Test>::get(operator_id, nominator_account).unwrap();
+            Withdrawals::<Test>::mutate(operator_id, nominator_account, |maybe_withdrawal| {
+                let withdrawal_in_shares = maybe_withdrawal
+                    .as_mut()
+                    .unwrap()
+                    .withdrawal_in_shares
+                    .as_mut()
+                    .unwrap();
+                withdrawal_in_shares.domain_epoch = (domain_id, 100).into();
+            });
+            assert_err!(
+                check_staking_invariants::<Test>(),
+                "withdrawal has no epoch share price to convert with"
+            );
+            Withdrawals::<Test>::insert(operator_id, nominator_account, withdrawal);
+
+            DomainStakingSummary::<Test>::mutate(domain_id, |maybe_stake_summary| {
+                maybe_stake_summary.as_mut().unwrap().current_total_stake += 1;
+            });
+            assert_err!(
+                check_staking_invariants::<Test>(),
+                "domain total stake mismatches the sum of current operator stakes"
+            );
+        });
+    }
+
+    #[test]
+    fn cancel_deferred_slash() {
+        let domain_id = DomainId::new(0);
+        let operator_account = 1;
+        let operator_free_balance = 250 * SSC;
+        let operator_stake = 200 * SSC;
+        let pair = OperatorPair::from_seed(&U256::from(0u32).into());
+
+        let mut ext = new_test_ext();
+        ext.execute_with(|| {
+            SlashDeferDuration::set(2);
+            let (operator_id, _) = register_operator(
+                domain_id,
+                operator_account,
+                operator_free_balance,
+                operator_stake,
+                10 * SSC,
+                pair.public(),
+                BTreeMap::new(),
+            );
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+
+            do_slash_operators::<Test>(
+                vec![operator_id],
+                SlashedReason::BadExecutionReceipt(Default::default()),
+            )
+            .unwrap();
+            assert!(DeferredSlashes::<Test>::get(operator_id).is_some());
+
+            // Deferred slash is not finalized at the end of the epoch
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+            let operator = Operators::<Test>::get(operator_id).unwrap();
+            assert_eq!(
+                *operator.status::<Test>(operator_id),
+                OperatorStatus::Slashed
+            );
+            let domain_stake_summary = DomainStakingSummary::<Test>::get(domain_id).unwrap();
+            assert!(!domain_stake_summary
+                .current_operators
+                .contains_key(&operator_id));
+
+            // Operator can't deregister while the slash is deferred
+            assert_err!(
+                Domains::deregister_operator(RuntimeOrigin::signed(operator_account), operator_id),
+                Error::<Test>::Staking(StakingError::OperatorNotRegistered)
+            );
+
+            // Only root can cancel the slash
+            assert_err!(
+                Domains::cancel_pending_slash(
+                    RuntimeOrigin::signed(operator_account),
+                    domain_id,
+                    operator_id
+                ),
+                DispatchError::BadOrigin
+            );
+            assert_ok!(Domains::cancel_pending_slash(
+                RuntimeOrigin::root(),
+                domain_id,
+                operator_id
+            ));
+            assert_eq!(PendingSlashes::<Test>::get(domain_id), None);
+            assert_eq!(DeferredSlashes::<Test>::get(operator_id), None);
+            assert_err!(
+                Domains::cancel_pending_slash(RuntimeOrigin::root(), domain_id, operator_id),
+                Error::<Test>::Staking(StakingError::SlashNotDeferred)
+            );
+
+            // Operator is restored and elected again starting from the next epoch
+            let operator = Operators::<Test>::get(operator_id).unwrap();
+            assert_eq!(
+                *operator.status::<Test>(operator_id),
+                OperatorStatus::Registered
+            );
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+            let domain_stake_summary = DomainStakingSummary::<Test>::get(domain_id).unwrap();
+            assert_eq!(
+                domain_stake_summary.current_operators.get(&operator_id),
+                Some(&operator.current_total_stake)
+            );
+        });
+    }
+
+    #[test]
+    fn deferred_slash_finalized_after_defer_duration() {
+        let domain_id = DomainId::new(0);
+        let operator_account = 1;
+        let operator_free_balance = 250 * SSC;
+        let operator_stake = 200 * SSC;
+        let pair = OperatorPair::from_seed(&U256::from(0u32).into());
+
+        let mut ext = new_test_ext();
+        ext.execute_with(|| {
+            SlashDeferDuration::set(2);
+            let (operator_id, _) = register_operator(
+                domain_id,
+                operator_account,
+                operator_free_balance,
+                operator_stake,
+                10 * SSC,
+                pair.public(),
+                BTreeMap::new(),
+            );
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+
+            do_slash_operators::<Test>(
+                vec![operator_id],
+                SlashedReason::BadExecutionReceipt(Default::default()),
+            )
+            .unwrap();
+
+            // The slash is deferred for the current epoch and the next `SlashDeferDuration - 1`
+            // epochs
+            for _ in 0..SlashDeferDuration::get() {
+                do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+                assert!(Operators::<Test>::get(operator_id).is_some());
+                assert!(PendingSlashes::<Test>::get(domain_id)
+                    .unwrap()
+                    .contains(&operator_id));
+            }
+
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+            assert_eq!(Operators::<Test>::get(operator_id), None);
+            assert_eq!(PendingSlashes::<Test>::get(domain_id), None);
+            assert_eq!(DeferredSlashes::<Test>::get(operator_id), None);
+            assert_eq!(
+                Balances::total_balance(&operator_account),
+                operator_free_balance - operator_stake + STORAGE_FEE_RESERVE * operator_stake
+            );
+
+            // Finalized slash can't be cancelled
+            assert_err!(
+                Domains::cancel_pending_slash(RuntimeOrigin::root(), domain_id, operator_id),
+                Error::<Test>::Staking(StakingError::SlashNotDeferred)
+            );
+        });
+    }
+
+    fn register_operator_and_withdraw(
+        operator_account: NominatorId<Test>,
+        nominator_id: NominatorId<Test>,
+        withdraw_shares: Share,
+    ) -> OperatorId {
+        let domain_id = DomainId::new(0);
+        let pair = OperatorPair::from_seed(&U256::from(0u32).into());
+        let (operator_id, _) = register_operator(
+            domain_id,
+            operator_account,
+            250 * SSC,
+            200 * SSC,
+            SSC,
+            pair.public(),
+            BTreeMap::from_iter([(
+                nominator_id,
+                (100 * SSC + ExistentialDeposit::get(), 100 * SSC),
+            )]),
+        );
+        do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+
+        let confirmed_domain_block = |block_number| ConfirmedDomainBlock {
+            block_number,
+            block_hash: Default::default(),
+            parent_block_receipt_hash: Default::default(),
+            state_root: Default::default(),
+            extrinsics_root: Default::default(),
+        };
+        LatestConfirmedDomainBlock::<Test>::insert(domain_id, confirmed_domain_block(100));
+        assert_ok!(Domains::withdraw_stake(
+            RuntimeOrigin::signed(nominator_id),
+            operator_id,
+            withdraw_shares,
+        ));
+        do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+
+        // staking withdrawal is `StakeWithdrawalLockingPeriod` blocks
+        LatestConfirmedDomainBlock::<Test>::insert(
+            domain_id,
+            confirmed_domain_block(100 + StakeWithdrawalLockingPeriod::get()),
+        );
+
+        operator_id
+    }
+
+    #[test]
+    fn unlock_funds_to_beneficiary() {
+        let nominator_id = 2;
+        let beneficiary = 3;
+
+        let mut ext = new_test_ext();
+        ext.execute_with(|| {
+            System::set_block_number(1);
+            // Withdraw 40 shares, which is 40 SSC stake and 10 SSC storage fee deposit
+            let operator_id = register_operator_and_withdraw(1, nominator_id, 40 * SSC);
+            let nominator_balance = Balances::usable_balance(nominator_id);
+
+            // Beneficiary can't receive the funds below existential deposit, the whole call fails
+            ExistentialDeposit::set(100 * SSC);
+            assert_err!(
+                Domains::unlock_funds(
+                    RuntimeOrigin::signed(nominator_id),
+                    operator_id,
+                    Some(beneficiary)
+                ),
+                Error::<Test>::Staking(StakingError::TransferToBeneficiary)
+            );
+            assert!(Withdrawals::<Test>::get(operator_id, nominator_id).is_some());
+            ExistentialDeposit::set(1);
+
+            assert_ok!(Domains::unlock_funds(
+                RuntimeOrigin::signed(nominator_id),
+                operator_id,
+                Some(beneficiary)
+            ));
+            assert!(Withdrawals::<Test>::get(operator_id, nominator_id).is_none());
+            assert_eq!(Balances::usable_balance(nominator_id), nominator_balance);
+            assert_eq!(Balances::total_balance(&beneficiary), 50 * SSC);
+            System::assert_last_event(RuntimeEvent::Domains(Event::FundsUnlocked {
+                operator_id,
+                nominator_id,
+                amount: 40 * SSC,
+                beneficiary: Some(beneficiary),
+            }));
+        });
+    }
+
+    #[test]
+    fn unlock_funds_to_nominator_as_beneficiary() {
+        let nominator_id = 2;
+
+        let mut ext = new_test_ext();
+        ext.execute_with(|| {
+            System::set_block_number(1);
+            let operator_id = register_operator_and_withdraw(1, nominator_id, 40 * SSC);
+            let nominator_balance = Balances::usable_balance(nominator_id);
+
+            // Same as unlocking without beneficiary
+            assert_ok!(Domains::unlock_funds(
+                RuntimeOrigin::signed(nominator_id),
+                operator_id,
+                Some(nominator_id)
+            ));
+            assert!(Withdrawals::<Test>::get(operator_id, nominator_id).is_none());
+            assert_eq!(
+                Balances::usable_balance(nominator_id),
+                nominator_balance + 50 * SSC
+            );
+            System::assert_last_event(RuntimeEvent::Domains(Event::FundsUnlocked {
+                operator_id,
+                nominator_id,
+                amount: 40 * SSC,
+                beneficiary: Some(nominator_id),
+            }));
+        });
+    }
+
+    #[test]
+    fn frozen_domain_exit_on_consensus_clock() {
+        let domain_id = DomainId::new(0);
+        let operator_account = 1;
+        let nominator_id = 2;
+
+        let mut ext = new_test_ext();
+        ext.execute_with(|| {
+            System::set_block_number(1);
+            let operator_id =
+                register_operator_and_withdraw(operator_account, nominator_id, 40 * SSC);
+            let nominator_balance = Balances::usable_balance(nominator_id);
+
+            // The domain stops confirming new blocks before the withdrawal is unlocked
+            LatestConfirmedDomainBlock::<Test>::insert(
+                domain_id,
+                ConfirmedDomainBlock {
+                    block_number: 100,
+                    block_hash: Default::default(),
+                    parent_block_receipt_hash: Default::default(),
+                    state_root: Default::default(),
+                    extrinsics_root: Default::default(),
+                },
+            );
+            assert_err!(
+                Domains::unlock_funds(RuntimeOrigin::signed(nominator_id), operator_id, None),
+                Error::<Test>::Staking(StakingError::UnlockPeriodNotComplete)
+            );
+
+            // The domain is frozen with pending ERs, which can be challenged for longer than the
+            // staking withdrawal locking period, the withdrawal is scheduled on the consensus clock
+            HeadReceiptNumber::<Test>::insert(domain_id, 110);
+            let frozen_at = DomainFreezePeriod::get();
+            System::set_block_number(frozen_at);
+            assert_eq!(Domains::domain_frozen_at(domain_id), Some(frozen_at));
+            let unlock_at = frozen_at + 110 + u64::from(BlockTreePruningDepth::get()) - 100;
+            assert_ok!(Domains::unlock_funds(
+                RuntimeOrigin::signed(nominator_id),
+                operator_id,
+                None
+            ));
+            System::assert_last_event(RuntimeEvent::Domains(Event::FundsUnlockScheduled {
+                operator_id,
+                nominator_id,
+                unlock_at,
+            }));
+            let withdrawal = Withdrawals::<Test>::get(operator_id, nominator_id).unwrap();
+            assert_eq!(
+                withdrawal.withdrawals[0].unlock_at_consensus_block_number,
+                Some(unlock_at)
+            );
+            assert_eq!(Balances::usable_balance(nominator_id), nominator_balance);
+
+            // The schedule is kept as is, the staking withdrawal period alone is not enough
+            HeadReceiptNumber::<Test>::insert(domain_id, 100);
+            System::set_block_number(frozen_at + u64::from(StakeWithdrawalLockingPeriod::get()));
+            assert_err!(
+                Domains::unlock_funds(RuntimeOrigin::signed(nominator_id), operator_id, None),
+                Error::<Test>::Staking(StakingError::UnlockPeriodNotComplete)
+            );
+
+            System::set_block_number(unlock_at);
+            assert_ok!(Domains::unlock_funds(
+                RuntimeOrigin::signed(nominator_id),
+                operator_id,
+                None
+            ));
+            assert!(Withdrawals::<Test>::get(operator_id, nominator_id).is_none());
+            assert_eq!(
+                Balances::usable_balance(nominator_id),
+                nominator_balance + 50 * SSC
+            );
+
+            // The operator deregistered on the frozen domain is unlocked on the consensus clock
+            // once the pending ERs are out of the challenge period
+            let challenge_period = u64::from(BlockTreePruningDepth::get());
+            let deregistered_at = System::block_number();
+            assert_ok!(Domains::deregister_operator(
+                RuntimeOrigin::signed(operator_account),
+                operator_id
+            ));
+            let operator = Operators::<Test>::get(operator_id).unwrap();
+            match operator.status::<Test>(operator_id) {
+                OperatorStatus::Deregistered(OperatorDeregisteredInfo {
+                    unlock_at_consensus_block_number,
+                    ..
+                }) => assert_eq!(
+                    *unlock_at_consensus_block_number,
+                    Some(deregistered_at + challenge_period)
+                ),
+                status => panic!("unexpected operator status: {status:?}"),
+            }
+
+            // The consensus unlock recorded at deregistration still applies after the domain
+            // resumes
+            HeadReceiptExtendedAt::<Test>::insert(domain_id, deregistered_at);
+            assert_eq!(Domains::domain_frozen_at(domain_id), None);
+            assert_err!(
+                Domains::unlock_operator(RuntimeOrigin::signed(operator_account), operator_id),
+                Error::<Test>::Staking(StakingError::UnlockPeriodNotComplete)
+            );
+
+            System::set_block_number(deregistered_at + challenge_period);
+            assert_ok!(Domains::unlock_operator(
+                RuntimeOrigin::signed(operator_account),
+                operator_id
+            ));
+            assert!(Operators::<Test>::get(operator_id).is_none());
+            assert!(Deposits::<Test>::get(operator_id, nominator_id).is_none());
+        });
+    }
 
-            let domain_obj = DomainObject {
-                owner_account_id: 0,
-                created_at: 0,
-                genesis_receipt_hash: Default::default(),
-                domain_config,
-                domain_runtime_info: Default::default(),
-            };
+    #[test]
+    fn operator_deregistered_before_freeze_unlocks_on_consensus_clock() {
+        let domain_id = DomainId::new(0);
+        let operator_account = 1;
+        let nominator_id = 2;
 
-            DomainRegistry::<Test>::insert(new_domain_id, domain_obj);
-            DomainStakingSummary::<Test>::insert(
-                new_domain_id,
-                StakingSummary {
-                    current_epoch_index: 0,
-                    current_total_stake: 0,
-                    current_operators: BTreeMap::new(),
-                    next_operators: BTreeSet::new(),
-                    current_epoch_rewards: BTreeMap::new(),
+        let mut ext = new_test_ext();
+        ext.execute_with(|| {
+            System::set_block_number(1);
+            let operator_id =
+                register_operator_and_withdraw(operator_account, nominator_id, 40 * SSC);
+            LatestConfirmedDomainBlock::<Test>::insert(
+                domain_id,
+                ConfirmedDomainBlock {
+                    block_number: 100,
+                    block_hash: Default::default(),
+                    parent_block_receipt_hash: Default::default(),
+                    state_root: Default::default(),
+                    extrinsics_root: Default::default(),
                 },
             );
+            HeadReceiptNumber::<Test>::insert(domain_id, 100);
 
-            // nominations will not work since the is frozen
-            let nominator_account = 100;
-            let nominator_stake = 100 * SSC;
-            let res = Domains::nominate_operator(
-                RuntimeOrigin::signed(nominator_account),
-                operator_id,
-                nominator_stake,
+            // The operator is deregistered before the domain is frozen, so it is unlocked on the
+            // domain clock only
+            assert_ok!(Domains::deregister_operator(
+                RuntimeOrigin::signed(operator_account),
+                operator_id
+            ));
+            let operator = Operators::<Test>::get(operator_id).unwrap();
+            assert!(matches!(
+                operator.status::<Test>(operator_id),
+                OperatorStatus::Deregistered(OperatorDeregisteredInfo {
+                    unlock_at_consensus_block_number: None,
+                    ..
+                })
+            ));
+            assert_err!(
+                Domains::unlock_operator(RuntimeOrigin::signed(operator_account), operator_id),
+                Error::<Test>::Staking(StakingError::UnlockPeriodNotComplete)
             );
+
+            // Once the domain is frozen the operator is scheduled on the consensus clock, after the
+            // challenge period of the pending ERs
+            let frozen_at = DomainFreezePeriod::get();
+            let challenge_period = u64::from(BlockTreePruningDepth::get());
+            System::set_block_number(frozen_at);
+            assert_ok!(Domains::unlock_operator(
+                RuntimeOrigin::signed(operator_account),
+                operator_id
+            ));
+            System::assert_last_event(RuntimeEvent::Domains(Event::OperatorUnlockScheduled {
+                operator_id,
+                unlock_at: frozen_at + challenge_period,
+            }));
+            assert!(Operators::<Test>::get(operator_id).is_some());
+
+            System::set_block_number(frozen_at + challenge_period - 1);
             assert_err!(
-                res,
-                Error::<Test>::Staking(crate::staking::Error::OperatorNotRegistered)
+                Domains::unlock_operator(RuntimeOrigin::signed(operator_account), operator_id),
+                Error::<Test>::Staking(StakingError::UnlockPeriodNotComplete)
             );
+
+            System::set_block_number(frozen_at + challenge_period);
+            assert_ok!(Domains::unlock_operator(
+                RuntimeOrigin::signed(operator_account),
+                operator_id
+            ));
+            assert!(Operators::<Test>::get(operator_id).is_none());
         });
     }
 
@@ -2048,9 +3721,9 @@ pub(crate) mod tests {
             if let Some((withdraw, include_ed)) = expected_withdraw {
                 let previous_usable_balance = Balances::usable_balance(nominator_id);
 
-                // staking withdrawal is 5 blocks
-                // to unlock funds, confirmed block should be atleast 105
-                let confirmed_domain_block = 105;
+                // staking withdrawal is `StakeWithdrawalLockingPeriod` blocks
+                // to unlock funds, confirmed block should be atleast 100 + locking period
+                let confirmed_domain_block = 100 + StakeWithdrawalLockingPeriod::get();
                 LatestConfirmedDomainBlock::<Test>::insert(
                     domain_id,
                     ConfirmedDomainBlock {
@@ -2061,7 +3734,7 @@ pub(crate) mod tests {
                         extrinsics_root: Default::default(),
                     },
                 );
-                assert_ok!(do_unlock_funds::<Test>(operator_id, nominator_id));
+                assert_ok!(do_unlock_funds::<Test>(operator_id, nominator_id, None));
 
                 let expected_balance = if include_ed {
                     total_balance += crate::tests::ExistentialDeposit::get();
@@ -2691,7 +4364,7 @@ pub(crate) mod tests {
             );
 
             for deposit in deposits {
-                do_nominate_operator::<Test>(operator_id, deposit.0, deposit.1).unwrap();
+                do_nominate_operator::<Test>(operator_id, deposit.0, deposit.1, false).unwrap();
             }
 
             do_slash_operators::<Test>(vec![operator_id], SlashedReason::InvalidBundle(1)).unwrap();
@@ -2718,16 +4391,19 @@ pub(crate) mod tests {
             assert_eq!(Operators::<Test>::get(operator_id), None);
             assert_eq!(OperatorIdOwner::<Test>::get(operator_id), None);
 
+            // The whole storage fee deposit, including the storage fee of the withdrawal, is
+            // refunded while the stake is slashed, the storage fee deposit is 40 for the operator
+            // and 20 for the nominator
             assert_eq!(
                 Balances::total_balance(&operator_account),
-                operator_free_balance - operator_stake
+                operator_free_balance - operator_stake + 40 * SSC
             );
             assert_eq!(
                 Balances::total_balance(&nominator_account),
-                nominator_free_balance - nominator_stake
+                nominator_free_balance - nominator_stake + 20 * SSC
             );
 
-            assert!(Balances::total_balance(&crate::tests::TreasuryAccount::get()) >= 320 * SSC);
+            assert!(Balances::total_balance(&crate::tests::TreasuryAccount::get()) >= 260 * SSC);
             assert_eq!(bundle_storage_fund::total_balance::<Test>(operator_id), 0);
         });
     }
@@ -2843,16 +4519,231 @@ pub(crate) mod tests {
             assert_eq!(Operators::<Test>::get(operator_id_3), None);
             assert_eq!(OperatorIdOwner::<Test>::get(operator_id_3), None);
 
+            // Only the stake is slashed, the storage fee deposit is refunded to the operators
             assert_eq!(
                 Balances::total_balance(&crate::tests::TreasuryAccount::get()),
-                600 * SSC
+                STORAGE_FEE_RESERVE.left_from_one() * 600 * SSC
             );
+            for operator_account in [operator_account_1, operator_account_2, operator_account_3] {
+                assert_eq!(
+                    Balances::total_balance(&operator_account),
+                    operator_free_balance - operator_stake + STORAGE_FEE_RESERVE * operator_stake
+                );
+            }
             for operator_id in [operator_id_1, operator_id_2, operator_id_3] {
                 assert_eq!(bundle_storage_fund::total_balance::<Test>(operator_id), 0);
             }
         });
     }
 
+    #[test]
+    fn slash_operator_refund_storage_fund() {
+        let domain_id = DomainId::new(0);
+        let operator_account = 1;
+        let operator_free_balance = 250 * SSC;
+        let operator_stake = 200 * SSC;
+        let nominator_account = 2;
+        let nominator_free_balance = 250 * SSC;
+        let nominator_stake = 200 * SSC;
+        let pair = OperatorPair::from_seed(&U256::from(0u32).into());
+
+        let mut ext = new_test_ext();
+        ext.execute_with(|| {
+            System::set_block_number(1);
+            let (operator_id, _) = register_operator(
+                domain_id,
+                operator_account,
+                operator_free_balance,
+                operator_stake,
+                10 * SSC,
+                pair.public(),
+                BTreeMap::from_iter(vec![(
+                    nominator_account,
+                    (nominator_free_balance, nominator_stake),
+                )]),
+            );
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+
+            // The storage fund gains 20 from the refunded storage fee, 80 + 20 in total
+            bundle_storage_fund::refund_storage_fee::<Test>(
+                20 * SSC,
+                BTreeMap::from_iter([(operator_id, 1)]),
+            )
+            .unwrap();
+            assert_eq!(
+                bundle_storage_fund::total_balance::<Test>(operator_id),
+                100 * SSC
+            );
+
+            do_slash_operators::<Test>(vec![operator_id], SlashedReason::InvalidBundle(1)).unwrap();
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+            assert_eq!(Operators::<Test>::get(operator_id), None);
+
+            // The storage fund is refunded to the nominators pro-rata to their storage fee deposit
+            for (nominator_id, free_balance, stake) in [
+                (operator_account, operator_free_balance, operator_stake),
+                (nominator_account, nominator_free_balance, nominator_stake),
+            ] {
+                assert_eq!(
+                    Balances::total_balance(&nominator_id),
+                    free_balance - stake + 50 * SSC
+                );
+                System::assert_has_event(RuntimeEvent::Domains(Event::StorageFundRefunded {
+                    operator_id,
+                    nominator_id,
+                    amount: 50 * SSC,
+                }));
+            }
+
+            // Only the stake is slashed and the storage fund account is removed
+            assert_eq!(
+                Balances::total_balance(&crate::tests::TreasuryAccount::get()),
+                320 * SSC
+            );
+            assert_eq!(bundle_storage_fund::total_balance::<Test>(operator_id), 0);
+            assert!(!System::account_exists(
+                &bundle_storage_fund::storage_fund_account::<Test>(operator_id)
+            ));
+        });
+    }
+
+    #[test]
+    fn slash_operator_with_dust_storage_fund() {
+        let domain_id = DomainId::new(0);
+        let operator_account = 1;
+        let operator_free_balance = 250 * SSC;
+        let operator_stake = 200 * SSC;
+        let nominator_account = 2;
+        let nominator_free_balance = 150 * SSC;
+        let nominator_stake = 100 * SSC;
+        let pair = OperatorPair::from_seed(&U256::from(0u32).into());
+
+        let mut ext = new_test_ext();
+        ext.execute_with(|| {
+            System::set_block_number(1);
+            let (operator_id, _) = register_operator(
+                domain_id,
+                operator_account,
+                operator_free_balance,
+                operator_stake,
+                10 * SSC,
+                pair.public(),
+                BTreeMap::from_iter(vec![(
+                    nominator_account,
+                    (nominator_free_balance, nominator_stake),
+                )]),
+            );
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+
+            // The storage fund is drained by the bundle storage fee, only dust is left which can
+            // not be refunded to any nominator
+            let storage_fund_acc = bundle_storage_fund::storage_fund_account::<Test>(operator_id);
+            Balances::set_balance(&storage_fund_acc, ExistentialDeposit::get());
+
+            do_slash_operators::<Test>(vec![operator_id], SlashedReason::InvalidBundle(1)).unwrap();
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+            assert_eq!(Operators::<Test>::get(operator_id), None);
+
+            // Nothing is refunded and the dust goes to the treasury
+            assert_eq!(
+                Balances::total_balance(&operator_account),
+                operator_free_balance - operator_stake
+            );
+            assert_eq!(
+                Balances::total_balance(&nominator_account),
+                nominator_free_balance - nominator_stake
+            );
+            assert!(!System::events().iter().any(|record| matches!(
+                record.event,
+                RuntimeEvent::Domains(Event::StorageFundRefunded { .. })
+            )));
+            assert_eq!(
+                Balances::total_balance(&crate::tests::TreasuryAccount::get()),
+                STORAGE_FEE_RESERVE.left_from_one() * 300 * SSC + ExistentialDeposit::get()
+            );
+            assert_eq!(bundle_storage_fund::total_balance::<Test>(operator_id), 0);
+            assert!(!System::account_exists(&storage_fund_acc));
+        });
+    }
+
+    #[test]
+    fn slash_operator_refund_storage_fee_of_withdrawal_and_pending_deposit() {
+        let domain_id = DomainId::new(0);
+        let operator_account = 1;
+        let operator_free_balance = 250 * SSC;
+        let operator_stake = 200 * SSC;
+        let nominator_account = 2;
+        let nominator_free_balance = 150 * SSC;
+        let nominator_stake = 100 * SSC;
+        let nominator_extra_deposit = 40 * SSC;
+        let pair = OperatorPair::from_seed(&U256::from(0u32).into());
+
+        let mut ext = new_test_ext();
+        ext.execute_with(|| {
+            System::set_block_number(1);
+            let (operator_id, _) = register_operator(
+                domain_id,
+                operator_account,
+                operator_free_balance,
+                operator_stake,
+                10 * SSC,
+                pair.public(),
+                BTreeMap::from_iter(vec![(
+                    nominator_account,
+                    (nominator_free_balance, nominator_stake),
+                )]),
+            );
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+
+            // Withdraw half of the 80 shares, the storage fee of the withdrawal is withdrawn from
+            // the storage fund and held on the nominator
+            do_withdraw_stake::<Test>(operator_id, nominator_account, 40 * SSC).unwrap();
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+            let storage_fund_hold_id =
+                HoldIdentifier::Domains(DomainsHoldIdentifier::StorageFund(operator_id));
+            assert_eq!(
+                Balances::balance_on_hold(&storage_fund_hold_id, &nominator_account),
+                10 * SSC
+            );
+
+            // The pending deposit puts 8 into the storage fund
+            do_nominate_operator::<Test>(
+                operator_id,
+                nominator_account,
+                nominator_extra_deposit,
+                false,
+            )
+            .unwrap();
+            let deposit = Deposits::<Test>::get(operator_id, nominator_account).unwrap();
+            assert_eq!(
+                deposit.pending.unwrap().storage_fee_deposit,
+                STORAGE_FEE_RESERVE * nominator_extra_deposit
+            );
+
+            do_slash_operators::<Test>(vec![operator_id], SlashedReason::InvalidBundle(1)).unwrap();
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+            assert_eq!(Operators::<Test>::get(operator_id), None);
+
+            // The storage fee of the withdrawal, the known shares and the pending deposit are all
+            // refunded to the nominator, only the stake is slashed
+            assert_eq!(
+                Balances::balance_on_hold(&storage_fund_hold_id, &nominator_account),
+                0
+            );
+            assert_eq!(
+                Balances::total_balance(&nominator_account),
+                nominator_free_balance - nominator_stake + STORAGE_FEE_RESERVE * nominator_stake
+            );
+            System::assert_has_event(RuntimeEvent::Domains(Event::StorageFundRefunded {
+                operator_id,
+                nominator_id: nominator_account,
+                amount: 10 * SSC + 10 * SSC + STORAGE_FEE_RESERVE * nominator_extra_deposit,
+            }));
+
+            assert_eq!(bundle_storage_fund::total_balance::<Test>(operator_id), 0);
+        });
+    }
+
     #[test]
     fn bundle_storage_fund_charged_and_refund_storege_fee() {
         let domain_id = DomainId::new(0);
@@ -2897,11 +4788,11 @@ pub(crate) mod tests {
             assert_eq!(bundle_storage_fund::total_balance::<Test>(operator_id), 0);
             assert_err!(
                 bundle_storage_fund::charge_bundle_storage_fee::<Test>(operator_id, 1,),
-                bundle_storage_fund::Error::BundleStorageFeePayment
+                bundle_storage_fund::Error::BundleStorageFundEmpty
             );
 
             // The operator add more stake thus add deposit to the bundle storage fund
-            do_nominate_operator::<Test>(operator_id, operator_account, 5 * SSC).unwrap();
+            do_nominate_operator::<Test>(operator_id, operator_account, 5 * SSC, false).unwrap();
             assert_eq!(bundle_storage_fund::total_balance::<Test>(operator_id), SSC);
 
             bundle_storage_fund::charge_bundle_storage_fee::<Test>(operator_id, 1).unwrap();
@@ -2909,7 +4800,7 @@ pub(crate) mod tests {
 
             // New nominator add deposit to the bundle storage fund
             Balances::set_balance(&nominator_account, 100 * SSC);
-            do_nominate_operator::<Test>(operator_id, nominator_account, 5 * SSC).unwrap();
+            do_nominate_operator::<Test>(operator_id, nominator_account, 5 * SSC, false).unwrap();
             assert_eq!(bundle_storage_fund::total_balance::<Test>(operator_id), SSC);
 
             bundle_storage_fund::charge_bundle_storage_fee::<Test>(operator_id, 1).unwrap();
@@ -2933,4 +4824,76 @@ pub(crate) mod tests {
             assert_eq!(bundle_storage_fund::total_balance::<Test>(operator_id), 0);
         });
     }
+
+    #[test]
+    fn nomination_keep_alive_free_balance_buffer() {
+        let domain_id = DomainId::new(0);
+        let operator_account = 1;
+        let operator_stake = 200 * SSC;
+        let keep_alive = SSC;
+        let pair = OperatorPair::from_seed(&U256::from(0u32).into());
+        let nominator_account = 2;
+        let nominator_stake = 10 * SSC;
+        let nominator_free_balance = nominator_stake + ExistentialDeposit::get() + keep_alive;
+
+        let mut ext = new_test_ext();
+        ext.execute_with(|| {
+            NominationKeepAlive::set(keep_alive);
+
+            // Operator registration also keeps the free balance buffer
+            let (operator_id, _) = register_operator(
+                domain_id,
+                operator_account,
+                operator_stake + ExistentialDeposit::get() + keep_alive,
+                operator_stake,
+                SSC,
+                pair.public(),
+                BTreeMap::default(),
+            );
+
+            Balances::set_balance(&nominator_account, nominator_free_balance);
+
+            // Nominating more than the buffer allows is rejected by default
+            assert_err!(
+                Domains::nominate_operator(
+                    RuntimeOrigin::signed(nominator_account),
+                    operator_id,
+                    nominator_stake + 1,
+                ),
+                Error::<Test>::Staking(StakingError::InsufficientFreeBalanceBuffer)
+            );
+            assert_err!(
+                Domains::nominate_operator_with_allow_death(
+                    RuntimeOrigin::signed(nominator_account),
+                    operator_id,
+                    nominator_stake + 1,
+                    false,
+                ),
+                Error::<Test>::Staking(StakingError::InsufficientFreeBalanceBuffer)
+            );
+
+            // Leaving exactly the buffer is allowed
+            assert_ok!(Domains::nominate_operator(
+                RuntimeOrigin::signed(nominator_account),
+                operator_id,
+                nominator_stake,
+            ));
+            assert_eq!(
+                Balances::usable_balance(nominator_account),
+                ExistentialDeposit::get() + keep_alive
+            );
+
+            // The buffer can be staked if the nominator explicitly opt-out of the check
+            assert_ok!(Domains::nominate_operator_with_allow_death(
+                RuntimeOrigin::signed(nominator_account),
+                operator_id,
+                keep_alive,
+                true,
+            ));
+            assert_eq!(
+                Balances::usable_balance(nominator_account),
+                ExistentialDeposit::get()
+            );
+        });
+    }
 }
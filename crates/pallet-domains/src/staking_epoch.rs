@@ -1,16 +1,19 @@
 //! Staking epoch transition for domain
 use crate::bundle_storage_fund::deposit_reserve_for_storage_fund;
 use crate::pallet::{
-    Deposits, DomainStakingSummary, LastEpochStakingDistribution, OperatorIdOwner, Operators,
-    PendingOperatorSwitches, PendingSlashes, PendingStakingOperationCount, Withdrawals,
+    DeferredSlashes, Deposits, DomainStakingSummary, ElectionParamsHistory, EpochSummary,
+    LastEpochStakingDistribution, LastRejectedBundleSlot, OperatorIdOwner, OperatorRejectedBundles,
+    Operators, PendingOperatorSwitches, PendingSlashes, PendingStakingOperationCount,
+    RejectedBundleCount, Withdrawals,
 };
 use crate::staking::{
-    do_convert_previous_epoch_deposits, do_convert_previous_epoch_withdrawal, DomainEpoch,
-    Error as TransitionError, OperatorStatus, SharePrice, WithdrawalInShares,
+    do_convert_previous_epoch_deposits, do_convert_previous_epoch_withdrawal, is_slash_deferred,
+    remove_operator_metadata, remove_operator_tax, schedule_slash_evidence_expiry, DomainEpoch,
+    Error as TransitionError, OperatorEpochSummary, OperatorStatus, SharePrice, WithdrawalInShares,
 };
 use crate::{
     bundle_storage_fund, BalanceOf, Config, ElectionVerificationParams, Event, HoldIdentifier,
-    OperatorEpochSharePrice, Pallet,
+    OperatorEpochSharePrice, OperatorEpochTaxPerShare, OperatorTaxPerShare, Pallet,
 };
 use codec::{Decode, Encode};
 use frame_support::traits::fungible::{InspectHold, Mutate, MutateHold};
@@ -20,7 +23,7 @@ use scale_info::TypeInfo;
 use sp_core::Get;
 use sp_domains::{DomainId, EpochIndex, OperatorId};
 use sp_runtime::traits::{CheckedAdd, CheckedSub, One, Zero};
-use sp_runtime::Saturating;
+use sp_runtime::{FixedPointNumber, FixedU128, SaturatedConversion, Saturating};
 use sp_std::collections::btree_map::BTreeMap;
 use sp_std::collections::btree_set::BTreeSet;
 
@@ -30,6 +33,7 @@ pub enum Error {
     FinalizeDomainEpochStaking(TransitionError),
     OperatorRewardStaking(TransitionError),
     SlashOperator(TransitionError),
+    PendingEpochTransition,
 }
 
 pub(crate) struct EpochTransitionResult {
@@ -51,8 +55,8 @@ pub(crate) fn do_finalize_domain_current_epoch<T: Config>(
     let rewarded_operator_count = operator_take_reward_tax_and_stake::<T>(domain_id)?;
 
     // slash the operators
-    let slashed_nominator_count =
-        do_finalize_slashed_operators::<T>(domain_id).map_err(Error::SlashOperator)?;
+    let (_, slashed_nominator_count) =
+        do_finalize_slashed_operators::<T>(domain_id, u32::MAX).map_err(Error::SlashOperator)?;
 
     // finalize any operator switches
     do_finalize_switch_operator_domain::<T>(domain_id)?;
@@ -91,6 +95,17 @@ pub(crate) fn operator_take_reward_tax_and_stake<T: Config>(
                 // calculate operator tax, mint the balance, and stake them
                 let operator_tax_amount = operator.nomination_tax.mul_floor(reward);
                 if !operator_tax_amount.is_zero() {
+                    // accumulate the tax per share of the epoch, so the tax paid by each nominator
+                    // can be derived from the shares they held, see `TaxPaid`
+                    let epoch_tax_per_share = FixedU128::checked_from_rational(
+                        operator_tax_amount.saturated_into::<u128>(),
+                        operator.current_total_shares.saturated_into::<u128>(),
+                    )
+                    .unwrap_or_default();
+                    OperatorTaxPerShare::<T>::mutate(operator_id, |tax_per_share| {
+                        *tax_per_share = tax_per_share.saturating_add(epoch_tax_per_share)
+                    });
+
                     let nominator_id = OperatorIdOwner::<T>::get(operator_id)
                         .ok_or(TransitionError::MissingOperatorOwner)?;
                     T::Currency::mint_into(&nominator_id, operator_tax_amount)
@@ -127,6 +142,12 @@ pub(crate) fn operator_take_reward_tax_and_stake<T: Config>(
                             operator_tax_deposit,
                         )?;
 
+                    Pallet::<T>::note_domain_operator_tax(
+                        domain_id,
+                        stake_summary.current_epoch_index,
+                        operator_tax_amount,
+                    );
+
                     Pallet::<T>::deposit_event(Event::OperatorTaxCollected {
                         operator_id,
                         tax: operator_tax_amount,
@@ -238,15 +259,22 @@ pub(crate) fn do_finalize_domain_epoch_staking<T: Config>(
                 previous_epoch,
             )?;
 
-            total_domain_stake = total_domain_stake
-                .checked_add(&operator_stake)
-                .ok_or(TransitionError::BalanceOverflow)?;
-            current_operators.insert(*next_operator_id, operator_stake);
             next_operators.insert(*next_operator_id);
 
             if stake_changed {
                 finalized_operator_count += 1;
             }
+
+            // Paused operator keeps its staking finalized, but it is not elected (and its stake
+            // doesn't count towards the domain stake) until it is resumed
+            if Operators::<T>::get(next_operator_id).is_some_and(|operator| operator.paused) {
+                continue;
+            }
+
+            total_domain_stake = total_domain_stake
+                .checked_add(&operator_stake)
+                .ok_or(TransitionError::BalanceOverflow)?;
+            current_operators.insert(*next_operator_id, operator_stake);
         }
 
         let election_verification_params = ElectionVerificationParams {
@@ -254,6 +282,20 @@ pub(crate) fn do_finalize_domain_epoch_staking<T: Config>(
             total_domain_stake: stake_summary.current_total_stake,
         };
 
+        // Keep the stake distribution of the completed epoch in the history and prune the
+        // expired one
+        ElectionParamsHistory::<T>::insert(
+            domain_id,
+            previous_epoch,
+            election_verification_params.clone(),
+        );
+        if let Some(expired_epoch) =
+            previous_epoch.checked_sub(Pallet::<T>::election_params_history_depth())
+        {
+            ElectionParamsHistory::<T>::remove(domain_id, expired_epoch);
+        }
+        Pallet::<T>::prune_domain_epoch_fees(domain_id, next_epoch);
+
         LastEpochStakingDistribution::<T>::insert(domain_id, election_verification_params);
 
         let previous_epoch = stake_summary.current_epoch_index;
@@ -285,6 +327,12 @@ pub(crate) fn do_finalize_operator_epoch_staking<T: Config>(
         return Err(TransitionError::OperatorNotRegistered);
     }
 
+    // prune the epoch summary that falls out of the history
+    let summary_history_depth = T::OperatorEpochSummaryHistoryDepth::get();
+    if let Some(expired_epoch) = previous_epoch.checked_sub(summary_history_depth) {
+        EpochSummary::<T>::remove(operator_id, DomainEpoch::from((domain_id, expired_epoch)));
+    }
+
     // if there are no deposits, withdrawls, and epoch rewards for this operator
     // then short-circuit and return early.
     if operator.deposits_in_epoch.is_zero()
@@ -304,10 +352,10 @@ pub(crate) fn do_finalize_operator_epoch_staking<T: Config>(
     let share_price = SharePrice::new::<T>(total_shares, total_stake);
 
     // calculate and subtract total withdrew shares from previous epoch
+    let withdrawals_finalized = share_price.shares_to_stake::<T>(operator.withdrawals_in_epoch);
     let (total_stake, total_shares) = if !operator.withdrawals_in_epoch.is_zero() {
-        let withdraw_stake = share_price.shares_to_stake::<T>(operator.withdrawals_in_epoch);
         let total_stake = total_stake
-            .checked_sub(&withdraw_stake)
+            .checked_sub(&withdrawals_finalized)
             .ok_or(TransitionError::BalanceUnderflow)?;
         let total_shares = total_shares
             .checked_sub(&operator.withdrawals_in_epoch)
@@ -320,6 +368,7 @@ pub(crate) fn do_finalize_operator_epoch_staking<T: Config>(
     };
 
     // calculate and add total deposits from the previous epoch
+    let deposits_converted = operator.deposits_in_epoch;
     let (total_stake, total_shares) = if !operator.deposits_in_epoch.is_zero() {
         let deposited_shares = share_price.stake_to_shares::<T>(operator.deposits_in_epoch);
         let total_stake = total_stake
@@ -341,7 +390,12 @@ pub(crate) fn do_finalize_operator_epoch_staking<T: Config>(
     OperatorEpochSharePrice::<T>::insert(
         operator_id,
         DomainEpoch::from((domain_id, previous_epoch)),
-        share_price,
+        share_price.clone(),
+    );
+    OperatorEpochTaxPerShare::<T>::insert(
+        operator_id,
+        DomainEpoch::from((domain_id, previous_epoch)),
+        OperatorTaxPerShare::<T>::get(operator_id),
     );
 
     // update operator state
@@ -350,6 +404,30 @@ pub(crate) fn do_finalize_operator_epoch_staking<T: Config>(
     operator.current_epoch_rewards = Zero::zero();
     Operators::<T>::set(operator_id, Some(operator));
 
+    let epoch_summary = OperatorEpochSummary {
+        total_stake,
+        total_shares,
+        share_price,
+        deposits_converted,
+        withdrawals_finalized,
+    };
+    if !summary_history_depth.is_zero() {
+        EpochSummary::<T>::insert(
+            operator_id,
+            DomainEpoch::from((domain_id, previous_epoch)),
+            epoch_summary.clone(),
+        );
+    }
+    Pallet::<T>::deposit_event(Event::OperatorEpochFinalized {
+        operator_id,
+        epoch_index: previous_epoch,
+        new_total_stake: epoch_summary.total_stake,
+        new_total_shares: epoch_summary.total_shares,
+        share_price: epoch_summary.share_price,
+        deposits_converted: epoch_summary.deposits_converted,
+        withdrawals_finalized: epoch_summary.withdrawals_finalized,
+    });
+
     Ok((total_stake, true))
 }
 
@@ -365,11 +443,37 @@ pub(crate) fn mint_funds<T: Config>(
     Ok(())
 }
 
+/// Finalizes at most `max_operators` of the pending slashes that are not deferred, the rest are
+/// kept pending.
+///
+/// Returns the number of the finalized operators and the number of the slashed nominators.
 pub(crate) fn do_finalize_slashed_operators<T: Config>(
     domain_id: DomainId,
-) -> Result<u32, TransitionError> {
+    max_operators: u32,
+) -> Result<(u32, u32), TransitionError> {
     let mut slashed_nominator_count = 0;
-    for operator_id in PendingSlashes::<T>::take(domain_id).unwrap_or_default() {
+
+    // the deferred slashes are kept pending until the defer duration is passed
+    let (mut kept_slashes, finalizable_slashes): (BTreeSet<_>, BTreeSet<_>) =
+        PendingSlashes::<T>::take(domain_id)
+            .unwrap_or_default()
+            .into_iter()
+            .partition(|operator_id| is_slash_deferred::<T>(domain_id, *operator_id));
+    let mut finalized_slashes = BTreeSet::new();
+    for operator_id in finalizable_slashes {
+        if finalized_slashes.len() < max_operators as usize {
+            finalized_slashes.insert(operator_id);
+        } else {
+            kept_slashes.insert(operator_id);
+        }
+    }
+    if !kept_slashes.is_empty() {
+        PendingSlashes::<T>::insert(domain_id, kept_slashes);
+    }
+    let finalized_operator_count = finalized_slashes.len() as u32;
+
+    for operator_id in finalized_slashes {
+        DeferredSlashes::<T>::remove(operator_id);
         Operators::<T>::try_mutate_exists(operator_id, |maybe_operator| {
             // take the operator so this operator info is removed once we slash the operator.
             let operator = maybe_operator
@@ -379,6 +483,23 @@ pub(crate) fn do_finalize_slashed_operators<T: Config>(
             // remove OperatorOwner Details
             OperatorIdOwner::<T>::remove(operator_id);
 
+            // remove the operator metadata and release its deposit
+            remove_operator_metadata::<T>(operator_id)?;
+
+            // keep the slash evidence for a while after the operator is removed
+            schedule_slash_evidence_expiry::<T>(operator_id);
+
+            // remove the rejected bundle records along with the operator
+            RejectedBundleCount::<T>::remove(operator_id);
+            OperatorRejectedBundles::<T>::remove(operator_id);
+            LastRejectedBundleSlot::<T>::remove(operator_id);
+
+            // remove the operator tax paid by the nominators along with the operator
+            remove_operator_tax::<T>(operator_id);
+
+            // remove the epoch summaries along with the operator
+            let _ = EpochSummary::<T>::clear_prefix(operator_id, u32::MAX, None);
+
             let staked_hold_id = T::HoldIdentifier::staking_staked(operator_id);
             let mut total_stake = operator
                 .current_total_stake
@@ -387,7 +508,6 @@ pub(crate) fn do_finalize_slashed_operators<T: Config>(
             let total_shares = operator.current_total_shares;
             let share_price = SharePrice::new::<T>(total_shares, total_stake);
 
-            let storage_fund_hold_id = T::HoldIdentifier::storage_fund_withdrawal(operator_id);
             let storage_fund_redeem_price = bundle_storage_fund::storage_fund_redeem_price::<T>(
                 operator_id,
                 operator.total_storage_fee_deposit,
@@ -469,36 +589,25 @@ pub(crate) fn do_finalize_slashed_operators<T: Config>(
                     T::Currency::release_all(&staked_hold_id, &nominator_id, Precision::BestEffort)
                         .map_err(|_| TransitionError::RemoveLock)?;
 
-                    // Transfer the deposited unstaked storage fee back to nominator
-                    if let Some(pending_deposit) = deposit.pending {
-                        let storage_fee_deposit = bundle_storage_fund::withdraw_and_hold::<T>(
-                            operator_id,
-                            &nominator_id,
-                            storage_fund_redeem_price.redeem(pending_deposit.storage_fee_deposit),
-                        )
-                        .map_err(TransitionError::BundleStorageFund)?;
-                        T::Currency::release(
-                            &storage_fund_hold_id,
-                            &nominator_id,
-                            storage_fee_deposit,
-                            Precision::Exact,
+                    // Refund the storage fee deposit of the known shares and the pending deposit, as
+                    // well as the storage fee of any withdrawal, back to nominator pro-rata, the
+                    // storage fund is never part of the stake thus it is not slashed
+                    let storage_fee_deposit = deposit
+                        .known
+                        .storage_fee_deposit
+                        .checked_add(
+                            &deposit
+                                .pending
+                                .map(|pending_deposit| pending_deposit.storage_fee_deposit)
+                                .unwrap_or_default(),
                         )
-                        .map_err(|_| TransitionError::RemoveLock)?;
-                    }
-
-                    // Transfer all the storage fee on withdraw to the treasury
-                    let withdraw_storage_fee_on_hold =
-                        T::Currency::balance_on_hold(&storage_fund_hold_id, &nominator_id);
-                    T::Currency::transfer_on_hold(
-                        &storage_fund_hold_id,
+                        .ok_or(TransitionError::BalanceOverflow)?;
+                    bundle_storage_fund::refund_to_contributor::<T>(
+                        operator_id,
                         &nominator_id,
-                        &T::TreasuryAccount::get(),
-                        withdraw_storage_fee_on_hold,
-                        Precision::Exact,
-                        Restriction::Free,
-                        Fortitude::Force,
+                        storage_fund_redeem_price.redeem(storage_fee_deposit),
                     )
-                    .map_err(|_| TransitionError::RemoveLock)?;
+                    .map_err(TransitionError::BundleStorageFund)?;
 
                     slashed_nominator_count += 1;
 
@@ -509,7 +618,8 @@ pub(crate) fn do_finalize_slashed_operators<T: Config>(
             // mint any gains to treasury account
             mint_funds::<T>(&T::TreasuryAccount::get(), total_stake)?;
 
-            // Transfer all the storage fund to treasury
+            // Transfer the remaining storage fund (i.e. rounding dust and refund that is below
+            // the existential deposit) to treasury, the storage fund account should be empty after
             bundle_storage_fund::transfer_all_to_treasury::<T>(operator_id)
                 .map_err(TransitionError::BundleStorageFund)?;
 
@@ -517,28 +627,30 @@ pub(crate) fn do_finalize_slashed_operators<T: Config>(
         })?;
     }
 
-    Ok(slashed_nominator_count)
+    Ok((finalized_operator_count, slashed_nominator_count))
 }
 
 #[cfg(test)]
 mod tests {
     use crate::bundle_storage_fund::STORAGE_FEE_RESERVE;
     use crate::pallet::{
-        Deposits, DomainStakingSummary, LastEpochStakingDistribution, LatestConfirmedDomainBlock,
-        NominatorCount, OperatorIdOwner, OperatorSigningKey, Operators, Withdrawals,
+        Deposits, DomainStakingSummary, EpochSummary, LastEpochStakingDistribution,
+        LatestConfirmedDomainBlock, NominatorCount, OperatorIdOwner, OperatorSigningKey, Operators,
+        Withdrawals,
     };
     use crate::staking::tests::{register_operator, Share};
     use crate::staking::{
-        do_deregister_operator, do_nominate_operator, do_reward_operators, do_unlock_operator,
-        do_withdraw_stake,
+        do_convert_previous_epoch_deposits, do_deregister_operator, do_nominate_operator,
+        do_reward_operators, do_unlock_operator, do_withdraw_stake, DomainEpoch, SharePrice,
     };
     use crate::staking_epoch::{
         do_finalize_domain_current_epoch, operator_take_reward_tax_and_stake,
     };
-    use crate::tests::{new_test_ext, Test};
-    use crate::{BalanceOf, Config, HoldIdentifier, NominatorId};
+    use crate::tests::{new_test_ext, RuntimeEvent, Test};
+    use crate::{BalanceOf, Config, Event, HoldIdentifier, NominatorId, Pallet};
     use frame_support::assert_ok;
     use frame_support::traits::fungible::InspectHold;
+    use frame_support::traits::Get;
     use sp_core::{Pair, U256};
     use sp_domains::{ConfirmedDomainBlock, DomainId, OperatorPair};
     use sp_runtime::traits::Zero;
@@ -547,6 +659,7 @@ mod tests {
     use subspace_runtime_primitives::SSC;
 
     type Balances = pallet_balances::Pallet<Test>;
+    type System = frame_system::Pallet<Test>;
 
     // TODO: `switch_domain` is not supported currently due to incompatible with lazily slashing
     // enable this test when `switch_domain` is ready
@@ -666,8 +779,13 @@ mod tests {
 
             // add pending deposits
             for pending_deposit in &pending_deposits {
-                do_nominate_operator::<Test>(operator_id, pending_deposit.0, pending_deposit.1)
-                    .unwrap();
+                do_nominate_operator::<Test>(
+                    operator_id,
+                    pending_deposit.0,
+                    pending_deposit.1,
+                    false,
+                )
+                .unwrap();
             }
 
             for (nominator_id, shares) in withdrawals {
@@ -676,7 +794,7 @@ mod tests {
 
             if !rewards.is_zero() {
                 do_reward_operators::<Test>(domain_id, vec![operator_id].into_iter(), rewards)
-                    .unwrap()
+                    .unwrap();
             }
 
             // de-register operator
@@ -696,9 +814,9 @@ mod tests {
             // finalize and add to pending operator unlocks
             do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
 
-            // staking withdrawal is 5 blocks,
-            // to unlock funds, confirmed block should be atleast 105
-            let domain_block_number = 105;
+            // staking withdrawal is `StakeWithdrawalLockingPeriod` blocks,
+            // to unlock funds, confirmed block should be atleast 100 + locking period
+            let domain_block_number = 100 + <Test as Config>::StakeWithdrawalLockingPeriod::get();
             LatestConfirmedDomainBlock::<Test>::insert(
                 domain_id,
                 ConfirmedDomainBlock {
@@ -813,7 +931,7 @@ mod tests {
 
             let mut total_new_deposit = BalanceOf::<Test>::zero();
             for deposit in &deposits {
-                do_nominate_operator::<Test>(operator_id, deposit.0, deposit.1).unwrap();
+                do_nominate_operator::<Test>(operator_id, deposit.0, deposit.1, false).unwrap();
                 total_new_deposit += deposit.1;
             }
 
@@ -940,4 +1058,186 @@ mod tests {
             assert!(domain_stake_summary.current_epoch_rewards.is_empty())
         });
     }
+
+    #[test]
+    fn nominator_tax_paid() {
+        let domain_id = DomainId::new(0);
+        let operator_account = 1;
+        let nominator_account = 2;
+        let new_nominator_account = 3;
+        let pair = OperatorPair::from_seed(&U256::from(0u32).into());
+        let operator_rewards = 10 * SSC;
+        let nomination_tax = Percent::from_parts(10);
+        let operator_tax = nomination_tax.mul_floor(operator_rewards);
+
+        let mut ext = new_test_ext();
+        ext.execute_with(|| {
+            let (operator_id, _) = register_operator(
+                domain_id,
+                operator_account,
+                200 * SSC,
+                100 * SSC,
+                10 * SSC,
+                pair.public(),
+                BTreeMap::from_iter(vec![(nominator_account, (60 * SSC, 50 * SSC))]),
+            );
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+
+            let mut operator = Operators::<Test>::get(operator_id).unwrap();
+            operator.nomination_tax = nomination_tax;
+            Operators::<Test>::insert(operator_id, operator);
+
+            let shares_of = |nominator_id: NominatorId<Test>| {
+                let mut deposit = Deposits::<Test>::get(operator_id, nominator_id).unwrap();
+                do_convert_previous_epoch_deposits::<Test>(operator_id, &mut deposit).unwrap();
+                deposit.known.shares
+            };
+            let tax_of = |shares: Share, total_shares: Share| operator_tax * shares / total_shares;
+            // the accumulated tax per share is rounded down
+            let assert_tax_paid =
+                |nominator_id: NominatorId<Test>, expected_tax: BalanceOf<Test>| {
+                    let tax_paid = Pallet::<Test>::nominator_tax_paid(operator_id, nominator_id);
+                    assert!(tax_paid <= expected_tax && expected_tax - tax_paid <= 2);
+                };
+
+            // first epoch, only the initial nominators bear the tax
+            let total_shares = Operators::<Test>::get(operator_id)
+                .unwrap()
+                .current_total_shares;
+            let nominator_shares = shares_of(nominator_account);
+            do_reward_operators::<Test>(domain_id, vec![operator_id].into_iter(), operator_rewards)
+                .unwrap();
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+            let mut expected_nominator_tax = tax_of(nominator_shares, total_shares);
+            assert!(!expected_nominator_tax.is_zero());
+            assert_tax_paid(nominator_account, expected_nominator_tax);
+
+            // a nominator joining mid-way does not bear the tax of the epoch it deposits in
+            Balances::set_balance(&new_nominator_account, 100 * SSC);
+            do_nominate_operator::<Test>(operator_id, new_nominator_account, 50 * SSC, false)
+                .unwrap();
+            let total_shares = Operators::<Test>::get(operator_id)
+                .unwrap()
+                .current_total_shares;
+            do_reward_operators::<Test>(domain_id, vec![operator_id].into_iter(), operator_rewards)
+                .unwrap();
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+            expected_nominator_tax += tax_of(nominator_shares, total_shares);
+            assert_tax_paid(nominator_account, expected_nominator_tax);
+            assert_tax_paid(new_nominator_account, 0);
+
+            // the new nominator bears the tax from the next epoch
+            let total_shares = Operators::<Test>::get(operator_id)
+                .unwrap()
+                .current_total_shares;
+            let new_nominator_shares = shares_of(new_nominator_account);
+            do_reward_operators::<Test>(domain_id, vec![operator_id].into_iter(), operator_rewards)
+                .unwrap();
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+            expected_nominator_tax += tax_of(nominator_shares, total_shares);
+            let expected_new_nominator_tax = tax_of(new_nominator_shares, total_shares);
+            assert!(!expected_new_nominator_tax.is_zero());
+            assert_tax_paid(nominator_account, expected_nominator_tax);
+            assert_tax_paid(new_nominator_account, expected_new_nominator_tax);
+
+            // settling the tax paid when the deposit changes does not change the tax paid
+            do_nominate_operator::<Test>(operator_id, new_nominator_account, 5 * SSC, false)
+                .unwrap();
+            assert_tax_paid(new_nominator_account, expected_new_nominator_tax);
+        });
+    }
+
+    #[test]
+    fn operator_epoch_finalized() {
+        let domain_id = DomainId::new(0);
+        let operator_account = 1;
+        let nominator_account = 2;
+        let new_nominator_account = 3;
+        let pair = OperatorPair::from_seed(&U256::from(0u32).into());
+        let operator_rewards = 10 * SSC;
+        let new_deposit = 50 * SSC;
+        let shares_withdrew = 10 * SSC;
+
+        let mut ext = new_test_ext();
+        ext.execute_with(|| {
+            System::set_block_number(1);
+            let (operator_id, _) = register_operator(
+                domain_id,
+                operator_account,
+                200 * SSC,
+                100 * SSC,
+                10 * SSC,
+                pair.public(),
+                BTreeMap::from_iter(vec![(nominator_account, (60 * SSC, 50 * SSC))]),
+            );
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+
+            // transition an epoch with a deposit, a withdrawal and a reward
+            Balances::set_balance(&new_nominator_account, 100 * SSC);
+            do_nominate_operator::<Test>(operator_id, new_nominator_account, new_deposit, false)
+                .unwrap();
+            do_withdraw_stake::<Test>(operator_id, nominator_account, shares_withdrew).unwrap();
+            do_reward_operators::<Test>(domain_id, vec![operator_id].into_iter(), operator_rewards)
+                .unwrap();
+
+            let epoch_index = DomainStakingSummary::<Test>::get(domain_id)
+                .unwrap()
+                .current_epoch_index;
+            let previous_operator = Operators::<Test>::get(operator_id).unwrap();
+            System::reset_events();
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+
+            let operator = Operators::<Test>::get(operator_id).unwrap();
+            let epoch_summary =
+                EpochSummary::<Test>::get(operator_id, DomainEpoch::from((domain_id, epoch_index)))
+                    .unwrap();
+            let share_price = SharePrice::new::<Test>(
+                previous_operator.current_total_shares,
+                previous_operator.current_total_stake + operator_rewards,
+            );
+            assert_eq!(epoch_summary.share_price, share_price);
+            assert_eq!(
+                epoch_summary.deposits_converted,
+                STORAGE_FEE_RESERVE.left_from_one() * new_deposit
+            );
+            assert_eq!(
+                epoch_summary.withdrawals_finalized,
+                share_price.shares_to_stake::<Test>(shares_withdrew)
+            );
+            assert!(epoch_summary.withdrawals_finalized > shares_withdrew);
+
+            // the summary matches the operator after the transition
+            assert_eq!(epoch_summary.total_stake, operator.current_total_stake);
+            assert_eq!(epoch_summary.total_shares, operator.current_total_shares);
+            assert_eq!(
+                operator.current_total_stake,
+                previous_operator.current_total_stake + operator_rewards
+                    - epoch_summary.withdrawals_finalized
+                    + epoch_summary.deposits_converted
+            );
+
+            // only one event is emitted for the operator and it matches the summary
+            let events: Vec<_> = System::events()
+                .into_iter()
+                .filter_map(|record| match record.event {
+                    RuntimeEvent::Domains(event @ Event::OperatorEpochFinalized { .. }) => {
+                        Some(event)
+                    }
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(
+                events,
+                vec![Event::OperatorEpochFinalized {
+                    operator_id,
+                    epoch_index,
+                    new_total_stake: epoch_summary.total_stake,
+                    new_total_shares: epoch_summary.total_shares,
+                    share_price: epoch_summary.share_price,
+                    deposits_converted: epoch_summary.deposits_converted,
+                    withdrawals_finalized: epoch_summary.withdrawals_finalized,
+                }]
+            );
+        });
+    }
 }
@@ -1,17 +1,27 @@
-use crate::block_tree::BlockTreeNode;
+use crate::block_tree::{BlockTreeNode, Error as BlockTreeError};
+use crate::bundle_storage_fund::Error as BundleStorageFundError;
 use crate::domain_registry::{DomainConfig, DomainObject};
-use crate::staking::Operator;
+use crate::staking::{
+    note_slash_evidence, schedule_slash_evidence_expiry, Operator, OperatorStatus,
+};
+use crate::staking_epoch::do_finalize_domain_epoch_staking;
 use crate::{
-    self as pallet_domains, BalanceOf, BlockSlot, BlockTree, BlockTreeNodes, BundleError, Config,
-    ConsensusBlockHash, DomainBlockNumberFor, DomainHashingFor, DomainRegistry, ExecutionInbox,
-    ExecutionReceiptOf, FraudProofError, FungibleHoldId, HeadReceiptNumber, NextDomainId,
-    Operators, ReceiptHashFor,
+    self as pallet_domains, bundle_storage_fund, BalanceOf, BlockSlot, BlockTree, BlockTreeNodes,
+    BundleError, BundleSignatureVerifier, Config, ConsensusBlockHash,
+    DefaultBundleSignatureVerifier, DomainBlockNumberFor, DomainHashingFor, DomainRegistry,
+    DomainStakingSummary, DomainTxRangeState, ElectionVerificationParams, Event, ExecutionInbox,
+    ExecutionReceiptOf, FraudProofError, FungibleHoldId, HeadDomainNumber, HeadReceiptNumber,
+    InboxedBundleAuthor, LastEpochStakingDistribution, LastRejectedBundleSlot, NextDomainId,
+    OperatorIdOwner, Operators, PendingEpochTransition, PendingEpochTransitionInfo, PendingSlashes,
+    ReceiptHashFor, SealedBundleHeaderOf, SlashEvidenceOf, TxRangeState,
 };
 use codec::{Decode, Encode, MaxEncodedLen};
+use core::cell::RefCell;
 use core::mem;
 use domain_runtime_primitives::opaque::Header as DomainHeader;
 use domain_runtime_primitives::BlockNumber as DomainBlockNumber;
-use frame_support::dispatch::{DispatchInfo, RawOrigin};
+use frame_support::dispatch::{DispatchInfo, Pays, RawOrigin};
+use frame_support::inherent::{InherentData, ProvideInherent};
 use frame_support::traits::{ConstU64, Currency, Hooks, VariantCount};
 use frame_support::weights::constants::ParityDbWeight;
 use frame_support::weights::{IdentityFee, Weight};
@@ -19,36 +29,56 @@ use frame_support::{assert_err, assert_ok, derive_impl, parameter_types, PalletI
 use frame_system::mocking::MockUncheckedExtrinsic;
 use frame_system::pallet_prelude::*;
 use scale_info::TypeInfo;
-use sp_core::crypto::Pair;
+use sp_core::crypto::{Pair, VrfSecret, Wraps};
 use sp_core::storage::{StateVersion, StorageKey};
 use sp_core::{Get, H256, U256};
+use sp_domains::bundle_producer_election::{
+    check_proof_of_election, make_transcript, BundleProducerElectionParams, EpochElectionParams,
+    ProofOfElectionError,
+};
+use sp_domains::inherents::{
+    InherentType, RejectedBundle, RejectedBundleReason, RejectedBundleRecord, RejectedBundles,
+    INHERENT_IDENTIFIER, MAX_REJECTED_BUNDLES_PER_BLOCK,
+};
 use sp_domains::merkle_tree::MerkleTree;
 use sp_domains::proof_provider_and_verifier::StorageProofProvider;
 use sp_domains::storage::RawGenesis;
 use sp_domains::{
-    BundleHeader, ChainId, DomainId, DomainsHoldIdentifier, ExecutionReceipt, ExtrinsicDigest,
-    InboxedBundle, InvalidBundleType, OpaqueBundle, OperatorAllowList, OperatorId, OperatorPair,
-    ProofOfElection, RuntimeType, SealedBundleHeader, StakingHoldIdentifier,
+    BadReceiptMismatch, BundleDigest, BundleHeader, ChainId, DomainBlockLimit, DomainFees,
+    DomainId, DomainsHoldIdentifier, EffectiveDomainParams, EpochIndex, ExecutionReceipt,
+    ExtrinsicDigest, InboxedBundle, InvalidBundleType, OpaqueBundle, OperatorAllowList, OperatorId,
+    OperatorPair, OperatorPublicKey, OperatorSignature, ProofOfElection, RuntimeType,
+    SealedBundleHeader, SlashEvidence, StakingHoldIdentifier,
 };
 use sp_domains_fraud_proof::fraud_proof::{
     FraudProof, InvalidBlockFeesProof, InvalidBundlesFraudProof, InvalidDomainBlockHashProof,
     InvalidExtrinsicsRootProof, ValidBundleDigest,
 };
 use sp_domains_fraud_proof::{
-    DomainChainAllowlistUpdateExtrinsic, FraudProofExtension, FraudProofHostFunctions,
-    FraudProofVerificationInfoRequest, FraudProofVerificationInfoResponse, SetCodeExtrinsic,
+    ConsensusBlockMmrProofVerifier, DomainChainAllowlistUpdateExtrinsic,
+    EvmContractCreationAllowListUpdateExtrinsic, FraudProofExtension, FraudProofHostFunctions,
+    FraudProofVerificationInfoRequest, FraudProofVerificationInfoResponse, InvalidTransactionCode,
+    SetCodeExtrinsic,
 };
+use sp_keystore::testing::MemoryKeystore;
+use sp_keystore::KeystoreExt;
+use sp_messenger::messages::ConsensusChainMmrLeafProof;
+use sp_mmr_primitives::{EncodableOpaqueLeaf, Proof as MmrProof};
 use sp_runtime::traits::{
     AccountIdConversion, BlakeTwo256, BlockNumberProvider, Hash as HashT, IdentityLookup, One,
+    ValidateUnsigned,
 };
-use sp_runtime::{BuildStorage, Digest, OpaqueExtrinsic, Saturating};
+use sp_runtime::transaction_validity::{InvalidTransaction, TransactionSource};
+use sp_runtime::{BuildStorage, Digest, OpaqueExtrinsic, Percent, Perquintill, Saturating};
 use sp_state_machine::backend::AsTrieBackend;
 use sp_state_machine::{prove_read, Backend, TrieBackendBuilder};
+use sp_std::collections::btree_map::BTreeMap;
+use sp_std::collections::btree_set::BTreeSet;
 use sp_std::sync::Arc;
 use sp_trie::trie_types::TrieDBMutBuilderV1;
 use sp_trie::{LayoutV1, PrefixedMemoryDB, StorageProof, TrieMut};
 use sp_version::RuntimeVersion;
-use subspace_core_primitives::{Randomness, U256 as P256};
+use subspace_core_primitives::{PotOutput, Randomness, U256 as P256};
 use subspace_runtime_primitives::{Moment, StorageFee, SSC};
 
 type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
@@ -91,12 +121,19 @@ parameter_types! {
     pub const InitialDomainTxRange: u64 = 3;
     pub const DomainTxRangeAdjustmentInterval: u64 = 100;
     pub const DomainRuntimeUpgradeDelay: BlockNumber = 100;
+    pub const MinRuntimeUpgradeDelay: BlockNumber = 10;
+    pub const MaxRuntimeUpgradeDelay: BlockNumber = 1_000;
     pub const MaxBundlesPerBlock: u32 = 10;
+    pub const MaxBundleExtrinsics: u32 = 16;
+    pub const MinBundleSize: u32 = 64;
+    pub const MinBundleWeight: Weight = Weight::from_parts(64, 0);
     pub const MaxDomainBlockSize: u32 = 1024 * 1024;
     pub const MaxDomainBlockWeight: Weight = Weight::from_parts(1024 * 1024, 0);
     pub const DomainInstantiationDeposit: Balance = 100;
     pub const MaxDomainNameLength: u32 = 16;
+    pub const MaxDomainsPerOwner: u32 = 3;
     pub const BlockTreePruningDepth: u32 = 16;
+    pub const MaxReceiptGap: u32 = 4;
     pub const SlotProbability: (u64, u64) = (1, 6);
 }
 
@@ -129,6 +166,10 @@ impl pallet_domains::HoldIdentifier<Test> for HoldIdentifier {
     fn storage_fund_withdrawal(operator_id: OperatorId) -> Self {
         Self::Domains(DomainsHoldIdentifier::StorageFund(operator_id))
     }
+
+    fn operator_metadata_deposit(operator_id: OperatorId) -> Self {
+        Self::Domains(DomainsHoldIdentifier::OperatorMetadata(operator_id))
+    }
 }
 
 impl VariantCount for HoldIdentifier {
@@ -137,7 +178,29 @@ impl VariantCount for HoldIdentifier {
 
 parameter_types! {
     pub const MaxHolds: u32 = 10;
-    pub const ExistentialDeposit: Balance = 1;
+}
+
+thread_local! {
+    static EXISTENTIAL_DEPOSIT: RefCell<Balance> = const { RefCell::new(1) };
+}
+
+/// Existential deposit that can be overridden by the test, default to 1.
+pub struct ExistentialDeposit;
+
+impl ExistentialDeposit {
+    pub fn get() -> Balance {
+        EXISTENTIAL_DEPOSIT.with(|v| *v.borrow())
+    }
+
+    pub(crate) fn set(existential_deposit: Balance) {
+        EXISTENTIAL_DEPOSIT.with(|v| *v.borrow_mut() = existential_deposit);
+    }
+}
+
+impl Get<Balance> for ExistentialDeposit {
+    fn get() -> Balance {
+        Self::get()
+    }
 }
 
 #[derive_impl(pallet_balances::config_preludes::TestDefaultConfig as pallet_balances::DefaultConfig)]
@@ -152,19 +215,113 @@ impl pallet_balances::Config for Test {
 parameter_types! {
     pub const MinOperatorStake: Balance = 100 * SSC;
     pub const MinNominatorStake: Balance = SSC;
-    pub const StakeWithdrawalLockingPeriod: DomainBlockNumber = 5;
     pub const StakeEpochDuration: DomainBlockNumber = 5;
     pub TreasuryAccount: u128 = PalletId(*b"treasury").into_account_truncating();
     pub const BlockReward: Balance = 10 * SSC;
     pub const MaxPendingStakingOperation: u32 = 512;
     pub const MaxNominators: u32 = 5;
+    pub const OperatorMetadataDeposit: Balance = 10;
+    pub const MaxSlashEvidence: u32 = 2;
+    pub const SlashEvidenceRetentionPeriod: BlockNumber = 10;
+    pub const MaxRejectedBundleRecords: u32 = 2;
+    pub const DomainFeeHistoryDepth: u32 = 2;
+    pub const OperatorEpochSummaryHistoryDepth: u32 = 2;
+    pub const DomainStallPeriod: BlockNumber = 10;
+    pub const DomainFreezePeriod: BlockNumber = 100;
     pub const DomainsPalletId: PalletId = PalletId(*b"domains_");
     pub const DomainChainByteFee: Balance = 1;
     pub const MaxInitialDomainAccounts: u32 = 5;
     pub const MinInitialDomainAccountBalance: Balance = SSC;
+    pub const MaxContractCreationAllowListLength: u32 = 2;
     pub const BundleLongevity: u32 = 5;
 }
 
+thread_local! {
+    static STAKE_WITHDRAWAL_LOCKING_PERIOD: RefCell<DomainBlockNumber> =
+        RefCell::new(BlockTreePruningDepth::get());
+}
+
+/// Staking withdrawal locking period that can be overridden by the test to construct a runtime
+/// with bad config.
+///
+/// Defaults to `BlockTreePruningDepth` so the runtime passes the integrity test.
+pub struct StakeWithdrawalLockingPeriod;
+
+impl StakeWithdrawalLockingPeriod {
+    pub(crate) fn set(locking_period: DomainBlockNumber) {
+        STAKE_WITHDRAWAL_LOCKING_PERIOD.with(|v| *v.borrow_mut() = locking_period);
+    }
+}
+
+impl Get<DomainBlockNumber> for StakeWithdrawalLockingPeriod {
+    fn get() -> DomainBlockNumber {
+        STAKE_WITHDRAWAL_LOCKING_PERIOD.with(|v| *v.borrow())
+    }
+}
+
+thread_local! {
+    static NOMINATION_KEEP_ALIVE: RefCell<Balance> = const { RefCell::new(0) };
+}
+
+/// Nomination free balance buffer that can be overridden by the test, default to zero so tests
+/// can stake the whole free balance.
+pub struct NominationKeepAlive;
+
+impl NominationKeepAlive {
+    pub(crate) fn set(keep_alive: Balance) {
+        NOMINATION_KEEP_ALIVE.with(|v| *v.borrow_mut() = keep_alive);
+    }
+}
+
+impl Get<Balance> for NominationKeepAlive {
+    fn get() -> Balance {
+        NOMINATION_KEEP_ALIVE.with(|v| *v.borrow())
+    }
+}
+
+thread_local! {
+    static SLASH_DEFER_DURATION: RefCell<EpochIndex> = const { RefCell::new(0) };
+}
+
+/// Slash defer duration that can be overridden by the test, default to zero so the slashes are
+/// finalized at the end of the current epoch.
+pub struct SlashDeferDuration;
+
+impl SlashDeferDuration {
+    pub(crate) fn set(defer_duration: EpochIndex) {
+        SLASH_DEFER_DURATION.with(|v| *v.borrow_mut() = defer_duration);
+    }
+}
+
+impl Get<EpochIndex> for SlashDeferDuration {
+    fn get() -> EpochIndex {
+        SLASH_DEFER_DURATION.with(|v| *v.borrow())
+    }
+}
+
+thread_local! {
+    static BUNDLE_SIGNATURE_CHECKS: RefCell<u32> = const { RefCell::new(0) };
+}
+
+/// Verifies the bundle signatures like the runtime does and counts the verifications, used to
+/// check that cheap checks of the bundle are done before the signature is verified.
+pub struct CountingBundleSignatureVerifier;
+
+impl BundleSignatureVerifier for CountingBundleSignatureVerifier {
+    fn verify_bundle_signature<M: AsRef<[u8]>>(
+        signing_key: &OperatorPublicKey,
+        pre_hash: &M,
+        signature: &OperatorSignature,
+    ) -> bool {
+        BUNDLE_SIGNATURE_CHECKS.with(|v| *v.borrow_mut() += 1);
+        DefaultBundleSignatureVerifier::verify_bundle_signature(signing_key, pre_hash, signature)
+    }
+}
+
+fn bundle_signature_checks() -> u32 {
+    BUNDLE_SIGNATURE_CHECKS.with(|v| *v.borrow())
+}
+
 pub struct MockRandomness;
 
 impl frame_support::traits::Randomness<Hash, BlockNumber> for MockRandomness {
@@ -253,12 +410,66 @@ impl sp_domains::DomainsTransfersTracker<Balance> for MockDomainsTransfersTracke
     }
 }
 
+thread_local! {
+    static MMR_ROOT: RefCell<H256> = const { RefCell::new(H256::zero()) };
+}
+
+/// MMR proof verifier of a single leaf MMR, the MMR root is the hash of the only leaf and can be
+/// overridden by the test. The leaf is the encoded `(block_number, block_hash)` of the consensus
+/// block.
+pub struct MockMmrProofVerifier;
+
+impl MockMmrProofVerifier {
+    pub(crate) fn set_leaf(block_number: BlockNumber, block_hash: Hash) {
+        let leaf_hash = BlakeTwo256::hash_of(&(block_number, block_hash));
+        MMR_ROOT.with(|v| *v.borrow_mut() = leaf_hash);
+    }
+}
+
+impl ConsensusBlockMmrProofVerifier<BlockNumber, Hash, H256> for MockMmrProofVerifier {
+    fn verify_proof_and_extract_consensus_block(
+        mmr_leaf_proof: ConsensusChainMmrLeafProof<Hash, H256>,
+    ) -> Option<(BlockNumber, Hash)> {
+        let leaf_hash = BlakeTwo256::hash(&mmr_leaf_proof.opaque_mmr_leaf.0);
+        if leaf_hash != MMR_ROOT.with(|v| *v.borrow()) {
+            return None;
+        }
+        mmr_leaf_proof
+            .opaque_mmr_leaf
+            .into_opaque_leaf()
+            .try_decode()
+    }
+
+    #[cfg(feature = "runtime-benchmarks")]
+    fn consensus_block_mmr_proof_for_benchmarks(
+    ) -> Option<(BlockNumber, ConsensusChainMmrLeafProof<Hash, H256>)> {
+        let (block_number, block_hash) = (0, H256::zero());
+        Self::set_leaf(block_number, block_hash);
+        Some((
+            block_number,
+            ConsensusChainMmrLeafProof {
+                consensus_block_hash: H256::zero(),
+                opaque_mmr_leaf: EncodableOpaqueLeaf::from_leaf(&(block_number, block_hash)),
+                proof: MmrProof {
+                    leaf_indices: vec![0],
+                    leaf_count: 1,
+                    items: vec![],
+                },
+            },
+        ))
+    }
+}
+
 impl pallet_domains::Config for Test {
     type RuntimeEvent = RuntimeEvent;
     type DomainHash = sp_core::H256;
     type DomainHeader = DomainHeader;
+    type MmrHash = H256;
+    type MmrProofVerifier = MockMmrProofVerifier;
     type ConfirmationDepthK = ConfirmationDepthK;
     type DomainRuntimeUpgradeDelay = DomainRuntimeUpgradeDelay;
+    type MinRuntimeUpgradeDelay = MinRuntimeUpgradeDelay;
+    type MaxRuntimeUpgradeDelay = MaxRuntimeUpgradeDelay;
     type Currency = Balances;
     type HoldIdentifier = HoldIdentifier;
     type WeightInfo = pallet_domains::weights::SubstrateWeight<Test>;
@@ -266,18 +477,33 @@ impl pallet_domains::Config for Test {
     type DomainTxRangeAdjustmentInterval = DomainTxRangeAdjustmentInterval;
     type MinOperatorStake = MinOperatorStake;
     type MinNominatorStake = MinNominatorStake;
+    type NominationKeepAlive = NominationKeepAlive;
     type MaxDomainBlockSize = MaxDomainBlockSize;
     type MaxDomainBlockWeight = MaxDomainBlockWeight;
     type MaxBundlesPerBlock = MaxBundlesPerBlock;
+    type MaxBundleExtrinsics = MaxBundleExtrinsics;
+    type MinBundleSize = MinBundleSize;
+    type MinBundleWeight = MinBundleWeight;
     type DomainInstantiationDeposit = DomainInstantiationDeposit;
     type MaxDomainNameLength = MaxDomainNameLength;
+    type MaxDomainsPerOwner = MaxDomainsPerOwner;
     type Share = Balance;
     type BlockTreePruningDepth = BlockTreePruningDepth;
+    type MaxReceiptGap = MaxReceiptGap;
     type StakeWithdrawalLockingPeriod = StakeWithdrawalLockingPeriod;
     type StakeEpochDuration = StakeEpochDuration;
     type TreasuryAccount = TreasuryAccount;
     type MaxPendingStakingOperation = MaxPendingStakingOperation;
     type MaxNominators = MaxNominators;
+    type OperatorMetadataDeposit = OperatorMetadataDeposit;
+    type MaxSlashEvidence = MaxSlashEvidence;
+    type SlashEvidenceRetentionPeriod = SlashEvidenceRetentionPeriod;
+    type MaxRejectedBundleRecords = MaxRejectedBundleRecords;
+    type SlashDeferDuration = SlashDeferDuration;
+    type DomainFeeHistoryDepth = DomainFeeHistoryDepth;
+    type OperatorEpochSummaryHistoryDepth = OperatorEpochSummaryHistoryDepth;
+    type DomainStallPeriod = DomainStallPeriod;
+    type DomainFreezePeriod = DomainFreezePeriod;
     type Randomness = MockRandomness;
     type PalletId = DomainsPalletId;
     type StorageFee = DummyStorageFee;
@@ -285,9 +511,11 @@ impl pallet_domains::Config for Test {
     type DomainsTransfersTracker = MockDomainsTransfersTracker;
     type MaxInitialDomainAccounts = MaxInitialDomainAccounts;
     type MinInitialDomainAccountBalance = MinInitialDomainAccountBalance;
+    type MaxContractCreationAllowListLength = MaxContractCreationAllowListLength;
     type BundleLongevity = BundleLongevity;
     type ConsensusSlotProbability = SlotProbability;
     type DomainBundleSubmitted = ();
+    type BundleSignatureVerifier = CountingBundleSignatureVerifier;
 }
 
 pub struct ExtrinsicStorageFees;
@@ -420,6 +648,11 @@ impl FraudProofHostFunctions for MockDomainFraudProofExtension {
                     DomainChainAllowlistUpdateExtrinsic::None,
                 )
             }
+            FraudProofVerificationInfoRequest::EvmContractCreationAllowListUpdateExtrinsic(_) => {
+                FraudProofVerificationInfoResponse::EvmContractCreationAllowListUpdateExtrinsic(
+                    EvmContractCreationAllowListUpdateExtrinsic::None,
+                )
+            }
         };
 
         Some(response)
@@ -464,6 +697,7 @@ pub(crate) fn new_test_ext_with_extensions() -> sp_io::TestExternalities {
     ext.register_extension(sp_core::traits::ReadRuntimeVersionExt::new(
         ReadRuntimeVersion(version.encode()),
     ));
+    ext.register_extension(KeystoreExt::new(MemoryKeystore::new()));
     ext
 }
 
@@ -549,6 +783,29 @@ pub(crate) fn create_dummy_bundle_with_receipts(
     }
 }
 
+/// Returns the report of a rejected dummy bundle of the operator built at `slot`, the bundle
+/// header is signed by the operator
+pub(crate) fn rejected_bundle_at(
+    domain_id: DomainId,
+    operator_id: OperatorId,
+    slot: u64,
+    receipt: ExecutionReceipt<BlockNumber, Hash, DomainBlockNumber, H256, u128>,
+    reason: RejectedBundleReason,
+) -> RejectedBundle {
+    let pair = OperatorPair::from_seed(&U256::from(0u32).into());
+    let mut header =
+        create_dummy_bundle_with_receipts(domain_id, operator_id, H256::random(), receipt)
+            .sealed_header
+            .header;
+    header.proof_of_election.slot_number = slot;
+    let signature = pair.sign(header.hash().as_ref());
+
+    RejectedBundle {
+        encoded_sealed_header: SealedBundleHeader::new(header, signature).encode(),
+        reason,
+    }
+}
+
 pub(crate) struct ReadRuntimeVersion(pub Vec<u8>);
 
 impl sp_core::traits::ReadRuntimeVersion for ReadRuntimeVersion {
@@ -598,12 +855,13 @@ pub(crate) fn register_genesis_domain(creator: u128, operator_ids: Vec<OperatorI
         DomainConfig {
             domain_name: "evm-domain".to_owned(),
             runtime_id: 0,
-            max_block_size: 1u32,
-            max_block_weight: Weight::from_parts(1, 0),
+            max_block_size: 1024u32,
+            max_block_weight: Weight::from_parts(1024, 0),
             bundle_slot_probability: (1, 1),
             target_bundles_per_block: 1,
             operator_allow_list: OperatorAllowList::Anyone,
             initial_balances: Default::default(),
+            initial_contract_creation_allow_list: None,
         },
     )
     .unwrap();
@@ -763,6 +1021,7 @@ fn test_bundle_fromat_verification() {
             target_bundles_per_block: 1,
             operator_allow_list: OperatorAllowList::Anyone,
             initial_balances: Default::default(),
+            initial_contract_creation_allow_list: None,
         };
         let domain_obj = DomainObject {
             owner_account_id: Default::default(),
@@ -858,7 +1117,7 @@ fn test_invalid_fraud_proof() {
             .hash::<DomainHashingFor<Test>>();
         let fraud_proof = FraudProof::dummy_fraud_proof(domain_id, bad_receipt_hash);
         assert_eq!(
-            Domains::validate_fraud_proof(&fraud_proof),
+            Domains::validate_fraud_proof(&fraud_proof, &None),
             Err(FraudProofError::ChallengingGenesisReceipt)
         );
 
@@ -866,9 +1125,210 @@ fn test_invalid_fraud_proof() {
         let bad_receipt_hash = H256::random();
         let fraud_proof = FraudProof::dummy_fraud_proof(domain_id, bad_receipt_hash);
         assert_eq!(
-            Domains::validate_fraud_proof(&fraud_proof),
+            Domains::validate_fraud_proof(&fraud_proof, &None),
             Err(FraudProofError::BadReceiptNotFound)
         );
+
+        // Fraud proof target ER that is built on an unknown consensus block is invalid
+        let mut domain_block = get_block_tree_node_at::<Test>(domain_id, 8).unwrap();
+        domain_block.execution_receipt.consensus_block_hash = H256::random();
+        let bad_receipt_hash = domain_block
+            .execution_receipt
+            .hash::<DomainHashingFor<Test>>();
+        BlockTreeNodes::<Test>::insert(bad_receipt_hash, domain_block);
+        let fraud_proof = FraudProof::dummy_fraud_proof(domain_id, bad_receipt_hash);
+        assert_eq!(
+            Domains::validate_fraud_proof(&fraud_proof, &None),
+            Err(FraudProofError::BadReceiptConsensusBlock(
+                BlockTreeError::BuiltOnUnknownConsensusBlock
+            ))
+        );
+    });
+}
+
+#[test]
+fn test_fraud_proof_with_consensus_block_mmr_proof() {
+    let creator = 0u128;
+    let operator_id = 1u64;
+    let head_domain_number = 5;
+    let mut ext = new_test_ext_with_extensions();
+    ext.execute_with(|| {
+        let domain_id = register_genesis_domain(creator, vec![operator_id]);
+        extend_block_tree_from_zero(domain_id, operator_id, head_domain_number + 2);
+
+        let bad_receipt = get_block_tree_node_at::<Test>(domain_id, head_domain_number)
+            .unwrap()
+            .execution_receipt;
+        let bad_receipt_hash = bad_receipt.hash::<DomainHashingFor<Test>>();
+        let consensus_number = bad_receipt.consensus_block_number;
+        let consensus_hash = bad_receipt.consensus_block_hash;
+        let fraud_proof = FraudProof::dummy_fraud_proof(domain_id, bad_receipt_hash);
+        let mmr_leaf_proof = |block_number: BlockNumber, block_hash: Hash| {
+            Some(ConsensusChainMmrLeafProof {
+                consensus_block_hash: frame_system::Pallet::<Test>::parent_hash(),
+                opaque_mmr_leaf: EncodableOpaqueLeaf::from_leaf(&(block_number, block_hash)),
+                proof: MmrProof {
+                    leaf_indices: vec![block_number],
+                    leaf_count: 1,
+                    items: vec![],
+                },
+            })
+        };
+        MockMmrProofVerifier::set_leaf(consensus_number, consensus_hash);
+
+        // The consensus block hash is used while it is available, the MMR proof is not required
+        assert_ok!(Domains::validate_fraud_proof(&fraud_proof, &None));
+
+        // Simulate the consensus block hash is pruned
+        ConsensusBlockHash::<Test>::remove(domain_id, consensus_number);
+        assert_eq!(
+            Domains::validate_fraud_proof(&fraud_proof, &None),
+            Err(FraudProofError::BadReceiptConsensusBlock(
+                BlockTreeError::UnavailableConsensusBlockHash
+            ))
+        );
+
+        // The consensus block is verified with the MMR proof instead
+        assert_ok!(Domains::validate_fraud_proof(
+            &fraud_proof,
+            &mmr_leaf_proof(consensus_number, consensus_hash)
+        ));
+
+        // Forged MMR proof that claims a different consensus block hash
+        assert_eq!(
+            Domains::validate_fraud_proof(
+                &fraud_proof,
+                &mmr_leaf_proof(consensus_number, H256::random())
+            ),
+            Err(FraudProofError::InvalidConsensusBlockMmrProof)
+        );
+
+        // Valid MMR proof but of a different consensus block
+        let other_number = consensus_number - 1;
+        let other_hash = frame_system::Pallet::<Test>::block_hash(other_number);
+        MockMmrProofVerifier::set_leaf(other_number, other_hash);
+        assert_eq!(
+            Domains::validate_fraud_proof(&fraud_proof, &mmr_leaf_proof(other_number, other_hash)),
+            Err(FraudProofError::InvalidConsensusBlockMmrProof)
+        );
+    });
+}
+
+#[test]
+fn test_invalid_transaction_code() {
+    let creator = 0u128;
+    let operator_id = 1u64;
+    let mut ext = new_test_ext_with_extensions();
+    ext.execute_with(|| {
+        let domain_id = register_genesis_domain(creator, vec![operator_id]);
+        let genesis_receipt = get_block_tree_node_at::<Test>(domain_id, 0)
+            .unwrap()
+            .execution_receipt;
+        let valid_bundle = create_dummy_bundle_with_receipts(
+            domain_id,
+            operator_id,
+            Default::default(),
+            genesis_receipt.clone(),
+        );
+
+        // The same custom code is returned in both `validate_unsigned` and `pre_dispatch`
+        let assert_bundle_code =
+            |opaque_bundle: OpaqueBundle<BlockNumber, Hash, DomainHeader, u128>,
+             code: InvalidTransactionCode| {
+                let call = pallet_domains::Call::<Test>::submit_bundle { opaque_bundle };
+                assert_eq!(
+                    Domains::validate_unsigned(TransactionSource::External, &call),
+                    code.into()
+                );
+                assert_eq!(Domains::pre_dispatch(&call), Err(code.into()));
+            };
+
+        // Unknown operator
+        let unknown_operator_bundle = create_dummy_bundle_with_receipts(
+            domain_id,
+            operator_id + 1,
+            Default::default(),
+            genesis_receipt,
+        );
+        assert_bundle_code(
+            unknown_operator_bundle,
+            InvalidTransactionCode::BundleOperator,
+        );
+
+        // Slashed operator
+        Operators::<Test>::mutate(operator_id, |maybe_operator| {
+            maybe_operator
+                .as_mut()
+                .unwrap()
+                .update_status(OperatorStatus::Slashed)
+        });
+        assert_bundle_code(valid_bundle.clone(), InvalidTransactionCode::BundleOperator);
+        Operators::<Test>::mutate(operator_id, |maybe_operator| {
+            maybe_operator
+                .as_mut()
+                .unwrap()
+                .update_status(OperatorStatus::Registered)
+        });
+
+        // Paused operator
+        Operators::<Test>::mutate(operator_id, |maybe_operator| {
+            maybe_operator.as_mut().unwrap().paused = true
+        });
+        assert_eq!(
+            Domains::validate_bundle(&valid_bundle, false),
+            Err(BundleError::OperatorPaused)
+        );
+        assert_bundle_code(valid_bundle.clone(), InvalidTransactionCode::BundleOperator);
+        Operators::<Test>::mutate(operator_id, |maybe_operator| {
+            maybe_operator.as_mut().unwrap().paused = false
+        });
+
+        // Bundle header is modified after signing
+        let mut bad_signature_bundle = valid_bundle.clone();
+        bad_signature_bundle
+            .sealed_header
+            .header
+            .bundle_extrinsics_root = H256::random();
+        assert_bundle_code(
+            bad_signature_bundle,
+            InvalidTransactionCode::BundleSignature,
+        );
+
+        // Duplicated bundle
+        let bundle_header_hash = valid_bundle.sealed_header.pre_hash();
+        InboxedBundleAuthor::<Test>::insert(bundle_header_hash, operator_id);
+        assert_bundle_code(
+            valid_bundle.clone(),
+            InvalidTransactionCode::DuplicatedBundle,
+        );
+        InboxedBundleAuthor::<Test>::remove(bundle_header_hash);
+
+        // Bundle exceeds the `max_block_size` of the domain
+        let mut too_large_bundle = valid_bundle;
+        too_large_bundle.extrinsics.push(
+            UncheckedExtrinsic {
+                signature: None,
+                function: RuntimeCall::Balances(pallet_balances::Call::transfer_allow_death {
+                    dest: 1,
+                    value: 1,
+                }),
+            }
+            .into(),
+        );
+        assert_bundle_code(too_large_bundle, InvalidTransactionCode::BundleLimit);
+
+        // Fraud proof targets an unknown receipt
+        let fraud_proof = FraudProof::dummy_fraud_proof(domain_id, H256::random());
+        let call = pallet_domains::Call::<Test>::submit_fraud_proof {
+            fraud_proof: Box::new(fraud_proof),
+            maybe_consensus_block_proof: None,
+        };
+        let code = InvalidTransactionCode::FraudProofBadReceiptNotFound;
+        assert_eq!(
+            Domains::validate_unsigned(TransactionSource::External, &call),
+            code.into()
+        );
+        assert_eq!(Domains::pre_dispatch(&call), Err(code.into()));
     });
 }
 
@@ -912,7 +1372,7 @@ fn test_invalid_block_fees_fraud_proof() {
         );
         domain_block.execution_receipt.final_state_root = root;
         BlockTreeNodes::<Test>::insert(bad_receipt_hash, domain_block);
-        assert_ok!(Domains::validate_fraud_proof(&fraud_proof),);
+        assert_ok!(Domains::validate_fraud_proof(&fraud_proof, &None),);
     });
 }
 
@@ -1013,7 +1473,7 @@ fn test_invalid_domain_extrinsic_root_proof() {
     ext.register_extension(fraud_proof_ext);
 
     ext.execute_with(|| {
-        assert_ok!(Domains::validate_fraud_proof(&fraud_proof),);
+        assert_ok!(Domains::validate_fraud_proof(&fraud_proof, &None),);
     })
 }
 
@@ -1095,7 +1555,7 @@ fn test_true_invalid_bundles_inherent_extrinsic_proof() {
     ext.register_extension(fraud_proof_ext);
 
     ext.execute_with(|| {
-        assert_ok!(Domains::validate_fraud_proof(&fraud_proof),);
+        assert_ok!(Domains::validate_fraud_proof(&fraud_proof, &None),);
     })
 }
 
@@ -1163,7 +1623,7 @@ fn test_false_invalid_bundles_inherent_extrinsic_proof() {
     ext.register_extension(fraud_proof_ext);
 
     ext.execute_with(|| {
-        assert_ok!(Domains::validate_fraud_proof(&fraud_proof),);
+        assert_ok!(Domains::validate_fraud_proof(&fraud_proof, &None),);
     })
 }
 
@@ -1220,7 +1680,7 @@ fn test_invalid_domain_block_hash_fraud_proof() {
             bad_receipt_hash,
             digest_storage_proof,
         });
-        assert_ok!(Domains::validate_fraud_proof(&fraud_proof),);
+        assert_ok!(Domains::validate_fraud_proof(&fraud_proof, &None),);
     });
 }
 
@@ -1269,14 +1729,34 @@ fn test_basic_fraud_proof_processing() {
                 .execution_receipt;
             let bad_receipt_hash = bad_receipt.hash::<DomainHashingFor<Test>>();
             let fraud_proof = FraudProof::dummy_fraud_proof(domain_id, bad_receipt_hash);
+            let fraud_proof_hash = fraud_proof.hash();
             assert_ok!(Domains::submit_fraud_proof(
                 RawOrigin::None.into(),
-                Box::new(fraud_proof)
+                Box::new(fraud_proof),
+                None
             ));
 
+            // The evidence of the bad ER should be kept for the slashed submitter
+            let mut expected_slash_evidence = vec![SlashEvidence {
+                receipt_hash: bad_receipt_hash,
+                domain_block_number: bad_receipt_at,
+                consensus_block_number: bad_receipt.consensus_block_number,
+                fraud_proof_hash: Some(fraud_proof_hash),
+                mismatch: BadReceiptMismatch::Unknown,
+            }];
+            assert_eq!(
+                Domains::slash_evidence(malicious_operator),
+                expected_slash_evidence
+            );
+            assert!(Domains::slash_evidence(honest_operator).is_empty());
+
             // The head receipt number should be reverted to `bad_receipt_at - 1`
             let head_receipt_number_after_fraud_proof = HeadReceiptNumber::<Test>::get(domain_id);
             assert_eq!(head_receipt_number_after_fraud_proof, bad_receipt_at - 1);
+            assert_eq!(
+                Domains::revert_depth(domain_id),
+                head_domain_number - bad_receipt_at
+            );
 
             for block_number in bad_receipt_at..=head_domain_number {
                 if block_number == bad_receipt_at {
@@ -1322,6 +1802,10 @@ fn test_basic_fraud_proof_processing() {
                 HeadReceiptNumber::<Test>::get(domain_id),
                 head_receipt_number_after_fraud_proof + 1
             );
+            assert_eq!(
+                Domains::revert_depth(domain_id),
+                head_domain_number - bad_receipt_at
+            );
 
             // Submit one more ER, the bad ER at the same domain block should be pruned
             let next_block_number = frame_system::Pallet::<Test>::current_block_number() + 1;
@@ -1343,12 +1827,1505 @@ fn test_basic_fraud_proof_processing() {
                     HeadReceiptNumber::<Test>::get(domain_id),
                     head_receipt_number_after_fraud_proof + 2
                 );
+                assert_eq!(
+                    Domains::revert_depth(domain_id),
+                    head_domain_number - bad_receipt_at - 1
+                );
                 assert!(BlockTreeNodes::<Test>::get(receipt_hash).is_none());
                 assert!(!Domains::is_bad_er_pending_to_prune(
                     domain_id,
                     receipt.domain_block_number
                 ));
+
+                // The evidence of the lazily pruned bad ER should also be kept
+                expected_slash_evidence.push(SlashEvidence {
+                    receipt_hash,
+                    domain_block_number: receipt.domain_block_number,
+                    consensus_block_number: receipt.consensus_block_number,
+                    fraud_proof_hash: None,
+                    mismatch: BadReceiptMismatch::Unknown,
+                });
+                assert_eq!(
+                    Domains::slash_evidence(malicious_operator),
+                    expected_slash_evidence
+                );
             }
         });
     }
 }
+
+#[test]
+fn test_maintain_stalled_domain() {
+    let creator = 0u128;
+    let keeper = 100u128;
+    let malicious_operator = 1u64;
+    let head_domain_number = 5;
+    let mut ext = new_test_ext_with_extensions();
+    ext.execute_with(|| {
+        let domain_id = register_genesis_domain(creator, vec![malicious_operator]);
+        extend_block_tree_from_zero(domain_id, malicious_operator, head_domain_number + 2);
+        assert_eq!(
+            HeadReceiptNumber::<Test>::get(domain_id),
+            head_domain_number
+        );
+
+        // Submit fraud proof to slash the malicious operator
+        let bad_receipt_hash = get_block_tree_node_at::<Test>(domain_id, head_domain_number)
+            .unwrap()
+            .execution_receipt
+            .hash::<DomainHashingFor<Test>>();
+        let fraud_proof = FraudProof::dummy_fraud_proof(domain_id, bad_receipt_hash);
+        assert_ok!(Domains::submit_fraud_proof(
+            RawOrigin::None.into(),
+            Box::new(fraud_proof),
+            None
+        ));
+        assert!(PendingSlashes::<Test>::get(domain_id)
+            .unwrap()
+            .contains(&malicious_operator));
+
+        // Nothing is overdue before the domain is stalled, the caller needs to pay the fee
+        let post_info =
+            Domains::maintain_domain(RawOrigin::Signed(keeper).into(), domain_id).unwrap();
+        assert_eq!(post_info.pays_fee, Pays::Yes);
+        assert!(PendingSlashes::<Test>::get(domain_id).is_some());
+
+        // No more bundle is submitted and the domain is stalled
+        let next_block_number = frame_system::Pallet::<Test>::current_block_number()
+            + <Test as Config>::DomainStallPeriod::get();
+        run_to_block::<Test>(next_block_number, H256::random());
+        assert!(Domains::is_domain_stalled(domain_id));
+
+        // The pending slash is finalized and the fee is waived
+        let post_info =
+            Domains::maintain_domain(RawOrigin::Signed(keeper).into(), domain_id).unwrap();
+        assert_eq!(post_info.pays_fee, Pays::No);
+        assert!(post_info
+            .actual_weight
+            .unwrap()
+            .all_lte(Domains::max_maintain_domain_weight()));
+        assert_eq!(PendingSlashes::<Test>::get(domain_id), None);
+        assert_eq!(Operators::<Test>::get(malicious_operator), None);
+
+        // Nothing is overdue anymore
+        let post_info =
+            Domains::maintain_domain(RawOrigin::Signed(keeper).into(), domain_id).unwrap();
+        assert_eq!(post_info.pays_fee, Pays::Yes);
+    });
+}
+
+#[test]
+fn test_maintain_stalled_domain_with_many_pending_slashes() {
+    let creator = 0u128;
+    let keeper = 100u128;
+    let max_operators = <Test as Config>::MaxBundlesPerBlock::get();
+    let slashed_operators: Vec<OperatorId> = (1..=max_operators as u64 + 1).collect();
+    let mut ext = new_test_ext_with_extensions();
+    ext.execute_with(|| {
+        let domain_id = register_genesis_domain(creator, slashed_operators.clone());
+        PendingSlashes::<Test>::insert(
+            domain_id,
+            slashed_operators.iter().copied().collect::<BTreeSet<_>>(),
+        );
+        let next_block_number = frame_system::Pallet::<Test>::current_block_number()
+            + <Test as Config>::DomainStallPeriod::get();
+        run_to_block::<Test>(next_block_number, H256::random());
+        assert!(Domains::is_domain_stalled(domain_id));
+
+        // Only `MaxBundlesPerBlock` slashed operators are finalized and reported in the first call
+        let post_info =
+            Domains::maintain_domain(RawOrigin::Signed(keeper).into(), domain_id).unwrap();
+        assert_eq!(post_info.pays_fee, Pays::No);
+        System::assert_last_event(RuntimeEvent::Domains(Event::DomainMaintained {
+            domain_id,
+            finalized_slashed_operators: max_operators,
+            completed_epoch_index: None,
+        }));
+        assert_eq!(PendingSlashes::<Test>::get(domain_id).unwrap().len(), 1);
+
+        // The rest are finalized by the following call
+        let post_info =
+            Domains::maintain_domain(RawOrigin::Signed(keeper).into(), domain_id).unwrap();
+        assert_eq!(post_info.pays_fee, Pays::No);
+        System::assert_last_event(RuntimeEvent::Domains(Event::DomainMaintained {
+            domain_id,
+            finalized_slashed_operators: 1,
+            completed_epoch_index: None,
+        }));
+        assert_eq!(PendingSlashes::<Test>::get(domain_id), None);
+        assert!(slashed_operators
+            .iter()
+            .all(|operator_id| Operators::<Test>::get(operator_id).is_none()));
+    });
+}
+
+#[test]
+fn test_pending_epoch_transition_recovery() {
+    let creator = 0u128;
+    let keeper = 100u128;
+    let operator_id = 1u64;
+    let mut ext = new_test_ext_with_extensions();
+    ext.execute_with(|| {
+        let domain_id = register_genesis_domain(creator, vec![operator_id]);
+        let current_epoch_index = || {
+            DomainStakingSummary::<Test>::get(domain_id)
+                .unwrap()
+                .current_epoch_index
+        };
+
+        // The epoch transition is done upon the confirmation of the epoch boundary block
+        extend_block_tree_from_zero(
+            domain_id,
+            operator_id,
+            BlockTreePruningDepth::get() + StakeEpochDuration::get() + 2,
+        );
+        assert!(
+            Domains::latest_confirmed_domain_block_number(domain_id) >= StakeEpochDuration::get()
+        );
+        assert_eq!(PendingEpochTransition::<Test>::get(domain_id), None);
+
+        let pending_transition = |failed_attempts, retry_at| PendingEpochTransitionInfo {
+            domain_block_number: StakeEpochDuration::get(),
+            failed_attempts,
+            retry_at,
+        };
+
+        // The epoch transition is skipped upon the confirmation of the epoch boundary block, it is
+        // completed automatically in the next block
+        let epoch_index = current_epoch_index();
+        PendingEpochTransition::<Test>::insert(domain_id, pending_transition(0, 0));
+        let next_block_number = frame_system::Pallet::<Test>::current_block_number() + 1;
+        run_to_block::<Test>(next_block_number, H256::random());
+        assert_eq!(PendingEpochTransition::<Test>::get(domain_id), None);
+        assert_eq!(current_epoch_index(), epoch_index + 1);
+        System::assert_has_event(RuntimeEvent::Domains(Event::DomainEpochCompleted {
+            domain_id,
+            completed_epoch_index: epoch_index,
+        }));
+
+        // The failed transition is kept pending and retried with an exponential backoff
+        let stake_summary = DomainStakingSummary::<Test>::take(domain_id).unwrap();
+        PendingEpochTransition::<Test>::insert(domain_id, pending_transition(0, 0));
+        let failed_at = next_block_number + 1;
+        run_to_block::<Test>(failed_at, H256::random());
+        assert_eq!(
+            PendingEpochTransition::<Test>::get(domain_id),
+            Some(pending_transition(1, failed_at + 1))
+        );
+        System::assert_has_event(RuntimeEvent::Domains(
+            Event::DomainEpochTransitionDeferred {
+                domain_id,
+                domain_block_number: StakeEpochDuration::get(),
+            },
+        ));
+
+        // Only the first failure is reported
+        run_to_block::<Test>(failed_at + 1, H256::random());
+        assert_eq!(
+            PendingEpochTransition::<Test>::get(domain_id),
+            Some(pending_transition(2, failed_at + 3))
+        );
+        assert!(!System::events().iter().any(|record| matches!(
+            record.event,
+            RuntimeEvent::Domains(Event::DomainEpochTransitionDeferred { .. })
+        )));
+
+        // The transition is not retried before the backoff elapses
+        run_to_block::<Test>(failed_at + 2, H256::random());
+        assert_eq!(
+            PendingEpochTransition::<Test>::get(domain_id),
+            Some(pending_transition(2, failed_at + 3))
+        );
+        DomainStakingSummary::<Test>::insert(domain_id, stake_summary);
+
+        // The pending transition can also be completed by anyone with the fee waived
+        let post_info =
+            Domains::maintain_domain(RawOrigin::Signed(keeper).into(), domain_id).unwrap();
+        assert_eq!(post_info.pays_fee, Pays::No);
+        assert_eq!(PendingEpochTransition::<Test>::get(domain_id), None);
+        assert_eq!(current_epoch_index(), epoch_index + 2);
+        System::assert_last_event(RuntimeEvent::Domains(Event::DomainMaintained {
+            domain_id,
+            finalized_slashed_operators: 0,
+            completed_epoch_index: Some(epoch_index + 1),
+        }));
+
+        // Nothing is overdue anymore
+        let post_info =
+            Domains::maintain_domain(RawOrigin::Signed(keeper).into(), domain_id).unwrap();
+        assert_eq!(post_info.pays_fee, Pays::Yes);
+        assert_eq!(current_epoch_index(), epoch_index + 2);
+    });
+}
+
+#[test]
+fn test_slash_evidence_retention() {
+    let creator = 0u128;
+    let operator_id = 1u64;
+    let unknown_operator_id = 100u64;
+    let mut ext = new_test_ext_with_extensions();
+    ext.execute_with(|| {
+        register_genesis_domain(creator, vec![operator_id]);
+
+        let evidence_at = |domain_block_number: DomainBlockNumber| -> SlashEvidenceOf<Test> {
+            SlashEvidence {
+                receipt_hash: H256::repeat_byte(domain_block_number as u8),
+                domain_block_number,
+                consensus_block_number: domain_block_number as u64,
+                fraud_proof_hash: None,
+                mismatch: BadReceiptMismatch::Unknown,
+            }
+        };
+
+        let max_evidence = MaxSlashEvidence::get();
+        for domain_block_number in 1..=max_evidence + 1 {
+            note_slash_evidence::<Test>(vec![operator_id], evidence_at(domain_block_number));
+        }
+
+        // Only the latest `MaxSlashEvidence` evidence are kept
+        assert_eq!(
+            Domains::slash_evidence(operator_id),
+            (2..=max_evidence + 1).map(evidence_at).collect::<Vec<_>>()
+        );
+
+        // Evidence is not kept for operator that does not exist
+        note_slash_evidence::<Test>(vec![unknown_operator_id], evidence_at(1));
+        assert!(Domains::slash_evidence(unknown_operator_id).is_empty());
+
+        // Evidence is kept for `SlashEvidenceRetentionPeriod` blocks after the operator is removed
+        let removed_at = System::block_number();
+        Operators::<Test>::remove(operator_id);
+        schedule_slash_evidence_expiry::<Test>(operator_id);
+        let expire_at = removed_at + SlashEvidenceRetentionPeriod::get();
+        Domains::on_initialize(expire_at - 1);
+        assert_eq!(
+            Domains::slash_evidence(operator_id).len() as u32,
+            max_evidence
+        );
+        Domains::on_initialize(expire_at);
+        assert!(Domains::slash_evidence(operator_id).is_empty());
+    });
+}
+
+#[test]
+fn test_rejected_bundles_inherent() {
+    let creator = 0u128;
+    let operator_id = 1u64;
+    let mut ext = new_test_ext_with_extensions();
+    ext.execute_with(|| {
+        let domain_id = register_genesis_domain(creator, vec![operator_id]);
+        let genesis_receipt = get_block_tree_node_at::<Test>(domain_id, 0)
+            .unwrap()
+            .execution_receipt;
+
+        let inherent_data_with = |rejected_bundles: Vec<RejectedBundle>| {
+            let mut inherent_data = InherentData::new();
+            inherent_data
+                .put_data(
+                    INHERENT_IDENTIFIER,
+                    &InherentType {
+                        rejected_bundles: rejected_bundles.try_into().unwrap(),
+                    },
+                )
+                .unwrap();
+            inherent_data
+        };
+        let rejected_bundle = |operator_id, slot| {
+            rejected_bundle_at(
+                domain_id,
+                operator_id,
+                slot,
+                genesis_receipt.clone(),
+                RejectedBundleReason::StaleReceipt,
+            )
+        };
+
+        // No inherent if the inherent data is not provided or there is no rejected bundle
+        assert!(Domains::create_inherent(&InherentData::new()).is_none());
+        assert!(Domains::create_inherent(&inherent_data_with(vec![])).is_none());
+        assert!(matches!(
+            Domains::is_inherent_required(&InherentData::new()),
+            Ok(None)
+        ));
+
+        // At most `MAX_REJECTED_BUNDLES_PER_BLOCK` rejected bundles are reported in one block,
+        // the bound is enforced when decoding the inherent data and the call
+        let max = MAX_REJECTED_BUNDLES_PER_BLOCK;
+        let rejected_bundles: Vec<_> = (0..max)
+            .map(|slot| rejected_bundle(operator_id, slot as u64))
+            .collect();
+        let inherent_data = inherent_data_with(rejected_bundles.clone());
+        let call = Domains::create_inherent(&inherent_data).unwrap();
+        assert_eq!(
+            call,
+            crate::Call::report_rejected_bundles {
+                rejected_bundles: rejected_bundles.clone().try_into().unwrap(),
+            }
+        );
+        assert!(Domains::is_inherent(&call));
+        let oversized_bundles: Vec<_> = (0..=max)
+            .map(|slot| rejected_bundle(operator_id, slot as u64))
+            .collect();
+        assert!(RejectedBundles::try_from(oversized_bundles.clone()).is_err());
+        // The call index of `report_rejected_bundles` followed by the oversized rejected bundles
+        let oversized_call = (18u8, oversized_bundles).encode();
+        assert!(crate::Call::<Test>::decode(&mut oversized_call.as_slice()).is_err());
+
+        // The report can't be checked against the local inherent data
+        assert!(Domains::check_inherent(&call, &InherentData::new()).is_ok());
+        assert_ok!(Domains::pre_dispatch(&call));
+        let empty_call = crate::Call::report_rejected_bundles {
+            rejected_bundles: Default::default(),
+        };
+        assert_eq!(
+            Domains::pre_dispatch(&empty_call),
+            Err(InvalidTransaction::Call.into())
+        );
+
+        // Only the bundles whose header is verified to be signed by an existing operator are
+        // recorded, the bundles that are included or that are not newer than the last reported
+        // bundle of the operator are skipped too
+        let unknown_operator_id = 100u64;
+        let forged_bundle = {
+            let mut rejected_bundle = rejected_bundle(operator_id, 2);
+            let mut sealed_header = SealedBundleHeaderOf::<Test>::decode(
+                &mut rejected_bundle.encoded_sealed_header.as_slice(),
+            )
+            .unwrap();
+            sealed_header.header.bundle_extrinsics_root = H256::random();
+            rejected_bundle.encoded_sealed_header = sealed_header.encode();
+            rejected_bundle
+        };
+        let included_bundle = rejected_bundle(operator_id, 3);
+        let included_bundle_header_hash = SealedBundleHeaderOf::<Test>::decode(
+            &mut included_bundle.encoded_sealed_header.as_slice(),
+        )
+        .unwrap()
+        .pre_hash();
+        InboxedBundleAuthor::<Test>::insert(included_bundle_header_hash, operator_id);
+        let valid_bundle = rejected_bundle(operator_id, 4);
+        assert_ok!(Domains::report_rejected_bundles(
+            RawOrigin::None.into(),
+            vec![
+                rejected_bundle(unknown_operator_id, 1),
+                forged_bundle,
+                RejectedBundle {
+                    encoded_sealed_header: vec![1, 2, 3],
+                    reason: RejectedBundleReason::StaleReceipt,
+                },
+                included_bundle,
+                valid_bundle.clone(),
+                valid_bundle,
+                rejected_bundle(operator_id, 1),
+            ]
+            .try_into()
+            .unwrap(),
+        ));
+        let reported_at = frame_system::Pallet::<Test>::current_block_number();
+        assert_eq!(
+            Domains::rejected_bundles(operator_id),
+            (
+                1,
+                vec![RejectedBundleRecord {
+                    consensus_block_number: reported_at,
+                    reason: RejectedBundleReason::StaleReceipt,
+                }]
+            )
+        );
+        assert_eq!(LastRejectedBundleSlot::<Test>::get(operator_id), Some(4));
+        assert_eq!(Domains::rejected_bundles(unknown_operator_id), (0, vec![]));
+
+        // The report is accepted at most once per block
+        assert_eq!(
+            Domains::pre_dispatch(&call),
+            Err(InvalidTransaction::Call.into())
+        );
+        Domains::on_finalize(reported_at);
+        assert_ok!(Domains::pre_dispatch(&call));
+    });
+}
+
+#[test]
+fn test_rejected_bundles_retention() {
+    let creator = 0u128;
+    let operator_id = 1u64;
+    let mut ext = new_test_ext_with_extensions();
+    ext.execute_with(|| {
+        let domain_id = register_genesis_domain(creator, vec![operator_id]);
+        let genesis_receipt = get_block_tree_node_at::<Test>(domain_id, 0)
+            .unwrap()
+            .execution_receipt;
+
+        let max_records = MaxRejectedBundleRecords::get();
+        let reasons = [
+            RejectedBundleReason::StaleReceipt,
+            RejectedBundleReason::BundleLimit,
+        ];
+        let reason_at = |block_number: u64| reasons[block_number as usize % reasons.len()];
+        for block_number in 1..=max_records as u64 + 1 {
+            run_to_block::<Test>(block_number, H256::random());
+            assert_ok!(Domains::report_rejected_bundles(
+                RawOrigin::None.into(),
+                vec![rejected_bundle_at(
+                    domain_id,
+                    operator_id,
+                    block_number,
+                    genesis_receipt.clone(),
+                    reason_at(block_number),
+                )]
+                .try_into()
+                .unwrap(),
+            ));
+        }
+
+        // Only the latest `MaxRejectedBundleRecords` records are kept while the total count is
+        // still tracked
+        let (count, records) = Domains::rejected_bundles(operator_id);
+        assert_eq!(count, max_records + 1);
+        assert_eq!(
+            records,
+            (2..=max_records as u64 + 1)
+                .map(|block_number| RejectedBundleRecord {
+                    consensus_block_number: block_number,
+                    reason: reason_at(block_number),
+                })
+                .collect::<Vec<_>>()
+        );
+    });
+}
+
+#[test]
+fn test_conflicting_receipt_in_same_block() {
+    let creator = 0u128;
+    let operator_set = vec![1, 2];
+    let mut ext = new_test_ext_with_extensions();
+    ext.execute_with(|| {
+        let domain_id = register_genesis_domain(creator, operator_set.clone());
+        let next_receipt = extend_block_tree_from_zero(domain_id, operator_set[0], 3);
+        let conflicting_receipt = {
+            let mut receipt = next_receipt.clone();
+            receipt.final_state_root = H256::random();
+            receipt
+        };
+        let accepted_receipt_hash = next_receipt.hash::<DomainHashingFor<Test>>();
+        let conflicting_receipt_hash = conflicting_receipt.hash::<DomainHashingFor<Test>>();
+        let create_bundle_and_fund = |operator_id: OperatorId, receipt| {
+            let opaque_bundle =
+                create_dummy_bundle_with_receipts(domain_id, operator_id, H256::random(), receipt);
+            <Test as Config>::Currency::make_free_balance_be(
+                &bundle_storage_fund::storage_fund_account::<Test>(operator_id),
+                <Test as Config>::StorageFee::transaction_byte_fee()
+                    * opaque_bundle.size() as Balance,
+            );
+            opaque_bundle
+        };
+
+        // The first bundle extends the head receipt
+        let opaque_bundle = create_bundle_and_fund(operator_set[0], next_receipt.clone());
+        let call = pallet_domains::Call::<Test>::submit_bundle {
+            opaque_bundle: opaque_bundle.clone(),
+        };
+        assert_ok!(Domains::pre_dispatch(&call));
+        assert_ok!(Domains::submit_bundle(
+            RawOrigin::None.into(),
+            opaque_bundle
+        ));
+        assert_eq!(
+            HeadReceiptNumber::<Test>::get(domain_id),
+            next_receipt.domain_block_number
+        );
+
+        // The sibling bundle that carries a different receipt for the same domain block is
+        // rejected as a whole, so none of its extrinsics is inboxed
+        let opaque_bundle = create_bundle_and_fund(operator_set[1], conflicting_receipt);
+        let sealed_header = opaque_bundle.sealed_header.clone();
+        let bundle_header_hash = sealed_header.pre_hash();
+        assert_eq!(
+            Domains::validate_bundle(&opaque_bundle, true),
+            Err(BundleError::ConflictingReceipt)
+        );
+        let call = pallet_domains::Call::<Test>::submit_bundle { opaque_bundle };
+        assert_eq!(
+            Domains::pre_dispatch(&call),
+            Err(InvalidTransactionCode::ConflictingReceipt.into())
+        );
+        assert!(InboxedBundleAuthor::<Test>::get(bundle_header_hash).is_none());
+
+        // The bundle dropped by the block author is revalidated in the next block with the
+        // same reason
+        run_to_block::<Test>(4, H256::random());
+        assert_eq!(
+            Domains::validate_unsigned(TransactionSource::External, &call),
+            InvalidTransactionCode::ConflictingReceipt.into()
+        );
+
+        // The conflict reported by the block author is recorded
+        let report = |rejected_bundle| {
+            assert_ok!(Domains::report_rejected_bundles(
+                RawOrigin::None.into(),
+                vec![rejected_bundle].try_into().unwrap(),
+            ));
+        };
+        let conflicting_report = |encoded_sealed_header| RejectedBundle {
+            encoded_sealed_header,
+            reason: RejectedBundleReason::ConflictingReceipt,
+        };
+        report(conflicting_report(sealed_header.encode()));
+        let expected_competing_receipts = vec![(operator_set[1], conflicting_receipt_hash)];
+        assert_eq!(
+            Domains::competing_receipts(accepted_receipt_hash),
+            expected_competing_receipts
+        );
+
+        // Duplicated reports and reports that can't be verified are ignored: the signature doesn't
+        // match the header, the receipt is the accepted one, or the header can't be decoded
+        report(conflicting_report(sealed_header.encode()));
+        let mut forged_header = sealed_header.clone();
+        forged_header.header.proof_of_election.slot_number += 1;
+        forged_header.header.receipt.final_state_root = H256::random();
+        report(conflicting_report(forged_header.encode()));
+        report(rejected_bundle_at(
+            domain_id,
+            operator_set[1],
+            sealed_header.header.proof_of_election.slot_number + 2,
+            next_receipt.clone(),
+            RejectedBundleReason::ConflictingReceipt,
+        ));
+        report(conflicting_report(vec![1, 2, 3]));
+        assert_eq!(
+            Domains::competing_receipts(accepted_receipt_hash),
+            expected_competing_receipts
+        );
+        assert!(Domains::competing_receipts(conflicting_receipt_hash).is_empty());
+
+        // The record is removed along with the accepted receipt
+        assert_ok!(crate::block_tree::prune_receipt::<Test>(
+            domain_id,
+            next_receipt.domain_block_number
+        ));
+        assert!(Domains::competing_receipts(accepted_receipt_hash).is_empty());
+    });
+}
+
+#[test]
+#[should_panic(
+    expected = "`StakeWithdrawalLockingPeriod` must not be less than `BlockTreePruningDepth`"
+)]
+fn test_integrity_test_with_bad_config() {
+    <Domains as Hooks<BlockNumber>>::integrity_test();
+
+    StakeWithdrawalLockingPeriod::set(BlockTreePruningDepth::get() - 1);
+    <Domains as Hooks<BlockNumber>>::integrity_test();
+}
+
+#[test]
+fn test_operator_election_threshold() {
+    let creator = 0u128;
+    let operator_id = 1;
+    let operator_stake = SSC;
+    let total_domain_stake = 4 * SSC;
+    let mut ext = new_test_ext_with_extensions();
+    ext.execute_with(|| {
+        let domain_id = register_genesis_domain(creator, vec![operator_id]);
+        DomainStakingSummary::<Test>::mutate(domain_id, |maybe_stake_summary| {
+            let stake_summary = maybe_stake_summary.as_mut().unwrap();
+            stake_summary
+                .current_operators
+                .insert(operator_id, operator_stake);
+            stake_summary.current_total_stake = total_domain_stake;
+        });
+
+        // With `bundle_slot_probability` of (1, 1), the operator holding 1/4 of the total stake
+        // gets 1/4 of the VRF output space, minus the rounding error of the calculation
+        let threshold = Domains::operator_election_threshold(domain_id, operator_id).unwrap();
+        assert!(P256::from(u128::MAX / 4 - total_domain_stake) < threshold);
+        assert!(threshold <= P256::from(u128::MAX / 4));
+        let expected_bundles_per_slot =
+            Domains::operator_expected_bundles_per_slot(domain_id, operator_id).unwrap();
+        assert!(
+            Perquintill::from_percent(25).saturating_sub(expected_bundles_per_slot)
+                <= Perquintill::from_parts(1)
+        );
+
+        // Use the stake distribution of the last epoch if the epoch transitioned in this block
+        LastEpochStakingDistribution::<Test>::insert(
+            domain_id,
+            ElectionVerificationParams {
+                operators: BTreeMap::from_iter([(operator_id, total_domain_stake)]),
+                total_domain_stake,
+            },
+        );
+        let last_epoch_threshold =
+            Domains::operator_election_threshold(domain_id, operator_id).unwrap();
+        assert!(P256::from(u128::MAX - total_domain_stake) <= last_epoch_threshold);
+        assert!(last_epoch_threshold <= P256::from(u128::MAX));
+        LastEpochStakingDistribution::<Test>::remove(domain_id);
+
+        // An active operator without stake never wins the election, which is different from an
+        // unknown operator
+        DomainStakingSummary::<Test>::mutate(domain_id, |maybe_stake_summary| {
+            let stake_summary = maybe_stake_summary.as_mut().unwrap();
+            stake_summary.current_operators.insert(operator_id, 0);
+        });
+        assert_eq!(
+            Domains::operator_election_threshold(domain_id, operator_id),
+            Some(P256::zero())
+        );
+        assert_eq!(
+            Domains::operator_expected_bundles_per_slot(domain_id, operator_id),
+            Some(Perquintill::zero())
+        );
+
+        // Unknown operator
+        assert!(Domains::operator_election_threshold(domain_id, operator_id + 1).is_none());
+        assert!(Domains::operator_expected_bundles_per_slot(domain_id, operator_id + 1).is_none());
+    });
+}
+
+#[test]
+fn test_proof_of_election_at_operator_election_threshold() {
+    let creator = 0u128;
+    let operator_id = 1;
+    let mut ext = new_test_ext_with_extensions();
+    ext.execute_with(|| {
+        let domain_id = register_genesis_domain(creator, vec![operator_id]);
+
+        let pair = OperatorPair::from_seed(&U256::from(0u32).into());
+        let slot_number = 1u64;
+        let proof_of_time = PotOutput::default();
+        let global_challenge = proof_of_time
+            .derive_global_randomness()
+            .derive_global_challenge(slot_number);
+        let vrf_signature = pair
+            .as_inner_ref()
+            .vrf_sign(&make_transcript(domain_id, &global_challenge).into_sign_data());
+        let vrf_output = u128::from_le_bytes(
+            vrf_signature.pre_output.0.to_bytes()[..mem::size_of::<u128>()]
+                .try_into()
+                .unwrap(),
+        );
+        let proof_of_election = ProofOfElection {
+            domain_id,
+            slot_number,
+            proof_of_time,
+            vrf_signature,
+            operator_id,
+            consensus_block_hash: H256::default(),
+        };
+
+        // With `bundle_slot_probability` of (1, 1) and a total stake of `u128::MAX`, the threshold
+        // is the operator stake
+        let check_proof_of_election_with_operator_stake = |operator_stake: Balance| {
+            DomainStakingSummary::<Test>::mutate(domain_id, |maybe_stake_summary| {
+                let stake_summary = maybe_stake_summary.as_mut().unwrap();
+                stake_summary
+                    .current_operators
+                    .insert(operator_id, operator_stake);
+                stake_summary.current_total_stake = u128::MAX;
+            });
+            let threshold = Domains::operator_election_threshold(domain_id, operator_id).unwrap();
+            let result = check_proof_of_election(
+                &pair.public(),
+                (1, 1),
+                &proof_of_election,
+                operator_stake,
+                u128::MAX,
+            );
+            (threshold, result)
+        };
+
+        // The vrf output just below the threshold wins the election
+        let (threshold, result) = check_proof_of_election_with_operator_stake(vrf_output + 1);
+        assert_eq!(threshold, P256::from(vrf_output) + P256::one());
+        assert_ok!(result);
+
+        // The vrf output exactly at the threshold loses the election
+        let (threshold, result) = check_proof_of_election_with_operator_stake(vrf_output);
+        assert_eq!(threshold, P256::from(vrf_output));
+        assert_err!(result, ProofOfElectionError::ThresholdUnsatisfied);
+    });
+}
+
+#[test]
+fn test_bundle_producer_election_params_at() {
+    let creator = 0u128;
+    let operator_id = 1;
+    let mut ext = new_test_ext_with_extensions();
+    ext.execute_with(|| {
+        let domain_id = register_genesis_domain(creator, vec![operator_id]);
+        DomainStakingSummary::<Test>::mutate(domain_id, |maybe_stake_summary| {
+            let stake_summary = maybe_stake_summary.as_mut().unwrap();
+            stake_summary.current_operators.insert(operator_id, SSC);
+            stake_summary.current_total_stake = SSC;
+            stake_summary.next_operators.insert(operator_id);
+        });
+        let epoch_transition_with_operator_stake = |operator_stake: BalanceOf<Test>| {
+            Operators::<Test>::mutate(operator_id, |maybe_operator| {
+                maybe_operator.as_mut().unwrap().current_total_stake = operator_stake
+            });
+            assert_ok!(do_finalize_domain_epoch_staking::<Test>(domain_id));
+        };
+
+        // Produce a proof of election in epoch 0
+        let pair = OperatorPair::from_seed(&U256::from(0u32).into());
+        let slot_number = 1u64;
+        let proof_of_time = PotOutput::default();
+        let global_challenge = proof_of_time
+            .derive_global_randomness()
+            .derive_global_challenge(slot_number);
+        let proof_of_election = ProofOfElection {
+            domain_id,
+            slot_number,
+            proof_of_time,
+            vrf_signature: pair
+                .as_inner_ref()
+                .vrf_sign(&make_transcript(domain_id, &global_challenge).into_sign_data()),
+            operator_id,
+            consensus_block_hash: H256::default(),
+        };
+
+        // Two further epoch transitions with the operator stake changed
+        epoch_transition_with_operator_stake(2 * SSC);
+        epoch_transition_with_operator_stake(3 * SSC);
+
+        let expected_epoch_params = |epoch_stake: BalanceOf<Test>| EpochElectionParams {
+            params: BundleProducerElectionParams {
+                total_domain_stake: epoch_stake,
+                bundle_slot_probability: (1, 1),
+            },
+            operator_stakes: BTreeMap::from_iter([(operator_id, epoch_stake)]),
+        };
+        assert_eq!(
+            Domains::bundle_producer_election_params_at(domain_id, 2),
+            Some(expected_epoch_params(3 * SSC))
+        );
+        assert_eq!(
+            Domains::bundle_producer_election_params_at(domain_id, 1),
+            Some(expected_epoch_params(2 * SSC))
+        );
+        assert_eq!(
+            Domains::bundle_producer_election_params_at(domain_id, 3),
+            None
+        );
+
+        // The old proof of election can be verified against the historical snapshot
+        let EpochElectionParams {
+            params,
+            operator_stakes,
+        } = Domains::bundle_producer_election_params_at(domain_id, 0).unwrap();
+        assert_eq!(params.total_domain_stake, SSC);
+        assert_ok!(check_proof_of_election(
+            &pair.public(),
+            params.bundle_slot_probability,
+            &proof_of_election,
+            operator_stakes[&operator_id],
+            params.total_domain_stake,
+        ));
+
+        // The snapshot is pruned after `election_params_history_depth` epochs
+        for _ in 0..Domains::election_params_history_depth() - 1 {
+            epoch_transition_with_operator_stake(3 * SSC);
+        }
+        assert_eq!(
+            Domains::bundle_producer_election_params_at(domain_id, 0),
+            None
+        );
+        assert!(Domains::bundle_producer_election_params_at(domain_id, 1).is_some());
+    });
+}
+
+#[test]
+fn test_execution_inbox_digests() {
+    let creator = 0u128;
+    let operator_set = vec![1, 2, 3];
+    let mut ext = new_test_ext_with_extensions();
+    ext.execute_with(|| {
+        let domain_id = register_genesis_domain(creator, operator_set.clone());
+        let next_receipt = extend_block_tree_from_zero(domain_id, operator_set[0], 3);
+        let current_block_number = frame_system::Pallet::<Test>::current_block_number();
+
+        // No bundle submitted in the current block yet
+        assert!(Domains::current_inbox(domain_id).is_empty());
+
+        let mut expected_digests = vec![];
+        for operator_id in operator_set.iter() {
+            let bundle_extrinsics_root = H256::random();
+            let bundle = create_dummy_bundle_with_receipts(
+                domain_id,
+                *operator_id,
+                bundle_extrinsics_root,
+                next_receipt.clone(),
+            );
+            expected_digests.push(BundleDigest {
+                header_hash: bundle.sealed_header.pre_hash(),
+                extrinsics_root: bundle_extrinsics_root,
+                size: bundle.size(),
+            });
+            assert_ok!(Domains::submit_bundle(RawOrigin::None.into(), bundle));
+        }
+
+        let head_domain_number = Domains::domain_best_number(domain_id).unwrap();
+        let execution_inbox =
+            ExecutionInbox::<Test>::get((domain_id, head_domain_number, current_block_number));
+        assert_eq!(execution_inbox, expected_digests);
+
+        let current_inbox = Domains::current_inbox(domain_id);
+        assert_eq!(current_inbox, expected_digests);
+        assert_eq!(current_inbox.encode(), execution_inbox.encode());
+        assert_eq!(
+            Domains::execution_inbox_digests(domain_id, head_domain_number, current_block_number),
+            expected_digests
+        );
+
+        // The inbox of the previous domain block only contains the bundle submitted in the
+        // previous block
+        assert_eq!(
+            Domains::execution_inbox_digests(
+                domain_id,
+                head_domain_number - 1,
+                current_block_number - 1
+            )
+            .len(),
+            1
+        );
+        assert!(Domains::execution_inbox_digests(
+            domain_id,
+            head_domain_number,
+            current_block_number - 1
+        )
+        .is_empty());
+    });
+}
+
+#[test]
+fn test_lagging_receipt_bundle_priority() {
+    let creator = 0u128;
+    let operator_set = vec![1, 2];
+    let mut ext = new_test_ext_with_extensions();
+    ext.execute_with(|| {
+        let domain_id = register_genesis_domain(creator, operator_set.clone());
+        let next_receipt = extend_block_tree_from_zero(domain_id, operator_set[0], 3);
+        for operator_id in operator_set.iter() {
+            <Test as Config>::Currency::make_free_balance_be(
+                &bundle_storage_fund::storage_fund_account::<Test>(*operator_id),
+                1000 * SSC,
+            );
+        }
+        let bundle_priority = |operator_id: OperatorId| {
+            let opaque_bundle = create_dummy_bundle_with_receipts(
+                domain_id,
+                operator_id,
+                H256::random(),
+                next_receipt.clone(),
+            );
+            let call = pallet_domains::Call::<Test>::submit_bundle { opaque_bundle };
+            Domains::validate_unsigned(TransactionSource::External, &call)
+                .unwrap()
+                .priority
+        };
+
+        // Bundle that extends the head receipt is not deprioritized even if the receipts are lagging
+        HeadDomainNumber::<Test>::insert(
+            domain_id,
+            HeadReceiptNumber::<Test>::get(domain_id) + MaxReceiptGap::get() + 1,
+        );
+        assert_eq!(bundle_priority(operator_set[0]), 1);
+        assert_ok!(Domains::submit_bundle(
+            RawOrigin::None.into(),
+            create_dummy_bundle_with_receipts(
+                domain_id,
+                operator_set[0],
+                H256::random(),
+                next_receipt.clone(),
+            ),
+        ));
+
+        // Bundle that only confirms the head receipt is deprioritized
+        assert_eq!(bundle_priority(operator_set[1]), 0);
+
+        HeadDomainNumber::<Test>::insert(
+            domain_id,
+            HeadReceiptNumber::<Test>::get(domain_id) + MaxReceiptGap::get(),
+        );
+        assert_eq!(bundle_priority(operator_set[1]), 1);
+    });
+}
+
+#[test]
+fn test_bundle_storage_fee_charged_only_at_inclusion() {
+    let creator = 0u128;
+    let operator_set = vec![1, 2];
+    let mut ext = new_test_ext_with_extensions();
+    ext.execute_with(|| {
+        let domain_id = register_genesis_domain(creator, operator_set.clone());
+        let next_receipt = extend_block_tree_from_zero(domain_id, operator_set[0], 3);
+        // Create a bundle of the given operator and fund its bundle storage fund with the given
+        // amount relative to the bundle storage fee
+        let create_bundle_and_fund = |operator_id: OperatorId, lack: Balance| {
+            let opaque_bundle = create_dummy_bundle_with_receipts(
+                domain_id,
+                operator_id,
+                H256::random(),
+                next_receipt.clone(),
+            );
+            let storage_fee = <Test as Config>::StorageFee::transaction_byte_fee()
+                * opaque_bundle.size() as Balance;
+            <Test as Config>::Currency::make_free_balance_be(
+                &bundle_storage_fund::storage_fund_account::<Test>(operator_id),
+                storage_fee - lack,
+            );
+            (opaque_bundle, storage_fee)
+        };
+
+        // Pool validation fails without charging if the fund can't afford the storage fee
+        let (opaque_bundle, storage_fee) = create_bundle_and_fund(operator_set[0], 1);
+        let call = pallet_domains::Call::<Test>::submit_bundle {
+            opaque_bundle: opaque_bundle.clone(),
+        };
+        assert_eq!(
+            Domains::validate_unsigned(TransactionSource::External, &call),
+            InvalidTransactionCode::InsufficientBundleStorageFund.into()
+        );
+        assert_eq!(
+            bundle_storage_fund::total_balance::<Test>(operator_set[0]),
+            storage_fee - 1
+        );
+
+        // Pool validation without inclusion doesn't charge the storage fee
+        let (opaque_bundle, storage_fee) = create_bundle_and_fund(operator_set[0], 0);
+        let call = pallet_domains::Call::<Test>::submit_bundle {
+            opaque_bundle: opaque_bundle.clone(),
+        };
+        assert_ok!(Domains::validate_unsigned(
+            TransactionSource::External,
+            &call
+        ));
+        assert_ok!(Domains::validate_unsigned(
+            TransactionSource::External,
+            &call
+        ));
+        assert_eq!(
+            bundle_storage_fund::total_balance::<Test>(operator_set[0]),
+            storage_fee
+        );
+
+        // Inclusion after pool validation charges the storage fee exactly once
+        assert_ok!(Domains::pre_dispatch(&call));
+        assert_ok!(Domains::submit_bundle(
+            RawOrigin::None.into(),
+            opaque_bundle
+        ));
+        assert_eq!(
+            bundle_storage_fund::total_balance::<Test>(operator_set[0]),
+            0
+        );
+
+        // Bundle included directly by the block author without pool validation is also charged
+        // exactly once
+        let (opaque_bundle, _) = create_bundle_and_fund(operator_set[1], 0);
+        let call = pallet_domains::Call::<Test>::submit_bundle {
+            opaque_bundle: opaque_bundle.clone(),
+        };
+        assert_ok!(Domains::pre_dispatch(&call));
+        assert_ok!(Domains::submit_bundle(
+            RawOrigin::None.into(),
+            opaque_bundle
+        ));
+        assert_eq!(
+            bundle_storage_fund::total_balance::<Test>(operator_set[1]),
+            0
+        );
+    });
+}
+
+#[test]
+fn test_bundle_storage_fee_payment_errors() {
+    let operator_id = 1;
+    let bundle_size = 10;
+    let storage_fee = <Test as Config>::StorageFee::transaction_byte_fee() * bundle_size as Balance;
+    let storage_fund_acc = bundle_storage_fund::storage_fund_account::<Test>(operator_id);
+
+    let mut ext = new_test_ext_with_extensions();
+    ext.execute_with(|| {
+        // The shortfall and the error of the pool validation must match the charge path exactly
+        let assert_storage_fee_payment =
+            |expected: Result<(), BundleStorageFundError>, shortfall: Option<Balance>| {
+                assert_eq!(
+                    Domains::storage_fee_shortfall(operator_id, bundle_size),
+                    shortfall
+                );
+                assert_eq!(
+                    bundle_storage_fund::ensure_bundle_storage_fee_affordable::<Test>(
+                        operator_id,
+                        bundle_size
+                    )
+                    .map(|_| ()),
+                    expected
+                );
+                assert_eq!(
+                    bundle_storage_fund::charge_bundle_storage_fee::<Test>(
+                        operator_id,
+                        bundle_size
+                    ),
+                    expected
+                );
+            };
+
+        // Empty fund
+        assert_storage_fee_payment(
+            Err(BundleStorageFundError::BundleStorageFundEmpty),
+            Some(storage_fee),
+        );
+        assert_eq!(
+            InvalidTransactionCode::from(&BundleStorageFundError::BundleStorageFundEmpty),
+            InvalidTransactionCode::BundleStorageFundEmpty
+        );
+
+        // Fund below the storage fee
+        Balances::make_free_balance_be(&storage_fund_acc, storage_fee - 3);
+        let err = BundleStorageFundError::InsufficientBundleStorageFund {
+            required: storage_fee,
+            available: storage_fee - 3,
+        };
+        assert_eq!(
+            InvalidTransactionCode::from(&err),
+            InvalidTransactionCode::InsufficientBundleStorageFund
+        );
+        assert_storage_fee_payment(Err(err), Some(3));
+
+        // Fund that must be kept alive can't pay the existential deposit part
+        Balances::make_free_balance_be(&storage_fund_acc, storage_fee);
+        assert_ok!(frame_system::Pallet::<Test>::inc_consumers(
+            &storage_fund_acc
+        ));
+        let err = BundleStorageFundError::BundleStorageFeeWouldKillAccount {
+            required: storage_fee,
+            available: storage_fee - ExistentialDeposit::get(),
+        };
+        assert_eq!(
+            InvalidTransactionCode::from(&err),
+            InvalidTransactionCode::BundleStorageFeeWouldKillAccount
+        );
+        assert_storage_fee_payment(Err(err), Some(ExistentialDeposit::get()));
+        frame_system::Pallet::<Test>::dec_consumers(&storage_fund_acc);
+
+        // Affordable
+        assert_storage_fee_payment(Ok(()), None);
+        assert_eq!(bundle_storage_fund::total_balance::<Test>(operator_id), 0);
+    });
+}
+
+#[test]
+fn test_domain_fee_totals() {
+    let creator = 0u128;
+    let operator_id = 1;
+    let mut ext = new_test_ext_with_extensions();
+    ext.execute_with(|| {
+        let domain_id = register_genesis_domain(creator, vec![operator_id]);
+        // Operator takes a part of its rewards as tax at every epoch transition
+        OperatorIdOwner::<Test>::insert(operator_id, creator);
+        Operators::<Test>::mutate(operator_id, |maybe_operator| {
+            maybe_operator.as_mut().unwrap().nomination_tax = Percent::from_percent(10);
+        });
+        let mut latest_receipt =
+            extend_block_tree_from_zero(domain_id, operator_id, BlockTreePruningDepth::get() + 1);
+
+        // Submit receipts with non-zero fees and collect the fees reported by the events of every
+        // confirmed domain block and epoch transition
+        let mut expected_totals = Domains::domain_fee_totals(domain_id);
+        let mut expected_epoch_fees = BTreeMap::<EpochIndex, DomainFees<Balance>>::new();
+        for _ in 0..BlockTreePruningDepth::get() + 3 * StakeEpochDuration::get() {
+            let block_number = frame_system::Pallet::<Test>::current_block_number();
+            latest_receipt.block_fees.domain_execution_fee = block_number as Balance * SSC;
+            latest_receipt.block_fees.consensus_storage_fee = 2 * SSC;
+
+            let epoch_index = DomainStakingSummary::<Test>::get(domain_id)
+                .unwrap()
+                .current_epoch_index;
+            let bundle_extrinsics_root = H256::random();
+            let bundle = create_dummy_bundle_with_receipts(
+                domain_id,
+                operator_id,
+                bundle_extrinsics_root,
+                latest_receipt,
+            );
+            System::reset_events();
+            assert_ok!(Domains::submit_bundle(RawOrigin::None.into(), bundle));
+
+            let mut block_fees = DomainFees::default();
+            for record in System::events() {
+                match record.event {
+                    RuntimeEvent::Domains(Event::OperatorRewarded { reward, .. }) => {
+                        block_fees.operator_rewards += reward
+                    }
+                    RuntimeEvent::Domains(Event::StorageFeeRefunded { amount, .. }) => {
+                        block_fees.storage_fee_refunds += amount
+                    }
+                    RuntimeEvent::Domains(Event::OperatorTaxCollected { tax, .. }) => {
+                        block_fees.operator_tax += tax
+                    }
+                    _ => {}
+                }
+            }
+            // Tax is taken from the rewards noted in the same epoch, hence they are adjusted
+            // after accruing
+            let operator_tax = mem::take(&mut block_fees.operator_tax);
+            for fees in [
+                &mut expected_totals,
+                expected_epoch_fees.entry(epoch_index).or_default(),
+            ] {
+                fees.saturating_accrue(block_fees);
+                fees.operator_rewards -= operator_tax;
+                fees.operator_tax += operator_tax;
+            }
+
+            let head_receipt_number = HeadReceiptNumber::<Test>::get(domain_id);
+            let parent_block_tree_node =
+                get_block_tree_node_at::<Test>(domain_id, head_receipt_number).unwrap();
+            latest_receipt = create_dummy_receipt(
+                block_number,
+                H256::random(),
+                parent_block_tree_node
+                    .execution_receipt
+                    .hash::<DomainHashingFor<Test>>(),
+                vec![bundle_extrinsics_root],
+            );
+            run_to_block::<Test>(block_number + 1, latest_receipt.consensus_block_hash);
+        }
+
+        // Fees of the receipts submitted above are confirmed and refunded to the operator
+        let totals = Domains::domain_fee_totals(domain_id);
+        assert_eq!(totals, expected_totals);
+        assert!(totals.operator_rewards > 0);
+        assert!(totals.storage_fee_refunds > 0);
+        assert!(totals.operator_tax > 0);
+
+        // Only the last `DomainFeeHistoryDepth` epochs are kept
+        let current_epoch_index = DomainStakingSummary::<Test>::get(domain_id)
+            .unwrap()
+            .current_epoch_index;
+        let history_depth = DomainFeeHistoryDepth::get();
+        assert!(current_epoch_index >= history_depth);
+        for (epoch_index, epoch_fees) in expected_epoch_fees {
+            if epoch_index + history_depth > current_epoch_index {
+                assert_eq!(
+                    Domains::domain_epoch_fees(domain_id, epoch_index),
+                    Some(epoch_fees)
+                );
+            } else {
+                assert_eq!(Domains::domain_epoch_fees(domain_id, epoch_index), None);
+            }
+        }
+    });
+}
+
+#[test]
+fn test_cheap_bundle_checks_before_signature() {
+    let creator = 0u128;
+    let operator_id = 1u64;
+    let mut ext = new_test_ext_with_extensions();
+    ext.execute_with(|| {
+        let domain_id = register_genesis_domain(creator, vec![operator_id]);
+        let genesis_receipt = get_block_tree_node_at::<Test>(domain_id, 0)
+            .unwrap()
+            .execution_receipt;
+        let valid_bundle = create_dummy_bundle_with_receipts(
+            domain_id,
+            operator_id,
+            Default::default(),
+            genesis_receipt,
+        );
+        let extrinsic =
+            |size: usize| OpaqueExtrinsic::from_bytes(&vec![0u8; size].encode()).unwrap();
+        let signature_checks = bundle_signature_checks();
+
+        // Bundle body exceeds the hard size limit
+        let mut bundle = valid_bundle.clone();
+        bundle
+            .extrinsics
+            .push(extrinsic(MaxDomainBlockSize::get() as usize));
+        assert_err!(
+            Domains::validate_bundle(&bundle, false),
+            BundleError::BundleTooLarge
+        );
+
+        // Bundle contains too many extrinsics
+        let mut bundle = valid_bundle.clone();
+        bundle.extrinsics = vec![extrinsic(0); MaxBundleExtrinsics::get() as usize + 1];
+        assert_err!(
+            Domains::validate_bundle(&bundle, false),
+            BundleError::TooManyExtrinsics
+        );
+
+        // The extrinsic count is checked before the body size is computed
+        bundle
+            .extrinsics
+            .push(extrinsic(MaxDomainBlockSize::get() as usize));
+        assert_err!(
+            Domains::validate_bundle(&bundle, false),
+            BundleError::TooManyExtrinsics
+        );
+
+        // Bundle exceeds the bundle size limit of the domain
+        let mut bundle = valid_bundle.clone();
+        bundle.extrinsics.push(extrinsic(1024));
+        assert_err!(
+            Domains::validate_bundle(&bundle, false),
+            BundleError::BundleTooLarge
+        );
+
+        // Bundle of an unknown operator
+        let mut bundle = valid_bundle.clone();
+        bundle.sealed_header.header.proof_of_election.operator_id = operator_id + 1;
+        assert_err!(
+            Domains::validate_bundle(&bundle, false),
+            BundleError::InvalidOperatorId
+        );
+
+        // Bundle of an unknown domain
+        let mut bundle = valid_bundle.clone();
+        bundle.sealed_header.header.proof_of_election.domain_id = DomainId::new(100);
+        assert_err!(
+            Domains::validate_bundle(&bundle, false),
+            BundleError::InvalidDomainId
+        );
+
+        // Bundle of a paused operator
+        Operators::<Test>::mutate(operator_id, |maybe_operator| {
+            maybe_operator.as_mut().unwrap().paused = true
+        });
+        assert_err!(
+            Domains::validate_bundle(&valid_bundle, false),
+            BundleError::OperatorPaused
+        );
+        Operators::<Test>::mutate(operator_id, |maybe_operator| {
+            maybe_operator.as_mut().unwrap().paused = false
+        });
+
+        // Duplicated bundle
+        let bundle_header_hash = valid_bundle.sealed_header.pre_hash();
+        InboxedBundleAuthor::<Test>::insert(bundle_header_hash, operator_id);
+        assert_err!(
+            Domains::validate_bundle(&valid_bundle, false),
+            BundleError::DuplicatedBundle
+        );
+        InboxedBundleAuthor::<Test>::remove(bundle_header_hash);
+
+        // Bundle with the receipt of another domain
+        let other_domain_id = register_genesis_domain(creator + 1, vec![]);
+        let other_genesis_receipt = get_block_tree_node_at::<Test>(other_domain_id, 0)
+            .unwrap()
+            .execution_receipt;
+        assert_ne!(other_genesis_receipt, valid_bundle.receipt().clone());
+        let bundle = create_dummy_bundle_with_receipts(
+            domain_id,
+            operator_id,
+            Default::default(),
+            other_genesis_receipt,
+        );
+        assert_err!(
+            Domains::validate_bundle(&bundle, false),
+            BundleError::ReceiptDomainMismatch
+        );
+
+        // Bundle with the next receipt that doesn't extend the block tree of the domain
+        let mut next_receipt = valid_bundle.receipt().clone();
+        next_receipt.domain_block_number = 1;
+        next_receipt.parent_domain_block_receipt_hash = H256::random();
+        let bundle = create_dummy_bundle_with_receipts(
+            domain_id,
+            operator_id,
+            Default::default(),
+            next_receipt,
+        );
+        assert_err!(
+            Domains::validate_bundle(&bundle, false),
+            BundleError::ReceiptDomainMismatch
+        );
+
+        // None of the bundles above reached the signature verification
+        assert_eq!(bundle_signature_checks(), signature_checks);
+
+        let mut bad_signature_bundle = valid_bundle;
+        bad_signature_bundle
+            .sealed_header
+            .header
+            .bundle_extrinsics_root = H256::random();
+        assert_err!(
+            Domains::validate_bundle(&bad_signature_bundle, false),
+            BundleError::BadBundleSignature
+        );
+        assert_eq!(bundle_signature_checks(), signature_checks + 1);
+    });
+}
+
+#[test]
+fn test_storage_fund_account_derivation() {
+    new_test_ext().execute_with(|| {
+        // Wallets and explorers rely on the derivation, changing it is a breaking change
+        assert_eq!(
+            Domains::storage_fund_account(0),
+            0x000000005f736e69616d6f646c646f6d
+        );
+        assert_eq!(
+            Domains::storage_fund_account(1),
+            0x000001005f736e69616d6f646c646f6d
+        );
+        assert_eq!(
+            Domains::storage_fund_account(1),
+            bundle_storage_fund::storage_fund_account::<Test>(1)
+        );
+    });
+}
+
+/// Builds the effective params of the domain from the config items and functions used by
+/// validation. The struct is built without `..`, such that a new param fails to compile until it
+/// is covered here.
+fn expected_effective_domain_params(
+    domain_id: DomainId,
+) -> EffectiveDomainParams<DomainBlockNumber> {
+    let domain_config = DomainRegistry::<Test>::get(domain_id)
+        .unwrap()
+        .domain_config;
+
+    EffectiveDomainParams {
+        block_limit: DomainBlockLimit {
+            max_block_size: domain_config.max_block_size,
+            max_block_weight: domain_config.max_block_weight,
+        },
+        bundle_limit: domain_config.calculate_bundle_limit::<Test>().unwrap(),
+        bundle_longevity: BundleLongevity::get(),
+        epoch_duration: StakeEpochDuration::get(),
+        pruning_depth: BlockTreePruningDepth::get(),
+        bundle_slot_probability: domain_config.bundle_slot_probability,
+        tx_range: Domains::domain_tx_range(domain_id),
+    }
+}
+
+#[test]
+fn test_effective_domain_params_defaults() {
+    new_test_ext().execute_with(|| {
+        let domain_id = register_genesis_domain(0u128, vec![]);
+
+        let params = Domains::effective_domain_params(domain_id).unwrap();
+        assert_eq!(params, expected_effective_domain_params(domain_id));
+
+        // Each param matches what the corresponding API returns on its own
+        let EffectiveDomainParams {
+            block_limit,
+            bundle_limit,
+            bundle_longevity,
+            epoch_duration,
+            pruning_depth,
+            bundle_slot_probability,
+            tx_range,
+        } = params;
+        assert_eq!(Some(block_limit), Domains::domain_block_limit(domain_id));
+        assert_eq!(
+            Some(bundle_limit),
+            Domains::domain_bundle_limit(domain_id).unwrap()
+        );
+        assert_eq!(bundle_longevity, BundleLongevity::get());
+        assert_eq!(epoch_duration, StakeEpochDuration::get());
+        assert_eq!(pruning_depth, BlockTreePruningDepth::get());
+        assert_eq!(bundle_slot_probability, (1, 1));
+        assert_eq!(
+            tx_range,
+            U256::MAX / <Test as Config>::InitialDomainTxRange::get()
+        );
+
+        assert_eq!(Domains::effective_domain_params(DomainId::new(100)), None);
+    });
+}
+
+#[test]
+fn test_effective_domain_params_overrides() {
+    new_test_ext().execute_with(|| {
+        let domain_id = register_genesis_domain(0u128, vec![]);
+        let default_params = Domains::effective_domain_params(domain_id).unwrap();
+
+        // Domain with its own block limit, slot probability and adjusted tx range
+        DomainRegistry::<Test>::mutate(domain_id, |maybe_domain_obj| {
+            let domain_config = &mut maybe_domain_obj.as_mut().unwrap().domain_config;
+            domain_config.max_block_size = 2048;
+            domain_config.max_block_weight = Weight::from_parts(4096, 0);
+            domain_config.bundle_slot_probability = (1, 2);
+        });
+        let tx_range = U256::MAX / 7;
+        DomainTxRangeState::<Test>::insert(
+            domain_id,
+            TxRangeState {
+                tx_range,
+                interval_blocks: 1,
+                interval_bundles: 1,
+            },
+        );
+
+        let params = Domains::effective_domain_params(domain_id).unwrap();
+        assert_eq!(params, expected_effective_domain_params(domain_id));
+        assert_ne!(params, default_params);
+        assert_eq!(
+            params.block_limit,
+            DomainBlockLimit {
+                max_block_size: 2048,
+                max_block_weight: Weight::from_parts(4096, 0),
+            }
+        );
+        assert_eq!(params.bundle_slot_probability, (1, 2));
+        assert_eq!(params.tx_range, tx_range);
+        assert_ne!(params.bundle_limit, default_params.bundle_limit);
+        assert_eq!(
+            Some(params.bundle_limit),
+            Domains::domain_bundle_limit(domain_id).unwrap()
+        );
+    });
+}
+
+#[test]
+fn test_genesis_execution_receipt() {
+    let creator = 0u128;
+    let operator_id = 1u64;
+    let mut ext = new_test_ext_with_extensions();
+    ext.execute_with(|| {
+        let domain_id = register_genesis_domain(creator, vec![operator_id]);
+        let genesis_node = get_block_tree_node_at::<Test>(domain_id, 0).unwrap();
+
+        // The exact genesis receipt in the block tree is returned
+        let genesis_receipt = Domains::genesis_execution_receipt(domain_id).unwrap();
+        assert_eq!(genesis_receipt, genesis_node.execution_receipt);
+        assert_eq!(
+            genesis_receipt.hash::<DomainHashingFor<Test>>(),
+            DomainRegistry::<Test>::get(domain_id)
+                .unwrap()
+                .genesis_receipt_hash
+        );
+        assert_eq!(
+            Domains::genesis_state_root(domain_id),
+            Some(genesis_receipt.final_state_root)
+        );
+
+        // The same genesis receipt is derived from the domain instance data
+        let runtime_id = Domains::runtime_id(domain_id).unwrap();
+        let state_version = crate::RuntimeRegistry::<Test>::get(runtime_id)
+            .unwrap()
+            .version
+            .state_version();
+        let (domain_instance_data, _) = Domains::domain_instance_data(domain_id).unwrap();
+        assert_eq!(
+            crate::domain_registry::derive_genesis_receipt::<Test>(
+                &domain_instance_data.raw_genesis,
+                state_version
+            ),
+            genesis_receipt
+        );
+
+        // The genesis receipt stays the same while the block tree extends
+        extend_block_tree_from_zero(domain_id, operator_id, 3);
+        assert_eq!(
+            Domains::genesis_execution_receipt(domain_id),
+            Some(genesis_receipt)
+        );
+
+        assert!(Domains::genesis_execution_receipt(DomainId::new(100)).is_none());
+    });
+}
@@ -238,27 +238,31 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
 	/// Proof: `Domains::NextRuntimeId` (`max_values`: Some(1), `max_size`: None, mode: `Measured`)
 	/// Storage: `Domains::RuntimeRegistry` (r:0 w:1)
 	/// Proof: `Domains::RuntimeRegistry` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Domains::RuntimeSummaries` (r:0 w:1)
+	/// Proof: `Domains::RuntimeSummaries` (`max_values`: None, `max_size`: None, mode: `Measured`)
 	fn register_domain_runtime() -> Weight {
 		// Proof Size summary in bytes:
 		//  Measured:  `297`
 		//  Estimated: `1782`
-		// Minimum execution time: 19_240_000_000 picoseconds.
-		Weight::from_parts(20_604_000_000, 1782)
+		// Minimum execution time: 19_262_000_000 picoseconds.
+		Weight::from_parts(20_631_000_000, 1782)
 			.saturating_add(T::DbWeight::get().reads(1_u64))
-			.saturating_add(T::DbWeight::get().writes(2_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
 	}
 	/// Storage: `Domains::RuntimeRegistry` (r:1 w:0)
 	/// Proof: `Domains::RuntimeRegistry` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Domains::PendingRuntimeUpgrades` (r:1 w:1)
+	/// Proof: `Domains::PendingRuntimeUpgrades` (`max_values`: None, `max_size`: None, mode: `Measured`)
 	/// Storage: `Domains::ScheduledRuntimeUpgrades` (r:0 w:1)
 	/// Proof: `Domains::ScheduledRuntimeUpgrades` (`max_values`: None, `max_size`: None, mode: `Measured`)
 	fn upgrade_domain_runtime() -> Weight {
 		// Proof Size summary in bytes:
 		//  Measured:  `2481404`
 		//  Estimated: `2484869`
-		// Minimum execution time: 19_355_000_000 picoseconds.
-		Weight::from_parts(19_883_000_000, 2484869)
-			.saturating_add(T::DbWeight::get().reads(1_u64))
-			.saturating_add(T::DbWeight::get().writes(1_u64))
+		// Minimum execution time: 19_361_000_000 picoseconds.
+		Weight::from_parts(19_890_000_000, 2484869)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
 	}
 	/// Storage: `Sudo::Key` (r:1 w:0)
 	/// Proof: `Sudo::Key` (`max_values`: Some(1), `max_size`: Some(32), added: 527, mode: `MaxEncodedLen`)
@@ -421,18 +425,20 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
 	/// Proof: `Domains::LatestConfirmedDomainBlock` (`max_values`: None, `max_size`: None, mode: `Measured`)
 	/// Storage: `Balances::Holds` (r:1 w:1)
 	/// Proof: `Balances::Holds` (`max_values`: None, `max_size`: Some(2750), added: 5225, mode: `MaxEncodedLen`)
-	/// Storage: `System::Account` (r:1 w:1)
+	/// Storage: `System::Account` (r:2 w:2)
 	/// Proof: `System::Account` (`max_values`: None, `max_size`: Some(128), added: 2603, mode: `MaxEncodedLen`)
 	/// Storage: `Domains::Deposits` (r:1 w:1)
 	/// Proof: `Domains::Deposits` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Domains::TaxPaid` (r:0 w:1)
+	/// Proof: `Domains::TaxPaid` (`max_values`: None, `max_size`: None, mode: `Measured`)
 	fn unlock_funds() -> Weight {
 		// Proof Size summary in bytes:
-		//  Measured:  `1390`
-		//  Estimated: `6215`
-		// Minimum execution time: 89_000_000 picoseconds.
-		Weight::from_parts(93_000_000, 6215)
-			.saturating_add(T::DbWeight::get().reads(8_u64))
-			.saturating_add(T::DbWeight::get().writes(4_u64))
+		//  Measured:  `1496`
+		//  Estimated: `8818`
+		// Minimum execution time: 104_000_000 picoseconds.
+		Weight::from_parts(109_000_000, 8818)
+			.saturating_add(T::DbWeight::get().reads(9_u64))
+			.saturating_add(T::DbWeight::get().writes(6_u64))
 	}
 	/// Storage: `Domains::Operators` (r:1 w:1)
 	/// Proof: `Domains::Operators` (`max_values`: None, `max_size`: None, mode: `Measured`)
@@ -671,27 +677,31 @@ impl WeightInfo for () {
 	/// Proof: `Domains::NextRuntimeId` (`max_values`: Some(1), `max_size`: None, mode: `Measured`)
 	/// Storage: `Domains::RuntimeRegistry` (r:0 w:1)
 	/// Proof: `Domains::RuntimeRegistry` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Domains::RuntimeSummaries` (r:0 w:1)
+	/// Proof: `Domains::RuntimeSummaries` (`max_values`: None, `max_size`: None, mode: `Measured`)
 	fn register_domain_runtime() -> Weight {
 		// Proof Size summary in bytes:
 		//  Measured:  `297`
 		//  Estimated: `1782`
-		// Minimum execution time: 19_240_000_000 picoseconds.
-		Weight::from_parts(20_604_000_000, 1782)
+		// Minimum execution time: 19_262_000_000 picoseconds.
+		Weight::from_parts(20_631_000_000, 1782)
 			.saturating_add(ParityDbWeight::get().reads(1_u64))
-			.saturating_add(ParityDbWeight::get().writes(2_u64))
+			.saturating_add(ParityDbWeight::get().writes(3_u64))
 	}
 	/// Storage: `Domains::RuntimeRegistry` (r:1 w:0)
 	/// Proof: `Domains::RuntimeRegistry` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Domains::PendingRuntimeUpgrades` (r:1 w:1)
+	/// Proof: `Domains::PendingRuntimeUpgrades` (`max_values`: None, `max_size`: None, mode: `Measured`)
 	/// Storage: `Domains::ScheduledRuntimeUpgrades` (r:0 w:1)
 	/// Proof: `Domains::ScheduledRuntimeUpgrades` (`max_values`: None, `max_size`: None, mode: `Measured`)
 	fn upgrade_domain_runtime() -> Weight {
 		// Proof Size summary in bytes:
 		//  Measured:  `2481404`
 		//  Estimated: `2484869`
-		// Minimum execution time: 19_355_000_000 picoseconds.
-		Weight::from_parts(19_883_000_000, 2484869)
-			.saturating_add(ParityDbWeight::get().reads(1_u64))
-			.saturating_add(ParityDbWeight::get().writes(1_u64))
+		// Minimum execution time: 19_361_000_000 picoseconds.
+		Weight::from_parts(19_890_000_000, 2484869)
+			.saturating_add(ParityDbWeight::get().reads(2_u64))
+			.saturating_add(ParityDbWeight::get().writes(2_u64))
 	}
 	/// Storage: `Sudo::Key` (r:1 w:0)
 	/// Proof: `Sudo::Key` (`max_values`: Some(1), `max_size`: Some(32), added: 527, mode: `MaxEncodedLen`)
@@ -854,18 +864,20 @@ impl WeightInfo for () {
 	/// Proof: `Domains::LatestConfirmedDomainBlock` (`max_values`: None, `max_size`: None, mode: `Measured`)
 	/// Storage: `Balances::Holds` (r:1 w:1)
 	/// Proof: `Balances::Holds` (`max_values`: None, `max_size`: Some(2750), added: 5225, mode: `MaxEncodedLen`)
-	/// Storage: `System::Account` (r:1 w:1)
+	/// Storage: `System::Account` (r:2 w:2)
 	/// Proof: `System::Account` (`max_values`: None, `max_size`: Some(128), added: 2603, mode: `MaxEncodedLen`)
 	/// Storage: `Domains::Deposits` (r:1 w:1)
 	/// Proof: `Domains::Deposits` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Domains::TaxPaid` (r:0 w:1)
+	/// Proof: `Domains::TaxPaid` (`max_values`: None, `max_size`: None, mode: `Measured`)
 	fn unlock_funds() -> Weight {
 		// Proof Size summary in bytes:
-		//  Measured:  `1390`
-		//  Estimated: `6215`
-		// Minimum execution time: 89_000_000 picoseconds.
-		Weight::from_parts(93_000_000, 6215)
-			.saturating_add(ParityDbWeight::get().reads(8_u64))
-			.saturating_add(ParityDbWeight::get().writes(4_u64))
+		//  Measured:  `1496`
+		//  Estimated: `8818`
+		// Minimum execution time: 104_000_000 picoseconds.
+		Weight::from_parts(109_000_000, 8818)
+			.saturating_add(ParityDbWeight::get().reads(9_u64))
+			.saturating_add(ParityDbWeight::get().writes(6_u64))
 	}
 	/// Storage: `Domains::Operators` (r:1 w:1)
 	/// Proof: `Domains::Operators` (`max_values`: None, `max_size`: None, mode: `Measured`)
@@ -12,8 +12,8 @@ use sp_core::H256;
 use sp_domain_digests::AsPredigest;
 use sp_domains::proof_provider_and_verifier::StorageProofVerifier;
 use sp_domains::{
-    BundleValidity, DomainId, ExecutionReceiptFor, ExtrinsicDigest, HeaderHashFor,
-    HeaderHashingFor, InvalidBundleType, OperatorId, SealedBundleHeader,
+    BadReceiptMismatch, BundleValidity, DomainId, ExecutionReceiptFor, ExtrinsicDigest,
+    HeaderHashFor, HeaderHashingFor, InvalidBundleType, OperatorId, SealedBundleHeader,
 };
 use sp_runtime::traits::{Block as BlockT, Hash as HashT, Header as HeaderT};
 use sp_runtime::{Digest, DigestItem};
@@ -353,6 +353,12 @@ pub enum VerificationError<DomainHash> {
         error("Failed to derive domain chain allowlist extrinsic")
     )]
     FailedToDeriveDomainChainAllowlistExtrinsic,
+    /// Failed to derive EVM domain contract creation allow list extrinsic
+    #[cfg_attr(
+        feature = "thiserror",
+        error("Failed to derive EVM domain contract creation allow list extrinsic")
+    )]
+    FailedToDeriveEvmContractCreationAllowListExtrinsic,
     /// Bundle with requested index not found in execution receipt
     #[cfg_attr(
         feature = "thiserror",
@@ -517,6 +523,24 @@ impl<Number, Hash, DomainHeader: HeaderT> FraudProof<Number, Hash, DomainHeader>
         }
     }
 
+    /// Returns the field of the targeted bad receipt that this fraud proof proves mismatched.
+    pub fn bad_receipt_mismatch(&self) -> Option<BadReceiptMismatch> {
+        match self {
+            Self::InvalidStateTransition(_) => Some(BadReceiptMismatch::StateTransition),
+            Self::InvalidTransaction(_) => Some(BadReceiptMismatch::InboxedBundles),
+            Self::ImproperTransactionSortition(_) => Some(BadReceiptMismatch::ExtrinsicsRoot),
+            Self::BundleEquivocation(_) => None,
+            #[cfg(any(feature = "std", feature = "runtime-benchmarks"))]
+            Self::Dummy { .. } => Some(BadReceiptMismatch::Unknown),
+            Self::InvalidExtrinsicsRoot(_) => Some(BadReceiptMismatch::ExtrinsicsRoot),
+            Self::InvalidBlockFees(_) => Some(BadReceiptMismatch::BlockFees),
+            Self::ValidBundle(_) => Some(BadReceiptMismatch::InboxedBundles),
+            Self::InvalidBundles(_) => Some(BadReceiptMismatch::InboxedBundles),
+            Self::InvalidDomainBlockHash(_) => Some(BadReceiptMismatch::DomainBlockHash),
+            Self::InvalidTransfers(_) => Some(BadReceiptMismatch::Transfers),
+        }
+    }
+
     pub fn targeted_bad_operator_and_slot_for_bundle_equivocation(
         &self,
     ) -> Option<(OperatorId, Slot)> {
@@ -2,8 +2,9 @@
 extern crate alloc;
 
 use crate::{
-    DomainChainAllowlistUpdateExtrinsic, FraudProofVerificationInfoRequest,
-    FraudProofVerificationInfoResponse, SetCodeExtrinsic, StorageKeyRequest,
+    DomainChainAllowlistUpdateExtrinsic, EvmContractCreationAllowListUpdateExtrinsic,
+    FraudProofVerificationInfoRequest, FraudProofVerificationInfoResponse, SetCodeExtrinsic,
+    StorageKeyRequest,
 };
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
@@ -171,6 +172,26 @@ where
             .map(|ext| ext.encode())
     }
 
+    fn derive_evm_contract_creation_allow_list_update_extrinsic(
+        &self,
+        consensus_block_hash: H256,
+        domain_id: DomainId,
+    ) -> Option<Vec<u8>> {
+        let runtime_api = self.consensus_client.runtime_api();
+        let runtime_code = self.get_domain_runtime_code(consensus_block_hash, domain_id)?;
+        let contract_creation_allowed_by = runtime_api
+            .evm_domain_contract_creation_allow_list_update(consensus_block_hash.into(), domain_id)
+            .ok()??;
+
+        let domain_stateless_runtime =
+            StatelessRuntime::<DomainBlock, _>::new(self.executor.clone(), runtime_code.into());
+
+        domain_stateless_runtime
+            .construct_evm_contract_creation_allowed_by_extrinsic(contract_creation_allowed_by)
+            .ok()?
+            .map(|ext| ext.encode())
+    }
+
     fn derive_consensus_chain_byte_fee_extrinsic(
         &self,
         consensus_block_hash: H256,
@@ -590,6 +611,18 @@ where
                     ),
                 )
             }
+            FraudProofVerificationInfoRequest::EvmContractCreationAllowListUpdateExtrinsic(
+                domain_id,
+            ) => Some(
+                FraudProofVerificationInfoResponse::EvmContractCreationAllowListUpdateExtrinsic(
+                    self.derive_evm_contract_creation_allow_list_update_extrinsic(
+                        consensus_block_hash,
+                        domain_id,
+                    )
+                    .map(EvmContractCreationAllowListUpdateExtrinsic::EncodedExtrinsic)
+                    .unwrap_or(EvmContractCreationAllowListUpdateExtrinsic::None),
+                ),
+            ),
         }
     }
 
@@ -47,9 +47,13 @@ pub use runtime_interface::fraud_proof_runtime_interface;
 pub use runtime_interface::fraud_proof_runtime_interface::HostFunctions;
 use scale_info::TypeInfo;
 use sp_core::H256;
+use sp_domains::inherents::RejectedBundleReason;
 use sp_domains::{DomainId, OperatorId};
+use sp_messenger::messages::ConsensusChainMmrLeafProof;
 use sp_runtime::traits::{Header as HeaderT, NumberFor};
-use sp_runtime::transaction_validity::{InvalidTransaction, TransactionValidity};
+use sp_runtime::transaction_validity::{
+    InvalidTransaction, TransactionValidity, TransactionValidityError,
+};
 use sp_runtime::OpaqueExtrinsic;
 use sp_runtime_interface::pass_by;
 use sp_runtime_interface::pass_by::PassBy;
@@ -58,7 +62,37 @@ use subspace_core_primitives::Randomness;
 use subspace_runtime_primitives::Balance;
 
 /// Custom invalid validity code for the extrinsics in pallet-domains.
+///
+/// The code is returned as `InvalidTransaction::Custom(code)` by both `validate_unsigned` and
+/// `pre_dispatch`, the generic codes (`ExecutionReceipt`, `Bundle` and `FraudProof`) are used for
+/// the failures that don't have a dedicated code:
+///
+/// | Code | Meaning                                                                   |
+/// |------|---------------------------------------------------------------------------|
+/// | 101  | Bundle equivocation                                                       |
+/// | 102  | Invalid transaction proof                                                 |
+/// | 103  | Invalid execution receipt in the bundle                                   |
+/// | 104  | Invalid bundle                                                            |
+/// | 105  | Invalid fraud proof                                                       |
+/// | 106  | Operator can't pay the bundle storage fee                                 |
+/// | 107  | Bundle is duplicated with an already submitted bundle                     |
+/// | 108  | Invalid proof of time in the bundle                                       |
+/// | 109  | Bundle is built on a slot in the past                                     |
+/// | 110  | Bundle is built on a slot in the future                                   |
+/// | 111  | Invalid bundle signature or VRF signature                                 |
+/// | 112  | Bundle operator is unknown, slashed or pending slash                      |
+/// | 113  | Bundle operator doesn't pass the election threshold                       |
+/// | 114  | Bundle exceeds the size or weight limit                                   |
+/// | 115  | Execution receipt in the bundle is stale                                  |
+/// | 116  | Execution receipt in the bundle is in the future                          |
+/// | 117  | Fraud proof targets a receipt that is not found, i.e. already pruned      |
+/// | 118  | Fraud proof targets a receipt that is already reported by another proof   |
+/// | 119  | Operator's bundle storage fund is empty                                   |
+/// | 120  | Operator's bundle storage fund is below the bundle storage fee            |
+/// | 121  | Paying the bundle storage fee would kill the bundle storage fund account  |
+/// | 122  | Execution receipt conflicts with a receipt accepted in the same block     |
 #[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum InvalidTransactionCode {
     BundleEquivocation = 101,
     TransactionProof = 102,
@@ -66,6 +100,22 @@ pub enum InvalidTransactionCode {
     Bundle = 104,
     FraudProof = 105,
     BundleStorageFeePayment = 106,
+    DuplicatedBundle = 107,
+    BundleProofOfTime = 108,
+    BundleSlotInThePast = 109,
+    BundleSlotInTheFuture = 110,
+    BundleSignature = 111,
+    BundleOperator = 112,
+    BundleThresholdUnsatisfied = 113,
+    BundleLimit = 114,
+    StaleReceipt = 115,
+    InFutureReceipt = 116,
+    FraudProofBadReceiptNotFound = 117,
+    FraudProofBadReceiptAlreadyReported = 118,
+    BundleStorageFundEmpty = 119,
+    InsufficientBundleStorageFund = 120,
+    BundleStorageFeeWouldKillAccount = 121,
+    ConflictingReceipt = 122,
 }
 
 impl From<InvalidTransactionCode> for InvalidTransaction {
@@ -75,6 +125,13 @@ impl From<InvalidTransactionCode> for InvalidTransaction {
     }
 }
 
+impl From<InvalidTransactionCode> for TransactionValidityError {
+    #[inline]
+    fn from(invalid_code: InvalidTransactionCode) -> Self {
+        InvalidTransaction::Custom(invalid_code as u8).into()
+    }
+}
+
 impl From<InvalidTransactionCode> for TransactionValidity {
     #[inline]
     fn from(invalid_code: InvalidTransactionCode) -> Self {
@@ -82,6 +139,109 @@ impl From<InvalidTransactionCode> for TransactionValidity {
     }
 }
 
+impl InvalidTransactionCode {
+    /// The bundle related codes along with the reasons the bundles are reported as rejected with.
+    const REJECTED_BUNDLE_REASONS: [(Self, RejectedBundleReason); 18] = [
+        (Self::Bundle, RejectedBundleReason::Bundle),
+        (
+            Self::BundleEquivocation,
+            RejectedBundleReason::BundleEquivocation,
+        ),
+        (Self::BundleOperator, RejectedBundleReason::BundleOperator),
+        (Self::BundleSignature, RejectedBundleReason::BundleSignature),
+        (
+            Self::BundleThresholdUnsatisfied,
+            RejectedBundleReason::BundleThresholdUnsatisfied,
+        ),
+        (Self::BundleLimit, RejectedBundleReason::BundleLimit),
+        (
+            Self::DuplicatedBundle,
+            RejectedBundleReason::DuplicatedBundle,
+        ),
+        (
+            Self::BundleProofOfTime,
+            RejectedBundleReason::BundleProofOfTime,
+        ),
+        (
+            Self::BundleSlotInThePast,
+            RejectedBundleReason::BundleSlotInThePast,
+        ),
+        (
+            Self::BundleSlotInTheFuture,
+            RejectedBundleReason::BundleSlotInTheFuture,
+        ),
+        (
+            Self::ExecutionReceipt,
+            RejectedBundleReason::ExecutionReceipt,
+        ),
+        (Self::StaleReceipt, RejectedBundleReason::StaleReceipt),
+        (Self::InFutureReceipt, RejectedBundleReason::InFutureReceipt),
+        (
+            Self::ConflictingReceipt,
+            RejectedBundleReason::ConflictingReceipt,
+        ),
+        (
+            Self::BundleStorageFeePayment,
+            RejectedBundleReason::BundleStorageFeePayment,
+        ),
+        (
+            Self::BundleStorageFundEmpty,
+            RejectedBundleReason::BundleStorageFundEmpty,
+        ),
+        (
+            Self::InsufficientBundleStorageFund,
+            RejectedBundleReason::InsufficientBundleStorageFund,
+        ),
+        (
+            Self::BundleStorageFeeWouldKillAccount,
+            RejectedBundleReason::BundleStorageFeeWouldKillAccount,
+        ),
+    ];
+
+    /// Returns the reason a bundle rejected with the `InvalidTransaction::Custom(code)` is
+    /// reported with, `None` if `code` is not a bundle related code.
+    pub fn rejected_bundle_reason(code: u8) -> Option<RejectedBundleReason> {
+        Self::REJECTED_BUNDLE_REASONS
+            .into_iter()
+            .find_map(|(invalid_code, reason)| (invalid_code as u8 == code).then_some(reason))
+    }
+}
+
+/// Verifier of the MMR proof of a consensus block against the current consensus chain MMR.
+pub trait ConsensusBlockMmrProofVerifier<CBlockNumber, CBlockHash, MmrHash> {
+    /// Returns the number and hash of the consensus block of the MMR leaf if the given MMR proof
+    /// is valid.
+    fn verify_proof_and_extract_consensus_block(
+        mmr_leaf_proof: ConsensusChainMmrLeafProof<CBlockHash, MmrHash>,
+    ) -> Option<(CBlockNumber, CBlockHash)>;
+
+    /// Returns the number of a consensus block and a valid MMR proof of it, used to benchmark the
+    /// verification of the MMR proof.
+    #[cfg(feature = "runtime-benchmarks")]
+    fn consensus_block_mmr_proof_for_benchmarks() -> Option<(
+        CBlockNumber,
+        ConsensusChainMmrLeafProof<CBlockHash, MmrHash>,
+    )>;
+}
+
+impl<CBlockNumber, CBlockHash, MmrHash>
+    ConsensusBlockMmrProofVerifier<CBlockNumber, CBlockHash, MmrHash> for ()
+{
+    fn verify_proof_and_extract_consensus_block(
+        _mmr_leaf_proof: ConsensusChainMmrLeafProof<CBlockHash, MmrHash>,
+    ) -> Option<(CBlockNumber, CBlockHash)> {
+        None
+    }
+
+    #[cfg(feature = "runtime-benchmarks")]
+    fn consensus_block_mmr_proof_for_benchmarks() -> Option<(
+        CBlockNumber,
+        ConsensusChainMmrLeafProof<CBlockHash, MmrHash>,
+    )> {
+        None
+    }
+}
+
 /// Type that specifies the request of storage keys
 #[derive(Debug, Decode, Encode, TypeInfo, PartialEq, Eq, Clone)]
 pub enum StorageKeyRequest {
@@ -159,6 +319,8 @@ pub enum FraudProofVerificationInfoRequest {
         domain_id: DomainId,
         req: StorageKeyRequest,
     },
+    /// EVM domain `set_contract_creation_allowed_by` extrinsic at a given consensus block hash.
+    EvmContractCreationAllowListUpdateExtrinsic(DomainId),
 }
 
 impl PassBy for FraudProofVerificationInfoRequest {
@@ -183,6 +345,15 @@ pub enum DomainChainAllowlistUpdateExtrinsic {
     EncodedExtrinsic(Vec<u8>),
 }
 
+/// Type that maybe holds an encoded EVM domain contract creation allow list update extrinsic
+#[derive(Debug, Decode, Encode, TypeInfo, PartialEq, Eq, Clone)]
+pub enum EvmContractCreationAllowListUpdateExtrinsic {
+    /// No update
+    None,
+    /// Holds an encoded extrinsic with the updated allow list.
+    EncodedExtrinsic(Vec<u8>),
+}
+
 /// Response holds required verification information for fraud proof from Host function.
 #[derive(Debug, Decode, Encode, TypeInfo, PartialEq, Eq, Clone)]
 pub enum FraudProofVerificationInfoResponse {
@@ -222,6 +393,9 @@ pub enum FraudProofVerificationInfoResponse {
     CheckExtrinsicsInSingleContext(Option<u32>),
     /// Result of the storage key request
     StorageKey(Option<Vec<u8>>),
+    /// Encoded EVM domain `set_contract_creation_allowed_by` extrinsic if the contract creation
+    /// allow list is updated on consensus chain for this domain at a specific consensus hash.
+    EvmContractCreationAllowListUpdateExtrinsic(EvmContractCreationAllowListUpdateExtrinsic),
 }
 
 impl FraudProofVerificationInfoResponse {
@@ -273,6 +447,17 @@ impl FraudProofVerificationInfoResponse {
         }
     }
 
+    pub fn into_evm_contract_creation_allow_list_update_extrinsic(
+        self,
+    ) -> EvmContractCreationAllowListUpdateExtrinsic {
+        match self {
+            FraudProofVerificationInfoResponse::EvmContractCreationAllowListUpdateExtrinsic(
+                allow_list_update_extrinsic,
+            ) => allow_list_update_extrinsic,
+            _ => EvmContractCreationAllowListUpdateExtrinsic::None,
+        }
+    }
+
     pub fn into_tx_range_check(self) -> Option<bool> {
         match self {
             FraudProofVerificationInfoResponse::TxRangeCheck(is_tx_in_range) => {
@@ -350,10 +535,18 @@ impl FraudProofVerificationInfoResponse {
 
 sp_api::decl_runtime_apis! {
     /// API necessary for fraud proof.
+    #[api_version(2)]
     pub trait FraudProofApi<DomainHeader: HeaderT> {
         /// Submit the fraud proof via an unsigned extrinsic.
         fn submit_fraud_proof_unsigned(fraud_proof: FraudProof<NumberFor<Block>, Block::Hash, DomainHeader>);
 
+        /// Submit the fraud proof along with the MMR proof of the consensus block of the bad
+        /// receipt via an unsigned extrinsic.
+        fn submit_fraud_proof_with_consensus_block_proof_unsigned(
+            fraud_proof: FraudProof<NumberFor<Block>, Block::Hash, DomainHeader>,
+            consensus_block_proof: ConsensusChainMmrLeafProof<Block::Hash, H256>,
+        );
+
         /// Extract the fraud proof handled successfully from the given extrinsics.
         fn extract_fraud_proofs(
             domain_id: DomainId,
@@ -8,8 +8,8 @@ use crate::fraud_proof::{
 use crate::fraud_proof_runtime_interface::get_fraud_proof_verification_info;
 use crate::{
     fraud_proof_runtime_interface, DomainChainAllowlistUpdateExtrinsic,
-    FraudProofVerificationInfoRequest, FraudProofVerificationInfoResponse, SetCodeExtrinsic,
-    StorageKeyRequest,
+    EvmContractCreationAllowListUpdateExtrinsic, FraudProofVerificationInfoRequest,
+    FraudProofVerificationInfoResponse, SetCodeExtrinsic, StorageKeyRequest,
 };
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
@@ -95,6 +95,13 @@ where
     .map(|resp| resp.into_domain_chain_allowlist_update_extrinsic())
     .ok_or(VerificationError::FailedToDeriveDomainChainAllowlistExtrinsic)?;
 
+    let evm_contract_creation_allow_list_extrinsic = get_fraud_proof_verification_info(
+        H256::from_slice(consensus_block_hash.as_ref()),
+        FraudProofVerificationInfoRequest::EvmContractCreationAllowListUpdateExtrinsic(*domain_id),
+    )
+    .map(|resp| resp.into_evm_contract_creation_allow_list_update_extrinsic())
+    .ok_or(VerificationError::FailedToDeriveEvmContractCreationAllowListExtrinsic)?;
+
     let bad_receipt_valid_bundle_digests = bad_receipt.valid_bundle_digests();
     if valid_bundle_digests.len() != bad_receipt_valid_bundle_digests.len() {
         return Err(VerificationError::InvalidBundleDigest);
@@ -129,11 +136,23 @@ where
     // - executive set_code extrinsic
     // - messenger update_domain_allowlist extrinsic
     // - block_fees transaction_byte_fee_extrinsic
+    // - evm_tracker set_contract_creation_allowed_by extrinsic (EVM domains only)
     // since we use `push_front` the extrinsic should be pushed in reversed order
     // TODO: this will not be valid once we have a different runtime. To achive consistency across
     //  domains, we should define a runtime api for each domain that should order the extrinsics
     //  like inherent are derived while domain block is being built
 
+    if let EvmContractCreationAllowListUpdateExtrinsic::EncodedExtrinsic(
+        evm_contract_creation_allow_list_extrinsic,
+    ) = evm_contract_creation_allow_list_extrinsic
+    {
+        let evm_contract_creation_allow_list_extrinsic =
+            ExtrinsicDigest::new::<LayoutV1<HeaderHashingFor<DomainHeader>>>(
+                evm_contract_creation_allow_list_extrinsic,
+            );
+        ordered_extrinsics.push_front(evm_contract_creation_allow_list_extrinsic);
+    }
+
     let transaction_byte_fee_extrinsic = ExtrinsicDigest::new::<
         LayoutV1<HeaderHashingFor<DomainHeader>>,
     >(consensus_chain_byte_fee_extrinsic);
@@ -1,8 +1,9 @@
-use crate::{DomainId, OperatorPublicKey, ProofOfElection, StakeWeight};
+use crate::{DomainId, OperatorId, OperatorPublicKey, ProofOfElection, StakeWeight};
 use parity_scale_codec::{Decode, Encode};
 use scale_info::TypeInfo;
 use sp_core::crypto::{VrfPublic, Wraps};
 use sp_core::sr25519::vrf::{VrfPreOutput, VrfSignature, VrfTranscript};
+use sp_std::collections::btree_map::BTreeMap;
 use subspace_core_primitives::Blake3Hash;
 
 const VRF_TRANSCRIPT_LABEL: &[u8] = b"bundle_producer_election";
@@ -57,6 +58,15 @@ pub struct BundleProducerElectionParams<Balance> {
     pub bundle_slot_probability: (u64, u64),
 }
 
+/// The bundle producer election parameters of a given epoch, used to verify the proof of election
+/// of the bundle produced in that epoch.
+#[derive(Debug, Decode, Encode, TypeInfo, PartialEq, Eq, Clone)]
+pub struct EpochElectionParams<Balance> {
+    pub params: BundleProducerElectionParams<Balance>,
+    /// The stake of each operator that is eligible to produce bundle in the epoch.
+    pub operator_stakes: BTreeMap<OperatorId, Balance>,
+}
+
 #[derive(Debug, Decode, Encode, TypeInfo, PartialEq, Eq, Clone)]
 pub enum ProofOfElectionError {
     /// Invalid vrf proof.
@@ -1,11 +1,11 @@
 #[cfg(not(feature = "std"))]
 extern crate alloc;
 
-use crate::{BlockFees, DomainAllowlistUpdates, Transfers};
+use crate::{BlockFees, DomainAllowlistUpdates, PermissionedActionAllowedBy, Transfers};
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
 use domain_runtime_primitives::{
-    opaque, Balance, CheckExtrinsicsValidityError, DecodeExtrinsicError,
+    opaque, Balance, CheckExtrinsicsValidityError, DecodeExtrinsicError, EthereumAccountId,
 };
 use sp_runtime::generic::Era;
 use sp_runtime::traits::{Block as BlockT, NumberFor};
@@ -49,6 +49,10 @@ sp_api::decl_runtime_apis! {
         /// Returns an extrinsic to update chain allowlist.
         fn construct_domain_update_chain_allowlist_extrinsic(updates: DomainAllowlistUpdates) -> Block::Extrinsic;
 
+        /// Returns an extrinsic to update the contract creation allow list of the EVM domain,
+        /// `None` if the domain does not support contract creation allow list.
+        fn construct_evm_contract_creation_allowed_by_extrinsic(contract_creation_allowed_by: PermissionedActionAllowedBy<EthereumAccountId>) -> Option<Block::Extrinsic>;
+
         /// Returns true if the extrinsic is an inherent extrinsic.
         fn is_inherent_extrinsic(extrinsic: &<Block as BlockT>::Extrinsic) -> bool;
 
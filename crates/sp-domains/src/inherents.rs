@@ -0,0 +1,130 @@
+//! Inherents for the domains pallet
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use sp_core::ConstU32;
+use sp_inherents::InherentIdentifier;
+use sp_runtime::BoundedVec;
+
+/// The domains inherent identifier.
+pub const INHERENT_IDENTIFIER: InherentIdentifier = *b"domains_";
+
+/// The maximum number of rejected bundles reported in one block, the block author keeps the rest
+/// for the following blocks.
+pub const MAX_REJECTED_BUNDLES_PER_BLOCK: u32 = 16;
+
+/// The rejected bundles reported in one block.
+pub type RejectedBundles = BoundedVec<RejectedBundle, ConstU32<MAX_REJECTED_BUNDLES_PER_BLOCK>>;
+
+/// The reason a bundle is rejected with, i.e. the bundle related `InvalidTransactionCode`s of the
+/// domains pallet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub enum RejectedBundleReason {
+    /// The bundle is invalid, i.e. unknown domain, stale bundle or bad extrinsics root.
+    Bundle,
+    /// The bundle is equivocated with another bundle of the operator.
+    BundleEquivocation,
+    /// The operator is unknown, slashed or paused.
+    BundleOperator,
+    /// The bundle or proof of election signature is invalid.
+    BundleSignature,
+    /// The proof of election doesn't satisfy the threshold of the operator.
+    BundleThresholdUnsatisfied,
+    /// The bundle exceeds the bundle limit.
+    BundleLimit,
+    /// The bundle is already included.
+    DuplicatedBundle,
+    /// The proof of time of the bundle is invalid.
+    BundleProofOfTime,
+    /// The bundle is built on a slot in the past.
+    BundleSlotInThePast,
+    /// The bundle is built on a slot in the future.
+    BundleSlotInTheFuture,
+    /// The execution receipt of the bundle is invalid.
+    ExecutionReceipt,
+    /// The execution receipt of the bundle is stale.
+    StaleReceipt,
+    /// The execution receipt of the bundle is in the future.
+    InFutureReceipt,
+    /// The execution receipt conflicts with a receipt accepted in the same block.
+    ConflictingReceipt,
+    /// The bundle storage fee can't be paid.
+    BundleStorageFeePayment,
+    /// The bundle storage fund of the operator is empty.
+    BundleStorageFundEmpty,
+    /// The bundle storage fund of the operator is below the bundle storage fee.
+    InsufficientBundleStorageFund,
+    /// Paying the bundle storage fee would kill the bundle storage fund account.
+    BundleStorageFeeWouldKillAccount,
+}
+
+/// A bundle that is dropped by the block author while building the block.
+///
+/// The bundle is reported with its header signed by the operator, so the runtime only records
+/// bundles that the operator did submit instead of trusting the block author.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub struct RejectedBundle {
+    /// The SCALE encoded `SealedBundleHeader` of the rejected bundle.
+    pub encoded_sealed_header: Vec<u8>,
+    /// The reason the bundle is rejected with.
+    pub reason: RejectedBundleReason,
+}
+
+/// A rejected bundle of an operator, as recorded by the domains pallet.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub struct RejectedBundleRecord<Number> {
+    /// The consensus block in which the rejection is reported.
+    pub consensus_block_number: Number,
+    /// The reason the bundle is rejected with.
+    pub reason: RejectedBundleReason,
+}
+
+/// The type of the domains inherent data.
+#[derive(Debug, Default, Encode, Decode)]
+pub struct InherentType {
+    /// Bundles dropped by the block author that are not reported yet, oldest first.
+    pub rejected_bundles: RejectedBundles,
+}
+
+/// Provides the domains inherent data.
+#[cfg(feature = "std")]
+pub struct InherentDataProvider {
+    data: InherentType,
+}
+
+#[cfg(feature = "std")]
+impl InherentDataProvider {
+    /// Create new inherent data provider from the given `rejected_bundles`.
+    pub fn new(rejected_bundles: RejectedBundles) -> Self {
+        Self {
+            data: InherentType { rejected_bundles },
+        }
+    }
+
+    /// Returns the `data` of this inherent data provider.
+    pub fn data(&self) -> &InherentType {
+        &self.data
+    }
+}
+
+#[cfg(feature = "std")]
+#[async_trait::async_trait]
+impl sp_inherents::InherentDataProvider for InherentDataProvider {
+    async fn provide_inherent_data(
+        &self,
+        inherent_data: &mut sp_inherents::InherentData,
+    ) -> Result<(), sp_inherents::Error> {
+        inherent_data.put_data(INHERENT_IDENTIFIER, &self.data)
+    }
+
+    async fn try_handle_error(
+        &self,
+        _identifier: &InherentIdentifier,
+        _error: &[u8],
+    ) -> Option<Result<(), sp_inherents::Error>> {
+        // The domains inherent is never checked against the local inherent data.
+        None
+    }
+}
@@ -20,6 +20,7 @@
 pub mod bundle_producer_election;
 pub mod core_api;
 pub mod extrinsics;
+pub mod inherents;
 pub mod merkle_tree;
 pub mod proof_provider_and_verifier;
 pub mod storage;
@@ -37,11 +38,13 @@ use alloc::collections::BTreeSet;
 use alloc::string::String;
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
-use bundle_producer_election::{BundleProducerElectionParams, ProofOfElectionError};
+use bundle_producer_election::{
+    BundleProducerElectionParams, EpochElectionParams, ProofOfElectionError,
+};
 use core::num::ParseIntError;
 use core::ops::{Add, Sub};
 use core::str::FromStr;
-use domain_runtime_primitives::MultiAccountId;
+use domain_runtime_primitives::{EthereumAccountId, MultiAccountId};
 use frame_support::storage::storage_prefix;
 use frame_support::{Blake2_128Concat, StorageHasher};
 use hexlit::hex;
@@ -53,12 +56,13 @@ use sp_core::crypto::KeyTypeId;
 use sp_core::sr25519::vrf::VrfSignature;
 #[cfg(any(feature = "std", feature = "runtime-benchmarks"))]
 use sp_core::sr25519::vrf::{VrfPreOutput, VrfProof};
-use sp_core::H256;
+use sp_core::{ConstU32, H256};
 use sp_runtime::generic::OpaqueDigestItemId;
 use sp_runtime::traits::{
-    BlakeTwo256, Block as BlockT, CheckedAdd, Hash as HashT, Header as HeaderT, NumberFor, Zero,
+    BlakeTwo256, Block as BlockT, CheckedAdd, Hash as HashT, Header as HeaderT, NumberFor,
+    Saturating, Zero,
 };
-use sp_runtime::{Digest, DigestItem, OpaqueExtrinsic, Percent};
+use sp_runtime::{BoundedVec, Digest, DigestItem, OpaqueExtrinsic, Percent, Perquintill};
 use sp_runtime_interface::pass_by;
 use sp_runtime_interface::pass_by::PassBy;
 use sp_std::collections::btree_map::BTreeMap;
@@ -70,7 +74,7 @@ use sp_weights::Weight;
 use std::collections::BTreeSet;
 use subspace_core_primitives::crypto::blake3_hash;
 use subspace_core_primitives::{bidirectional_distance, Blake3Hash, PotOutput, Randomness, U256};
-use subspace_runtime_primitives::{Balance, Moment};
+use subspace_runtime_primitives::{AccountId, Balance, Moment};
 
 /// Key type for Operator.
 pub const KEY_TYPE: KeyTypeId = KeyTypeId(*b"oper");
@@ -764,6 +768,92 @@ pub struct ConfirmedDomainBlock<DomainBlockNumber: Codec, DomainHash: Codec> {
     pub extrinsics_root: DomainHash,
 }
 
+/// Fees collected by a domain and paid out to its operators.
+#[derive(TypeInfo, Encode, Decode, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DomainFees<Balance> {
+    /// Domain execution fees distributed to the operators that submitted bundles, excluding
+    /// `operator_tax`, i.e. the part shared between operators and their nominators.
+    pub operator_rewards: Balance,
+    /// Consensus storage fees refunded to the bundle storage funds of the operators.
+    pub storage_fee_refunds: Balance,
+    /// Nomination tax collected by the operators from their rewards, the operators rewards in
+    /// total are `operator_rewards + operator_tax`.
+    pub operator_tax: Balance,
+}
+
+impl<Balance: Saturating> DomainFees<Balance> {
+    /// Add `other` to the fees, saturating at the numeric bounds.
+    pub fn saturating_accrue(&mut self, other: Self) {
+        self.operator_rewards
+            .saturating_accrue(other.operator_rewards);
+        self.storage_fee_refunds
+            .saturating_accrue(other.storage_fee_refunds);
+        self.operator_tax.saturating_accrue(other.operator_tax);
+    }
+}
+
+/// State root of a domain block, flagged with whether the domain block is confirmed.
+#[derive(TypeInfo, Encode, Decode, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DomainStateRoot {
+    /// The domain block is confirmed, the state root is final.
+    Confirmed(H256),
+    /// The receipt of the domain block is still in the block tree and can be challenged by fraud
+    /// proof, the state root may be reverted.
+    Unconfirmed(H256),
+}
+
+impl DomainStateRoot {
+    /// Returns the state root regardless of whether the domain block is confirmed.
+    pub fn state_root(&self) -> H256 {
+        match self {
+            DomainStateRoot::Confirmed(state_root) | DomainStateRoot::Unconfirmed(state_root) => {
+                *state_root
+            }
+        }
+    }
+
+    /// Returns `true` if the domain block is confirmed.
+    pub fn is_confirmed(&self) -> bool {
+        matches!(self, DomainStateRoot::Confirmed(_))
+    }
+}
+
+/// The field of a bad execution receipt that mismatches with the honest one.
+#[derive(TypeInfo, Encode, Decode, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BadReceiptMismatch {
+    /// Mismatch in one of the intermediate or final state roots.
+    StateTransition,
+    /// Mismatch in the `block_fees`.
+    BlockFees,
+    /// Mismatch in the `transfers`.
+    Transfers,
+    /// Mismatch in the `domain_block_extrinsic_root`.
+    ExtrinsicsRoot,
+    /// Mismatch in the `domain_block_hash`.
+    DomainBlockHash,
+    /// Mismatch in the `inboxed_bundles`.
+    InboxedBundles,
+    /// The bad receipt is not targeted by a fraud proof directly, i.e. it is pruned as the
+    /// descendant of another bad receipt, so the mismatching field is unknown.
+    Unknown,
+}
+
+/// Compact evidence of a bad execution receipt that an operator is slashed for, kept after
+/// the receipt itself is pruned from the block tree.
+#[derive(TypeInfo, Encode, Decode, Debug, Clone, PartialEq, Eq)]
+pub struct SlashEvidence<DomainNumber, ConsensusNumber, DomainHash> {
+    /// Hash of the bad receipt.
+    pub receipt_hash: DomainHash,
+    /// Domain block number of the bad receipt.
+    pub domain_block_number: DomainNumber,
+    /// Consensus block number of the bad receipt.
+    pub consensus_block_number: ConsensusNumber,
+    /// Hash of the fraud proof that targeted the bad receipt, if any.
+    pub fraud_proof_hash: Option<DomainHash>,
+    /// The mismatching field of the bad receipt.
+    pub mismatch: BadReceiptMismatch,
+}
+
 /// Type that represents an operator allow list for Domains.
 #[derive(TypeInfo, Debug, Encode, Decode, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum OperatorAllowList<AccountId: Ord> {
@@ -785,7 +875,7 @@ impl<AccountId: Ord> OperatorAllowList<AccountId> {
 }
 
 /// Permissioned actions allowed by either specific accounts or anyone.
-#[derive(TypeInfo, Encode, Decode, Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[derive(TypeInfo, Encode, Decode, Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub enum PermissionedActionAllowedBy<AccountId: Codec + Clone> {
     Accounts(Vec<AccountId>),
     Anyone,
@@ -798,6 +888,14 @@ impl<AccountId: Codec + PartialEq + Clone> PermissionedActionAllowedBy<AccountId
             PermissionedActionAllowedBy::Anyone => true,
         }
     }
+
+    /// Returns the number of accounts explicitly allowed, `0` if anyone is allowed.
+    pub fn accounts_len(&self) -> usize {
+        match self {
+            PermissionedActionAllowedBy::Accounts(accounts) => accounts.len(),
+            PermissionedActionAllowedBy::Anyone => 0,
+        }
+    }
 }
 
 #[derive(TypeInfo, Debug, Encode, Decode, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -838,6 +936,27 @@ pub enum RuntimeType {
 /// Type representing the runtime ID.
 pub type RuntimeId = u32;
 
+/// Metadata of a registered domain runtime, without the raw genesis that embeds the runtime code.
+#[derive(TypeInfo, Encode, Decode, Debug, Clone, PartialEq, Eq)]
+pub struct RuntimeSummary<Number, Hash> {
+    /// Id of the runtime.
+    pub runtime_id: RuntimeId,
+    /// Name of the runtime given at registration.
+    pub runtime_name: String,
+    /// Type of the runtime.
+    pub runtime_type: RuntimeType,
+    /// Version of the current runtime code.
+    pub version: RuntimeVersion,
+    /// Hash of the current runtime code.
+    pub code_hash: Hash,
+    /// Number of upgrades of the runtime.
+    pub runtime_upgrades: u32,
+    /// Consensus block number at which the runtime is registered.
+    pub created_at: Number,
+    /// Consensus block number at which the runtime is last upgraded.
+    pub updated_at: Number,
+}
+
 /// Type representing domain epoch.
 pub type EpochIndex = u32;
 
@@ -873,6 +992,22 @@ pub enum DomainsHoldIdentifier {
     Staking(StakingHoldIdentifier),
     DomainInstantiation(DomainId),
     StorageFund(OperatorId),
+    OperatorMetadata(OperatorId),
+}
+
+/// Maximum length in bytes of the operator display name.
+pub const MAX_OPERATOR_NAME_LENGTH: u32 = 64;
+
+/// Maximum length in bytes of the operator website.
+pub const MAX_OPERATOR_WEBSITE_LENGTH: u32 = 128;
+
+/// Human readable metadata of an operator, registered by the operator owner.
+#[derive(TypeInfo, Debug, Encode, Decode, MaxEncodedLen, Clone, PartialEq, Eq)]
+pub struct OperatorMetadata {
+    /// Display name of the operator.
+    pub name: BoundedVec<u8, ConstU32<MAX_OPERATOR_NAME_LENGTH>>,
+    /// Website or other contact of the operator.
+    pub website: Option<BoundedVec<u8, ConstU32<MAX_OPERATOR_WEBSITE_LENGTH>>>,
 }
 
 /// Domains specific digest item.
@@ -987,6 +1122,23 @@ pub fn self_domain_id_storage_key() -> StorageKey {
     )
 }
 
+/// The storage key of the `ContractCreationAllowedBy` storage item in the EVM domain runtime.
+///
+/// The EVM domain runtime is expected to expose the contract creation allow list under this key,
+/// any change to the storage item name or the pallet name used in the `construct_runtime` macro
+/// must be reflected here.
+pub fn evm_contract_creation_allowed_by_storage_key() -> StorageKey {
+    StorageKey(
+        storage_prefix(
+            // This is the name expected for the EVM tracker pallet in the `construct_runtime` macro
+            "EVMTracker".as_bytes(),
+            // This is the storage item name expected inside the EVM tracker pallet
+            "ContractCreationAllowedBy".as_bytes(),
+        )
+        .to_vec(),
+    )
+}
+
 /// `DomainInstanceData` is used to construct the genesis storage of domain instance chain
 #[derive(Debug, PartialEq, Eq, Clone, Encode, Decode, TypeInfo)]
 pub struct DomainInstanceData {
@@ -994,7 +1146,7 @@ pub struct DomainInstanceData {
     pub raw_genesis: RawGenesis,
 }
 
-#[derive(Debug, Decode, Encode, TypeInfo, Clone)]
+#[derive(Debug, Decode, Encode, TypeInfo, Clone, PartialEq, Eq)]
 pub struct DomainBlockLimit {
     /// The max block size for the domain.
     pub max_block_size: u32,
@@ -1002,7 +1154,7 @@ pub struct DomainBlockLimit {
     pub max_block_weight: Weight,
 }
 
-#[derive(Debug, Decode, Encode, TypeInfo, Clone)]
+#[derive(Debug, Decode, Encode, TypeInfo, Clone, PartialEq, Eq)]
 pub struct DomainBundleLimit {
     /// The max bundle size for the domain.
     pub max_bundle_size: u32,
@@ -1010,6 +1162,27 @@ pub struct DomainBundleLimit {
     pub max_bundle_weight: Weight,
 }
 
+/// Limits and parameters that are in effect for a domain, resolved the same way they are resolved
+/// when bundles and receipts of the domain are validated.
+#[derive(Debug, Decode, Encode, TypeInfo, Clone, PartialEq, Eq)]
+pub struct EffectiveDomainParams<DomainNumber> {
+    /// The domain block limit.
+    pub block_limit: DomainBlockLimit,
+    /// The bundle limit calculated from the domain block limit.
+    pub bundle_limit: DomainBundleLimit,
+    /// Number of consensus blocks a bundle stays valid for after the slot it was produced at.
+    pub bundle_longevity: u32,
+    /// Number of domain blocks in a staking epoch.
+    pub epoch_duration: DomainNumber,
+    /// Number of domain blocks an execution receipt can be challenged for before it is pruned
+    /// from the block tree.
+    pub pruning_depth: DomainNumber,
+    /// The probability of successful bundle in a slot (active slots coefficient).
+    pub bundle_slot_probability: (u64, u64),
+    /// The current tx range.
+    pub tx_range: U256,
+}
+
 /// Checks if the signer Id hash is within the tx range
 pub fn signer_in_tx_range(bundle_vrf_hash: &U256, signer_id_hash: &U256, tx_range: &U256) -> bool {
     let distance_from_vrf_hash = bidirectional_distance(bundle_vrf_hash, signer_id_hash);
@@ -1258,7 +1431,7 @@ pub struct DomainAllowlistUpdates {
 
 sp_api::decl_runtime_apis! {
     /// API necessary for domains pallet.
-    #[api_version(3)]
+    #[api_version(4)]
     pub trait DomainsApi<DomainHeader: HeaderT> {
         /// Submits the transaction bundle via an unsigned extrinsic.
         fn submit_bundle_unsigned(opaque_bundle: OpaqueBundle<NumberFor<Block>, Block::Hash, DomainHeader, Balance>);
@@ -1299,6 +1472,9 @@ sp_api::decl_runtime_apis! {
         /// Return the genesis state root if not pruned
         fn genesis_state_root(domain_id: DomainId) -> Option<H256>;
 
+        /// Returns the genesis execution receipt stored in the block tree if not pruned
+        fn genesis_execution_receipt(domain_id: DomainId) -> Option<ExecutionReceiptFor<DomainHeader, Block, Balance>>;
+
         /// Returns the best execution chain number.
         fn head_receipt_number(domain_id: DomainId) -> HeaderNumberFor<DomainHeader>;
 
@@ -1311,6 +1487,9 @@ sp_api::decl_runtime_apis! {
         /// Returns the domain bundle limit of the given domain.
         fn domain_bundle_limit(domain_id: DomainId) -> Option<DomainBundleLimit>;
 
+        /// Returns the limits and parameters that are in effect for the given domain.
+        fn effective_domain_params(domain_id: DomainId) -> Option<EffectiveDomainParams<HeaderNumberFor<DomainHeader>>>;
+
         /// Returns true if there are any ERs in the challenge period with non empty extrinsics.
         fn non_empty_er_exists(domain_id: DomainId) -> bool;
 
@@ -1341,11 +1520,82 @@ sp_api::decl_runtime_apis! {
 
         /// Return the balance of the storage fund account
         fn storage_fund_account_balance(operator_id: OperatorId) -> Balance;
+
+        /// Return the bundle storage fund account of the operator
+        fn storage_fund_account(operator_id: OperatorId) -> AccountId;
+
+        /// Returns the evidence of the bad receipts the given operator is slashed for
+        fn slash_evidence(operator_id: OperatorId) -> Vec<SlashEvidence<HeaderNumberFor<DomainHeader>, NumberFor<Block>, HeaderHashFor<DomainHeader>>>;
+
+        /// Returns the current contract creation allow list of the given EVM domain
+        fn evm_domain_contract_creation_allowed_by(domain_id: DomainId) -> Option<PermissionedActionAllowedBy<EthereumAccountId>>;
+
+        /// Returns the contract creation allow list update of the given EVM domain that is not yet
+        /// delivered to the domain
+        fn evm_domain_contract_creation_allow_list_update(domain_id: DomainId) -> Option<PermissionedActionAllowedBy<EthereumAccountId>>;
+
+        /// Returns the state root of the given domain block if it is within the retained confirmed
+        /// history or its receipt is still in the block tree
+        fn domain_state_root(domain_id: DomainId, domain_block_number: HeaderNumberFor<DomainHeader>, domain_block_hash: HeaderHashFor<DomainHeader>) -> Option<DomainStateRoot>;
+
+        /// Returns the digests of the bundles inboxed for the given domain block in the given
+        /// consensus block, in the same order they are submitted
+        fn execution_inbox_digests(domain_id: DomainId, domain_block_number: HeaderNumberFor<DomainHeader>, consensus_block_number: NumberFor<Block>) -> Vec<BundleDigest<HeaderHashFor<DomainHeader>>>;
+
+        /// Returns the digests of the bundles inboxed for the domain block under construction in
+        /// the current consensus block
+        fn current_inbox(domain_id: DomainId) -> Vec<BundleDigest<HeaderHashFor<DomainHeader>>>;
+
+        /// Returns the number of receipts above the head receipt that are pending lazy pruning
+        /// after a fraud proof reverted the head receipt
+        fn revert_depth(domain_id: DomainId) -> HeaderNumberFor<DomainHeader>;
+
+        /// Returns the total number of rejected bundles of the operator reported by the block
+        /// authors, along with the latest rejected bundles
+        fn rejected_bundles(operator_id: OperatorId) -> (u32, Vec<inherents::RejectedBundleRecord<NumberFor<Block>>>);
+
+        /// Returns the fees collected by the domain since its instantiation
+        fn domain_fee_totals(domain_id: DomainId) -> DomainFees<Balance>;
+
+        /// Returns the fees collected by the domain in the given epoch, only a bounded number of
+        /// the latest epochs are retained
+        fn domain_epoch_fees(domain_id: DomainId, epoch_index: EpochIndex) -> Option<DomainFees<Balance>>;
+
+        /// Returns the summary of the given domain runtime
+        fn runtime_summary(runtime_id: RuntimeId) -> Option<RuntimeSummary<NumberFor<Block>, Block::Hash>>;
+
+        /// Returns the summaries of all the registered domain runtimes
+        fn runtime_summaries() -> Vec<RuntimeSummary<NumberFor<Block>, Block::Hash>>;
+
+        /// Returns the metadata registered by the owner of the given operator
+        fn operator_metadata(operator_id: OperatorId) -> Option<OperatorMetadata>;
+
+        /// Returns the domains owned by the given account
+        fn domains_owned_by(owner: AccountId) -> Vec<DomainId>;
+
+        /// Returns the operator tax paid by the nominator to the given operator so far
+        fn nominator_tax_paid(operator_id: OperatorId, nominator: AccountId) -> Balance;
     }
 
+    #[api_version(2)]
     pub trait BundleProducerElectionApi<Balance: Encode + Decode> {
         fn bundle_producer_election_params(domain_id: DomainId) -> Option<BundleProducerElectionParams<Balance>>;
 
         fn operator(operator_id: OperatorId) -> Option<(OperatorPublicKey, Balance)>;
+
+        /// Returns the bundle producer election threshold of the given operator, calculated with
+        /// the same stake distribution used to verify the proof of election of its bundle, `None`
+        /// if the operator is unknown or inactive.
+        ///
+        /// The operator wins the election if its vrf output is strictly less than the threshold,
+        /// so a threshold of zero means the operator never wins.
+        fn operator_election_threshold(domain_id: DomainId, operator_id: OperatorId) -> Option<U256>;
+
+        /// Returns the expected number of bundles produced by the given operator per slot.
+        fn operator_expected_bundles_per_slot(domain_id: DomainId, operator_id: OperatorId) -> Option<Perquintill>;
+
+        /// Returns the bundle producer election parameters of the given epoch, the parameters of
+        /// the past epochs are only kept for a limited number of epochs.
+        fn bundle_producer_election_params_at(domain_id: DomainId, epoch_index: EpochIndex) -> Option<EpochElectionParams<Balance>>;
     }
 }
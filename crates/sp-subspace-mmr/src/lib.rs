@@ -38,12 +38,24 @@ pub enum MmrLeaf<BlockNumber, Hash> {
     V0(LeafDataV0<BlockNumber, Hash>),
 }
 
-impl<BlockNumber, Hash: Clone> MmrLeaf<BlockNumber, Hash> {
+impl<BlockNumber: Clone, Hash: Clone> MmrLeaf<BlockNumber, Hash> {
     pub fn state_root(&self) -> Hash {
         match self {
             MmrLeaf::V0(leaf) => leaf.state_root.clone(),
         }
     }
+
+    pub fn block_number(&self) -> BlockNumber {
+        match self {
+            MmrLeaf::V0(leaf) => leaf.block_number.clone(),
+        }
+    }
+
+    pub fn block_hash(&self) -> Hash {
+        match self {
+            MmrLeaf::V0(leaf) => leaf.block_hash.clone(),
+        }
+    }
 }
 
 /// MMR v0 leaf data
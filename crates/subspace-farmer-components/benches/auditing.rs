@@ -9,7 +9,8 @@ use subspace_archiving::archiver::Archiver;
 use subspace_core_primitives::crypto::kzg;
 use subspace_core_primitives::crypto::kzg::Kzg;
 use subspace_core_primitives::{
-    Blake3Hash, HistorySize, PublicKey, Record, RecordedHistorySegment, SectorId, SolutionRange,
+    Blake3Hash, HistorySize, PublicKey, Record, RecordedHistorySegment, SectorId, SectorIndex,
+    SolutionRange,
 };
 use subspace_erasure_coding::ErasureCoding;
 use subspace_farmer_components::auditing::audit_plot_sync;
@@ -155,6 +156,7 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                     black_box(global_challenge),
                     black_box(solution_range),
                     black_box(&plotted_sector_bytes),
+                    black_box(1),
                     black_box(slice::from_ref(&plotted_sector.sector_metadata)),
                     black_box(None),
                 )
@@ -200,6 +202,7 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                         black_box(global_challenge),
                         black_box(solution_range),
                         black_box(&plot_file),
+                        black_box(sectors_count as SectorIndex),
                         black_box(&sectors_metadata),
                         black_box(None),
                     )
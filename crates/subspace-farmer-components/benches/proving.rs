@@ -14,7 +14,7 @@ use subspace_core_primitives::crypto::kzg;
 use subspace_core_primitives::crypto::kzg::Kzg;
 use subspace_core_primitives::{
     Blake3Hash, HistorySize, PosSeed, PublicKey, Record, RecordedHistorySegment, SectorId,
-    SolutionRange,
+    SectorIndex, SolutionRange,
 };
 use subspace_erasure_coding::ErasureCoding;
 use subspace_farmer_components::auditing::audit_plot_sync;
@@ -163,6 +163,7 @@ pub fn criterion_benchmark(c: &mut Criterion) {
             &global_challenge,
             solution_range,
             &plotted_sector_bytes,
+            1,
             slice::from_ref(&plotted_sector.sector_metadata),
             None,
         )
@@ -252,6 +253,7 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 global_challenge,
                 solution_range,
                 &plot_file,
+                sectors_count as SectorIndex,
                 &sectors_metadata,
                 None,
             )
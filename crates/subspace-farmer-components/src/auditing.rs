@@ -9,6 +9,7 @@ use subspace_core_primitives::{
 };
 use subspace_verification::is_within_solution_range;
 use thiserror::Error;
+use tracing::error;
 
 /// Errors that happen during proving
 #[derive(Debug, Error)]
@@ -102,12 +103,16 @@ where
     }))
 }
 
-/// Audit the whole plot and generate streams of solutions
+/// Audit the whole plot and generate streams of solutions.
+///
+/// `sector_count` is the number of sectors `plot` has space for, sectors with index past it are
+/// skipped instead of auditing and proving with bytes that don't belong to them.
 pub fn audit_plot_sync<'a, Plot>(
     public_key: &'a PublicKey,
     global_challenge: &Blake3Hash,
     solution_range: SolutionRange,
     plot: &'a Plot,
+    sector_count: SectorIndex,
     sectors_metadata: &'a [SectorMetadataChecksummed],
     maybe_sector_being_modified: Option<SectorIndex>,
 ) -> Result<Vec<AuditResult<'a, ReadAtOffset<'a, Plot>>>, AuditingError>
@@ -133,6 +138,15 @@ where
                 return None;
             }
 
+            if sector_metadata.sector_index >= sector_count {
+                error!(
+                    sector_index = %sector_metadata.sector_index,
+                    %sector_count,
+                    "Sector doesn't fit into the plot and can't be audited"
+                );
+                return None;
+            }
+
             if sector_auditing_info.s_bucket_audit_size == 0 {
                 // S-bucket is empty
                 return None;
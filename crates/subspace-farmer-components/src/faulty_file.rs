@@ -0,0 +1,383 @@
+//! File wrapper that injects faults into operations of another file according to a script, such
+//! that error handling can be tested deterministically, for example in tests, see [`FaultyFile`]
+
+use crate::file_ext::{AccessPattern, FileExt, IoGeometry};
+use crate::read_plan::ReadAlignment;
+use crate::{ReadAtSync, WriteAtSync};
+use parking_lot::Mutex;
+use std::mem::MaybeUninit;
+use std::ops::Range;
+use std::time::Duration;
+use std::{io, thread};
+
+/// Kind of operation on a file
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Operation {
+    /// [`ReadAtSync::read_at()`] or [`FileExt::read_exact_at()`]
+    Read,
+    /// [`WriteAtSync::write_all_at()`] or [`FileExt::write_all_at()`]
+    Write,
+    /// [`FileExt::preallocate()`]
+    Preallocate,
+    /// [`FileExt::punch_hole()`]
+    PunchHole,
+    /// [`WriteAtSync::sync_data()`], [`FileExt::sync_data()`] or [`FileExt::sync_all()`]
+    Sync,
+}
+
+/// Fault injected into an operation
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Fault {
+    /// Operation fails with an error of specified kind without reaching wrapped file
+    Fail(io::ErrorKind),
+    /// First byte of written (or read) bytes is flipped
+    Corrupt,
+    /// Operation is delayed before reaching wrapped file
+    Delay(Duration),
+}
+
+/// Rule of the script of [`FaultyFile`], injects fault into operations of specified kind that
+/// overlap with specified range of offsets
+#[derive(Debug, Clone)]
+pub struct FaultRule {
+    operation: Operation,
+    fault: Fault,
+    offsets: Range<u64>,
+    skip: usize,
+    times: usize,
+}
+
+impl FaultRule {
+    /// Inject `fault` into the next operation of kind `operation` at any offset
+    pub fn new(operation: Operation, fault: Fault) -> Self {
+        Self {
+            operation,
+            fault,
+            offsets: 0..u64::MAX,
+            skip: 0,
+            times: 1,
+        }
+    }
+
+    /// Only consider operations that overlap with `offsets` (operations without offset, like
+    /// syncs, are considered to be at offset 0)
+    pub fn offsets(mut self, offsets: Range<u64>) -> Self {
+        self.offsets = offsets;
+        self
+    }
+
+    /// Inject fault starting with `n`th (starting from 1) matching operation, preceding matching
+    /// operations are not affected
+    pub fn nth(mut self, n: usize) -> Self {
+        self.skip = n.saturating_sub(1);
+        self
+    }
+
+    /// Inject fault into `times` matching operations in a row (`usize::MAX` for every one)
+    pub fn times(mut self, times: usize) -> Self {
+        self.times = times;
+        self
+    }
+
+    fn matches(&self, operation: Operation, offset: u64, len: u64) -> bool {
+        operation == self.operation
+            && offset < self.offsets.end
+            && offset.saturating_add(len.max(1)) > self.offsets.start
+    }
+}
+
+/// Record of an operation done through [`FaultyFile`]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct OperationRecord {
+    /// Sequence number of the operation, operations are numbered in the order they reach the file
+    /// starting with 0
+    pub sequence: u64,
+    /// Kind of operation
+    pub operation: Operation,
+    /// Offset of the operation
+    pub offset: u64,
+    /// Length of the operation
+    pub len: u64,
+    /// Fault injected into the operation, if any
+    pub fault: Option<Fault>,
+}
+
+#[derive(Debug, Default)]
+struct Script {
+    rules: Vec<FaultRule>,
+    capacity: Option<u64>,
+    history: Vec<OperationRecord>,
+}
+
+/// Wrapper around a file (any [`FileExt`], [`ReadAtSync`] or [`WriteAtSync`] implementation)
+/// that injects faults into its operations according to a programmable script of [`FaultRule`]s,
+/// optionally with short capacity that makes writes and preallocation past it fail like disk is
+/// full.
+///
+/// Every operation gets a sequence number and is matched against the script atomically, such that
+/// faults are injected deterministically even with concurrent use, see [`Self::history()`].
+#[derive(Debug)]
+pub struct FaultyFile<F> {
+    file: F,
+    script: Mutex<Script>,
+}
+
+impl<F> ReadAtSync for FaultyFile<F>
+where
+    F: ReadAtSync,
+{
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        let fault = self.before(Operation::Read, offset, buf.len() as u64)?;
+        self.file.read_at(buf, offset)?;
+        if fault == Some(Fault::Corrupt) {
+            Self::corrupt(buf);
+        }
+
+        Ok(())
+    }
+
+    fn read_at_uninit<'a>(
+        &self,
+        buf: &'a mut [MaybeUninit<u8>],
+        offset: u64,
+    ) -> io::Result<&'a mut [u8]> {
+        let fault = self.before(Operation::Read, offset, buf.len() as u64)?;
+        let buf = self.file.read_at_uninit(buf, offset)?;
+        if fault == Some(Fault::Corrupt) {
+            Self::corrupt(buf);
+        }
+
+        Ok(buf)
+    }
+
+    fn read_alignment(&self) -> Option<ReadAlignment> {
+        self.file.read_alignment()
+    }
+}
+
+impl<F> ReadAtSync for &FaultyFile<F>
+where
+    F: ReadAtSync,
+{
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        (**self).read_at(buf, offset)
+    }
+
+    fn read_at_uninit<'a>(
+        &self,
+        buf: &'a mut [MaybeUninit<u8>],
+        offset: u64,
+    ) -> io::Result<&'a mut [u8]> {
+        (**self).read_at_uninit(buf, offset)
+    }
+
+    fn read_alignment(&self) -> Option<ReadAlignment> {
+        (**self).read_alignment()
+    }
+}
+
+impl<F> WriteAtSync for FaultyFile<F>
+where
+    F: WriteAtSync,
+{
+    fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        match self.before(Operation::Write, offset, buf.len() as u64)? {
+            Some(Fault::Corrupt) => {
+                let mut buf = buf.to_vec();
+                Self::corrupt(&mut buf);
+                self.file.write_all_at(&buf, offset)
+            }
+            _ => self.file.write_all_at(buf, offset),
+        }
+    }
+
+    fn sync_data(&self) -> io::Result<()> {
+        self.before(Operation::Sync, 0, 0)?;
+        self.file.sync_data()
+    }
+}
+
+impl<F> WriteAtSync for &FaultyFile<F>
+where
+    F: WriteAtSync,
+{
+    fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        (**self).write_all_at(buf, offset)
+    }
+
+    fn sync_data(&self) -> io::Result<()> {
+        (**self).sync_data()
+    }
+}
+
+impl<F> FileExt for FaultyFile<F>
+where
+    F: FileExt,
+{
+    fn size(&self) -> io::Result<u64> {
+        self.file.size()
+    }
+
+    fn preallocate(&self, len: u64) -> io::Result<()> {
+        self.before(Operation::Preallocate, 0, len)?;
+        self.file.preallocate(len)
+    }
+
+    fn geometry(&self) -> IoGeometry {
+        self.file.geometry()
+    }
+
+    fn advise_random_access(&self) -> io::Result<()> {
+        self.file.advise_random_access()
+    }
+
+    fn advise_sequential_access(&self) -> io::Result<()> {
+        self.file.advise_sequential_access()
+    }
+
+    fn set_access_pattern(&self, access_pattern: AccessPattern) -> io::Result<()> {
+        self.file.set_access_pattern(access_pattern)
+    }
+
+    fn try_lock_exclusive(&self) -> io::Result<()> {
+        self.file.try_lock_exclusive()
+    }
+
+    fn lock_shared(&self) -> io::Result<()> {
+        self.file.lock_shared()
+    }
+
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        let fault = self.before(Operation::Read, offset, buf.len() as u64)?;
+        self.file.read_exact_at(buf, offset)?;
+        if fault == Some(Fault::Corrupt) {
+            Self::corrupt(buf);
+        }
+
+        Ok(())
+    }
+
+    fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        match self.before(Operation::Write, offset, buf.len() as u64)? {
+            Some(Fault::Corrupt) => {
+                let mut buf = buf.to_vec();
+                Self::corrupt(&mut buf);
+                self.file.write_all_at(&buf, offset)
+            }
+            _ => self.file.write_all_at(buf, offset),
+        }
+    }
+
+    fn punch_hole(&self, offset: u64, len: u64) -> io::Result<()> {
+        self.before(Operation::PunchHole, offset, len)?;
+        self.file.punch_hole(offset, len)
+    }
+
+    fn sync_all(&self) -> io::Result<()> {
+        self.before(Operation::Sync, 0, 0)?;
+        self.file.sync_all()
+    }
+
+    fn sync_data(&self) -> io::Result<()> {
+        self.before(Operation::Sync, 0, 0)?;
+        self.file.sync_data()
+    }
+}
+
+impl<F> FaultyFile<F> {
+    /// Wrap `file` without any faults
+    pub fn new(file: F) -> Self {
+        Self {
+            file,
+            script: Mutex::default(),
+        }
+    }
+
+    /// Wrapped file
+    pub fn inner(&self) -> &F {
+        &self.file
+    }
+
+    /// Add rule to the script, rules are checked in the order they were added and only the first
+    /// matching rule injects its fault into an operation
+    pub fn inject(&self, rule: FaultRule) {
+        self.script.lock().rules.push(rule);
+    }
+
+    /// Remove all rules from the script
+    pub fn clear(&self) {
+        self.script.lock().rules.clear();
+    }
+
+    /// Make writes and preallocation that end past `capacity` bytes fail with
+    /// [`io::ErrorKind::StorageFull`] like disk is full, `None` removes the limit
+    pub fn set_capacity(&self, capacity: Option<u64>) {
+        self.script.lock().capacity = capacity;
+    }
+
+    /// Records of all operations done so far in the order of their sequence numbers
+    pub fn history(&self) -> Vec<OperationRecord> {
+        self.script.lock().history.clone()
+    }
+
+    /// Assign sequence number to the operation and match it against the script, returns error if
+    /// operation must fail, sleeps if it must be delayed
+    fn before(&self, operation: Operation, offset: u64, len: u64) -> io::Result<Option<Fault>> {
+        let (sequence, fault) = {
+            let mut script = self.script.lock();
+            let sequence = script.history.len() as u64;
+
+            let mut fault = script
+                .rules
+                .iter_mut()
+                .filter(|rule| rule.times > 0 && rule.matches(operation, offset, len))
+                .find_map(|rule| {
+                    if rule.skip > 0 {
+                        rule.skip -= 1;
+                        return None;
+                    }
+                    rule.times -= 1;
+                    Some(rule.fault)
+                });
+            if fault.is_none()
+                && matches!(operation, Operation::Write | Operation::Preallocate)
+                && script
+                    .capacity
+                    .is_some_and(|capacity| offset.saturating_add(len) > capacity)
+            {
+                fault = Some(Fault::Fail(io::ErrorKind::StorageFull));
+            }
+
+            script.history.push(OperationRecord {
+                sequence,
+                operation,
+                offset,
+                len,
+                fault,
+            });
+
+            (sequence, fault)
+        };
+
+        match fault {
+            Some(Fault::Fail(kind)) => Err(io::Error::new(
+                kind,
+                format!(
+                    "Injected failure of {operation:?} operation #{sequence} of {len} bytes at \
+                    offset {offset}"
+                ),
+            )),
+            Some(Fault::Delay(delay)) => {
+                thread::sleep(delay);
+                Ok(fault)
+            }
+            Some(Fault::Corrupt) | None => Ok(fault),
+        }
+    }
+
+    fn corrupt(buf: &mut [u8]) {
+        if let Some(byte) = buf.first_mut() {
+            *byte ^= 0xff;
+        }
+    }
+}
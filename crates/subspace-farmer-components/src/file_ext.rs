@@ -2,6 +2,8 @@
 
 use std::fs::{File, OpenOptions};
 use std::io::Result;
+use std::sync::Arc;
+use tracing::warn;
 
 /// Extension convenience trait that allows setting some file opening options in cross-platform way
 pub trait OpenOptionsExt {
@@ -9,11 +11,14 @@ pub trait OpenOptionsExt {
     /// undesirable, only has impact on Windows, for other operating systems see [`FileExt`]
     fn advise_random_access(&mut self) -> &mut Self;
 
-    /// Advise Windows to not use buffering for this file and that file access will be random.
+    /// Advise OS to not use buffering for this file and that file access will be random, uses
+    /// `O_DIRECT` on Linux.
     ///
     /// NOTE: There are major alignment requirements described here:
     /// https://learn.microsoft.com/en-us/windows/win32/fileio/file-buffering#alignment-and-file-access-requirements
-    #[cfg(windows)]
+    /// and in the `O_DIRECT` section of `open(2)` man page on Linux, not all file systems support
+    /// `O_DIRECT` either, in which case opening the file will fail with `EINVAL`.
+    #[cfg(any(target_os = "linux", windows))]
     fn advise_unbuffered(&mut self) -> &mut Self;
 
     /// Advise OS/file system that file will use sequential access and read-ahead behavior is
@@ -47,6 +52,12 @@ impl OpenOptionsExt for OpenOptions {
         )
     }
 
+    #[cfg(target_os = "linux")]
+    fn advise_unbuffered(&mut self) -> &mut Self {
+        use std::os::unix::fs::OpenOptionsExt;
+        self.custom_flags(libc::O_DIRECT)
+    }
+
     #[cfg(windows)]
     fn advise_unbuffered(&mut self) -> &mut Self {
         use std::os::windows::fs::OpenOptionsExt;
@@ -75,6 +86,44 @@ impl OpenOptionsExt for OpenOptions {
     }
 }
 
+/// Expected access pattern of the file, see [`FileExt::set_access_pattern()`]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum AccessPattern {
+    /// No particular access pattern, implementations may detect it automatically
+    #[default]
+    Normal,
+    /// Random access, read-ahead is undesirable
+    Random,
+    /// Sequential access, read-ahead is desirable
+    Sequential,
+}
+
+/// Alignment constraints of the storage file is on, such that requests can be sized and aligned to
+/// avoid read-modify-write cycles in the storage and splitting of requests by OS
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct IoGeometry {
+    /// Smallest unit the storage can address, offsets and sizes of unbuffered requests must be
+    /// multiples of it
+    pub logical_sector_size: usize,
+    /// Unit the storage works with internally, requests aligned to it don't touch more sectors
+    /// than necessary
+    pub physical_sector_size: usize,
+    /// Max size of a single request, larger requests are split into multiple
+    pub max_request_size: usize,
+}
+
+impl Default for IoGeometry {
+    /// Geometry of typical modern disk that emulates 512-byte sectors on top of 4096-byte physical
+    /// sectors, used when actual geometry is unknown
+    fn default() -> Self {
+        Self {
+            logical_sector_size: 512,
+            physical_sector_size: 4096,
+            max_request_size: 1024 * 1024,
+        }
+    }
+}
+
 /// Extension convenience trait that allows pre-allocating files, suggesting random access pattern
 /// and doing cross-platform exact reads/writes
 pub trait FileExt {
@@ -84,6 +133,12 @@ pub trait FileExt {
     /// Make sure file has specified number of bytes allocated for it
     fn preallocate(&self, len: u64) -> Result<()>;
 
+    /// Alignment constraints of the storage file is on, [`IoGeometry::default()`] unless
+    /// implementation knows better (like files that detect sector sizes for unbuffered I/O)
+    fn geometry(&self) -> IoGeometry {
+        IoGeometry::default()
+    }
+
     /// Advise OS/file system that file will use random access and read-ahead behavior is
     /// undesirable, on Windows this can only be set when file is opened, see [`OpenOptionsExt`]
     fn advise_random_access(&self) -> Result<()>;
@@ -92,11 +147,84 @@ pub trait FileExt {
     /// desirable, on Windows this can only be set when file is opened, see [`OpenOptionsExt`]
     fn advise_sequential_access(&self) -> Result<()>;
 
+    /// Set expected access pattern of the file, by default [`AccessPattern::Random`] and
+    /// [`AccessPattern::Sequential`] call [`Self::advise_random_access()`] and
+    /// [`Self::advise_sequential_access()`] respectively, while [`AccessPattern::Normal`] does
+    /// nothing
+    fn set_access_pattern(&self, access_pattern: AccessPattern) -> Result<()> {
+        match access_pattern {
+            AccessPattern::Normal => Ok(()),
+            AccessPattern::Random => self.advise_random_access(),
+            AccessPattern::Sequential => self.advise_sequential_access(),
+        }
+    }
+
+    /// Try to acquire exclusive advisory lock on the file without blocking (`flock` on Unix,
+    /// `LockFileEx` on Windows), lock is released when file is closed.
+    ///
+    /// Returns error of [`std::io::ErrorKind::WouldBlock`] kind if file is already locked by
+    /// someone else.
+    ///
+    /// NOTE: `LockFileEx` locks are mandatory, I/O through other handles of the locked file fails
+    /// on Windows, so files that are read concurrently through multiple handles should be
+    /// protected by locking a dedicated lock file instead.
+    fn try_lock_exclusive(&self) -> Result<()>;
+
+    /// Acquire shared advisory lock on the file, blocks until exclusive lock held by someone else
+    /// (if any) is released, lock is released when file is closed
+    fn lock_shared(&self) -> Result<()>;
+
     /// Read exact number of bytes at a specific offset
     fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> Result<()>;
 
+    /// Read exact number of bytes at multiple offsets, each request is a pair of offset and buffer
+    /// to read into.
+    ///
+    /// Implementations are free to reorder and merge requests, if any of the requests fails, error
+    /// identifies its offset.
+    fn read_exact_at_vectored(&self, requests: &mut [(u64, &mut [u8])]) -> Result<()> {
+        for (offset, buf) in requests.iter_mut() {
+            self.read_exact_at(buf, *offset).map_err(|error| {
+                std::io::Error::new(
+                    error.kind(),
+                    format!(
+                        "Failed to read {} bytes at offset {offset}: {error}",
+                        buf.len()
+                    ),
+                )
+            })?;
+        }
+
+        Ok(())
+    }
+
     /// Write all provided bytes at a specific offset
     fn write_all_at(&self, buf: &[u8], offset: u64) -> Result<()>;
+
+    /// Deallocate `len` bytes at `offset` without changing file size, such that space occupied by
+    /// contents that are no longer needed is returned to the file system (matters for
+    /// thin-provisioned and compressed volumes), reads of deallocated range return zeroes.
+    ///
+    /// Uses `FALLOC_FL_PUNCH_HOLE` on Linux and `FSCTL_SET_ZERO_DATA` on Windows, where only
+    /// sparse files are supported. Does nothing (with a warning) where hole punching is not
+    /// supported by the platform or file system, which is also the default implementation.
+    ///
+    /// Implementations that require aligned access round the range inward to their alignment, in
+    /// which case unaligned head and tail of the range keep their contents.
+    fn punch_hole(&self, _offset: u64, len: u64) -> Result<()> {
+        if len > 0 {
+            warn!("Hole punching is not supported, ignoring");
+        }
+
+        Ok(())
+    }
+
+    /// Flush all data and metadata (like file length) to disk, see [`File::sync_all()`]
+    fn sync_all(&self) -> Result<()>;
+
+    /// Flush data to disk, but not necessarily metadata that is not needed to read the data back,
+    /// see [`File::sync_data()`]
+    fn sync_data(&self) -> Result<()>;
 }
 
 impl FileExt for File {
@@ -108,6 +236,122 @@ impl FileExt for File {
         fs2::FileExt::allocate(self, len)
     }
 
+    fn try_lock_exclusive(&self) -> Result<()> {
+        fs2::FileExt::try_lock_exclusive(self).map_err(|error| {
+            // Lock contention is reported with different error codes on different platforms
+            if error.raw_os_error() == fs2::lock_contended_error().raw_os_error() {
+                std::io::Error::new(std::io::ErrorKind::WouldBlock, error)
+            } else {
+                error
+            }
+        })
+    }
+
+    fn lock_shared(&self) -> Result<()> {
+        fs2::FileExt::lock_shared(self)
+    }
+
+    fn sync_all(&self) -> Result<()> {
+        File::sync_all(self)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn punch_hole(&self, offset: u64, len: u64) -> Result<()> {
+        use std::os::unix::io::AsRawFd;
+
+        // Empty range is rejected by `fallocate`
+        if len == 0 {
+            return Ok(());
+        }
+
+        let (Ok(offset), Ok(len)) = (libc::off_t::try_from(offset), libc::off_t::try_from(len))
+        else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Range of {len} bytes at offset {offset} is too large"),
+            ));
+        };
+
+        let result = unsafe {
+            libc::fallocate(
+                self.as_raw_fd(),
+                libc::FALLOC_FL_PUNCH_HOLE | libc::FALLOC_FL_KEEP_SIZE,
+                offset,
+                len,
+            )
+        };
+        if result == 0 {
+            return Ok(());
+        }
+
+        let error = std::io::Error::last_os_error();
+        if error.raw_os_error() == Some(libc::EOPNOTSUPP) {
+            warn!(%error, "Hole punching is not supported by file system, ignoring");
+            Ok(())
+        } else {
+            Err(error)
+        }
+    }
+
+    #[cfg(windows)]
+    fn punch_hole(&self, offset: u64, len: u64) -> Result<()> {
+        use std::os::windows::fs::MetadataExt;
+        use std::os::windows::io::AsRawHandle;
+        use std::{mem, ptr};
+        use winapi::um::ioapiset::DeviceIoControl;
+        use winapi::um::winioctl::{FILE_ZERO_DATA_INFORMATION, FSCTL_SET_ZERO_DATA};
+        use winapi::um::winnt::FILE_ATTRIBUTE_SPARSE_FILE;
+
+        if len == 0 {
+            return Ok(());
+        }
+
+        // Zeroing data of non-sparse file writes zeroes instead of deallocating anything
+        if self.metadata()?.file_attributes() & FILE_ATTRIBUTE_SPARSE_FILE == 0 {
+            warn!("Hole punching is only supported for sparse files on Windows, ignoring");
+            return Ok(());
+        }
+
+        let end = offset
+            .checked_add(len)
+            .and_then(|end| i64::try_from(end).ok());
+        let (Ok(offset), Some(end)) = (i64::try_from(offset), end) else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Range of {len} bytes at offset {offset} is too large"),
+            ));
+        };
+
+        let mut zero_data_information: FILE_ZERO_DATA_INFORMATION = unsafe { mem::zeroed() };
+        unsafe {
+            *zero_data_information.FileOffset.QuadPart_mut() = offset;
+            *zero_data_information.BeyondFinalZero.QuadPart_mut() = end;
+        }
+        let mut bytes_returned = 0;
+        let result = unsafe {
+            DeviceIoControl(
+                self.as_raw_handle().cast(),
+                FSCTL_SET_ZERO_DATA,
+                ptr::addr_of_mut!(zero_data_information).cast(),
+                mem::size_of::<FILE_ZERO_DATA_INFORMATION>() as u32,
+                ptr::null_mut(),
+                0,
+                &mut bytes_returned,
+                ptr::null_mut(),
+            )
+        };
+
+        if result == 0 {
+            Err(std::io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn sync_data(&self) -> Result<()> {
+        File::sync_data(self)
+    }
+
     #[cfg(target_os = "linux")]
     fn advise_random_access(&self) -> Result<()> {
         use std::os::unix::io::AsRawFd;
@@ -229,3 +473,66 @@ impl FileExt for File {
         Ok(())
     }
 }
+
+/// Shared handle, such that the same file can be given to multiple subsystems without tying their
+/// lifetimes together
+impl<T> FileExt for Arc<T>
+where
+    T: FileExt + ?Sized,
+{
+    fn size(&self) -> Result<u64> {
+        (**self).size()
+    }
+
+    fn preallocate(&self, len: u64) -> Result<()> {
+        (**self).preallocate(len)
+    }
+
+    fn geometry(&self) -> IoGeometry {
+        (**self).geometry()
+    }
+
+    fn advise_random_access(&self) -> Result<()> {
+        (**self).advise_random_access()
+    }
+
+    fn advise_sequential_access(&self) -> Result<()> {
+        (**self).advise_sequential_access()
+    }
+
+    fn set_access_pattern(&self, access_pattern: AccessPattern) -> Result<()> {
+        (**self).set_access_pattern(access_pattern)
+    }
+
+    fn try_lock_exclusive(&self) -> Result<()> {
+        (**self).try_lock_exclusive()
+    }
+
+    fn lock_shared(&self) -> Result<()> {
+        (**self).lock_shared()
+    }
+
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> Result<()> {
+        (**self).read_exact_at(buf, offset)
+    }
+
+    fn read_exact_at_vectored(&self, requests: &mut [(u64, &mut [u8])]) -> Result<()> {
+        (**self).read_exact_at_vectored(requests)
+    }
+
+    fn write_all_at(&self, buf: &[u8], offset: u64) -> Result<()> {
+        (**self).write_all_at(buf, offset)
+    }
+
+    fn punch_hole(&self, offset: u64, len: u64) -> Result<()> {
+        (**self).punch_hole(offset, len)
+    }
+
+    fn sync_all(&self) -> Result<()> {
+        (**self).sync_all()
+    }
+
+    fn sync_data(&self) -> Result<()> {
+        (**self).sync_data()
+    }
+}
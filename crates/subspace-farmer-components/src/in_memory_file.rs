@@ -0,0 +1,116 @@
+//! In-memory file that can be used instead of a real file, for example in tests
+
+use crate::{ReadAtSync, WriteAtSync};
+use parking_lot::{Mutex, RwLock};
+use std::collections::VecDeque;
+use std::io;
+
+/// In-memory file implementing [`ReadAtSync`] and [`WriteAtSync`] that grows on writes as
+/// necessary, reads and writes can be configured to fail to test error handling
+#[derive(Debug, Default)]
+pub struct InMemoryFile {
+    bytes: RwLock<Vec<u8>>,
+    read_errors: Mutex<VecDeque<io::Error>>,
+    write_errors: Mutex<VecDeque<io::Error>>,
+    syncs: Mutex<usize>,
+}
+
+impl From<Vec<u8>> for InMemoryFile {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self {
+            bytes: RwLock::new(bytes),
+            ..Self::default()
+        }
+    }
+}
+
+impl ReadAtSync for InMemoryFile {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        if let Some(error) = self.read_errors.lock().pop_front() {
+            return Err(error);
+        }
+
+        let bytes = self.bytes.read();
+        match usize::try_from(offset)
+            .ok()
+            .and_then(|offset| bytes.get(offset..)?.get(..buf.len()))
+        {
+            Some(source) => {
+                buf.copy_from_slice(source);
+                Ok(())
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!(
+                    "Read of {} bytes at offset {offset} is outside of {} bytes file",
+                    buf.len(),
+                    bytes.len()
+                ),
+            )),
+        }
+    }
+}
+
+impl ReadAtSync for &InMemoryFile {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        (**self).read_at(buf, offset)
+    }
+}
+
+impl WriteAtSync for InMemoryFile {
+    fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        if let Some(error) = self.write_errors.lock().pop_front() {
+            return Err(error);
+        }
+
+        let offset = usize::try_from(offset)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+        let mut bytes = self.bytes.write();
+        if bytes.len() < offset + buf.len() {
+            bytes.resize(offset + buf.len(), 0);
+        }
+        bytes[offset..][..buf.len()].copy_from_slice(buf);
+
+        Ok(())
+    }
+
+    fn sync_data(&self) -> io::Result<()> {
+        *self.syncs.lock() += 1;
+
+        Ok(())
+    }
+}
+
+impl WriteAtSync for &InMemoryFile {
+    fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        (**self).write_all_at(buf, offset)
+    }
+
+    fn sync_data(&self) -> io::Result<()> {
+        (**self).sync_data()
+    }
+}
+
+impl InMemoryFile {
+    /// Copy of file contents
+    pub fn bytes(&self) -> Vec<u8> {
+        self.bytes.read().clone()
+    }
+
+    /// Number of times [`WriteAtSync::sync_data()`] was called
+    pub fn syncs(&self) -> usize {
+        *self.syncs.lock()
+    }
+
+    /// Make next read fail with provided error instead of reading, multiple calls queue multiple
+    /// errors for subsequent reads
+    pub fn fail_next_read(&self, error: io::Error) {
+        self.read_errors.lock().push_back(error);
+    }
+
+    /// Make next write fail with provided error instead of writing, multiple calls queue multiple
+    /// errors for subsequent writes
+    pub fn fail_next_write(&self, error: io::Error) {
+        self.write_errors.lock().push_back(error);
+    }
+}
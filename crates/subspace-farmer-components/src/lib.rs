@@ -10,27 +10,34 @@
     slice_flatten,
     try_blocks
 )]
+#![cfg_attr(feature = "testing", feature(io_error_more))]
 
 //! Components of the reference implementation of Subspace Farmer for Subspace Network Blockchain.
 //!
 //! These components are used to implement farmer itself, but can also be used independently if necessary.
 
 pub mod auditing;
+#[cfg(feature = "testing")]
+pub mod faulty_file;
 pub mod file_ext;
+pub mod in_memory_file;
 pub mod plotting;
 pub mod proving;
+pub mod read_plan;
 pub mod reading;
 pub mod sector;
 mod segment_reconstruction;
 
 use crate::file_ext::FileExt;
+use crate::read_plan::ReadAlignment;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use static_assertions::const_assert;
 use std::fs::File;
 use std::future::Future;
-use std::io;
+use std::mem::MaybeUninit;
 use std::sync::Arc;
+use std::{io, mem, slice};
 use subspace_core_primitives::{ArchivedHistorySegment, HistorySize, Piece, PieceIndex};
 
 use std::error::Error;
@@ -118,6 +125,103 @@ pub trait ReadAtSync: Send + Sync {
 
     /// Fill the buffer by reading bytes at a specific offset
     fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()>;
+
+    /// Fill uninitialized buffer by reading bytes at a specific offset and return the whole buffer
+    /// as initialized slice, such that large buffers don't need to be zeroed before reading.
+    ///
+    /// Default implementation zero-initializes the buffer and calls [`Self::read_at()`],
+    /// implementations may write file contents into uninitialized buffer directly, but only through
+    /// APIs that accept uninitialized memory: viewing uninitialized bytes as `&mut [u8]` (even just
+    /// to pass it to OS read) is undefined behavior. On error contents of the buffer are
+    /// unspecified and must not be assumed to be initialized.
+    fn read_at_uninit<'a>(
+        &self,
+        buf: &'a mut [MaybeUninit<u8>],
+        offset: u64,
+    ) -> io::Result<&'a mut [u8]> {
+        buf.fill(MaybeUninit::new(0));
+        // SAFETY: All bytes were initialized with zeroes above
+        let buf = unsafe { assume_init_bytes(buf) };
+        self.read_at(buf, offset)?;
+
+        Ok(buf)
+    }
+
+    /// Read multiple regions, each defined as offset relative to `base_offset` and length, into
+    /// `out`, where regions are placed back to back in the order they are provided.
+    ///
+    /// `out` length must be equal to the total length of all regions. Default implementation reads
+    /// regions one by one, implementations may read multiple regions at once more efficiently.
+    fn read_regions(
+        &self,
+        base_offset: u64,
+        regions: &[(u32, u32)],
+        out: &mut [u8],
+    ) -> io::Result<()> {
+        for (offset, buf) in region_buffers(regions, out)? {
+            self.read_at(buf, base_offset + offset)?;
+        }
+
+        Ok(())
+    }
+
+    /// Alignment constraints of the storage reads end up on, such that reads of many small chunks
+    /// can be planned to land on sector boundaries (see [`read_plan::ReadPlan`]), `None` if
+    /// unknown, in which case chunks are read as is
+    fn read_alignment(&self) -> Option<ReadAlignment> {
+        None
+    }
+}
+
+/// Sync counterpart of [`ReadAtSync`] for writing, it is both [`Send`] and [`Sync`] and allows
+/// plotting to write into different storage backends
+pub trait WriteAtSync: Send + Sync {
+    /// Write all provided bytes at a specific offset
+    fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()>;
+
+    /// Flush written data to storage, see [`File::sync_data()`]
+    fn sync_data(&self) -> io::Result<()>;
+}
+
+/// Split output buffer of [`ReadAtSync::read_regions()`] into buffers of individual regions, each
+/// returned with its offset, fails if output buffer length doesn't match regions
+pub fn region_buffers<'a>(
+    regions: &[(u32, u32)],
+    mut out: &'a mut [u8],
+) -> io::Result<Vec<(u64, &'a mut [u8])>> {
+    let total_len = regions
+        .iter()
+        .map(|&(_offset, len)| len as usize)
+        .sum::<usize>();
+    if total_len != out.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Output buffer length {} doesn't match total length of regions {total_len}",
+                out.len()
+            ),
+        ));
+    }
+
+    Ok(regions
+        .iter()
+        .map(|&(offset, len)| {
+            let (buf, rest) = mem::take(&mut out).split_at_mut(len as usize);
+            out = rest;
+            (u64::from(offset), buf)
+        })
+        .collect())
+}
+
+/// View uninitialized bytes as initialized, used by [`ReadAtSync::read_at_uninit()`]
+/// implementations once every byte of `buf` was written.
+///
+/// # Safety
+/// Every byte of `buf` must be initialized.
+pub unsafe fn assume_init_bytes(buf: &mut [MaybeUninit<u8>]) -> &mut [u8] {
+    // SAFETY: `MaybeUninit<u8>` has the same layout as `u8` and bytes are initialized according to
+    // the contract of this function
+    unsafe { slice::from_raw_parts_mut(buf.as_mut_ptr().cast::<u8>(), buf.len()) }
 }
 
 impl ReadAtSync for ! {
@@ -248,6 +352,103 @@ impl ReadAtSync for &File {
     }
 }
 
+impl WriteAtSync for File {
+    fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        FileExt::write_all_at(self, buf, offset)
+    }
+
+    fn sync_data(&self) -> io::Result<()> {
+        File::sync_data(self)
+    }
+}
+
+impl WriteAtSync for &File {
+    fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        FileExt::write_all_at(*self, buf, offset)
+    }
+
+    fn sync_data(&self) -> io::Result<()> {
+        File::sync_data(*self)
+    }
+}
+
+/// Shared handle, such that the same reader can be given to multiple subsystems without tying their
+/// lifetimes together
+impl<T> ReadAtSync for Arc<T>
+where
+    T: ReadAtSync + ?Sized,
+{
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        (**self).read_at(buf, offset)
+    }
+
+    fn read_at_uninit<'a>(
+        &self,
+        buf: &'a mut [MaybeUninit<u8>],
+        offset: u64,
+    ) -> io::Result<&'a mut [u8]> {
+        (**self).read_at_uninit(buf, offset)
+    }
+
+    fn read_regions(
+        &self,
+        base_offset: u64,
+        regions: &[(u32, u32)],
+        out: &mut [u8],
+    ) -> io::Result<()> {
+        (**self).read_regions(base_offset, regions, out)
+    }
+
+    fn read_alignment(&self) -> Option<ReadAlignment> {
+        (**self).read_alignment()
+    }
+}
+
+impl<T> ReadAtSync for &Arc<T>
+where
+    T: ReadAtSync + ?Sized,
+{
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        (***self).read_at(buf, offset)
+    }
+
+    fn read_at_uninit<'a>(
+        &self,
+        buf: &'a mut [MaybeUninit<u8>],
+        offset: u64,
+    ) -> io::Result<&'a mut [u8]> {
+        (***self).read_at_uninit(buf, offset)
+    }
+
+    fn read_regions(
+        &self,
+        base_offset: u64,
+        regions: &[(u32, u32)],
+        out: &mut [u8],
+    ) -> io::Result<()> {
+        (***self).read_regions(base_offset, regions, out)
+    }
+
+    fn read_alignment(&self) -> Option<ReadAlignment> {
+        (***self).read_alignment()
+    }
+}
+
+/// Shared handle, such that the same writer can be given to multiple subsystems without tying their
+/// lifetimes together
+impl<T> WriteAtSync for Arc<T>
+where
+    T: WriteAtSync + ?Sized,
+{
+    fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        (**self).write_all_at(buf, offset)
+    }
+
+    fn sync_data(&self) -> io::Result<()> {
+        (**self).sync_data()
+    }
+}
+
 /// Reader with fixed offset added to all attempted reads
 #[derive(Debug, Copy, Clone)]
 pub struct ReadAtOffset<'a, T> {
@@ -262,6 +463,30 @@ where
     fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
         self.inner.read_at(buf, offset + self.offset)
     }
+
+    fn read_at_uninit<'a>(
+        &self,
+        buf: &'a mut [MaybeUninit<u8>],
+        offset: u64,
+    ) -> io::Result<&'a mut [u8]> {
+        self.inner.read_at_uninit(buf, offset + self.offset)
+    }
+
+    fn read_regions(
+        &self,
+        base_offset: u64,
+        regions: &[(u32, u32)],
+        out: &mut [u8],
+    ) -> io::Result<()> {
+        self.inner
+            .read_regions(base_offset + self.offset, regions, out)
+    }
+
+    fn read_alignment(&self) -> Option<ReadAlignment> {
+        self.inner
+            .read_alignment()
+            .map(|alignment| alignment.with_offset(self.offset))
+    }
 }
 
 impl<T> ReadAtSync for &ReadAtOffset<'_, T>
@@ -271,6 +496,30 @@ where
     fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
         self.inner.read_at(buf, offset + self.offset)
     }
+
+    fn read_at_uninit<'a>(
+        &self,
+        buf: &'a mut [MaybeUninit<u8>],
+        offset: u64,
+    ) -> io::Result<&'a mut [u8]> {
+        self.inner.read_at_uninit(buf, offset + self.offset)
+    }
+
+    fn read_regions(
+        &self,
+        base_offset: u64,
+        regions: &[(u32, u32)],
+        out: &mut [u8],
+    ) -> io::Result<()> {
+        self.inner
+            .read_regions(base_offset + self.offset, regions, out)
+    }
+
+    fn read_alignment(&self) -> Option<ReadAlignment> {
+        self.inner
+            .read_alignment()
+            .map(|alignment| alignment.with_offset(self.offset))
+    }
 }
 
 impl<T> ReadAtAsync for ReadAtOffset<'_, T>
@@ -0,0 +1,139 @@
+//! Planning of reads of many small chunks at arbitrary offsets, such that chunks are read with
+//! requests that are aligned to physical sectors of the storage and don't exceed its max request
+//! size, see [`ReadPlan`]
+
+#[cfg(test)]
+mod tests;
+
+use crate::file_ext::IoGeometry;
+use std::ops::Range;
+
+/// Alignment constraints of reads from [`ReadAtSync`](crate::ReadAtSync) implementation, see
+/// [`ReadAtSync::read_alignment()`](crate::ReadAtSync::read_alignment)
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ReadAlignment {
+    /// Geometry of the storage reads end up on
+    pub geometry: IoGeometry,
+    /// Offset of the first byte of the reader in the underlying file, sector boundaries are
+    /// relative to the start of the file rather than the reader
+    pub offset: u64,
+}
+
+impl ReadAlignment {
+    /// Alignment of the reader that starts `offset` bytes later
+    pub fn with_offset(self, offset: u64) -> Self {
+        Self {
+            geometry: self.geometry,
+            offset: self.offset + offset,
+        }
+    }
+}
+
+/// Single request of [`ReadPlan`]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ReadRequest {
+    /// Offset of the request relative to the reader
+    pub offset: u64,
+    /// Length of the request in bytes
+    pub len: usize,
+    /// Offset of the first chunk covered by the request
+    pub first_chunk_offset: u64,
+}
+
+/// Plan of reading chunks of the same size at arbitrary offsets.
+///
+/// With known alignment every chunk is extended to physical sectors it is in and chunks whose
+/// sectors overlap or touch are read with a single request, as long as it doesn't exceed max
+/// request size. Requests are limited to the length of the reader, so requests at its edges may be
+/// unaligned. Without alignment chunks are read as is (adjacent chunks are still merged).
+#[derive(Debug, Clone)]
+pub struct ReadPlan {
+    chunk_size: usize,
+    requests: Vec<ReadRequest>,
+}
+
+impl ReadPlan {
+    /// Create plan for reading `chunk_size` bytes at each of `chunk_offsets` from reader of `len`
+    /// bytes
+    pub fn new<I>(
+        chunk_offsets: I,
+        chunk_size: usize,
+        len: u64,
+        alignment: Option<ReadAlignment>,
+    ) -> Self
+    where
+        I: IntoIterator<Item = u64>,
+    {
+        let mut chunk_offsets = chunk_offsets.into_iter().collect::<Vec<_>>();
+        chunk_offsets.sort_unstable();
+        chunk_offsets.dedup();
+
+        let max_request_size = alignment.map_or(u64::MAX, |alignment| {
+            alignment.geometry.max_request_size as u64
+        });
+        let mut requests = Vec::<ReadRequest>::new();
+        for chunk_offset in chunk_offsets {
+            let window = chunk_window(chunk_offset, chunk_size, len, alignment);
+
+            if let Some(request) = requests.last_mut() {
+                let request_end = request.offset + request.len as u64;
+                let merged_len = window.end.max(request_end) - request.offset;
+                if window.start <= request_end && merged_len <= max_request_size {
+                    request.len = merged_len as usize;
+                    continue;
+                }
+            }
+
+            requests.push(ReadRequest {
+                offset: window.start,
+                len: (window.end - window.start) as usize,
+                first_chunk_offset: chunk_offset,
+            });
+        }
+
+        Self {
+            chunk_size,
+            requests,
+        }
+    }
+
+    /// Requests of the plan, ordered by offset
+    pub fn requests(&self) -> &[ReadRequest] {
+        &self.requests
+    }
+
+    /// Find chunk at `chunk_offset` in results of requests, returns index of the request and range
+    /// of the chunk within its result, `None` if chunk is not covered by the plan
+    pub fn locate(&self, chunk_offset: u64) -> Option<(usize, Range<usize>)> {
+        let request_index = self
+            .requests
+            .partition_point(|request| request.offset <= chunk_offset)
+            .checked_sub(1)?;
+        let request = &self.requests[request_index];
+        let start = usize::try_from(chunk_offset - request.offset).ok()?;
+        let end = start + self.chunk_size;
+
+        (end <= request.len).then_some((request_index, start..end))
+    }
+}
+
+/// Range of the reader that needs to be read to get the chunk at `chunk_offset`
+fn chunk_window(
+    chunk_offset: u64,
+    chunk_size: usize,
+    len: u64,
+    alignment: Option<ReadAlignment>,
+) -> Range<u64> {
+    let chunk_end = chunk_offset + chunk_size as u64;
+    let Some(ReadAlignment { geometry, offset }) = alignment else {
+        return chunk_offset..chunk_end;
+    };
+    let physical_sector_size = geometry.physical_sector_size as u64;
+
+    let start = ((offset + chunk_offset) / physical_sector_size * physical_sector_size)
+        .saturating_sub(offset);
+    let end = ((offset + chunk_end).next_multiple_of(physical_sector_size) - offset)
+        .min(len.max(chunk_end));
+
+    start..end
+}
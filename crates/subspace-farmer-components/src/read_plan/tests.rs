@@ -0,0 +1,152 @@
+use crate::file_ext::IoGeometry;
+use crate::read_plan::{ReadAlignment, ReadPlan, ReadRequest};
+
+const CHUNK_SIZE: usize = 32;
+const LEN: u64 = 8192;
+const CHUNK_OFFSETS: [u64; 3] = [600, 1600, 5000];
+
+fn alignment(physical_sector_size: usize, max_request_size: usize) -> ReadAlignment {
+    ReadAlignment {
+        geometry: IoGeometry {
+            logical_sector_size: 512,
+            physical_sector_size,
+            max_request_size,
+        },
+        offset: 0,
+    }
+}
+
+fn request(offset: u64, len: usize, first_chunk_offset: u64) -> ReadRequest {
+    ReadRequest {
+        offset,
+        len,
+        first_chunk_offset,
+    }
+}
+
+/// Execute `plan` against `data` and check that every chunk at `chunk_offsets` is found in results
+/// of requests
+fn check_plan(plan: &ReadPlan, data: &[u8], chunk_offsets: &[u64]) {
+    let results = plan
+        .requests()
+        .iter()
+        .map(|request| {
+            let offset = request.offset as usize;
+            data[offset..][..request.len].to_vec()
+        })
+        .collect::<Vec<_>>();
+
+    for &chunk_offset in chunk_offsets {
+        let (request_index, range) = plan.locate(chunk_offset).unwrap();
+        let chunk_offset = chunk_offset as usize;
+        assert_eq!(
+            &results[request_index][range],
+            &data[chunk_offset..][..CHUNK_SIZE]
+        );
+    }
+}
+
+fn patterned_data() -> Vec<u8> {
+    (0..LEN).map(|i| (i % 251) as u8).collect()
+}
+
+#[test]
+fn plans_depend_on_geometry() {
+    let data = patterned_data();
+
+    let plan_512 = ReadPlan::new(
+        CHUNK_OFFSETS,
+        CHUNK_SIZE,
+        LEN,
+        Some(alignment(512, 1 << 20)),
+    );
+    assert_eq!(
+        plan_512.requests(),
+        &[
+            request(512, 512, 600),
+            request(1536, 512, 1600),
+            request(4608, 512, 5000),
+        ]
+    );
+    check_plan(&plan_512, &data, &CHUNK_OFFSETS);
+
+    // The first two chunks are in the same physical sector and are read with one request
+    let plan_4096 = ReadPlan::new(
+        CHUNK_OFFSETS,
+        CHUNK_SIZE,
+        LEN,
+        Some(alignment(4096, 1 << 20)),
+    );
+    assert_eq!(
+        plan_4096.requests(),
+        &[request(0, 4096, 600), request(4096, 4096, 5000)]
+    );
+    check_plan(&plan_4096, &data, &CHUNK_OFFSETS);
+
+    // Without alignment chunks are read as is
+    let plan_unaligned = ReadPlan::new(CHUNK_OFFSETS, CHUNK_SIZE, LEN, None);
+    assert_eq!(
+        plan_unaligned.requests(),
+        &[
+            request(600, CHUNK_SIZE, 600),
+            request(1600, CHUNK_SIZE, 1600),
+            request(5000, CHUNK_SIZE, 5000),
+        ]
+    );
+    check_plan(&plan_unaligned, &data, &CHUNK_OFFSETS);
+    assert_eq!(plan_unaligned.locate(601), None);
+    assert_eq!(plan_unaligned.locate(0), None);
+}
+
+#[test]
+fn reader_offset() {
+    let data = patterned_data();
+    // Reader starts 100 bytes into the file, sector boundaries are at 412, 924, etc. of the reader
+    let alignment = alignment(512, 1 << 20).with_offset(100);
+    let reader_len = 3020;
+    let chunk_offsets = [10, 1500, 3000];
+
+    let plan = ReadPlan::new(chunk_offsets, CHUNK_SIZE, reader_len, Some(alignment));
+    assert_eq!(
+        plan.requests(),
+        &[
+            request(0, 412, 10),
+            request(1436, 512, 1500),
+            request(2972, 48, 3000),
+        ]
+    );
+    for request in plan.requests() {
+        let start = alignment.offset + request.offset;
+        let end = start + request.len as u64;
+        // Requests at the edges of the reader are limited by it
+        if request.offset != 0 {
+            assert_eq!(start % 512, 0);
+        }
+        if end != alignment.offset + reader_len {
+            assert_eq!(end % 512, 0);
+        }
+    }
+    check_plan(&plan, &data[..reader_len as usize], &chunk_offsets);
+}
+
+#[test]
+fn max_request_size() {
+    let data = patterned_data();
+    let chunk_offsets = [0, 512, 1024, 1536];
+
+    let plan = ReadPlan::new(chunk_offsets, CHUNK_SIZE, LEN, Some(alignment(512, 1024)));
+    assert_eq!(
+        plan.requests(),
+        &[request(0, 1024, 0), request(1024, 1024, 1024)]
+    );
+    check_plan(&plan, &data, &chunk_offsets);
+
+    let plan = ReadPlan::new(
+        chunk_offsets,
+        CHUNK_SIZE,
+        LEN,
+        Some(alignment(512, 1 << 20)),
+    );
+    assert_eq!(plan.requests(), &[request(0, 2048, 0)]);
+    check_plan(&plan, &data, &chunk_offsets);
+}
@@ -1,3 +1,4 @@
+use crate::read_plan::{ReadPlan, ReadRequest};
 use crate::sector::{
     sector_record_chunks_size, RecordMetadata, SectorContentsMap, SectorContentsMapFromBytesError,
     SectorMetadataChecksummed,
@@ -96,12 +97,71 @@ impl ReadingError {
 pub enum ReadSectorRecordChunksMode {
     /// Read individual chunks ([`Scalar::FULL_BYTES`] in size) concurrently, which results in lower
     /// total data transfer, but requires for SSD to support high concurrency and low latency
+    ///
+    /// When alignment of the storage is known (see [`ReadAtSync::read_alignment()`]), chunks are
+    /// read with requests aligned to its physical sectors and chunks in the same or adjacent
+    /// sectors are read together, see [`ReadPlan`].
     ConcurrentChunks,
     /// Read the whole sector at once and extract chunks from in-memory buffer, which uses more
     /// memory, but only requires linear read speed from the disk to be decent
     WholeSector,
 }
 
+/// Execute a single request of [`ReadPlan`]
+fn read_planned_request<S>(
+    sector: &S,
+    request: &ReadRequest,
+    sector_contents_map_size: u64,
+) -> Result<Vec<u8>, ReadingError>
+where
+    S: ReadAtSync,
+{
+    let mut bytes = vec![0; request.len];
+    sector
+        .read_at(&mut bytes, request.offset)
+        .map_err(|error| ReadingError::FailedToReadChunk {
+            chunk_location: (request.first_chunk_offset - sector_contents_map_size)
+                / Scalar::FULL_BYTES as u64,
+            error,
+        })?;
+
+    Ok(bytes)
+}
+
+/// Decode `record_chunk` read from `chunk_location` if necessary and store it in
+/// `maybe_record_chunk`
+fn decode_record_chunk<PosTable>(
+    pos_table: &PosTable,
+    maybe_record_chunk: &mut Option<Scalar>,
+    mut record_chunk: [u8; Scalar::FULL_BYTES],
+    chunk_location: u64,
+    encoded_chunk_used: bool,
+    s_bucket: SBucket,
+) -> Result<(), ReadingError>
+where
+    PosTable: Table,
+{
+    // Decode chunk if necessary
+    if encoded_chunk_used {
+        let proof = pos_table
+            .find_proof(s_bucket.into())
+            .expect("encoded_chunk_used implies proof exists for this chunk; qed");
+
+        record_chunk = Simd::to_array(Simd::from(record_chunk) ^ Simd::from(proof.hash()));
+    }
+
+    maybe_record_chunk.replace(Scalar::try_from(record_chunk).map_err(|error| {
+        ReadingError::InvalidChunk {
+            s_bucket,
+            encoded_chunk_used,
+            chunk_location,
+            error,
+        }
+    })?);
+
+    Ok(())
+}
+
 /// Read sector record chunks, only plotted s-buckets are returned (in decoded form).
 ///
 /// NOTE: This is an async function, but it also does CPU-intensive operation internally, while it
@@ -151,74 +211,133 @@ where
         .collect::<Vec<_>>();
 
     let sector_contents_map_size = SectorContentsMap::encoded_size(pieces_in_sector) as u64;
-    let sector_bytes = match mode {
-        ReadSectorRecordChunksMode::ConcurrentChunks => None,
-        ReadSectorRecordChunksMode::WholeSector => {
-            Some(vec![0u8; crate::sector::sector_size(pieces_in_sector)])
-        }
-    };
     match sector {
-        ReadAt::Sync(sector) => {
-            let sector_bytes = {
-                if let Some(mut sector_bytes) = sector_bytes {
-                    sector.read_at(&mut sector_bytes, 0)?;
-                    Some(sector_bytes)
-                } else {
-                    None
+        ReadAt::Sync(sector) => match (mode, sector.read_alignment()) {
+            (ReadSectorRecordChunksMode::ConcurrentChunks, Some(alignment)) => {
+                let read_plan = ReadPlan::new(
+                    read_chunks_inputs
+                        .iter()
+                        .flatten()
+                        .map(|&(_, chunk_location, _, _)| {
+                            sector_contents_map_size + chunk_location * Scalar::FULL_BYTES as u64
+                        }),
+                    Scalar::FULL_BYTES,
+                    crate::sector::sector_size(pieces_in_sector) as u64,
+                    Some(alignment),
+                );
+
+                let mut chunks_by_request = read_plan
+                    .requests()
+                    .iter()
+                    .map(|_request| Vec::new())
+                    .collect::<Vec<_>>();
+                for (maybe_record_chunk, chunk_location, encoded_chunk_used, s_bucket) in
+                    read_chunks_inputs.into_iter().flatten()
+                {
+                    let (request_index, chunk_range) = read_plan
+                        .locate(
+                            sector_contents_map_size + chunk_location * Scalar::FULL_BYTES as u64,
+                        )
+                        .expect("Plan was created for all chunks that are read; qed");
+                    chunks_by_request[request_index].push((
+                        maybe_record_chunk,
+                        chunk_location,
+                        encoded_chunk_used,
+                        s_bucket,
+                        chunk_range,
+                    ));
                 }
-            };
-            read_chunks_inputs.into_par_iter().flatten().try_for_each(
-                |(maybe_record_chunk, chunk_location, encoded_chunk_used, s_bucket)| {
-                    let mut record_chunk = [0; Scalar::FULL_BYTES];
-                    if let Some(sector_bytes) = &sector_bytes {
-                        record_chunk.copy_from_slice(
-                            &sector_bytes[sector_contents_map_size as usize
-                                + chunk_location as usize * Scalar::FULL_BYTES..]
-                                [..Scalar::FULL_BYTES],
-                        );
-                    } else {
-                        sector
-                            .read_at(
-                                &mut record_chunk,
-                                sector_contents_map_size
-                                    + chunk_location * Scalar::FULL_BYTES as u64,
-                            )
-                            .map_err(|error| ReadingError::FailedToReadChunk {
-                                chunk_location,
-                                error,
-                            })?;
-                    }
 
-                    // Decode chunk if necessary
-                    if encoded_chunk_used {
-                        let proof = pos_table
-                            .find_proof(s_bucket.into())
-                            .expect("encoded_chunk_used implies proof exists for this chunk; qed");
+                // Chunks are decoded right after each request is read, such that only buffers of
+                // requests that are being processed by the thread pool are in memory at once
+                read_plan
+                    .requests()
+                    .par_iter()
+                    .zip(chunks_by_request)
+                    .try_for_each(|(request, chunks)| {
+                        let bytes =
+                            read_planned_request(sector, request, sector_contents_map_size)?;
+
+                        for (
+                            maybe_record_chunk,
+                            chunk_location,
+                            encoded_chunk_used,
+                            s_bucket,
+                            chunk_range,
+                        ) in chunks
+                        {
+                            let mut record_chunk = [0; Scalar::FULL_BYTES];
+                            record_chunk.copy_from_slice(&bytes[chunk_range]);
+
+                            decode_record_chunk(
+                                pos_table,
+                                maybe_record_chunk,
+                                record_chunk,
+                                chunk_location,
+                                encoded_chunk_used,
+                                s_bucket,
+                            )?;
+                        }
 
-                        record_chunk =
-                            Simd::to_array(Simd::from(record_chunk) ^ Simd::from(proof.hash()));
+                        Ok::<_, ReadingError>(())
+                    })?;
+            }
+            _ => {
+                let sector_bytes = match mode {
+                    ReadSectorRecordChunksMode::ConcurrentChunks => None,
+                    ReadSectorRecordChunksMode::WholeSector => {
+                        // Whole sector is overwritten by the read, so storage that can read into
+                        // uninitialized memory doesn't need to zero it first
+                        let mut sector_bytes = Box::<[u8]>::new_uninit_slice(
+                            crate::sector::sector_size(pieces_in_sector),
+                        );
+                        sector.read_at_uninit(&mut sector_bytes, 0)?;
+                        // SAFETY: Successful read initialized the whole buffer
+                        Some(unsafe { sector_bytes.assume_init() })
                     }
+                };
+                read_chunks_inputs.into_par_iter().flatten().try_for_each(
+                    |(maybe_record_chunk, chunk_location, encoded_chunk_used, s_bucket)| {
+                        let mut record_chunk = [0; Scalar::FULL_BYTES];
+                        if let Some(sector_bytes) = &sector_bytes {
+                            record_chunk.copy_from_slice(
+                                &sector_bytes[sector_contents_map_size as usize
+                                    + chunk_location as usize * Scalar::FULL_BYTES..]
+                                    [..Scalar::FULL_BYTES],
+                            );
+                        } else {
+                            sector
+                                .read_at(
+                                    &mut record_chunk,
+                                    sector_contents_map_size
+                                        + chunk_location * Scalar::FULL_BYTES as u64,
+                                )
+                                .map_err(|error| ReadingError::FailedToReadChunk {
+                                    chunk_location,
+                                    error,
+                                })?;
+                        }
 
-                    maybe_record_chunk.replace(Scalar::try_from(record_chunk).map_err(
-                        |error| ReadingError::InvalidChunk {
-                            s_bucket,
-                            encoded_chunk_used,
+                        decode_record_chunk(
+                            pos_table,
+                            maybe_record_chunk,
+                            record_chunk,
                             chunk_location,
-                            error,
-                        },
-                    )?);
-
-                    Ok::<_, ReadingError>(())
-                },
-            )?;
-        }
+                            encoded_chunk_used,
+                            s_bucket,
+                        )
+                    },
+                )?;
+            }
+        },
         ReadAt::Async(sector) => {
-            let sector_bytes = &{
-                if let Some(sector_bytes) = sector_bytes {
-                    Some(sector.read_at(sector_bytes, 0).await?)
-                } else {
-                    None
-                }
+            let sector_bytes = &match mode {
+                ReadSectorRecordChunksMode::ConcurrentChunks => None,
+                ReadSectorRecordChunksMode::WholeSector => Some(
+                    sector
+                        .read_at(vec![0u8; crate::sector::sector_size(pieces_in_sector)], 0)
+                        .await?,
+                ),
             };
             let processing_chunks = read_chunks_inputs
                 .into_iter()
@@ -247,28 +366,14 @@ where
                             );
                         }
 
-
-                        // Decode chunk if necessary
-                        if encoded_chunk_used {
-                            let proof = pos_table.find_proof(s_bucket.into()).expect(
-                                "encoded_chunk_used implies proof exists for this chunk; qed",
-                            );
-
-                            record_chunk = Simd::to_array(
-                                Simd::from(record_chunk) ^ Simd::from(proof.hash()),
-                            );
-                        }
-
-                        maybe_record_chunk.replace(Scalar::try_from(record_chunk).map_err(
-                            |error| ReadingError::InvalidChunk {
-                                s_bucket,
-                                encoded_chunk_used,
-                                chunk_location,
-                                error,
-                            },
-                        )?);
-
-                        Ok::<_, ReadingError>(())
+                        decode_record_chunk(
+                            pos_table,
+                            maybe_record_chunk,
+                            record_chunk,
+                            chunk_location,
+                            encoded_chunk_used,
+                            s_bucket,
+                        )
                     },
                 )
                 .collect::<FuturesUnordered<_>>()
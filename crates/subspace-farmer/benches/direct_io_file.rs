@@ -0,0 +1,205 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use rand::prelude::*;
+use std::{env, fs};
+use subspace_farmer::single_disk_farm::direct_io_file::{
+    DirectIoFile, DirectIoOptions, DISK_SECTOR_SIZE,
+};
+use subspace_farmer_components::file_ext::{AccessPattern, FileExt};
+use subspace_farmer_components::ReadAtSync;
+
+/// Size of a single read
+const READ_SIZE: usize = 1024 * 1024;
+/// Number of reads (at different offsets) done in each iteration
+const READS_COUNT: usize = 16;
+/// Size of a single region read with [`ReadAtSync::read_regions()`], same as record chunk size
+const REGION_SIZE: u32 = 32;
+/// Number of regions read with [`ReadAtSync::read_regions()`] in each iteration
+const REGIONS_COUNT: usize = 64;
+/// Size of a single large read, the whole file
+const LARGE_READ_SIZE: usize = READ_SIZE * READS_COUNT;
+/// Size of a single small read, same as record chunk size
+const SMALL_READ_SIZE: usize = 32;
+/// Number of small reads (at random offsets within sectors) done in each iteration
+const SMALL_READS_COUNT: usize = 1024;
+
+pub fn criterion_benchmark(c: &mut Criterion) {
+    println!("Initializing...");
+    let base_path = env::var("BASE_PATH")
+        .map(|base_path| base_path.parse().unwrap())
+        .unwrap_or_else(|_error| env::temp_dir());
+
+    let file_path = base_path.join("subspace_bench_direct_io_file.bin");
+    let mut data = vec![0u8; READ_SIZE * READS_COUNT];
+    thread_rng().fill(data.as_mut_slice());
+    fs::write(&file_path, &data).unwrap();
+
+    let file = DirectIoFile::open(&file_path).unwrap();
+    // Offsets below are sequential, but these benchmarks measure individual reads
+    file.set_access_pattern(AccessPattern::Random).unwrap();
+
+    // Memory for reads, with enough space to place buffer at both aligned and unaligned address
+    let mut memory = vec![0u8; READ_SIZE + DISK_SECTOR_SIZE * 2];
+    let aligned_start = memory.as_ptr().align_offset(DISK_SECTOR_SIZE);
+
+    let mut group = c.benchmark_group("direct-io-file");
+    group.throughput(Throughput::Bytes((READ_SIZE * READS_COUNT) as u64));
+    group.bench_function("read/aligned", |b| {
+        let buffer = &mut memory[aligned_start..][..READ_SIZE];
+        b.iter(|| {
+            for offset in (0..READS_COUNT).map(|index| (index * READ_SIZE) as u64) {
+                file.read_exact_at(black_box(&mut *buffer), black_box(offset))
+                    .unwrap();
+            }
+        });
+    });
+    group.bench_function("read/unaligned-buffer", |b| {
+        // Offset and size are aligned, but the buffer itself is not, hence scratch buffer is used
+        let buffer = &mut memory[aligned_start + 1..][..READ_SIZE];
+        b.iter(|| {
+            for offset in (0..READS_COUNT).map(|index| (index * READ_SIZE) as u64) {
+                file.read_exact_at(black_box(&mut *buffer), black_box(offset))
+                    .unwrap();
+            }
+        });
+    });
+    group.bench_function("read/sequential-read-ahead", |b| {
+        let buffer = &mut memory[aligned_start..][..READ_SIZE];
+        b.iter(|| {
+            file.set_access_pattern(AccessPattern::Sequential).unwrap();
+            for offset in (0..READS_COUNT).map(|index| (index * READ_SIZE) as u64) {
+                file.read_exact_at(black_box(&mut *buffer), black_box(offset))
+                    .unwrap();
+            }
+            file.set_access_pattern(AccessPattern::Random).unwrap();
+        });
+    });
+    group.finish();
+
+    let covering_reads_file = DirectIoFile::open_with_options(
+        &file_path,
+        DirectIoOptions {
+            covering_read_threshold: usize::MAX,
+            ..DirectIoOptions::default()
+        },
+    )
+    .unwrap();
+    let per_region_reads_file = DirectIoFile::open_with_options(
+        &file_path,
+        DirectIoOptions {
+            covering_read_threshold: 0,
+            ..DirectIoOptions::default()
+        },
+    )
+    .unwrap();
+
+    let scratch_buffer_reads_file = DirectIoFile::open_with_options(
+        &file_path,
+        DirectIoOptions {
+            large_read_threshold: Some(usize::MAX),
+            ..DirectIoOptions::default()
+        },
+    )
+    .unwrap();
+    let one_shot_buffer_reads_file = DirectIoFile::open_with_options(
+        &file_path,
+        DirectIoOptions {
+            large_read_threshold: Some(0),
+            max_large_read_size: LARGE_READ_SIZE,
+            ..DirectIoOptions::default()
+        },
+    )
+    .unwrap();
+
+    let mut group = c.benchmark_group("direct-io-file-large-read");
+    group.throughput(Throughput::Bytes(LARGE_READ_SIZE as u64));
+    // Buffer is not aligned, hence it can't be read into directly
+    let mut large_read_memory = vec![0u8; LARGE_READ_SIZE + DISK_SECTOR_SIZE * 2];
+    let large_read_start = large_read_memory.as_ptr().align_offset(DISK_SECTOR_SIZE) + 1;
+    let large_read_buffer = &mut large_read_memory[large_read_start..][..LARGE_READ_SIZE];
+    for (name, file) in [
+        ("scratch-buffer", &scratch_buffer_reads_file),
+        ("one-shot-buffer", &one_shot_buffer_reads_file),
+    ] {
+        file.set_access_pattern(AccessPattern::Random).unwrap();
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                file.read_exact_at(black_box(&mut *large_read_buffer), black_box(0))
+                    .unwrap();
+            });
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("direct-io-file-small-read");
+    group.throughput(Throughput::Elements(SMALL_READS_COUNT as u64));
+    // Reads never cross sector boundary, such that all of them are served by the fast path
+    let small_read_offsets = (0..SMALL_READS_COUNT)
+        .map(|_| {
+            let sector_offset = thread_rng().gen_range(0..LARGE_READ_SIZE / DISK_SECTOR_SIZE);
+            let offset_in_sector = thread_rng().gen_range(0..DISK_SECTOR_SIZE / SMALL_READ_SIZE);
+            (sector_offset * DISK_SECTOR_SIZE + offset_in_sector * SMALL_READ_SIZE) as u64
+        })
+        .collect::<Vec<_>>();
+    let mut small_read_buffer = [0u8; SMALL_READ_SIZE];
+    group.bench_function("general-path", |b| {
+        b.iter(|| {
+            for &offset in &small_read_offsets {
+                file.read_exact_at(black_box(&mut small_read_buffer), black_box(offset))
+                    .unwrap();
+            }
+        });
+    });
+    group.bench_function("fast-path", |b| {
+        b.iter(|| {
+            for &offset in &small_read_offsets {
+                file.read_small_at(black_box(&mut small_read_buffer), black_box(offset))
+                    .unwrap();
+            }
+        });
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("direct-io-file-read-regions");
+    group.throughput(Throughput::Bytes(REGION_SIZE as u64 * REGIONS_COUNT as u64));
+    let mut out = vec![0u8; REGION_SIZE as usize * REGIONS_COUNT];
+    // Regions spread over the span of the given size
+    for span in [
+        DISK_SECTOR_SIZE,
+        64 * 1024,
+        READ_SIZE,
+        READ_SIZE * READS_COUNT,
+    ] {
+        let mut regions = (0..REGIONS_COUNT)
+            .map(|_| {
+                (
+                    thread_rng().gen_range(0..(span as u32 - REGION_SIZE)),
+                    REGION_SIZE,
+                )
+            })
+            .collect::<Vec<_>>();
+        regions.sort_unstable();
+
+        for (name, file) in [
+            ("covering", &covering_reads_file),
+            ("per-region", &per_region_reads_file),
+        ] {
+            group.bench_function(format!("{name}/span-{span}"), |b| {
+                b.iter(|| {
+                    file.read_regions(black_box(0), black_box(&regions), black_box(&mut out))
+                        .unwrap();
+                });
+            });
+        }
+    }
+    group.finish();
+
+    drop(file);
+    drop(covering_reads_file);
+    drop(per_region_reads_file);
+    drop(scratch_buffer_reads_file);
+    drop(one_shot_buffer_reads_file);
+    fs::remove_file(file_path).unwrap();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
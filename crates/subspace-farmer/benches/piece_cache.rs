@@ -0,0 +1,79 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use rand::prelude::*;
+use std::{env, fs, mem};
+use subspace_core_primitives::{Blake3Hash, Piece, PieceIndex};
+use subspace_farmer::single_disk_farm::direct_io_file::{DirectIoFile, DirectIoOptions};
+use subspace_farmer::single_disk_farm::piece_cache::DiskPieceCache;
+use subspace_farmer_components::file_ext::FileExt;
+
+/// Size of cache element, same as in piece cache file
+const ELEMENT_SIZE: usize = PieceIndex::SIZE + Piece::SIZE + mem::size_of::<Blake3Hash>();
+/// Number of elements written or read in each iteration
+const ELEMENTS_COUNT: usize = 16;
+
+pub fn criterion_benchmark(c: &mut Criterion) {
+    println!("Initializing...");
+    let base_path = env::var("BASE_PATH")
+        .map(|base_path| base_path.parse().unwrap())
+        .unwrap_or_else(|_error| env::temp_dir());
+
+    let file_path = base_path.join("subspace_bench_piece_cache.bin");
+    let mut data = vec![0u8; ELEMENT_SIZE * ELEMENTS_COUNT];
+    thread_rng().fill(data.as_mut_slice());
+    fs::write(&file_path, &data).unwrap();
+
+    // Cache file with options tuned for the cache compared to options used for the plot
+    let files = [
+        (
+            "cache-options",
+            DirectIoFile::open_with_options(
+                &file_path,
+                DiskPieceCache::direct_io_options(DirectIoOptions::default()),
+            )
+            .unwrap(),
+        ),
+        (
+            "default-options",
+            DirectIoFile::open_with_options(&file_path, DirectIoOptions::default()).unwrap(),
+        ),
+    ];
+
+    let mut element = vec![0u8; ELEMENT_SIZE];
+    thread_rng().fill(element.as_mut_slice());
+    let mut random_order = (0..ELEMENTS_COUNT).collect::<Vec<_>>();
+    random_order.shuffle(&mut thread_rng());
+
+    let mut group = c.benchmark_group("piece-cache");
+    group.throughput(Throughput::Bytes((ELEMENT_SIZE * ELEMENTS_COUNT) as u64));
+    for (name, file) in &files {
+        group.bench_function(format!("write/slots-in-order/{name}"), |b| {
+            b.iter(|| {
+                for index in 0..ELEMENTS_COUNT {
+                    file.write_all_at(
+                        black_box(&element),
+                        black_box((index * ELEMENT_SIZE) as u64),
+                    )
+                    .unwrap();
+                }
+            });
+        });
+        group.bench_function(format!("read/random-slots/{name}"), |b| {
+            b.iter(|| {
+                for &index in &random_order {
+                    file.read_exact_at(
+                        black_box(&mut element),
+                        black_box((index * ELEMENT_SIZE) as u64),
+                    )
+                    .unwrap();
+                }
+            });
+        });
+    }
+    group.finish();
+
+    drop(files);
+    fs::remove_file(file_path).unwrap();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -3,18 +3,20 @@ use anyhow::anyhow;
 use clap::Subcommand;
 use criterion::{black_box, BatchSize, Criterion, Throughput};
 use parking_lot::Mutex;
+use std::fs;
 use std::fs::OpenOptions;
 use std::num::NonZeroUsize;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use subspace_core_primitives::crypto::kzg::{embedded_kzg_settings, Kzg};
-use subspace_core_primitives::{Record, SolutionRange};
+use subspace_core_primitives::{Record, SectorIndex, SolutionRange};
 use subspace_erasure_coding::ErasureCoding;
+use subspace_farmer::single_disk_farm::direct_io_file::DirectIoFile;
 use subspace_farmer::single_disk_farm::farming::rayon_files::RayonFiles;
 use subspace_farmer::single_disk_farm::farming::{PlotAudit, PlotAuditOptions};
-use subspace_farmer::single_disk_farm::unbuffered_io_file_windows::UnbufferedIoFileWindows;
 use subspace_farmer::single_disk_farm::{SingleDiskFarm, SingleDiskFarmSummary};
 use subspace_farmer_components::reading::ReadSectorRecordChunksMode;
 use subspace_farmer_components::sector::sector_size;
+use subspace_farmer_components::ReadAtSync;
 use subspace_proof_of_space::Table;
 use subspace_rpc_primitives::SlotInfo;
 
@@ -116,8 +118,18 @@ fn audit(
     .map_err(|error| anyhow::anyhow!(error))?;
     let table_generator = Mutex::new(PosTable::generator());
 
+    // Farm must not be modified by farmer while it is benchmarked
+    let _farm_lock = SingleDiskFarm::try_lock_shared(&disk_farm)
+        .map_err(|error| anyhow::anyhow!("Failed to lock farm: {error}"))?;
     let sectors_metadata = SingleDiskFarm::read_all_sectors_metadata(&disk_farm)
         .map_err(|error| anyhow::anyhow!("Failed to read sectors metadata: {error}"))?;
+    let plot_data_offset = SingleDiskFarm::read_plot_data_offset(&disk_farm)
+        .map_err(|error| anyhow::anyhow!("Failed to read plot data offset: {error}"))?;
+    let sector_count = plot_sector_count(
+        &disk_farm,
+        single_disk_farm_info.pieces_in_sector(),
+        plot_data_offset,
+    )?;
 
     let mut criterion = Criterion::default().sample_size(sample_size);
     if let Some(filter) = filter {
@@ -133,7 +145,7 @@ fn audit(
                 .read(true)
                 .open(disk_farm.join(SingleDiskFarm::PLOT_FILE))
                 .map_err(|error| anyhow::anyhow!("Failed to open plot: {error}"))?;
-            let plot_audit = PlotAudit::new(&plot);
+            let plot_audit = PlotAudit::new(plot.offset(plot_data_offset));
 
             group.bench_function("plot/single", |b| {
                 b.iter_batched(
@@ -150,6 +162,7 @@ fn audit(
                                 // No solution will be found, pure audit
                                 voting_solution_range: SolutionRange::MIN,
                             },
+                            sector_count,
                             sectors_metadata: &sectors_metadata,
                             kzg: &kzg,
                             erasure_coding: &erasure_coding,
@@ -165,13 +178,13 @@ fn audit(
                 )
             });
         }
-        if cfg!(windows) {
+        {
             let plot = RayonFiles::open_with(
                 &disk_farm.join(SingleDiskFarm::PLOT_FILE),
-                UnbufferedIoFileWindows::open,
+                DirectIoFile::open,
             )
             .map_err(|error| anyhow::anyhow!("Failed to open plot: {error}"))?;
-            let plot_audit = PlotAudit::new(&plot);
+            let plot_audit = PlotAudit::new(plot.offset(plot_data_offset));
 
             group.bench_function("plot/rayon/unbuffered", |b| {
                 b.iter_batched(
@@ -188,6 +201,7 @@ fn audit(
                                 // No solution will be found, pure audit
                                 voting_solution_range: SolutionRange::MIN,
                             },
+                            sector_count,
                             sectors_metadata: &sectors_metadata,
                             kzg: &kzg,
                             erasure_coding: &erasure_coding,
@@ -206,7 +220,7 @@ fn audit(
         {
             let plot = RayonFiles::open(&disk_farm.join(SingleDiskFarm::PLOT_FILE))
                 .map_err(|error| anyhow::anyhow!("Failed to open plot: {error}"))?;
-            let plot_audit = PlotAudit::new(&plot);
+            let plot_audit = PlotAudit::new(plot.offset(plot_data_offset));
 
             group.bench_function("plot/rayon/regular", |b| {
                 b.iter_batched(
@@ -223,6 +237,7 @@ fn audit(
                                 // No solution will be found, pure audit
                                 voting_solution_range: SolutionRange::MIN,
                             },
+                            sector_count,
                             sectors_metadata: &sectors_metadata,
                             kzg: &kzg,
                             erasure_coding: &erasure_coding,
@@ -278,8 +293,18 @@ fn prove(
     .map_err(|error| anyhow::anyhow!(error))?;
     let table_generator = Mutex::new(PosTable::generator());
 
+    // Farm must not be modified by farmer while it is benchmarked
+    let _farm_lock = SingleDiskFarm::try_lock_shared(&disk_farm)
+        .map_err(|error| anyhow::anyhow!("Failed to lock farm: {error}"))?;
     let mut sectors_metadata = SingleDiskFarm::read_all_sectors_metadata(&disk_farm)
         .map_err(|error| anyhow::anyhow!("Failed to read sectors metadata: {error}"))?;
+    let plot_data_offset = SingleDiskFarm::read_plot_data_offset(&disk_farm)
+        .map_err(|error| anyhow::anyhow!("Failed to read plot data offset: {error}"))?;
+    let sector_count = plot_sector_count(
+        &disk_farm,
+        single_disk_farm_info.pieces_in_sector(),
+        plot_data_offset,
+    )?;
     if let Some(limit_sector_count) = limit_sector_count {
         sectors_metadata.truncate(limit_sector_count);
     };
@@ -295,7 +320,7 @@ fn prove(
                 .read(true)
                 .open(disk_farm.join(SingleDiskFarm::PLOT_FILE))
                 .map_err(|error| anyhow::anyhow!("Failed to open plot: {error}"))?;
-            let plot_audit = PlotAudit::new(&plot);
+            let plot_audit = PlotAudit::new(plot.offset(plot_data_offset));
             let mut options = PlotAuditOptions::<PosTable> {
                 public_key: single_disk_farm_info.public_key(),
                 reward_address: single_disk_farm_info.public_key(),
@@ -307,6 +332,7 @@ fn prove(
                     // Solution is guaranteed to be found
                     voting_solution_range: SolutionRange::MAX,
                 },
+                sector_count,
                 sectors_metadata: &sectors_metadata,
                 kzg: &kzg,
                 erasure_coding: &erasure_coding,
@@ -362,13 +388,13 @@ fn prove(
                 )
             });
         }
-        if cfg!(windows) {
+        {
             let plot = RayonFiles::open_with(
                 &disk_farm.join(SingleDiskFarm::PLOT_FILE),
-                UnbufferedIoFileWindows::open,
+                DirectIoFile::open,
             )
             .map_err(|error| anyhow::anyhow!("Failed to open plot: {error}"))?;
-            let plot_audit = PlotAudit::new(&plot);
+            let plot_audit = PlotAudit::new(plot.offset(plot_data_offset));
             let mut options = PlotAuditOptions::<PosTable> {
                 public_key: single_disk_farm_info.public_key(),
                 reward_address: single_disk_farm_info.public_key(),
@@ -380,6 +406,7 @@ fn prove(
                     // Solution is guaranteed to be found
                     voting_solution_range: SolutionRange::MAX,
                 },
+                sector_count,
                 sectors_metadata: &sectors_metadata,
                 kzg: &kzg,
                 erasure_coding: &erasure_coding,
@@ -438,7 +465,7 @@ fn prove(
         {
             let plot = RayonFiles::open(&disk_farm.join(SingleDiskFarm::PLOT_FILE))
                 .map_err(|error| anyhow::anyhow!("Failed to open plot: {error}"))?;
-            let plot_audit = PlotAudit::new(&plot);
+            let plot_audit = PlotAudit::new(plot.offset(plot_data_offset));
             let mut options = PlotAuditOptions::<PosTable> {
                 public_key: single_disk_farm_info.public_key(),
                 reward_address: single_disk_farm_info.public_key(),
@@ -450,6 +477,7 @@ fn prove(
                     // Solution is guaranteed to be found
                     voting_solution_range: SolutionRange::MAX,
                 },
+                sector_count,
                 sectors_metadata: &sectors_metadata,
                 kzg: &kzg,
                 erasure_coding: &erasure_coding,
@@ -511,3 +539,19 @@ fn prove(
 
     Ok(())
 }
+
+/// Number of sectors plot file of the farm has space for after `plot_data_offset`
+fn plot_sector_count(
+    disk_farm: &Path,
+    pieces_in_sector: u16,
+    plot_data_offset: u64,
+) -> anyhow::Result<SectorIndex> {
+    let plot_file_size = fs::metadata(disk_farm.join(SingleDiskFarm::PLOT_FILE))
+        .map_err(|error| anyhow::anyhow!("Failed to read plot file size: {error}"))?
+        .len();
+
+    Ok(
+        (plot_file_size.saturating_sub(plot_data_offset) / sector_size(pieces_in_sector) as u64)
+            as SectorIndex,
+    )
+}
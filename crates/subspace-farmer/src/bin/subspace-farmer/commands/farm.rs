@@ -15,7 +15,7 @@ use futures::{FutureExt, StreamExt, TryStreamExt};
 use parking_lot::Mutex;
 use prometheus_client::registry::Registry;
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
-use std::num::{NonZeroU8, NonZeroUsize};
+use std::num::{NonZeroU32, NonZeroU64, NonZeroU8, NonZeroUsize};
 use std::path::PathBuf;
 use std::pin::pin;
 use std::str::FromStr;
@@ -29,8 +29,15 @@ use subspace_farmer::farm::{
     Farm, FarmingNotification, SectorExpirationDetails, SectorPlottingDetails, SectorUpdate,
 };
 use subspace_farmer::farmer_cache::FarmerCache;
+use subspace_farmer::single_disk_farm::direct_io_file::memory_budget::IoMemoryBudget;
+use subspace_farmer::single_disk_farm::direct_io_file::metrics::DiskMetrics;
+use subspace_farmer::single_disk_farm::direct_io_file::DirectIoOptions;
+use subspace_farmer::single_disk_farm::preallocation::{
+    PreallocationProgress, DEFAULT_PREALLOCATION_CHUNK_SIZE,
+};
+use subspace_farmer::single_disk_farm::read_self_test::{ReadSelfTestAction, ReadSelfTestOptions};
 use subspace_farmer::single_disk_farm::{
-    SingleDiskFarm, SingleDiskFarmError, SingleDiskFarmOptions,
+    Durability, SingleDiskFarm, SingleDiskFarmError, SingleDiskFarmOptions,
 };
 use subspace_farmer::utils::farmer_piece_getter::{DsnCacheRetryPolicy, FarmerPieceGetter};
 use subspace_farmer::utils::piece_validator::SegmentCommitmentPieceValidator;
@@ -117,6 +124,57 @@ impl From<PlottingThreadPriority> for Option<ThreadPriority> {
     }
 }
 
+/// Durability of plotted sectors
+#[derive(Debug, Copy, Clone)]
+enum PlotDurability {
+    /// Sync after every plotted sector
+    Always,
+    /// Sync after every `n` plotted sectors
+    Periodic(NonZeroU32),
+    /// Never sync explicitly
+    Never,
+}
+
+impl FromStr for PlotDurability {
+    type Err = String;
+
+    fn from_str(s: &str) -> anyhow::Result<Self, Self::Err> {
+        match s {
+            "always" => Ok(Self::Always),
+            "never" => Ok(Self::Never),
+            s => s
+                .parse::<NonZeroU32>()
+                .map(Self::Periodic)
+                .map_err(|error| {
+                    format!(
+                    "Durability {s} is not valid, must be `always`, `never` or non-zero number of \
+                    sectors: {error}"
+                )
+                }),
+        }
+    }
+}
+
+impl fmt::Display for PlotDurability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Always => f.write_str("always"),
+            Self::Periodic(n) => write!(f, "{n}"),
+            Self::Never => f.write_str("never"),
+        }
+    }
+}
+
+impl From<PlotDurability> for Durability {
+    fn from(value: PlotDurability) -> Self {
+        match value {
+            PlotDurability::Always => Durability::Always,
+            PlotDurability::Periodic(n) => Durability::Periodic(n),
+            PlotDurability::Never => Durability::Never,
+        }
+    }
+}
+
 /// Arguments for farmer
 #[derive(Debug, Parser)]
 pub(crate) struct FarmingArgs {
@@ -133,6 +191,45 @@ pub(crate) struct FarmingArgs {
     /// `size` is max allocated size in human readable format (e.g. 10GB, 2TiB) or just bytes that
     /// farmer will make sure not not exceed (and will pre-allocated all the space on startup to
     /// ensure it will not run out of space in runtime).
+    ///
+    /// Optional `max-request-size` is max size of a single disk read or write request in human
+    /// readable format (e.g. 256KiB, 4MiB), defaults to 256KiB for rotational disks, 512KiB for
+    /// USB-connected disks and 1MiB otherwise. Larger values can improve throughput on NVMe
+    /// drives, smaller values reduce memory usage.
+    ///
+    /// Optional `auto-tune-io=true` calibrates max request size with a short series of timed reads
+    /// when farm is opened for the first time and reuses the choice afterwards, ignored when
+    /// `max-request-size` is set.
+    ///
+    /// Optional `max-io-requests` is max number of concurrent disk requests of the farm, defaults
+    /// to 2 for rotational disks and queue depth of SSDs up to 128 (32 if queue depth is unknown, 4
+    /// if connected via USB, 16 if kind of the disk can't be detected). Lower values help when
+    /// many farms share one disk, higher values can improve NVMe performance.
+    ///
+    /// Optional `read-only=true` farms already plotted sectors of existing farm without writing
+    /// anything to disk, which allows farming from read-only mounts and write-protected snapshots,
+    /// plotting is not done in this mode and `size` is ignored.
+    ///
+    /// Optional `verify-writes=true` reads every plotted sector back right after writing and
+    /// compares it with what was written, which catches silent corruption on flaky USB/SATA
+    /// hardware at the cost of plotting throughput.
+    ///
+    /// Optional `background-io-rate` is max throughput of background disk requests (integrity
+    /// scrubbing and plot cache check on startup) per second in human readable format (e.g.
+    /// 50MiB), not limited by default. Background requests always yield to auditing and proving
+    /// reads regardless of this limit.
+    ///
+    /// Optional `self-test=warn` or `self-test=fail` reads randomly sampled regions of plotted
+    /// sectors on startup and either warns or fails farm startup when reads fail or are too slow,
+    /// which notices failing disks before rewards are missed.
+    ///
+    /// Optional `integrity-scrub-rate` enables background integrity scrub that verifies checksums
+    /// of plotted sectors at up to specified throughput per second in human readable format (e.g.
+    /// 10MiB) and replots corrupted sectors, disabled by default.
+    ///
+    /// Optional `force-version=true` opens farm even if version headers of its files are not
+    /// recognized or farm was created by a newer farmer and replaces headers with the version of
+    /// this farmer, meant for recovery of farms with damaged headers only.
     disk_farms: Vec<DiskFarm>,
     /// WebSocket RPC URL of the Subspace node to connect to
     #[arg(long, value_hint = ValueHint::Url, default_value = "ws://127.0.0.1:9944")]
@@ -260,11 +357,25 @@ pub(crate) struct FarmingArgs {
     /// Disable farm locking, for example if file system doesn't support it
     #[arg(long)]
     disable_farm_locking: bool,
+    /// Max memory used by disk I/O buffers of all farms together in human readable format (e.g.
+    /// 256MiB), defaults to 1/64 of system memory, but not less than 16MiB and not more than 1GiB.
+    ///
+    /// Once the limit is reached, disk requests are not failing, but share a single buffer and wait
+    /// for each other instead, which keeps memory usage bounded with many farms at the cost of
+    /// throughput.
+    #[arg(long)]
+    io_memory_budget: Option<ByteSize>,
     /// Exit on farm error.
     ///
     /// By default, farmer will continue running if the are still other working farms.
     #[arg(long)]
     exit_on_farm_error: bool,
+    /// How often plotted sectors are synced to disk: `always` (after every sector), `never` (rely
+    /// on OS to flush data eventually) or a number of sectors to sync after.
+    ///
+    /// Sectors plotted since the last sync may need to be plotted again after power failure.
+    #[arg(long, default_value_t = PlotDurability::Always)]
+    durability: PlotDurability,
 }
 
 fn cache_percentage_parser(s: &str) -> anyhow::Result<NonZeroU8> {
@@ -325,6 +436,24 @@ pub(crate) struct DiskFarm {
     directory: PathBuf,
     /// How much space in bytes can farm use for plots (metadata space is not included)
     allocated_plotting_space: u64,
+    /// Max size of a single disk read or write request
+    max_request_size: Option<usize>,
+    /// Calibrate max request size on first open
+    auto_tune_io: bool,
+    /// Max number of concurrent disk requests
+    max_io_requests: Option<NonZeroUsize>,
+    /// Farm already plotted sectors without writing anything to disk
+    read_only: bool,
+    /// Read plotted sectors back after writing and verify them
+    verify_writes: bool,
+    /// Max throughput of background disk requests in bytes per second
+    background_io_rate: Option<NonZeroU64>,
+    /// Read self-test on startup and what to do when it fails
+    read_self_test: Option<ReadSelfTestAction>,
+    /// Max throughput of background integrity scrub in bytes per second, disabled if `None`
+    integrity_scrub_rate: Option<NonZeroU64>,
+    /// Open farm even if its version headers are not recognized or not supported
+    force_version: bool,
 }
 
 impl FromStr for DiskFarm {
@@ -332,12 +461,21 @@ impl FromStr for DiskFarm {
 
     fn from_str(s: &str) -> anyhow::Result<Self, Self::Err> {
         let parts = s.split(',').collect::<Vec<_>>();
-        if parts.len() != 2 {
-            return Err("Must contain 2 coma-separated components".to_string());
+        if !(2..=11).contains(&parts.len()) {
+            return Err("Must contain 2 to 11 coma-separated components".to_string());
         }
 
         let mut plot_directory = None;
         let mut allocated_plotting_space = None;
+        let mut max_request_size = None;
+        let mut auto_tune_io = false;
+        let mut max_io_requests = None;
+        let mut read_only = false;
+        let mut verify_writes = false;
+        let mut background_io_rate = None;
+        let mut read_self_test = None;
+        let mut integrity_scrub_rate = None;
+        let mut force_version = false;
 
         for part in parts {
             let part = part.splitn(2, '=').collect::<Vec<_>>();
@@ -362,9 +500,80 @@ impl FromStr for DiskFarm {
                             .as_u64(),
                     );
                 }
+                "max-request-size" => {
+                    max_request_size.replace(
+                        value
+                            .parse::<ByteSize>()
+                            .map_err(|error| {
+                                format!("Failed to parse `max-request-size` \"{value}\": {error}")
+                            })?
+                            .as_u64() as usize,
+                    );
+                }
+                "auto-tune-io" => {
+                    auto_tune_io = value.parse::<bool>().map_err(|error| {
+                        format!("Failed to parse `auto-tune-io` \"{value}\": {error}")
+                    })?;
+                }
+                "max-io-requests" => {
+                    max_io_requests.replace(value.parse::<NonZeroUsize>().map_err(|error| {
+                        format!("Failed to parse `max-io-requests` \"{value}\": {error}")
+                    })?);
+                }
+                "read-only" => {
+                    read_only = value.parse::<bool>().map_err(|error| {
+                        format!("Failed to parse `read-only` \"{value}\": {error}")
+                    })?;
+                }
+                "verify-writes" => {
+                    verify_writes = value.parse::<bool>().map_err(|error| {
+                        format!("Failed to parse `verify-writes` \"{value}\": {error}")
+                    })?;
+                }
+                "background-io-rate" => {
+                    let bytes_per_second = value
+                        .parse::<ByteSize>()
+                        .map_err(|error| {
+                            format!("Failed to parse `background-io-rate` \"{value}\": {error}")
+                        })?
+                        .as_u64();
+                    background_io_rate.replace(NonZeroU64::new(bytes_per_second).ok_or_else(
+                        || format!("`background-io-rate` \"{value}\" must not be zero"),
+                    )?);
+                }
+                "self-test" => {
+                    read_self_test.replace(match value {
+                        "warn" => ReadSelfTestAction::Warn,
+                        "fail" => ReadSelfTestAction::Fail,
+                        value => {
+                            return Err(format!(
+                                "Failed to parse `self-test` \"{value}\": must be `warn` or `fail`"
+                            ));
+                        }
+                    });
+                }
+                "integrity-scrub-rate" => {
+                    let bytes_per_second = value
+                        .parse::<ByteSize>()
+                        .map_err(|error| {
+                            format!("Failed to parse `integrity-scrub-rate` \"{value}\": {error}")
+                        })?
+                        .as_u64();
+                    integrity_scrub_rate.replace(NonZeroU64::new(bytes_per_second).ok_or_else(
+                        || format!("`integrity-scrub-rate` \"{value}\" must not be zero"),
+                    )?);
+                }
+                "force-version" => {
+                    force_version = value.parse::<bool>().map_err(|error| {
+                        format!("Failed to parse `force-version` \"{value}\": {error}")
+                    })?;
+                }
                 key => {
                     return Err(format!(
-                        "Key \"{key}\" is not supported, only `path` or `size`"
+                        "Key \"{key}\" is not supported, only `path`, `size`, \
+                        `max-request-size`, `auto-tune-io`, `max-io-requests`, `read-only`, \
+                        `verify-writes`, `background-io-rate`, `self-test`, \
+                        `integrity-scrub-rate` or `force-version`"
                     ));
                 }
             }
@@ -377,6 +586,15 @@ impl FromStr for DiskFarm {
             allocated_plotting_space: allocated_plotting_space.ok_or({
                 "`size` key is required with path to directory where plots will be stored"
             })?,
+            max_request_size,
+            auto_tune_io,
+            max_io_requests,
+            read_only,
+            verify_writes,
+            background_io_rate,
+            read_self_test,
+            integrity_scrub_rate,
+            force_version,
         })
     }
 }
@@ -412,7 +630,9 @@ where
         plotting_thread_priority,
         plot_cache,
         disable_farm_locking,
+        io_memory_budget,
         exit_on_farm_error,
+        durability,
     } = farming_args;
 
     let plot_cache = plot_cache.unwrap_or_else(|| {
@@ -436,6 +656,15 @@ where
         disk_farms = vec![DiskFarm {
             directory: tmp_directory.as_ref().to_path_buf(),
             allocated_plotting_space: plot_size.as_u64(),
+            max_request_size: None,
+            auto_tune_io: false,
+            max_io_requests: None,
+            read_only: false,
+            verify_writes: false,
+            background_io_rate: None,
+            read_self_test: None,
+            integrity_scrub_rate: None,
+            force_version: false,
         }];
 
         Some(tmp_directory)
@@ -484,6 +713,19 @@ where
     let mut prometheus_metrics_registry = Registry::default();
     let farmer_metrics = FarmerMetrics::new(&mut prometheus_metrics_registry);
     let should_start_prometheus_server = !prometheus_listen_on.is_empty();
+    let disk_metrics =
+        should_start_prometheus_server.then(|| DiskMetrics::new(&mut prometheus_metrics_registry));
+    // All farms share the same memory budget for I/O
+    let io_memory_budget = IoMemoryBudget::new(
+        io_memory_budget
+            .map(|io_memory_budget| {
+                usize::try_from(io_memory_budget.as_u64()).unwrap_or(usize::MAX)
+            })
+            .unwrap_or_else(IoMemoryBudget::default_limit),
+    );
+    if should_start_prometheus_server {
+        io_memory_budget.register_metrics(&mut prometheus_metrics_registry);
+    }
 
     let (node, mut node_runner) = {
         if dsn.bootstrap_nodes.is_empty() {
@@ -678,6 +920,8 @@ where
                 let kzg = kzg.clone();
                 let erasure_coding = erasure_coding.clone();
                 let piece_getter = piece_getter.clone();
+                let disk_metrics = disk_metrics.clone();
+                let io_memory_budget = io_memory_budget.clone();
                 let downloading_semaphore = Arc::clone(&downloading_semaphore);
                 let plotting_thread_pool_manager = plotting_thread_pool_manager.clone();
                 let global_mutex = Arc::clone(&global_mutex);
@@ -700,6 +944,7 @@ where
                             directory: disk_farm.directory.clone(),
                             farmer_app_info,
                             allocated_space: disk_farm.allocated_plotting_space,
+                            plot_segments: Vec::new(),
                             max_pieces_in_sector,
                             node_client,
                             reward_address,
@@ -707,6 +952,7 @@ where
                             erasure_coding,
                             piece_getter,
                             cache_percentage,
+                            plot_cache,
                             downloading_semaphore,
                             record_encoding_concurrency,
                             farm_during_initial_plotting,
@@ -717,6 +963,39 @@ where
                             disable_farm_locking,
                             faster_read_sector_record_chunks_mode_barrier,
                             faster_read_sector_record_chunks_mode_concurrency,
+                            direct_io_options: DirectIoOptions {
+                                max_request_size: disk_farm.max_request_size,
+                                max_io_requests: disk_farm.max_io_requests,
+                                ..DirectIoOptions::default()
+                            },
+                            auto_tune_io: disk_farm.auto_tune_io,
+                            disk_metrics,
+                            durability: durability.into(),
+                            read_only: disk_farm.read_only,
+                            async_reads: false,
+                            metadata_write_through: true,
+                            verify_writes: disk_farm.verify_writes,
+                            integrity_scrub_rate: disk_farm.integrity_scrub_rate,
+                            background_io_rate: disk_farm.background_io_rate,
+                            io_memory_budget: Some(io_memory_budget),
+                            preallocation_chunk_size: DEFAULT_PREALLOCATION_CHUNK_SIZE,
+                            on_preallocation_progress: Some(Arc::new(
+                                |progress: &PreallocationProgress| {
+                                    info!(
+                                        "Preallocating plot file: {}/{} ({:.2}%)",
+                                        bytesize::to_string(progress.allocated, true),
+                                        bytesize::to_string(progress.total, true),
+                                        progress.allocated as f64 / progress.total as f64 * 100.0
+                                    );
+                                },
+                            )),
+                            read_self_test: disk_farm.read_self_test.map(|on_failure| {
+                                ReadSelfTestOptions {
+                                    on_failure,
+                                    ..ReadSelfTestOptions::default()
+                                }
+                            }),
+                            force_farm_version: disk_farm.force_version,
                         },
                         farm_index,
                     );
@@ -759,6 +1038,15 @@ where
                             bytesize::to_string(info.allocated_space(), false)
                         );
                         info!("  Directory: {}", disk_farm.directory.display());
+                        if farm.is_unbuffered() {
+                            info!("  I/O mode: direct");
+                        } else {
+                            info!("  I/O mode: buffered (higher memory usage)");
+                        }
+                        info!("  Device profile: {}", farm.device_profile());
+                        if let Some(report) = farm.read_self_test_report() {
+                            info!("  Read self-test: {report}");
+                        }
                     }
 
                     (farm_index, Ok(Box::new(farm) as Box<dyn Farm>))
@@ -809,11 +1097,24 @@ where
                 }
             })));
     }
+    // Read-only farms can't store pieces, so their caches are not used by farmer cache
+    let writable_farms = farms
+        .iter()
+        .zip(&disk_farms)
+        .filter(|(_farm, disk_farm)| !disk_farm.read_only)
+        .map(|(farm, _disk_farm)| farm)
+        .collect::<Vec<_>>();
     farmer_cache
         .replace_backing_caches(
-            farms.iter().map(|farm| farm.piece_cache()).collect(),
+            writable_farms
+                .iter()
+                .map(|farm| farm.piece_cache())
+                .collect(),
             if plot_cache {
-                farms.iter().map(|farm| farm.plot_cache()).collect()
+                writable_farms
+                    .iter()
+                    .map(|farm| farm.plot_cache())
+                    .collect()
             } else {
                 Vec::new()
             },
@@ -926,7 +1227,7 @@ where
                 let farm_id = *farm.id();
                 let farmer_metrics = farmer_metrics.clone();
 
-                move |(_sector_index, sector_state)| match sector_state {
+                move |(sector_index, sector_state)| match sector_state {
                     SectorUpdate::Plotting(SectorPlottingDetails::Starting { .. }) => {
                         farmer_metrics.sector_plotting.inc();
                     }
@@ -951,6 +1252,14 @@ where
                         farmer_metrics.observe_sector_writing_time(&farm_id, time);
                         farmer_metrics.sector_written.inc();
                     }
+                    SectorUpdate::Plotting(SectorPlottingDetails::DiskFull { required_space }) => {
+                        warn!(
+                            %farm_id,
+                            %sector_index,
+                            %required_space,
+                            "Disk is full, plotting is paused until enough space is available"
+                        );
+                    }
                     SectorUpdate::Plotting(SectorPlottingDetails::Finished {
                         plotted_sector,
                         old_plotted_sector,
@@ -970,6 +1279,13 @@ where
                     SectorUpdate::Expiration(SectorExpirationDetails::Determined { .. }) => {
                         // Not interested in here
                     }
+                    SectorUpdate::Corrupted(_corruption_details) => {
+                        warn!(
+                            %farm_id,
+                            %sector_index,
+                            "Sector is corrupted, it will be replotted unless farm is read-only"
+                        );
+                    }
                 }
             }))
             .detach();
@@ -48,6 +48,10 @@ enum Command {
         /// Check for errors, but do not attempt to correct them
         #[arg(long)]
         dry_run: bool,
+        /// Scrub farm even if it was created by incompatible farmer or its version header is not
+        /// recognized, version header is replaced with the version of this farmer afterwards
+        #[arg(long)]
+        force: bool,
     },
     /// Wipes the farm
     Wipe {
@@ -100,11 +104,12 @@ async fn main() -> anyhow::Result<()> {
             disk_farms,
             disable_farm_locking,
             dry_run,
+            force,
         } => {
             if disk_farms.is_empty() {
                 info!("No farm was specified, so there is nothing to do");
             } else {
-                commands::scrub(&disk_farms, disable_farm_locking, dry_run);
+                commands::scrub(&disk_farms, disable_farm_locking, dry_run, force);
             }
         }
         Command::Wipe { disk_farms } => {
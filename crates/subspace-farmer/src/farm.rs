@@ -9,7 +9,9 @@ use std::pin::Pin;
 use std::sync::Arc;
 use std::time::Duration;
 use std::{fmt, io};
-use subspace_core_primitives::{Piece, PieceIndex, PieceOffset, SectorIndex, SegmentIndex};
+use subspace_core_primitives::{
+    Blake3Hash, Piece, PieceIndex, PieceOffset, SectorIndex, SegmentIndex,
+};
 use subspace_farmer_components::auditing::AuditingError;
 use subspace_farmer_components::plotting::PlottedSector;
 use subspace_farmer_components::proving::ProvingError;
@@ -272,6 +274,12 @@ pub enum SectorPlottingDetails {
     Writing,
     /// Written sector
     Written(Duration),
+    /// Disk is full, writing of the sector is paused until enough space is available, after which
+    /// [`Self::Writing`] is sent again
+    DiskFull {
+        /// Free space in bytes necessary to resume writing
+        required_space: u64,
+    },
     /// Finished plotting
     Finished {
         /// Information about plotted sector
@@ -297,6 +305,15 @@ pub enum SectorExpirationDetails {
     Expired,
 }
 
+/// Details about corrupted sector
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Encode, Decode)]
+pub struct SectorCorruptionDetails {
+    /// Checksum of sector contents as read from disk
+    pub actual_checksum: Blake3Hash,
+    /// Checksum stored alongside sector when it was plotted
+    pub expected_checksum: Blake3Hash,
+}
+
 /// Various sector updates
 #[derive(Debug, Clone, Encode, Decode)]
 pub enum SectorUpdate {
@@ -304,6 +321,8 @@ pub enum SectorUpdate {
     Plotting(SectorPlottingDetails),
     /// Sector expiration information updated
     Expiration(SectorExpirationDetails),
+    /// Sector contents don't match its checksum and sector should be replotted
+    Corrupted(SectorCorruptionDetails),
 }
 
 /// Abstract piece reader implementation
@@ -1,5 +1,6 @@
 use crate::farmer_cache::FarmerCache;
 use crate::node_client::Error;
+use crate::single_disk_farm::direct_io_file::DirectIoOptions;
 use crate::single_disk_farm::piece_cache::DiskPieceCache;
 use crate::NodeClient;
 use async_trait::async_trait;
@@ -208,8 +209,26 @@ async fn basic() {
         farmer_cache
             .replace_backing_caches(
                 vec![
-                    Arc::new(DiskPieceCache::open(path1.as_ref(), 1).unwrap()),
-                    Arc::new(DiskPieceCache::open(path2.as_ref(), 1).unwrap()),
+                    Arc::new(
+                        DiskPieceCache::open(
+                            path1.as_ref(),
+                            1,
+                            DirectIoOptions::default(),
+                            None,
+                            None,
+                        )
+                        .unwrap(),
+                    ),
+                    Arc::new(
+                        DiskPieceCache::open(
+                            path2.as_ref(),
+                            1,
+                            DirectIoOptions::default(),
+                            None,
+                            None,
+                        )
+                        .unwrap(),
+                    ),
                 ],
                 vec![],
             )
@@ -408,8 +427,26 @@ async fn basic() {
         farmer_cache
             .replace_backing_caches(
                 vec![
-                    Arc::new(DiskPieceCache::open(path1.as_ref(), 1).unwrap()),
-                    Arc::new(DiskPieceCache::open(path2.as_ref(), 1).unwrap()),
+                    Arc::new(
+                        DiskPieceCache::open(
+                            path1.as_ref(),
+                            1,
+                            DirectIoOptions::default(),
+                            None,
+                            None,
+                        )
+                        .unwrap(),
+                    ),
+                    Arc::new(
+                        DiskPieceCache::open(
+                            path2.as_ref(),
+                            1,
+                            DirectIoOptions::default(),
+                            None,
+                            None,
+                        )
+                        .unwrap(),
+                    ),
                 ],
                 vec![],
             )
@@ -1,6 +1,8 @@
 use parity_scale_codec::{Decode, Encode};
 use schnorrkel::context::SigningContext;
 use schnorrkel::{ExpansionMode, Keypair, PublicKey, SecretKey, Signature};
+use std::fs::File;
+use std::io::Write;
 use std::ops::Deref;
 use std::path::Path;
 use std::{fs, io};
@@ -24,6 +26,14 @@ fn keypair_from_entropy(entropy: &[u8]) -> Keypair {
         .expand_to_keypair(ExpansionMode::Ed25519)
 }
 
+/// Write identity file and make sure it is persisted before returning, identity is only written
+/// once and losing it on power loss means losing the farm
+fn write_identity_file(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut file = File::create(path)?;
+    file.write_all(contents)?;
+    file.sync_all()
+}
+
 /// Errors happening when trying to create/open single disk farm
 #[derive(Debug, Error)]
 pub enum IdentityError {
@@ -102,7 +112,7 @@ impl Identity {
         let entropy = rand::random::<[u8; ENTROPY_LENGTH]>().to_vec();
 
         let identity_file_contents = IdentityFileContents { entropy };
-        fs::write(identity_file, identity_file_contents.encode())?;
+        write_identity_file(&identity_file, &identity_file_contents.encode())?;
 
         let IdentityFileContents { entropy } = identity_file_contents;
 
@@ -125,7 +135,7 @@ impl Identity {
         debug!("Creating identity from provided entropy");
 
         let identity_file_contents = IdentityFileContents { entropy };
-        fs::write(identity_file, identity_file_contents.encode())?;
+        write_identity_file(&identity_file, &identity_file_contents.encode())?;
 
         let IdentityFileContents { entropy } = identity_file_contents;
 
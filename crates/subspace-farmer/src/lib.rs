@@ -7,10 +7,10 @@
     hash_extract_if,
     impl_trait_in_assoc_type,
     int_roundings,
+    io_error_more,
     iter_collect_into,
     let_chains,
     never_type,
-    slice_flatten,
     trait_alias,
     try_blocks,
     type_alias_impl_trait,
@@ -1,31 +1,97 @@
+pub mod concatenated_files;
+pub mod direct_io_file;
+mod farm_version;
 pub mod farming;
+pub mod file_region;
+pub mod filesystem_profile;
+pub mod grow;
+mod integrity;
+pub mod io_calibration;
+mod metadata_journal;
+mod metadata_layout;
 pub mod piece_cache;
 pub mod piece_reader;
 pub mod plot_cache;
 mod plotting;
-pub mod unbuffered_io_file_windows;
+pub mod preallocation;
+pub mod read_benchmark;
+pub mod read_self_test;
+pub mod readability_scrub;
+pub mod sector_reader;
+pub mod sector_rewrite;
+pub mod shrink;
+mod shutdown;
+#[cfg(test)]
+mod test_farm;
+#[cfg(test)]
+mod tests;
 
 use crate::farm::{
-    Farm, FarmError, FarmId, HandlerFn, PieceCache, PieceReader, PlotCache, SectorUpdate,
+    Farm, FarmError, FarmId, HandlerFn, PieceCache, PieceReader, PlotCache,
+    SectorCorruptionDetails, SectorUpdate,
 };
 pub use crate::farm::{FarmingError, FarmingNotification};
 use crate::identity::{Identity, IdentityError};
 use crate::node_client::NodeClient;
 use crate::reward_signing::reward_signing;
+use crate::single_disk_farm::concatenated_files::ConcatenatedFiles;
+use crate::single_disk_farm::direct_io_file::async_file::IoThreadPool;
+use crate::single_disk_farm::direct_io_file::device_profile::DeviceProfile;
+use crate::single_disk_farm::direct_io_file::memory_budget::IoMemoryBudget;
+use crate::single_disk_farm::direct_io_file::metrics::{DiskFileKind, DiskMetrics, FileMetrics};
+use crate::single_disk_farm::direct_io_file::{
+    DirectIoFile, DirectIoOptions, IoPriority, IoRequestLimiter, IoStats, OpenMode, RequestStats,
+    DISK_SECTOR_SIZE,
+};
+use crate::single_disk_farm::farm_version::{
+    check_version, plot_data_offset, resolve_farm_version, write_version, FarmVersionError,
+    VersionedFile, FARM_VERSION, LEGACY_FARM_VERSION, PLOT_VERSION_HEADER_SIZE,
+};
 use crate::single_disk_farm::farming::rayon_files::RayonFiles;
 use crate::single_disk_farm::farming::{
-    farming, slot_notification_forwarder, FarmingOptions, PlotAudit,
+    farming, slot_notification_forwarder, FarmingOptions, FarmingPlotFile,
+};
+use crate::single_disk_farm::filesystem_profile::{
+    check_plot_file, FilesystemProfile, OsFilesystem,
+};
+use crate::single_disk_farm::grow::{FarmGrowError, FarmGrowHandle, FarmGrowSummary};
+use crate::single_disk_farm::integrity::{
+    integrity_scrub, verify_sector_checksum, IntegrityScrubOptions, IntegrityScrubPosition,
+    InterruptOnDrop, RateLimiterInterrupt,
 };
+use crate::single_disk_farm::io_calibration::{
+    tune_max_request_size, DEFAULT_CALIBRATION_TIME_BUDGET,
+};
+use crate::single_disk_farm::metadata_journal::MetadataJournal;
+use crate::single_disk_farm::metadata_layout::{migrate_metadata_file, MetadataLayout};
 use crate::single_disk_farm::piece_cache::{DiskPieceCache, DiskPieceCacheError};
 use crate::single_disk_farm::piece_reader::DiskPieceReader;
 use crate::single_disk_farm::plot_cache::DiskPlotCache;
 pub use crate::single_disk_farm::plotting::PlottingError;
 use crate::single_disk_farm::plotting::{
-    plotting, plotting_scheduler, PlottingOptions, PlottingSchedulerOptions,
+    plotting, plotting_scheduler, write_dummy_sector_metadata, PlottingOptions,
+    PlottingSchedulerOptions,
+};
+use crate::single_disk_farm::preallocation::{
+    BackgroundPreallocation, CancelOnDrop, PreallocatedSize, PreallocationFrontier,
+    PreallocationProgress,
+};
+use crate::single_disk_farm::read_benchmark::{
+    read_benchmark, ReadBenchmarkError, ReadBenchmarkOptions, ReadBenchmarkReport,
+};
+use crate::single_disk_farm::read_self_test::{
+    read_self_test, ReadSelfTestError, ReadSelfTestOptions, ReadSelfTestReport,
 };
-#[cfg(windows)]
-use crate::single_disk_farm::unbuffered_io_file_windows::UnbufferedIoFileWindows;
-use crate::single_disk_farm::unbuffered_io_file_windows::DISK_SECTOR_SIZE;
+use crate::single_disk_farm::readability_scrub::{
+    readability_scrub, ReadabilityScrubError, ReadabilityScrubOptions, ReadabilityScrubSummary,
+    SectorReadability,
+};
+use crate::single_disk_farm::sector_reader::SectorReader;
+use crate::single_disk_farm::sector_rewrite::{
+    rewrite_sector, SectorRewriteError, SectorRewriteOptions,
+};
+use crate::single_disk_farm::shrink::{FarmShrinkError, FarmShrinkSummary};
+use crate::single_disk_farm::shutdown::{close_files, ShutdownMarker};
 use crate::thread_pool_manager::PlottingThreadPoolManager;
 use crate::utils::{tokio_rayon_spawn_handler, AsyncJoinOnDrop};
 use crate::KNOWN_PEERS_CACHE_SIZE;
@@ -45,22 +111,21 @@ use static_assertions::const_assert;
 use std::error::Error;
 use std::fs::{File, OpenOptions};
 use std::future::Future;
-use std::num::{NonZeroU8, NonZeroUsize};
+use std::num::{NonZeroU32, NonZeroU64, NonZeroU8, NonZeroUsize};
+use std::ops::Add;
 use std::path::{Path, PathBuf};
 use std::pin::Pin;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicU16, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use std::{fs, io, mem};
+use std::{fmt, fs, io, iter, mem, thread};
 use subspace_core_primitives::crypto::kzg::Kzg;
 use subspace_core_primitives::crypto::{blake3_hash, Scalar};
 use subspace_core_primitives::{
     Blake3Hash, HistorySize, PieceOffset, PublicKey, Record, SectorId, SectorIndex, SegmentIndex,
 };
 use subspace_erasure_coding::ErasureCoding;
-use subspace_farmer_components::file_ext::FileExt;
-#[cfg(not(windows))]
-use subspace_farmer_components::file_ext::OpenOptionsExt;
+use subspace_farmer_components::file_ext::{AccessPattern, FileExt};
 use subspace_farmer_components::plotting::PlottedSector;
 use subspace_farmer_components::reading::ReadSectorRecordChunksMode;
 use subspace_farmer_components::sector::{sector_size, SectorMetadata, SectorMetadataChecksummed};
@@ -86,6 +151,24 @@ const NEW_SEGMENT_PROCESSING_DELAY: Duration = Duration::from_secs(30);
 ///
 /// 4 seconds is proving time, hence 3 seconds for reads.
 const INTERNAL_BENCHMARK_READ_TIMEOUT: Duration = Duration::from_millis(3500);
+/// Fraction of plot file writes requiring read-modify-write above which plot layout is considered
+/// misaligned, normally only the head and tail of every sector write need it
+const READ_MODIFY_WRITE_RATIO_WARNING_THRESHOLD: f64 = 0.1;
+/// Min number of plot file writes for read-modify-write ratio to be meaningful
+const READ_MODIFY_WRITE_WARNING_MIN_WRITES: u64 = 100;
+/// How long to wait for disk requests in progress to finish when farm is dropped
+const FILE_CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Plot file, which may span multiple directories, see [`SingleDiskFarmOptions::plot_segments`]
+pub(crate) type PlotFile = ConcatenatedFiles<Arc<DirectIoFile>>;
+
+/// Lock of farm (or plot segment) directory taken through dedicated lock file, see
+/// [`SingleDiskFarm::LOCK_FILE`], lock is released when dropped
+#[must_use = "Lock file must be kept around or as long as farm is used"]
+#[derive(Debug)]
+pub struct FarmLock {
+    _file: File,
+}
 
 /// Exclusive lock for single disk farm info file, ensuring no concurrent edits by cooperating processes is done
 #[must_use = "Lock file must be kept around or as long as farm is used"]
@@ -94,7 +177,7 @@ pub struct SingleDiskFarmInfoLock {
 }
 
 /// Important information about the contents of the `SingleDiskFarm`
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub enum SingleDiskFarmInfo {
     /// V0 of the info
@@ -111,6 +194,18 @@ pub enum SingleDiskFarmInfo {
         pieces_in_sector: u16,
         /// How much space in bytes is allocated for this farm
         allocated_space: u64,
+        /// Segments of plot file in order, starting with the farm directory itself, empty if plot
+        /// file is stored in farm directory only
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        plot_segments: Vec<PlotSegmentInfo>,
+        /// Max request size chosen by calibration when farm was first opened with
+        /// [`SingleDiskFarmOptions::auto_tune_io`], `None` if farm was never calibrated
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        calibrated_max_request_size: Option<usize>,
+        /// File system profile of the plot file detected when farm was opened, `None` if
+        /// detection is not supported
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        filesystem_profile: Option<FilesystemProfile>,
     },
 }
 
@@ -130,6 +225,9 @@ impl SingleDiskFarmInfo {
             public_key,
             pieces_in_sector,
             allocated_space,
+            plot_segments: Vec::new(),
+            calibrated_max_request_size: None,
+            filesystem_profile: None,
         }
     }
 
@@ -202,6 +300,52 @@ impl SingleDiskFarmInfo {
         } = self;
         *allocated_space
     }
+
+    /// Segments of plot file in order, starting with the farm directory itself, empty if plot file
+    /// is stored in farm directory only
+    pub fn plot_segments(&self) -> &[PlotSegmentInfo] {
+        let Self::V0 { plot_segments, .. } = self;
+        plot_segments
+    }
+
+    /// Max request size chosen by calibration, `None` if farm was never calibrated, see
+    /// [`SingleDiskFarmOptions::auto_tune_io`]
+    pub fn calibrated_max_request_size(&self) -> Option<usize> {
+        let Self::V0 {
+            calibrated_max_request_size,
+            ..
+        } = self;
+        *calibrated_max_request_size
+    }
+
+    /// File system profile of the plot file, `None` if detection is not supported
+    pub fn filesystem_profile(&self) -> Option<&FilesystemProfile> {
+        let Self::V0 {
+            filesystem_profile, ..
+        } = self;
+        filesystem_profile.as_ref()
+    }
+}
+
+/// Segment of plot file stored in farm info
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlotSegmentInfo {
+    /// Path to directory where plot file of the segment is stored
+    pub directory: PathBuf,
+    /// Number of sectors in the segment
+    pub sector_count: SectorIndex,
+}
+
+/// Additional segment of plot file stored outside of farm directory, see
+/// [`SingleDiskFarmOptions::plot_segments`]
+#[derive(Debug, Clone)]
+pub struct PlotSegment {
+    /// Path to directory where plot file of the segment is stored
+    pub directory: PathBuf,
+    /// How much space in bytes is allocated for the segment, all of it is used for sectors, while
+    /// metadata of those sectors is stored in farm directory
+    pub allocated_space: u64,
 }
 
 /// Summary of single disk farm for presentational purposes
@@ -228,6 +372,81 @@ pub enum SingleDiskFarmSummary {
     },
 }
 
+/// I/O statistics of a single file of the farm, see [`FarmIoStats`]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct FileIoStats {
+    /// Requests made to the disk
+    pub requests: RequestStats,
+    /// Alignment of writes
+    pub write_alignment: IoStats,
+}
+
+impl Add for FileIoStats {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            requests: self.requests + rhs.requests,
+            write_alignment: self.write_alignment + rhs.write_alignment,
+        }
+    }
+}
+
+impl FileIoStats {
+    fn new(file: &DirectIoFile) -> Self {
+        Self {
+            requests: file.request_stats(),
+            write_alignment: file.io_stats(),
+        }
+    }
+}
+
+/// Aggregate I/O statistics of files of the farm since it was started or since stats were reset,
+/// see [`SingleDiskFarm::io_stats()`].
+///
+/// Reads done by farming are not included, since every farming thread reads through its own clone
+/// of the plot file.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct FarmIoStats {
+    /// Plot file (all plot segments)
+    pub plot: FileIoStats,
+    /// Metadata file
+    pub metadata: FileIoStats,
+    /// Piece cache file
+    pub piece_cache: FileIoStats,
+}
+
+impl fmt::Display for FarmIoStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let FileIoStats {
+            requests,
+            write_alignment,
+        } = self.total();
+
+        write!(
+            f,
+            "{} read in {} requests (avg {:?}, {} errors), {} written in {} requests (avg {:?}, {} \
+            errors), read-modify-write ratio {:.3}",
+            bytesize::to_string(requests.bytes_read, true),
+            requests.reads,
+            requests.average_read_latency(),
+            requests.read_errors,
+            bytesize::to_string(requests.bytes_written, true),
+            requests.writes,
+            requests.average_write_latency(),
+            requests.write_errors,
+            write_alignment.read_modify_write_ratio()
+        )
+    }
+}
+
+impl FarmIoStats {
+    /// Sum of stats of all files of the farm
+    pub fn total(&self) -> FileIoStats {
+        self.plot + self.metadata + self.piece_cache
+    }
+}
+
 #[derive(Debug, Encode, Decode)]
 struct PlotMetadataHeader {
     version: u8,
@@ -246,6 +465,35 @@ impl PlotMetadataHeader {
     }
 }
 
+/// Durability of plotted sectors, defines how often written data is flushed to disk.
+///
+/// Even with direct I/O data may stay in disk's volatile write cache and file length and other
+/// metadata are not guaranteed to be persisted without explicit sync, such that recently plotted
+/// sectors can be lost on power failure. Syncing is not free though, it blocks plotting until disk
+/// confirms all data is persisted, which may take a while on slow disks and network storage.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum Durability {
+    /// Sync plot and metadata files after every plotted sector, the safest, but slowest option
+    #[default]
+    Always,
+    /// Sync plot and metadata files after every `n` plotted sectors, up to `n - 1` most recently
+    /// plotted sectors may need to be plotted again after power failure
+    Periodic(NonZeroU32),
+    /// Never sync explicitly and rely on OS to flush data eventually
+    Never,
+}
+
+impl Durability {
+    /// Whether files need to be synced after writing `sectors_written`-th sector
+    pub(crate) fn should_sync(&self, sectors_written: u64) -> bool {
+        match self {
+            Self::Always => true,
+            Self::Periodic(n) => sectors_written % u64::from(n.get()) == 0,
+            Self::Never => false,
+        }
+    }
+}
+
 /// Options used to open single disk farm
 pub struct SingleDiskFarmOptions<NC, PG> {
     /// Path to directory where farm is stored.
@@ -254,6 +502,10 @@ pub struct SingleDiskFarmOptions<NC, PG> {
     pub farmer_app_info: FarmerAppInfo,
     /// How much space in bytes was allocated
     pub allocated_space: u64,
+    /// Additional directories plot file spans after the farm directory in order, such that one farm
+    /// can use multiple smaller disks. Directories must stay the same once farm is created and only
+    /// the last one (or farm directory if there are no additional directories) can be resized.
+    pub plot_segments: Vec<PlotSegment>,
     /// How many pieces one sector is supposed to contain (max)
     pub max_pieces_in_sector: u16,
     /// RPC client connected to Subspace node
@@ -268,6 +520,10 @@ pub struct SingleDiskFarmOptions<NC, PG> {
     pub erasure_coding: ErasureCoding,
     /// Percentage of allocated space dedicated for caching purposes
     pub cache_percentage: NonZeroU8,
+    /// Use space of the plot file that is not plotted yet as additional piece cache (see
+    /// [`SingleDiskFarm::plot_cache()`]), which shrinks as plotting consumes the space. Plot cache
+    /// is always empty when disabled.
+    pub plot_cache: bool,
     /// Semaphore for part of the plotting when farmer downloads new sector, allows to limit memory
     /// usage of the plotting process, permit will be held until the end of the plotting process
     pub downloading_semaphore: Arc<Semaphore>,
@@ -289,6 +545,66 @@ pub struct SingleDiskFarmOptions<NC, PG> {
     pub global_mutex: Arc<AsyncMutex<()>>,
     /// Disable farm locking, for example if file system doesn't support it
     pub disable_farm_locking: bool,
+    /// Open farm even if version headers of its files are not recognized or farm was created by
+    /// newer farmer (see [`SingleDiskFarmError::IncompatibleFarmVersion`]), such that recovery
+    /// tooling can get to its contents. Headers are replaced with the version of this farmer
+    /// unless farm is read-only, unrecognized header of metadata file is treated as missing.
+    pub force_farm_version: bool,
+    /// Options for direct I/O of plot, metadata and cache files, max request size, number of
+    /// scratch buffers and max number of concurrent I/O requests that are not set explicitly are
+    /// tuned for the disk farm is stored on, see [`DeviceProfile`]
+    pub direct_io_options: DirectIoOptions,
+    /// Calibrate max request size of farm files with a short series of timed reads of different
+    /// sizes when farm is opened for the first time with this option, the choice is stored in
+    /// farm info and reused afterwards (see [`SingleDiskFarmInfo::calibrated_max_request_size()`]).
+    /// Max request size of [`DeviceProfile`] is used when disabled and explicitly set
+    /// [`DirectIoOptions::max_request_size`] always wins.
+    pub auto_tune_io: bool,
+    /// Disk I/O metrics of plot, metadata and cache files, nothing is measured when `None`
+    pub disk_metrics: Option<DiskMetrics>,
+    /// How often plotted sectors are synced to disk
+    pub durability: Durability,
+    /// Read-only farm that only farms already plotted sectors: farm must already exist, no plotting
+    /// is done and no files are written or locked, such that farm can be used from read-only mount
+    /// or write-protected snapshot
+    pub read_only: bool,
+    /// Read pieces with asynchronous reads on a dedicated I/O thread pool sized after
+    /// [`DirectIoOptions::scratch_buffers`] (tuned for the disk if not set), such that reads of
+    /// record chunks overlap with each other
+    pub async_reads: bool,
+    /// Open metadata file in write-through mode (see [`DirectIoOptions::write_through`]), which
+    /// protects metadata from corruption on power loss with disks that have volatile write cache,
+    /// plot file is not affected
+    pub metadata_write_through: bool,
+    /// Read every sector back right after it is written and compare with what was written,
+    /// sector is written again once on mismatch and plotting fails with
+    /// [`PlottingError::WriteVerificationFailed`] if mismatch persists. Catches silent corruption
+    /// on flaky hardware at the cost of plotting throughput.
+    pub verify_writes: bool,
+    /// Rate limit in bytes per second for background integrity scrub that verifies checksums of
+    /// plotted sectors, reports corrupted sectors with [`SectorUpdate::Corrupted`] and replots them
+    /// (unless farm is read-only), scrub is disabled when `None`
+    pub integrity_scrub_rate: Option<NonZeroU64>,
+    /// Rate limit in bytes per second for disk requests with background priority (integrity scrub
+    /// and plot cache contents check on startup, see [`IoPriority::Background`]) that are shared
+    /// by all files of the farm, background requests are not rate limited when `None`, but still
+    /// yield to time-critical auditing and proving reads
+    pub background_io_rate: Option<NonZeroU64>,
+    /// Budget of memory used by scratch buffers of farm files, normally shared by all farms of the
+    /// process such that memory used for I/O doesn't grow with the number of farms, not bounded
+    /// when `None`
+    pub io_memory_budget: Option<IoMemoryBudget>,
+    /// Plot file is preallocated in chunks of this size, such that progress can be reported and
+    /// preallocation can be cancelled between chunks
+    pub preallocation_chunk_size: NonZeroU64,
+    /// Called with progress of plot file preallocation, which can take a long time on file systems
+    /// that zero-fill allocated space. Preallocation continues in the background after farm is
+    /// opened, while sectors are plotted into space that is preallocated already.
+    pub on_preallocation_progress: Option<HandlerFn<PreallocationProgress>>,
+    /// Read randomly sampled regions of plotted sectors on startup and time them, such that
+    /// failing or too slow disk is noticed right away (see
+    /// [`SingleDiskFarm::read_self_test_report()`]), self-test is skipped when `None`
+    pub read_self_test: Option<ReadSelfTestOptions>,
     /// Barrier before internal benchmarking between different farms
     pub faster_read_sector_record_chunks_mode_barrier: Arc<Barrier>,
     /// Limit concurrency of internal benchmarking between different farms
@@ -304,6 +620,61 @@ pub enum SingleDiskFarmError {
     /// Farm is likely already in use, make sure no other farmer is using it
     #[error("Farm is likely already in use, make sure no other farmer is using it: {0}")]
     LikelyAlreadyInUse(io::Error),
+    /// Farm file is locked by another process, make sure no other farmer is using it
+    #[error(
+        "Farm file {} is locked by another process, make sure no other farmer is using it",
+        path.display()
+    )]
+    FarmAlreadyInUse {
+        /// Locked file
+        path: PathBuf,
+    },
+    /// File of a new farm already exists, likely left by aborted attempt to create the farm
+    #[error(
+        "File {} already exists, but farm is not initialized, did you mean to resume an existing \
+        farm? Leftovers of aborted farm creation must be removed before creating a new farm",
+        path.display()
+    )]
+    FarmFileAlreadyExists {
+        /// Existing file
+        path: PathBuf,
+    },
+    /// File of an existing farm is missing
+    #[error(
+        "File {} of existing farm is missing, make sure disk is mounted and files were not moved",
+        path.display()
+    )]
+    FarmFileMissing {
+        /// Missing file
+        path: PathBuf,
+    },
+    /// File of an existing farm is too small to contain sectors that are plotted according to
+    /// metadata header
+    #[error(
+        "File {} is {actual_size} bytes, but {expected_size} bytes are expected for \
+        {plotted_sector_count} plotted sectors, file was likely truncated or belongs to a \
+        different farm",
+        path.display()
+    )]
+    FarmFileSizeMismatch {
+        /// Affected file
+        path: PathBuf,
+        /// Number of plotted sectors according to metadata header
+        plotted_sector_count: SectorIndex,
+        /// Min expected size of the file
+        expected_size: u64,
+        /// Actual size of the file
+        actual_size: u64,
+    },
+    /// Farm is not initialized, it can't be used in read-only mode
+    #[error(
+        "Farm at {} is not initialized, it can't be used in read-only mode",
+        directory.display()
+    )]
+    FarmNotInitialized {
+        /// Farm directory
+        directory: PathBuf,
+    },
     // TODO: Make more variants out of this generic one
     /// I/O error occurred
     #[error("Single disk farm I/O error: {0}")]
@@ -314,6 +685,9 @@ pub enum SingleDiskFarmError {
     /// Piece cache error
     #[error("Piece cache error: {0}")]
     PieceCacheError(#[from] DiskPieceCacheError),
+    /// Read self-test of plot file failed
+    #[error("Read self-test error: {0}")]
+    ReadSelfTest(#[from] ReadSelfTestError),
     /// Can't preallocate metadata file, probably not enough space on disk
     #[error("Can't preallocate metadata file, probably not enough space on disk: {0}")]
     CantPreallocateMetadataFile(io::Error),
@@ -360,12 +734,73 @@ pub enum SingleDiskFarmError {
         /// Number of pieces in sector farm is initialized with
         initialized_with: u16,
     },
+    /// Plot segments are different from those farm was created with
+    #[error(
+        "Plot segments of farm {id} {actual:?} are different from {expected:?} when farm was \
+        created, segments must be the same directories in the same order"
+    )]
+    PlotSegmentsMismatch {
+        /// Farm ID
+        id: FarmId,
+        /// Directories of additional segments farm was created with
+        expected: Vec<PathBuf>,
+        /// Directories of additional segments farm is opened with
+        actual: Vec<PathBuf>,
+    },
+    /// Plot segment other than the last one was resized
+    #[error(
+        "Plot segment at {} of farm {id} was resized from {sector_count} to {new_sector_count} \
+        sectors, only the last segment can be resized",
+        directory.display()
+    )]
+    PlotSegmentResized {
+        /// Farm ID
+        id: FarmId,
+        /// Directory of the segment
+        directory: PathBuf,
+        /// Number of sectors in the segment
+        sector_count: SectorIndex,
+        /// New number of sectors in the segment
+        new_sector_count: SectorIndex,
+    },
+    /// Plot segment is missing
+    #[error(
+        "Plot file of segment at {} of farm {id} is missing, make sure disk is mounted",
+        directory.display()
+    )]
+    PlotSegmentMissing {
+        /// Farm ID
+        id: FarmId,
+        /// Directory of the segment
+        directory: PathBuf,
+    },
     /// Failed to decode metadata header
     #[error("Failed to decode metadata header: {0}")]
     FailedToDecodeMetadataHeader(parity_scale_codec::Error),
     /// Unexpected metadata version
     #[error("Unexpected metadata version {0}")]
     UnexpectedMetadataVersion(u8),
+    /// Farm was created by farmer that is not compatible with this one
+    #[error(
+        "Farm version {found} is not supported, this farmer supports versions up to {supported}, \
+        farm was likely created by a newer farmer"
+    )]
+    IncompatibleFarmVersion {
+        /// Version of the farm
+        found: u32,
+        /// The latest version supported by this farmer
+        supported: u32,
+    },
+    /// Farm version header is not recognized
+    #[error(
+        "Farm version header of {} is not recognized, file was not created by the farmer or is \
+        corrupted",
+        path.display()
+    )]
+    UnrecognizedFarmVersionHeader {
+        /// Affected file
+        path: PathBuf,
+    },
     /// Allocated space is not enough for one sector
     #[error(
         "Allocated space is not enough for one sector. \
@@ -504,6 +939,26 @@ pub enum SingleDiskFarmScrubError {
     /// Unexpected metadata version
     #[error("Unexpected metadata version {0}")]
     UnexpectedMetadataVersion(u8),
+    /// Farm was created by farmer that is not compatible with this one
+    #[error(
+        "Farm version {found} is not supported, this farmer supports versions up to {supported}, \
+        use force to scrub it anyway"
+    )]
+    IncompatibleFarmVersion {
+        /// Version of the farm
+        found: u32,
+        /// The latest version supported by this farmer
+        supported: u32,
+    },
+    /// Farm version header is not recognized
+    #[error(
+        "Farm version header of {file} is not recognized, file was not created by the farmer or \
+        is corrupted, use force to scrub it anyway"
+    )]
+    UnrecognizedFarmVersionHeader {
+        /// Affected file
+        file: PathBuf,
+    },
     /// Cache file does not exist
     #[error("Cache file does not exist at {file}")]
     CacheFileDoesNotExist {
@@ -520,6 +975,29 @@ pub enum SingleDiskFarmScrubError {
     },
 }
 
+/// Errors happening during sector verification
+#[derive(Debug, Error)]
+pub enum SectorVerificationError {
+    /// Sector is not plotted
+    #[error("Sector {sector_index} is not plotted")]
+    NotPlotted {
+        /// Sector index
+        sector_index: SectorIndex,
+    },
+    /// Sector is being plotted right now
+    #[error("Sector {sector_index} is being plotted right now")]
+    BeingPlotted {
+        /// Sector index
+        sector_index: SectorIndex,
+    },
+    /// I/O error occurred
+    #[error("Sector verification I/O error: {0}")]
+    Io(#[from] io::Error),
+    /// Tokio join error
+    #[error("Tokio join error: {0}")]
+    TokioJoinError(#[from] tokio::task::JoinError),
+}
+
 /// Errors that happen in background tasks
 #[derive(Debug, Error)]
 pub enum BackgroundTaskError {
@@ -552,19 +1030,22 @@ struct SingleDiskFarmInit {
     identity: Identity,
     single_disk_farm_info: SingleDiskFarmInfo,
     single_disk_farm_info_lock: Option<SingleDiskFarmInfoLock>,
-    #[cfg(not(windows))]
-    plot_file: Arc<File>,
-    #[cfg(windows)]
-    plot_file: Arc<UnbufferedIoFileWindows>,
-    #[cfg(not(windows))]
-    metadata_file: File,
-    #[cfg(windows)]
-    metadata_file: UnbufferedIoFileWindows,
+    farm_locks: Vec<FarmLock>,
+    device_profile: DeviceProfile,
+    plot_file: Arc<PlotFile>,
+    metadata_file: Arc<DirectIoFile>,
+    sector_reader: SectorReader<Arc<PlotFile>, Arc<DirectIoFile>>,
     metadata_header: PlotMetadataHeader,
+    shutdown_marker: Option<Arc<ShutdownMarker>>,
+    read_self_test_report: Option<ReadSelfTestReport>,
     target_sector_count: u16,
+    total_sectors_count: Arc<AtomicU16>,
     sectors_metadata: Arc<AsyncRwLock<Vec<SectorMetadataChecksummed>>>,
     piece_cache: DiskPieceCache,
     plot_cache: DiskPlotCache,
+    /// `None` if plot file is preallocated already
+    plot_preallocation: Option<BackgroundPreallocation>,
+    preallocation_frontier: PreallocationFrontier,
 }
 
 /// Single disk farm abstraction is a container for everything necessary to plot/farm with a single
@@ -578,27 +1059,119 @@ pub struct SingleDiskFarm {
     /// Metadata of all sectors plotted so far
     sectors_metadata: Arc<AsyncRwLock<Vec<SectorMetadataChecksummed>>>,
     pieces_in_sector: u16,
-    total_sectors_count: SectorIndex,
+    /// Shared with [`FarmGrowHandle`], which changes it when farm grows or shrinks
+    total_sectors_count: Arc<AtomicU16>,
+    plot_file: Arc<PlotFile>,
+    /// Metadata file shared with plotting, used to close it on drop (see [`close_files()`]) and for
+    /// I/O stats
+    metadata_file: Arc<DirectIoFile>,
+    sector_reader: SectorReader<Arc<PlotFile>, Arc<DirectIoFile>>,
+    /// Shared with plotting, removed once files are closed cleanly, `None` for read-only farm
+    shutdown_marker: Option<Arc<ShutdownMarker>>,
+    modifying_sector_index: Arc<AsyncRwLock<Option<SectorIndex>>>,
+    /// Shared with plotting, held for as long as sector is being modified
+    sector_modification_mutex: Arc<AsyncMutex<()>>,
+    /// Journal of metadata file updates shared with plotting, `None` for read-only farm
+    metadata_journal: Option<Arc<MetadataJournal<Arc<DirectIoFile>>>>,
+    plot_file_metrics: Option<Arc<FileMetrics>>,
     span: Span,
     tasks: FuturesUnordered<BackgroundTask>,
     handlers: Arc<Handlers>,
     piece_cache: DiskPieceCache,
     plot_cache: DiskPlotCache,
     piece_reader: DiskPieceReader,
+    /// Whether plot file uses direct/unbuffered I/O
+    unbuffered: bool,
+    /// I/O parameters used for files of this farm
+    device_profile: DeviceProfile,
+    /// Report of read self-test done on startup, if any
+    read_self_test_report: Option<ReadSelfTestReport>,
+    grow_handle: FarmGrowHandle,
     /// Sender that will be used to signal to background threads that they should start
     start_sender: Option<broadcast::Sender<()>>,
     /// Sender that will be used to signal to background threads that they must stop
     stop_sender: Option<broadcast::Sender<()>>,
-    _single_disk_farm_info_lock: Option<SingleDiskFarmInfoLock>,
+    /// Released once files are closed
+    single_disk_farm_info_lock: Option<SingleDiskFarmInfoLock>,
+    /// Released once files are closed
+    farm_locks: Vec<FarmLock>,
 }
 
 impl Drop for SingleDiskFarm {
     fn drop(&mut self) {
+        {
+            let _span_guard = self.span.enter();
+            debug!(io_stats = %self.io_stats(), "Farm I/O stats");
+        }
+
+        let io_stats = self.plot_file.io_stats();
+        let read_modify_write_ratio = io_stats.read_modify_write_ratio();
+        if io_stats.aligned_writes + io_stats.read_modify_writes
+            >= READ_MODIFY_WRITE_WARNING_MIN_WRITES
+            && read_modify_write_ratio > READ_MODIFY_WRITE_RATIO_WARNING_THRESHOLD
+        {
+            let _span_guard = self.span.enter();
+            warn!(
+                aligned_writes = %io_stats.aligned_writes,
+                read_modify_writes = %io_stats.read_modify_writes,
+                amplified_bytes = %io_stats.amplified_bytes,
+                %read_modify_write_ratio,
+                "Large fraction of plot file writes required read-modify-write, this indicates \
+                misaligned layout of the plot (like plot segment sizes that are not a multiple of \
+                disk sector size) and degrades plotting performance"
+            );
+        }
+
         self.piece_reader.close_all_readers();
         // Make background threads that are waiting to do something exit immediately
         self.start_sender.take();
         // Notify background tasks that they must stop
         self.stop_sender.take();
+
+        // Requests that are in progress are allowed to finish, while background tasks that didn't
+        // stop yet will fail to do new requests. Waiting for that happens in a dedicated thread,
+        // such that drop (likely called from async context) doesn't block, farm stays locked until
+        // files are closed. If process exits before that, shutdown is considered unclean and
+        // sectors recorded in shutdown marker are checked on the next start.
+        let plot_file = Arc::clone(&self.plot_file);
+        let metadata_file = Arc::clone(&self.metadata_file);
+        let modifying_sector_index = Arc::clone(&self.modifying_sector_index);
+        let shutdown_marker = self.shutdown_marker.take();
+        let single_disk_farm_info_lock = self.single_disk_farm_info_lock.take();
+        let farm_locks = mem::take(&mut self.farm_locks);
+        let span = self.span.clone();
+        let close = move || {
+            let _span_guard = span.enter();
+
+            let clean = close_files(
+                &plot_file,
+                &metadata_file,
+                &modifying_sector_index,
+                FILE_CLOSE_TIMEOUT,
+            );
+            if let Some(shutdown_marker) = shutdown_marker {
+                if clean {
+                    if let Err(error) = shutdown_marker.remove() {
+                        warn!(%error, "Failed to remove shutdown marker");
+                    }
+                } else {
+                    warn!(
+                        "Farm was not shut down cleanly, recently modified sectors will be \
+                        checked on start"
+                    );
+                }
+            }
+
+            drop(single_disk_farm_info_lock);
+            drop(farm_locks);
+        };
+        if let Err(error) = thread::Builder::new()
+            .name("farm-close".to_string())
+            .spawn(close)
+        {
+            let _span_guard = self.span.enter();
+            warn!(%error, "Failed to spawn thread for closing farm files");
+        }
     }
 }
 
@@ -609,7 +1182,7 @@ impl Farm for SingleDiskFarm {
     }
 
     fn total_sectors_count(&self) -> SectorIndex {
-        self.total_sectors_count
+        self.total_sectors_count()
     }
 
     async fn plotted_sectors_count(&self) -> Result<SectorIndex, FarmError> {
@@ -668,7 +1241,16 @@ impl Farm for SingleDiskFarm {
 impl SingleDiskFarm {
     pub const PLOT_FILE: &'static str = "plot.bin";
     pub const METADATA_FILE: &'static str = "metadata.bin";
-    const SUPPORTED_PLOT_VERSION: u8 = 0;
+    /// Locked exclusively while farm is in use and shared by tools that read farm files, farm files
+    /// themselves are not locked
+    pub const LOCK_FILE: &'static str = "farm.lock";
+    pub const INTEGRITY_SCRUB_POSITION_FILE: &'static str = "integrity_scrub_position.bin";
+    /// Exists while farm is open and is removed on clean shutdown
+    pub const SHUTDOWN_MARKER_FILE: &'static str = "shutdown_marker.bin";
+    pub const PLOT_PREALLOCATION_FILE: &'static str = "plot_preallocation.bin";
+    /// Temporary file used for calibration of max request size of a new farm, see
+    /// [`SingleDiskFarmOptions::auto_tune_io`]
+    pub const IO_CALIBRATION_FILE: &'static str = "io_calibration.bin";
 
     /// Create new single disk farm instance
     pub async fn new<NC, PG, PosTable>(
@@ -698,19 +1280,29 @@ impl SingleDiskFarm {
             identity,
             single_disk_farm_info,
             single_disk_farm_info_lock,
+            farm_locks,
+            device_profile,
             plot_file,
             metadata_file,
+            sector_reader,
             metadata_header,
+            shutdown_marker,
+            read_self_test_report,
             target_sector_count,
+            total_sectors_count,
             sectors_metadata,
             piece_cache,
             plot_cache,
+            plot_preallocation,
+            preallocation_frontier,
         } = single_disk_farm_init;
 
+        let unbuffered = plot_file.is_unbuffered();
+        let farm_metadata_file = Arc::clone(&metadata_file);
+
         let public_key = *single_disk_farm_info.public_key();
         let pieces_in_sector = single_disk_farm_info.pieces_in_sector();
         let sector_size = sector_size(pieces_in_sector);
-        let sector_metadata_size = SectorMetadataChecksummed::encoded_size();
 
         let SingleDiskFarmOptions {
             directory,
@@ -729,6 +1321,14 @@ impl SingleDiskFarm {
             global_mutex,
             faster_read_sector_record_chunks_mode_barrier,
             faster_read_sector_record_chunks_mode_concurrency,
+            disk_metrics,
+            durability,
+            read_only,
+            async_reads,
+            integrity_scrub_rate,
+            verify_writes,
+            preallocation_chunk_size,
+            on_preallocation_progress,
             ..
         } = options;
 
@@ -745,21 +1345,117 @@ impl SingleDiskFarm {
             Ok(())
         }));
 
+        if let Some(plot_preallocation) = plot_preallocation {
+            // Cancels preallocation if farm is dropped, it will continue from the last completed
+            // chunk next time
+            let cancel_preallocation = CancelOnDrop::default();
+            let plot_preallocation_fut = tokio::task::spawn_blocking({
+                let plot_file = Arc::clone(&plot_file);
+                let on_preallocation_progress = on_preallocation_progress.clone();
+                let preallocation_cancelled = cancel_preallocation.cancelled();
+                let span = span.clone();
+
+                move || {
+                    let _span_guard = span.enter();
+
+                    plot_preallocation.run(
+                        &*plot_file,
+                        preallocation_chunk_size,
+                        |progress| {
+                            debug!(
+                                allocated = %progress.allocated,
+                                total = %progress.total,
+                                "Preallocating plot file"
+                            );
+
+                            if let Some(on_preallocation_progress) = &on_preallocation_progress {
+                                on_preallocation_progress(&progress);
+                            }
+                        },
+                        |len| plot_file.set_len(len),
+                        &preallocation_cancelled,
+                    )
+                }
+            });
+            let plot_preallocation_fut = AsyncJoinOnDrop::new(plot_preallocation_fut, false);
+
+            tasks.push(Box::pin(async move {
+                let _cancel_preallocation = cancel_preallocation;
+
+                match plot_preallocation_fut.await {
+                    Ok(Ok(preallocated)) => {
+                        if preallocated {
+                            info!("Plot file preallocation finished");
+                        }
+
+                        Ok(())
+                    }
+                    Ok(Err(error)) => Err(PlottingError::PlotFilePreallocation { error }.into()),
+                    // Panic will already be printed by now
+                    Err(_error) => Err(BackgroundTaskError::BackgroundTaskPanicked {
+                        task: format!("plot-preallocation-{farm_index}"),
+                    }),
+                }
+            }));
+        }
+
         let handlers = Arc::<Handlers>::default();
         let (start_sender, mut start_receiver) = broadcast::channel::<()>(1);
         let (stop_sender, mut stop_receiver) = broadcast::channel::<()>(1);
         let modifying_sector_index = Arc::<AsyncRwLock<Option<SectorIndex>>>::default();
+        let sector_modification_mutex = Arc::<AsyncMutex<()>>::default();
+        // All metadata updates go through the journal, which was recovered when farm was opened
+        let metadata_journal =
+            (!read_only).then(|| Arc::new(MetadataJournal::new(Arc::clone(&metadata_file))));
         let (sectors_to_plot_sender, sectors_to_plot_receiver) = mpsc::channel(1);
+        let (new_sectors_sender, new_sectors_receiver) = mpsc::unbounded();
+        let (corrupted_sectors_sender, corrupted_sectors_receiver) = mpsc::unbounded();
         // Some sectors may already be plotted, skip them
         let sectors_indices_left_to_plot =
             metadata_header.plotted_sector_count..target_sector_count;
+        let grow_handle = FarmGrowHandle::new(
+            directory.clone(),
+            single_disk_farm_info.clone(),
+            Arc::clone(&plot_file),
+            Arc::clone(&metadata_file),
+            metadata_journal.clone(),
+            Arc::clone(&sectors_metadata),
+            Arc::clone(&sector_modification_mutex),
+            plot_cache.clone(),
+            Arc::clone(&total_sectors_count),
+            // Read-only farm doesn't plot
+            (!read_only).then_some(new_sectors_sender),
+            preallocation_frontier.clone(),
+            preallocation_chunk_size,
+            on_preallocation_progress,
+            span.clone(),
+        );
 
-        let (farming_delay_sender, delay_farmer_receiver) = if farm_during_initial_plotting {
-            (None, None)
-        } else {
-            let (sender, receiver) = oneshot::channel();
-            (Some(sender), Some(receiver))
-        };
+        let (farming_delay_sender, delay_farmer_receiver) =
+            if farm_during_initial_plotting || read_only {
+                (None, None)
+            } else {
+                let (sender, receiver) = oneshot::channel();
+                (Some(sender), Some(receiver))
+            };
+
+        let plot_file_metrics = disk_metrics.map(|disk_metrics| {
+            disk_metrics.file_metrics(single_disk_farm_info.id(), DiskFileKind::Plot)
+        });
+        let integrity_scrub_init = integrity_scrub_rate
+            .map(|rate_limit| {
+                // Separate file instance, such that sequential reads don't affect other reads
+                let plot_file = plot_file.try_clone()?;
+                plot_file.set_access_pattern(AccessPattern::Sequential)?;
+                plot_file.set_io_priority(IoPriority::Background);
+                // Read-only farm doesn't write anything, so position is only kept in memory
+                let position = IntegrityScrubPosition::load(
+                    (!read_only).then(|| directory.join(Self::INTEGRITY_SCRUB_POSITION_FILE)),
+                )?;
+
+                Ok::<_, io::Error>((plot_file, position, rate_limit))
+            })
+            .transpose()?;
 
         let farming_thread_pool = ThreadPoolBuilder::new()
             .thread_name(move |thread_index| format!("farming-{farm_index}.{thread_index}"))
@@ -767,30 +1463,30 @@ impl SingleDiskFarm {
             .spawn_handler(tokio_rayon_spawn_handler())
             .build()
             .map_err(SingleDiskFarmError::FailedToCreateThreadPool)?;
-        let farming_plot_fut = tokio::task::spawn_blocking(|| {
-            farming_thread_pool
-                .install(move || {
-                    #[cfg(windows)]
-                    {
-                        RayonFiles::open_with(
-                            &directory.join(Self::PLOT_FILE),
-                            UnbufferedIoFileWindows::open,
-                        )
-                    }
-                    #[cfg(not(windows))]
-                    {
-                        RayonFiles::open(&directory.join(Self::PLOT_FILE))
-                    }
-                })
-                .map(|farming_plot| (farming_plot, farming_thread_pool))
+        let farming_plot_path = directory.join(Self::PLOT_FILE);
+        let farming_plot_fut = tokio::task::spawn_blocking({
+            let plot_file = Arc::clone(&plot_file);
+
+            move || {
+                farming_thread_pool
+                    .install(move || {
+                        // Every thread opens plot file on its own rather than duplicating handle,
+                        // such that reads from different threads are not serialized by OS
+                        RayonFiles::open_with(&farming_plot_path, |_path| {
+                            plot_file.reopen().map(FarmingPlotFile)
+                        })
+                    })
+                    .map(|farming_plot| (farming_plot, farming_thread_pool))
+            }
         });
 
         let (farming_plot, farming_thread_pool) =
             AsyncJoinOnDrop::new(farming_plot_fut, false).await??;
+        let farming_sector_reader = sector_reader.with_plot_file(farming_plot);
 
         faster_read_sector_record_chunks_mode_barrier.wait().await;
 
-        let (read_sector_record_chunks_mode, farming_plot, farming_thread_pool) = {
+        let (read_sector_record_chunks_mode, farming_sector_reader, farming_thread_pool) = {
             // Error doesn't matter here
             let _permit = faster_read_sector_record_chunks_mode_concurrency
                 .acquire()
@@ -804,14 +1500,15 @@ impl SingleDiskFarm {
                         let _span_guard = span.enter();
 
                         faster_read_sector_record_chunks_mode(
-                            &*plot_file,
-                            &farming_plot,
-                            sector_size,
+                            &plot_file,
+                            &farming_sector_reader,
                             metadata_header.plotted_sector_count,
                         )
-                        .map(|mode| (mode, farming_plot))
+                        .map(|mode| (mode, farming_sector_reader))
+                    })
+                    .map(|(mode, farming_sector_reader)| {
+                        (mode, farming_sector_reader, farming_thread_pool)
                     })
-                    .map(|(mode, farming_plot)| (mode, farming_plot, farming_thread_pool))
             });
 
             AsyncJoinOnDrop::new(read_sector_record_chunks_mode_fut, false).await??
@@ -819,105 +1516,133 @@ impl SingleDiskFarm {
 
         faster_read_sector_record_chunks_mode_barrier.wait().await;
 
-        let plotting_join_handle = tokio::task::spawn_blocking({
-            let sectors_metadata = Arc::clone(&sectors_metadata);
-            let kzg = kzg.clone();
-            let erasure_coding = erasure_coding.clone();
-            let handlers = Arc::clone(&handlers);
-            let modifying_sector_index = Arc::clone(&modifying_sector_index);
-            let node_client = node_client.clone();
-            let plot_file = Arc::clone(&plot_file);
-            let error_sender = Arc::clone(&error_sender);
-            let span = span.clone();
-            let global_mutex = Arc::clone(&global_mutex);
-
-            move || {
-                let _span_guard = span.enter();
-
-                let plotting_options = PlottingOptions {
-                    public_key,
-                    node_client: &node_client,
-                    pieces_in_sector,
-                    sector_size,
-                    sector_metadata_size,
-                    metadata_header,
-                    plot_file,
-                    metadata_file,
-                    sectors_metadata,
-                    piece_getter: &piece_getter,
-                    kzg: &kzg,
-                    erasure_coding: &erasure_coding,
-                    handlers,
-                    modifying_sector_index,
-                    sectors_to_plot_receiver,
-                    downloading_semaphore,
-                    record_encoding_concurrency,
-                    plotting_thread_pool_manager,
-                    stop_receiver: stop_receiver.resubscribe(),
-                    global_mutex: &global_mutex,
-                };
+        // Read-only farm doesn't plot
+        if !read_only {
+            let plotting_join_handle = tokio::task::spawn_blocking({
+                let directory = directory.clone();
+                let sectors_metadata = Arc::clone(&sectors_metadata);
+                let total_sectors_count = Arc::clone(&total_sectors_count);
+                let kzg = kzg.clone();
+                let erasure_coding = erasure_coding.clone();
+                let handlers = Arc::clone(&handlers);
+                let modifying_sector_index = Arc::clone(&modifying_sector_index);
+                let shutdown_marker = shutdown_marker
+                    .clone()
+                    .expect("Shutdown marker is created for farms that are not read-only; qed");
+                let sector_modification_mutex = Arc::clone(&sector_modification_mutex);
+                let metadata_file = metadata_journal
+                    .clone()
+                    .expect("Journal is created for farms that are not read-only; qed");
+                let plot_file_metrics = plot_file_metrics.clone();
+                let node_client = node_client.clone();
+                let plot_file = Arc::clone(&plot_file);
+                let plot_cache = plot_cache.clone();
+                let error_sender = Arc::clone(&error_sender);
+                let span = span.clone();
+                let global_mutex = Arc::clone(&global_mutex);
+
+                move || {
+                    let _span_guard = span.enter();
 
-                let plotting_fut = async {
-                    if start_receiver.recv().await.is_err() {
-                        // Dropped before starting
-                        return Ok(());
-                    }
+                    let plotting_options = PlottingOptions {
+                        public_key,
+                        node_client: &node_client,
+                        pieces_in_sector,
+                        sector_size,
+                        metadata_header,
+                        directory,
+                        plot_file,
+                        metadata_file,
+                        sectors_metadata,
+                        total_sectors_count,
+                        plot_cache,
+                        piece_getter: &piece_getter,
+                        kzg: &kzg,
+                        erasure_coding: &erasure_coding,
+                        handlers,
+                        modifying_sector_index,
+                        shutdown_marker,
+                        sector_modification_mutex,
+                        sectors_to_plot_receiver,
+                        downloading_semaphore,
+                        record_encoding_concurrency,
+                        plotting_thread_pool_manager,
+                        stop_receiver: stop_receiver.resubscribe(),
+                        global_mutex: &global_mutex,
+                        durability,
+                        verify_writes,
+                        plot_file_metrics,
+                    };
 
-                    if let Some(plotting_delay) = plotting_delay {
-                        if plotting_delay.await.is_err() {
-                            // Dropped before resolving
+                    let plotting_fut = async {
+                        if start_receiver.recv().await.is_err() {
+                            // Dropped before starting
                             return Ok(());
                         }
-                    }
-
-                    plotting::<_, _, PosTable>(plotting_options).await
-                };
 
-                Handle::current().block_on(async {
-                    select! {
-                        plotting_result = plotting_fut.fuse() => {
-                            if let Err(error) = plotting_result
-                                && let Some(error_sender) = error_sender.lock().take()
-                                && let Err(error) = error_sender.send(error.into())
-                            {
-                                error!(
-                                    %error,
-                                    "Plotting failed to send error to background task"
-                                );
+                        if let Some(plotting_delay) = plotting_delay {
+                            if plotting_delay.await.is_err() {
+                                // Dropped before resolving
+                                return Ok(());
                             }
                         }
-                        _ = stop_receiver.recv().fuse() => {
-                            // Nothing, just exit
-                        }
-                    }
-                });
-            }
-        });
-        let plotting_join_handle = AsyncJoinOnDrop::new(plotting_join_handle, false);
 
-        tasks.push(Box::pin(async move {
-            // Panic will already be printed by now
-            plotting_join_handle.await.map_err(|_error| {
-                BackgroundTaskError::BackgroundTaskPanicked {
-                    task: format!("plotting-{farm_index}"),
+                        plotting::<_, _, PosTable>(plotting_options).await
+                    };
+
+                    Handle::current().block_on(async {
+                        select! {
+                            plotting_result = plotting_fut.fuse() => {
+                                if let Err(error) = plotting_result
+                                    && let Some(error_sender) = error_sender.lock().take()
+                                    && let Err(error) = error_sender.send(error.into())
+                                {
+                                    error!(
+                                        %error,
+                                        "Plotting failed to send error to background task"
+                                    );
+                                }
+                            }
+                            _ = stop_receiver.recv().fuse() => {
+                                // Nothing, just exit
+                            }
+                        }
+                    });
                 }
-            })
-        }));
+            });
+            let plotting_join_handle = AsyncJoinOnDrop::new(plotting_join_handle, false);
 
-        let plotting_scheduler_options = PlottingSchedulerOptions {
-            public_key_hash: public_key.hash(),
-            sectors_indices_left_to_plot,
-            target_sector_count,
-            last_archived_segment_index: farmer_app_info.protocol_info.history_size.segment_index(),
-            min_sector_lifetime: farmer_app_info.protocol_info.min_sector_lifetime,
-            node_client: node_client.clone(),
-            handlers: Arc::clone(&handlers),
-            sectors_metadata: Arc::clone(&sectors_metadata),
-            sectors_to_plot_sender,
-            initial_plotting_finished: farming_delay_sender,
-            new_segment_processing_delay: NEW_SEGMENT_PROCESSING_DELAY,
-        };
-        tasks.push(Box::pin(plotting_scheduler(plotting_scheduler_options)));
+            tasks.push(Box::pin(async move {
+                // Panic will already be printed by now
+                plotting_join_handle.await.map_err(|_error| {
+                    BackgroundTaskError::BackgroundTaskPanicked {
+                        task: format!("plotting-{farm_index}"),
+                    }
+                })
+            }));
+
+            let plotting_scheduler_options = PlottingSchedulerOptions {
+                public_key_hash: public_key.hash(),
+                sectors_indices_left_to_plot,
+                target_sector_count,
+                sector_size: sector_size as u64,
+                preallocation_frontier: preallocation_frontier.clone(),
+                last_archived_segment_index: farmer_app_info
+                    .protocol_info
+                    .history_size
+                    .segment_index(),
+                min_sector_lifetime: farmer_app_info.protocol_info.min_sector_lifetime,
+                node_client: node_client.clone(),
+                handlers: Arc::clone(&handlers),
+                sectors_metadata: Arc::clone(&sectors_metadata),
+                sectors_to_plot_sender,
+                new_sectors_receiver,
+                corrupted_sectors_receiver,
+                initial_plotting_finished: farming_delay_sender,
+                new_segment_processing_delay: NEW_SEGMENT_PROCESSING_DELAY,
+            };
+            tasks.push(Box::pin(plotting_scheduler(plotting_scheduler_options)));
+        }
 
         let (slot_info_forwarder_sender, slot_info_forwarder_receiver) = mpsc::channel(0);
 
@@ -958,13 +1683,11 @@ impl SingleDiskFarm {
                         }
                     }
 
-                    let plot_audit = PlotAudit::new(&farming_plot);
-
                     let farming_options = FarmingOptions {
                         public_key,
                         reward_address,
                         node_client,
-                        plot_audit,
+                        sector_reader: farming_sector_reader,
                         sectors_metadata,
                         kzg,
                         erasure_coding,
@@ -975,7 +1698,7 @@ impl SingleDiskFarm {
                         read_sector_record_chunks_mode,
                         global_mutex,
                     };
-                    farming::<PosTable, _, _>(farming_options).await
+                    farming::<PosTable, _, _, _>(farming_options).await
                 };
 
                 Handle::current().block_on(async {
@@ -1009,23 +1732,94 @@ impl SingleDiskFarm {
             })
         }));
 
-        let (piece_reader, reading_fut) = DiskPieceReader::new::<PosTable>(
-            public_key,
-            pieces_in_sector,
-            plot_file,
-            Arc::clone(&sectors_metadata),
-            erasure_coding,
-            modifying_sector_index,
-            read_sector_record_chunks_mode,
-            global_mutex,
-        );
+        if let Some((plot_file, position, rate_limit)) = integrity_scrub_init {
+            // Scrub blocks its thread while waiting for rate limiter, so stop signal alone can't
+            // stop it promptly
+            let integrity_scrub_interrupt = Arc::<RateLimiterInterrupt>::default();
+            let interrupt_integrity_scrub = InterruptOnDrop(Arc::clone(&integrity_scrub_interrupt));
+            let integrity_scrub_options = IntegrityScrubOptions {
+                sector_reader: sector_reader.with_plot_file(plot_file),
+                position,
+                rate_limit,
+                sectors_metadata: Arc::clone(&sectors_metadata),
+                modifying_sector_index: Arc::clone(&modifying_sector_index),
+                handlers: Arc::clone(&handlers),
+                // Read-only farm doesn't plot, so corrupted sectors are only reported
+                corrupted_sectors_sender: (!read_only).then_some(corrupted_sectors_sender),
+                interrupt: integrity_scrub_interrupt,
+            };
 
-        let reading_join_handle = tokio::task::spawn_blocking({
-            let mut stop_receiver = stop_sender.subscribe();
-            let reading_fut = reading_fut.instrument(span.clone());
+            let integrity_scrub_join_handle = tokio::task::spawn_blocking({
+                let mut start_receiver = start_sender.subscribe();
+                let mut stop_receiver = stop_sender.subscribe();
+                let span = span.clone();
 
-            move || {
-                Handle::current().block_on(async {
+                move || {
+                    let _span_guard = span.enter();
+
+                    let integrity_scrub_fut = async move {
+                        if start_receiver.recv().await.is_err() {
+                            // Dropped before starting
+                            return;
+                        }
+
+                        integrity_scrub(integrity_scrub_options).await
+                    };
+
+                    Handle::current().block_on(async {
+                        select! {
+                            _ = integrity_scrub_fut.fuse() => {
+                                // Nothing, just exit
+                            }
+                            _ = stop_receiver.recv().fuse() => {
+                                // Nothing, just exit
+                            }
+                        }
+                    });
+                }
+            });
+            let integrity_scrub_join_handle =
+                AsyncJoinOnDrop::new(integrity_scrub_join_handle, false);
+
+            tasks.push(Box::pin(async move {
+                let _interrupt_integrity_scrub = interrupt_integrity_scrub;
+                // Panic will already be printed by now
+                integrity_scrub_join_handle.await.map_err(|_error| {
+                    BackgroundTaskError::BackgroundTaskPanicked {
+                        task: format!("integrity-scrub-{farm_index}"),
+                    }
+                })
+            }));
+        }
+
+        let io_thread_pool = if async_reads {
+            let concurrency =
+                NonZeroUsize::new(device_profile.scratch_buffers).unwrap_or(NonZeroUsize::MIN);
+            Some(
+                IoThreadPool::new(format!("io-{farm_index}"), concurrency)
+                    .map_err(SingleDiskFarmError::FailedToCreateThreadPool)?,
+            )
+        } else {
+            None
+        };
+
+        let (piece_reader, reading_fut) = DiskPieceReader::new::<PosTable>(
+            public_key,
+            sector_reader.clone(),
+            io_thread_pool,
+            Arc::clone(&sectors_metadata),
+            erasure_coding,
+            Arc::clone(&modifying_sector_index),
+            read_sector_record_chunks_mode,
+            global_mutex,
+        );
+
+        let reading_join_handle = tokio::task::spawn_blocking({
+            let mut stop_receiver = stop_sender.subscribe();
+            let reading_fut = reading_fut.instrument(span.clone());
+
+            move || {
+                Handle::current().block_on(async {
                     select! {
                         _ = reading_fut.fuse() => {
                             // Nothing, just exit
@@ -1069,16 +1863,29 @@ impl SingleDiskFarm {
             single_disk_farm_info,
             sectors_metadata,
             pieces_in_sector,
-            total_sectors_count: target_sector_count,
+            total_sectors_count,
+            plot_file,
+            metadata_file: farm_metadata_file,
+            sector_reader,
+            shutdown_marker,
+            modifying_sector_index,
+            sector_modification_mutex,
+            metadata_journal,
+            plot_file_metrics,
             span,
             tasks,
             handlers,
             piece_cache,
             plot_cache,
             piece_reader,
+            unbuffered,
+            device_profile,
+            read_self_test_report,
+            grow_handle,
             start_sender: Some(start_sender),
             stop_sender: Some(stop_sender),
-            _single_disk_farm_info_lock: single_disk_farm_info_lock,
+            single_disk_farm_info_lock,
+            farm_locks,
         };
 
         Ok(farm)
@@ -1091,21 +1898,47 @@ impl SingleDiskFarm {
             directory,
             farmer_app_info,
             allocated_space,
+            plot_segments,
             max_pieces_in_sector,
             cache_percentage,
             disable_farm_locking,
+            direct_io_options,
+            auto_tune_io,
+            disk_metrics,
+            read_only,
+            metadata_write_through,
+            background_io_rate,
+            io_memory_budget,
+            read_self_test,
+            plot_cache,
+            force_farm_version,
             ..
         } = options;
 
         let allocated_space = *allocated_space;
         let max_pieces_in_sector = *max_pieces_in_sector;
+        let read_only = *read_only;
+        // Read-only farm doesn't lock files to not interfere with farmer that may be using it
+        let disable_farm_locking = *disable_farm_locking || read_only;
+        let direct_io_options = DirectIoOptions {
+            read_only,
+            ..*direct_io_options
+        };
 
-        fs::create_dir_all(directory)?;
+        let identity = if read_only {
+            Identity::open(directory)?.ok_or_else(|| SingleDiskFarmError::FarmNotInitialized {
+                directory: directory.clone(),
+            })?
+        } else {
+            fs::create_dir_all(directory)?;
 
-        let identity = Identity::open_or_create(directory)?;
+            Identity::open_or_create(directory)?
+        };
         let public_key = identity.public_key().to_bytes().into();
 
-        let single_disk_farm_info = match SingleDiskFarmInfo::load_from(directory)? {
+        let maybe_single_disk_farm_info = SingleDiskFarmInfo::load_from(directory)?;
+        let new_farm = maybe_single_disk_farm_info.is_none();
+        let mut single_disk_farm_info = match maybe_single_disk_farm_info {
             Some(mut single_disk_farm_info) => {
                 if &farmer_app_info.genesis_hash != single_disk_farm_info.genesis_hash() {
                     return Err(SingleDiskFarmError::WrongChain {
@@ -1142,7 +1975,7 @@ impl SingleDiskFarm {
                     );
                 }
 
-                if allocated_space != single_disk_farm_info.allocated_space() {
+                if allocated_space != single_disk_farm_info.allocated_space() && !read_only {
                     info!(
                         old_space = %bytesize::to_string(single_disk_farm_info.allocated_space(), true),
                         new_space = %bytesize::to_string(allocated_space, true),
@@ -1156,13 +1989,17 @@ impl SingleDiskFarm {
                         } = &mut single_disk_farm_info;
                         *allocated_space = new_allocated_space;
                     }
-
-                    single_disk_farm_info.store_to(directory)?;
                 }
 
                 single_disk_farm_info
             }
             None => {
+                if read_only {
+                    return Err(SingleDiskFarmError::FarmNotInitialized {
+                        directory: directory.clone(),
+                    });
+                }
+
                 let single_disk_farm_info = SingleDiskFarmInfo::new(
                     FarmId::new(),
                     farmer_app_info.genesis_hash,
@@ -1171,13 +2008,23 @@ impl SingleDiskFarm {
                     allocated_space,
                 );
 
-                single_disk_farm_info.store_to(directory)?;
-
                 single_disk_farm_info
             }
         };
+        // Farm info is only stored once all files of the farm are created and sized (see below),
+        // such that farm info never describes files that don't exist or are too small
+        let stored_single_disk_farm_info = (!new_farm).then(|| single_disk_farm_info.clone());
 
-        let single_disk_farm_info_lock = if *disable_farm_locking {
+        // Read-only farm uses allocated space farm was created with
+        let allocated_space = if read_only {
+            single_disk_farm_info.allocated_space()
+        } else {
+            allocated_space
+        };
+
+        // Farm info of a new farm doesn't exist yet, it is locked once stored, while farm
+        // directory locks below already prevent concurrent creation of the same farm
+        let mut single_disk_farm_info_lock = if disable_farm_locking || new_farm {
             None
         } else {
             Some(
@@ -1188,53 +2035,64 @@ impl SingleDiskFarm {
 
         let pieces_in_sector = single_disk_farm_info.pieces_in_sector();
         let sector_size = sector_size(pieces_in_sector);
-        let sector_metadata_size = SectorMetadataChecksummed::encoded_size();
-        let single_sector_overhead = (sector_size + sector_metadata_size) as u64;
-        // Fixed space usage regardless of plot size
-        let fixed_space_usage = RESERVED_PLOT_METADATA
-            + RESERVED_FARM_INFO
-            + Identity::file_size() as u64
-            + KnownPeersManager::file_size(KNOWN_PEERS_CACHE_SIZE) as u64;
-        // Calculate how many sectors can fit
-        let target_sector_count = {
-            let potentially_plottable_space = allocated_space.saturating_sub(fixed_space_usage)
-                / 100
-                * (100 - u64::from(cache_percentage.get()));
-            // Do the rounding to make sure we have exactly as much space as fits whole number of
-            // sectors, account for disk sector size just in case
-            (potentially_plottable_space - DISK_SECTOR_SIZE as u64) / single_sector_overhead
-        };
-
-        if target_sector_count == 0 {
-            let mut single_plot_with_cache_space =
-                single_sector_overhead.div_ceil(100 - u64::from(cache_percentage.get())) * 100;
-            // Cache must not be empty, ensure it contains at least one element even if
-            // percentage-wise it will use more space
-            if single_plot_with_cache_space - single_sector_overhead
-                < DiskPieceCache::element_size() as u64
-            {
-                single_plot_with_cache_space =
-                    single_sector_overhead + DiskPieceCache::element_size() as u64;
-            }
-
-            return Err(SingleDiskFarmError::InsufficientAllocatedSpace {
-                min_space: fixed_space_usage + single_plot_with_cache_space,
-                allocated_space,
-            });
-        }
-        let plot_file_size = target_sector_count * sector_size as u64;
-        // Align plot file size for disk sector size
-        let plot_file_size =
-            plot_file_size.div_ceil(DISK_SECTOR_SIZE as u64) * DISK_SECTOR_SIZE as u64;
-
+        let sector_metadata_slot_size = MetadataLayout::CURRENT.sector_metadata_slot_size();
+        let fixed_space_usage = fixed_space_usage();
+        let target_sector_count =
+            target_sector_count(allocated_space, cache_percentage, sector_size as u64)?;
         // Remaining space will be used for caching purposes
         let cache_capacity = {
+            let plot_file_size = target_sector_count * sector_size as u64;
+            // Align plot file size for disk sector size
+            let plot_file_size =
+                plot_file_size.div_ceil(DISK_SECTOR_SIZE as u64) * DISK_SECTOR_SIZE as u64;
+            // Version header is reserved regardless of farm version, such that cache capacity
+            // doesn't change once farm gets a newer version
             let cache_space = allocated_space
                 - fixed_space_usage
+                - PLOT_VERSION_HEADER_SIZE
                 - plot_file_size
-                - (sector_metadata_size as u64 * target_sector_count);
+                - (sector_metadata_slot_size * target_sector_count);
             (cache_space / u64::from(DiskPieceCache::element_size())) as u32
         };
+
+        // Farm directory is always the first segment
+        let plot_layout = if read_only && !single_disk_farm_info.plot_segments().is_empty() {
+            // Read-only farm uses layout farm was created with
+            single_disk_farm_info
+                .plot_segments()
+                .iter()
+                .enumerate()
+                .map(|(index, plot_segment)| {
+                    let directory = if index == 0 {
+                        directory.clone()
+                    } else {
+                        plot_segment.directory.clone()
+                    };
+
+                    (directory, u64::from(plot_segment.sector_count))
+                })
+                .collect::<Vec<_>>()
+        } else {
+            let mut plot_layout = Vec::with_capacity(1 + plot_segments.len());
+            plot_layout.push((directory.clone(), target_sector_count));
+            for plot_segment in plot_segments {
+                let sector_count = plot_segment.allocated_space / sector_size as u64;
+                if sector_count == 0 {
+                    return Err(SingleDiskFarmError::InsufficientAllocatedSpace {
+                        min_space: sector_size as u64,
+                        allocated_space: plot_segment.allocated_space,
+                    });
+                }
+
+                plot_layout.push((plot_segment.directory.clone(), sector_count));
+            }
+            plot_layout
+        };
+        let target_sector_count = plot_layout
+            .iter()
+            .map(|(_directory, sector_count)| sector_count)
+            .sum::<u64>();
+
         let target_sector_count = match SectorIndex::try_from(target_sector_count) {
             Ok(target_sector_count) if target_sector_count < SectorIndex::MAX => {
                 target_sector_count
@@ -1251,51 +2109,179 @@ impl SingleDiskFarm {
                 });
             }
         };
+        let plot_layout = plot_layout
+            .into_iter()
+            .map(|(directory, sector_count)| PlotSegmentInfo {
+                directory,
+                // Fits since total number of sectors fits
+                sector_count: sector_count as SectorIndex,
+            })
+            .collect::<Vec<_>>();
+
+        check_plot_layout(
+            single_disk_farm_info.id(),
+            single_disk_farm_info.plot_segments(),
+            &plot_layout,
+        )?;
+        // Files of a new farm and of newly added segments must not exist yet, while files of
+        // existing farm must exist, such that leftovers of aborted farm creation and missing files
+        // are detected before they are used
+        let existing_plot_segments = if new_farm {
+            0
+        } else {
+            single_disk_farm_info.plot_segments().len().max(1)
+        };
+        let open_mode = |existing: bool| {
+            if existing {
+                OpenMode::OpenExisting
+            } else {
+                OpenMode::CreateNew
+            }
+        };
 
-        let metadata_file_path = directory.join(Self::METADATA_FILE);
-        #[cfg(not(windows))]
-        let metadata_file = OpenOptions::new()
-            .read(true)
-            .write(true)
-            .create(true)
-            .advise_random_access()
-            .open(&metadata_file_path)?;
+        if !read_only && plot_layout.len() > 1 {
+            for plot_segment in &plot_layout[1..] {
+                fs::create_dir_all(&plot_segment.directory)?;
+            }
 
-        #[cfg(not(windows))]
-        metadata_file.advise_random_access()?;
+            if single_disk_farm_info.plot_segments() != plot_layout {
+                let SingleDiskFarmInfo::V0 { plot_segments, .. } = &mut single_disk_farm_info;
+                plot_segments.clone_from(&plot_layout);
+            }
+        }
+
+        // Farm files themselves are never locked since locks are mandatory on Windows and would
+        // break reads of the same files through other handles, dedicated lock file in every
+        // directory of the farm is locked instead
+        let farm_locks = if disable_farm_locking {
+            Vec::new()
+        } else {
+            plot_layout
+                .iter()
+                .map(|plot_segment| lock_farm_directory(&plot_segment.directory))
+                .collect::<Result<Vec<_>, _>>()?
+        };
 
-        #[cfg(windows)]
-        let metadata_file = UnbufferedIoFileWindows::open(&metadata_file_path)?;
+        // Explicitly configured I/O parameters win over those derived for the disk
+        let mut device_profile =
+            DeviceProfile::detect(directory).with_overrides(&direct_io_options);
+        if *auto_tune_io && direct_io_options.max_request_size.is_none() {
+            match tune_max_request_size(
+                directory,
+                &mut single_disk_farm_info,
+                read_only,
+                DEFAULT_CALIBRATION_TIME_BUDGET,
+                |file| file,
+            ) {
+                Ok(Some(max_request_size)) => {
+                    device_profile.max_request_size = max_request_size;
+                }
+                Ok(None) => {}
+                Err(error) => {
+                    warn!(%error, "Failed to calibrate max request size, using default");
+                }
+            }
+        }
+        info!(%device_profile, "Tuning I/O for the disk");
+        let direct_io_options = direct_io_options.with_device_profile(&device_profile);
+
+        // All files of the farm share the same limit of concurrent I/O requests, the same rate
+        // limit of background requests and the same memory budget
+        let io_request_limiter = IoRequestLimiter::new(device_profile.max_io_requests)
+            .with_background_rate_limit(*background_io_rate)
+            .with_memory_budget(io_memory_budget.clone());
+
+        let metadata_file_path = directory.join(Self::METADATA_FILE);
+        // Version is checked before metadata file is migrated or touched in any other way, such
+        // that files of incompatible farm are never modified
+        let mut version_headers_missing = new_farm;
+        let metadata_version = if new_farm {
+            None
+        } else {
+            let metadata_file = open_farm_file(
+                &metadata_file_path,
+                DirectIoOptions {
+                    read_only: true,
+                    open_mode: OpenMode::OpenExisting,
+                    ..direct_io_options
+                },
+                None,
+                &io_request_limiter,
+            )?;
+            match check_version(&metadata_file, VersionedFile::Metadata) {
+                Ok(maybe_version) => maybe_version,
+                Err(FarmVersionError::Io(error)) => {
+                    return Err(SingleDiskFarmError::Io(error));
+                }
+                Err(error) if *force_farm_version => {
+                    warn!(
+                        path = %metadata_file_path.display(),
+                        %error,
+                        "Farm version check failed, opening farm anyway since it is forced"
+                    );
+                    // Newer farms are assumed to have the same layout as the current version
+                    match error {
+                        FarmVersionError::IncompatibleVersion { .. } => Some(FARM_VERSION),
+                        _ => None,
+                    }
+                }
+                Err(error) => {
+                    return Err(farm_version_error(error, &metadata_file_path));
+                }
+            }
+        };
+        version_headers_missing |= metadata_version.is_none();
+        if !read_only && !new_farm {
+            // Farm info lock is already held, so nothing else uses metadata file during migration
+            migrate_metadata_file(&metadata_file_path)?;
+        }
+        let metadata_file = open_farm_file(
+            &metadata_file_path,
+            DirectIoOptions {
+                write_through: *metadata_write_through,
+                open_mode: open_mode(!new_farm),
+                ..direct_io_options
+            },
+            disk_metrics.as_ref().map(|disk_metrics| {
+                disk_metrics.file_metrics(single_disk_farm_info.id(), DiskFileKind::Metadata)
+            }),
+            &io_request_limiter,
+        )?;
+        // Marker is only created once farm is opened successfully, but it may have been left
+        // behind by previous run of the farm
+        let shutdown_marker_path = directory.join(Self::SHUTDOWN_MARKER_FILE);
+        let maybe_modified_sectors = if read_only {
+            None
+        } else {
+            ShutdownMarker::modified_sectors(&shutdown_marker_path)?
+        };
+        let unclean_shutdown = maybe_modified_sectors.is_some();
 
         let metadata_size = metadata_file.size()?;
-        let expected_metadata_size =
-            RESERVED_PLOT_METADATA + sector_metadata_size as u64 * u64::from(target_sector_count);
-        // Align plot file size for disk sector size
-        let expected_metadata_size =
-            expected_metadata_size.div_ceil(DISK_SECTOR_SIZE as u64) * DISK_SECTOR_SIZE as u64;
-        let metadata_header = if metadata_size == 0 {
+        if metadata_size > 0 && !read_only {
+            // Updates torn by unclean shutdown are repaired before anything is read
+            MetadataJournal::recover(&metadata_file, unclean_shutdown)?;
+        }
+        let (metadata_layout, metadata_header) = if metadata_size == 0 {
+            if read_only {
+                return Err(SingleDiskFarmError::FarmNotInitialized {
+                    directory: directory.clone(),
+                });
+            }
+
+            let metadata_layout = MetadataLayout::CURRENT;
             let metadata_header = PlotMetadataHeader {
-                version: 0,
+                version: metadata_layout.version(),
                 plotted_sector_count: 0,
             };
 
             metadata_file
-                .preallocate(expected_metadata_size)
+                .preallocate(metadata_layout.metadata_size(target_sector_count))
                 .map_err(SingleDiskFarmError::CantPreallocateMetadataFile)?;
-            metadata_file.write_all_at(metadata_header.encode().as_slice(), 0)?;
+            metadata_file.write_all_at(&metadata_layout.encode_header(&metadata_header), 0)?;
 
-            metadata_header
+            (metadata_layout, metadata_header)
         } else {
-            if metadata_size != expected_metadata_size {
-                // Allocating the whole file (`set_len` below can create a sparse file, which will
-                // cause writes to fail later)
-                metadata_file
-                    .preallocate(expected_metadata_size)
-                    .map_err(SingleDiskFarmError::CantPreallocateMetadataFile)?;
-                // Truncating file (if necessary)
-                metadata_file.set_len(expected_metadata_size)?;
-            }
-
             let mut metadata_header_bytes = vec![0; PlotMetadataHeader::encoded_size()];
             metadata_file.read_exact_at(&mut metadata_header_bytes, 0)?;
 
@@ -1303,28 +2289,59 @@ impl SingleDiskFarm {
                 PlotMetadataHeader::decode(&mut metadata_header_bytes.as_ref())
                     .map_err(SingleDiskFarmError::FailedToDecodeMetadataHeader)?;
 
-            if metadata_header.version != Self::SUPPORTED_PLOT_VERSION {
-                return Err(SingleDiskFarmError::UnexpectedMetadataVersion(
+            // Older layouts are migrated above, but read-only farm is read as is
+            let metadata_layout = MetadataLayout::from_version(metadata_header.version)
+                .filter(|&metadata_layout| read_only || metadata_layout == MetadataLayout::CURRENT)
+                .ok_or(SingleDiskFarmError::UnexpectedMetadataVersion(
                     metadata_header.version,
-                ));
+                ))?;
+
+            // Metadata of all plotted sectors must be present before file is resized
+            let plotted_metadata_size =
+                metadata_layout.metadata_size(metadata_header.plotted_sector_count);
+            if metadata_size < plotted_metadata_size {
+                return Err(SingleDiskFarmError::FarmFileSizeMismatch {
+                    path: metadata_file_path,
+                    plotted_sector_count: metadata_header.plotted_sector_count,
+                    expected_size: plotted_metadata_size,
+                    actual_size: metadata_size,
+                });
+            }
+
+            // Align metadata file size for disk sector size
+            let expected_metadata_size = metadata_layout
+                .metadata_size(target_sector_count)
+                .next_multiple_of(DISK_SECTOR_SIZE as u64);
+            if metadata_size != expected_metadata_size && !read_only {
+                // Allocating the whole file (`set_len` below can create a sparse file, which will
+                // cause writes to fail later)
+                metadata_file
+                    .preallocate(expected_metadata_size)
+                    .map_err(SingleDiskFarmError::CantPreallocateMetadataFile)?;
+                // Truncating file (if necessary)
+                metadata_file.set_len(expected_metadata_size)?;
             }
 
             if metadata_header.plotted_sector_count > target_sector_count {
                 metadata_header.plotted_sector_count = target_sector_count;
-                metadata_file.write_all_at(&metadata_header.encode(), 0)?;
+                if !read_only {
+                    metadata_file
+                        .write_all_at(&metadata_layout.encode_header(&metadata_header), 0)?;
+                }
             }
 
-            metadata_header
+            (metadata_layout, metadata_header)
         };
+        let farm_version =
+            resolve_farm_version(metadata_version, metadata_header.plotted_sector_count);
 
-        let sectors_metadata = {
+        let mut sectors_metadata = {
             let mut sectors_metadata =
                 Vec::<SectorMetadataChecksummed>::with_capacity(usize::from(target_sector_count));
 
-            let mut sector_metadata_bytes = vec![0; sector_metadata_size];
+            let mut sector_metadata_bytes = vec![0; SectorMetadataChecksummed::encoded_size()];
             for sector_index in 0..metadata_header.plotted_sector_count {
-                let sector_offset =
-                    RESERVED_PLOT_METADATA + sector_metadata_size as u64 * u64::from(sector_index);
+                let sector_offset = metadata_layout.sector_metadata_offset(sector_index);
                 metadata_file.read_exact_at(&mut sector_metadata_bytes, sector_offset)?;
 
                 let sector_metadata =
@@ -1345,7 +2362,12 @@ impl SingleDiskFarm {
                                 s_bucket_sizes: Box::new([0; Record::NUM_S_BUCKETS]),
                                 history_size: HistorySize::from(SegmentIndex::ZERO),
                             });
-                            metadata_file.write_all_at(&dummy_sector.encode(), sector_offset)?;
+                            if !read_only {
+                                metadata_file.write_all_at(
+                                    &metadata_layout.encode_sector_metadata(&dummy_sector),
+                                    sector_offset,
+                                )?;
+                            }
 
                             dummy_sector
                         }
@@ -1353,54 +2375,275 @@ impl SingleDiskFarm {
                 sectors_metadata.push(sector_metadata);
             }
 
-            Arc::new(AsyncRwLock::new(sectors_metadata))
+            sectors_metadata
         };
 
-        #[cfg(not(windows))]
-        let plot_file = OpenOptions::new()
-            .read(true)
-            .write(true)
-            .create(true)
-            .advise_random_access()
-            .open(directory.join(Self::PLOT_FILE))?;
-
-        #[cfg(not(windows))]
-        plot_file.advise_random_access()?;
-
-        #[cfg(windows)]
-        let plot_file = UnbufferedIoFileWindows::open(&directory.join(Self::PLOT_FILE))?;
-
-        if plot_file.size()? != plot_file_size {
-            // Allocating the whole file (`set_len` below can create a sparse file, which will cause
-            // writes to fail later)
-            plot_file
-                .preallocate(plot_file_size)
-                .map_err(SingleDiskFarmError::CantPreallocatePlotFile)?;
-            // Truncating file (if necessary)
-            plot_file.set_len(plot_file_size)?;
+        let plot_file_metrics = disk_metrics.as_ref().map(|disk_metrics| {
+            disk_metrics.file_metrics(single_disk_farm_info.id(), DiskFileKind::Plot)
+        });
+        let plot_file = open_plot_file(
+            &plot_layout,
+            sector_size as u64,
+            plot_data_offset(farm_version),
+            |segment_index, plot_file_path| {
+                open_farm_file(
+                    plot_file_path,
+                    DirectIoOptions {
+                        open_mode: open_mode(segment_index < existing_plot_segments),
+                        ..direct_io_options
+                    },
+                    plot_file_metrics.clone(),
+                    &io_request_limiter,
+                )
+            },
+        )?;
+        if farm_version != LEGACY_FARM_VERSION {
+            for (file, plot_segment) in plot_file.files().zip(&plot_layout) {
+                let plot_file_path = plot_segment.directory.join(Self::PLOT_FILE);
+                match check_version(file.as_ref(), VersionedFile::Plot) {
+                    Ok(Some(_plot_version)) => {}
+                    Ok(None) => {
+                        version_headers_missing = true;
+                    }
+                    Err(FarmVersionError::Io(error)) => {
+                        return Err(SingleDiskFarmError::Io(error));
+                    }
+                    Err(error) if *force_farm_version => {
+                        warn!(
+                            path = %plot_file_path.display(),
+                            %error,
+                            "Farm version check failed, opening farm anyway since it is forced"
+                        );
+                        version_headers_missing = true;
+                    }
+                    Err(error) => {
+                        return Err(farm_version_error(error, &plot_file_path));
+                    }
+                }
+            }
+        }
+        check_plot_file_size(
+            &plot_file,
+            &plot_layout,
+            sector_size as u64,
+            metadata_header.plotted_sector_count,
+        )?;
+        // Farming, plotting and reading pieces access plot file randomly, so reads that happen to
+        // be sequential must not trigger read-ahead, which would only waste I/O
+        plot_file.set_access_pattern(AccessPattern::Random)?;
+        for (segment_index, (file, plot_segment)) in plot_file.files().zip(&plot_layout).enumerate()
+        {
+            let plot_file_path = plot_segment.directory.join(Self::PLOT_FILE);
+            // Copy-on-write can only be disabled before anything is written to the plot file
+            let plot_file_size = file.size()?;
+            let filesystem_check = check_plot_file(
+                &OsFilesystem,
+                &plot_file_path,
+                plot_file_size,
+                !read_only && plot_file_size == 0,
+                single_disk_farm_info.filesystem_profile(),
+            );
+            // Only the plot file in farm directory is recorded in farm info
+            if let Some(filesystem_check) = filesystem_check
+                && segment_index == 0
+                && !read_only
+                && single_disk_farm_info.filesystem_profile() != Some(&filesystem_check.profile)
+            {
+                {
+                    let SingleDiskFarmInfo::V0 {
+                        filesystem_profile, ..
+                    } = &mut single_disk_farm_info;
+                    filesystem_profile.replace(filesystem_check.profile);
+                }
+            }
+            debug!(
+                path = %plot_file_path.display(),
+                logical_sector_size = %file.logical_sector_size(),
+                physical_sector_size = %file.physical_sector_size(),
+                unbuffered = %file.is_unbuffered(),
+                "Opened plot file"
+            );
+        }
+        let plot_file_size = {
+            let last_segment_size = plot_layout
+                .last()
+                .map(|plot_segment| u64::from(plot_segment.sector_count) * sector_size as u64)
+                .expect("Plot layout always contains farm directory; qed");
+            // Align plot file size for disk sector size
+            plot_file.last_segment_offset()
+                + last_segment_size.div_ceil(DISK_SECTOR_SIZE as u64) * DISK_SECTOR_SIZE as u64
+        };
+
+        let preallocated_size =
+            PreallocatedSize::new(directory.join(Self::PLOT_PREALLOCATION_FILE));
+        // Allocating the whole file in the background (`set_len` can create a sparse file, which
+        // will cause writes to fail later), sectors are plotted into preallocated space meanwhile
+        let (plot_preallocation, preallocation_frontier) = if !read_only
+            && (plot_file.size()? != plot_file_size || preallocated_size.is_interrupted())
+        {
+            let (plot_preallocation, preallocation_frontier) =
+                BackgroundPreallocation::new(&plot_file, plot_file_size, preallocated_size)
+                    .map_err(SingleDiskFarmError::CantPreallocatePlotFile)?;
+
+            (Some(plot_preallocation), preallocation_frontier)
+        } else {
+            (None, PreallocationFrontier::complete())
+        };
+
+        // All files of the farm are created and sized by now
+        if !read_only && stored_single_disk_farm_info.as_ref() != Some(&single_disk_farm_info) {
+            single_disk_farm_info.store_to(directory)?;
+        }
+        if !disable_farm_locking && single_disk_farm_info_lock.is_none() {
+            single_disk_farm_info_lock.replace(
+                SingleDiskFarmInfo::try_lock(directory)
+                    .map_err(SingleDiskFarmError::LikelyAlreadyInUse)?,
+            );
         }
 
         let plot_file = Arc::new(plot_file);
+        let metadata_file = Arc::new(metadata_file);
+        let total_sectors_count = Arc::new(AtomicU16::new(target_sector_count));
+        let sector_reader = SectorReader::new(
+            Arc::clone(&plot_file),
+            Arc::clone(&metadata_file),
+            metadata_layout,
+            pieces_in_sector,
+            Arc::clone(&total_sectors_count),
+        );
+
+        if let Some(modified_sectors) = maybe_modified_sectors {
+            // Sectors that were not synced or were being written during unclean shutdown may be
+            // corrupted, sectors that were not plotted before don't have metadata to replace
+            let sectors_to_check = modified_sectors
+                .into_iter()
+                .filter(|&sector_index| usize::from(sector_index) < sectors_metadata.len())
+                .collect::<Vec<_>>();
+            warn!(
+                ?sectors_to_check,
+                "Farm was not shut down cleanly, checking recently modified sectors"
+            );
+
+            let mut scratch_buffer = vec![0; Record::SIZE];
+            for sector_index in sectors_to_check {
+                let result = sector_reader
+                    .read_sector(sector_index)
+                    .map_err(io::Error::from)
+                    .and_then(|sector| verify_sector_checksum(&sector, &mut scratch_buffer));
+                match result {
+                    Ok(None) => {
+                        trace!(%sector_index, "Sector is intact");
+                    }
+                    Ok(Some(corruption_details)) => {
+                        warn!(
+                            %sector_index,
+                            actual_checksum = %hex::encode(corruption_details.actual_checksum),
+                            expected_checksum = %hex::encode(corruption_details.expected_checksum),
+                            "Sector is corrupted after unclean shutdown, replacing with dummy \
+                            expired sector metadata"
+                        );
+
+                        let dummy_sector = SectorMetadataChecksummed::from(SectorMetadata {
+                            sector_index,
+                            pieces_in_sector,
+                            s_bucket_sizes: Box::new([0; Record::NUM_S_BUCKETS]),
+                            history_size: HistorySize::from(SegmentIndex::ZERO),
+                        });
+                        metadata_file.write_all_at(
+                            &metadata_layout.encode_sector_metadata(&dummy_sector),
+                            metadata_layout.sector_metadata_offset(sector_index),
+                        )?;
+                        sectors_metadata[usize::from(sector_index)] = dummy_sector;
+                    }
+                    Err(error) => {
+                        warn!(
+                            %error,
+                            %sector_index,
+                            "Failed to read sector after unclean shutdown"
+                        );
+                    }
+                }
+            }
+            metadata_file.sync_data()?;
+        }
 
-        let piece_cache = DiskPieceCache::open(directory, cache_capacity)?;
+        let read_self_test_report = read_self_test
+            .as_ref()
+            .map(|read_self_test_options| {
+                read_self_test(
+                    &plot_file,
+                    &sectors_metadata,
+                    sector_size as u64,
+                    read_self_test_options,
+                    plot_file_metrics.as_deref(),
+                )
+            })
+            .transpose()?;
+
+        let sectors_metadata = Arc::new(AsyncRwLock::new(sectors_metadata));
+
+        let piece_cache = DiskPieceCache::open(
+            directory,
+            cache_capacity,
+            direct_io_options,
+            disk_metrics.as_ref().map(|disk_metrics| {
+                disk_metrics.file_metrics(single_disk_farm_info.id(), DiskFileKind::Cache)
+            }),
+            Some(io_request_limiter.clone()),
+        )?;
         let plot_cache = DiskPlotCache::new(
             &plot_file,
             &sectors_metadata,
-            target_sector_count,
+            // Read-only farm can't cache pieces in space that is not plotted yet and the rest can't
+            // cache pieces in space that is not preallocated yet
+            if read_only || !*plot_cache {
+                metadata_header.plotted_sector_count
+            } else {
+                SectorIndex::try_from(preallocation_frontier.allocated() / sector_size as u64)
+                    .map_or(target_sector_count, |preallocated_sector_count| {
+                        preallocated_sector_count.min(target_sector_count)
+                    })
+            },
             sector_size,
         );
 
+        // Written as the last step, such that farm creation of which was interrupted is not
+        // recognized as fully created, metadata file header goes after plot file headers for the
+        // same reason
+        if !read_only && (version_headers_missing || *force_farm_version) {
+            if farm_version != LEGACY_FARM_VERSION {
+                for file in plot_file.files() {
+                    write_version(file.as_ref(), VersionedFile::Plot, farm_version)?;
+                }
+            }
+            write_version(&metadata_file, VersionedFile::Metadata, farm_version)?;
+        }
+
+        let shutdown_marker = if read_only {
+            None
+        } else {
+            Some(Arc::new(ShutdownMarker::create(shutdown_marker_path)?))
+        };
+
         Ok(SingleDiskFarmInit {
             identity,
             single_disk_farm_info,
             single_disk_farm_info_lock,
+            farm_locks,
+            device_profile,
             plot_file,
             metadata_file,
+            sector_reader,
             metadata_header,
+            shutdown_marker,
+            read_self_test_report,
             target_sector_count,
+            total_sectors_count,
             sectors_metadata,
             piece_cache,
             plot_cache,
+            plot_preallocation,
+            preallocation_frontier,
         })
     }
 
@@ -1422,17 +2665,47 @@ impl SingleDiskFarm {
         }
     }
 
-    /// Read all sectors metadata
+    /// Acquire shared lock of farm in `directory`, such that farm files can be read while farm is
+    /// not in use by farmer.
+    ///
+    /// Fails with [`io::ErrorKind::WouldBlock`] instead of waiting if farm is in use.
+    pub fn try_lock_shared(directory: &Path) -> io::Result<FarmLock> {
+        let (file, path) = open_lock_file(directory)?;
+        fs4::FileExt::try_lock_shared(&file).map_err(|error| {
+            // Lock contention is reported with different error codes on different platforms
+            if error.raw_os_error() == fs4::lock_contended_error().raw_os_error() {
+                io::Error::new(
+                    io::ErrorKind::WouldBlock,
+                    format!(
+                        "Farm is in use, {} is locked by another process",
+                        path.display()
+                    ),
+                )
+            } else {
+                error
+            }
+        })?;
+
+        Ok(FarmLock { _file: file })
+    }
+
+    /// Read all sectors metadata.
+    ///
+    /// Takes shared lock of the farm, hence fails while farm is in use by farmer, see
+    /// [`Self::try_lock_shared()`].
     pub fn read_all_sectors_metadata(
         directory: &Path,
     ) -> io::Result<Vec<SectorMetadataChecksummed>> {
-        #[cfg(not(windows))]
-        let metadata_file = OpenOptions::new()
-            .read(true)
-            .open(directory.join(Self::METADATA_FILE))?;
-
-        #[cfg(windows)]
-        let metadata_file = UnbufferedIoFileWindows::open(&directory.join(Self::METADATA_FILE))?;
+        // Metadata of a missing farm must not be created by a read-only inspection
+        let metadata_file = DirectIoFile::open_with_options(
+            &directory.join(Self::METADATA_FILE),
+            DirectIoOptions {
+                read_only: true,
+                open_mode: OpenMode::OpenExisting,
+                ..DirectIoOptions::default()
+            },
+        )?;
+        let _farm_lock = Self::try_lock_shared(directory)?;
 
         let metadata_size = metadata_file.size()?;
         let sector_metadata_size = SectorMetadataChecksummed::encoded_size();
@@ -1448,22 +2721,22 @@ impl SingleDiskFarm {
                 )
             })?;
 
-        if metadata_header.version != SingleDiskFarm::SUPPORTED_PLOT_VERSION {
+        let Some(metadata_layout) = MetadataLayout::from_version(metadata_header.version) else {
             return Err(io::Error::new(
                 io::ErrorKind::Other,
                 format!("Unsupported metadata version {}", metadata_header.version),
             ));
-        }
+        };
 
         let mut sectors_metadata = Vec::<SectorMetadataChecksummed>::with_capacity(
-            ((metadata_size - RESERVED_PLOT_METADATA) / sector_metadata_size as u64) as usize,
+            metadata_layout.sector_count(metadata_size) as usize,
         );
 
         let mut sector_metadata_bytes = vec![0; sector_metadata_size];
         for sector_index in 0..metadata_header.plotted_sector_count {
             metadata_file.read_exact_at(
                 &mut sector_metadata_bytes,
-                RESERVED_PLOT_METADATA + sector_metadata_size as u64 * u64::from(sector_index),
+                metadata_layout.sector_metadata_offset(sector_index),
             )?;
             sectors_metadata.push(
                 SectorMetadataChecksummed::decode(&mut sector_metadata_bytes.as_ref()).map_err(
@@ -1480,6 +2753,25 @@ impl SingleDiskFarm {
         Ok(sectors_metadata)
     }
 
+    /// Offset of plotted sectors in every plot file of the farm, plot files of farms created before
+    /// version headers were introduced don't have headers in front of sectors.
+    ///
+    /// Takes shared lock of the farm, hence fails while farm is in use by farmer, see
+    /// [`Self::try_lock_shared()`].
+    pub fn read_plot_data_offset(directory: &Path) -> io::Result<u64> {
+        let metadata_file = DirectIoFile::open_with_options(
+            &directory.join(Self::METADATA_FILE),
+            DirectIoOptions {
+                read_only: true,
+                open_mode: OpenMode::OpenExisting,
+                ..DirectIoOptions::default()
+            },
+        )?;
+        let _farm_lock = Self::try_lock_shared(directory)?;
+
+        read_plot_data_offset(&metadata_file)
+    }
+
     /// ID of this farm
     pub fn id(&self) -> &FarmId {
         self.single_disk_farm_info.id()
@@ -1490,9 +2782,64 @@ impl SingleDiskFarm {
         &self.single_disk_farm_info
     }
 
+    /// Whether plot file uses direct/unbuffered I/O, `false` means buffered I/O fallback is used
+    /// and memory usage will be much higher
+    pub fn is_unbuffered(&self) -> bool {
+        self.unbuffered
+    }
+
+    /// Detected disk and I/O parameters used for files of this farm (including explicitly
+    /// configured ones)
+    pub fn device_profile(&self) -> &DeviceProfile {
+        &self.device_profile
+    }
+
+    /// Report of read self-test done on startup, `None` if self-test was not requested
+    pub fn read_self_test_report(&self) -> Option<&ReadSelfTestReport> {
+        self.read_self_test_report.as_ref()
+    }
+
+    /// Aggregate I/O statistics of files of this farm, see [`FarmIoStats`]
+    pub fn io_stats(&self) -> FarmIoStats {
+        farm_io_stats(&self.plot_file, &self.metadata_file, &self.piece_cache)
+    }
+
+    /// Reset I/O statistics of all files of this farm, such that [`Self::io_stats()`] covers the
+    /// time since reset
+    pub fn reset_io_stats(&self) {
+        reset_farm_io_stats(&self.plot_file, &self.metadata_file, &self.piece_cache);
+    }
+
     /// Number of sectors in this farm
     pub fn total_sectors_count(&self) -> SectorIndex {
-        self.total_sectors_count
+        self.total_sectors_count.load(Ordering::Acquire)
+    }
+
+    /// Get handle that grows or shrinks this farm while it is running
+    pub fn grow_handle(&self) -> FarmGrowHandle {
+        self.grow_handle.clone()
+    }
+
+    /// Grow this farm to fit into `new_allocated_space`, see [`FarmGrowHandle::grow()`]
+    pub async fn grow(
+        &self,
+        new_allocated_space: u64,
+        cache_percentage: NonZeroU8,
+    ) -> Result<FarmGrowSummary, FarmGrowError> {
+        self.grow_handle
+            .grow(new_allocated_space, cache_percentage)
+            .await
+    }
+
+    /// Shrink this farm to fit into `new_allocated_space`, see [`FarmGrowHandle::shrink()`]
+    pub async fn shrink(
+        &self,
+        new_allocated_space: u64,
+        cache_percentage: NonZeroU8,
+    ) -> Result<FarmShrinkSummary, FarmShrinkError> {
+        self.grow_handle
+            .shrink(new_allocated_space, cache_percentage)
+            .await
     }
 
     /// Number of sectors successfully plotted so far
@@ -1540,6 +2887,75 @@ impl SingleDiskFarm {
             })
     }
 
+    /// Verify plotted sector against checksum stored alongside it during plotting, returns `None`
+    /// if sector is intact
+    pub async fn verify_sector(
+        &self,
+        sector_index: SectorIndex,
+    ) -> Result<Option<SectorCorruptionDetails>, SectorVerificationError> {
+        if usize::from(sector_index) >= self.sectors_metadata.read().await.len() {
+            return Err(SectorVerificationError::NotPlotted { sector_index });
+        }
+
+        // Plotting needs write lock before it can start writing the sector, holding read lock
+        // ensures sector is not modified while being verified
+        let modifying_sector_index = self.modifying_sector_index.read().await;
+        if *modifying_sector_index == Some(sector_index) {
+            return Err(SectorVerificationError::BeingPlotted { sector_index });
+        }
+
+        let sector_reader = self.sector_reader.clone();
+        let span = self.span.clone();
+        let verify_fut = tokio::task::spawn_blocking(move || {
+            let _span_guard = span.enter();
+
+            let sector = sector_reader.read_sector(sector_index)?;
+            verify_sector_checksum(&sector, &mut vec![0; Record::SIZE])
+        });
+
+        Ok(AsyncJoinOnDrop::new(verify_fut, false).await??)
+    }
+
+    /// Rewrite plotted sector with externally provided `sector_bytes` and `sector_metadata_bytes`
+    /// (SCALE-encoded [`SectorMetadataChecksummed`]), which is a low-level tool for repairing
+    /// sectors that were found to be corrupted.
+    ///
+    /// Both are validated against farm layout and checksums before anything is written. Waits for
+    /// plotting of the sector in progress to finish, sector is not audited or read from while
+    /// being rewritten. Sector is read back after writing and both files are synced before sector
+    /// is used again.
+    pub async fn rewrite_sector(
+        &self,
+        sector_index: SectorIndex,
+        sector_bytes: Vec<u8>,
+        sector_metadata_bytes: Vec<u8>,
+    ) -> Result<(), SectorRewriteError> {
+        let Some(metadata_journal) = self.metadata_journal.clone() else {
+            return Err(SectorRewriteError::ReadOnly);
+        };
+
+        rewrite_sector(
+            SectorRewriteOptions {
+                plot_file: Arc::clone(&self.plot_file),
+                metadata_file: metadata_journal,
+                pieces_in_sector: self.pieces_in_sector,
+                sectors_metadata: Arc::clone(&self.sectors_metadata),
+                modifying_sector_index: Arc::clone(&self.modifying_sector_index),
+                shutdown_marker: self
+                    .shutdown_marker
+                    .clone()
+                    .expect("Shutdown marker is created for farms that are not read-only; qed"),
+                sector_modification_mutex: Arc::clone(&self.sector_modification_mutex),
+                plot_file_metrics: self.plot_file_metrics.clone(),
+            },
+            sector_index,
+            sector_bytes,
+            sector_metadata_bytes,
+        )
+        .instrument(self.span.clone())
+        .await
+    }
+
     /// Get piece cache instance
     pub fn piece_cache(&self) -> DiskPieceCache {
         self.piece_cache.clone()
@@ -1587,9 +3003,11 @@ impl SingleDiskFarm {
     /// Wipe everything that belongs to this single disk farm
     pub fn wipe(directory: &Path) -> io::Result<()> {
         let single_disk_info_info_path = directory.join(SingleDiskFarmInfo::FILE_NAME);
-        match SingleDiskFarmInfo::load_from(directory) {
+        let plot_segments = match SingleDiskFarmInfo::load_from(directory) {
             Ok(Some(single_disk_farm_info)) => {
                 info!("Found single disk farm {}", single_disk_farm_info.id());
+
+                single_disk_farm_info.plot_segments().to_vec()
             }
             Ok(None) => {
                 return Err(io::Error::new(
@@ -1602,8 +3020,10 @@ impl SingleDiskFarm {
             }
             Err(error) => {
                 warn!("Found unknown single disk farm: {}", error);
+
+                Vec::new()
             }
-        }
+        };
 
         {
             let plot = directory.join(Self::PLOT_FILE);
@@ -1612,6 +3032,14 @@ impl SingleDiskFarm {
                 fs::remove_file(plot)?;
             }
         }
+        // Farm directory is the first segment, which was already deleted above
+        for plot_segment in plot_segments.iter().skip(1) {
+            let plot = plot_segment.directory.join(Self::PLOT_FILE);
+            if plot.exists() {
+                info!("Deleting plot file at {}", plot.display());
+                fs::remove_file(plot)?;
+            }
+        }
         {
             let metadata = directory.join(Self::METADATA_FILE);
             if metadata.exists() {
@@ -1619,6 +3047,46 @@ impl SingleDiskFarm {
                 fs::remove_file(metadata)?;
             }
         }
+        {
+            let plot_preallocation = directory.join(Self::PLOT_PREALLOCATION_FILE);
+            if plot_preallocation.exists() {
+                info!(
+                    "Deleting plot preallocation file at {}",
+                    plot_preallocation.display()
+                );
+                fs::remove_file(plot_preallocation)?;
+            }
+        }
+        {
+            let io_calibration = directory.join(Self::IO_CALIBRATION_FILE);
+            if io_calibration.exists() {
+                info!(
+                    "Deleting I/O calibration file at {}",
+                    io_calibration.display()
+                );
+                fs::remove_file(io_calibration)?;
+            }
+        }
+        {
+            let shutdown_marker = directory.join(Self::SHUTDOWN_MARKER_FILE);
+            if shutdown_marker.exists() {
+                info!(
+                    "Deleting shutdown marker file at {}",
+                    shutdown_marker.display()
+                );
+                fs::remove_file(shutdown_marker)?;
+            }
+        }
+        {
+            let integrity_scrub_position = directory.join(Self::INTEGRITY_SCRUB_POSITION_FILE);
+            if integrity_scrub_position.exists() {
+                info!(
+                    "Deleting integrity scrub position file at {}",
+                    integrity_scrub_position.display()
+                );
+                fs::remove_file(integrity_scrub_position)?;
+            }
+        }
         // TODO: Identity should be able to wipe itself instead of assuming a specific file name
         //  here
         {
@@ -1640,10 +3108,15 @@ impl SingleDiskFarm {
 
     /// Check the farm for corruption and repair errors (caused by disk errors or something else),
     /// returns an error when irrecoverable errors occur.
+    ///
+    /// Farm with unrecognized or incompatible version header is only scrubbed with `force`, in
+    /// which case header is replaced with the version of this farmer once farm is checked (unless
+    /// `dry_run` is used).
     pub fn scrub(
         directory: &Path,
         disable_farm_locking: bool,
         dry_run: bool,
+        force: bool,
     ) -> Result<(), SingleDiskFarmScrubError> {
         let span = Span::current();
 
@@ -1697,10 +3170,9 @@ impl SingleDiskFarm {
             });
         }
 
-        let sector_metadata_size = SectorMetadataChecksummed::encoded_size();
-
         let metadata_file_path = directory.join(Self::METADATA_FILE);
-        let (metadata_file, mut metadata_header) = {
+        let mut farm_version_ignored = false;
+        let (metadata_file, metadata_layout, mut metadata_header, farm_version) = {
             info!(path = %metadata_file_path.display(), "Checking metadata file");
 
             let metadata_file = match OpenOptions::new()
@@ -1723,6 +3195,44 @@ impl SingleDiskFarm {
                 }
             };
 
+            // Version is checked before anything else is read, such that files of incompatible
+            // farm are not misinterpreted
+            let metadata_version = match check_version(&metadata_file, VersionedFile::Metadata) {
+                Ok(maybe_version) => maybe_version,
+                Err(error) if force && !matches!(error, FarmVersionError::Io(_)) => {
+                    warn!(
+                        path = %metadata_file_path.display(),
+                        %error,
+                        "Farm version check failed, scrubbing anyway since force is used"
+                    );
+                    farm_version_ignored = true;
+                    // Newer farms are assumed to have the same layout as the current version
+                    match error {
+                        FarmVersionError::IncompatibleVersion { .. } => Some(FARM_VERSION),
+                        _ => None,
+                    }
+                }
+                Err(FarmVersionError::UnexpectedMagic { .. }) => {
+                    return Err(SingleDiskFarmScrubError::UnrecognizedFarmVersionHeader {
+                        file: metadata_file_path,
+                    });
+                }
+                Err(FarmVersionError::IncompatibleVersion { found, supported }) => {
+                    return Err(SingleDiskFarmScrubError::IncompatibleFarmVersion {
+                        found,
+                        supported,
+                    });
+                }
+                Err(FarmVersionError::Io(error)) => {
+                    return Err(SingleDiskFarmScrubError::FailedToReadBytes {
+                        file: metadata_file_path,
+                        size: DISK_SECTOR_SIZE as u64,
+                        offset: METADATA_VERSION_HEADER_OFFSET,
+                        error,
+                    });
+                }
+            };
+
             // Error doesn't matter here
             let _ = metadata_file.advise_sequential_access();
 
@@ -1760,16 +3270,17 @@ impl SingleDiskFarm {
                     .map_err(SingleDiskFarmScrubError::FailedToDecodeMetadataHeader)?
             };
 
-            if metadata_header.version != Self::SUPPORTED_PLOT_VERSION {
+            // Metadata is checked and repaired in place, so any supported layout will do
+            let Some(metadata_layout) = MetadataLayout::from_version(metadata_header.version)
+            else {
                 return Err(SingleDiskFarmScrubError::UnexpectedMetadataVersion(
                     metadata_header.version,
                 ));
-            }
+            };
 
             let plotted_sector_count = metadata_header.plotted_sector_count;
 
-            let expected_metadata_size = RESERVED_PLOT_METADATA
-                + sector_metadata_size as u64 * u64::from(plotted_sector_count);
+            let expected_metadata_size = metadata_layout.metadata_size(plotted_sector_count);
 
             if metadata_size < expected_metadata_size {
                 warn!(
@@ -1779,10 +3290,9 @@ impl SingleDiskFarm {
                     sectors to correct value"
                 );
 
-                metadata_header.plotted_sector_count = ((metadata_size - RESERVED_PLOT_METADATA)
-                    / sector_metadata_size as u64)
-                    as SectorIndex;
-                let metadata_header_bytes = metadata_header.encode();
+                metadata_header.plotted_sector_count =
+                    metadata_layout.sector_count(metadata_size) as SectorIndex;
+                let metadata_header_bytes = metadata_layout.encode_header(&metadata_header);
 
                 if !dry_run {
                     if let Err(error) = metadata_file.write_all_at(&metadata_header_bytes, 0) {
@@ -1796,25 +3306,42 @@ impl SingleDiskFarm {
                 }
             }
 
-            (metadata_file, metadata_header)
+            let farm_version =
+                resolve_farm_version(metadata_version, metadata_header.plotted_sector_count);
+
+            (
+                metadata_file,
+                metadata_layout,
+                metadata_header,
+                farm_version,
+            )
         };
 
         let pieces_in_sector = info.pieces_in_sector();
         let sector_size = sector_size(pieces_in_sector) as u64;
 
         let plot_file_path = directory.join(Self::PLOT_FILE);
+        // Farm directory is always the first segment, the rest are stored in farm info
+        let plot_file_paths = iter::once(directory)
+            .chain(
+                info.plot_segments()
+                    .iter()
+                    .skip(1)
+                    .map(|plot_segment| plot_segment.directory.as_path()),
+            )
+            .map(|directory| directory.join(Self::PLOT_FILE))
+            .collect::<Vec<_>>();
         let plot_file = {
-            let plot_file_path = directory.join(Self::PLOT_FILE);
-            info!(path = %plot_file_path.display(), "Checking plot file");
-
-            let plot_file = match OpenOptions::new()
-                .read(true)
-                .write(!dry_run)
-                .open(&plot_file_path)
-            {
-                Ok(plot_file) => plot_file,
-                Err(error) => {
-                    return Err(if error.kind() == io::ErrorKind::NotFound {
+            let open = |plot_file_path: PathBuf| {
+                info!(path = %plot_file_path.display(), "Checking plot file");
+
+                match OpenOptions::new()
+                    .read(true)
+                    .write(!dry_run)
+                    .open(&plot_file_path)
+                {
+                    Ok(plot_file) => Ok(plot_file),
+                    Err(error) => Err(if error.kind() == io::ErrorKind::NotFound {
                         SingleDiskFarmScrubError::MetadataFileDoesNotExist {
                             file: plot_file_path,
                         }
@@ -1823,10 +3350,68 @@ impl SingleDiskFarm {
                             file: plot_file_path,
                             error,
                         }
-                    });
+                    }),
                 }
             };
 
+            let mut plot_segments = plot_file_paths
+                .iter()
+                .zip(
+                    info.plot_segments()
+                        .iter()
+                        .map(|plot_segment| u64::from(plot_segment.sector_count) * sector_size),
+                )
+                .map(|(plot_file_path, size)| Ok((open(plot_file_path.clone())?, size)))
+                .collect::<Result<Vec<_>, SingleDiskFarmScrubError>>()?;
+            let last_plot_file = match plot_segments.pop() {
+                Some((last_plot_file, _size)) => last_plot_file,
+                None => open(directory.join(Self::PLOT_FILE))?,
+            };
+            let plot_file = ConcatenatedFiles::new(plot_segments, last_plot_file)
+                .with_data_offset(plot_data_offset(farm_version));
+
+            if farm_version != LEGACY_FARM_VERSION {
+                for (file, plot_file_path) in plot_file.files().zip(&plot_file_paths) {
+                    match check_version(file, VersionedFile::Plot) {
+                        Ok(Some(_plot_version)) => {}
+                        Ok(None) => {
+                            warn!(
+                                path = %plot_file_path.display(),
+                                "Plot file version header is missing, it will be written"
+                            );
+                            farm_version_ignored = true;
+                        }
+                        Err(error) if force && !matches!(error, FarmVersionError::Io(_)) => {
+                            warn!(
+                                path = %plot_file_path.display(),
+                                %error,
+                                "Farm version check failed, scrubbing anyway since force is used"
+                            );
+                            farm_version_ignored = true;
+                        }
+                        Err(FarmVersionError::UnexpectedMagic { .. }) => {
+                            return Err(SingleDiskFarmScrubError::UnrecognizedFarmVersionHeader {
+                                file: plot_file_path.clone(),
+                            });
+                        }
+                        Err(FarmVersionError::IncompatibleVersion { found, supported }) => {
+                            return Err(SingleDiskFarmScrubError::IncompatibleFarmVersion {
+                                found,
+                                supported,
+                            });
+                        }
+                        Err(FarmVersionError::Io(error)) => {
+                            return Err(SingleDiskFarmScrubError::FailedToReadBytes {
+                                file: plot_file_path.clone(),
+                                size: PLOT_VERSION_HEADER_SIZE,
+                                offset: 0,
+                                error,
+                            });
+                        }
+                    }
+                }
+            }
+
             // Error doesn't matter here
             let _ = plot_file.advise_sequential_access();
 
@@ -1851,7 +3436,7 @@ impl SingleDiskFarm {
                 );
 
                 metadata_header.plotted_sector_count = (plot_size / sector_size) as SectorIndex;
-                let metadata_header_bytes = metadata_header.encode();
+                let metadata_header_bytes = metadata_layout.encode_header(&metadata_header);
 
                 if !dry_run {
                     if let Err(error) = metadata_file.write_all_at(&metadata_header_bytes, 0) {
@@ -1865,10 +3450,33 @@ impl SingleDiskFarm {
                 }
             }
 
-            plot_file
+            Arc::new(plot_file)
         };
+        let metadata_file = Arc::new(metadata_file);
+        let sector_reader = SectorReader::new(
+            Arc::clone(&plot_file),
+            Arc::clone(&metadata_file),
+            metadata_layout,
+            pieces_in_sector,
+            Arc::new(AtomicU16::new(metadata_header.plotted_sector_count)),
+        );
 
         let sector_bytes_range = 0..(sector_size as usize - mem::size_of::<Blake3Hash>());
+        let write_dummy_metadata = |sector_index| {
+            write_dummy_sector_metadata(
+                &metadata_file,
+                metadata_layout,
+                sector_index,
+                pieces_in_sector,
+                metadata_layout.sector_metadata_offset(sector_index),
+            )
+            .map_err(|error| SingleDiskFarmScrubError::FailedToWriteBytes {
+                file: metadata_file_path.clone(),
+                size: metadata_layout.sector_metadata_slot_size(),
+                offset: metadata_layout.sector_metadata_offset(sector_index),
+                error,
+            })
+        };
 
         info!("Checking sectors and corresponding metadata");
         (0..metadata_header.plotted_sector_count)
@@ -1878,76 +3486,24 @@ impl SingleDiskFarm {
                 |scratch_buffer, sector_index| {
                     let _span_guard = span.enter();
 
-                    let offset = RESERVED_PLOT_METADATA
-                        + u64::from(sector_index) * sector_metadata_size as u64;
-                    if let Err(error) = metadata_file
-                        .read_exact_at(&mut scratch_buffer[..sector_metadata_size], offset)
-                    {
-                        warn!(
-                            path = %metadata_file_path.display(),
-                            %error,
-                            %offset,
-                            size = %sector_metadata_size,
-                            %sector_index,
-                            "Failed to read sector metadata, replacing with dummy expired sector \
-                            metadata"
-                        );
-
-                        if !dry_run {
-                            write_dummy_sector_metadata(
-                                &metadata_file,
-                                &metadata_file_path,
-                                sector_index,
-                                pieces_in_sector,
-                            )?;
-                        }
-                        return Ok(());
-                    }
-
-                    let sector_metadata = match SectorMetadataChecksummed::decode(
-                        &mut &scratch_buffer[..sector_metadata_size],
-                    ) {
+                    let sector_metadata = match sector_reader.read_sector_metadata(sector_index) {
                         Ok(sector_metadata) => sector_metadata,
                         Err(error) => {
                             warn!(
                                 path = %metadata_file_path.display(),
                                 %error,
                                 %sector_index,
-                                "Failed to decode sector metadata, replacing with dummy expired \
+                                "Failed to read sector metadata, replacing with dummy expired \
                                 sector metadata"
                             );
 
                             if !dry_run {
-                                write_dummy_sector_metadata(
-                                    &metadata_file,
-                                    &metadata_file_path,
-                                    sector_index,
-                                    pieces_in_sector,
-                                )?;
+                                write_dummy_metadata(sector_index)?;
                             }
                             return Ok(());
                         }
                     };
 
-                    if sector_metadata.sector_index != sector_index {
-                        warn!(
-                            path = %metadata_file_path.display(),
-                            %sector_index,
-                            found_sector_index = sector_metadata.sector_index,
-                            "Sector index mismatch, replacing with dummy expired sector metadata"
-                        );
-
-                        if !dry_run {
-                            write_dummy_sector_metadata(
-                                &metadata_file,
-                                &metadata_file_path,
-                                sector_index,
-                                pieces_in_sector,
-                            )?;
-                        }
-                        return Ok(());
-                    }
-
                     if sector_metadata.pieces_in_sector != pieces_in_sector {
                         warn!(
                             path = %metadata_file_path.display(),
@@ -1959,90 +3515,54 @@ impl SingleDiskFarm {
                         );
 
                         if !dry_run {
-                            write_dummy_sector_metadata(
-                                &metadata_file,
-                                &metadata_file_path,
-                                sector_index,
-                                pieces_in_sector,
-                            )?;
+                            write_dummy_metadata(sector_index)?;
                         }
                         return Ok(());
                     }
 
-                    let mut hasher = blake3::Hasher::new();
-                    // Read sector bytes and compute checksum
-                    for offset_in_sector in sector_bytes_range.clone().step_by(scratch_buffer.len())
-                    {
-                        let offset =
-                            u64::from(sector_index) * sector_size + offset_in_sector as u64;
-                        let bytes_to_read = (offset_in_sector + scratch_buffer.len())
-                            .min(sector_bytes_range.end)
-                            - offset_in_sector;
-
-                        let bytes = &mut scratch_buffer[..bytes_to_read];
-
-                        if let Err(error) = plot_file.read_exact_at(bytes, offset) {
+                    let sector = sector_reader
+                        .read_sector(sector_index)
+                        .expect("Only plotted sectors within the plot file are checked; qed");
+                    let corrupted = match verify_sector_checksum(&sector, scratch_buffer) {
+                        Ok(None) => false,
+                        Ok(Some(SectorCorruptionDetails {
+                            actual_checksum,
+                            expected_checksum,
+                        })) => {
                             warn!(
                                 path = %plot_file_path.display(),
-                                %error,
                                 %sector_index,
-                                %offset,
-                                size = %bytes.len() as u64,
-                                "Failed to read sector bytes"
+                                actual_checksum = %hex::encode(actual_checksum),
+                                expected_checksum = %hex::encode(expected_checksum),
+                                "Plotted sector checksum mismatch, replacing with dummy expired \
+                                sector"
                             );
-
-                            continue;
+                            true
                         }
-
-                        hasher.update(bytes);
-                    }
-
-                    let actual_checksum = *hasher.finalize().as_bytes();
-                    let mut expected_checksum = [0; mem::size_of::<Blake3Hash>()];
-                    {
-                        let offset =
-                            u64::from(sector_index) * sector_size + sector_bytes_range.end as u64;
-                        if let Err(error) = plot_file.read_exact_at(&mut expected_checksum, offset)
-                        {
+                        Err(error) => {
                             warn!(
                                 path = %plot_file_path.display(),
                                 %error,
                                 %sector_index,
-                                %offset,
-                                size = %expected_checksum.len() as u64,
-                                "Failed to read sector checksum bytes"
+                                "Failed to read sector bytes, replacing with dummy expired sector"
                             );
+                            true
                         }
-                    }
-
-                    // Verify checksum
-                    if actual_checksum != expected_checksum {
-                        warn!(
-                            path = %plot_file_path.display(),
-                            %sector_index,
-                            actual_checksum = %hex::encode(actual_checksum),
-                            expected_checksum = %hex::encode(expected_checksum),
-                            "Plotted sector checksum mismatch, replacing with dummy expired sector"
-                        );
+                    };
 
+                    if corrupted {
                         if !dry_run {
-                            write_dummy_sector_metadata(
-                                &metadata_file,
-                                &metadata_file_path,
-                                sector_index,
-                                pieces_in_sector,
-                            )?;
+                            write_dummy_metadata(sector_index)?;
                         }
 
                         scratch_buffer.fill(0);
 
-                        hasher.reset();
+                        let mut hasher = blake3::Hasher::new();
                         // Fill sector with zeroes and compute checksum
                         for offset_in_sector in
                             sector_bytes_range.clone().step_by(scratch_buffer.len())
                         {
-                            let offset =
-                                u64::from(sector_index) * sector_size + offset_in_sector as u64;
+                            let offset = sector.start() + offset_in_sector as u64;
                             let bytes_to_write = (offset_in_sector + scratch_buffer.len())
                                 .min(sector_bytes_range.end)
                                 - offset_in_sector;
@@ -2064,8 +3584,7 @@ impl SingleDiskFarm {
                         // Write checksum
                         {
                             let checksum = *hasher.finalize().as_bytes();
-                            let offset = u64::from(sector_index) * sector_size
-                                + sector_bytes_range.end as u64;
+                            let offset = sector.start() + sector_bytes_range.end as u64;
                             if !dry_run {
                                 if let Err(error) = plot_file.write_all_at(&checksum, offset) {
                                     return Err(SingleDiskFarmScrubError::FailedToWriteBytes {
@@ -2213,41 +3732,471 @@ impl SingleDiskFarm {
                 })?;
         }
 
+        if farm_version_ignored && !dry_run {
+            info!(
+                path = %metadata_file_path.display(),
+                %farm_version,
+                "Replacing farm version headers"
+            );
+
+            // Metadata file header goes last, such that interrupted replacement is resumed on the
+            // next scrub or open
+            if farm_version != LEGACY_FARM_VERSION {
+                for (file, plot_file_path) in plot_file.files().zip(&plot_file_paths) {
+                    if let Err(error) = write_version(file, VersionedFile::Plot, farm_version) {
+                        return Err(SingleDiskFarmScrubError::FailedToWriteBytes {
+                            file: plot_file_path.clone(),
+                            size: PLOT_VERSION_HEADER_SIZE,
+                            offset: 0,
+                            error,
+                        });
+                    }
+                }
+            }
+            if let Err(error) =
+                write_version(&*metadata_file, VersionedFile::Metadata, farm_version)
+            {
+                return Err(SingleDiskFarmScrubError::FailedToWriteBytes {
+                    file: metadata_file_path,
+                    size: DISK_SECTOR_SIZE as u64,
+                    offset: METADATA_VERSION_HEADER_OFFSET,
+                    error,
+                });
+            }
+        }
+
         info!("Farm check completed");
 
         Ok(())
     }
+
+    /// Sequentially read the whole plot and metadata of the farm with direct I/O, verify metadata
+    /// invariants and checksums of all plotted sectors, calling `on_sector` with status of every
+    /// sector in order.
+    ///
+    /// Files are opened in read-only mode and not locked, such that scrub can run while farm is in
+    /// use (with [`ReadabilityScrubOptions::rate_limit`] to not affect proving), but sectors that
+    /// are replotted while scrub is running may be reported as corrupted.
+    pub fn readability_scrub<S>(
+        directory: &Path,
+        options: ReadabilityScrubOptions<'_>,
+        on_sector: S,
+    ) -> Result<ReadabilityScrubSummary, ReadabilityScrubError>
+    where
+        S: FnMut(SectorIndex, &SectorReadability),
+    {
+        let info = SingleDiskFarmInfo::load_from(directory)?.ok_or_else(|| {
+            ReadabilityScrubError::FarmInfoFileDoesNotExist {
+                directory: directory.to_path_buf(),
+            }
+        })?;
+        let (metadata_file, plot_file) = open_files_read_only(directory, &info)?;
+
+        readability_scrub(
+            plot_file,
+            metadata_file,
+            info.pieces_in_sector(),
+            options,
+            on_sector,
+        )
+    }
+
+    /// Benchmark reads from the plot of the farm with direct I/O using the same read pattern as
+    /// auditing and proving for [`ReadBenchmarkOptions::duration`], such that it is possible to
+    /// check whether disk is fast enough to prove in time.
+    ///
+    /// Files are opened in read-only mode and not locked, such that benchmark can run while farm
+    /// is in use, only sectors that are already plotted are read from.
+    pub fn benchmark_reads(
+        directory: &Path,
+        options: ReadBenchmarkOptions<'_>,
+    ) -> Result<ReadBenchmarkReport, ReadBenchmarkError> {
+        let info = SingleDiskFarmInfo::load_from(directory)?.ok_or_else(|| {
+            ReadBenchmarkError::FarmInfoFileDoesNotExist {
+                directory: directory.to_path_buf(),
+            }
+        })?;
+        let (metadata_file, plot_file) = open_files_read_only(directory, &info)?;
+
+        read_benchmark(
+            &plot_file,
+            plot_file.size()?,
+            &metadata_file,
+            info.pieces_in_sector(),
+            options,
+        )
+    }
 }
 
-fn write_dummy_sector_metadata(
-    metadata_file: &File,
-    metadata_file_path: &Path,
-    sector_index: SectorIndex,
-    pieces_in_sector: u16,
-) -> Result<(), SingleDiskFarmScrubError> {
-    let dummy_sector_bytes = SectorMetadataChecksummed::from(SectorMetadata {
-        sector_index,
-        pieces_in_sector,
-        s_bucket_sizes: Box::new([0; Record::NUM_S_BUCKETS]),
-        history_size: HistorySize::from(SegmentIndex::ZERO),
+fn farm_io_stats(
+    plot_file: &PlotFile,
+    metadata_file: &DirectIoFile,
+    piece_cache: &DiskPieceCache,
+) -> FarmIoStats {
+    FarmIoStats {
+        plot: FileIoStats {
+            requests: plot_file.request_stats(),
+            write_alignment: plot_file.io_stats(),
+        },
+        metadata: FileIoStats::new(metadata_file),
+        piece_cache: FileIoStats {
+            requests: piece_cache.request_stats(),
+            write_alignment: piece_cache.io_stats(),
+        },
+    }
+}
+
+fn reset_farm_io_stats(
+    plot_file: &PlotFile,
+    metadata_file: &DirectIoFile,
+    piece_cache: &DiskPieceCache,
+) {
+    plot_file.reset_io_stats();
+    metadata_file.reset_io_stats();
+    piece_cache.reset_io_stats();
+}
+
+/// Space used by the farm regardless of plot size
+fn fixed_space_usage() -> u64 {
+    RESERVED_PLOT_METADATA
+        + RESERVED_FARM_INFO
+        + Identity::file_size() as u64
+        + KnownPeersManager::file_size(KNOWN_PEERS_CACHE_SIZE) as u64
+}
+
+/// Number of sectors of `sector_size` bytes that fit into `allocated_space` of the farm directory
+/// with `cache_percentage` of it used for piece cache, returns error if not even one sector fits
+fn target_sector_count(
+    allocated_space: u64,
+    cache_percentage: NonZeroU8,
+    sector_size: u64,
+) -> Result<u64, SingleDiskFarmError> {
+    let single_sector_overhead = sector_size + MetadataLayout::CURRENT.sector_metadata_slot_size();
+    let fixed_space_usage = fixed_space_usage();
+    // Calculate how many sectors can fit
+    let target_sector_count = {
+        let potentially_plottable_space = allocated_space.saturating_sub(fixed_space_usage) / 100
+            * (100 - u64::from(cache_percentage.get()));
+        // Do the rounding to make sure we have exactly as much space as fits whole number of
+        // sectors, account for disk sector size just in case
+        potentially_plottable_space.saturating_sub(DISK_SECTOR_SIZE as u64) / single_sector_overhead
+    };
+
+    if target_sector_count == 0 {
+        let mut single_plot_with_cache_space =
+            single_sector_overhead.div_ceil(100 - u64::from(cache_percentage.get())) * 100;
+        // Cache must not be empty, ensure it contains at least one element even if
+        // percentage-wise it will use more space
+        if single_plot_with_cache_space - single_sector_overhead
+            < DiskPieceCache::element_size() as u64
+        {
+            single_plot_with_cache_space =
+                single_sector_overhead + DiskPieceCache::element_size() as u64;
+        }
+
+        return Err(SingleDiskFarmError::InsufficientAllocatedSpace {
+            min_space: fixed_space_usage + single_plot_with_cache_space,
+            allocated_space,
+        });
+    }
+
+    Ok(target_sector_count)
+}
+
+/// Open metadata and plot files of the farm in read-only mode without locking them
+fn open_files_read_only(
+    directory: &Path,
+    info: &SingleDiskFarmInfo,
+) -> io::Result<(DirectIoFile, PlotFile)> {
+    let sector_size = sector_size(info.pieces_in_sector()) as u64;
+
+    // Farm directory is always the first segment, the rest are stored in farm info, size of the
+    // last segment doesn't matter
+    let mut plot_layout = info.plot_segments().to_vec();
+    match plot_layout.first_mut() {
+        Some(plot_segment) => {
+            plot_segment.directory = directory.to_path_buf();
+        }
+        None => {
+            plot_layout.push(PlotSegmentInfo {
+                directory: directory.to_path_buf(),
+                sector_count: 0,
+            });
+        }
+    }
+
+    let direct_io_options = DirectIoOptions {
+        read_only: true,
+        ..DirectIoOptions::default()
+    };
+    let io_request_limiter =
+        IoRequestLimiter::for_path(directory, direct_io_options.max_io_requests);
+    let metadata_file = DirectIoFile::open_with_limiter(
+        &directory.join(SingleDiskFarm::METADATA_FILE),
+        direct_io_options,
+        None,
+        Some(io_request_limiter.clone()),
+    )?;
+    let plot_file = open_plot_file(
+        &plot_layout,
+        sector_size,
+        read_plot_data_offset(&metadata_file)?,
+        |_segment_index, plot_file_path| {
+            DirectIoFile::open_with_limiter(
+                plot_file_path,
+                direct_io_options,
+                None,
+                Some(io_request_limiter.clone()),
+            )
+        },
+    )?;
+
+    Ok((metadata_file, plot_file))
+}
+
+/// Offset of plotted sectors in every plot file of the farm with `metadata_file`, see
+/// [`plot_data_offset()`]
+fn read_plot_data_offset<F>(metadata_file: &F) -> io::Result<u64>
+where
+    F: FileExt,
+{
+    let metadata_version =
+        check_version(metadata_file, VersionedFile::Metadata).map_err(|error| match error {
+            FarmVersionError::Io(error) => error,
+            error => io::Error::new(io::ErrorKind::InvalidData, error),
+        })?;
+
+    let mut metadata_header_bytes = vec![0; PlotMetadataHeader::encoded_size()];
+    metadata_file.read_exact_at(&mut metadata_header_bytes, 0)?;
+    let metadata_header =
+        PlotMetadataHeader::decode(&mut metadata_header_bytes.as_ref()).map_err(|error| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Failed to decode metadata header: {}", error),
+            )
+        })?;
+
+    Ok(plot_data_offset(resolve_farm_version(
+        metadata_version,
+        metadata_header.plotted_sector_count,
+    )))
+}
+
+/// Open file of the farm, failures due to [`DirectIoOptions::open_mode`] are turned into
+/// [`SingleDiskFarmError::FarmFileAlreadyExists`] and [`SingleDiskFarmError::FarmFileMissing`]
+fn open_farm_file(
+    path: &Path,
+    direct_io_options: DirectIoOptions,
+    metrics: Option<Arc<FileMetrics>>,
+    io_request_limiter: &IoRequestLimiter,
+) -> Result<DirectIoFile, SingleDiskFarmError> {
+    DirectIoFile::open_with_limiter(
+        path,
+        direct_io_options,
+        metrics,
+        Some(io_request_limiter.clone()),
+    )
+    .map_err(|error| match (direct_io_options.open_mode, error.kind()) {
+        (OpenMode::CreateNew, io::ErrorKind::AlreadyExists) => {
+            SingleDiskFarmError::FarmFileAlreadyExists {
+                path: path.to_path_buf(),
+            }
+        }
+        (OpenMode::OpenExisting, io::ErrorKind::NotFound) => SingleDiskFarmError::FarmFileMissing {
+            path: path.to_path_buf(),
+        },
+        _ => SingleDiskFarmError::Io(error),
     })
-    .encode();
-    let sector_offset = RESERVED_PLOT_METADATA
-        + u64::from(sector_index) * SectorMetadataChecksummed::encoded_size() as u64;
-    metadata_file
-        .write_all_at(&dummy_sector_bytes, sector_offset)
-        .map_err(|error| SingleDiskFarmScrubError::FailedToWriteBytes {
-            file: metadata_file_path.to_path_buf(),
-            size: dummy_sector_bytes.len() as u64,
-            offset: sector_offset,
-            error,
+}
+
+/// Turn error of version check of farm file at `path` into [`SingleDiskFarmError`]
+fn farm_version_error(error: FarmVersionError, path: &Path) -> SingleDiskFarmError {
+    match error {
+        FarmVersionError::UnexpectedMagic { .. } => {
+            SingleDiskFarmError::UnrecognizedFarmVersionHeader {
+                path: path.to_path_buf(),
+            }
+        }
+        FarmVersionError::IncompatibleVersion { found, supported } => {
+            SingleDiskFarmError::IncompatibleFarmVersion { found, supported }
+        }
+        FarmVersionError::Io(error) => SingleDiskFarmError::Io(error),
+    }
+}
+
+/// Open (creating if necessary) lock file in farm `directory`, see [`SingleDiskFarm::LOCK_FILE`]
+fn open_lock_file(directory: &Path) -> io::Result<(File, PathBuf)> {
+    let path = directory.join(SingleDiskFarm::LOCK_FILE);
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&path)?;
+
+    Ok((file, path))
+}
+
+/// Acquire exclusive lock on lock file in farm (or plot segment) `directory`, lock contention
+/// means farm is used by another process
+pub(crate) fn lock_farm_directory(directory: &Path) -> Result<FarmLock, SingleDiskFarmError> {
+    let (file, path) = open_lock_file(directory)?;
+    file.try_lock_exclusive().map_err(|error| {
+        if error.kind() == io::ErrorKind::WouldBlock {
+            SingleDiskFarmError::FarmAlreadyInUse { path }
+        } else {
+            SingleDiskFarmError::Io(error)
+        }
+    })?;
+
+    Ok(FarmLock { _file: file })
+}
+
+/// Check that `plot_layout` farm is opened with is compatible with `stored_plot_layout` farm was
+/// created with: additional segments must be the same directories in the same order, their plot
+/// files must exist and only the last segment can be resized.
+///
+/// Farm directory itself is not compared, such that farm can be moved.
+fn check_plot_layout(
+    id: &FarmId,
+    stored_plot_layout: &[PlotSegmentInfo],
+    plot_layout: &[PlotSegmentInfo],
+) -> Result<(), SingleDiskFarmError> {
+    // Farm stored in farm directory only can be resized and extended with additional segments
+    let Some((_, stored_plot_segments)) = stored_plot_layout.split_first() else {
+        return Ok(());
+    };
+    let plot_segments = plot_layout.get(1..).unwrap_or_default();
+
+    if !stored_plot_segments
+        .iter()
+        .map(|plot_segment| &plot_segment.directory)
+        .eq(plot_segments
+            .iter()
+            .map(|plot_segment| &plot_segment.directory))
+    {
+        return Err(SingleDiskFarmError::PlotSegmentsMismatch {
+            id: *id,
+            expected: stored_plot_segments
+                .iter()
+                .map(|plot_segment| plot_segment.directory.clone())
+                .collect(),
+            actual: plot_segments
+                .iter()
+                .map(|plot_segment| plot_segment.directory.clone())
+                .collect(),
+        });
+    }
+
+    for plot_segment in stored_plot_segments {
+        if !plot_segment
+            .directory
+            .join(SingleDiskFarm::PLOT_FILE)
+            .exists()
+        {
+            return Err(SingleDiskFarmError::PlotSegmentMissing {
+                id: *id,
+                directory: plot_segment.directory.clone(),
+            });
+        }
+    }
+
+    // Everything except the last segment must have the same size
+    for (stored_plot_segment, plot_segment) in stored_plot_layout
+        .iter()
+        .zip(plot_layout)
+        .take(plot_layout.len() - 1)
+    {
+        if stored_plot_segment.sector_count != plot_segment.sector_count {
+            return Err(SingleDiskFarmError::PlotSegmentResized {
+                id: *id,
+                directory: plot_segment.directory.clone(),
+                sector_count: stored_plot_segment.sector_count,
+                new_sector_count: plot_segment.sector_count,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Open plot file that spans all segments of `plot_layout` with sectors starting at `data_offset`
+/// of each segment, plot file of each segment is opened with `open_file` that receives index of the
+/// segment and path of the file
+fn open_plot_file<O, E>(
+    plot_layout: &[PlotSegmentInfo],
+    sector_size: u64,
+    data_offset: u64,
+    open_file: O,
+) -> Result<PlotFile, E>
+where
+    O: Fn(usize, &Path) -> Result<DirectIoFile, E>,
+{
+    let open = |segment_index: usize, plot_segment: &PlotSegmentInfo| {
+        open_file(
+            segment_index,
+            &plot_segment.directory.join(SingleDiskFarm::PLOT_FILE),
+        )
+        .map(Arc::new)
+    };
+
+    let (last_plot_segment, plot_segments) = plot_layout
+        .split_last()
+        .expect("Plot layout always contains farm directory; qed");
+    let segments = plot_segments
+        .iter()
+        .enumerate()
+        .map(|(segment_index, plot_segment)| {
+            Ok((
+                open(segment_index, plot_segment)?,
+                u64::from(plot_segment.sector_count) * sector_size,
+            ))
         })
+        .collect::<Result<Vec<_>, E>>()?;
+
+    Ok(
+        ConcatenatedFiles::new(segments, open(plot_segments.len(), last_plot_segment)?)
+            .with_data_offset(data_offset),
+    )
+}
+
+/// Check that plot file of each segment of `plot_layout` is large enough to contain sectors that
+/// are plotted according to metadata header (after [`ConcatenatedFiles::data_offset()`])
+fn check_plot_file_size(
+    plot_file: &PlotFile,
+    plot_layout: &[PlotSegmentInfo],
+    sector_size: u64,
+    plotted_sector_count: SectorIndex,
+) -> Result<(), SingleDiskFarmError> {
+    let mut first_sector_index = 0;
+    for (file, plot_segment) in plot_file.files().zip(plot_layout) {
+        let plotted_sectors_in_segment = plotted_sector_count
+            .saturating_sub(first_sector_index)
+            .min(plot_segment.sector_count);
+        let expected_size = if plotted_sectors_in_segment == 0 {
+            0
+        } else {
+            plot_file.data_offset() + u64::from(plotted_sectors_in_segment) * sector_size
+        };
+        let actual_size = file.size()?;
+        if actual_size < expected_size {
+            return Err(SingleDiskFarmError::FarmFileSizeMismatch {
+                path: plot_segment.directory.join(SingleDiskFarm::PLOT_FILE),
+                plotted_sector_count,
+                expected_size,
+                actual_size,
+            });
+        }
+
+        first_sector_index = first_sector_index.saturating_add(plot_segment.sector_count);
+    }
+
+    Ok(())
 }
 
-fn faster_read_sector_record_chunks_mode<OP, FP>(
+fn faster_read_sector_record_chunks_mode<OP, FP, MF>(
     original_plot: &OP,
-    farming_plot: &FP,
-    sector_size: usize,
+    farming_sector_reader: &SectorReader<FP, MF>,
     mut plotted_sector_count: SectorIndex,
 ) -> Result<ReadSectorRecordChunksMode, SingleDiskFarmError>
 where
@@ -2256,6 +4205,7 @@ where
 {
     info!("Benchmarking faster proving method");
 
+    let sector_size = farming_sector_reader.sector_size() as usize;
     let mut sector_bytes = vec![0u8; sector_size];
 
     if plotted_sector_count == 0 {
@@ -2269,9 +4219,9 @@ where
     let mut fastest_time = Duration::MAX;
 
     for _ in 0..3 {
-        let sector_offset =
-            sector_size as u64 * thread_rng().gen_range(0..plotted_sector_count) as u64;
-        let farming_plot = farming_plot.offset(sector_offset);
+        let farming_plot = farming_sector_reader
+            .read_sector(thread_rng().gen_range(0..plotted_sector_count))
+            .map_err(io::Error::from)?;
 
         // Reading the whole sector at once
         {
@@ -0,0 +1,509 @@
+//! Multiple files concatenated into one logical file, such that a single plot can span multiple
+//! smaller disks
+
+#[cfg(test)]
+mod tests;
+
+use crate::single_disk_farm::direct_io_file::{DirectIoFile, IoPriority, IoStats, RequestStats};
+use std::borrow::Borrow;
+use std::io;
+use std::mem::MaybeUninit;
+use std::ops::{Add, Range};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use subspace_farmer_components::file_ext::{AccessPattern, FileExt, IoGeometry};
+use subspace_farmer_components::read_plan::ReadAlignment;
+use subspace_farmer_components::{assume_init_bytes, ReadAtSync, WriteAtSync};
+
+/// Geometry that satisfies constraints of both `a` and `b`
+fn strictest_geometry(a: IoGeometry, b: IoGeometry) -> IoGeometry {
+    IoGeometry {
+        logical_sector_size: a.logical_sector_size.max(b.logical_sector_size),
+        physical_sector_size: a.physical_sector_size.max(b.physical_sector_size),
+        max_request_size: a.max_request_size.min(b.max_request_size),
+    }
+}
+
+#[derive(Debug)]
+struct Segment<F> {
+    file: F,
+    /// Offset of the segment in concatenated file
+    offset: u64,
+    /// Size of the segment, `None` for the last segment that can be resized
+    size: Option<u64>,
+}
+
+/// Ordered list of files concatenated into one logical file.
+///
+/// Offsets are translated into offsets within corresponding segment and access crossing segment
+/// boundary is split between segments. All segments except the last one have fixed size, only the
+/// last segment can be resized. Data of every segment file may start at non-zero offset (see
+/// [`ConcatenatedFiles::with_data_offset()`]), space before it is not accessible through
+/// concatenated file.
+#[derive(Debug)]
+pub struct ConcatenatedFiles<F> {
+    segments: Vec<Segment<F>>,
+    data_offset: u64,
+}
+
+impl<F> ReadAtSync for ConcatenatedFiles<F>
+where
+    F: ReadAtSync,
+{
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        self.for_each_part(offset, buf.len(), |file, offset, range| {
+            file.read_at(&mut buf[range], offset)
+        })
+    }
+
+    fn read_at_uninit<'a>(
+        &self,
+        buf: &'a mut [MaybeUninit<u8>],
+        offset: u64,
+    ) -> io::Result<&'a mut [u8]> {
+        self.for_each_part(offset, buf.len(), |file, offset, range| {
+            file.read_at_uninit(&mut buf[range], offset)
+                .map(|_bytes| ())
+        })?;
+
+        // SAFETY: Every part of the buffer was initialized by successful reads above
+        Ok(unsafe { assume_init_bytes(buf) })
+    }
+
+    /// Strictest alignment of all segments relative to the start of concatenated file, `None` if
+    /// alignment of any segment is unknown
+    fn read_alignment(&self) -> Option<ReadAlignment> {
+        let geometry = self
+            .segments
+            .iter()
+            .map(|segment| {
+                segment
+                    .file
+                    .read_alignment()
+                    .map(|alignment| alignment.geometry)
+            })
+            .reduce(|a, b| Some(strictest_geometry(a?, b?)))??;
+
+        Some(ReadAlignment {
+            geometry,
+            offset: self.data_offset,
+        })
+    }
+}
+
+impl<F> ReadAtSync for &ConcatenatedFiles<F>
+where
+    F: ReadAtSync,
+{
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        (**self).read_at(buf, offset)
+    }
+
+    fn read_at_uninit<'a>(
+        &self,
+        buf: &'a mut [MaybeUninit<u8>],
+        offset: u64,
+    ) -> io::Result<&'a mut [u8]> {
+        (**self).read_at_uninit(buf, offset)
+    }
+
+    fn read_alignment(&self) -> Option<ReadAlignment> {
+        (**self).read_alignment()
+    }
+}
+
+impl<F> WriteAtSync for ConcatenatedFiles<F>
+where
+    F: WriteAtSync,
+{
+    fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        self.for_each_part(offset, buf.len(), |file, offset, range| {
+            file.write_all_at(&buf[range], offset)
+        })
+    }
+
+    fn sync_data(&self) -> io::Result<()> {
+        self.segments
+            .iter()
+            .try_for_each(|segment| segment.file.sync_data())
+    }
+}
+
+impl<F> FileExt for ConcatenatedFiles<F>
+where
+    F: FileExt,
+{
+    /// Size of the prefix of concatenated file that is backed by segment files, such that
+    /// incompletely allocated segment is treated as the end of the file
+    fn size(&self) -> io::Result<u64> {
+        for segment in &self.segments {
+            let file_size = segment.file.size()?.saturating_sub(self.data_offset);
+
+            match segment.size {
+                Some(size) if file_size >= size => {
+                    // Segment is complete, continue with the next one
+                }
+                _ => {
+                    return Ok(segment.offset + file_size);
+                }
+            }
+        }
+
+        unreachable!("Last segment doesn't have size and always returns above; qed")
+    }
+
+    /// Fails without allocating anything if `len` is smaller than offset of the last segment,
+    /// since only the last segment can be resized
+    fn preallocate(&self, len: u64) -> io::Result<()> {
+        self.check_len(len)?;
+
+        for segment in &self.segments {
+            // Can't underflow since `len` is not smaller than offset of the last segment
+            let segment_len = len - segment.offset;
+            let segment_len = match segment.size {
+                Some(size) => segment_len.min(size),
+                None => segment_len,
+            };
+
+            // Empty allocation is not supported on some platforms
+            if segment_len > 0 {
+                segment.file.preallocate(self.data_offset + segment_len)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Strictest geometry of all segments
+    fn geometry(&self) -> IoGeometry {
+        self.segments
+            .iter()
+            .map(|segment| segment.file.geometry())
+            .reduce(strictest_geometry)
+            .expect("There is always at least the last segment; qed")
+    }
+
+    fn advise_random_access(&self) -> io::Result<()> {
+        self.segments
+            .iter()
+            .try_for_each(|segment| segment.file.advise_random_access())
+    }
+
+    fn advise_sequential_access(&self) -> io::Result<()> {
+        self.segments
+            .iter()
+            .try_for_each(|segment| segment.file.advise_sequential_access())
+    }
+
+    fn set_access_pattern(&self, access_pattern: AccessPattern) -> io::Result<()> {
+        self.segments
+            .iter()
+            .try_for_each(|segment| segment.file.set_access_pattern(access_pattern))
+    }
+
+    /// Locks all segments
+    fn try_lock_exclusive(&self) -> io::Result<()> {
+        self.segments
+            .iter()
+            .try_for_each(|segment| segment.file.try_lock_exclusive())
+    }
+
+    /// Locks all segments
+    fn lock_shared(&self) -> io::Result<()> {
+        self.segments
+            .iter()
+            .try_for_each(|segment| segment.file.lock_shared())
+    }
+
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        self.for_each_part(offset, buf.len(), |file, offset, range| {
+            file.read_exact_at(&mut buf[range], offset)
+        })
+    }
+
+    fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        self.for_each_part(offset, buf.len(), |file, offset, range| {
+            file.write_all_at(&buf[range], offset)
+        })
+    }
+
+    /// Range crossing segment boundary is split between segments, each of which may round its
+    /// part inward separately
+    fn punch_hole(&self, offset: u64, len: u64) -> io::Result<()> {
+        let len = usize::try_from(len).map_err(|_error| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Range of {len} bytes at offset {offset} is too large"),
+            )
+        })?;
+
+        self.for_each_part(offset, len, |file, offset, range| {
+            file.punch_hole(offset, range.len() as u64)
+        })
+    }
+
+    fn sync_all(&self) -> io::Result<()> {
+        self.segments
+            .iter()
+            .try_for_each(|segment| segment.file.sync_all())
+    }
+
+    fn sync_data(&self) -> io::Result<()> {
+        self.segments
+            .iter()
+            .try_for_each(|segment| segment.file.sync_data())
+    }
+}
+
+impl<F> ConcatenatedFiles<F> {
+    /// Concatenate `segments` of fixed size in bytes, followed by `last` file that can be resized
+    pub fn new(segments: Vec<(F, u64)>, last: F) -> Self {
+        let mut offset = 0;
+        let mut segments = segments
+            .into_iter()
+            .map(|(file, size)| {
+                let segment = Segment {
+                    file,
+                    offset,
+                    size: Some(size),
+                };
+                offset += size;
+                segment
+            })
+            .collect::<Vec<_>>();
+        segments.push(Segment {
+            file: last,
+            offset,
+            size: None,
+        });
+
+        Self {
+            segments,
+            data_offset: 0,
+        }
+    }
+
+    /// Data of every segment file starts at `data_offset`, such that the beginning of the file can
+    /// be used for something else (like a header)
+    pub fn with_data_offset(mut self, data_offset: u64) -> Self {
+        self.data_offset = data_offset;
+        self
+    }
+
+    /// Offset at which data of every segment file starts
+    pub fn data_offset(&self) -> u64 {
+        self.data_offset
+    }
+
+    /// Number of segments
+    pub fn segments_count(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// Files of all segments in order
+    pub fn files(&self) -> impl ExactSizeIterator<Item = &F> {
+        self.segments.iter().map(|segment| &segment.file)
+    }
+
+    /// File if concatenated file consists of a single segment
+    pub fn single_file(&self) -> Option<&F> {
+        match self.segments.as_slice() {
+            [segment] => Some(&segment.file),
+            _ => None,
+        }
+    }
+
+    /// Offset of the last segment, concatenated file can't be smaller than this
+    pub fn last_segment_offset(&self) -> u64 {
+        self.last_segment().offset
+    }
+
+    fn last_segment(&self) -> &Segment<F> {
+        self.segments
+            .last()
+            .expect("There is always at least the last segment; qed")
+    }
+
+    fn check_len(&self, len: u64) -> io::Result<()> {
+        let last_segment_offset = self.last_segment_offset();
+        if len < last_segment_offset {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Length {len} is smaller than offset {last_segment_offset} of the last \
+                    segment, only the last segment can be resized"
+                ),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Calls `f` with segment file, offset within segment file and range within the buffer for
+    /// every part of access of `len` bytes at `offset`, splitting access on segment boundaries
+    fn for_each_part<Part>(&self, offset: u64, len: usize, mut f: Part) -> io::Result<()>
+    where
+        Part: FnMut(&F, u64, Range<usize>) -> io::Result<()>,
+    {
+        // The first segment always has offset `0`, so there is at least one matching segment
+        let mut segment_index = self
+            .segments
+            .partition_point(|segment| segment.offset <= offset)
+            - 1;
+        let mut done = 0;
+
+        while done < len {
+            let segment = &self.segments[segment_index];
+            let offset_in_segment = offset + done as u64 - segment.offset;
+            let part_len = match segment.size {
+                Some(size) => (size - offset_in_segment).min((len - done) as u64) as usize,
+                None => len - done,
+            };
+
+            if part_len > 0 {
+                f(
+                    &segment.file,
+                    self.data_offset + offset_in_segment,
+                    done..done + part_len,
+                )?;
+            }
+
+            done += part_len;
+            segment_index += 1;
+        }
+
+        Ok(())
+    }
+}
+
+impl<F> ConcatenatedFiles<F>
+where
+    F: Borrow<DirectIoFile>,
+{
+    /// Truncate or extend the last segment such that concatenated file is `len` bytes long, fails
+    /// if `len` is smaller than offset of the last segment, since only the last segment can be
+    /// resized
+    pub fn set_len(&self, len: u64) -> io::Result<()> {
+        self.check_len(len)?;
+
+        let last_segment = self.last_segment();
+        last_segment
+            .file
+            .borrow()
+            .set_len(self.data_offset + len - last_segment.offset)
+    }
+
+    /// Sum of I/O stats of all segments, see [`DirectIoFile::io_stats()`]
+    pub fn io_stats(&self) -> IoStats {
+        self.segments
+            .iter()
+            .map(|segment| segment.file.borrow().io_stats())
+            .fold(IoStats::default(), Add::add)
+    }
+
+    /// Sum of request stats of all segments, see [`DirectIoFile::request_stats()`]
+    pub fn request_stats(&self) -> RequestStats {
+        self.segments
+            .iter()
+            .map(|segment| segment.file.borrow().request_stats())
+            .fold(RequestStats::default(), Add::add)
+    }
+
+    /// Reset I/O stats of all segments, see [`DirectIoFile::reset_io_stats()`]
+    pub fn reset_io_stats(&self) {
+        for segment in self.segments.iter() {
+            segment.file.borrow().reset_io_stats();
+        }
+    }
+
+    /// Clone every segment with [`DirectIoFile::try_clone()`]
+    pub fn try_clone(&self) -> io::Result<ConcatenatedFiles<Arc<DirectIoFile>>> {
+        self.map_segments(DirectIoFile::try_clone)
+    }
+
+    /// Open every segment again with [`DirectIoFile::reopen()`]
+    pub fn reopen(&self) -> io::Result<ConcatenatedFiles<Arc<DirectIoFile>>> {
+        self.map_segments(DirectIoFile::reopen)
+    }
+
+    fn map_segments<M>(&self, map: M) -> io::Result<ConcatenatedFiles<Arc<DirectIoFile>>>
+    where
+        M: Fn(&DirectIoFile) -> io::Result<DirectIoFile>,
+    {
+        let segments = self
+            .segments
+            .iter()
+            .map(|segment| {
+                Ok(Segment {
+                    file: Arc::new(map(segment.file.borrow())?),
+                    offset: segment.offset,
+                    size: segment.size,
+                })
+            })
+            .collect::<io::Result<_>>()?;
+
+        Ok(ConcatenatedFiles {
+            segments,
+            data_offset: self.data_offset,
+        })
+    }
+
+    /// Whether all segments use direct/unbuffered I/O
+    pub fn is_unbuffered(&self) -> bool {
+        self.segments
+            .iter()
+            .all(|segment| segment.file.borrow().is_unbuffered())
+    }
+
+    /// Close every segment gracefully within `timeout`, see [`DirectIoFile::close()`], segments
+    /// are closed even if closing of some of them fails, the first error is returned
+    pub fn close(&self, timeout: Duration) -> io::Result<()> {
+        let deadline = Instant::now() + timeout;
+        self.segments
+            .iter()
+            .map(|segment| {
+                segment
+                    .file
+                    .borrow()
+                    .close(deadline.saturating_duration_since(Instant::now()))
+            })
+            .fold(Ok(()), Result::and)
+    }
+
+    /// Set priority of requests of every segment, see [`DirectIoFile::set_io_priority()`]
+    pub fn set_io_priority(&self, io_priority: IoPriority) {
+        for segment in &self.segments {
+            segment.file.borrow().set_io_priority(io_priority);
+        }
+    }
+
+    /// Read `N` bytes at `offset` with fast path of small reads, see
+    /// [`DirectIoFile::read_small_at()`]
+    pub fn read_small_at<const N: usize>(&self, buf: &mut [u8; N], offset: u64) -> io::Result<()> {
+        self.read_small(buf, offset)
+    }
+
+    /// Same as [`Self::read_small_at()`], but for buffers of size not known at compile time
+    pub(crate) fn read_small(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        self.for_each_part(offset, buf.len(), |file, offset, range| {
+            file.borrow().read_small(&mut buf[range], offset)
+        })
+    }
+
+    /// Same as [`Self::read_small()`], but reads into uninitialized buffer and returns it as
+    /// initialized slice, see [`ReadAtSync::read_at_uninit()`]
+    pub(crate) fn read_small_uninit<'a>(
+        &self,
+        buf: &'a mut [MaybeUninit<u8>],
+        offset: u64,
+    ) -> io::Result<&'a mut [u8]> {
+        self.for_each_part(offset, buf.len(), |file, offset, range| {
+            file.borrow()
+                .read_small_uninit(&mut buf[range], offset)
+                .map(|_bytes| ())
+        })?;
+
+        // SAFETY: Every part of the buffer was initialized by successful reads above
+        Ok(unsafe { assume_init_bytes(buf) })
+    }
+}
@@ -0,0 +1,184 @@
+use crate::single_disk_farm::concatenated_files::ConcatenatedFiles;
+use crate::single_disk_farm::direct_io_file::DirectIoFile;
+use rand::prelude::*;
+use subspace_farmer_components::file_ext::FileExt;
+use subspace_farmer_components::in_memory_file::InMemoryFile;
+use subspace_farmer_components::{ReadAtSync, WriteAtSync};
+use tempfile::tempdir;
+
+#[test]
+fn boundary_crossing() {
+    let mut bytes = vec![0u8; 40];
+    thread_rng().fill(bytes.as_mut_slice());
+
+    // Empty segment in the middle must be skipped
+    let files = ConcatenatedFiles::new(
+        vec![
+            (InMemoryFile::from(bytes[..10].to_vec()), 10),
+            (InMemoryFile::default(), 0),
+            (InMemoryFile::from(bytes[10..25].to_vec()), 15),
+        ],
+        InMemoryFile::from(bytes[25..].to_vec()),
+    );
+    assert_eq!(files.segments_count(), 4);
+    assert_eq!(files.last_segment_offset(), 25);
+
+    for (offset, len) in [
+        (0, 10),
+        (5, 10),
+        (9, 2),
+        (10, 15),
+        (8, 20),
+        (0, 40),
+        (30, 10),
+    ] {
+        let mut buf = vec![0; len];
+        files.read_at(&mut buf, offset as u64).unwrap();
+        assert_eq!(buf, bytes[offset..][..len], "offset {offset}, len {len}");
+    }
+
+    // Reading beyond the end of the last segment fails
+    assert!(files.read_at(&mut [0; 2], 39).is_err());
+
+    // Write crossing all segments at once
+    let mut new_bytes = vec![0u8; 30];
+    thread_rng().fill(new_bytes.as_mut_slice());
+    WriteAtSync::write_all_at(&files, &new_bytes, 5).unwrap();
+    bytes[5..35].copy_from_slice(&new_bytes);
+
+    let segment_bytes = files.files().map(InMemoryFile::bytes).collect::<Vec<_>>();
+    assert_eq!(segment_bytes[0], bytes[..10]);
+    assert!(segment_bytes[1].is_empty());
+    assert_eq!(segment_bytes[2], bytes[10..25]);
+    assert_eq!(segment_bytes[3], bytes[25..]);
+
+    // Writes beyond the end grow the last segment only
+    WriteAtSync::write_all_at(&files, &[1; 5], 40).unwrap();
+    assert_eq!(files.files().last().unwrap().bytes().len(), 20);
+
+    WriteAtSync::sync_data(&files).unwrap();
+    assert!(files.files().all(|file| file.syncs() == 1));
+}
+
+#[test]
+fn read_at_uninit() {
+    let mut bytes = vec![0u8; 40];
+    thread_rng().fill(bytes.as_mut_slice());
+
+    let files = ConcatenatedFiles::new(
+        vec![
+            (InMemoryFile::from(bytes[..10].to_vec()), 10),
+            (InMemoryFile::from(bytes[10..25].to_vec()), 15),
+        ],
+        InMemoryFile::from(bytes[25..].to_vec()),
+    );
+
+    for (offset, len) in [(0, 10), (8, 20), (0, 40), (39, 1), (5, 0)] {
+        let mut memory = Vec::<u8>::with_capacity(len);
+        let read = files
+            .read_at_uninit(&mut memory.spare_capacity_mut()[..len], offset as u64)
+            .unwrap();
+        assert_eq!(read, &bytes[offset..][..len], "offset {offset}, len {len}");
+
+        // Same result as initialized path
+        let mut buf = vec![0; len];
+        files.read_at(&mut buf, offset as u64).unwrap();
+        assert_eq!(read, buf.as_slice());
+    }
+
+    // Reading beyond the end of the last segment fails
+    let mut memory = Vec::<u8>::with_capacity(2);
+    assert!(files
+        .read_at_uninit(&mut memory.spare_capacity_mut()[..2], 39)
+        .is_err());
+}
+
+#[test]
+fn resizing() {
+    let tempdir = tempdir().unwrap();
+    let open = |name: &str| DirectIoFile::open(&tempdir.as_ref().join(name)).unwrap();
+
+    let files = ConcatenatedFiles::new(
+        vec![(open("0.bin"), 4096), (open("1.bin"), 8192)],
+        open("2.bin"),
+    );
+    assert_eq!(files.size().unwrap(), 0);
+
+    // Partially allocated segment is the end of the file
+    files.preallocate(6000).unwrap();
+    assert_eq!(files.size().unwrap(), 6000);
+
+    files.preallocate(20_000).unwrap();
+    assert_eq!(files.size().unwrap(), 20_000);
+    let sizes = files
+        .files()
+        .map(|file| file.size().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(sizes, [4096, 8192, 20_000 - 4096 - 8192]);
+
+    let mut bytes = vec![0u8; 10_000];
+    thread_rng().fill(bytes.as_mut_slice());
+    FileExt::write_all_at(&files, &bytes, 1000).unwrap();
+    let mut read_bytes = vec![0u8; 10_000];
+    files.read_exact_at(&mut read_bytes, 1000).unwrap();
+    assert_eq!(read_bytes, bytes);
+
+    // Only the last segment can be resized
+    files.set_len(4096 + 8192).unwrap();
+    assert_eq!(files.size().unwrap(), 4096 + 8192);
+    assert!(files.set_len(4096 + 8191).is_err());
+    assert!(files.preallocate(100).is_err());
+    assert_eq!(files.size().unwrap(), 4096 + 8192);
+
+    // Data in non-last segments is not affected by resizing
+    let mut read_bytes = vec![0u8; 4096 + 8192 - 1000];
+    files.read_exact_at(&mut read_bytes, 1000).unwrap();
+    assert_eq!(read_bytes, bytes[..read_bytes.len()]);
+}
+
+#[test]
+fn data_offset() {
+    let tempdir = tempdir().unwrap();
+    let open = |name: &str| DirectIoFile::open(&tempdir.as_ref().join(name)).unwrap();
+
+    let files =
+        ConcatenatedFiles::new(vec![(open("0.bin"), 8192)], open("1.bin")).with_data_offset(4096);
+    assert_eq!(files.data_offset(), 4096);
+    assert_eq!(files.size().unwrap(), 0);
+
+    // Space before data offset is allocated in addition to requested size
+    files.preallocate(10_000).unwrap();
+    assert_eq!(files.size().unwrap(), 10_000);
+    let sizes = files
+        .files()
+        .map(|file| file.size().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(sizes, [4096 + 8192, 4096 + 10_000 - 8192]);
+
+    // Headers before data offset are not affected by access through concatenated file
+    for file in files.files() {
+        file.write_all_at(&[1; 4096], 0).unwrap();
+    }
+    let mut bytes = vec![0u8; 10_000];
+    thread_rng().fill(bytes.as_mut_slice());
+    FileExt::write_all_at(&files, &bytes, 0).unwrap();
+    let mut read_bytes = vec![0u8; 10_000];
+    files.read_exact_at(&mut read_bytes, 0).unwrap();
+    assert_eq!(read_bytes, bytes);
+    for (file, data) in files.files().zip([&bytes[..8192], &bytes[8192..]]) {
+        let mut header = vec![0u8; 4096];
+        file.read_exact_at(&mut header, 0).unwrap();
+        assert_eq!(header, [1; 4096]);
+
+        let mut read_data = vec![0u8; data.len()];
+        file.read_exact_at(&mut read_data, 4096).unwrap();
+        assert_eq!(read_data, data);
+    }
+
+    files.set_len(9000).unwrap();
+    assert_eq!(files.size().unwrap(), 9000);
+    assert_eq!(
+        files.files().last().unwrap().size().unwrap(),
+        4096 + 9000 - 8192
+    );
+}
@@ -0,0 +1,4958 @@
+//! Direct I/O file abstraction that bypasses OS page cache, see [`DirectIoFile`]
+
+pub mod async_file;
+mod buffered;
+pub mod device_profile;
+#[cfg(target_os = "linux")]
+mod linux;
+pub mod memory_budget;
+pub mod metrics;
+#[cfg(windows)]
+mod windows;
+
+#[cfg(not(any(target_os = "linux", windows)))]
+use self::buffered as backend;
+#[cfg(target_os = "linux")]
+use self::linux as backend;
+#[cfg(windows)]
+use self::windows as backend;
+use crate::single_disk_farm::direct_io_file::device_profile::DeviceProfile;
+use crate::single_disk_farm::direct_io_file::memory_budget::{
+    IoMemoryBudget, IoMemoryReservation, SharedScratchBuffer,
+};
+use crate::single_disk_farm::direct_io_file::metrics::FileMetrics;
+use async_lock::{Semaphore, SemaphoreGuard};
+use parking_lot::{Condvar, Mutex};
+use static_assertions::const_assert_eq;
+use std::cell::RefCell;
+use std::fs::{File, OpenOptions};
+use std::mem::MaybeUninit;
+use std::num::{NonZeroU64, NonZeroUsize};
+use std::ops::{Add, Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+use std::{io, mem, slice, thread};
+use subspace_farmer_components::file_ext::{AccessPattern, FileExt, IoGeometry};
+use subspace_farmer_components::read_plan::ReadAlignment;
+use subspace_farmer_components::{assume_init_bytes, region_buffers, ReadAtSync};
+use thiserror::Error;
+use tracing::{debug, trace, warn};
+
+/// 4096 is as a relatively safe size due to sector size on SSDs commonly being 512 or 4096 bytes
+pub const DISK_SECTOR_SIZE: usize = 4096;
+/// Default max size of a single read or write request, restricts how much data to read from disk
+/// in a single call to avoid very large memory usage
+pub const DEFAULT_MAX_REQUEST_SIZE: usize = 1024 * 1024;
+/// Smallest supported max request size
+pub const MIN_MAX_REQUEST_SIZE: usize = 64 * 1024;
+/// Largest supported max request size
+pub const MAX_MAX_REQUEST_SIZE: usize = 64 * 1024 * 1024;
+/// Default max number of bytes a covering read of multiple regions may read in addition to the
+/// regions themselves, see [`DirectIoOptions::covering_read_threshold`]
+pub const DEFAULT_COVERING_READ_THRESHOLD: usize = 256 * 1024;
+/// Default max size of one-shot buffer of large reads, see
+/// [`DirectIoOptions::max_large_read_size`]
+pub const DEFAULT_MAX_LARGE_READ_SIZE: usize = 16 * 1024 * 1024;
+/// Default max number of concurrent I/O requests to rotational disks, where concurrent requests
+/// result in expensive seeks
+pub const DEFAULT_MAX_IO_REQUESTS_ROTATIONAL: usize = 2;
+/// Default max number of concurrent I/O requests to solid state disks, matches typical queue depth
+/// of SATA SSDs
+pub const DEFAULT_MAX_IO_REQUESTS_SOLID_STATE: usize = 32;
+/// Default max number of concurrent I/O requests when kind of the disk can't be detected
+pub const DEFAULT_MAX_IO_REQUESTS: usize = 16;
+
+/// Max size of reads that [`DirectIoFile::read_small_at()`] serves with its fast path, larger
+/// reads fall back to the general read path
+pub const MAX_SMALL_READ_SIZE: usize = DISK_SECTOR_SIZE;
+
+/// Upper bound of the default number of scratch buffers kept around for concurrent I/O
+const MAX_DEFAULT_SCRATCH_BUFFER_POOL_SIZE: usize = 8;
+/// Number of sequential reads in a row after which read-ahead starts with
+/// [`AccessPattern::Normal`]
+const SEQUENTIAL_READS_BEFORE_READ_AHEAD: u32 = 2;
+
+/// Disk sector worth of memory aligned to [`DISK_SECTOR_SIZE`], direct I/O requires not only file
+/// offsets, but also user buffers to be aligned.
+///
+/// Alignment is part of the type, so any allocation of these (like [`ScratchBuffer`]) is aligned
+/// regardless of the allocator, unlike `Vec<[u8; DISK_SECTOR_SIZE]>` that is only aligned to 1.
+#[derive(Debug, Copy, Clone)]
+#[repr(C, align(4096))]
+struct AlignedSectorSize([u8; DISK_SECTOR_SIZE]);
+
+const_assert_eq!(mem::align_of::<AlignedSectorSize>(), DISK_SECTOR_SIZE);
+const_assert_eq!(mem::size_of::<AlignedSectorSize>(), DISK_SECTOR_SIZE);
+
+thread_local! {
+    /// Single sector buffer of [`DirectIoFile::read_small_at()`], such that small reads don't
+    /// touch scratch buffer pool shared by all threads
+    static SMALL_READ_BUFFER: RefCell<AlignedSectorSize> =
+        const { RefCell::new(AlignedSectorSize([0; DISK_SECTOR_SIZE])) };
+}
+
+impl Default for AlignedSectorSize {
+    fn default() -> Self {
+        Self([0; DISK_SECTOR_SIZE])
+    }
+}
+
+impl AlignedSectorSize {
+    fn slice_to_bytes(slice: &[Self]) -> &[u8] {
+        // SAFETY: `AlignedSectorSize` is `#[repr(C)]` wrapper around byte array without padding,
+        // size checked above
+        unsafe { slice::from_raw_parts(slice.as_ptr().cast::<u8>(), mem::size_of_val(slice)) }
+    }
+
+    fn slice_to_bytes_mut(slice: &mut [Self]) -> &mut [u8] {
+        // SAFETY: `AlignedSectorSize` is `#[repr(C)]` wrapper around byte array without padding,
+        // size checked above
+        unsafe {
+            slice::from_raw_parts_mut(slice.as_mut_ptr().cast::<u8>(), mem::size_of_val(slice))
+        }
+    }
+}
+
+type ScratchBuffer = Vec<AlignedSectorSize>;
+
+/// I/O mode file was opened with
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum IoMode {
+    /// Direct/unbuffered I/O that bypasses OS page cache
+    #[cfg_attr(not(any(target_os = "linux", windows)), allow(dead_code))]
+    Direct,
+    /// Buffered I/O through OS page cache, used when direct I/O is not available
+    Buffered,
+}
+
+/// Options for opening [`DirectIoFile`]
+#[derive(Debug, Copy, Clone)]
+pub struct DirectIoOptions {
+    /// Max size of a single read or write request in bytes, larger requests are split into multiple
+    /// smaller ones, [`DEFAULT_MAX_REQUEST_SIZE`] is used when `None` (unless options are tuned
+    /// for the disk with [`Self::with_device_profile()`]).
+    ///
+    /// Must be a multiple of [`DISK_SECTOR_SIZE`] between [`MIN_MAX_REQUEST_SIZE`] and
+    /// [`MAX_MAX_REQUEST_SIZE`], each scratch buffer is this large.
+    pub max_request_size: Option<usize>,
+    /// Number of scratch buffers kept for concurrent reads and writes, when more concurrent reads
+    /// and writes happen than buffers in the pool, temporary buffers are allocated and freed
+    /// afterwards.
+    ///
+    /// Number of CPU cores (up to 8) is used when `None` (unless options are tuned for the disk
+    /// with [`Self::with_device_profile()`]).
+    pub scratch_buffers: Option<usize>,
+    /// Open file in read-only mode, file must already exist and all writes fail with
+    /// [`io::ErrorKind::PermissionDenied`] error
+    pub read_only: bool,
+    /// Max number of bytes outside of requested regions [`ReadAtSync::read_regions()`] may read
+    /// and throw away to cover multiple regions with a few large reads, regions are read one by
+    /// one when they are too sparse
+    pub covering_read_threshold: usize,
+    /// Reads of more than this many bytes that can't be done into destination buffer directly (due
+    /// to alignment) bypass scratch buffers. Instead, they are read into a one-shot aligned buffer
+    /// with as few requests as possible (see [`Self::max_large_read_size`]) and copied into
+    /// destination once, such that large reads (like whole sectors) don't loop through scratch
+    /// buffer window by window.
+    ///
+    /// 4 × max request size is used when `None`.
+    pub large_read_threshold: Option<usize>,
+    /// Max size of one-shot buffer of large reads and of requests they are done with, larger reads
+    /// are done in multiple requests through the same buffer, see [`Self::large_read_threshold`].
+    ///
+    /// Must be a multiple of [`DISK_SECTOR_SIZE`], max request size is used if it is smaller.
+    pub max_large_read_size: usize,
+    /// Max number of concurrent I/O requests to the disk, chosen based on the kind of the disk
+    /// when `None`, see [`IoRequestLimiter::for_path()`].
+    ///
+    /// Independent of the number of scratch buffers, requests wait for each other when the limit
+    /// is reached.
+    pub max_io_requests: Option<NonZeroUsize>,
+    /// Open file in write-through mode, such that every write is persisted to disk (bypassing
+    /// volatile write cache) before returning, uses `O_DSYNC` on Unix and
+    /// `FILE_FLAG_WRITE_THROUGH` on Windows (which is always used there).
+    ///
+    /// Only affects writes, reads (including reads of read-modify-write) are not slowed down.
+    /// Recommended for small files like metadata, where cost of write-through is negligible, but
+    /// corruption after power loss is expensive to recover from.
+    pub write_through: bool,
+    /// Use buffered I/O through OS page cache right away instead of trying direct I/O first, for
+    /// file systems where direct I/O is known to be slow or broken, see
+    /// [`DirectIoFile::is_unbuffered()`]
+    pub buffered_io: bool,
+    /// Whether file must or must not exist already, ignored for read-only files, which must always
+    /// exist
+    pub open_mode: OpenMode,
+}
+
+impl Default for DirectIoOptions {
+    fn default() -> Self {
+        Self {
+            max_request_size: None,
+            scratch_buffers: None,
+            read_only: false,
+            covering_read_threshold: DEFAULT_COVERING_READ_THRESHOLD,
+            large_read_threshold: None,
+            max_large_read_size: DEFAULT_MAX_LARGE_READ_SIZE,
+            max_io_requests: None,
+            write_through: false,
+            buffered_io: false,
+            open_mode: OpenMode::default(),
+        }
+    }
+}
+
+impl DirectIoOptions {
+    /// Set max request size, number of scratch buffers and max number of concurrent I/O requests
+    /// to values from `device_profile` unless they are explicitly set already
+    pub fn with_device_profile(self, device_profile: &DeviceProfile) -> Self {
+        Self {
+            max_request_size: Some(
+                self.max_request_size
+                    .unwrap_or(device_profile.max_request_size),
+            ),
+            scratch_buffers: Some(
+                self.scratch_buffers
+                    .unwrap_or(device_profile.scratch_buffers),
+            ),
+            max_io_requests: Some(
+                self.max_io_requests
+                    .unwrap_or(device_profile.max_io_requests),
+            ),
+            ..self
+        }
+    }
+
+    /// Default number of scratch buffers, see [`Self::scratch_buffers`]
+    pub fn default_scratch_buffers() -> usize {
+        num_cpus::get().min(MAX_DEFAULT_SCRATCH_BUFFER_POOL_SIZE)
+    }
+
+    fn validate(&self) -> io::Result<()> {
+        let max_request_size = self.max_request_size.unwrap_or(DEFAULT_MAX_REQUEST_SIZE);
+        if max_request_size % DISK_SECTOR_SIZE != 0
+            || !(MIN_MAX_REQUEST_SIZE..=MAX_MAX_REQUEST_SIZE).contains(&max_request_size)
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Max request size {max_request_size} must be a multiple of {DISK_SECTOR_SIZE} \
+                    between {MIN_MAX_REQUEST_SIZE} and {MAX_MAX_REQUEST_SIZE}"
+                ),
+            ));
+        }
+
+        if self.max_large_read_size == 0 || self.max_large_read_size % DISK_SECTOR_SIZE != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Max large read size {} must be a non-zero multiple of {DISK_SECTOR_SIZE}",
+                    self.max_large_read_size
+                ),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether file must or must not exist already when opened, see [`DirectIoOptions::open_mode`]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum OpenMode {
+    /// Open existing file or create a new one if it doesn't exist
+    #[default]
+    OpenOrCreate,
+    /// Create a new file, fails with [`io::ErrorKind::AlreadyExists`] if file already exists
+    CreateNew,
+    /// Open existing file, fails with [`io::ErrorKind::NotFound`] if file doesn't exist
+    OpenExisting,
+}
+
+/// Whether error indicates that there is no space left on the disk (or disk quota was exceeded),
+/// which is the case for `ENOSPC`/`EDQUOT` on Unix and `ERROR_DISK_FULL`/`ERROR_HANDLE_DISK_FULL`
+/// on Windows.
+///
+/// Works for errors returned by [`DirectIoFile`] too since they preserve [`io::ErrorKind`].
+pub fn is_disk_full(error: &io::Error) -> bool {
+    matches!(
+        error.kind(),
+        io::ErrorKind::StorageFull | io::ErrorKind::FilesystemQuotaExceeded
+    )
+}
+
+/// Statistics of write requests of [`DirectIoFile`] since it was opened, see
+/// [`DirectIoFile::io_stats()`]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct IoStats {
+    /// Number of write requests served without read-modify-write
+    pub aligned_writes: u64,
+    /// Number of write requests that required read-modify-write, because they didn't cover whole
+    /// physical sectors
+    pub read_modify_writes: u64,
+    /// Number of bytes read and written back by read-modify-write in addition to requested bytes
+    pub amplified_bytes: u64,
+}
+
+impl Add for IoStats {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            aligned_writes: self.aligned_writes + rhs.aligned_writes,
+            read_modify_writes: self.read_modify_writes + rhs.read_modify_writes,
+            amplified_bytes: self.amplified_bytes + rhs.amplified_bytes,
+        }
+    }
+}
+
+impl IoStats {
+    /// Fraction of write requests that required read-modify-write, `0.0` if nothing was written
+    pub fn read_modify_write_ratio(&self) -> f64 {
+        let writes = self.aligned_writes + self.read_modify_writes;
+        if writes == 0 {
+            0.0
+        } else {
+            self.read_modify_writes as f64 / writes as f64
+        }
+    }
+}
+
+/// Statistics of requests [`DirectIoFile`] made to the disk since it was opened or since stats were
+/// reset, see [`DirectIoFile::request_stats()`].
+///
+/// Requests are counted as they reach the disk, such that unaligned reads and writes are counted
+/// with bytes read and written to align them and read-ahead is counted when it happens.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct RequestStats {
+    /// Number of successful read requests
+    pub reads: u64,
+    /// Number of bytes read by successful read requests
+    pub bytes_read: u64,
+    /// Total time successful read requests took
+    pub read_time: Duration,
+    /// Number of failed read requests
+    pub read_errors: u64,
+    /// Number of successful write requests
+    pub writes: u64,
+    /// Number of bytes written by successful write requests
+    pub bytes_written: u64,
+    /// Total time successful write requests took
+    pub write_time: Duration,
+    /// Number of failed write requests
+    pub write_errors: u64,
+}
+
+impl Add for RequestStats {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            reads: self.reads + rhs.reads,
+            bytes_read: self.bytes_read + rhs.bytes_read,
+            read_time: self.read_time + rhs.read_time,
+            read_errors: self.read_errors + rhs.read_errors,
+            writes: self.writes + rhs.writes,
+            bytes_written: self.bytes_written + rhs.bytes_written,
+            write_time: self.write_time + rhs.write_time,
+            write_errors: self.write_errors + rhs.write_errors,
+        }
+    }
+}
+
+impl RequestStats {
+    /// Average latency of successful read requests, zero if nothing was read
+    pub fn average_read_latency(&self) -> Duration {
+        average_latency(self.read_time, self.reads)
+    }
+
+    /// Average latency of successful write requests, zero if nothing was written
+    pub fn average_write_latency(&self) -> Duration {
+        average_latency(self.write_time, self.writes)
+    }
+}
+
+fn average_latency(time: Duration, requests: u64) -> Duration {
+    if requests == 0 {
+        Duration::ZERO
+    } else {
+        Duration::from_nanos((time.as_nanos() / u128::from(requests)) as u64)
+    }
+}
+
+/// Counters behind [`RequestStats`], updated without locks
+#[derive(Debug, Default)]
+struct RequestCounters {
+    reads: AtomicU64,
+    bytes_read: AtomicU64,
+    read_nanos: AtomicU64,
+    read_errors: AtomicU64,
+    writes: AtomicU64,
+    bytes_written: AtomicU64,
+    write_nanos: AtomicU64,
+    write_errors: AtomicU64,
+}
+
+impl RequestCounters {
+    fn observe_read(&self, bytes: usize, time: Duration) {
+        self.reads.fetch_add(1, Ordering::Relaxed);
+        self.bytes_read.fetch_add(bytes as u64, Ordering::Relaxed);
+        self.read_nanos
+            .fetch_add(time.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    fn observe_read_error(&self) {
+        self.read_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn observe_write(&self, bytes: usize, time: Duration) {
+        self.writes.fetch_add(1, Ordering::Relaxed);
+        self.bytes_written
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+        self.write_nanos
+            .fetch_add(time.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    fn observe_write_error(&self) {
+        self.write_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn stats(&self) -> RequestStats {
+        RequestStats {
+            reads: self.reads.load(Ordering::Relaxed),
+            bytes_read: self.bytes_read.load(Ordering::Relaxed),
+            read_time: Duration::from_nanos(self.read_nanos.load(Ordering::Relaxed)),
+            read_errors: self.read_errors.load(Ordering::Relaxed),
+            writes: self.writes.load(Ordering::Relaxed),
+            bytes_written: self.bytes_written.load(Ordering::Relaxed),
+            write_time: Duration::from_nanos(self.write_nanos.load(Ordering::Relaxed)),
+            write_errors: self.write_errors.load(Ordering::Relaxed),
+        }
+    }
+
+    fn reset(&self) {
+        for counter in [
+            &self.reads,
+            &self.bytes_read,
+            &self.read_nanos,
+            &self.read_errors,
+            &self.writes,
+            &self.bytes_written,
+            &self.write_nanos,
+            &self.write_errors,
+        ] {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Context of a failed read or write of [`DirectIoFile`], returned as a payload of [`io::Error`]
+/// with the same [`io::ErrorKind`] as the underlying error
+#[derive(Debug, Error)]
+#[error(
+    "Failed to {operation} {len} bytes at offset {offset} of {path} (logical sector size \
+    {logical_sector_size}, physical sector size {physical_sector_size}): {source}",
+    path = .path.display()
+)]
+pub struct DirectIoError {
+    /// Failed operation, `read` or `write`
+    pub operation: &'static str,
+    /// Path of the file
+    pub path: PathBuf,
+    /// Requested offset
+    pub offset: u64,
+    /// Requested length
+    pub len: usize,
+    /// Logical sector size reads are aligned to
+    pub logical_sector_size: usize,
+    /// Physical sector size writes are aligned to
+    pub physical_sector_size: usize,
+    /// Underlying error, includes aligned offset and length of the failed request
+    pub source: io::Error,
+}
+
+/// Priority of I/O requests of [`DirectIoFile`], see [`DirectIoFile::set_io_priority()`]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum IoPriority {
+    /// Time-critical requests like auditing and proving reads
+    #[default]
+    Foreground,
+    /// Requests that can wait like scrubbing, they are rate limited (see
+    /// [`IoRequestLimiter::with_background_rate_limit()`]) and always yield to foreground requests
+    /// that are waiting for their turn
+    Background,
+}
+
+/// Foreground requests waiting for their turn, background requests wait for them
+#[derive(Debug, Default)]
+struct ForegroundQueue {
+    waiting: AtomicUsize,
+    mutex: Mutex<()>,
+    drained: Condvar,
+}
+
+impl ForegroundQueue {
+    fn enter(&self) {
+        self.waiting.fetch_add(1, Ordering::AcqRel);
+    }
+
+    fn leave(&self) {
+        if self.waiting.fetch_sub(1, Ordering::AcqRel) == 1 {
+            let _guard = self.mutex.lock();
+            self.drained.notify_all();
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.waiting.load(Ordering::Acquire) == 0
+    }
+
+    /// Block current thread until there are no foreground requests waiting
+    fn wait_drained(&self) {
+        if self.is_empty() {
+            return;
+        }
+
+        let mut guard = self.mutex.lock();
+        while !self.is_empty() {
+            self.drained.wait(&mut guard);
+        }
+    }
+}
+
+/// Token bucket that limits throughput of background requests, holds up to one second worth of
+/// tokens
+#[derive(Debug)]
+struct TokenBucket {
+    bytes_per_second: NonZeroU64,
+    /// Available tokens (negative when requests larger than available tokens were let through)
+    /// and when they were last updated
+    state: Mutex<(f64, Instant)>,
+}
+
+impl TokenBucket {
+    fn new(bytes_per_second: NonZeroU64) -> Self {
+        Self {
+            bytes_per_second,
+            state: Mutex::new((bytes_per_second.get() as f64, Instant::now())),
+        }
+    }
+
+    /// Take `bytes` tokens, blocking current thread until tokens taken so far are accumulated.
+    ///
+    /// Tokens are taken upfront even if there is not enough of them, such that requests of any size
+    /// are let through and average rate is maintained.
+    fn take(&self, bytes: usize) {
+        let bytes_per_second = self.bytes_per_second.get() as f64;
+        let delay = {
+            let mut state = self.state.lock();
+            let (tokens, updated_at) = &mut *state;
+            let now = Instant::now();
+            *tokens = (*tokens + now.duration_since(*updated_at).as_secs_f64() * bytes_per_second)
+                .min(bytes_per_second);
+            *updated_at = now;
+            *tokens -= bytes as f64;
+
+            Duration::from_secs_f64((-*tokens).max(0.0) / bytes_per_second)
+        };
+
+        if !delay.is_zero() {
+            thread::sleep(delay);
+        }
+    }
+}
+
+/// Limits the number of concurrent I/O requests to the disk.
+///
+/// Can be cloned and shared between multiple files (like all files of a farm) to limit the number
+/// of requests they do together, see [`DirectIoFile::open_with_limiter()`].
+///
+/// Requests with [`IoPriority::Background`] are additionally rate limited if
+/// [`Self::with_background_rate_limit()`] is used and never take a turn of waiting foreground
+/// requests.
+///
+/// Scratch buffers of files opened with the limiter are additionally bounded by
+/// [`IoMemoryBudget`] if [`Self::with_memory_budget()`] is used.
+#[derive(Debug, Clone)]
+pub struct IoRequestLimiter {
+    semaphore: Arc<Semaphore>,
+    max_requests: NonZeroUsize,
+    in_flight: Arc<AtomicUsize>,
+    foreground_queue: Arc<ForegroundQueue>,
+    background_rate_limit: Option<Arc<TokenBucket>>,
+    memory_budget: Option<IoMemoryBudget>,
+}
+
+impl IoRequestLimiter {
+    /// Create new instance that allows up to `max_requests` concurrent requests
+    pub fn new(max_requests: NonZeroUsize) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(max_requests.get())),
+            max_requests,
+            in_flight: Arc::default(),
+            foreground_queue: Arc::default(),
+            background_rate_limit: None,
+            memory_budget: None,
+        }
+    }
+
+    /// Limit average throughput of requests with [`IoPriority::Background`] to `bytes_per_second`,
+    /// background requests are not rate limited when `None`
+    pub fn with_background_rate_limit(mut self, bytes_per_second: Option<NonZeroU64>) -> Self {
+        self.background_rate_limit =
+            bytes_per_second.map(|bytes_per_second| Arc::new(TokenBucket::new(bytes_per_second)));
+        self
+    }
+
+    /// Rate limit of requests with [`IoPriority::Background`] in bytes per second, see
+    /// [`Self::with_background_rate_limit()`]
+    pub fn background_rate_limit(&self) -> Option<NonZeroU64> {
+        self.background_rate_limit
+            .as_ref()
+            .map(|token_bucket| token_bucket.bytes_per_second)
+    }
+
+    /// Bound memory used by scratch buffers of files opened with this limiter by `memory_budget`,
+    /// which can be shared with other limiters (like limiters of all farms of the process), memory
+    /// usage is not bounded when `None`
+    pub fn with_memory_budget(mut self, memory_budget: Option<IoMemoryBudget>) -> Self {
+        self.memory_budget = memory_budget;
+        self
+    }
+
+    /// Memory budget of scratch buffers, see [`Self::with_memory_budget()`]
+    pub fn memory_budget(&self) -> Option<&IoMemoryBudget> {
+        self.memory_budget.as_ref()
+    }
+
+    /// Create new instance for the disk `path` is stored on, `max_requests` is chosen based on
+    /// the kind of the disk when `None` (see [`DeviceProfile::max_io_requests`])
+    pub fn for_path(path: &Path, max_requests: Option<NonZeroUsize>) -> Self {
+        let max_requests =
+            max_requests.unwrap_or_else(|| DeviceProfile::detect(path).max_io_requests);
+
+        Self::new(max_requests)
+    }
+
+    /// Max number of concurrent requests
+    pub fn max_requests(&self) -> NonZeroUsize {
+        self.max_requests
+    }
+
+    /// Number of requests that are currently in flight
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::Relaxed)
+    }
+
+    /// Wait for the number of requests in flight to go below the limit, request of `bytes` bytes
+    /// is in flight until returned permit is dropped.
+    ///
+    /// Background requests also wait for rate limit and for waiting foreground requests to go
+    /// first.
+    fn acquire<'a>(
+        &'a self,
+        priority: IoPriority,
+        bytes: usize,
+        metrics: Option<&'a FileMetrics>,
+    ) -> IoRequestPermit<'a> {
+        let guard = match priority {
+            IoPriority::Foreground => {
+                self.foreground_queue.enter();
+                let guard = self.semaphore.acquire_blocking();
+                self.foreground_queue.leave();
+                guard
+            }
+            IoPriority::Background => {
+                if let Some(token_bucket) = &self.background_rate_limit {
+                    token_bucket.take(bytes);
+                }
+
+                loop {
+                    self.foreground_queue.wait_drained();
+                    let guard = self.semaphore.acquire_blocking();
+                    // Foreground request may have arrived while waiting for permit, it goes first
+                    if self.foreground_queue.is_empty() {
+                        break guard;
+                    }
+                }
+            }
+        };
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+        if let Some(metrics) = metrics {
+            metrics.start_request();
+            if priority == IoPriority::Background {
+                metrics.observe_background_request(bytes);
+            }
+        }
+
+        IoRequestPermit {
+            _guard: guard,
+            in_flight: &self.in_flight,
+            metrics,
+        }
+    }
+}
+
+/// Permit of a request in flight, see [`IoRequestLimiter::acquire()`]
+struct IoRequestPermit<'a> {
+    _guard: SemaphoreGuard<'a>,
+    in_flight: &'a AtomicUsize,
+    metrics: Option<&'a FileMetrics>,
+}
+
+impl Drop for IoRequestPermit<'_> {
+    fn drop(&mut self) {
+        self.in_flight.fetch_sub(1, Ordering::Relaxed);
+        if let Some(metrics) = self.metrics {
+            metrics.finish_request();
+        }
+    }
+}
+
+/// Logical and physical sector sizes of the disk file is stored on
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+struct SectorSizes {
+    /// Smallest unit disk can address, reads are aligned to it
+    logical: usize,
+    /// Unit disk writes internally, writes aligned to it avoid read-modify-write inside the disk
+    /// (for example on 512e drives with 512 bytes logical and 4096 bytes physical sectors)
+    physical: usize,
+}
+
+/// Result of reading a window ahead, buffer is returned regardless of success
+type ReadAheadResult = (ScratchBuffer, io::Result<()>);
+
+/// Request to read `len` bytes at `offset` into `buffer`, processed by [`ReadAheadWorker`]
+struct ReadAheadJob {
+    buffer: ScratchBuffer,
+    offset: u64,
+    len: usize,
+    io_priority: IoPriority,
+    result_sender: mpsc::SyncSender<ReadAheadResult>,
+}
+
+/// Background thread that reads windows ahead one after another, exits once its file is dropped
+#[derive(Debug)]
+struct ReadAheadWorker {
+    job_sender: mpsc::Sender<ReadAheadJob>,
+}
+
+impl ReadAheadWorker {
+    fn spawn(file: &DirectIoFile) -> io::Result<Self> {
+        let (job_sender, job_receiver) = mpsc::channel::<ReadAheadJob>();
+        let file_handle = Arc::clone(&file.file);
+        let metrics = file.metrics.clone();
+        let request_counters = Arc::clone(&file.request_counters);
+        let io_request_limiter = file.io_request_limiter.clone();
+
+        thread::Builder::new()
+            .name("read-ahead".to_string())
+            .spawn(move || {
+                for job in job_receiver {
+                    let ReadAheadJob {
+                        mut buffer,
+                        offset,
+                        len,
+                        io_priority,
+                        result_sender,
+                    } = job;
+                    let bytes = &mut AlignedSectorSize::slice_to_bytes_mut(&mut buffer)[..len];
+                    let _permit = io_request_limiter.acquire(io_priority, len, metrics.as_deref());
+                    let start = Instant::now();
+                    let result = file_handle.read_exact_at(bytes, offset);
+                    match (&result, &metrics) {
+                        (Ok(()), metrics) => {
+                            let time = start.elapsed();
+                            request_counters.observe_read(len, time);
+                            if let Some(metrics) = metrics {
+                                metrics.observe_read(len, time);
+                            }
+                        }
+                        (Err(_), metrics) => {
+                            request_counters.observe_read_error();
+                            if let Some(metrics) = metrics {
+                                metrics.observe_read_error();
+                            }
+                        }
+                    }
+
+                    // Doesn't matter if window was discarded meanwhile
+                    let _ = result_sender.send((buffer, result));
+                }
+            })?;
+
+        Ok(Self { job_sender })
+    }
+}
+
+/// Window of the file that is being read ahead by [`ReadAheadWorker`]
+#[derive(Debug)]
+struct ReadAheadWindow {
+    offset: u64,
+    len: usize,
+    result_receiver: mpsc::Receiver<ReadAheadResult>,
+}
+
+impl ReadAheadWindow {
+    fn covers(&self, offset: u64, len: usize) -> bool {
+        self.offset <= offset && offset + len as u64 <= self.offset + self.len as u64
+    }
+
+    fn overlaps(&self, offset: u64, len: usize) -> bool {
+        offset < self.offset + self.len as u64 && self.offset < offset + len as u64
+    }
+
+    /// Wait for read to finish, returns buffer with window contents if read succeeded
+    fn wait(self) -> (ScratchBuffer, bool) {
+        match self.result_receiver.recv() {
+            Ok((buffer, result)) => (buffer, result.is_ok()),
+            Err(mpsc::RecvError) => (ScratchBuffer::new(), false),
+        }
+    }
+
+    /// Same as [`Self::wait()`], but returns `None` without waiting if read is not finished yet
+    fn try_wait(&self) -> Option<(ScratchBuffer, bool)> {
+        match self.result_receiver.try_recv() {
+            Ok((buffer, result)) => Some((buffer, result.is_ok())),
+            Err(mpsc::TryRecvError::Empty) => None,
+            Err(mpsc::TryRecvError::Disconnected) => Some((ScratchBuffer::new(), false)),
+        }
+    }
+}
+
+/// Software read-ahead state, at most one window is read ahead at a time to bound memory usage
+#[derive(Debug, Default)]
+struct ReadAhead {
+    access_pattern: AccessPattern,
+    /// End of the last read, used to detect sequential reads
+    last_read_end: Option<u64>,
+    /// Number of sequential reads in a row
+    sequential_reads: u32,
+    window: Option<ReadAheadWindow>,
+    /// Buffer of the last window, reused for the next one
+    buffer: ScratchBuffer,
+    /// Started on the first read ahead and stopped with [`AccessPattern::Random`]
+    worker: Option<ReadAheadWorker>,
+}
+
+/// Scratch buffer together with its reservation in [`IoMemoryBudget`] (if any), reservation is
+/// released once buffer is deallocated
+#[derive(Debug)]
+struct ReservedScratchBuffer {
+    buffer: ScratchBuffer,
+    reservation: Option<IoMemoryReservation>,
+}
+
+impl Deref for ReservedScratchBuffer {
+    type Target = ScratchBuffer;
+
+    fn deref(&self) -> &Self::Target {
+        &self.buffer
+    }
+}
+
+impl DerefMut for ReservedScratchBuffer {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.buffer
+    }
+}
+
+enum PooledScratchBufferKind<'a> {
+    /// Buffer owned by the file, returned into the pool
+    Owned(ReservedScratchBuffer),
+    /// Buffer shared by all files of [`IoMemoryBudget`] once the budget is exhausted
+    Shared(SharedScratchBuffer<'a>),
+    /// Buffer outside of the budget for nested requests while shared buffer is borrowed, dropped
+    /// right away
+    Temporary(ScratchBuffer),
+}
+
+/// Scratch buffer taken from the pool, returned back into the pool on drop unless the pool is
+/// already full
+struct PooledScratchBuffer<'a> {
+    scratch_buffer: PooledScratchBufferKind<'a>,
+    file: &'a DirectIoFile,
+}
+
+impl Deref for PooledScratchBuffer<'_> {
+    type Target = ScratchBuffer;
+
+    fn deref(&self) -> &Self::Target {
+        match &self.scratch_buffer {
+            PooledScratchBufferKind::Owned(scratch_buffer) => scratch_buffer,
+            PooledScratchBufferKind::Shared(scratch_buffer) => scratch_buffer,
+            PooledScratchBufferKind::Temporary(scratch_buffer) => scratch_buffer,
+        }
+    }
+}
+
+impl DerefMut for PooledScratchBuffer<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match &mut self.scratch_buffer {
+            PooledScratchBufferKind::Owned(scratch_buffer) => scratch_buffer,
+            PooledScratchBufferKind::Shared(scratch_buffer) => scratch_buffer,
+            PooledScratchBufferKind::Temporary(scratch_buffer) => scratch_buffer,
+        }
+    }
+}
+
+impl Drop for PooledScratchBuffer<'_> {
+    fn drop(&mut self) {
+        // Buffer that has grown beyond max request size is shrunk back before being reused
+        let max_buffer_size = self.file.max_request_size / DISK_SECTOR_SIZE;
+        let shrink = |scratch_buffer: &mut ScratchBuffer| {
+            if scratch_buffer.capacity() > max_buffer_size {
+                scratch_buffer.truncate(max_buffer_size);
+                scratch_buffer.shrink_to_fit();
+            }
+        };
+
+        match &mut self.scratch_buffer {
+            PooledScratchBufferKind::Owned(scratch_buffer) => {
+                let mut scratch_buffers = self.file.scratch_buffers.lock();
+                if scratch_buffers.len() < self.file.scratch_buffer_pool_size {
+                    let mut scratch_buffer = ReservedScratchBuffer {
+                        buffer: mem::take(&mut scratch_buffer.buffer),
+                        reservation: scratch_buffer.reservation.take(),
+                    };
+                    shrink(&mut scratch_buffer);
+                    scratch_buffers.push(scratch_buffer);
+                }
+            }
+            PooledScratchBufferKind::Shared(scratch_buffer) => {
+                shrink(scratch_buffer);
+            }
+            PooledScratchBufferKind::Temporary(_) => {}
+        }
+    }
+}
+
+/// Lifecycle of a file shared by the file and all of its clones, see [`DirectIoFile::close()`]
+#[derive(Debug, Default)]
+struct Lifecycle {
+    /// New operations are not admitted once set
+    closed: AtomicBool,
+    /// Whether file was locked through any instance, such that lock is released on close
+    locked: AtomicBool,
+    in_progress: AtomicUsize,
+    mutex: Mutex<()>,
+    finished: Condvar,
+}
+
+impl Lifecycle {
+    /// Register start of an operation, fails if file is closed already
+    fn start(&self, path: &Path) -> io::Result<OperationGuard<'_>> {
+        // Counter is incremented before checking the flag (and the other way around in `close()`),
+        // such that either operation is rejected or `close()` waits for it
+        self.in_progress.fetch_add(1, Ordering::SeqCst);
+        let guard = OperationGuard { lifecycle: self };
+
+        if self.closed.load(Ordering::SeqCst) {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("File {} is closed", path.display()),
+            ));
+        }
+
+        Ok(guard)
+    }
+
+    fn finish(&self) {
+        if self.in_progress.fetch_sub(1, Ordering::SeqCst) == 1
+            && self.closed.load(Ordering::SeqCst)
+        {
+            let _guard = self.mutex.lock();
+            self.finished.notify_all();
+        }
+    }
+
+    /// Stop admitting new operations and block current thread for up to `timeout` until
+    /// operations in progress finish, returns `false` if some are still in progress
+    fn close(&self, timeout: Duration) -> bool {
+        self.closed.store(true, Ordering::SeqCst);
+
+        let deadline = Instant::now() + timeout;
+        let mut guard = self.mutex.lock();
+        while self.in_progress.load(Ordering::SeqCst) > 0 {
+            if self.finished.wait_until(&mut guard, deadline).timed_out() {
+                return self.in_progress.load(Ordering::SeqCst) == 0;
+            }
+        }
+
+        true
+    }
+}
+
+/// Operation in progress, see [`Lifecycle::start()`]
+struct OperationGuard<'a> {
+    lifecycle: &'a Lifecycle,
+}
+
+impl Drop for OperationGuard<'_> {
+    fn drop(&mut self) {
+        self.lifecycle.finish();
+    }
+}
+
+/// Wrapper data structure for direct/unbuffered I/O that bypasses OS page cache, which otherwise
+/// results in huge memory usage with large farms.
+///
+/// Uses `O_DIRECT` on Linux and unbuffered I/O on Windows, on file systems that do not support
+/// direct I/O and on other platforms falls back to buffered I/O. Reads and writes of arbitrary size
+/// and at arbitrary offsets are supported, they are translated into aligned I/O internally.
+///
+/// Since there is no OS read-ahead with direct I/O, sequential reads (detected automatically or
+/// requested with [`FileExt::set_access_pattern()`]) read the next window ahead of time in
+/// background.
+#[derive(Debug)]
+pub struct DirectIoFile {
+    file: Arc<File>,
+    /// Handle of the file that was opened originally (same as `file` unless this is a clone), all
+    /// clones lock the file through it and keep it open, since on Windows file locks are released
+    /// once the handle that acquired them is closed
+    origin: Arc<File>,
+    path: PathBuf,
+    io_mode: IoMode,
+    read_only: bool,
+    /// See [`DirectIoOptions::write_through`], used when file is opened again with
+    /// [`Self::reopen()`]
+    write_through: bool,
+    sector_sizes: SectorSizes,
+    /// Pool of scratch buffers of aligned memory for reads and writes, each buffer is only held for
+    /// the duration of one read or write such that reads can happen concurrently
+    scratch_buffers: Mutex<Vec<ReservedScratchBuffer>>,
+    /// Max number of scratch buffers kept in the pool, bounds the memory usage of idle buffers
+    scratch_buffer_pool_size: usize,
+    /// Max size of a single read or write request, also the size of each scratch buffer
+    max_request_size: usize,
+    /// See [`DirectIoOptions::covering_read_threshold`]
+    covering_read_threshold: usize,
+    /// See [`DirectIoOptions::large_read_threshold`]
+    large_read_threshold: usize,
+    /// See [`DirectIoOptions::max_large_read_size`], multiple of logical sector size that is never
+    /// smaller than `max_request_size`
+    max_large_read_size: usize,
+    /// Writes of partial sectors read and write back whole sectors, writes are serialized (shared
+    /// with clones) to prevent concurrent writes into the same sector from overriding each other
+    write_lock: Arc<Mutex<()>>,
+    /// Operations in progress on this file and its clones, see [`Self::close()`]
+    lifecycle: Arc<Lifecycle>,
+    /// See [`IoStats::aligned_writes`]
+    aligned_writes: AtomicU64,
+    /// See [`IoStats::read_modify_writes`]
+    read_modify_writes: AtomicU64,
+    /// See [`IoStats::amplified_bytes`]
+    amplified_bytes: AtomicU64,
+    /// See [`RequestStats`], shared with read-ahead thread and clones of this file
+    request_counters: Arc<RequestCounters>,
+    /// Size of the file as of the last time it was queried, shared with clones of this file.
+    /// Reads that end within it don't query the size again, it is reset by [`Self::set_len()`],
+    /// which is the only way file shrinks.
+    known_size: Arc<AtomicU64>,
+    /// Disk I/O metrics, nothing is measured when `None`
+    metrics: Option<Arc<FileMetrics>>,
+    /// Limits the number of concurrent requests to the disk, potentially shared with other files
+    io_request_limiter: IoRequestLimiter,
+    /// Requests are done with [`IoPriority::Background`] priority, see [`Self::set_io_priority()`]
+    background: AtomicBool,
+    read_ahead: Mutex<ReadAhead>,
+}
+
+impl ReadAtSync for DirectIoFile {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        self.read_exact_at(buf, offset)
+    }
+
+    /// Aligned requests are read from disk straight into `buf` with OS APIs that accept
+    /// uninitialized memory, other requests are read through scratch buffer (or served from
+    /// read-ahead window) and copied into `buf`
+    fn read_at_uninit<'a>(
+        &self,
+        buf: &'a mut [MaybeUninit<u8>],
+        offset: u64,
+    ) -> io::Result<&'a mut [u8]> {
+        self.read_exact_at_uninit(buf, offset)?;
+
+        // SAFETY: Every byte of the buffer was written by successful read above
+        Ok(unsafe { assume_init_bytes(buf) })
+    }
+
+    fn read_regions(
+        &self,
+        base_offset: u64,
+        regions: &[(u32, u32)],
+        out: &mut [u8],
+    ) -> io::Result<()> {
+        let mut requests = region_buffers(regions, out)?;
+        requests.retain(|(_offset, buf)| !buf.is_empty());
+        for (offset, _buf) in &mut requests {
+            *offset += base_offset;
+        }
+
+        let Some(start) = requests.iter().map(|(offset, _buf)| *offset).min() else {
+            return Ok(());
+        };
+        let end = requests
+            .iter()
+            .map(|(offset, buf)| *offset + buf.len() as u64)
+            .max()
+            .unwrap_or(start);
+        let logical_sector_size = self.sector_sizes.logical as u64;
+        // Covering reads are done with granularity of logical sector size
+        let span = end.next_multiple_of(logical_sector_size)
+            - start / logical_sector_size * logical_sector_size;
+        let useful = requests
+            .iter()
+            .map(|(_offset, buf)| buf.len() as u64)
+            .sum::<u64>();
+        let waste = span.saturating_sub(useful);
+
+        if waste <= self.covering_read_threshold as u64 {
+            trace!(
+                regions = requests.len(),
+                %span,
+                %waste,
+                "Reading regions with covering reads"
+            );
+
+            self.read_exact_at_vectored(&mut requests)
+        } else {
+            trace!(
+                regions = requests.len(),
+                %span,
+                %waste,
+                "Regions are too sparse for covering reads, reading one by one"
+            );
+
+            for (offset, buf) in requests {
+                self.read_exact_at(buf, offset)?;
+            }
+
+            Ok(())
+        }
+    }
+
+    fn read_alignment(&self) -> Option<ReadAlignment> {
+        Some(ReadAlignment {
+            geometry: self.geometry(),
+            offset: 0,
+        })
+    }
+}
+
+impl ReadAtSync for &DirectIoFile {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        (*self).read_at(buf, offset)
+    }
+
+    fn read_at_uninit<'a>(
+        &self,
+        buf: &'a mut [MaybeUninit<u8>],
+        offset: u64,
+    ) -> io::Result<&'a mut [u8]> {
+        (*self).read_at_uninit(buf, offset)
+    }
+
+    fn read_regions(
+        &self,
+        base_offset: u64,
+        regions: &[(u32, u32)],
+        out: &mut [u8],
+    ) -> io::Result<()> {
+        (*self).read_regions(base_offset, regions, out)
+    }
+
+    fn read_alignment(&self) -> Option<ReadAlignment> {
+        (*self).read_alignment()
+    }
+}
+
+impl FileExt for DirectIoFile {
+    /// Logical size of the file, reads that end past it fail with [`io::ErrorKind::UnexpectedEof`]
+    /// (see [`Self::read_exact_at()`]), even if unaligned size was set with
+    /// [`DirectIoFile::set_len()`] and the last sector is only partially within the file
+    fn size(&self) -> io::Result<u64> {
+        Ok(self.file.metadata()?.len())
+    }
+
+    /// Unaligned size is handled the same way as in [`DirectIoFile::set_len()`]
+    fn preallocate(&self, len: u64) -> io::Result<()> {
+        let _operation = self.lifecycle.start(&self.path)?;
+        let physical_sector_size = self.sector_sizes.physical as u64;
+        // Preallocation never shrinks the file
+        if self.io_mode == IoMode::Buffered
+            || len % physical_sector_size == 0
+            || self.file.size()? >= len
+        {
+            return self.file.preallocate(len);
+        }
+
+        self.file
+            .preallocate(len.next_multiple_of(physical_sector_size))?;
+        self.set_len_buffered(len)
+    }
+
+    /// Sector sizes detected when file was opened and max request size file was opened with
+    fn geometry(&self) -> IoGeometry {
+        IoGeometry {
+            logical_sector_size: self.sector_sizes.logical,
+            physical_sector_size: self.sector_sizes.physical,
+            max_request_size: self.max_request_size,
+        }
+    }
+
+    fn try_lock_exclusive(&self) -> io::Result<()> {
+        self.origin.try_lock_exclusive()?;
+        self.lifecycle.locked.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    fn lock_shared(&self) -> io::Result<()> {
+        self.origin.lock_shared()?;
+        self.lifecycle.locked.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    fn sync_all(&self) -> io::Result<()> {
+        let _operation = self.lifecycle.start(&self.path)?;
+        self.file.sync_all()
+    }
+
+    fn sync_data(&self) -> io::Result<()> {
+        let _operation = self.lifecycle.start(&self.path)?;
+        self.file.sync_data()
+    }
+
+    fn advise_random_access(&self) -> io::Result<()> {
+        self.set_access_pattern(AccessPattern::Random)
+    }
+
+    fn advise_sequential_access(&self) -> io::Result<()> {
+        self.set_access_pattern(AccessPattern::Sequential)
+    }
+
+    fn set_access_pattern(&self, access_pattern: AccessPattern) -> io::Result<()> {
+        let window = {
+            let mut read_ahead = self.read_ahead.lock();
+            read_ahead.access_pattern = access_pattern;
+            if access_pattern == AccessPattern::Random {
+                read_ahead.worker = None;
+                read_ahead.buffer = ScratchBuffer::new();
+                read_ahead.window.take()
+            } else {
+                None
+            }
+        };
+        // Wait outside of the lock for in-flight read to finish, such that it doesn't keep using
+        // I/O capacity in random access mode
+        if let Some(window) = window {
+            window.wait();
+        }
+
+        Ok(())
+    }
+
+    /// Reads that end past [`Self::size()`] fail with [`io::ErrorKind::UnexpectedEof`] and the
+    /// number of available bytes in the message regardless of I/O mode and alignment, nothing is
+    /// written into `buf` in that case
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        // SAFETY: Read paths only write initialized bytes into the buffer
+        self.read_exact_at_uninit(unsafe { as_uninit_bytes(buf) }, offset)
+    }
+
+    fn read_exact_at_vectored(&self, requests: &mut [(u64, &mut [u8])]) -> io::Result<()> {
+        // Process requests in the order of offsets, such that requests close to each other can be
+        // merged into a single read
+        let mut order = (0..requests.len())
+            .filter(|&index| !requests[index].1.is_empty())
+            .collect::<Vec<_>>();
+        order.sort_unstable_by_key(|&index| requests[index].0);
+
+        if order.is_empty() {
+            return Ok(());
+        }
+
+        let _operation = self.lifecycle.start(&self.path)?;
+        let mut scratch_buffer = self.take_scratch_buffer();
+        let mut window = Vec::with_capacity(order.len());
+        let mut order = order.into_iter().peekable();
+
+        while let Some(first_index) = order.next() {
+            let (first_offset, first_buf) = &requests[first_index];
+            let window_start =
+                *first_offset / self.sector_sizes.logical as u64 * self.sector_sizes.logical as u64;
+            let mut window_end = *first_offset + first_buf.len() as u64;
+
+            if window_end - window_start > self.max_request_size as u64 {
+                // Request is too large to be merged with anything else, read it on its own
+                let (offset, buf) = &mut requests[first_index];
+                self.read_exact_at(buf, *offset)?;
+                continue;
+            }
+
+            // Collect requests that fall into the same window of `max_request_size` bytes
+            window.clear();
+            window.push(first_index);
+            while let Some(&index) = order.peek() {
+                let (offset, buf) = &requests[index];
+                let end = *offset + buf.len() as u64;
+                if end.max(window_end) - window_start > self.max_request_size as u64 {
+                    break;
+                }
+
+                window_end = window_end.max(end);
+                window.push(index);
+                order.next();
+            }
+
+            let window_bytes = match self.read_window(
+                &mut scratch_buffer,
+                (window_end - window_start) as usize,
+                window_start,
+                self.max_request_size,
+            ) {
+                Ok(window_bytes) => window_bytes,
+                Err(_error) => {
+                    // Read requests one by one to find which of them has failed
+                    for &index in &window {
+                        let (offset, buf) = &mut requests[index];
+                        self.read_exact_at(buf, *offset)?;
+                    }
+                    continue;
+                }
+            };
+
+            // Scatter read bytes into destination buffers
+            for &index in &window {
+                let (offset, buf) = &mut requests[index];
+                buf.copy_from_slice(
+                    &window_bytes[(*offset - window_start) as usize..][..buf.len()],
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        let _operation = self.lifecycle.start(&self.path)?;
+        let result = self
+            .write_all_at_unaligned(buf, offset)
+            .map_err(|error| self.io_error("write", offset, buf.len(), error));
+        // Window may have been read before or during the write, even if the write failed
+        self.invalidate_read_ahead(offset, buf.len());
+        result
+    }
+
+    /// With unbuffered I/O the range is rounded inward to physical sector size, such that partial
+    /// sectors at the edges of the range are not modified
+    fn punch_hole(&self, offset: u64, len: u64) -> io::Result<()> {
+        if self.read_only {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "File is opened in read-only mode",
+            ));
+        }
+
+        let (offset, len) = if self.io_mode == IoMode::Buffered {
+            (offset, len)
+        } else {
+            let physical_sector_size = self.sector_sizes.physical as u64;
+            let start = offset.next_multiple_of(physical_sector_size);
+            let end = offset.saturating_add(len) / physical_sector_size * physical_sector_size;
+            (start, end.saturating_sub(start))
+        };
+
+        if len == 0 {
+            return Ok(());
+        }
+
+        let _operation = self.lifecycle.start(&self.path)?;
+        let result = self.file.punch_hole(offset, len);
+        let len = usize::try_from(len).unwrap_or(usize::MAX);
+        self.invalidate_read_ahead(offset, len);
+        result.map_err(|error| self.io_error("punch hole", offset, len, error))
+    }
+}
+
+// Trait is not imported to avoid ambiguity with `FileExt` methods of the same name
+impl subspace_farmer_components::WriteAtSync for DirectIoFile {
+    fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        FileExt::write_all_at(self, buf, offset)
+    }
+
+    fn sync_data(&self) -> io::Result<()> {
+        FileExt::sync_data(self)
+    }
+}
+
+/// Attach offset and length of the aligned request that was actually sent to the disk to the error
+fn aligned_io_error(operation: &str, offset: u64, len: usize, error: io::Error) -> io::Error {
+    io::Error::new(
+        error.kind(),
+        format!("Aligned {operation} of {len} bytes at offset {offset} failed: {error}"),
+    )
+}
+
+/// Read into possibly uninitialized `buf` at `offset` with a single request, returns the number of
+/// bytes read, which are initialized, `0` at the end of the file.
+///
+/// Memory is passed to the OS as a raw pointer, such that uninitialized bytes are never viewed as
+/// `&mut [u8]`.
+fn read_at_raw(file: &File, buf: &mut [MaybeUninit<u8>], offset: u64) -> io::Result<usize> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::io::AsRawFd;
+
+        let offset = libc::off_t::try_from(offset)
+            .map_err(|_error| io::Error::new(io::ErrorKind::InvalidInput, "Offset is too large"))?;
+        // SAFETY: Pointer and length describe memory of `buf` that OS only writes into
+        let bytes_read =
+            unsafe { libc::pread(file.as_raw_fd(), buf.as_mut_ptr().cast(), buf.len(), offset) };
+        if bytes_read < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(bytes_read as usize)
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::io::AsRawHandle;
+        use winapi::shared::minwindef::DWORD;
+        use winapi::um::fileapi::ReadFile;
+        use winapi::um::minwinbase::OVERLAPPED;
+
+        /// Returned by synchronous reads at the end of the file
+        const ERROR_HANDLE_EOF: i32 = 38;
+
+        // SAFETY: All-zero `OVERLAPPED` is valid
+        let mut overlapped = unsafe { mem::zeroed::<OVERLAPPED>() };
+        // SAFETY: Offset variant of the union is the one used by `ReadFile()` for files
+        unsafe {
+            let overlapped_offset = overlapped.u.s_mut();
+            overlapped_offset.Offset = offset as DWORD;
+            overlapped_offset.OffsetHigh = (offset >> 32) as DWORD;
+        }
+        let mut bytes_read: DWORD = 0;
+        // SAFETY: Pointer and length describe memory of `buf` that OS only writes into,
+        // `overlapped` outlives synchronous request
+        let result = unsafe {
+            ReadFile(
+                file.as_raw_handle().cast(),
+                buf.as_mut_ptr().cast(),
+                DWORD::try_from(buf.len()).unwrap_or(DWORD::MAX),
+                &mut bytes_read,
+                &mut overlapped,
+            )
+        };
+        if result == 0 {
+            let error = io::Error::last_os_error();
+            if error.raw_os_error() == Some(ERROR_HANDLE_EOF) {
+                return Ok(0);
+            }
+            return Err(error);
+        }
+
+        Ok(bytes_read as usize)
+    }
+}
+
+/// View initialized bytes as possibly uninitialized, such that the same read paths serve both
+/// initialized and uninitialized buffers.
+///
+/// # Safety
+/// Only initialized bytes may be written into returned slice.
+unsafe fn as_uninit_bytes(buf: &mut [u8]) -> &mut [MaybeUninit<u8>] {
+    // SAFETY: `MaybeUninit<u8>` has the same layout as `u8` and bytes stay initialized according
+    // to the contract of this function
+    unsafe { slice::from_raw_parts_mut(buf.as_mut_ptr().cast::<MaybeUninit<u8>>(), buf.len()) }
+}
+
+/// Copy `src` into possibly uninitialized `dst` of the same length
+fn write_bytes(dst: &mut [MaybeUninit<u8>], src: &[u8]) {
+    assert_eq!(dst.len(), src.len(), "Lengths are always the same; qed");
+    for (dst, &src) in dst.iter_mut().zip(src) {
+        dst.write(src);
+    }
+}
+
+/// Error of a read of `len` bytes at `offset` that extends beyond the end of the file of `size`
+/// bytes
+fn eof_error(offset: u64, len: usize, size: u64) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::UnexpectedEof,
+        format!(
+            "Read of {len} bytes at offset {offset} extends beyond the end of the file of {size} \
+            bytes, {} bytes available",
+            size.saturating_sub(offset)
+        ),
+    )
+}
+
+impl DirectIoFile {
+    /// Open file at specified path for random direct I/O access to prevent huge memory usage (if
+    /// file doesn't exist, it will be created).
+    ///
+    /// Falls back to buffered I/O with a warning if file system doesn't support direct I/O, see
+    /// [`Self::is_unbuffered()`].
+    pub fn open(path: &Path) -> io::Result<Self> {
+        Self::open_with_options(path, DirectIoOptions::default())
+    }
+
+    /// Same as [`Self::open()`], but with custom options, see [`DirectIoOptions`] for details.
+    pub fn open_with_options(path: &Path, options: DirectIoOptions) -> io::Result<Self> {
+        Self::open_with_metrics(path, options, None)
+    }
+
+    /// Same as [`Self::open_with_options()`], but also collects disk I/O metrics of all reads and
+    /// writes if `metrics` is provided, see [`metrics::DiskMetrics`] for details.
+    pub fn open_with_metrics(
+        path: &Path,
+        options: DirectIoOptions,
+        metrics: Option<Arc<FileMetrics>>,
+    ) -> io::Result<Self> {
+        Self::open_with_limiter(path, options, metrics, None)
+    }
+
+    /// Same as [`Self::open_with_metrics()`], but limits concurrent I/O requests with provided
+    /// limiter that can be shared with other files, in which case
+    /// [`DirectIoOptions::max_io_requests`] is ignored. Limiter is created for this file only when
+    /// `None`.
+    pub fn open_with_limiter(
+        path: &Path,
+        options: DirectIoOptions,
+        metrics: Option<Arc<FileMetrics>>,
+        io_request_limiter: Option<IoRequestLimiter>,
+    ) -> io::Result<Self> {
+        options.validate()?;
+        let DirectIoOptions {
+            max_request_size,
+            scratch_buffers,
+            read_only,
+            covering_read_threshold,
+            large_read_threshold,
+            max_large_read_size,
+            max_io_requests,
+            write_through,
+            buffered_io,
+            open_mode,
+        } = options;
+
+        let max_request_size = max_request_size.unwrap_or(DEFAULT_MAX_REQUEST_SIZE);
+        let large_read_threshold = large_read_threshold.unwrap_or(max_request_size * 4);
+        let scratch_buffers =
+            scratch_buffers.unwrap_or_else(DirectIoOptions::default_scratch_buffers);
+
+        let mut open_options = OpenOptions::new();
+        open_options.read(true);
+        if !read_only {
+            open_options
+                .write(true)
+                .create(open_mode != OpenMode::OpenExisting)
+                .create_new(open_mode == OpenMode::CreateNew)
+                .truncate(false);
+        }
+        let (file, sector_sizes, io_mode) = if buffered_io {
+            Self::open_buffered(&mut open_options, path, max_request_size, write_through)?
+        } else {
+            Self::open_file(
+                &mut open_options,
+                path,
+                max_request_size,
+                write_through,
+                |file, physical_sector_size| {
+                    Self::verify_direct_io(file, physical_sector_size, read_only)
+                },
+            )?
+        };
+        let io_request_limiter =
+            io_request_limiter.unwrap_or_else(|| IoRequestLimiter::for_path(path, max_io_requests));
+        let file = Arc::new(file);
+        // Max request size is a multiple of logical sector size already
+        let max_large_read_size = (max_large_read_size / sector_sizes.logical
+            * sector_sizes.logical)
+            .max(max_request_size);
+
+        Ok(Self {
+            origin: Arc::clone(&file),
+            file,
+            path: path.to_path_buf(),
+            io_mode,
+            read_only,
+            write_through,
+            sector_sizes,
+            // In many cases we'll want to read this much at once, so pre-allocate one buffer right
+            // away, the rest is allocated on demand
+            scratch_buffers: Mutex::new(
+                Self::new_scratch_buffer(max_request_size, io_request_limiter.memory_budget())
+                    .into_iter()
+                    .collect(),
+            ),
+            scratch_buffer_pool_size: scratch_buffers.max(1),
+            max_request_size,
+            covering_read_threshold,
+            large_read_threshold,
+            max_large_read_size,
+            write_lock: Arc::default(),
+            lifecycle: Arc::default(),
+            aligned_writes: AtomicU64::new(0),
+            read_modify_writes: AtomicU64::new(0),
+            amplified_bytes: AtomicU64::new(0),
+            request_counters: Arc::default(),
+            known_size: Arc::default(),
+            metrics,
+            io_request_limiter,
+            background: AtomicBool::new(false),
+            read_ahead: Mutex::default(),
+        })
+    }
+
+    /// Create another instance of this file with duplicated file handle (`dup()` on Unix,
+    /// `DuplicateHandle()` on Windows).
+    ///
+    /// Duplicated handle refers to the same open file description (`FILE_OBJECT` on Windows) as
+    /// the original, so OS still serializes operations that it serializes per open file, use
+    /// [`Self::reopen()`] for handles that must be independent, like the ones used by different
+    /// threads for concurrent reads.
+    ///
+    /// Clone uses the same I/O mode, options, metrics, [`RequestStats`] and I/O request limiter,
+    /// but has its own scratch buffer pool, read-ahead window and worker, [`IoStats`] and I/O
+    /// priority (foreground initially).
+    /// Writes are still serialized with this file and its other clones. File size is shared by all
+    /// clones, locks taken through any clone are held until this file and all of its clones are
+    /// dropped or closed, closing any of them closes all of them, see [`Self::close()`].
+    pub fn try_clone(&self) -> io::Result<Self> {
+        Ok(self.with_handle(self.file.try_clone()?))
+    }
+
+    /// Same as [`Self::try_clone()`], but instead of duplicating file handle the file is opened
+    /// again by its path with the same I/O mode, such that the new handle has its own open file
+    /// description (`FILE_OBJECT` on Windows) and I/O through it is independent from I/O through
+    /// this file
+    pub fn reopen(&self) -> io::Result<Self> {
+        let mut open_options = OpenOptions::new();
+        open_options.read(true).write(!self.read_only);
+        let file = match self.io_mode {
+            IoMode::Direct => backend::open(&mut open_options, &self.path, self.write_through)?,
+            IoMode::Buffered => buffered::open(&mut open_options, &self.path, self.write_through)?,
+        };
+
+        Ok(self.with_handle(file))
+    }
+
+    /// Another instance of this file that does I/O through `file` handle of the same file
+    fn with_handle(&self, file: File) -> Self {
+        Self {
+            file: Arc::new(file),
+            origin: Arc::clone(&self.origin),
+            path: self.path.clone(),
+            io_mode: self.io_mode,
+            read_only: self.read_only,
+            write_through: self.write_through,
+            sector_sizes: self.sector_sizes,
+            scratch_buffers: Mutex::new(
+                Self::new_scratch_buffer(
+                    self.max_request_size,
+                    self.io_request_limiter.memory_budget(),
+                )
+                .into_iter()
+                .collect(),
+            ),
+            scratch_buffer_pool_size: self.scratch_buffer_pool_size,
+            max_request_size: self.max_request_size,
+            covering_read_threshold: self.covering_read_threshold,
+            large_read_threshold: self.large_read_threshold,
+            max_large_read_size: self.max_large_read_size,
+            write_lock: Arc::clone(&self.write_lock),
+            lifecycle: Arc::clone(&self.lifecycle),
+            aligned_writes: AtomicU64::new(0),
+            read_modify_writes: AtomicU64::new(0),
+            amplified_bytes: AtomicU64::new(0),
+            request_counters: Arc::clone(&self.request_counters),
+            known_size: Arc::clone(&self.known_size),
+            metrics: self.metrics.clone(),
+            io_request_limiter: self.io_request_limiter.clone(),
+            background: AtomicBool::new(false),
+            read_ahead: Mutex::default(),
+        }
+    }
+
+    /// Logical sector size of the disk file is stored on, reads are aligned to it
+    pub fn logical_sector_size(&self) -> usize {
+        self.sector_sizes.logical
+    }
+
+    /// Physical sector size of the disk file is stored on, writes are aligned to it since writes of
+    /// partial physical sectors result in read-modify-write inside the disk
+    pub fn physical_sector_size(&self) -> usize {
+        self.sector_sizes.physical
+    }
+
+    /// Whether file is opened with direct/unbuffered I/O, `false` means buffered I/O fallback is
+    /// used and OS page cache will grow with the amount of data read and written
+    pub fn is_unbuffered(&self) -> bool {
+        self.io_mode == IoMode::Direct
+    }
+
+    /// Number of read-modify-write operations done so far for writes that do not cover whole
+    /// sectors, a large number indicates misaligned writes
+    pub fn read_modify_writes(&self) -> u64 {
+        self.read_modify_writes.load(Ordering::Relaxed)
+    }
+
+    /// Statistics of write requests done so far, a high read-modify-write ratio indicates
+    /// misaligned layout of data in the file
+    pub fn io_stats(&self) -> IoStats {
+        IoStats {
+            aligned_writes: self.aligned_writes.load(Ordering::Relaxed),
+            read_modify_writes: self.read_modify_writes.load(Ordering::Relaxed),
+            amplified_bytes: self.amplified_bytes.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Statistics of requests made to the disk so far through this file and all of its clones (see
+    /// [`Self::try_clone()`] and [`Self::reopen()`])
+    pub fn request_stats(&self) -> RequestStats {
+        self.request_counters.stats()
+    }
+
+    /// Reset both [`IoStats`] and [`RequestStats`] to zero, such that they can be used to measure
+    /// specific interval of time.
+    ///
+    /// [`RequestStats`] are reset for clones of this file too. Counters are reset one by one,
+    /// requests that complete concurrently with reset may be partially accounted for.
+    pub fn reset_io_stats(&self) {
+        self.aligned_writes.store(0, Ordering::Relaxed);
+        self.read_modify_writes.store(0, Ordering::Relaxed);
+        self.amplified_bytes.store(0, Ordering::Relaxed);
+        self.request_counters.reset();
+    }
+
+    /// Limiter of concurrent I/O requests used by this file
+    pub fn io_request_limiter(&self) -> &IoRequestLimiter {
+        &self.io_request_limiter
+    }
+
+    /// Set priority of all subsequent requests of this instance (including read-ahead and all
+    /// requests of vectored reads and writes), other instances (including clones, see
+    /// [`Self::try_clone()`]) are not affected
+    pub fn set_io_priority(&self, io_priority: IoPriority) {
+        self.background
+            .store(io_priority == IoPriority::Background, Ordering::Relaxed);
+    }
+
+    /// Priority of requests of this instance, see [`Self::set_io_priority()`]
+    pub fn io_priority(&self) -> IoPriority {
+        if self.background.load(Ordering::Relaxed) {
+            IoPriority::Background
+        } else {
+            IoPriority::Foreground
+        }
+    }
+
+    /// Close file gracefully: stop admitting new requests through this file and all of its clones
+    /// (they fail with an error from now on), wait for up to `timeout` for requests in progress to
+    /// finish, sync written data to disk and release file locks.
+    ///
+    /// Returns [`io::ErrorKind::TimedOut`] error if some requests are still in progress after
+    /// `timeout`, in which case data is not synced and locks are held until file is dropped.
+    pub fn close(&self, timeout: Duration) -> io::Result<()> {
+        if !self.lifecycle.close(timeout) {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!(
+                    "Requests to file {} are still in progress after {timeout:?}",
+                    self.path.display()
+                ),
+            ));
+        }
+
+        if !self.read_only {
+            self.file.sync_data()?;
+        }
+        if self.lifecycle.locked.swap(false, Ordering::AcqRel) {
+            fs4::FileExt::unlock(&*self.origin)?;
+        }
+
+        Ok(())
+    }
+
+    /// Whether file was closed with [`Self::close()`] through this instance or any of its clones
+    pub fn is_closed(&self) -> bool {
+        self.lifecycle.closed.load(Ordering::Acquire)
+    }
+
+    /// Open file with direct I/O and verify it actually works with `verify_direct_io` probe (called
+    /// with physical sector size), some file systems accept direct I/O on open, but fail actual I/O
+    /// later. Reopens file with buffered I/O if direct I/O is not supported.
+    #[cfg(any(target_os = "linux", windows))]
+    fn open_file<V>(
+        open_options: &mut OpenOptions,
+        path: &Path,
+        max_request_size: usize,
+        write_through: bool,
+        verify_direct_io: V,
+    ) -> io::Result<(File, SectorSizes, IoMode)>
+    where
+        V: FnOnce(&File, usize) -> io::Result<()>,
+    {
+        let error = match backend::open(open_options, path, write_through) {
+            Ok(file) => {
+                let sector_sizes = Self::detect_sector_sizes(&file, path, max_request_size);
+                match verify_direct_io(&file, sector_sizes.physical) {
+                    Ok(()) => {
+                        return Ok((file, sector_sizes, IoMode::Direct));
+                    }
+                    Err(error) => error,
+                }
+            }
+            // `EINVAL` is returned on Linux when file system doesn't support `O_DIRECT`
+            Err(error) if error.kind() == io::ErrorKind::InvalidInput => error,
+            Err(error) => {
+                return Err(error);
+            }
+        };
+
+        warn!(
+            path = %path.display(),
+            %error,
+            "Direct I/O is not supported, falling back to buffered I/O, this will result in much \
+            higher memory usage by OS page cache"
+        );
+
+        // File may have been created by the first attempt already (Linux creates file before
+        // rejecting `O_DIRECT`), it didn't exist before though
+        open_options.create_new(false);
+
+        Self::open_buffered(open_options, path, max_request_size, write_through)
+    }
+
+    /// Direct I/O is not supported on this platform, open file with buffered I/O
+    #[cfg(not(any(target_os = "linux", windows)))]
+    fn open_file<V>(
+        open_options: &mut OpenOptions,
+        path: &Path,
+        max_request_size: usize,
+        write_through: bool,
+        _verify_direct_io: V,
+    ) -> io::Result<(File, SectorSizes, IoMode)>
+    where
+        V: FnOnce(&File, usize) -> io::Result<()>,
+    {
+        Self::open_buffered(open_options, path, max_request_size, write_through)
+    }
+
+    fn open_buffered(
+        open_options: &mut OpenOptions,
+        path: &Path,
+        max_request_size: usize,
+        write_through: bool,
+    ) -> io::Result<(File, SectorSizes, IoMode)> {
+        let file = buffered::open(open_options, path, write_through)?;
+        let sector_sizes = Self::detect_sector_sizes(&file, path, max_request_size);
+
+        Ok((file, sector_sizes, IoMode::Buffered))
+    }
+
+    /// Read the first sector of the file with direct I/O or write it if file is empty, fails if
+    /// direct I/O doesn't actually work
+    fn verify_direct_io(
+        file: &File,
+        physical_sector_size: usize,
+        read_only: bool,
+    ) -> io::Result<()> {
+        let mut probe_buffer =
+            vec![AlignedSectorSize::default(); physical_sector_size.div_ceil(DISK_SECTOR_SIZE)];
+        let probe_buffer =
+            &mut AlignedSectorSize::slice_to_bytes_mut(&mut probe_buffer)[..physical_sector_size];
+
+        let file_size = file.size()?;
+        if file_size >= physical_sector_size as u64 {
+            file.read_exact_at(probe_buffer, 0)
+        } else if file_size == 0 && !read_only {
+            file.write_all_at(probe_buffer, 0)?;
+            file.set_len(0)
+        } else {
+            // Partial sector can't be read or written with direct I/O without changing file size,
+            // read-only file can't be written at all
+            Ok(())
+        }
+    }
+
+    fn detect_sector_sizes(file: &File, path: &Path, max_request_size: usize) -> SectorSizes {
+        match backend::sector_sizes(file) {
+            Ok(sector_sizes)
+                if Self::is_supported_sector_size(sector_sizes.logical, max_request_size)
+                    && Self::is_supported_sector_size(sector_sizes.physical, max_request_size)
+                    && sector_sizes.physical % sector_sizes.logical == 0 =>
+            {
+                sector_sizes
+            }
+            result => {
+                debug!(
+                    path = %path.display(),
+                    ?result,
+                    "Failed to query supported sector sizes, probing instead"
+                );
+
+                // Physical sector size can't be probed, align writes to a size that is safe for
+                // all disks used in practice
+                let logical = Self::probe_logical_sector_size(file);
+                SectorSizes {
+                    logical,
+                    physical: logical.max(DISK_SECTOR_SIZE),
+                }
+            }
+        }
+    }
+
+    /// Sector sizes that divide max request size are supported, which includes all power of two
+    /// sizes that are used in practice
+    fn is_supported_sector_size(sector_size: usize, max_request_size: usize) -> bool {
+        sector_size >= 512 && sector_size.is_power_of_two() && max_request_size % sector_size == 0
+    }
+
+    /// Logical sector size on many disks is smaller than 4096 and should improve read performance,
+    /// use 512 bytes if reading with such alignment succeeds
+    fn probe_logical_sector_size(file: &File) -> usize {
+        let mut probe_buffer = AlignedSectorSize::default();
+        if file.read_at(&mut probe_buffer.0[..512], 512).is_ok() {
+            512
+        } else {
+            DISK_SECTOR_SIZE
+        }
+    }
+
+    /// Allocate new scratch buffer, returns `None` if it doesn't fit into `memory_budget`
+    fn new_scratch_buffer(
+        max_request_size: usize,
+        memory_budget: Option<&IoMemoryBudget>,
+    ) -> Option<ReservedScratchBuffer> {
+        let reservation = match memory_budget {
+            Some(memory_budget) => Some(memory_budget.try_reserve(max_request_size)?),
+            None => None,
+        };
+
+        Some(ReservedScratchBuffer {
+            buffer: vec![AlignedSectorSize::default(); max_request_size / DISK_SECTOR_SIZE],
+            reservation,
+        })
+    }
+
+    /// Take scratch buffer from the pool or allocate a new one if the pool is empty.
+    ///
+    /// Once memory budget is exhausted, buffer shared by all files of the budget is borrowed
+    /// instead (waiting for other requests that use it). Nested requests on the thread that has
+    /// borrowed shared buffer already get a temporary buffer outside of the budget, such that they
+    /// don't wait for themselves.
+    fn take_scratch_buffer(&self) -> PooledScratchBuffer<'_> {
+        let memory_budget = self.io_request_limiter.memory_budget();
+        let maybe_scratch_buffer = self
+            .scratch_buffers
+            .lock()
+            .pop()
+            .or_else(|| Self::new_scratch_buffer(self.max_request_size, memory_budget));
+
+        let scratch_buffer = match maybe_scratch_buffer {
+            Some(scratch_buffer) => PooledScratchBufferKind::Owned(scratch_buffer),
+            None => {
+                let maybe_shared_scratch_buffer = memory_budget.and_then(|memory_budget| {
+                    memory_budget.borrow_shared_buffer(self.max_request_size / DISK_SECTOR_SIZE)
+                });
+
+                match maybe_shared_scratch_buffer {
+                    Some(scratch_buffer) => PooledScratchBufferKind::Shared(scratch_buffer),
+                    None => PooledScratchBufferKind::Temporary(vec![
+                        AlignedSectorSize::default();
+                        self.max_request_size
+                            / DISK_SECTOR_SIZE
+                    ]),
+                }
+            }
+        };
+
+        PooledScratchBuffer {
+            scratch_buffer,
+            file: self,
+        }
+    }
+
+    /// Whether I/O of `len` bytes at `offset` can be done with memory at `ptr` directly, without
+    /// going through scratch buffer, `sector_size` is logical sector size for reads and physical
+    /// sector size for writes
+    fn is_aligned(ptr: *const u8, len: usize, offset: u64, sector_size: usize) -> bool {
+        offset % sector_size as u64 == 0
+            && len % sector_size == 0
+            && ptr as usize % sector_size == 0
+    }
+
+    /// Truncates or extends the underlying file, updating the size of this file to become `size`.
+    ///
+    /// Changing size of a file opened with unbuffered I/O fails or leaves the tail of the file in
+    /// undefined state on some Windows configurations unless size is a multiple of physical sector
+    /// size. When unaligned size is requested, the file is extended to the physical sector
+    /// boundary first (if it grows) and exact size is then set through temporarily opened buffered
+    /// handle to the same path, this file continues to use unbuffered handle afterwards.
+    /// [`FileExt::preallocate()`] handles unaligned sizes the same way.
+    ///
+    /// [`FileExt::size()`] always reports exact size that was set and the unaligned tail of the
+    /// file can be read, but writes into the last partial sector extend the file to the physical
+    /// sector boundary.
+    pub fn set_len(&self, size: u64) -> io::Result<()> {
+        let _operation = self.lifecycle.start(&self.path)?;
+        let result = self.set_len_internal(size);
+        self.known_size.store(0, Ordering::Relaxed);
+        self.invalidate_read_ahead(0, usize::MAX);
+        result
+    }
+
+    fn set_len_internal(&self, size: u64) -> io::Result<()> {
+        if self.read_only {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "File is opened in read-only mode",
+            ));
+        }
+
+        let physical_sector_size = self.sector_sizes.physical as u64;
+        if self.io_mode == IoMode::Buffered || size % physical_sector_size == 0 {
+            return self.file.set_len(size);
+        }
+
+        if size > self.file.size()? {
+            self.file
+                .set_len(size.next_multiple_of(physical_sector_size))?;
+        }
+        self.set_len_buffered(size)
+    }
+
+    /// Set exact size of the file through temporarily opened buffered handle to the same path
+    fn set_len_buffered(&self, size: u64) -> io::Result<()> {
+        buffered::open(OpenOptions::new().write(true), &self.path, false)?.set_len(size)
+    }
+
+    /// Serve read from read-ahead window if possible and read the next window ahead in background
+    /// if access is sequential, returns `true` if `buf` was filled from read-ahead window
+    fn read_ahead(&self, buf: &mut [MaybeUninit<u8>], offset: u64) -> bool {
+        let len = buf.len();
+        let read_end = offset + len as u64;
+        let mut read_ahead = self.read_ahead.lock();
+
+        if read_ahead.last_read_end == Some(offset) {
+            read_ahead.sequential_reads = read_ahead.sequential_reads.saturating_add(1);
+        } else {
+            read_ahead.sequential_reads = 0;
+        }
+        read_ahead.last_read_end = Some(read_end);
+
+        let mut hit = false;
+        if let Some(window) = read_ahead.window.take() {
+            if window.covers(offset, len) {
+                // Wait without holding the lock, such that other reads and writes are not blocked
+                drop(read_ahead);
+                let window_offset = window.offset;
+                let (buffer, success) = window.wait();
+                if success {
+                    write_bytes(
+                        buf,
+                        &AlignedSectorSize::slice_to_bytes(&buffer)
+                            [(offset - window_offset) as usize..][..len],
+                    );
+                    hit = true;
+                }
+                read_ahead = self.read_ahead.lock();
+                read_ahead.buffer = buffer;
+                if read_ahead.window.is_some() {
+                    // Concurrent reader started the next window meanwhile
+                    return hit;
+                }
+            } else if let Some((buffer, _success)) = window.try_wait() {
+                read_ahead.buffer = buffer;
+            } else {
+                // Still reading, keep it to not read more than one window at a time, this also
+                // prevents concurrent readers from discarding each other's windows
+                read_ahead.window.replace(window);
+                return false;
+            }
+        }
+
+        let enabled = match read_ahead.access_pattern {
+            AccessPattern::Normal => {
+                read_ahead.sequential_reads >= SEQUENTIAL_READS_BEFORE_READ_AHEAD
+            }
+            AccessPattern::Random => false,
+            AccessPattern::Sequential => true,
+        };
+        if enabled {
+            // Assume the next read will be of the same size
+            read_ahead.window =
+                self.start_read_ahead(&mut read_ahead, read_end, len.min(self.max_request_size));
+        }
+
+        hit
+    }
+
+    /// Read `len` bytes at `offset` (expanded to the logical sector size) ahead with read-ahead
+    /// worker, starting it if necessary
+    fn start_read_ahead(
+        &self,
+        read_ahead: &mut ReadAhead,
+        offset: u64,
+        len: usize,
+    ) -> Option<ReadAheadWindow> {
+        let logical_sector_size = self.sector_sizes.logical;
+        let window_offset = offset / logical_sector_size as u64 * logical_sector_size as u64;
+        // Window never exceeds max request size, such that its buffer doesn't grow any larger
+        let window_len = ((offset - window_offset) as usize + len)
+            .next_multiple_of(logical_sector_size)
+            .min(self.max_request_size);
+        let mut buffer = mem::take(&mut read_ahead.buffer);
+        let desired_buffer_size = window_len.div_ceil(DISK_SECTOR_SIZE);
+        if buffer.len() < desired_buffer_size {
+            buffer.resize(desired_buffer_size, AlignedSectorSize::default());
+        }
+
+        let worker = match &mut read_ahead.worker {
+            Some(worker) => worker,
+            worker => worker.insert(ReadAheadWorker::spawn(self).ok()?),
+        };
+        let (result_sender, result_receiver) = mpsc::sync_channel(1);
+        let job = ReadAheadJob {
+            buffer,
+            offset: window_offset,
+            len: window_len,
+            io_priority: self.io_priority(),
+            result_sender,
+        };
+        if worker.job_sender.send(job).is_err() {
+            // Worker thread is gone, it'll be started again next time
+            read_ahead.worker = None;
+            return None;
+        }
+
+        Some(ReadAheadWindow {
+            offset: window_offset,
+            len: window_len,
+            result_receiver,
+        })
+    }
+
+    /// Discard read-ahead window that overlaps with `len` bytes at `offset` that were modified
+    fn invalidate_read_ahead(&self, offset: u64, len: usize) {
+        let window = {
+            let mut read_ahead = self.read_ahead.lock();
+            match read_ahead.window.take() {
+                Some(window) if window.overlaps(offset, len) => window,
+                window => {
+                    read_ahead.window = window;
+                    return;
+                }
+            }
+        };
+
+        // Wait outside of the lock for in-flight read to finish before its buffer can be reused
+        let (buffer, _success) = window.wait();
+        self.read_ahead.lock().buffer = buffer;
+    }
+
+    /// Read `N` bytes at `offset` without locking, intended for large numbers of tiny reads like
+    /// audit and proving reads of record chunks.
+    ///
+    /// Reads within a single logical sector (which includes all reads of at most
+    /// [`MAX_SMALL_READ_SIZE`] bytes that don't cross sector boundary) are done with exactly one
+    /// aligned request into a thread-local buffer and one copy into `buf`, bypassing scratch buffer
+    /// pool and read-ahead. Other reads fall back to [`FileExt::read_exact_at()`]. Reads that end
+    /// past [`FileExt::size()`] fail with [`io::ErrorKind::UnexpectedEof`] either way.
+    pub fn read_small_at<const N: usize>(&self, buf: &mut [u8; N], offset: u64) -> io::Result<()> {
+        self.read_small(buf, offset)
+    }
+
+    /// Same as [`Self::read_small_at()`], but for buffers of size not known at compile time
+    pub(crate) fn read_small(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        // SAFETY: Read paths only write initialized bytes into the buffer
+        self.read_small_uninit(unsafe { as_uninit_bytes(buf) }, offset)
+            .map(|_bytes| ())
+    }
+
+    /// Same as [`Self::read_small()`], but reads into uninitialized buffer and returns it as
+    /// initialized slice, see [`ReadAtSync::read_at_uninit()`]
+    pub(crate) fn read_small_uninit<'a>(
+        &self,
+        buf: &'a mut [MaybeUninit<u8>],
+        offset: u64,
+    ) -> io::Result<&'a mut [u8]> {
+        let len = buf.len();
+        let logical_sector_size = self.sector_sizes.logical;
+        let offset_in_sector = (offset % logical_sector_size as u64) as usize;
+        if len == 0
+            || offset_in_sector + len > logical_sector_size
+            || logical_sector_size > DISK_SECTOR_SIZE
+        {
+            return self.read_at_uninit(buf, offset);
+        }
+
+        let _operation = self.lifecycle.start(&self.path)?;
+        // Buffered I/O doesn't need alignment, but reading through the same buffer ensures `buf`
+        // is left untouched if read fails
+        let result = SMALL_READ_BUFFER.with_borrow_mut(|sector| {
+            let aligned_offset = offset - offset_in_sector as u64;
+            let sector = &mut sector.0[..logical_sector_size];
+            // Short read only happens at the end of the file, hence there is no need to check file
+            // size upfront
+            let bytes_read = self.file_read_at_most(sector, aligned_offset)?;
+            if bytes_read < offset_in_sector + len {
+                return Err(eof_error(offset, len, self.file.size()?));
+            }
+            write_bytes(buf, &sector[offset_in_sector..][..len]);
+
+            Ok(())
+        });
+        result.map_err(|error| self.io_error("read", offset, len, error))?;
+
+        // SAFETY: Every byte of the buffer was written by successful read above
+        Ok(unsafe { assume_init_bytes(buf) })
+    }
+
+    /// Read into possibly uninitialized `buf`, which is fully initialized on success, see
+    /// [`FileExt::read_exact_at()`]
+    fn read_exact_at_uninit(&self, buf: &mut [MaybeUninit<u8>], offset: u64) -> io::Result<()> {
+        let _operation = self.lifecycle.start(&self.path)?;
+        let len = buf.len();
+        self.read_exact_at_unaligned(buf, offset)
+            .map_err(|error| self.io_error("read", offset, len, error))
+    }
+
+    /// Read of arbitrary size at arbitrary offset into possibly uninitialized `buf`, errors are
+    /// returned without context
+    fn read_exact_at_unaligned(&self, buf: &mut [MaybeUninit<u8>], offset: u64) -> io::Result<()> {
+        if buf.is_empty() {
+            return Ok(());
+        }
+
+        // Direct I/O, buffered I/O and reads through scratch buffer fail differently past the end
+        // of the file and may write partial data into `buf` before failing, so bounds are checked
+        // upfront, size is only queried for reads that end past the last known size
+        let len = buf.len();
+        let read_end = offset.saturating_add(len as u64);
+        if read_end > self.known_size.load(Ordering::Relaxed) {
+            let size = self.file.size()?;
+            self.known_size.store(size, Ordering::Relaxed);
+            if read_end > size {
+                return Err(eof_error(offset, len, size));
+            }
+        }
+
+        if self.read_ahead(buf, offset) {
+            return Ok(());
+        }
+
+        if Self::is_aligned(buf.as_ptr().cast(), len, offset, self.sector_sizes.logical) {
+            // Everything is aligned already, read directly into provided buffer without extra copies
+            // (and without initializing it first)
+            return self
+                .file_read_exact_at_uninit(buf, offset)
+                .map_err(|error| {
+                    // File may have been truncated concurrently
+                    match error.kind() {
+                        io::ErrorKind::UnexpectedEof | io::ErrorKind::InvalidInput => {
+                            match self.file.size() {
+                                Ok(size) if offset + len as u64 > size => {
+                                    eof_error(offset, len, size)
+                                }
+                                _ => error,
+                            }
+                        }
+                        _ => error,
+                    }
+                });
+        }
+
+        if buf.len() > self.large_read_threshold {
+            // Buffer is only allocated as large as necessary, but is not kept around afterwards
+            let logical_sector_size = self.sector_sizes.logical;
+            let aligned_len = ((offset % logical_sector_size as u64) as usize + buf.len())
+                .next_multiple_of(logical_sector_size);
+            let mut large_read_buffer = ScratchBuffer::with_capacity(
+                aligned_len
+                    .min(self.max_large_read_size)
+                    .div_ceil(DISK_SECTOR_SIZE),
+            );
+            return self.read_exact_at_internal(
+                &mut large_read_buffer,
+                buf,
+                offset,
+                self.max_large_read_size,
+            );
+        }
+
+        let mut scratch_buffer = self.take_scratch_buffer();
+        self.read_exact_at_internal(&mut scratch_buffer, buf, offset, self.max_request_size)
+    }
+
+    /// Write of arbitrary size at arbitrary offset, errors are returned without context
+    fn write_all_at_unaligned(&self, buf: &[u8], mut offset: u64) -> io::Result<()> {
+        if self.read_only {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "File is opened in read-only mode",
+            ));
+        }
+
+        if buf.is_empty() {
+            return Ok(());
+        }
+
+        let _write_guard = self.write_lock.lock();
+        let physical_sector_size = self.sector_sizes.physical;
+
+        if Self::is_aligned(buf.as_ptr(), buf.len(), offset, physical_sector_size) {
+            // Everything is aligned already, write directly from provided buffer without extra
+            // copies
+            self.count_write(None);
+            return self.file_write_all_at(buf, offset);
+        }
+
+        let mut scratch_buffer = self.take_scratch_buffer();
+
+        // Unaligned head up to the next sector boundary, needs read-modify-write
+        let padding = (offset % physical_sector_size as u64) as usize;
+        let head_size = if padding == 0 {
+            0
+        } else {
+            (physical_sector_size - padding).min(buf.len())
+        };
+        let (head, buf) = buf.split_at(head_size);
+        if !head.is_empty() {
+            self.write_all_at_internal(&mut scratch_buffer, head, offset)?;
+            offset += head.len() as u64;
+        }
+
+        // Whole sectors in the middle are written without reading them first, up to
+        // `max_request_size` at a time
+        let body_size = buf.len() / physical_sector_size * physical_sector_size;
+        let (body, tail) = buf.split_at(body_size);
+        for chunk in body.chunks(self.max_request_size) {
+            if Self::is_aligned(chunk.as_ptr(), chunk.len(), offset, physical_sector_size) {
+                self.count_write(None);
+                self.file_write_all_at(chunk, offset)?;
+            } else {
+                self.write_all_at_internal(&mut scratch_buffer, chunk, offset)?;
+            }
+            offset += chunk.len() as u64;
+        }
+
+        // Unaligned tail within the last sector, needs read-modify-write
+        if !tail.is_empty() {
+            self.write_all_at_internal(&mut scratch_buffer, tail, offset)?;
+        }
+
+        Ok(())
+    }
+
+    /// Attach context of the requested operation to the error
+    fn io_error(
+        &self,
+        operation: &'static str,
+        offset: u64,
+        len: usize,
+        error: io::Error,
+    ) -> io::Error {
+        io::Error::new(
+            error.kind(),
+            DirectIoError {
+                operation,
+                path: self.path.clone(),
+                offset,
+                len,
+                logical_sector_size: self.sector_sizes.logical,
+                physical_sector_size: self.sector_sizes.physical,
+                source: error,
+            },
+        )
+    }
+
+    /// Read from the underlying file with a single request, observing metrics if enabled
+    fn file_read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        let len = buf.len();
+        let _permit =
+            self.io_request_limiter
+                .acquire(self.io_priority(), len, self.metrics.as_deref());
+
+        let start = Instant::now();
+        if let Err(error) = self.file.read_exact_at(buf, offset) {
+            self.observe_read_error();
+            return Err(aligned_io_error("read", offset, len, error));
+        }
+        self.observe_read(len, start.elapsed());
+
+        Ok(())
+    }
+
+    /// Same as [`Self::file_read_exact_at()`], but reads into possibly uninitialized `buf`
+    fn file_read_exact_at_uninit(
+        &self,
+        mut buf: &mut [MaybeUninit<u8>],
+        mut offset: u64,
+    ) -> io::Result<()> {
+        let len = buf.len();
+        let aligned_offset = offset;
+        let _permit =
+            self.io_request_limiter
+                .acquire(self.io_priority(), len, self.metrics.as_deref());
+
+        let start = Instant::now();
+        while !buf.is_empty() {
+            match read_at_raw(&self.file, buf, offset) {
+                Ok(0) => {
+                    self.observe_read_error();
+                    return Err(aligned_io_error(
+                        "read",
+                        aligned_offset,
+                        len,
+                        io::Error::new(io::ErrorKind::UnexpectedEof, "failed to fill whole buffer"),
+                    ));
+                }
+                Ok(n) => {
+                    buf = &mut mem::take(&mut buf)[n..];
+                    offset += n as u64;
+                }
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {
+                    // Try again
+                }
+                Err(error) => {
+                    self.observe_read_error();
+                    return Err(aligned_io_error("read", aligned_offset, len, error));
+                }
+            }
+        }
+        self.observe_read(len, start.elapsed());
+
+        Ok(())
+    }
+
+    /// Read from the underlying file up to the end of the file, observing metrics if enabled,
+    /// returns number of bytes read.
+    ///
+    /// Used for aligned reads covering unaligned tail of the file, reading stops at the first
+    /// unaligned short read since the next read would be unaligned, which only happens at the end
+    /// of the file.
+    fn file_read_at_most(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        let _permit =
+            self.io_request_limiter
+                .acquire(self.io_priority(), buf.len(), self.metrics.as_deref());
+        let start = Instant::now();
+
+        let mut bytes_read = 0;
+        while bytes_read < buf.len() {
+            #[cfg(unix)]
+            let result = std::os::unix::fs::FileExt::read_at(
+                &*self.file,
+                &mut buf[bytes_read..],
+                offset + bytes_read as u64,
+            );
+            #[cfg(windows)]
+            let result = std::os::windows::fs::FileExt::seek_read(
+                &*self.file,
+                &mut buf[bytes_read..],
+                offset + bytes_read as u64,
+            );
+
+            match result {
+                Ok(0) => {
+                    break;
+                }
+                Ok(n) => {
+                    bytes_read += n;
+                    if bytes_read % self.sector_sizes.logical != 0 {
+                        break;
+                    }
+                }
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {
+                    // Try again
+                }
+                Err(error) => {
+                    self.observe_read_error();
+                    return Err(aligned_io_error("read", offset, buf.len(), error));
+                }
+            }
+        }
+
+        self.observe_read(bytes_read, start.elapsed());
+
+        Ok(bytes_read)
+    }
+
+    /// Count successful read request in [`RequestStats`] and metrics (if enabled)
+    fn observe_read(&self, bytes: usize, time: Duration) {
+        self.request_counters.observe_read(bytes, time);
+        if let Some(metrics) = &self.metrics {
+            metrics.observe_read(bytes, time);
+        }
+    }
+
+    /// Count failed read request in [`RequestStats`] and metrics (if enabled)
+    fn observe_read_error(&self) {
+        self.request_counters.observe_read_error();
+        if let Some(metrics) = &self.metrics {
+            metrics.observe_read_error();
+        }
+    }
+
+    /// Count write request in I/O stats and metrics (if enabled), `amplified_bytes` is `Some` for
+    /// read-modify-write, see [`IoStats::amplified_bytes`]
+    fn count_write(&self, amplified_bytes: Option<usize>) {
+        match amplified_bytes {
+            Some(amplified_bytes) => {
+                self.read_modify_writes.fetch_add(1, Ordering::Relaxed);
+                self.amplified_bytes
+                    .fetch_add(amplified_bytes as u64, Ordering::Relaxed);
+            }
+            None => {
+                self.aligned_writes.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        if let Some(metrics) = &self.metrics {
+            metrics.observe_write_alignment(amplified_bytes);
+        }
+    }
+
+    /// Write into the underlying file with a single request, observing metrics if enabled
+    fn file_write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        let _permit =
+            self.io_request_limiter
+                .acquire(self.io_priority(), buf.len(), self.metrics.as_deref());
+
+        let start = Instant::now();
+        if let Err(error) = self.file.write_all_at(buf, offset) {
+            self.request_counters.observe_write_error();
+            if let Some(metrics) = &self.metrics {
+                metrics.observe_write_error();
+            }
+            return Err(aligned_io_error("write", offset, buf.len(), error));
+        }
+        let time = start.elapsed();
+        self.request_counters.observe_write(buf.len(), time);
+        if let Some(metrics) = &self.metrics {
+            metrics.observe_write(buf.len(), time);
+        }
+
+        Ok(())
+    }
+
+    /// Read into `buf` through scratch buffer in aligned windows of at most `max_window_size`
+    /// bytes, such that request of any size doesn't grow scratch buffer beyond `max_window_size`
+    fn read_exact_at_internal(
+        &self,
+        scratch_buffer: &mut ScratchBuffer,
+        mut buf: &mut [MaybeUninit<u8>],
+        mut offset: u64,
+        max_window_size: usize,
+    ) -> io::Result<()> {
+        while !buf.is_empty() {
+            let offset_in_window = (offset % self.sector_sizes.logical as u64) as usize;
+            let chunk_size = (max_window_size - offset_in_window).min(buf.len());
+            let (chunk, rest) = mem::take(&mut buf).split_at_mut(chunk_size);
+            write_bytes(
+                chunk,
+                self.read_window(scratch_buffer, chunk_size, offset, max_window_size)?,
+            );
+            offset += chunk_size as u64;
+            buf = rest;
+        }
+
+        Ok(())
+    }
+
+    /// Read `bytes_to_read` bytes at `offset` with a single aligned request into scratch buffer,
+    /// panics if aligned request (including padding on both ends) exceeds `max_window_size`
+    fn read_window<'a>(
+        &self,
+        scratch_buffer: &'a mut ScratchBuffer,
+        bytes_to_read: usize,
+        offset: u64,
+        max_window_size: usize,
+    ) -> io::Result<&'a [u8]> {
+        // Make scratch buffer of a size that is necessary to read aligned memory, accounting
+        // for extra bytes at the beginning and the end that will be thrown away, reads are done
+        // with granularity of logical sector size, while buffer is allocated with granularity of
+        // `DISK_SECTOR_SIZE`
+        let logical_sector_size = self.sector_sizes.logical;
+        let offset_in_buffer = (offset % logical_sector_size as u64) as usize;
+        let aligned_bytes_to_read =
+            (bytes_to_read + offset_in_buffer).div_ceil(logical_sector_size) * logical_sector_size;
+        assert!(
+            aligned_bytes_to_read <= max_window_size,
+            "Callers split requests into windows of at most max window size; qed"
+        );
+        let desired_buffer_size = aligned_bytes_to_read.div_ceil(DISK_SECTOR_SIZE);
+        if scratch_buffer.len() < desired_buffer_size {
+            scratch_buffer.resize(desired_buffer_size, AlignedSectorSize::default());
+        }
+
+        let aligned_offset = offset / logical_sector_size as u64 * logical_sector_size as u64;
+        let aligned_buffer =
+            &mut AlignedSectorSize::slice_to_bytes_mut(scratch_buffer)[..aligned_bytes_to_read];
+        if let Err(error) = self.file_read_exact_at(aligned_buffer, aligned_offset) {
+            // Aligned read extends beyond the end of the file if its size is not a multiple of
+            // sector size (see `Self::set_len()`), which results in either unexpected end of file
+            // or invalid unaligned read after the short read, this is fine as long as requested
+            // bytes are before the end of the file
+            if !matches!(
+                error.kind(),
+                io::ErrorKind::UnexpectedEof | io::ErrorKind::InvalidInput
+            ) {
+                return Err(error);
+            }
+
+            let bytes_read = self.file_read_at_most(aligned_buffer, aligned_offset)?;
+            if bytes_read < offset_in_buffer + bytes_to_read {
+                return Err(eof_error(
+                    offset,
+                    bytes_to_read,
+                    aligned_offset + bytes_read as u64,
+                ));
+            }
+            // Bytes beyond the end of the file are zeroes for read-modify-write
+            aligned_buffer[bytes_read..].fill(0);
+        }
+
+        Ok(&AlignedSectorSize::slice_to_bytes(scratch_buffer)[offset_in_buffer..][..bytes_to_read])
+    }
+
+    /// Panics on writes over max request size (including padding on both ends)
+    fn write_all_at_internal(
+        &self,
+        scratch_buffer: &mut ScratchBuffer,
+        bytes_to_write: &[u8],
+        offset: u64,
+    ) -> io::Result<()> {
+        // This is guaranteed by `DirectIoFile::new_scratch_buffer()`
+        assert!(AlignedSectorSize::slice_to_bytes(scratch_buffer).len() >= self.max_request_size);
+
+        // Writes are done with granularity of physical sector size, such that disk doesn't need to
+        // do read-modify-write internally
+        let physical_sector_size = self.sector_sizes.physical;
+        let aligned_offset = offset / physical_sector_size as u64 * physical_sector_size as u64;
+        let padding = (offset - aligned_offset) as usize;
+        // Calculate the size of the read including padding on both ends
+        let bytes_to_read =
+            (padding + bytes_to_write.len()).div_ceil(physical_sector_size) * physical_sector_size;
+
+        if padding == 0 && bytes_to_read == bytes_to_write.len() {
+            let scratch_buffer =
+                &mut AlignedSectorSize::slice_to_bytes_mut(scratch_buffer)[..bytes_to_read];
+            scratch_buffer.copy_from_slice(bytes_to_write);
+            self.count_write(None);
+            self.file_write_all_at(scratch_buffer, offset)?;
+        } else {
+            self.count_write(Some(bytes_to_read - bytes_to_write.len()));
+            // Read whole pages where `bytes_to_write` will be written
+            self.read_window(
+                scratch_buffer,
+                bytes_to_read,
+                aligned_offset,
+                self.max_request_size,
+            )?;
+            let scratch_buffer =
+                &mut AlignedSectorSize::slice_to_bytes_mut(scratch_buffer)[..bytes_to_read];
+            // Update contents of existing pages and write into the file
+            scratch_buffer[padding..][..bytes_to_write.len()].copy_from_slice(bytes_to_write);
+            self.file_write_all_at(scratch_buffer, aligned_offset)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::farm::FarmId;
+    use crate::single_disk_farm::direct_io_file::memory_budget::IoMemoryBudget;
+    use crate::single_disk_farm::direct_io_file::metrics::{DiskFileKind, DiskMetrics};
+    use crate::single_disk_farm::direct_io_file::{
+        AlignedSectorSize, DirectIoError, DirectIoFile, DirectIoOptions, IoMode, IoPriority,
+        IoRequestLimiter, IoStats, OpenMode, ScratchBuffer, SectorSizes,
+        DEFAULT_MAX_LARGE_READ_SIZE, DEFAULT_MAX_REQUEST_SIZE, DISK_SECTOR_SIZE,
+        MAX_SMALL_READ_SIZE, MIN_MAX_REQUEST_SIZE,
+    };
+    use parking_lot::RwLock;
+    use prometheus_client::registry::Registry;
+    use rand::prelude::*;
+    use std::fs::OpenOptions;
+    use std::mem::MaybeUninit;
+    use std::num::{NonZeroU64, NonZeroUsize};
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+    use std::{fs, io, slice, thread};
+    use subspace_farmer_components::file_ext::{AccessPattern, FileExt};
+    use subspace_farmer_components::ReadAtSync;
+    use tempfile::tempdir;
+
+    /// Sector sizes to override detected ones with, logical and physical sizes are set
+    /// independently, but stay compatible with detected sizes such that direct I/O keeps working
+    fn sector_sizes_overrides(file: &DirectIoFile) -> [Option<SectorSizes>; 3] {
+        [
+            None,
+            Some(SectorSizes {
+                logical: file.logical_sector_size(),
+                physical: DISK_SECTOR_SIZE.max(file.logical_sector_size()),
+            }),
+            Some(SectorSizes {
+                logical: DISK_SECTOR_SIZE.max(file.logical_sector_size()),
+                physical: DISK_SECTOR_SIZE.max(file.physical_sector_size()),
+            }),
+        ]
+    }
+
+    #[test]
+    fn basic() {
+        let tempdir = tempdir().unwrap();
+        let file_path = tempdir.as_ref().join("file.bin");
+        let mut data = vec![0u8; DEFAULT_MAX_REQUEST_SIZE * 5];
+        thread_rng().fill(data.as_mut_slice());
+        fs::write(&file_path, &data).unwrap();
+
+        let mut file = DirectIoFile::open(&file_path).unwrap();
+
+        for override_sector_sizes in sector_sizes_overrides(&file) {
+            if let Some(sector_sizes) = override_sector_sizes {
+                file.sector_sizes = sector_sizes;
+            }
+
+            let mut buffer = Vec::new();
+            for (offset, size) in [
+                (0_usize, 512_usize),
+                (0_usize, 4096_usize),
+                (0, 500),
+                (0, 4000),
+                (5, 50),
+                (12, 500),
+                (96, 4000),
+                (4000, 96),
+                (10000, 5),
+                (0, DEFAULT_MAX_REQUEST_SIZE),
+                (0, DEFAULT_MAX_REQUEST_SIZE * 2),
+                (5, DEFAULT_MAX_REQUEST_SIZE - 5),
+                (5, DEFAULT_MAX_REQUEST_SIZE * 2 - 5),
+                (5, DEFAULT_MAX_REQUEST_SIZE),
+                (5, DEFAULT_MAX_REQUEST_SIZE * 2),
+                (DEFAULT_MAX_REQUEST_SIZE, DEFAULT_MAX_REQUEST_SIZE),
+                (DEFAULT_MAX_REQUEST_SIZE, DEFAULT_MAX_REQUEST_SIZE * 2),
+                (DEFAULT_MAX_REQUEST_SIZE + 5, DEFAULT_MAX_REQUEST_SIZE - 5),
+                (
+                    DEFAULT_MAX_REQUEST_SIZE + 5,
+                    DEFAULT_MAX_REQUEST_SIZE * 2 - 5,
+                ),
+                (DEFAULT_MAX_REQUEST_SIZE + 5, DEFAULT_MAX_REQUEST_SIZE),
+                (DEFAULT_MAX_REQUEST_SIZE + 5, DEFAULT_MAX_REQUEST_SIZE * 2),
+            ] {
+                let data = &mut data[offset..][..size];
+                buffer.resize(size, 0);
+                // Read contents
+                file.read_exact_at(buffer.as_mut_slice(), offset as u64)
+                    .unwrap_or_else(|error| {
+                        panic!(
+                            "Offset {offset}, size {size}, override sector sizes \
+                            {override_sector_sizes:?}: {error}"
+                        )
+                    });
+
+                // Ensure it is correct
+                assert_eq!(
+                    data,
+                    buffer.as_slice(),
+                    "Offset {offset}, size {size}, override sector sizes \
+                    {override_sector_sizes:?}"
+                );
+
+                // Update data with random contents and write
+                thread_rng().fill(data);
+                file.write_all_at(data, offset as u64)
+                    .unwrap_or_else(|error| {
+                        panic!(
+                            "Offset {offset}, size {size}, override sector sizes \
+                            {override_sector_sizes:?}: {error}"
+                        )
+                    });
+
+                // Read contents again
+                file.read_exact_at(buffer.as_mut_slice(), offset as u64)
+                    .unwrap_or_else(|error| {
+                        panic!(
+                            "Offset {offset}, size {size}, override sector sizes \
+                            {override_sector_sizes:?}: {error}"
+                        )
+                    });
+
+                // Ensure it is correct too
+                assert_eq!(
+                    data,
+                    buffer.as_slice(),
+                    "Offset {offset}, size {size}, override sector sizes \
+                    {override_sector_sizes:?}"
+                );
+            }
+        }
+    }
+
+    /// Returns slices of `size` bytes within `memory`, one aligned to [`DISK_SECTOR_SIZE`] and one
+    /// that is not aligned
+    fn aligned_and_unaligned(memory: &mut [u8], size: usize) -> (&mut [u8], &mut [u8]) {
+        let (aligned, unaligned) = memory.split_at_mut(memory.len() / 2);
+        let aligned_start = aligned.as_ptr().align_offset(DISK_SECTOR_SIZE);
+        let unaligned_start = unaligned.as_ptr().align_offset(DISK_SECTOR_SIZE) + 1;
+
+        (
+            &mut aligned[aligned_start..][..size],
+            &mut unaligned[unaligned_start..][..size],
+        )
+    }
+
+    #[test]
+    fn aligned_and_unaligned_paths() {
+        let tempdir = tempdir().unwrap();
+        let file_path = tempdir.as_ref().join("file.bin");
+        let mut data = vec![0u8; DEFAULT_MAX_REQUEST_SIZE * 3];
+        thread_rng().fill(data.as_mut_slice());
+        fs::write(&file_path, &data).unwrap();
+
+        let mut file = DirectIoFile::open(&file_path).unwrap();
+        let mut memory = vec![0u8; (DEFAULT_MAX_REQUEST_SIZE * 2 + DISK_SECTOR_SIZE) * 2];
+
+        for override_sector_sizes in sector_sizes_overrides(&file) {
+            if let Some(sector_sizes) = override_sector_sizes {
+                file.sector_sizes = sector_sizes;
+            }
+
+            for (offset, size) in [
+                (0_usize, DISK_SECTOR_SIZE),
+                (DISK_SECTOR_SIZE, DISK_SECTOR_SIZE * 3),
+                (0, DEFAULT_MAX_REQUEST_SIZE),
+                (DISK_SECTOR_SIZE, DEFAULT_MAX_REQUEST_SIZE),
+                (DEFAULT_MAX_REQUEST_SIZE, DEFAULT_MAX_REQUEST_SIZE * 2),
+            ] {
+                let (aligned, unaligned) = aligned_and_unaligned(&mut memory, size);
+
+                // Reads with both paths must match file contents
+                file.read_exact_at(aligned, offset as u64).unwrap();
+                file.read_exact_at(unaligned, offset as u64).unwrap();
+                assert_eq!(
+                    &data[offset..][..size],
+                    aligned,
+                    "Offset {offset}, size {size}, override sector sizes \
+                    {override_sector_sizes:?}"
+                );
+                assert_eq!(
+                    aligned, unaligned,
+                    "Offset {offset}, size {size}, override sector sizes \
+                    {override_sector_sizes:?}"
+                );
+
+                // Write with aligned path and read back with unaligned path
+                thread_rng().fill(&mut data[offset..][..size]);
+                aligned.copy_from_slice(&data[offset..][..size]);
+                file.write_all_at(aligned, offset as u64).unwrap();
+                file.read_exact_at(unaligned, offset as u64).unwrap();
+                assert_eq!(
+                    &data[offset..][..size],
+                    unaligned,
+                    "Offset {offset}, size {size}, override sector sizes \
+                    {override_sector_sizes:?}"
+                );
+
+                // Write with unaligned path and read back with aligned path
+                thread_rng().fill(&mut data[offset..][..size]);
+                unaligned.copy_from_slice(&data[offset..][..size]);
+                file.write_all_at(unaligned, offset as u64).unwrap();
+                file.read_exact_at(aligned, offset as u64).unwrap();
+                assert_eq!(
+                    &data[offset..][..size],
+                    aligned,
+                    "Offset {offset}, size {size}, override sector sizes \
+                    {override_sector_sizes:?}"
+                );
+            }
+        }
+
+        assert_eq!(data, fs::read(&file_path).unwrap());
+    }
+
+    #[test]
+    fn large_unaligned_writes() {
+        let tempdir = tempdir().unwrap();
+        let file_path = tempdir.as_ref().join("file.bin");
+        let write_size = DEFAULT_MAX_REQUEST_SIZE * 5 + 7;
+        let file_size = DEFAULT_MAX_REQUEST_SIZE * 6;
+        let mut data = vec![0u8; file_size];
+        thread_rng().fill(data.as_mut_slice());
+        fs::write(&file_path, &data).unwrap();
+
+        let file = DirectIoFile::open(&file_path).unwrap();
+        let physical_sector_size = file.physical_sector_size();
+
+        for offset in [0_usize, 1, 511, 512, 4095, 4096, 4097, 10_000] {
+            thread_rng().fill(&mut data[offset..][..write_size]);
+            let read_modify_writes_before = file.read_modify_writes();
+            file.write_all_at(&data[offset..][..write_size], offset as u64)
+                .unwrap();
+
+            // Only unaligned head and tail need read-modify-write
+            let expected_read_modify_writes = [offset, offset + write_size]
+                .into_iter()
+                .filter(|position| position % physical_sector_size != 0)
+                .count() as u64;
+            assert_eq!(
+                file.read_modify_writes() - read_modify_writes_before,
+                expected_read_modify_writes,
+                "Offset {offset}, physical sector size {physical_sector_size}"
+            );
+
+            let mut buffer = vec![0u8; file_size];
+            file.read_exact_at(&mut buffer, 0).unwrap();
+            assert_eq!(
+                data, buffer,
+                "Offset {offset}, physical sector size {physical_sector_size}"
+            );
+        }
+
+        assert_eq!(data, fs::read(&file_path).unwrap());
+    }
+
+    #[test]
+    fn read_ahead() {
+        let tempdir = tempdir().unwrap();
+        let file_path = tempdir.as_ref().join("file.bin");
+        let file_size = DEFAULT_MAX_REQUEST_SIZE * 8;
+        let mut data = vec![0u8; file_size];
+        thread_rng().fill(data.as_mut_slice());
+        fs::write(&file_path, &data).unwrap();
+
+        let file = DirectIoFile::open(&file_path).unwrap();
+        let mut buffer = Vec::new();
+
+        for access_pattern in [
+            AccessPattern::Normal,
+            AccessPattern::Sequential,
+            AccessPattern::Random,
+            AccessPattern::Sequential,
+            AccessPattern::Normal,
+        ] {
+            file.set_access_pattern(access_pattern).unwrap();
+
+            for (start, size) in [
+                (0_usize, DEFAULT_MAX_REQUEST_SIZE),
+                (5, DEFAULT_MAX_REQUEST_SIZE),
+                (7, 100_000),
+                (DISK_SECTOR_SIZE, DISK_SECTOR_SIZE),
+            ] {
+                let mut offset = start;
+                let mut step = 0;
+                while offset + size <= file_size {
+                    match step % 4 {
+                        // Random read in between sequential reads
+                        1 => {
+                            let random_offset = thread_rng().gen_range(0..file_size - size);
+                            buffer.resize(size, 0);
+                            file.read_exact_at(&mut buffer, random_offset as u64)
+                                .unwrap();
+                            assert_eq!(&data[random_offset..][..size], buffer.as_slice());
+                        }
+                        // Modify contents right after the last read, that was likely read ahead
+                        3 => {
+                            let update_size = size.min(file_size - offset).min(1000);
+                            thread_rng().fill(&mut data[offset..][..update_size]);
+                            file.write_all_at(&data[offset..][..update_size], offset as u64)
+                                .unwrap();
+                        }
+                        _ => {}
+                    }
+                    step += 1;
+
+                    buffer.resize(size, 0);
+                    file.read_exact_at(&mut buffer, offset as u64)
+                        .unwrap_or_else(|error| {
+                            panic!(
+                                "Offset {offset}, size {size}, access pattern {access_pattern:?}: \
+                            {error}"
+                            )
+                        });
+                    assert_eq!(
+                        &data[offset..][..size],
+                        buffer.as_slice(),
+                        "Offset {offset}, size {size}, access pattern {access_pattern:?}"
+                    );
+                    offset += size;
+                }
+            }
+
+            if access_pattern == AccessPattern::Random {
+                let read_ahead = file.read_ahead.lock();
+                assert!(read_ahead.window.is_none());
+                assert!(read_ahead.worker.is_none());
+            }
+        }
+
+        // Read ahead after every read in sequential mode
+        file.set_access_pattern(AccessPattern::Sequential).unwrap();
+        buffer.resize(DISK_SECTOR_SIZE, 0);
+        file.read_exact_at(&mut buffer, 0).unwrap();
+        assert!(file.read_ahead.lock().window.is_some());
+
+        // Read ahead window is discarded when file is truncated
+        file.set_len(DISK_SECTOR_SIZE as u64).unwrap();
+        assert!(file.read_ahead.lock().window.is_none());
+        assert!(file
+            .read_exact_at(&mut buffer, DISK_SECTOR_SIZE as u64)
+            .is_err());
+
+        // Concurrent sequential readers
+        let file = Arc::new(DirectIoFile::open(&file_path).unwrap());
+        fs::write(&file_path, &data).unwrap();
+        thread::scope(|scope| {
+            for thread_index in 0..4 {
+                let file = &file;
+                let data = &data;
+                scope.spawn(move || {
+                    let mut buffer = vec![0u8; 100_000];
+                    let mut offset = thread_index * file_size / 4;
+                    while offset + buffer.len() <= (thread_index + 1) * file_size / 4 {
+                        file.read_exact_at(&mut buffer, offset as u64).unwrap();
+                        assert_eq!(&data[offset..][..buffer.len()], buffer.as_slice());
+                        offset += buffer.len();
+                    }
+                });
+            }
+        });
+    }
+
+    #[test]
+    fn scratch_buffer_alignment() {
+        let tempdir = tempdir().unwrap();
+        let file = DirectIoFile::open(&tempdir.as_ref().join("file.bin")).unwrap();
+
+        let assert_aligned = |scratch_buffer: &ScratchBuffer| {
+            assert_eq!(
+                AlignedSectorSize::slice_to_bytes(scratch_buffer).as_ptr() as usize
+                    % DISK_SECTOR_SIZE,
+                0
+            );
+        };
+
+        // Pre-allocated, newly allocated and reallocated after resizing buffers are all aligned
+        let mut pooled_scratch_buffer = file.take_scratch_buffer();
+        assert_aligned(&pooled_scratch_buffer);
+        let mut scratch_buffer = file.take_scratch_buffer();
+        assert_aligned(&scratch_buffer);
+        for scratch_buffer in [&mut *pooled_scratch_buffer, &mut *scratch_buffer] {
+            let len = scratch_buffer.len();
+            scratch_buffer.resize(len * 3 + 1, AlignedSectorSize::default());
+            assert_aligned(scratch_buffer);
+        }
+    }
+
+    #[test]
+    fn sector_sizes() {
+        let tempdir = tempdir().unwrap();
+        let file_path = tempdir.as_ref().join("file.bin");
+        let file_size = DEFAULT_MAX_REQUEST_SIZE * 2;
+        let mut data = vec![0u8; file_size];
+        thread_rng().fill(data.as_mut_slice());
+        fs::write(&file_path, &data).unwrap();
+
+        let mut file = DirectIoFile::open(&file_path).unwrap();
+        assert!(DirectIoFile::is_supported_sector_size(
+            file.logical_sector_size(),
+            DEFAULT_MAX_REQUEST_SIZE
+        ));
+        assert!(DirectIoFile::is_supported_sector_size(
+            file.physical_sector_size(),
+            DEFAULT_MAX_REQUEST_SIZE
+        ));
+        assert_eq!(file.physical_sector_size() % file.logical_sector_size(), 0);
+
+        assert!(!DirectIoFile::is_supported_sector_size(
+            0,
+            DEFAULT_MAX_REQUEST_SIZE
+        ));
+        assert!(!DirectIoFile::is_supported_sector_size(
+            256,
+            DEFAULT_MAX_REQUEST_SIZE
+        ));
+        assert!(!DirectIoFile::is_supported_sector_size(
+            520,
+            DEFAULT_MAX_REQUEST_SIZE
+        ));
+        assert!(!DirectIoFile::is_supported_sector_size(
+            DEFAULT_MAX_REQUEST_SIZE * 2,
+            DEFAULT_MAX_REQUEST_SIZE
+        ));
+
+        // Replace file with buffered one to emulate query returning arbitrary supported sector
+        // sizes
+        file.file = Arc::new(
+            OpenOptions::new()
+                .read(true)
+                .write(true)
+                .open(&file_path)
+                .unwrap(),
+        );
+
+        for (logical, physical) in [
+            (512, 512),
+            (512, 4096),
+            (4096, 4096),
+            (4096, 8192),
+            (8192, 8192),
+        ] {
+            let sector_sizes = SectorSizes { logical, physical };
+            file.sector_sizes = sector_sizes;
+            assert_eq!(file.logical_sector_size(), logical);
+            assert_eq!(file.physical_sector_size(), physical);
+
+            for sector_size in [logical, physical] {
+                assert!(DirectIoFile::is_supported_sector_size(
+                    sector_size,
+                    DEFAULT_MAX_REQUEST_SIZE
+                ));
+                assert!(DirectIoFile::is_aligned(
+                    DISK_SECTOR_SIZE as *const u8,
+                    sector_size,
+                    sector_size as u64 * 3,
+                    sector_size
+                ));
+                assert!(!DirectIoFile::is_aligned(
+                    DISK_SECTOR_SIZE as *const u8,
+                    sector_size,
+                    sector_size as u64 / 2,
+                    sector_size
+                ));
+                assert!(!DirectIoFile::is_aligned(
+                    DISK_SECTOR_SIZE as *const u8,
+                    sector_size + 1,
+                    0,
+                    sector_size
+                ));
+            }
+
+            // Write of a whole logical sector needs read-modify-write unless it is also a whole
+            // physical sector
+            let read_modify_writes_before = file.read_modify_writes();
+            thread_rng().fill(&mut data[logical..][..logical]);
+            file.write_all_at(&data[logical..][..logical], logical as u64)
+                .unwrap();
+            assert_eq!(
+                file.read_modify_writes() - read_modify_writes_before,
+                u64::from(logical % physical != 0),
+                "Sector sizes {sector_sizes:?}"
+            );
+
+            let mut buffer = Vec::new();
+            for (offset, size) in [
+                (0_usize, 512_usize),
+                (5, 50),
+                (100, 8192),
+                (8000, 400),
+                (8192, 8192),
+                (5, DEFAULT_MAX_REQUEST_SIZE),
+                (DEFAULT_MAX_REQUEST_SIZE - 5, 10),
+                (DEFAULT_MAX_REQUEST_SIZE - 5, DEFAULT_MAX_REQUEST_SIZE),
+            ] {
+                buffer.resize(size, 0);
+
+                file.read_exact_at(&mut buffer, offset as u64).unwrap();
+                assert_eq!(
+                    &data[offset..][..size],
+                    buffer.as_slice(),
+                    "Offset {offset}, size {size}, sector sizes {sector_sizes:?}"
+                );
+
+                thread_rng().fill(&mut data[offset..][..size]);
+                file.write_all_at(&data[offset..][..size], offset as u64)
+                    .unwrap();
+                file.read_exact_at(&mut buffer, offset as u64).unwrap();
+                assert_eq!(
+                    &data[offset..][..size],
+                    buffer.as_slice(),
+                    "Offset {offset}, size {size}, sector sizes {sector_sizes:?}"
+                );
+            }
+        }
+
+        assert_eq!(data, fs::read(&file_path).unwrap());
+    }
+
+    #[test]
+    fn max_request_size() {
+        let tempdir = tempdir().unwrap();
+        let file_path = tempdir.as_ref().join("file.bin");
+        let file_size = 4 * 1024 * 1024 * 3;
+        let mut data = vec![0u8; file_size];
+        thread_rng().fill(data.as_mut_slice());
+        fs::write(&file_path, &data).unwrap();
+
+        for max_request_size in [
+            DISK_SECTOR_SIZE,
+            DISK_SECTOR_SIZE * 16 + 1,
+            128 * 1024 * 1024,
+        ] {
+            assert!(DirectIoFile::open_with_options(
+                &file_path,
+                DirectIoOptions {
+                    max_request_size,
+                    ..DirectIoOptions::default()
+                },
+            )
+            .is_err());
+        }
+
+        for max_request_size in [256 * 1024, 4 * 1024 * 1024] {
+            let file = DirectIoFile::open_with_options(
+                &file_path,
+                DirectIoOptions {
+                    max_request_size,
+                    ..DirectIoOptions::default()
+                },
+            )
+            .unwrap();
+
+            let mut buffer = Vec::new();
+            for (offset, size) in [
+                (0_usize, 512_usize),
+                (5, 50),
+                (4000, 96),
+                (0, max_request_size),
+                (5, max_request_size - 5),
+                (5, max_request_size),
+                (5, max_request_size * 2),
+                (max_request_size + 5, max_request_size * 2 - 5),
+                (max_request_size - 5, 10),
+            ] {
+                buffer.resize(size, 0);
+
+                file.read_exact_at(&mut buffer, offset as u64).unwrap();
+                assert_eq!(
+                    &data[offset..][..size],
+                    buffer.as_slice(),
+                    "Offset {offset}, size {size}, max request size {max_request_size}"
+                );
+
+                thread_rng().fill(&mut data[offset..][..size]);
+                file.write_all_at(&data[offset..][..size], offset as u64)
+                    .unwrap();
+                file.read_exact_at(&mut buffer, offset as u64).unwrap();
+                assert_eq!(
+                    &data[offset..][..size],
+                    buffer.as_slice(),
+                    "Offset {offset}, size {size}, max request size {max_request_size}"
+                );
+            }
+
+            let mut first = vec![0u8; 100];
+            let mut second = vec![0u8; 100];
+            let second_offset = max_request_size as u64 - 50;
+            file.read_exact_at_vectored(&mut [
+                (10, first.as_mut_slice()),
+                (second_offset, second.as_mut_slice()),
+            ])
+            .unwrap();
+            assert_eq!(&data[10..][..100], first.as_slice());
+            assert_eq!(&data[second_offset as usize..][..100], second.as_slice());
+        }
+
+        assert_eq!(data, fs::read(&file_path).unwrap());
+    }
+
+    #[test]
+    fn buffered_io() {
+        let tempdir = tempdir().unwrap();
+        let file_path = tempdir.as_ref().join("file.bin");
+        let mut data = vec![0u8; DEFAULT_MAX_REQUEST_SIZE * 2];
+        thread_rng().fill(data.as_mut_slice());
+        fs::write(&file_path, &data).unwrap();
+
+        let file = DirectIoFile::open_with_options(
+            &file_path,
+            DirectIoOptions {
+                buffered_io: true,
+                ..DirectIoOptions::default()
+            },
+        )
+        .unwrap();
+        assert!(!file.is_unbuffered());
+
+        let mut buffer = Vec::new();
+        for (offset, size) in [
+            (0_usize, 512_usize),
+            (5, 50),
+            (4000, 96),
+            (DEFAULT_MAX_REQUEST_SIZE - 5, 10),
+            (5, DEFAULT_MAX_REQUEST_SIZE + 5),
+        ] {
+            buffer.resize(size, 0);
+
+            file.read_exact_at(&mut buffer, offset as u64).unwrap();
+            assert_eq!(&data[offset..][..size], buffer.as_slice());
+
+            thread_rng().fill(&mut data[offset..][..size]);
+            file.write_all_at(&data[offset..][..size], offset as u64)
+                .unwrap();
+            file.read_exact_at(&mut buffer, offset as u64).unwrap();
+            assert_eq!(&data[offset..][..size], buffer.as_slice());
+        }
+
+        assert_eq!(data, fs::read(&file_path).unwrap());
+
+        // Probe on empty file writes and truncates it back
+        let empty_file_path = tempdir.as_ref().join("empty.bin");
+        let empty_file = DirectIoFile::open(&empty_file_path).unwrap();
+        assert_eq!(empty_file.size().unwrap(), 0);
+        // Unless buffered I/O is requested, mode only depends on platform and file system support
+        assert_eq!(
+            empty_file.is_unbuffered(),
+            DirectIoFile::open(&file_path).unwrap().is_unbuffered()
+        );
+    }
+
+    #[test]
+    fn write_through() {
+        let tempdir = tempdir().unwrap();
+        let file_path = tempdir.as_ref().join("file.bin");
+        let mut data = vec![0u8; DISK_SECTOR_SIZE * 4];
+        thread_rng().fill(data.as_mut_slice());
+        fs::write(&file_path, &data).unwrap();
+
+        let write_through_options = DirectIoOptions {
+            write_through: true,
+            ..DirectIoOptions::default()
+        };
+
+        let direct_file =
+            DirectIoFile::open_with_options(&file_path, write_through_options).unwrap();
+        let buffered_file = DirectIoFile::open_with_options(
+            &file_path,
+            DirectIoOptions {
+                buffered_io: true,
+                ..write_through_options
+            },
+        )
+        .unwrap();
+        assert!(!buffered_file.is_unbuffered());
+
+        #[cfg(target_os = "linux")]
+        {
+            use std::os::unix::io::AsRawFd;
+
+            let is_dsync = |file: &DirectIoFile| {
+                // SAFETY: File descriptor is valid while file is alive
+                let flags = unsafe { libc::fcntl(file.file.as_raw_fd(), libc::F_GETFL) };
+                assert_ne!(flags, -1);
+                flags & libc::O_DSYNC != 0
+            };
+
+            assert!(is_dsync(&direct_file));
+            assert!(is_dsync(&buffered_file));
+            assert!(!is_dsync(&DirectIoFile::open(&file_path).unwrap()));
+        }
+
+        // Reads and writes, including read-modify-write, work the same way
+        for file in [&direct_file, &buffered_file] {
+            let mut buffer = Vec::new();
+            for (offset, size) in [(0_usize, DISK_SECTOR_SIZE), (5, 100), (4000, 5000)] {
+                thread_rng().fill(&mut data[offset..][..size]);
+                file.write_all_at(&data[offset..][..size], offset as u64)
+                    .unwrap();
+
+                buffer.resize(size, 0);
+                file.read_exact_at(&mut buffer, offset as u64).unwrap();
+                assert_eq!(buffer, data[offset..][..size]);
+            }
+        }
+        assert_eq!(data, fs::read(&file_path).unwrap());
+    }
+
+    #[test]
+    fn metrics() {
+        let tempdir = tempdir().unwrap();
+        let file_path = tempdir.as_ref().join("file.bin");
+        fs::write(&file_path, vec![0u8; DISK_SECTOR_SIZE * 4]).unwrap();
+
+        let mut registry = Registry::default();
+        let disk_metrics = DiskMetrics::new(&mut registry);
+        let farm_id = FarmId::new();
+        let file_metrics = disk_metrics.file_metrics(&farm_id, DiskFileKind::Plot);
+        let file = DirectIoFile::open_with_metrics(
+            &file_path,
+            DirectIoOptions::default(),
+            Some(file_metrics.clone()),
+        )
+        .unwrap();
+
+        let mut memory = vec![0u8; DISK_SECTOR_SIZE * 4];
+        let (aligned, _) = aligned_and_unaligned(&mut memory, DISK_SECTOR_SIZE);
+        file.read_exact_at(aligned, 0).unwrap();
+        assert_eq!(file_metrics.read_bytes.get(), DISK_SECTOR_SIZE as u64);
+        assert_eq!(file_metrics.written_bytes.get(), 0);
+
+        file.write_all_at(aligned, DISK_SECTOR_SIZE as u64).unwrap();
+        assert_eq!(file_metrics.read_bytes.get(), DISK_SECTOR_SIZE as u64);
+        assert_eq!(file_metrics.written_bytes.get(), DISK_SECTOR_SIZE as u64);
+
+        assert_eq!(file_metrics.aligned_writes.get(), 1);
+        assert_eq!(file_metrics.read_modify_writes.get(), 0);
+
+        // Partial sector write is read-modify-write of the whole physical sector
+        file.write_all_at(&[1; 10], 5).unwrap();
+        let physical_sector_size = file.physical_sector_size() as u64;
+        assert_eq!(file_metrics.aligned_writes.get(), 1);
+        assert_eq!(file_metrics.read_modify_writes.get(), 1);
+        assert_eq!(
+            file_metrics.read_modify_write_bytes.get(),
+            physical_sector_size - 10
+        );
+        assert_eq!(
+            file_metrics.read_bytes.get(),
+            DISK_SECTOR_SIZE as u64 + physical_sector_size
+        );
+        assert_eq!(
+            file_metrics.written_bytes.get(),
+            DISK_SECTOR_SIZE as u64 + physical_sector_size
+        );
+
+        // Metrics are per farm and per file kind
+        assert_eq!(
+            disk_metrics
+                .file_metrics(&farm_id, DiskFileKind::Plot)
+                .read_bytes
+                .get(),
+            file_metrics.read_bytes.get()
+        );
+        assert_eq!(
+            disk_metrics
+                .file_metrics(&farm_id, DiskFileKind::Cache)
+                .read_bytes
+                .get(),
+            0
+        );
+        assert_eq!(
+            disk_metrics
+                .file_metrics(&FarmId::new(), DiskFileKind::Plot)
+                .read_bytes
+                .get(),
+            0
+        );
+    }
+
+    #[test]
+    fn io_stats() {
+        let tempdir = tempdir().unwrap();
+        let file_path = tempdir.as_ref().join("file.bin");
+        fs::write(&file_path, vec![0u8; DISK_SECTOR_SIZE * 8]).unwrap();
+
+        let file = DirectIoFile::open(&file_path).unwrap();
+        let physical_sector_size = file.physical_sector_size();
+        assert_eq!(file.io_stats(), IoStats::default());
+        assert_eq!(file.io_stats().read_modify_write_ratio(), 0.0);
+
+        // Whole physical sectors, both from aligned and unaligned memory
+        let mut memory = vec![0u8; (physical_sector_size + DISK_SECTOR_SIZE) * 2 + 2];
+        let (aligned, unaligned) = aligned_and_unaligned(&mut memory, physical_sector_size);
+        file.write_all_at(aligned, 0).unwrap();
+        file.write_all_at(unaligned, physical_sector_size as u64)
+            .unwrap();
+        assert_eq!(
+            file.io_stats(),
+            IoStats {
+                aligned_writes: 2,
+                read_modify_writes: 0,
+                amplified_bytes: 0,
+            }
+        );
+
+        // Unaligned head and tail need read-modify-write, whole sectors in between don't
+        file.write_all_at(&vec![1; physical_sector_size * 2], 10)
+            .unwrap();
+        assert_eq!(
+            file.io_stats(),
+            IoStats {
+                aligned_writes: 3,
+                read_modify_writes: 2,
+                amplified_bytes: physical_sector_size as u64,
+            }
+        );
+
+        // Small write within a sector
+        file.write_all_at(&[1; 100], 5).unwrap();
+        let io_stats = file.io_stats();
+        assert_eq!(io_stats.aligned_writes, 3);
+        assert_eq!(io_stats.read_modify_writes, 3);
+        assert_eq!(
+            io_stats.amplified_bytes,
+            physical_sector_size as u64 * 2 - 100
+        );
+        assert_eq!(io_stats.read_modify_write_ratio(), 0.5);
+        assert_eq!(
+            io_stats + io_stats,
+            IoStats {
+                aligned_writes: 6,
+                read_modify_writes: 6,
+                amplified_bytes: io_stats.amplified_bytes * 2,
+            }
+        );
+
+        // Reads don't affect write stats
+        file.read_exact_at(&mut [0; 10], 5).unwrap();
+        assert_eq!(file.io_stats(), io_stats);
+    }
+
+    #[test]
+    fn read_only() {
+        let tempdir = tempdir().unwrap();
+        let file_path = tempdir.as_ref().join("file.bin");
+        let read_only_options = DirectIoOptions {
+            read_only: true,
+            ..DirectIoOptions::default()
+        };
+
+        // Missing file is not created
+        assert_eq!(
+            DirectIoFile::open_with_options(&file_path, read_only_options)
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::NotFound
+        );
+        assert!(!file_path.exists());
+
+        let mut data = vec![0u8; DISK_SECTOR_SIZE * 4];
+        thread_rng().fill(data.as_mut_slice());
+        fs::write(&file_path, &data).unwrap();
+
+        let file = DirectIoFile::open_with_options(&file_path, read_only_options).unwrap();
+
+        let mut buffer = vec![0u8; 100];
+        file.read_exact_at(&mut buffer, 5).unwrap();
+        assert_eq!(&data[5..][..100], buffer.as_slice());
+
+        for (offset, size) in [(0_usize, DISK_SECTOR_SIZE), (5, 100), (0, 0)] {
+            assert_eq!(
+                file.write_all_at(&data[..size], offset as u64)
+                    .unwrap_err()
+                    .kind(),
+                io::ErrorKind::PermissionDenied
+            );
+        }
+        assert_eq!(data, fs::read(&file_path).unwrap());
+    }
+
+    #[test]
+    fn open_mode() {
+        let tempdir = tempdir().unwrap();
+        let file_path = tempdir.as_ref().join("file.bin");
+        let options = |open_mode| DirectIoOptions {
+            open_mode,
+            ..DirectIoOptions::default()
+        };
+
+        // Missing file is not created
+        assert_eq!(
+            DirectIoFile::open_with_options(&file_path, options(OpenMode::OpenExisting))
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::NotFound
+        );
+        assert!(!file_path.exists());
+
+        let file =
+            DirectIoFile::open_with_options(&file_path, options(OpenMode::CreateNew)).unwrap();
+        file.write_all_at(&[1; 100], 0).unwrap();
+        drop(file);
+
+        // Existing file is neither adopted nor modified
+        for buffered_io in [false, true] {
+            assert_eq!(
+                DirectIoFile::open_with_options(
+                    &file_path,
+                    DirectIoOptions {
+                        buffered_io,
+                        ..options(OpenMode::CreateNew)
+                    }
+                )
+                .unwrap_err()
+                .kind(),
+                io::ErrorKind::AlreadyExists
+            );
+        }
+        assert_eq!(fs::read(&file_path).unwrap(), [1; 100]);
+
+        let file =
+            DirectIoFile::open_with_options(&file_path, options(OpenMode::OpenExisting)).unwrap();
+        assert_eq!(file.size().unwrap(), 100);
+        drop(file);
+
+        // Buffered I/O fallback after failed direct I/O probe can create file after direct I/O
+        // attempt already did
+        fs::remove_file(&file_path).unwrap();
+        let (_file, _sector_sizes, io_mode) = DirectIoFile::open_file(
+            OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .create_new(true)
+                .truncate(false),
+            &file_path,
+            DEFAULT_MAX_REQUEST_SIZE,
+            false,
+            |_file, _physical_sector_size| Err(io::Error::other("Direct I/O probe failed")),
+        )
+        .unwrap();
+        assert_eq!(io_mode, IoMode::Buffered);
+        assert!(file_path.exists());
+    }
+
+    #[test]
+    fn unaligned_set_len() {
+        let tempdir = tempdir().unwrap();
+        let file_path = tempdir.as_ref().join("file.bin");
+        let mut data = vec![0u8; DISK_SECTOR_SIZE * 3];
+        thread_rng().fill(data.as_mut_slice());
+        fs::write(&file_path, &data).unwrap();
+
+        let file = DirectIoFile::open(&file_path).unwrap();
+        let mut buffer = Vec::new();
+
+        // Grow, shrink and set final unaligned size, the tail must be readable after every change
+        for size in [
+            DISK_SECTOR_SIZE * 4 + 100,
+            DISK_SECTOR_SIZE * 2 + 123,
+            DISK_SECTOR_SIZE * 2,
+            DISK_SECTOR_SIZE * 3 - 1,
+        ] {
+            file.set_len(size as u64).unwrap();
+            data.resize(size, 0);
+            assert_eq!(file.size().unwrap(), size as u64);
+            assert_eq!(fs::metadata(&file_path).unwrap().len(), size as u64);
+
+            for tail_size in [1, 50, 200, DISK_SECTOR_SIZE + 7] {
+                buffer.resize(tail_size, 0);
+                file.read_exact_at(&mut buffer, (size - tail_size) as u64)
+                    .unwrap();
+                assert_eq!(buffer, data[size - tail_size..], "size {size}");
+            }
+
+            // Reads beyond the end still fail
+            assert!(file.read_exact_at(&mut [0; 2], size as u64 - 1).is_err());
+        }
+
+        // Preallocation handles unaligned size the same way, but never shrinks the file
+        file.preallocate(DISK_SECTOR_SIZE as u64 * 5 + 3).unwrap();
+        data.resize(DISK_SECTOR_SIZE * 5 + 3, 0);
+        assert_eq!(file.size().unwrap(), data.len() as u64);
+        file.preallocate(DISK_SECTOR_SIZE as u64).unwrap();
+        assert_eq!(file.size().unwrap(), data.len() as u64);
+        buffer.resize(10, 0);
+        file.read_exact_at(&mut buffer, data.len() as u64 - 10)
+            .unwrap();
+        assert_eq!(buffer, data[data.len() - 10..]);
+        assert_eq!(data, fs::read(&file_path).unwrap());
+
+        // Read-only file can't be resized
+        let read_only_file = DirectIoFile::open_with_options(
+            &file_path,
+            DirectIoOptions {
+                read_only: true,
+                ..DirectIoOptions::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            read_only_file.set_len(100).unwrap_err().kind(),
+            io::ErrorKind::PermissionDenied
+        );
+        assert_eq!(file.size().unwrap(), data.len() as u64);
+    }
+
+    #[test]
+    fn error_context() {
+        let tempdir = tempdir().unwrap();
+        let file_path = tempdir.as_ref().join("file.bin");
+        fs::write(&file_path, vec![0u8; DISK_SECTOR_SIZE * 2]).unwrap();
+
+        let file = DirectIoFile::open(&file_path).unwrap();
+
+        // Read past the end of the file
+        let offset = DISK_SECTOR_SIZE as u64 * 2 - 5;
+        let error = file.read_exact_at(&mut [0u8; 100], offset).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+        let error_string = error.to_string();
+        assert!(
+            error_string.contains(&format!("offset {offset}")),
+            "{error}"
+        );
+        assert!(
+            error_string.contains(&file_path.display().to_string()),
+            "{error}"
+        );
+
+        let direct_io_error = error
+            .get_ref()
+            .and_then(|error| error.downcast_ref::<DirectIoError>())
+            .unwrap();
+        assert_eq!(direct_io_error.operation, "read");
+        assert_eq!(direct_io_error.path, file_path);
+        assert_eq!(direct_io_error.offset, offset);
+        assert_eq!(direct_io_error.len, 100);
+        assert_eq!(
+            direct_io_error.logical_sector_size,
+            file.logical_sector_size()
+        );
+        assert_eq!(
+            direct_io_error.physical_sector_size,
+            file.physical_sector_size()
+        );
+    }
+
+    #[test]
+    fn read_regions() {
+        let tempdir = tempdir().unwrap();
+        let file_path = tempdir.as_ref().join("file.bin");
+        let file_size = DEFAULT_MAX_REQUEST_SIZE * 4;
+        let mut data = vec![0u8; file_size];
+        thread_rng().fill(data.as_mut_slice());
+        fs::write(&file_path, &data).unwrap();
+
+        let base_offset = DISK_SECTOR_SIZE as u64 + 7;
+        let max_region_offset = (file_size as u64 - base_offset) as u32;
+        let mut random_regions = (0..1000)
+            .map(|_| {
+                let offset = thread_rng().gen_range(0..max_region_offset - 100);
+                (offset, thread_rng().gen_range(0..100))
+            })
+            .collect::<Vec<_>>();
+        // Dense regions within a single sector
+        let dense_regions = (0..100).map(|index| (index * 40, 32)).collect::<Vec<_>>();
+        random_regions.extend_from_slice(&dense_regions);
+
+        // Covering reads disabled and always used
+        for covering_read_threshold in [0, usize::MAX] {
+            let file = DirectIoFile::open_with_options(
+                &file_path,
+                DirectIoOptions {
+                    covering_read_threshold,
+                    ..DirectIoOptions::default()
+                },
+            )
+            .unwrap();
+
+            for regions in [
+                vec![],
+                // Empty regions
+                vec![(0, 0), (5, 0)],
+                // Duplicated and overlapping
+                vec![(10, 100), (10, 100), (50, 200), (0, 4096), (4000, 500)],
+                // Adjacent and crossing sector boundaries
+                vec![(4090, 6), (4096, 10), (4106, 4096), (8202, 1)],
+                // Out of order and far apart
+                vec![
+                    (DEFAULT_MAX_REQUEST_SIZE as u32 * 3, 100),
+                    (7, 13),
+                    (max_region_offset - 1, 1),
+                    (DEFAULT_MAX_REQUEST_SIZE as u32 - 50, 100),
+                ],
+                // Larger than max request size
+                vec![(5, DEFAULT_MAX_REQUEST_SIZE as u32 * 2), (0, 10)],
+                dense_regions.clone(),
+                random_regions.clone(),
+            ] {
+                let total_len = regions.iter().map(|&(_offset, len)| len as usize).sum();
+                let mut out = vec![0u8; total_len];
+                let mut expected = vec![0u8; total_len];
+                file.read_regions(base_offset, &regions, &mut out).unwrap();
+                data.read_regions(base_offset, &regions, &mut expected)
+                    .unwrap();
+
+                assert_eq!(
+                    out, expected,
+                    "Regions {regions:?}, covering read threshold {covering_read_threshold}"
+                );
+            }
+
+            // Output buffer size must match regions
+            assert_eq!(
+                file.read_regions(0, &[(0, 10), (20, 10)], &mut [0u8; 10])
+                    .unwrap_err()
+                    .kind(),
+                io::ErrorKind::InvalidInput
+            );
+            // Reads past the end of the file fail
+            assert!(file
+                .read_regions(0, &[(0, 10), (file_size as u32 - 5, 10)], &mut [0u8; 20])
+                .is_err());
+        }
+    }
+
+    #[test]
+    fn vectored() {
+        let tempdir = tempdir().unwrap();
+        let file_path = tempdir.as_ref().join("file.bin");
+        let file_size = DEFAULT_MAX_REQUEST_SIZE * 4;
+        let mut data = vec![0u8; file_size];
+        thread_rng().fill(data.as_mut_slice());
+        fs::write(&file_path, &data).unwrap();
+
+        let mut file = DirectIoFile::open(&file_path).unwrap();
+        let std_file = OpenOptions::new().read(true).open(&file_path).unwrap();
+
+        for override_sector_sizes in sector_sizes_overrides(&file) {
+            if let Some(sector_sizes) = override_sector_sizes {
+                file.sector_sizes = sector_sizes;
+            }
+
+            for ranges in [
+                // Overlapping
+                vec![(10_usize, 100_usize), (50, 200), (0, 4096), (4000, 500)],
+                // Adjacent
+                vec![(100, 50), (150, 50), (200, 4096), (4296, 10)],
+                // Far apart and out of order
+                vec![
+                    (DEFAULT_MAX_REQUEST_SIZE * 3 + 5, 100),
+                    (7, 13),
+                    (DEFAULT_MAX_REQUEST_SIZE + 17, 4096),
+                    (DEFAULT_MAX_REQUEST_SIZE * 2 - 50, 100),
+                ],
+                // Large, spanning multiple windows
+                vec![
+                    (5, DEFAULT_MAX_REQUEST_SIZE * 2),
+                    (DEFAULT_MAX_REQUEST_SIZE, 10),
+                    (0, 0),
+                ],
+            ] {
+                let mut buffers = ranges
+                    .iter()
+                    .map(|&(_offset, size)| vec![0u8; size])
+                    .collect::<Vec<_>>();
+                let mut std_buffers = buffers.clone();
+
+                let mut requests = ranges
+                    .iter()
+                    .zip(&mut buffers)
+                    .map(|(&(offset, _size), buffer)| (offset as u64, buffer.as_mut_slice()))
+                    .collect::<Vec<_>>();
+                file.read_exact_at_vectored(&mut requests).unwrap();
+
+                let mut std_requests = ranges
+                    .iter()
+                    .zip(&mut std_buffers)
+                    .map(|(&(offset, _size), buffer)| (offset as u64, buffer.as_mut_slice()))
+                    .collect::<Vec<_>>();
+                std_file.read_exact_at_vectored(&mut std_requests).unwrap();
+
+                for (&(offset, size), (buffer, std_buffer)) in
+                    ranges.iter().zip(buffers.iter().zip(&std_buffers))
+                {
+                    assert_eq!(
+                        &data[offset..][..size],
+                        buffer.as_slice(),
+                        "Offset {offset}, size {size}, override sector sizes \
+                        {override_sector_sizes:?}"
+                    );
+                    assert_eq!(
+                        buffer, std_buffer,
+                        "Offset {offset}, size {size}, override sector sizes \
+                        {override_sector_sizes:?}"
+                    );
+                }
+            }
+        }
+
+        // Error identifies offset of the failed request
+        let mut valid = [0u8; 10];
+        let mut invalid = [0u8; 10];
+        let invalid_offset = file_size as u64 - 5;
+        let error = file
+            .read_exact_at_vectored(&mut [(0, &mut valid[..]), (invalid_offset, &mut invalid[..])])
+            .unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains(&format!("offset {invalid_offset}")),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn concurrent() {
+        const THREADS: usize = 8;
+        const ITERATIONS: usize = 50;
+        /// Size of the stripes written by each thread, sectors are shared between threads
+        const STRIPE_SIZE: usize = 100;
+
+        let tempdir = tempdir().unwrap();
+        let file_path = tempdir.as_ref().join("file.bin");
+        let oracle_file_path = tempdir.as_ref().join("oracle.bin");
+        let file_size = DEFAULT_MAX_REQUEST_SIZE * 3;
+        let mut data = vec![0u8; file_size];
+        thread_rng().fill(data.as_mut_slice());
+        fs::write(&file_path, &data).unwrap();
+        fs::write(&oracle_file_path, &data).unwrap();
+
+        let file = DirectIoFile::open_with_options(
+            &file_path,
+            DirectIoOptions {
+                scratch_buffers: Some(THREADS / 2),
+                ..DirectIoOptions::default()
+            },
+        )
+        .unwrap();
+        let oracle_file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&oracle_file_path)
+            .unwrap();
+
+        // Concurrent reads at overlapping offsets
+        thread::scope(|scope| {
+            for thread_index in 0..THREADS {
+                let file = &file;
+                let data = &data;
+                scope.spawn(move || {
+                    let mut rng = StdRng::seed_from_u64(thread_index as u64);
+                    let mut buffer = Vec::new();
+                    for _ in 0..ITERATIONS {
+                        let offset = rng.gen_range(0..file_size);
+                        let size = rng
+                            .gen_range(1..=(file_size - offset).min(DEFAULT_MAX_REQUEST_SIZE * 2));
+                        buffer.resize(size, 0);
+                        file.read_exact_at(&mut buffer, offset as u64).unwrap();
+                        assert_eq!(
+                            &data[offset..][..size],
+                            buffer.as_slice(),
+                            "Thread {thread_index}, offset {offset}, size {size}"
+                        );
+                    }
+                });
+            }
+        });
+
+        // Concurrent writes into interleaved stripes that share sectors, mirrored into the buffered
+        // oracle file, along with concurrent reads
+        thread::scope(|scope| {
+            for thread_index in 0..THREADS {
+                let file = &file;
+                let oracle_file = &oracle_file;
+                scope.spawn(move || {
+                    let mut rng = StdRng::seed_from_u64(thread_index as u64);
+                    let mut stripe = [0u8; STRIPE_SIZE];
+                    let mut buffer = vec![0u8; STRIPE_SIZE];
+                    for _ in 0..ITERATIONS {
+                        let stripe_index = rng.gen_range(0..file_size / STRIPE_SIZE / THREADS)
+                            * THREADS
+                            + thread_index;
+                        let offset = (stripe_index * STRIPE_SIZE) as u64;
+                        rng.fill(stripe.as_mut_slice());
+
+                        file.write_all_at(&stripe, offset).unwrap();
+                        oracle_file.write_all_at(&stripe, offset).unwrap();
+
+                        file.read_exact_at(&mut buffer, offset).unwrap();
+                        assert_eq!(
+                            stripe.as_slice(),
+                            buffer.as_slice(),
+                            "Thread {thread_index}, offset {offset}"
+                        );
+
+                        let offset = rng.gen_range(0..file_size - DEFAULT_MAX_REQUEST_SIZE);
+                        let mut read_buffer = vec![0u8; DEFAULT_MAX_REQUEST_SIZE];
+                        file.read_exact_at(&mut read_buffer, offset as u64).unwrap();
+                    }
+                });
+            }
+        });
+
+        let mut contents = vec![0u8; file_size];
+        file.read_exact_at(&mut contents, 0).unwrap();
+        assert_eq!(contents, fs::read(&oracle_file_path).unwrap());
+    }
+
+    #[test]
+    fn shared_handle() {
+        const READERS: usize = 4;
+        const ITERATIONS: usize = 50;
+
+        let tempdir = tempdir().unwrap();
+        let file_path = tempdir.as_ref().join("file.bin");
+        let file_size = DEFAULT_MAX_REQUEST_SIZE * 2;
+        let mut data = vec![0u8; file_size];
+        thread_rng().fill(data.as_mut_slice());
+        fs::write(&file_path, &data).unwrap();
+
+        // Owned handles, threads are not tied to the lifetime of the original handle
+        let file = Arc::new(DirectIoFile::open(&file_path).unwrap());
+        let data = Arc::new(data);
+
+        let readers = (0..READERS)
+            .map(|thread_index| {
+                let file = Arc::clone(&file);
+                let data = Arc::clone(&data);
+
+                thread::spawn(move || {
+                    let mut rng = StdRng::seed_from_u64(thread_index as u64);
+                    let mut buffer = Vec::new();
+                    for _ in 0..ITERATIONS {
+                        let offset = rng.gen_range(0..file_size);
+                        let size =
+                            rng.gen_range(1..=(file_size - offset).min(DISK_SECTOR_SIZE * 3));
+                        buffer.resize(size, 0);
+                        ReadAtSync::read_at(&file, &mut buffer, offset as u64).unwrap();
+                        assert_eq!(&data[offset..][..size], buffer.as_slice());
+
+                        let regions = [(0, 10), (DISK_SECTOR_SIZE as u32 + 1, 20)];
+                        let mut out = [0u8; 30];
+                        file.read_regions(offset as u64 / 2, &regions, &mut out)
+                            .unwrap();
+                        let base_offset = offset / 2;
+                        assert_eq!(&out[..10], &data[base_offset..][..10]);
+                        assert_eq!(
+                            &out[10..],
+                            &data[base_offset + DISK_SECTOR_SIZE + 1..][..20]
+                        );
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let size_measurer = thread::spawn({
+            let file = Arc::clone(&file);
+
+            move || {
+                for _ in 0..ITERATIONS {
+                    assert_eq!(FileExt::size(&file).unwrap(), file_size as u64);
+                }
+            }
+        });
+
+        drop(file);
+
+        for reader in readers {
+            reader.join().unwrap();
+        }
+        size_measurer.join().unwrap();
+    }
+
+    #[test]
+    fn io_request_limit() {
+        const MAX_IO_REQUESTS: usize = 2;
+        const THREADS_PER_FILE: usize = 4;
+
+        let tempdir = tempdir().unwrap();
+        let mut registry = Registry::default();
+        let disk_metrics = DiskMetrics::new(&mut registry);
+        let file_metrics = disk_metrics.file_metrics(&FarmId::new(), DiskFileKind::Plot);
+        let io_request_limiter = IoRequestLimiter::new(NonZeroUsize::new(MAX_IO_REQUESTS).unwrap());
+
+        // Both files share the same limit
+        let files = ["file1.bin", "file2.bin"].map(|file_name| {
+            let file_path = tempdir.as_ref().join(file_name);
+            fs::write(&file_path, vec![0u8; DISK_SECTOR_SIZE * THREADS_PER_FILE]).unwrap();
+
+            let file = DirectIoFile::open_with_limiter(
+                &file_path,
+                DirectIoOptions::default(),
+                Some(file_metrics.clone()),
+                Some(io_request_limiter.clone()),
+            )
+            .unwrap();
+            file.set_access_pattern(AccessPattern::Random).unwrap();
+            file
+        });
+
+        // Limit is reached by requests in flight elsewhere
+        let permits = (0..MAX_IO_REQUESTS)
+            .map(|_| io_request_limiter.acquire(IoPriority::Foreground, 0, None))
+            .collect::<Vec<_>>();
+        assert_eq!(io_request_limiter.in_flight(), MAX_IO_REQUESTS);
+
+        let done = AtomicBool::new(false);
+        let finished_workers = AtomicUsize::new(0);
+        let max_in_flight = thread::scope(|scope| {
+            let monitor = scope.spawn(|| {
+                let mut max_in_flight = 0;
+                while !done.load(Ordering::Acquire) {
+                    max_in_flight = max_in_flight.max(io_request_limiter.in_flight());
+                    thread::yield_now();
+                }
+                max_in_flight
+            });
+
+            let workers = files
+                .iter()
+                .flat_map(|file| {
+                    let finished_workers = &finished_workers;
+                    (0..THREADS_PER_FILE).map(move |thread_index| {
+                        scope.spawn(move || {
+                            let offset = (thread_index * DISK_SECTOR_SIZE) as u64;
+                            let mut buffer = vec![0u8; DISK_SECTOR_SIZE];
+                            file.read_exact_at(&mut buffer, offset).unwrap();
+                            file.write_all_at(&buffer, offset).unwrap();
+                            finished_workers.fetch_add(1, Ordering::Relaxed);
+                        })
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            // Requests of both files wait for the limit
+            thread::sleep(Duration::from_millis(100));
+            assert_eq!(finished_workers.load(Ordering::Relaxed), 0);
+            assert_eq!(io_request_limiter.in_flight(), MAX_IO_REQUESTS);
+
+            drop(permits);
+            for worker in workers {
+                worker.join().unwrap();
+            }
+            done.store(true, Ordering::Release);
+
+            monitor.join().unwrap()
+        });
+
+        // Requests proceed once requests in flight finish, but never exceed the limit
+        assert_eq!(
+            finished_workers.load(Ordering::Relaxed),
+            files.len() * THREADS_PER_FILE
+        );
+        assert!(max_in_flight <= MAX_IO_REQUESTS);
+        assert_eq!(io_request_limiter.in_flight(), 0);
+        assert_eq!(file_metrics.requests_in_flight.get(), 0);
+
+        // Limiter is created from options when not provided
+        let file = DirectIoFile::open_with_options(
+            &tempdir.as_ref().join("file3.bin"),
+            DirectIoOptions {
+                max_io_requests: NonZeroUsize::new(3),
+                ..DirectIoOptions::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(file.io_request_limiter().max_requests().get(), 3);
+    }
+
+    #[test]
+    fn background_io_rate_limit() {
+        const SECTORS_PER_SECOND: usize = 16;
+
+        let tempdir = tempdir().unwrap();
+        let file_path = tempdir.as_ref().join("file.bin");
+        fs::write(
+            &file_path,
+            vec![0u8; DISK_SECTOR_SIZE * SECTORS_PER_SECOND * 2],
+        )
+        .unwrap();
+        let mut registry = Registry::default();
+        let disk_metrics = DiskMetrics::new(&mut registry);
+        let file_metrics = disk_metrics.file_metrics(&FarmId::new(), DiskFileKind::Plot);
+        let io_request_limiter = IoRequestLimiter::new(NonZeroUsize::MIN)
+            .with_background_rate_limit(NonZeroU64::new(
+                (DISK_SECTOR_SIZE * SECTORS_PER_SECOND) as u64,
+            ));
+        assert_eq!(
+            io_request_limiter.background_rate_limit().unwrap().get(),
+            (DISK_SECTOR_SIZE * SECTORS_PER_SECOND) as u64
+        );
+
+        let file = DirectIoFile::open_with_limiter(
+            &file_path,
+            DirectIoOptions::default(),
+            Some(Arc::clone(&file_metrics)),
+            Some(io_request_limiter),
+        )
+        .unwrap();
+        file.set_access_pattern(AccessPattern::Random).unwrap();
+        let background_file = file.try_clone().unwrap();
+        background_file
+            .set_access_pattern(AccessPattern::Random)
+            .unwrap();
+        background_file.set_io_priority(IoPriority::Background);
+        // Priority is not shared with clones
+        assert_eq!(file.io_priority(), IoPriority::Foreground);
+        assert_eq!(background_file.io_priority(), IoPriority::Background);
+
+        let mut buffer = vec![0u8; DISK_SECTOR_SIZE];
+        let read_all = |file: &DirectIoFile, buffer: &mut [u8]| {
+            let start = Instant::now();
+            for sector_index in 0..SECTORS_PER_SECOND * 2 {
+                file.read_exact_at(buffer, (sector_index * DISK_SECTOR_SIZE) as u64)
+                    .unwrap();
+            }
+            start.elapsed()
+        };
+
+        // Foreground requests are not rate limited
+        assert!(read_all(&file, &mut buffer) < Duration::from_millis(500));
+        assert_eq!(file_metrics.background_bytes.get(), 0);
+
+        // Background requests burst up to one second worth of bytes, the rest is throttled
+        assert!(read_all(&background_file, &mut buffer) >= Duration::from_millis(900));
+        assert_eq!(
+            file_metrics.background_bytes.get(),
+            (DISK_SECTOR_SIZE * SECTORS_PER_SECOND * 2) as u64
+        );
+    }
+
+    #[test]
+    fn background_io_yields_to_foreground() {
+        const BACKGROUND_THREADS: usize = 4;
+        const REQUEST_DELAY: Duration = Duration::from_millis(50);
+
+        let tempdir = tempdir().unwrap();
+        let file_path = tempdir.as_ref().join("file.bin");
+        fs::write(&file_path, vec![0u8; DISK_SECTOR_SIZE * BACKGROUND_THREADS]).unwrap();
+        // Single request at a time, such that every request waits for the previous one
+        let io_request_limiter = IoRequestLimiter::new(NonZeroUsize::MIN);
+        let file = DirectIoFile::open_with_limiter(
+            &file_path,
+            DirectIoOptions::default(),
+            None,
+            Some(io_request_limiter.clone()),
+        )
+        .unwrap();
+        file.set_access_pattern(AccessPattern::Random).unwrap();
+
+        let done = AtomicBool::new(false);
+        let background_reads = AtomicUsize::new(0);
+        let max_foreground_latency = thread::scope(|scope| {
+            let background_threads = (0..BACKGROUND_THREADS)
+                .map(|thread_index| {
+                    let background_file = file.try_clone().unwrap();
+                    background_file
+                        .set_access_pattern(AccessPattern::Random)
+                        .unwrap();
+                    background_file.set_io_priority(IoPriority::Background);
+                    let io_request_limiter = &io_request_limiter;
+                    let done = &done;
+                    let background_reads = &background_reads;
+
+                    scope.spawn(move || {
+                        let mut buffer = vec![0u8; DISK_SECTOR_SIZE];
+                        while !done.load(Ordering::Acquire) {
+                            // Slow background requests keep the disk busy
+                            {
+                                let _permit = io_request_limiter.acquire(
+                                    IoPriority::Background,
+                                    DISK_SECTOR_SIZE,
+                                    None,
+                                );
+                                thread::sleep(REQUEST_DELAY);
+                            }
+                            background_file
+                                .read_exact_at(
+                                    &mut buffer,
+                                    (thread_index * DISK_SECTOR_SIZE) as u64,
+                                )
+                                .unwrap();
+                            background_reads.fetch_add(1, Ordering::Relaxed);
+                        }
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            // Let background requests queue up
+            thread::sleep(REQUEST_DELAY * 2);
+
+            let mut buffer = vec![0u8; DISK_SECTOR_SIZE];
+            let mut max_foreground_latency = Duration::ZERO;
+            for _ in 0..10 {
+                let start = Instant::now();
+                file.read_exact_at(&mut buffer, 0).unwrap();
+                max_foreground_latency = max_foreground_latency.max(start.elapsed());
+            }
+            done.store(true, Ordering::Release);
+
+            for background_thread in background_threads {
+                background_thread.join().unwrap();
+            }
+
+            max_foreground_latency
+        });
+
+        // Foreground request waits for at most one background request in flight rather than for
+        // all queued background requests
+        assert!(
+            max_foreground_latency < REQUEST_DELAY * 3,
+            "{max_foreground_latency:?}"
+        );
+        assert!(background_reads.load(Ordering::Relaxed) > 0);
+        assert_eq!(file.io_request_limiter().in_flight(), 0);
+    }
+
+    #[test]
+    fn close() {
+        let tempdir = tempdir().unwrap();
+        let file_path = tempdir.as_ref().join("file.bin");
+        fs::write(&file_path, vec![0u8; DISK_SECTOR_SIZE * 4]).unwrap();
+
+        let io_request_limiter = IoRequestLimiter::new(NonZeroUsize::MIN);
+        let file = DirectIoFile::open_with_limiter(
+            &file_path,
+            DirectIoOptions::default(),
+            None,
+            Some(io_request_limiter.clone()),
+        )
+        .unwrap();
+        file.try_lock_exclusive().unwrap();
+        let clone = file.try_clone().unwrap();
+        let data = vec![1u8; DISK_SECTOR_SIZE * 2];
+
+        // Disk is busy with another request, such that write stays in progress
+        let permit = io_request_limiter.acquire(IoPriority::Foreground, 0, None);
+        thread::scope(|scope| {
+            // Shutdown races with a write in progress
+            let write = scope.spawn(|| clone.write_all_at(&data, 0));
+            while file.lifecycle.in_progress.load(Ordering::SeqCst) == 0 {
+                thread::yield_now();
+            }
+
+            // Write is still in progress
+            let error = file.close(Duration::from_millis(1)).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+            assert!(file.is_closed());
+            assert!(clone.is_closed());
+
+            // New requests are rejected through all instances, while request in progress completes
+            assert!(file
+                .write_all_at(&data, DISK_SECTOR_SIZE as u64 * 2)
+                .is_err());
+            assert!(clone.read_exact_at(&mut [0; 1], 0).is_err());
+            assert!(clone.sync_data().is_err());
+            drop(permit);
+            file.close(Duration::from_secs(10)).unwrap();
+            write.join().unwrap().unwrap();
+        });
+
+        // Write in progress is complete, rejected write didn't happen and lock is released even
+        // though file is not dropped yet
+        let other_file = DirectIoFile::open(&file_path).unwrap();
+        other_file.try_lock_exclusive().unwrap();
+        let mut contents = vec![0u8; DISK_SECTOR_SIZE * 4];
+        other_file.read_exact_at(&mut contents, 0).unwrap();
+        assert_eq!(contents[..data.len()], data);
+        assert!(contents[data.len()..].iter().all(|&byte| byte == 0));
+
+        // Closing again is fine
+        file.close(Duration::ZERO).unwrap();
+    }
+
+    #[test]
+    fn punch_hole() {
+        let tempdir = tempdir().unwrap();
+        let file_path = tempdir.as_ref().join("file.bin");
+        let mut data = vec![0u8; DISK_SECTOR_SIZE * 4];
+        thread_rng().fill(data.as_mut_slice());
+        fs::write(&file_path, &data).unwrap();
+
+        let file = DirectIoFile::open(&file_path).unwrap();
+        // Window covering punched range is read ahead and must not be served afterwards
+        file.set_access_pattern(AccessPattern::Sequential).unwrap();
+        let mut buffer = vec![0u8; DISK_SECTOR_SIZE];
+        file.read_exact_at(&mut buffer, 0).unwrap();
+
+        // Unaligned range is rounded inward to physical sector size
+        let physical_sector_size = file.physical_sector_size();
+        let start = physical_sector_size / 2;
+        let end = data.len() - 100;
+        file.punch_hole(start as u64, (end - start) as u64).unwrap();
+        let punched_range = if file.is_unbuffered() {
+            start.next_multiple_of(physical_sector_size)
+                ..end / physical_sector_size * physical_sector_size
+        } else {
+            start..end
+        };
+        data[punched_range].fill(0);
+
+        assert_eq!(file.size().unwrap(), data.len() as u64);
+        let mut contents = vec![0u8; data.len()];
+        file.read_exact_at(&mut contents, 0).unwrap();
+        assert_eq!(contents, data);
+        assert_eq!(fs::read(&file_path).unwrap(), data);
+
+        #[cfg(target_os = "linux")]
+        {
+            use std::os::unix::fs::MetadataExt;
+
+            // Punched sectors are no longer allocated
+            let allocated = fs::metadata(&file_path).unwrap().blocks() * 512;
+            assert!(allocated < (DISK_SECTOR_SIZE * 4) as u64, "{allocated}");
+        }
+
+        // Empty and too small ranges are ignored
+        file.punch_hole(10, 0).unwrap();
+        file.punch_hole(data.len() as u64 - 100, 50).unwrap();
+        assert_eq!(fs::read(&file_path).unwrap(), data);
+
+        // Read-only file can't be modified
+        let read_only_file = DirectIoFile::open_with_options(
+            &file_path,
+            DirectIoOptions {
+                read_only: true,
+                ..DirectIoOptions::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            read_only_file
+                .punch_hole(0, data.len() as u64)
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::PermissionDenied
+        );
+    }
+
+    #[test]
+    fn bounded_scratch_buffer() {
+        const MAX_REQUEST_SIZE: usize = MIN_MAX_REQUEST_SIZE;
+
+        let tempdir = tempdir().unwrap();
+        let file_path = tempdir.as_ref().join("file.bin");
+        let mut data = vec![0u8; MAX_REQUEST_SIZE * 3];
+        thread_rng().fill(data.as_mut_slice());
+        fs::write(&file_path, &data).unwrap();
+
+        let file = DirectIoFile::open_with_options(
+            &file_path,
+            DirectIoOptions {
+                max_request_size: Some(MAX_REQUEST_SIZE),
+                scratch_buffers: Some(1),
+                ..DirectIoOptions::default()
+            },
+        )
+        .unwrap();
+        file.set_access_pattern(AccessPattern::Random).unwrap();
+        let max_buffer_size = MAX_REQUEST_SIZE / DISK_SECTOR_SIZE;
+        let assert_bounded = || {
+            for scratch_buffer in file.scratch_buffers.lock().iter() {
+                assert!(scratch_buffer.capacity() <= max_buffer_size);
+            }
+        };
+
+        // Doesn't fit into a single request of max size once padding is added
+        let len = MAX_REQUEST_SIZE + file.physical_sector_size() - 1;
+        for offset in [1, 100, file.logical_sector_size() - 1, DISK_SECTOR_SIZE + 7] {
+            let mut buffer = vec![0u8; len];
+            file.read_exact_at(&mut buffer, offset as u64).unwrap();
+            assert_eq!(buffer, data[offset..][..len], "offset {offset}");
+            assert_bounded();
+
+            thread_rng().fill(buffer.as_mut_slice());
+            file.write_all_at(&buffer, offset as u64).unwrap();
+            data[offset..][..len].copy_from_slice(&buffer);
+            assert_eq!(fs::read(&file_path).unwrap(), data, "offset {offset}");
+            assert_bounded();
+        }
+
+        // Buffer that has grown is shrunk back when returned into the pool
+        file.take_scratch_buffer()
+            .resize(max_buffer_size * 2, AlignedSectorSize::default());
+        assert_bounded();
+        assert_eq!(file.scratch_buffers.lock()[0].len(), max_buffer_size);
+    }
+
+    #[test]
+    fn memory_budget() {
+        const FILES: usize = 8;
+        const THREADS_PER_FILE: usize = 2;
+        const ITERATIONS: usize = 50;
+        const MAX_REQUEST_SIZE: usize = MIN_MAX_REQUEST_SIZE;
+
+        let tempdir = tempdir().unwrap();
+        let file_size = MAX_REQUEST_SIZE * 3;
+        // Fits fewer buffers than there are files, most requests use shared buffer
+        let memory_budget = IoMemoryBudget::new(MAX_REQUEST_SIZE * 2);
+        let mut registry = Registry::default();
+        memory_budget.register_metrics(&mut registry);
+        let io_request_limiter = IoRequestLimiter::new(NonZeroUsize::new(4).unwrap())
+            .with_memory_budget(Some(memory_budget.clone()));
+
+        let files = (0..FILES)
+            .map(|file_index| {
+                let file_path = tempdir.as_ref().join(format!("file{file_index}.bin"));
+                let mut data = vec![0u8; file_size];
+                thread_rng().fill(data.as_mut_slice());
+                fs::write(&file_path, &data).unwrap();
+
+                let file = DirectIoFile::open_with_limiter(
+                    &file_path,
+                    DirectIoOptions {
+                        max_request_size: Some(MAX_REQUEST_SIZE),
+                        scratch_buffers: Some(THREADS_PER_FILE),
+                        ..DirectIoOptions::default()
+                    },
+                    None,
+                    Some(io_request_limiter.clone()),
+                )
+                .unwrap();
+                file.set_access_pattern(AccessPattern::Random).unwrap();
+
+                (file, RwLock::new(data))
+            })
+            .collect::<Vec<_>>();
+        assert!(memory_budget.reserved() <= memory_budget.limit());
+
+        // Concurrent unaligned reads (including vectored reads with nested requests) and writes
+        thread::scope(|scope| {
+            for (file_index, (file, data)) in files.iter().enumerate() {
+                for thread_index in 0..THREADS_PER_FILE {
+                    let memory_budget = &memory_budget;
+                    scope.spawn(move || {
+                        let mut rng = StdRng::seed_from_u64(
+                            (file_index * THREADS_PER_FILE + thread_index) as u64,
+                        );
+                        for _ in 0..ITERATIONS {
+                            let offset = rng.gen_range(1..file_size - MAX_REQUEST_SIZE);
+                            let size = rng.gen_range(1..=MAX_REQUEST_SIZE);
+
+                            if rng.gen_bool(0.3) {
+                                // Writes are exclusive with reads of this file, such that data
+                                // read concurrently is predictable
+                                let mut data = data.write();
+                                let mut buffer = vec![0u8; size];
+                                rng.fill(buffer.as_mut_slice());
+                                file.write_all_at(&buffer, offset as u64).unwrap();
+                                data[offset..][..size].copy_from_slice(&buffer);
+                            } else {
+                                let data = data.read();
+                                let mut buffer = vec![0u8; size];
+                                let mut other_buffer = vec![0u8; 100];
+                                file.read_exact_at_vectored(&mut [
+                                    (offset as u64, buffer.as_mut_slice()),
+                                    (7, other_buffer.as_mut_slice()),
+                                ])
+                                .unwrap();
+                                assert_eq!(
+                                    buffer,
+                                    data[offset..][..size],
+                                    "File {file_index}, offset {offset}, size {size}"
+                                );
+                                assert_eq!(other_buffer, data[7..][..100]);
+                            }
+
+                            assert!(memory_budget.reserved() <= memory_budget.limit());
+                        }
+                    });
+                }
+            }
+        });
+
+        for (file, data) in &files {
+            assert_eq!(fs::read(file.path.as_path()).unwrap(), *data.read());
+        }
+
+        // Reservations are released together with files
+        drop(files);
+        assert_eq!(memory_budget.reserved(), 0);
+    }
+
+    #[test]
+    fn try_clone() {
+        let tempdir = tempdir().unwrap();
+        let file_path = tempdir.as_ref().join("file.bin");
+        let mut data = vec![0u8; DEFAULT_MAX_REQUEST_SIZE * 3];
+        thread_rng().fill(data.as_mut_slice());
+        fs::write(&file_path, &data).unwrap();
+
+        let file = DirectIoFile::open(&file_path).unwrap();
+        file.try_lock_exclusive().unwrap();
+        let clones = (0..4)
+            .map(|_| file.try_clone().unwrap())
+            .collect::<Vec<_>>();
+
+        // Concurrent reads through different clones are independent
+        thread::scope(|scope| {
+            for (thread_index, clone) in clones.iter().enumerate() {
+                let data = &data;
+                scope.spawn(move || {
+                    assert_eq!(clone.is_unbuffered(), file.is_unbuffered());
+                    assert_eq!(clone.sector_sizes, file.sector_sizes);
+                    for iteration in 0..10 {
+                        let offset = (thread_index * 1000 + iteration * 4099) % (data.len() / 2);
+                        let mut buffer = vec![0u8; DEFAULT_MAX_REQUEST_SIZE + 1];
+                        clone.read_exact_at(&mut buffer, offset as u64).unwrap();
+                        assert_eq!(buffer, data[offset..][..buffer.len()]);
+                    }
+                });
+            }
+        });
+
+        // Request stats are shared, such that requests through clones are accounted for
+        let request_stats = file.request_stats();
+        assert!(request_stats.reads >= 40);
+        for clone in &clones {
+            assert_eq!(clone.request_stats(), request_stats);
+        }
+
+        // Write through one clone is visible through all others, I/O stats are per clone
+        data[1000..2000].fill(1);
+        clones[0].write_all_at(&data[1000..2000], 1000).unwrap();
+        assert!(clones[0].io_stats().read_modify_writes > 0);
+        assert_eq!(clones[1].io_stats(), IoStats::default());
+        assert_eq!(file.io_stats(), IoStats::default());
+        assert!(clones[1].request_stats().writes > 0);
+        for instance in clones.iter().chain([&file]) {
+            let mut contents = vec![0u8; data.len()];
+            instance.read_exact_at(&mut contents, 0).unwrap();
+            assert_eq!(contents, data);
+        }
+
+        // File size is shared
+        let new_size = data.len() as u64 + DISK_SECTOR_SIZE as u64;
+        clones[1].set_len(new_size).unwrap();
+        for instance in clones.iter().chain([&file]) {
+            assert_eq!(instance.size().unwrap(), new_size);
+        }
+
+        // Reads through all clones observe file shrinking through any of them
+        let mut buffer = vec![0u8; DISK_SECTOR_SIZE];
+        file.read_exact_at(&mut buffer, new_size - DISK_SECTOR_SIZE as u64)
+            .unwrap();
+        clones[2].set_len(data.len() as u64).unwrap();
+        for instance in clones.iter().chain([&file]) {
+            buffer.fill(0xaa);
+            let error = instance
+                .read_exact_at(&mut buffer, new_size - DISK_SECTOR_SIZE as u64)
+                .unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+            assert!(buffer.iter().all(|&byte| byte == 0xaa));
+        }
+
+        // Lock is held while any clone is alive, even after original file is dropped
+        drop(file);
+        let mut clones = clones;
+        let last_clone = clones.pop().unwrap();
+        drop(clones);
+        let other_file = DirectIoFile::open(&file_path).unwrap();
+        assert!(other_file.try_lock_exclusive().is_err());
+        drop(last_clone);
+        other_file.try_lock_exclusive().unwrap();
+    }
+
+    #[test]
+    fn reopen() {
+        let tempdir = tempdir().unwrap();
+        let file_path = tempdir.as_ref().join("file.bin");
+        let mut data = vec![0u8; DISK_SECTOR_SIZE * 4];
+        thread_rng().fill(data.as_mut_slice());
+        fs::write(&file_path, &data).unwrap();
+
+        let file = DirectIoFile::open(&file_path).unwrap();
+        file.try_lock_exclusive().unwrap();
+        let reopened = file.reopen().unwrap();
+        assert_eq!(reopened.is_unbuffered(), file.is_unbuffered());
+        assert_eq!(reopened.write_through, file.write_through);
+
+        // Writes through either instance are visible through the other one
+        data[100..200].fill(1);
+        reopened.write_all_at(&data[100..200], 100).unwrap();
+        let mut contents = vec![0u8; data.len()];
+        file.read_exact_at(&mut contents, 0).unwrap();
+        assert_eq!(contents, data);
+
+        // Lock is held through the original handle until all instances are dropped
+        drop(file);
+        let other_file = DirectIoFile::open(&file_path).unwrap();
+        assert!(other_file.try_lock_exclusive().is_err());
+        drop(reopened);
+        other_file.try_lock_exclusive().unwrap();
+
+        // Read-only file is opened again as read-only
+        let read_only_file = DirectIoFile::open_with_options(
+            &file_path,
+            DirectIoOptions {
+                read_only: true,
+                ..DirectIoOptions::default()
+            },
+        )
+        .unwrap()
+        .reopen()
+        .unwrap();
+        assert_eq!(
+            read_only_file.write_all_at(&[0], 0).unwrap_err().kind(),
+            io::ErrorKind::PermissionDenied
+        );
+    }
+
+    #[test]
+    fn read_at_uninit() {
+        let tempdir = tempdir().unwrap();
+        let file_path = tempdir.as_ref().join("file.bin");
+        let mut data = vec![0u8; DISK_SECTOR_SIZE * 4 + 100];
+        thread_rng().fill(data.as_mut_slice());
+        fs::write(&file_path, &data).unwrap();
+
+        let file = DirectIoFile::open(&file_path).unwrap();
+
+        // Aligned reads and those that go through scratch buffer
+        for (offset, size) in [
+            (0_usize, DISK_SECTOR_SIZE),
+            (DISK_SECTOR_SIZE, DISK_SECTOR_SIZE * 2),
+            (5, 100),
+            (DISK_SECTOR_SIZE - 1, DISK_SECTOR_SIZE + 2),
+            (0, data.len()),
+            (0, 0),
+        ] {
+            // Aligned uninitialized memory, such that aligned fast path can be used
+            let mut memory =
+                Vec::<AlignedSectorSize>::with_capacity(size.div_ceil(DISK_SECTOR_SIZE));
+            let memory = memory.spare_capacity_mut();
+            // SAFETY: Any memory can be viewed as uninitialized bytes
+            let uninit = unsafe {
+                slice::from_raw_parts_mut(memory.as_mut_ptr().cast::<MaybeUninit<u8>>(), size)
+            };
+
+            let bytes = file.read_at_uninit(uninit, offset as u64).unwrap();
+            assert_eq!(bytes.len(), size);
+            assert_eq!(
+                bytes,
+                &data[offset..][..size],
+                "offset {offset}, size {size}"
+            );
+
+            // Same result as initialized path
+            let mut buffer = vec![0u8; size];
+            file.read_at(&mut buffer, offset as u64).unwrap();
+            assert_eq!(bytes, buffer.as_slice());
+        }
+
+        // Small reads within a sector
+        let mut memory = Vec::<u8>::with_capacity(100);
+        let bytes = file
+            .read_small_uninit(&mut memory.spare_capacity_mut()[..100], 5)
+            .unwrap();
+        assert_eq!(bytes, &data[5..][..100]);
+
+        // Reading beyond the end fails
+        let mut memory = Vec::<u8>::with_capacity(DISK_SECTOR_SIZE);
+        assert!(file
+            .read_at_uninit(
+                &mut memory.spare_capacity_mut()[..DISK_SECTOR_SIZE],
+                DISK_SECTOR_SIZE as u64 * 4
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn large_reads() {
+        let tempdir = tempdir().unwrap();
+        let file_path = tempdir.as_ref().join("file.bin");
+        let mut data = vec![0u8; MIN_MAX_REQUEST_SIZE * 16];
+        thread_rng().fill(data.as_mut_slice());
+        fs::write(&file_path, &data).unwrap();
+
+        let large_read_threshold = MIN_MAX_REQUEST_SIZE * 4;
+        // Unaligned offset, such that reads can't be done into destination buffer directly
+        let offset = 5;
+
+        for (max_large_read_size, size, expected_requests) in [
+            // Just below the threshold, read through scratch buffer window by window
+            (
+                DEFAULT_MAX_LARGE_READ_SIZE,
+                large_read_threshold,
+                (offset + large_read_threshold).div_ceil(MIN_MAX_REQUEST_SIZE),
+            ),
+            // Just above the threshold, read with a single request
+            (DEFAULT_MAX_LARGE_READ_SIZE, large_read_threshold + 1, 1),
+            // Above the threshold, but larger than one-shot buffer can be
+            (
+                MIN_MAX_REQUEST_SIZE * 2,
+                large_read_threshold + 1,
+                (offset + large_read_threshold + 1).div_ceil(MIN_MAX_REQUEST_SIZE * 2),
+            ),
+            // Everything up to the end of the file
+            (DEFAULT_MAX_LARGE_READ_SIZE, data.len() - offset, 1),
+        ] {
+            let file = DirectIoFile::open_with_options(
+                &file_path,
+                DirectIoOptions {
+                    max_request_size: Some(MIN_MAX_REQUEST_SIZE),
+                    large_read_threshold: Some(large_read_threshold),
+                    max_large_read_size,
+                    ..DirectIoOptions::default()
+                },
+            )
+            .unwrap();
+            // Reads must not be served from read-ahead window
+            file.set_access_pattern(AccessPattern::Random).unwrap();
+
+            let mut buffer = vec![0u8; size];
+            file.read_exact_at(&mut buffer, offset as u64).unwrap();
+            assert!(buffer == data[offset..][..size], "size {size}");
+            assert_eq!(
+                file.request_stats().reads,
+                expected_requests as u64,
+                "max large read size {max_large_read_size}, size {size}"
+            );
+        }
+
+        // Max large read size must be a multiple of disk sector size
+        for max_large_read_size in [0, DISK_SECTOR_SIZE + 1] {
+            assert!(DirectIoFile::open_with_options(
+                &file_path,
+                DirectIoOptions {
+                    max_large_read_size,
+                    ..DirectIoOptions::default()
+                },
+            )
+            .is_err());
+        }
+    }
+
+    #[test]
+    fn eof_semantics() {
+        let tempdir = tempdir().unwrap();
+        let file_path = tempdir.as_ref().join("file.bin");
+
+        let direct_file = DirectIoFile::open(&file_path).unwrap();
+        let buffered_file = DirectIoFile::open_with_options(
+            &file_path,
+            DirectIoOptions {
+                buffered_io: true,
+                ..DirectIoOptions::default()
+            },
+        )
+        .unwrap();
+        assert!(!buffered_file.is_unbuffered());
+
+        // Aligned size and size that leaves the last sector partially within the file
+        for size in [DISK_SECTOR_SIZE * 4, DISK_SECTOR_SIZE * 4 + 100] {
+            let mut data = vec![0u8; size];
+            thread_rng().fill(data.as_mut_slice());
+            direct_file.set_len(0).unwrap();
+            direct_file.write_all_at(&data, 0).unwrap();
+            direct_file.set_len(size as u64).unwrap();
+
+            for file in [&direct_file, &buffered_file] {
+                assert_eq!(file.size().unwrap(), size as u64);
+
+                // Small unaligned, whole sector and large reads
+                for len in [1, DISK_SECTOR_SIZE, DISK_SECTOR_SIZE * 3 + 5] {
+                    // Aligned memory, such that aligned reads can be done into it directly
+                    let mut memory =
+                        vec![AlignedSectorSize::default(); len.div_ceil(DISK_SECTOR_SIZE)];
+                    let buffer = &mut AlignedSectorSize::slice_to_bytes_mut(&mut memory)[..len];
+
+                    // Reads that end at EOF - 1 and at EOF succeed
+                    for end in [size - 1, size] {
+                        let offset = end - len;
+                        file.read_exact_at(buffer, offset as u64).unwrap();
+                        assert!(buffer == &data[offset..end], "size {size}, len {len}");
+                    }
+
+                    // Reads that end at EOF + 1 or start at or beyond EOF fail the same way and
+                    // leave the buffer untouched
+                    for offset in [size + 1 - len, size, size + 1] {
+                        buffer.fill(0xaa);
+                        let error = file.read_exact_at(buffer, offset as u64).unwrap_err();
+                        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+                        assert!(
+                            error.to_string().contains(&format!(
+                                "{} bytes available",
+                                size.saturating_sub(offset)
+                            )),
+                            "{error}"
+                        );
+                        assert!(buffer.iter().all(|&byte| byte == 0xaa));
+
+                        let mut memory = Vec::<u8>::with_capacity(len);
+                        let error = file
+                            .read_at_uninit(&mut memory.spare_capacity_mut()[..len], offset as u64)
+                            .unwrap_err();
+                        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn small_reads() {
+        let tempdir = tempdir().unwrap();
+        let file_path = tempdir.as_ref().join("file.bin");
+        let mut data = vec![0u8; DISK_SECTOR_SIZE * 4];
+        thread_rng().fill(data.as_mut_slice());
+        fs::write(&file_path, &data).unwrap();
+
+        let mut direct_file = DirectIoFile::open(&file_path).unwrap();
+        let buffered_file = DirectIoFile::open_with_options(
+            &file_path,
+            DirectIoOptions {
+                buffered_io: true,
+                ..DirectIoOptions::default()
+            },
+        )
+        .unwrap();
+        assert!(!buffered_file.is_unbuffered());
+
+        let check_reads = |file: &DirectIoFile| {
+            let logical_sector_size = file.logical_sector_size();
+            for (offset, len, fast_path) in [
+                (0, 1, true),
+                (0, 32, true),
+                (5, 32, true),
+                (logical_sector_size - 32, 32, true),
+                (logical_sector_size, 32, true),
+                (0, logical_sector_size, true),
+                (DISK_SECTOR_SIZE * 3 + 17, 32, true),
+                // Crosses sector boundary
+                (logical_sector_size - 16, 32, false),
+                (logical_sector_size - 1, 2, false),
+                (1, logical_sector_size, false),
+                // Larger than a sector
+                (0, MAX_SMALL_READ_SIZE + 1, false),
+            ] {
+                file.scratch_buffers.lock().clear();
+                let mut buffer = vec![0u8; len];
+                file.read_small(&mut buffer, offset as u64).unwrap();
+                assert_eq!(buffer, data[offset..][..len], "offset {offset}, len {len}");
+                // Only general path takes scratch buffer, which is then returned into the pool
+                assert_eq!(
+                    file.scratch_buffers.lock().is_empty(),
+                    fast_path,
+                    "offset {offset}, len {len}"
+                );
+            }
+
+            let mut buffer = [0u8; 32];
+            file.read_small_at(&mut buffer, 100).unwrap();
+            assert_eq!(buffer, data[100..][..32]);
+        };
+
+        check_reads(&buffered_file);
+        for override_sector_sizes in sector_sizes_overrides(&direct_file) {
+            if let Some(sector_sizes) = override_sector_sizes {
+                direct_file.sector_sizes = sector_sizes;
+            }
+            check_reads(&direct_file);
+        }
+
+        // Size that leaves the last sector partially within the file
+        let size = DISK_SECTOR_SIZE * 3 + 100;
+        direct_file.set_len(size as u64).unwrap();
+        for file in [&direct_file, &buffered_file] {
+            let mut buffer = [0u8; 32];
+            file.read_small_at(&mut buffer, (size - 32) as u64).unwrap();
+            assert_eq!(buffer, data[size - 32..size]);
+
+            // Reads past the end fail the same way as with general path and leave the buffer
+            // untouched
+            for offset in [size - 31, size, size + 1, size + DISK_SECTOR_SIZE] {
+                buffer.fill(0xaa);
+                let error = file.read_small_at(&mut buffer, offset as u64).unwrap_err();
+                assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+                assert!(
+                    error
+                        .to_string()
+                        .contains(&format!("{} bytes available", size.saturating_sub(offset))),
+                    "{error}"
+                );
+                assert!(buffer.iter().all(|&byte| byte == 0xaa));
+            }
+        }
+    }
+}
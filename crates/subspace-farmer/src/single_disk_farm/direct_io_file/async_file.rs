@@ -0,0 +1,125 @@
+//! Asynchronous reads of [`DirectIoFile`] done on a dedicated I/O thread pool, see
+//! [`AsyncDirectIoFile`]
+
+#[cfg(test)]
+mod tests;
+
+use crate::single_disk_farm::direct_io_file::DirectIoFile;
+use futures::channel::oneshot;
+use rayon::{ThreadPool, ThreadPoolBuildError, ThreadPoolBuilder};
+use std::io;
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+use subspace_farmer_components::file_ext::FileExt;
+use subspace_farmer_components::{AsyncReadBytes, ReadAtAsync};
+use tokio::sync::Semaphore;
+
+/// How many requests per thread can be queued before further reads wait for queued requests to
+/// complete
+const QUEUED_REQUESTS_PER_THREAD: usize = 4;
+
+/// Bounded pool of threads doing blocking reads for [`AsyncDirectIoFile`], supposed to be shared
+/// by all files of a farm
+#[derive(Debug, Clone)]
+pub struct IoThreadPool {
+    thread_pool: Arc<ThreadPool>,
+    /// Limits the number of queued requests, provides backpressure when the queue is full
+    queue: Arc<Semaphore>,
+}
+
+impl IoThreadPool {
+    /// Create new thread pool with `concurrency` threads, which should match I/O concurrency of the
+    /// farm, for example [`DirectIoOptions::scratch_buffers`](super::DirectIoOptions)
+    pub fn new(
+        thread_name_prefix: String,
+        concurrency: NonZeroUsize,
+    ) -> Result<Self, ThreadPoolBuildError> {
+        let thread_pool = ThreadPoolBuilder::new()
+            .thread_name(move |thread_index| format!("{thread_name_prefix}.{thread_index}"))
+            .num_threads(concurrency.get())
+            .build()?;
+
+        Ok(Self {
+            thread_pool: Arc::new(thread_pool),
+            queue: Arc::new(Semaphore::new(
+                concurrency.get() * QUEUED_REQUESTS_PER_THREAD,
+            )),
+        })
+    }
+
+    /// Read `len` bytes at `offset` on the thread pool.
+    ///
+    /// Waits for space in the queue if it is full. Request is not executed if returned future is
+    /// dropped before one of the threads picks it up.
+    async fn read(&self, file: &Arc<DirectIoFile>, len: usize, offset: u64) -> io::Result<Vec<u8>> {
+        let permit = Arc::clone(&self.queue)
+            .acquire_owned()
+            .await
+            .expect("Semaphore is never closed; qed");
+        let (result_sender, result_receiver) = oneshot::channel();
+        let file = Arc::clone(file);
+
+        self.thread_pool.spawn(move || {
+            let _permit = permit;
+
+            if result_sender.is_canceled() {
+                // Nobody is waiting for the result anymore
+                return;
+            }
+
+            let mut buf = vec![0; len];
+            let result = file.read_exact_at(&mut buf, offset).map(|()| buf);
+            // Doesn't matter if receiver still cares about it
+            let _ = result_sender.send(result);
+        });
+
+        result_receiver.await.map_err(|_error| {
+            io::Error::new(io::ErrorKind::Other, "I/O thread pool dropped read request")
+        })?
+    }
+}
+
+/// Adapter that implements [`ReadAtAsync`] for [`DirectIoFile`] by doing blocking reads on
+/// [`IoThreadPool`], which allows many reads to be in flight at the same time
+#[derive(Debug, Clone)]
+pub struct AsyncDirectIoFile {
+    file: Arc<DirectIoFile>,
+    io_thread_pool: IoThreadPool,
+}
+
+impl ReadAtAsync for AsyncDirectIoFile {
+    async fn read_at<B>(&self, mut buf: B, offset: u64) -> io::Result<B>
+    where
+        AsyncReadBytes<B>: From<B>,
+        B: AsMut<[u8]> + Unpin + 'static,
+    {
+        // Buffer is not necessarily `Send`, hence reading into a temporary buffer and copying
+        let bytes = self
+            .io_thread_pool
+            .read(&self.file, buf.as_mut().len(), offset)
+            .await?;
+        buf.as_mut().copy_from_slice(&bytes);
+
+        Ok(buf)
+    }
+}
+
+impl ReadAtAsync for &AsyncDirectIoFile {
+    async fn read_at<B>(&self, buf: B, offset: u64) -> io::Result<B>
+    where
+        AsyncReadBytes<B>: From<B>,
+        B: AsMut<[u8]> + Unpin + 'static,
+    {
+        (*self).read_at(buf, offset).await
+    }
+}
+
+impl AsyncDirectIoFile {
+    /// Create new instance that reads `file` on provided thread pool
+    pub fn new(file: Arc<DirectIoFile>, io_thread_pool: IoThreadPool) -> Self {
+        Self {
+            file,
+            io_thread_pool,
+        }
+    }
+}
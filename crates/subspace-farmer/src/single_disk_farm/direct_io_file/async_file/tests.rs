@@ -0,0 +1,74 @@
+use crate::single_disk_farm::direct_io_file::async_file::{AsyncDirectIoFile, IoThreadPool};
+use crate::single_disk_farm::direct_io_file::{DirectIoFile, DEFAULT_MAX_REQUEST_SIZE};
+use futures::stream::FuturesUnordered;
+use futures::StreamExt;
+use rand::prelude::*;
+use std::fs;
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+use subspace_farmer_components::file_ext::FileExt;
+use subspace_farmer_components::ReadAtAsync;
+use tempfile::tempdir;
+
+#[tokio::test]
+async fn concurrent_reads() {
+    let tempdir = tempdir().unwrap();
+    let file_path = tempdir.as_ref().join("file.bin");
+    let file_size = DEFAULT_MAX_REQUEST_SIZE * 2;
+    let mut data = vec![0u8; file_size];
+    thread_rng().fill(data.as_mut_slice());
+    fs::write(&file_path, &data).unwrap();
+
+    let file = Arc::new(DirectIoFile::open(&file_path).unwrap());
+    // Small pool such that queue gets full and backpressure is exercised
+    let io_thread_pool =
+        IoThreadPool::new("io-test".to_string(), NonZeroUsize::new(2).unwrap()).unwrap();
+    let async_file = AsyncDirectIoFile::new(Arc::clone(&file), io_thread_pool);
+
+    let requests = (0..1000)
+        .map(|_| {
+            let size = thread_rng().gen_range(0..DEFAULT_MAX_REQUEST_SIZE / 4);
+            let offset = thread_rng().gen_range(0..file_size - size);
+            (offset, size)
+        })
+        .collect::<Vec<_>>();
+
+    let mut reads = requests
+        .iter()
+        .map(|&(offset, size)| {
+            let async_file = &async_file;
+            async move {
+                let buffer = async_file
+                    .read_at(vec![0u8; size], offset as u64)
+                    .await
+                    .unwrap();
+                (offset, size, buffer)
+            }
+        })
+        .collect::<FuturesUnordered<_>>();
+
+    while let Some((offset, size, buffer)) = reads.next().await {
+        let mut expected = vec![0u8; size];
+        file.read_exact_at(&mut expected, offset as u64).unwrap();
+        assert_eq!(expected, buffer, "Offset {offset}, size {size}");
+        assert_eq!(&data[offset..][..size], buffer.as_slice());
+    }
+
+    // Reads dropped after being queued don't prevent further reads
+    let mut dropped_reads = (0..100)
+        .map(|_| async_file.read_at(vec![0u8; 4096], 0))
+        .collect::<FuturesUnordered<_>>();
+    let _ = futures::poll!(dropped_reads.next());
+    drop(dropped_reads);
+    let buffer = async_file
+        .read_at(vec![0u8; 4096], file_size as u64 - 4096)
+        .await
+        .unwrap();
+    assert_eq!(&data[file_size - 4096..], buffer.as_slice());
+
+    // Errors are propagated
+    assert!(async_file
+        .read_at(vec![0u8; 4096], file_size as u64 - 5)
+        .await
+        .is_err());
+}
@@ -0,0 +1,62 @@
+//! Buffered I/O backend of [`DirectIoFile`](super::DirectIoFile), used on platforms and file
+//! systems without direct I/O support
+
+#[cfg(not(any(target_os = "linux", windows)))]
+use crate::single_disk_farm::direct_io_file::device_profile::DeviceInfo;
+#[cfg(not(any(target_os = "linux", windows)))]
+use crate::single_disk_farm::direct_io_file::SectorSizes;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::path::Path;
+use subspace_farmer_components::file_ext::{FileExt, OpenOptionsExt};
+
+/// Open file with OS page cache, advising random access pattern, with `O_DSYNC` on Unix if
+/// `write_through` is requested (files with random access pattern are always opened with
+/// `FILE_FLAG_WRITE_THROUGH` on Windows)
+pub(super) fn open(
+    open_options: &mut OpenOptions,
+    path: &Path,
+    write_through: bool,
+) -> io::Result<File> {
+    let mut open_options = open_options.clone();
+    open_options.advise_random_access();
+    #[cfg(unix)]
+    if write_through {
+        use std::os::unix::fs::OpenOptionsExt as _;
+        open_options.custom_flags(libc::O_DSYNC);
+    }
+    #[cfg(not(unix))]
+    let _ = write_through;
+
+    let file = open_options.open(path)?;
+    file.advise_random_access()?;
+
+    Ok(file)
+}
+
+/// Querying sector sizes is not supported on this platform
+#[cfg(not(any(target_os = "linux", windows)))]
+pub(super) fn sector_sizes(_file: &File) -> io::Result<SectorSizes> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Querying sector sizes is not supported on this platform",
+    ))
+}
+
+/// Detecting size of system memory is not supported on this platform
+#[cfg(not(any(target_os = "linux", windows)))]
+pub(super) fn total_memory() -> io::Result<u64> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Detecting size of system memory is not supported on this platform",
+    ))
+}
+
+/// Detecting kind of the disk is not supported on this platform
+#[cfg(not(any(target_os = "linux", windows)))]
+pub(super) fn device_info(_path: &Path) -> io::Result<DeviceInfo> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Detecting kind of the disk is not supported on this platform",
+    ))
+}
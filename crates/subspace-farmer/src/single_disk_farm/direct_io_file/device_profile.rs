@@ -0,0 +1,156 @@
+//! Detection of the kind of the disk files are stored on, such that I/O parameters of
+//! [`DirectIoFile`](super::DirectIoFile) can be tuned for it without manual configuration, see
+//! [`DeviceProfile`]
+
+#[cfg(test)]
+mod tests;
+
+use crate::single_disk_farm::direct_io_file::{
+    backend, DirectIoOptions, DEFAULT_MAX_IO_REQUESTS, DEFAULT_MAX_IO_REQUESTS_ROTATIONAL,
+    DEFAULT_MAX_IO_REQUESTS_SOLID_STATE, DEFAULT_MAX_REQUEST_SIZE,
+};
+use std::fmt;
+use std::num::NonZeroUsize;
+use std::path::Path;
+use tracing::debug;
+
+/// Max size of a single request to rotational disks, larger requests don't increase throughput
+/// much, but delay concurrent requests (like proving reads) for longer
+pub const MAX_REQUEST_SIZE_ROTATIONAL: usize = 256 * 1024;
+/// Max size of a single request to disks connected via USB, USB bridges split larger requests
+/// anyway
+pub const MAX_REQUEST_SIZE_USB: usize = 512 * 1024;
+/// Max number of concurrent I/O requests to solid state disks when derived from queue depth
+/// reported by the disk (NVMe disks report queue depths of thousands)
+pub const MAX_IO_REQUESTS_SOLID_STATE: usize = 128;
+/// Max number of concurrent I/O requests to disks connected via USB, USB bridges have shallow
+/// queues and deeper queues only increase latency
+pub const MAX_IO_REQUESTS_USB: usize = 4;
+
+/// Information about the disk reported by OS, see [`DeviceInfo::detect()`]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct DeviceInfo {
+    /// Whether disk is rotational (HDD) and incurs seek penalty, `None` if unknown
+    pub rotational: Option<bool>,
+    /// Queue depth of the disk, `None` if unknown
+    pub queue_depth: Option<NonZeroUsize>,
+    /// Whether disk is connected via USB
+    pub usb: bool,
+}
+
+impl fmt::Display for DeviceInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.rotational {
+            Some(true) => write!(f, "rotational disk")?,
+            Some(false) => write!(f, "solid state disk")?,
+            None => write!(f, "unknown disk")?,
+        }
+        if self.usb {
+            write!(f, " via USB")?;
+        }
+        if let Some(queue_depth) = self.queue_depth {
+            write!(f, " (queue depth {queue_depth})")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl DeviceInfo {
+    /// Detect information about the disk `path` is stored on (Linux: sysfs, Windows:
+    /// `IOCTL_STORAGE_QUERY_PROPERTY`), everything is unknown on other platforms and when
+    /// detection fails
+    pub fn detect(path: &Path) -> Self {
+        backend::device_info(path).unwrap_or_else(|error| {
+            debug!(
+                path = %path.display(),
+                %error,
+                "Failed to detect disk kind, using default I/O parameters"
+            );
+
+            Self::default()
+        })
+    }
+}
+
+/// I/O parameters of [`DirectIoFile`](super::DirectIoFile) tuned for a specific disk.
+///
+/// Large requests and high concurrency are great for NVMe disks, but rotational and USB-connected
+/// disks perform better with modest requests and concurrency of 1-2.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct DeviceProfile {
+    /// Information about the disk parameters were derived from
+    pub device_info: DeviceInfo,
+    /// Max size of a single read or write request, see [`DirectIoOptions::max_request_size`]
+    pub max_request_size: usize,
+    /// Number of scratch buffers, see [`DirectIoOptions::scratch_buffers`]
+    pub scratch_buffers: usize,
+    /// Max number of concurrent I/O requests, see [`DirectIoOptions::max_io_requests`]
+    pub max_io_requests: NonZeroUsize,
+}
+
+impl fmt::Display for DeviceProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: max request size {}, {} scratch buffers, {} concurrent I/O requests",
+            self.device_info,
+            bytesize::to_string(self.max_request_size as u64, true),
+            self.scratch_buffers,
+            self.max_io_requests
+        )
+    }
+}
+
+impl From<DeviceInfo> for DeviceProfile {
+    fn from(device_info: DeviceInfo) -> Self {
+        let DeviceInfo {
+            rotational,
+            queue_depth,
+            usb,
+        } = device_info;
+
+        let mut max_request_size = DEFAULT_MAX_REQUEST_SIZE;
+        let mut max_io_requests = match rotational {
+            Some(true) => {
+                max_request_size = MAX_REQUEST_SIZE_ROTATIONAL;
+                DEFAULT_MAX_IO_REQUESTS_ROTATIONAL
+            }
+            Some(false) => queue_depth
+                .map(|queue_depth| queue_depth.get().min(MAX_IO_REQUESTS_SOLID_STATE))
+                .unwrap_or(DEFAULT_MAX_IO_REQUESTS_SOLID_STATE),
+            None => DEFAULT_MAX_IO_REQUESTS,
+        };
+        if usb {
+            max_request_size = max_request_size.min(MAX_REQUEST_SIZE_USB);
+            max_io_requests = max_io_requests.min(MAX_IO_REQUESTS_USB);
+        }
+        let max_io_requests = NonZeroUsize::new(max_io_requests).unwrap_or(NonZeroUsize::MIN);
+
+        Self {
+            device_info,
+            max_request_size,
+            // There is no point in having more buffers than concurrent requests
+            scratch_buffers: DirectIoOptions::default_scratch_buffers().min(max_io_requests.get()),
+            max_io_requests,
+        }
+    }
+}
+
+impl DeviceProfile {
+    /// Detect the disk `path` is stored on and derive I/O parameters for it, see
+    /// [`DeviceInfo::detect()`]
+    pub fn detect(path: &Path) -> Self {
+        Self::from(DeviceInfo::detect(path))
+    }
+
+    /// Replace derived parameters with those that are explicitly set in `options`
+    pub fn with_overrides(self, options: &DirectIoOptions) -> Self {
+        Self {
+            device_info: self.device_info,
+            max_request_size: options.max_request_size.unwrap_or(self.max_request_size),
+            scratch_buffers: options.scratch_buffers.unwrap_or(self.scratch_buffers),
+            max_io_requests: options.max_io_requests.unwrap_or(self.max_io_requests),
+        }
+    }
+}
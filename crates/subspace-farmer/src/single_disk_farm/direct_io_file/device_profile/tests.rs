@@ -0,0 +1,144 @@
+use crate::single_disk_farm::direct_io_file::device_profile::{
+    DeviceInfo, DeviceProfile, MAX_IO_REQUESTS_SOLID_STATE, MAX_IO_REQUESTS_USB,
+    MAX_REQUEST_SIZE_ROTATIONAL, MAX_REQUEST_SIZE_USB,
+};
+use crate::single_disk_farm::direct_io_file::{
+    DirectIoOptions, DEFAULT_MAX_IO_REQUESTS, DEFAULT_MAX_IO_REQUESTS_ROTATIONAL,
+    DEFAULT_MAX_REQUEST_SIZE,
+};
+use std::num::NonZeroUsize;
+use tempfile::tempdir;
+
+fn assert_profile(
+    device_info: DeviceInfo,
+    max_request_size: usize,
+    max_io_requests: usize,
+) -> DeviceProfile {
+    let profile = DeviceProfile::from(device_info);
+
+    assert_eq!(profile.device_info, device_info);
+    assert_eq!(profile.max_request_size, max_request_size);
+    assert_eq!(profile.max_io_requests.get(), max_io_requests);
+    assert_eq!(
+        profile.scratch_buffers,
+        DirectIoOptions::default_scratch_buffers().min(max_io_requests)
+    );
+
+    profile
+}
+
+#[test]
+fn nvme() {
+    assert_profile(
+        DeviceInfo {
+            rotational: Some(false),
+            queue_depth: NonZeroUsize::new(1023),
+            usb: false,
+        },
+        DEFAULT_MAX_REQUEST_SIZE,
+        MAX_IO_REQUESTS_SOLID_STATE,
+    );
+    // Queue depth below the limit is used as is
+    assert_profile(
+        DeviceInfo {
+            rotational: Some(false),
+            queue_depth: NonZeroUsize::new(64),
+            usb: false,
+        },
+        DEFAULT_MAX_REQUEST_SIZE,
+        64,
+    );
+}
+
+#[test]
+fn rotational() {
+    let profile = assert_profile(
+        DeviceInfo {
+            rotational: Some(true),
+            queue_depth: NonZeroUsize::new(64),
+            usb: false,
+        },
+        MAX_REQUEST_SIZE_ROTATIONAL,
+        DEFAULT_MAX_IO_REQUESTS_ROTATIONAL,
+    );
+    assert!(profile.scratch_buffers <= DEFAULT_MAX_IO_REQUESTS_ROTATIONAL);
+    assert!(
+        profile
+            .to_string()
+            .starts_with("rotational disk (queue depth 64)"),
+        "{profile}"
+    );
+
+    // USB doesn't make it any better
+    assert_profile(
+        DeviceInfo {
+            rotational: Some(true),
+            queue_depth: None,
+            usb: true,
+        },
+        MAX_REQUEST_SIZE_ROTATIONAL,
+        DEFAULT_MAX_IO_REQUESTS_ROTATIONAL,
+    );
+}
+
+#[test]
+fn usb_solid_state() {
+    assert_profile(
+        DeviceInfo {
+            rotational: Some(false),
+            queue_depth: NonZeroUsize::new(32),
+            usb: true,
+        },
+        MAX_REQUEST_SIZE_USB,
+        MAX_IO_REQUESTS_USB,
+    );
+}
+
+#[test]
+fn unknown() {
+    assert_profile(
+        DeviceInfo::default(),
+        DEFAULT_MAX_REQUEST_SIZE,
+        DEFAULT_MAX_IO_REQUESTS,
+    );
+}
+
+#[test]
+fn overrides() {
+    let profile = DeviceProfile::from(DeviceInfo {
+        rotational: Some(true),
+        queue_depth: None,
+        usb: true,
+    });
+    let options = DirectIoOptions {
+        max_request_size: Some(DEFAULT_MAX_REQUEST_SIZE * 2),
+        max_io_requests: NonZeroUsize::new(7),
+        ..DirectIoOptions::default()
+    };
+
+    // Explicitly set parameters win
+    let profile = profile.with_overrides(&options);
+    assert_eq!(profile.max_request_size, DEFAULT_MAX_REQUEST_SIZE * 2);
+    assert_eq!(profile.max_io_requests.get(), 7);
+    assert!(profile.scratch_buffers <= DEFAULT_MAX_IO_REQUESTS_ROTATIONAL);
+
+    let tuned_options = options.with_device_profile(&profile);
+    assert_eq!(
+        tuned_options.max_request_size,
+        Some(profile.max_request_size)
+    );
+    assert_eq!(tuned_options.scratch_buffers, Some(profile.scratch_buffers));
+    assert_eq!(tuned_options.max_io_requests, Some(profile.max_io_requests));
+}
+
+#[test]
+fn detect() {
+    let tempdir = tempdir().unwrap();
+
+    // Whatever the disk is, detection must not fail and parameters must be valid
+    let profile = DeviceProfile::detect(tempdir.as_ref());
+    DirectIoOptions::default()
+        .with_device_profile(&profile)
+        .validate()
+        .unwrap();
+}
@@ -0,0 +1,108 @@
+//! Linux backend of [`DirectIoFile`](super::DirectIoFile)
+
+use crate::single_disk_farm::direct_io_file::device_profile::DeviceInfo;
+use crate::single_disk_farm::direct_io_file::SectorSizes;
+use std::error::Error;
+use std::fs::{File, OpenOptions};
+use std::os::unix::fs::{MetadataExt, OpenOptionsExt as _};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::{fs, io};
+use subspace_farmer_components::file_ext::OpenOptionsExt;
+
+/// Open file with `O_DIRECT` (and `O_DSYNC` if `write_through` is requested), returns
+/// [`io::ErrorKind::InvalidInput`] error if file system doesn't support it (like `tmpfs` and some
+/// network file systems)
+pub(super) fn open(
+    open_options: &mut OpenOptions,
+    path: &Path,
+    write_through: bool,
+) -> io::Result<File> {
+    let mut open_options = open_options.clone();
+    if write_through {
+        // Flags set with `custom_flags()` override each other, hence `O_DIRECT` is set here too
+        open_options.custom_flags(libc::O_DIRECT | libc::O_DSYNC);
+    } else {
+        open_options.advise_unbuffered();
+    }
+
+    open_options.open(path)
+}
+
+/// Query logical and physical sector sizes of the block device that contains the file from sysfs
+pub(super) fn sector_sizes(file: &File) -> io::Result<SectorSizes> {
+    let queue_path = queue_path(file.metadata()?.dev());
+
+    Ok(SectorSizes {
+        logical: read_queue_parameter(&queue_path, "logical_block_size")?,
+        physical: read_queue_parameter(&queue_path, "physical_block_size")?,
+    })
+}
+
+/// Query size of physical memory of the system with `sysconf()`
+pub(super) fn total_memory() -> io::Result<u64> {
+    // SAFETY: `sysconf()` has no preconditions
+    let (pages, page_size) = unsafe {
+        (
+            libc::sysconf(libc::_SC_PHYS_PAGES),
+            libc::sysconf(libc::_SC_PAGESIZE),
+        )
+    };
+    if pages <= 0 || page_size <= 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok((pages as u64).saturating_mul(page_size as u64))
+}
+
+/// Query whether the block device that contains the path is rotational, its queue depth and
+/// whether it is connected via USB from sysfs
+pub(super) fn device_info(path: &Path) -> io::Result<DeviceInfo> {
+    let queue_path = queue_path(fs::metadata(path)?.dev());
+    // Resolves to something like `/sys/devices/pci0000:00/0000:00:14.0/usb2/2-1/.../block/sdb` for
+    // USB-connected devices
+    let usb = fs::canonicalize(queue_path.join(".."))
+        .map(|device_path| {
+            device_path.components().any(|component| {
+                component
+                    .as_os_str()
+                    .to_str()
+                    .is_some_and(|component| component.starts_with("usb"))
+            })
+        })
+        .unwrap_or_default();
+
+    Ok(DeviceInfo {
+        rotational: Some(read_queue_parameter::<u8>(&queue_path, "rotational")? != 0),
+        // Queue depth of the device itself rather than the size of the block layer request queue
+        // (`nr_requests`), only reported by SCSI/SATA devices
+        queue_depth: read_queue_parameter(&queue_path, "../device/queue_depth").ok(),
+        usb,
+    })
+}
+
+/// Path of queue parameters of the block device with device number `device` in sysfs
+fn queue_path(device: u64) -> PathBuf {
+    // Decode device number the same way `major()` and `minor()` from glibc do
+    let major = ((device >> 32) & 0xffff_f000) | ((device >> 8) & 0x0000_0fff);
+    let minor = ((device >> 12) & 0xffff_ff00) | (device & 0x0000_00ff);
+
+    let device_path = PathBuf::from(format!("/sys/dev/block/{major}:{minor}"));
+    // Partitions do not have queue parameters, those belong to the parent device
+    if device_path.join("partition").exists() {
+        device_path.join("../queue")
+    } else {
+        device_path.join("queue")
+    }
+}
+
+fn read_queue_parameter<T>(queue_path: &Path, name: &str) -> io::Result<T>
+where
+    T: FromStr,
+    T::Err: Into<Box<dyn Error + Send + Sync>>,
+{
+    fs::read_to_string(queue_path.join(name))?
+        .trim()
+        .parse()
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+}
@@ -0,0 +1,198 @@
+//! Budget of memory used by scratch buffers of [`DirectIoFile`](super::DirectIoFile)s that can be
+//! shared by all files of the process, see [`IoMemoryBudget`]
+
+use crate::single_disk_farm::direct_io_file::{backend, AlignedSectorSize, ScratchBuffer};
+use parking_lot::{Mutex, MutexGuard};
+use prometheus_client::metrics::gauge::Gauge;
+use prometheus_client::registry::{Registry, Unit};
+use std::cell::Cell;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicI64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use tracing::debug;
+
+/// Default budget is this fraction of system memory
+const DEFAULT_BUDGET_SYSTEM_MEMORY_DIVISOR: u64 = 64;
+/// Smallest default budget, fits a few scratch buffers of default size
+const MIN_DEFAULT_BUDGET: usize = 16 * 1024 * 1024;
+/// Largest default budget
+const MAX_DEFAULT_BUDGET: usize = 1024 * 1024 * 1024;
+/// Default budget when size of system memory can't be detected
+const FALLBACK_DEFAULT_BUDGET: usize = 256 * 1024 * 1024;
+
+thread_local! {
+    /// Whether current thread has borrowed shared scratch buffer of any budget already, such that
+    /// nested requests (like reads of individual regions after failed covering read) don't wait
+    /// for a buffer held by the same thread
+    static SHARED_BUFFER_BORROWED: Cell<bool> = const { Cell::new(false) };
+}
+
+#[derive(Debug)]
+struct Inner {
+    limit: usize,
+    reserved: AtomicUsize,
+    /// Buffer shared by all files once budget is exhausted, requests that borrow it are serialized
+    shared_buffer: Mutex<ScratchBuffer>,
+    reserved_gauge: Gauge<i64, AtomicI64>,
+}
+
+/// Budget of memory used by scratch buffers of [`DirectIoFile`](super::DirectIoFile)s, it is
+/// shared by all files that are opened with the same budget (see
+/// [`IoRequestLimiter::with_memory_budget()`](super::IoRequestLimiter::with_memory_budget)), such
+/// that memory used for I/O doesn't grow with the number of farms.
+///
+/// Every scratch buffer reserves its size from the budget for as long as it exists (including
+/// buffers kept in the pool of the file). Once budget is exhausted, new scratch buffers are not
+/// allocated, instead requests borrow a single buffer shared by all files of the budget one at a
+/// time, which is slower, but keeps memory usage bounded without failing requests.
+///
+/// Shared buffer itself, read-ahead windows and one-shot buffers of large reads are not accounted
+/// for.
+#[derive(Debug, Clone)]
+pub struct IoMemoryBudget {
+    inner: Arc<Inner>,
+}
+
+impl IoMemoryBudget {
+    /// Create new budget of `limit` bytes
+    pub fn new(limit: usize) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                limit,
+                reserved: AtomicUsize::new(0),
+                shared_buffer: Mutex::default(),
+                reserved_gauge: Gauge::default(),
+            }),
+        }
+    }
+
+    /// Default budget of the process, 1/64 of system memory between 16 MiB and 1 GiB, 256 MiB is
+    /// used if size of system memory can't be detected
+    pub fn default_limit() -> usize {
+        match backend::total_memory() {
+            Ok(total_memory) => {
+                usize::try_from(total_memory / DEFAULT_BUDGET_SYSTEM_MEMORY_DIVISOR)
+                    .unwrap_or(usize::MAX)
+                    .clamp(MIN_DEFAULT_BUDGET, MAX_DEFAULT_BUDGET)
+            }
+            Err(error) => {
+                debug!(%error, "Failed to detect size of system memory, using fallback I/O budget");
+
+                FALLBACK_DEFAULT_BUDGET
+            }
+        }
+    }
+
+    /// Register metrics of the budget in provided registry
+    pub fn register_metrics(&self, registry: &mut Registry) {
+        let sub_registry = registry.sub_registry_with_prefix("subspace_farmer_io_memory_budget");
+
+        sub_registry.register_with_unit(
+            "reserved",
+            "Memory reserved by scratch buffers of all files",
+            Unit::Bytes,
+            self.inner.reserved_gauge.clone(),
+        );
+
+        let limit = Gauge::<i64, AtomicI64>::default();
+        limit.set(i64::try_from(self.inner.limit).unwrap_or(i64::MAX));
+        sub_registry.register_with_unit(
+            "limit",
+            "Max memory scratch buffers of all files can reserve",
+            Unit::Bytes,
+            limit,
+        );
+    }
+
+    /// Max number of bytes scratch buffers can reserve
+    pub fn limit(&self) -> usize {
+        self.inner.limit
+    }
+
+    /// Number of bytes currently reserved by scratch buffers
+    pub fn reserved(&self) -> usize {
+        self.inner.reserved.load(Ordering::Relaxed)
+    }
+
+    /// Reserve `bytes` bytes, returns `None` if that would exceed the budget
+    pub(super) fn try_reserve(&self, bytes: usize) -> Option<IoMemoryReservation> {
+        let limit = self.inner.limit;
+        let reserved = self
+            .inner
+            .reserved
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |reserved| {
+                reserved
+                    .checked_add(bytes)
+                    .filter(|&reserved| reserved <= limit)
+            })
+            .ok()?
+            + bytes;
+        self.inner
+            .reserved_gauge
+            .set(i64::try_from(reserved).unwrap_or(i64::MAX));
+
+        Some(IoMemoryReservation {
+            budget: self.clone(),
+            bytes,
+        })
+    }
+
+    /// Borrow buffer shared by all files of the budget, grown to at least `len` elements if
+    /// necessary. Waits for other threads to return it first, returns `None` if current thread
+    /// has already borrowed a shared buffer.
+    pub(super) fn borrow_shared_buffer(&self, len: usize) -> Option<SharedScratchBuffer<'_>> {
+        if SHARED_BUFFER_BORROWED.get() {
+            return None;
+        }
+
+        let mut buffer = self.inner.shared_buffer.lock();
+        if buffer.len() < len {
+            buffer.resize(len, AlignedSectorSize::default());
+        }
+        SHARED_BUFFER_BORROWED.set(true);
+
+        Some(SharedScratchBuffer { buffer })
+    }
+}
+
+/// Memory reserved in [`IoMemoryBudget`], released on drop
+#[derive(Debug)]
+pub(super) struct IoMemoryReservation {
+    budget: IoMemoryBudget,
+    bytes: usize,
+}
+
+impl Drop for IoMemoryReservation {
+    fn drop(&mut self) {
+        let inner = &self.budget.inner;
+        let reserved = inner.reserved.fetch_sub(self.bytes, Ordering::Relaxed) - self.bytes;
+        inner
+            .reserved_gauge
+            .set(i64::try_from(reserved).unwrap_or(i64::MAX));
+    }
+}
+
+/// Shared scratch buffer borrowed from [`IoMemoryBudget`], returned on drop
+pub(super) struct SharedScratchBuffer<'a> {
+    buffer: MutexGuard<'a, ScratchBuffer>,
+}
+
+impl Deref for SharedScratchBuffer<'_> {
+    type Target = ScratchBuffer;
+
+    fn deref(&self) -> &Self::Target {
+        &self.buffer
+    }
+}
+
+impl DerefMut for SharedScratchBuffer<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.buffer
+    }
+}
+
+impl Drop for SharedScratchBuffer<'_> {
+    fn drop(&mut self) {
+        SHARED_BUFFER_BORROWED.set(false);
+    }
+}
@@ -0,0 +1,330 @@
+//! Disk I/O metrics of [`DirectIoFile`](super::DirectIoFile)
+
+use crate::farm::FarmId;
+use prometheus_client::metrics::counter::Counter;
+use prometheus_client::metrics::family::Family;
+use prometheus_client::metrics::gauge::Gauge;
+use prometheus_client::metrics::histogram::{exponential_buckets, Histogram};
+use prometheus_client::registry::{Registry, Unit};
+use std::fmt;
+use std::sync::atomic::AtomicU64;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Kind of farm file metrics are collected for
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum DiskFileKind {
+    /// Plot file
+    Plot,
+    /// Plot metadata file
+    Metadata,
+    /// Piece cache file
+    Cache,
+}
+
+impl fmt::Display for DiskFileKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Plot => "plot",
+            Self::Metadata => "metadata",
+            Self::Cache => "cache",
+        })
+    }
+}
+
+/// Disk I/O metrics registered in Prometheus registry, see [`DiskMetrics::file_metrics()`] for
+/// metrics of individual files
+#[derive(Debug, Clone)]
+pub struct DiskMetrics {
+    read_bytes: Family<Vec<(String, String)>, Counter<u64, AtomicU64>>,
+    written_bytes: Family<Vec<(String, String)>, Counter<u64, AtomicU64>>,
+    request_size: Family<Vec<(String, String)>, Histogram>,
+    request_time: Family<Vec<(String, String)>, Histogram>,
+    request_errors: Family<Vec<(String, String)>, Counter<u64, AtomicU64>>,
+    requests_in_flight: Family<Vec<(String, String)>, Gauge>,
+    writes: Family<Vec<(String, String)>, Counter<u64, AtomicU64>>,
+    read_modify_write_bytes: Family<Vec<(String, String)>, Counter<u64, AtomicU64>>,
+    write_verification_failures: Family<Vec<(String, String)>, Counter<u64, AtomicU64>>,
+    background_bytes: Family<Vec<(String, String)>, Counter<u64, AtomicU64>>,
+    self_test_read_time: Family<Vec<(String, String)>, Histogram>,
+    self_test_read_errors: Family<Vec<(String, String)>, Counter<u64, AtomicU64>>,
+}
+
+impl DiskMetrics {
+    /// Create new instance and register metrics in provided registry
+    pub fn new(registry: &mut Registry) -> Self {
+        let sub_registry = registry.sub_registry_with_prefix("subspace_farmer_disk");
+
+        let read_bytes = Family::<_, _>::new_with_constructor(Counter::<_, _>::default);
+
+        sub_registry.register_with_unit(
+            "read",
+            "Bytes read from disk",
+            Unit::Bytes,
+            read_bytes.clone(),
+        );
+
+        let written_bytes = Family::<_, _>::new_with_constructor(Counter::<_, _>::default);
+
+        sub_registry.register_with_unit(
+            "written",
+            "Bytes written to disk",
+            Unit::Bytes,
+            written_bytes.clone(),
+        );
+
+        let request_size = Family::<_, _>::new_with_constructor(|| {
+            // 512 bytes to 64 MiB
+            Histogram::new(exponential_buckets(512.0, 2.0, 18))
+        });
+
+        sub_registry.register_with_unit(
+            "request_size",
+            "Size of individual disk requests",
+            Unit::Bytes,
+            request_size.clone(),
+        );
+
+        let request_time = Family::<_, _>::new_with_constructor(|| {
+            Histogram::new(exponential_buckets(0.00001, 2.0, 20))
+        });
+
+        sub_registry.register_with_unit(
+            "request_time",
+            "Latency of individual disk requests",
+            Unit::Seconds,
+            request_time.clone(),
+        );
+
+        let request_errors = Family::<_, _>::new_with_constructor(Counter::<_, _>::default);
+
+        sub_registry.register(
+            "request_errors",
+            "Number of disk requests that failed",
+            request_errors.clone(),
+        );
+
+        let requests_in_flight = Family::<_, _>::new_with_constructor(Gauge::<_, _>::default);
+
+        sub_registry.register(
+            "requests_in_flight",
+            "Number of disk requests currently in flight",
+            requests_in_flight.clone(),
+        );
+
+        let writes = Family::<_, _>::new_with_constructor(Counter::<_, _>::default);
+
+        sub_registry.register(
+            "writes",
+            "Number of write requests, either aligned or requiring read-modify-write",
+            writes.clone(),
+        );
+
+        let read_modify_write_bytes =
+            Family::<_, _>::new_with_constructor(Counter::<_, _>::default);
+
+        sub_registry.register_with_unit(
+            "read_modify_write_amplification",
+            "Bytes read and written back by read-modify-write in addition to requested bytes",
+            Unit::Bytes,
+            read_modify_write_bytes.clone(),
+        );
+
+        let write_verification_failures =
+            Family::<_, _>::new_with_constructor(Counter::<_, _>::default);
+
+        sub_registry.register(
+            "write_verification_failures",
+            "Number of writes that didn't match when read back",
+            write_verification_failures.clone(),
+        );
+
+        let background_bytes = Family::<_, _>::new_with_constructor(Counter::<_, _>::default);
+
+        sub_registry.register_with_unit(
+            "background",
+            "Bytes requested with background priority (like scrubbing reads), rate of this counter \
+            is background throughput",
+            Unit::Bytes,
+            background_bytes.clone(),
+        );
+
+        let self_test_read_time = Family::<_, _>::new_with_constructor(|| {
+            Histogram::new(exponential_buckets(0.00001, 2.0, 20))
+        });
+
+        sub_registry.register_with_unit(
+            "self_test_read_time",
+            "Latency of region reads of plot read self-test on farm startup",
+            Unit::Seconds,
+            self_test_read_time.clone(),
+        );
+
+        let self_test_read_errors = Family::<_, _>::new_with_constructor(Counter::<_, _>::default);
+
+        sub_registry.register(
+            "self_test_read_errors",
+            "Number of region reads of plot read self-test on farm startup that failed",
+            self_test_read_errors.clone(),
+        );
+
+        Self {
+            read_bytes,
+            written_bytes,
+            request_size,
+            request_time,
+            request_errors,
+            requests_in_flight,
+            writes,
+            read_modify_write_bytes,
+            write_verification_failures,
+            background_bytes,
+            self_test_read_time,
+            self_test_read_errors,
+        }
+    }
+
+    /// Metrics of a specific file of a farm, to be used with
+    /// [`DirectIoFile::open_with_metrics()`](super::DirectIoFile::open_with_metrics)
+    pub fn file_metrics(&self, farm_id: &FarmId, kind: DiskFileKind) -> Arc<FileMetrics> {
+        let labels = vec![
+            ("farm_id".to_string(), farm_id.to_string()),
+            ("file".to_string(), kind.to_string()),
+        ];
+        let operation_labels = |operation: &str| {
+            let mut labels = labels.clone();
+            labels.push(("operation".to_string(), operation.to_string()));
+            labels
+        };
+        let kind_labels = |kind: &str| {
+            let mut labels = labels.clone();
+            labels.push(("kind".to_string(), kind.to_string()));
+            labels
+        };
+
+        Arc::new(FileMetrics {
+            read_bytes: self.read_bytes.get_or_create(&labels).clone(),
+            written_bytes: self.written_bytes.get_or_create(&labels).clone(),
+            read_request_size: self
+                .request_size
+                .get_or_create(&operation_labels("read"))
+                .clone(),
+            write_request_size: self
+                .request_size
+                .get_or_create(&operation_labels("write"))
+                .clone(),
+            read_time: self
+                .request_time
+                .get_or_create(&operation_labels("read"))
+                .clone(),
+            write_time: self
+                .request_time
+                .get_or_create(&operation_labels("write"))
+                .clone(),
+            read_errors: self
+                .request_errors
+                .get_or_create(&operation_labels("read"))
+                .clone(),
+            write_errors: self
+                .request_errors
+                .get_or_create(&operation_labels("write"))
+                .clone(),
+            requests_in_flight: self.requests_in_flight.get_or_create(&labels).clone(),
+            aligned_writes: self.writes.get_or_create(&kind_labels("aligned")).clone(),
+            read_modify_writes: self
+                .writes
+                .get_or_create(&kind_labels("read_modify_write"))
+                .clone(),
+            read_modify_write_bytes: self.read_modify_write_bytes.get_or_create(&labels).clone(),
+            write_verification_failures: self
+                .write_verification_failures
+                .get_or_create(&labels)
+                .clone(),
+            background_bytes: self.background_bytes.get_or_create(&labels).clone(),
+            self_test_read_time: self.self_test_read_time.get_or_create(&labels).clone(),
+            self_test_read_errors: self.self_test_read_errors.get_or_create(&labels).clone(),
+        })
+    }
+}
+
+/// Disk I/O metrics of a single file, created with [`DiskMetrics::file_metrics()`]
+#[derive(Debug)]
+pub struct FileMetrics {
+    pub(super) read_bytes: Counter<u64, AtomicU64>,
+    pub(super) written_bytes: Counter<u64, AtomicU64>,
+    read_request_size: Histogram,
+    write_request_size: Histogram,
+    read_time: Histogram,
+    write_time: Histogram,
+    read_errors: Counter<u64, AtomicU64>,
+    write_errors: Counter<u64, AtomicU64>,
+    pub(super) requests_in_flight: Gauge,
+    pub(super) aligned_writes: Counter<u64, AtomicU64>,
+    pub(super) read_modify_writes: Counter<u64, AtomicU64>,
+    pub(super) read_modify_write_bytes: Counter<u64, AtomicU64>,
+    pub(crate) write_verification_failures: Counter<u64, AtomicU64>,
+    pub(super) background_bytes: Counter<u64, AtomicU64>,
+    self_test_read_time: Histogram,
+    self_test_read_errors: Counter<u64, AtomicU64>,
+}
+
+impl FileMetrics {
+    pub(super) fn observe_read(&self, bytes: usize, time: Duration) {
+        self.read_bytes.inc_by(bytes as u64);
+        self.read_request_size.observe(bytes as f64);
+        self.read_time.observe(time.as_secs_f64());
+    }
+
+    pub(super) fn observe_write(&self, bytes: usize, time: Duration) {
+        self.written_bytes.inc_by(bytes as u64);
+        self.write_request_size.observe(bytes as f64);
+        self.write_time.observe(time.as_secs_f64());
+    }
+
+    pub(super) fn observe_read_error(&self) {
+        self.read_errors.inc();
+    }
+
+    pub(super) fn observe_write_error(&self) {
+        self.write_errors.inc();
+    }
+
+    pub(super) fn observe_write_alignment(&self, amplified_bytes: Option<usize>) {
+        match amplified_bytes {
+            Some(amplified_bytes) => {
+                self.read_modify_writes.inc();
+                self.read_modify_write_bytes.inc_by(amplified_bytes as u64);
+            }
+            None => {
+                self.aligned_writes.inc();
+            }
+        }
+    }
+
+    /// Record write that didn't match its contents when read back
+    pub(crate) fn observe_write_verification_failure(&self) {
+        self.write_verification_failures.inc();
+    }
+
+    pub(super) fn observe_background_request(&self, bytes: usize) {
+        self.background_bytes.inc_by(bytes as u64);
+    }
+
+    /// Record successful region read of read self-test
+    pub(crate) fn observe_self_test_read(&self, time: Duration) {
+        self.self_test_read_time.observe(time.as_secs_f64());
+    }
+
+    /// Record failed region read of read self-test
+    pub(crate) fn observe_self_test_read_error(&self) {
+        self.self_test_read_errors.inc();
+    }
+
+    pub(super) fn start_request(&self) {
+        self.requests_in_flight.inc();
+    }
+
+    pub(super) fn finish_request(&self) {
+        self.requests_in_flight.dec();
+    }
+}
@@ -0,0 +1,238 @@
+//! Windows backend of [`DirectIoFile`](super::DirectIoFile)
+
+use crate::single_disk_farm::direct_io_file::device_profile::DeviceInfo;
+use crate::single_disk_farm::direct_io_file::SectorSizes;
+use std::ffi::OsString;
+use std::fs::{File, OpenOptions};
+use std::os::windows::ffi::{OsStrExt, OsStringExt};
+use std::os::windows::fs::OpenOptionsExt as _;
+use std::os::windows::io::AsRawHandle;
+use std::path::Path;
+use std::{io, mem, ptr};
+use subspace_farmer_components::file_ext::OpenOptionsExt;
+use winapi::um::fileapi::{GetVolumeNameForVolumeMountPointW, GetVolumePathNameW};
+use winapi::um::ioapiset::DeviceIoControl;
+use winapi::um::minwinbase::FileStorageInfo;
+use winapi::um::sysinfoapi::{GlobalMemoryStatusEx, MEMORYSTATUSEX};
+use winapi::um::winbase::GetFileInformationByHandleEx;
+use winapi::um::winioctl::IOCTL_STORAGE_QUERY_PROPERTY;
+use winapi::um::winnt::{FILE_SHARE_READ, FILE_SHARE_WRITE, HANDLE};
+
+/// `StorageAdapterProperty` of `STORAGE_PROPERTY_ID`
+const STORAGE_ADAPTER_PROPERTY: u32 = 1;
+/// `StorageDeviceSeekPenaltyProperty` of `STORAGE_PROPERTY_ID`
+const STORAGE_DEVICE_SEEK_PENALTY_PROPERTY: u32 = 7;
+/// `BusTypeUsb` of `STORAGE_BUS_TYPE`
+const BUS_TYPE_USB: u8 = 7;
+/// Max length of volume path and name in UTF-16 code units
+const MAX_VOLUME_PATH_LENGTH: usize = 1024;
+
+/// `FILE_STORAGE_INFO` structure returned by `GetFileInformationByHandleEx()`
+// Only some fields are used, but all of them are filled by the OS
+#[allow(dead_code)]
+#[derive(Debug, Default)]
+#[repr(C)]
+struct StorageInfo {
+    logical_bytes_per_sector: u32,
+    physical_bytes_per_sector_for_atomicity: u32,
+    physical_bytes_per_sector_for_performance: u32,
+    file_system_effective_physical_bytes_per_sector_for_atomicity: u32,
+    flags: u32,
+    byte_offset_for_sector_alignment: u32,
+    byte_offset_for_partition_alignment: u32,
+}
+
+/// `STORAGE_PROPERTY_QUERY` structure for `IOCTL_STORAGE_QUERY_PROPERTY` with
+/// `PropertyStandardQuery` query type
+#[derive(Debug)]
+#[repr(C)]
+struct StoragePropertyQuery {
+    property_id: u32,
+    query_type: u32,
+    additional_parameters: [u8; 1],
+}
+
+/// `DEVICE_SEEK_PENALTY_DESCRIPTOR` structure returned for `StorageDeviceSeekPenaltyProperty`
+// Only some fields are used, but all of them are filled by the OS
+#[allow(dead_code)]
+#[derive(Debug, Default)]
+#[repr(C)]
+struct DeviceSeekPenaltyDescriptor {
+    version: u32,
+    size: u32,
+    incurs_seek_penalty: u8,
+}
+
+/// `STORAGE_ADAPTER_DESCRIPTOR` structure returned for `StorageAdapterProperty`
+// Only some fields are used, but all of them are filled by the OS
+#[allow(dead_code)]
+#[derive(Debug, Default)]
+#[repr(C)]
+struct StorageAdapterDescriptor {
+    version: u32,
+    size: u32,
+    maximum_transfer_length: u32,
+    maximum_physical_pages: u32,
+    alignment_mask: u32,
+    adapter_uses_pio: u8,
+    adapter_scans_down: u8,
+    command_queueing: u8,
+    accelerated_transfer: u8,
+    bus_type: u8,
+    bus_major_version: u16,
+    bus_minor_version: u16,
+    srb_type: u8,
+    address_type: u8,
+}
+
+/// Open file with unbuffered I/O, unbuffered files are always opened with
+/// `FILE_FLAG_WRITE_THROUGH`, regardless of `write_through`
+pub(super) fn open(
+    open_options: &mut OpenOptions,
+    path: &Path,
+    _write_through: bool,
+) -> io::Result<File> {
+    open_options.clone().advise_unbuffered().open(path)
+}
+
+/// Query logical and physical sector sizes of the disk that contains the file using
+/// `FILE_STORAGE_INFO`
+pub(super) fn sector_sizes(file: &File) -> io::Result<SectorSizes> {
+    let mut storage_info = StorageInfo::default();
+    // SAFETY: Handle is valid while file is alive, provided buffer matches `FILE_STORAGE_INFO`
+    // layout and size
+    let result = unsafe {
+        GetFileInformationByHandleEx(
+            file.as_raw_handle() as HANDLE,
+            FileStorageInfo,
+            (&mut storage_info as *mut StorageInfo).cast(),
+            mem::size_of::<StorageInfo>() as u32,
+        )
+    };
+    if result == 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(SectorSizes {
+        logical: storage_info.logical_bytes_per_sector as usize,
+        physical: storage_info.physical_bytes_per_sector_for_performance as usize,
+    })
+}
+
+/// Query size of physical memory of the system with `GlobalMemoryStatusEx()`
+pub(super) fn total_memory() -> io::Result<u64> {
+    // SAFETY: `MEMORYSTATUSEX` is a plain C structure, zeroed value is valid
+    let mut memory_status = unsafe { mem::zeroed::<MEMORYSTATUSEX>() };
+    memory_status.dwLength = mem::size_of::<MEMORYSTATUSEX>() as u32;
+    // SAFETY: Pointer is valid for the duration of the call, structure size is set above
+    if unsafe { GlobalMemoryStatusEx(&mut memory_status) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(memory_status.ullTotalPhys)
+}
+
+/// Query whether the disk of the volume that contains the path incurs seek penalty
+/// (`StorageDeviceSeekPenaltyProperty`) and whether it is connected via USB
+/// (`StorageAdapterProperty`), queue depth is not reported on Windows
+pub(super) fn device_info(path: &Path) -> io::Result<DeviceInfo> {
+    let volume = open_volume(path)?;
+    let seek_penalty = query_storage_property::<DeviceSeekPenaltyDescriptor>(
+        &volume,
+        STORAGE_DEVICE_SEEK_PENALTY_PROPERTY,
+    )?;
+    // Not reported by all devices
+    let usb = query_storage_property::<StorageAdapterDescriptor>(&volume, STORAGE_ADAPTER_PROPERTY)
+        .map(|adapter| adapter.bus_type == BUS_TYPE_USB)
+        .unwrap_or_default();
+
+    Ok(DeviceInfo {
+        rotational: Some(seek_penalty.incurs_seek_penalty != 0),
+        queue_depth: None,
+        usb,
+    })
+}
+
+/// Open volume that contains the path for querying its properties (without read or write access)
+fn open_volume(path: &Path) -> io::Result<File> {
+    let path = path
+        .as_os_str()
+        .encode_wide()
+        .chain([0])
+        .collect::<Vec<u16>>();
+    let mut volume_path = vec![0u16; MAX_VOLUME_PATH_LENGTH];
+    // SAFETY: Path is null-terminated, buffer length is passed correctly
+    if unsafe {
+        GetVolumePathNameW(
+            path.as_ptr(),
+            volume_path.as_mut_ptr(),
+            volume_path.len() as u32,
+        )
+    } == 0
+    {
+        return Err(io::Error::last_os_error());
+    }
+    let mut volume_name = vec![0u16; MAX_VOLUME_PATH_LENGTH];
+    // SAFETY: Volume path is null-terminated, buffer length is passed correctly
+    if unsafe {
+        GetVolumeNameForVolumeMountPointW(
+            volume_path.as_ptr(),
+            volume_name.as_mut_ptr(),
+            volume_name.len() as u32,
+        )
+    } == 0
+    {
+        return Err(io::Error::last_os_error());
+    }
+
+    // Volume name looks like `\\?\Volume{GUID}\`, volume is opened without trailing slash
+    let length = volume_name
+        .iter()
+        .position(|&c| c == 0)
+        .unwrap_or(volume_name.len());
+    let volume_name = OsString::from_wide(
+        volume_name[..length]
+            .strip_suffix(&[u16::from(b'\\')])
+            .unwrap_or(&volume_name[..length]),
+    );
+
+    OpenOptions::new()
+        .access_mode(0)
+        .share_mode(FILE_SHARE_READ | FILE_SHARE_WRITE)
+        .open(volume_name)
+}
+
+/// Query storage property `property_id` of the volume with `IOCTL_STORAGE_QUERY_PROPERTY`, `T`
+/// must match the layout of the descriptor returned for the property
+fn query_storage_property<T>(volume: &File, property_id: u32) -> io::Result<T>
+where
+    T: Default,
+{
+    let mut query = StoragePropertyQuery {
+        property_id,
+        // `PropertyStandardQuery`
+        query_type: 0,
+        additional_parameters: [0],
+    };
+    let mut descriptor = T::default();
+    let mut bytes_returned = 0;
+    // SAFETY: Handle is valid while volume is alive, provided buffers match layouts and sizes of
+    // `STORAGE_PROPERTY_QUERY` and requested descriptor
+    let result = unsafe {
+        DeviceIoControl(
+            volume.as_raw_handle() as HANDLE,
+            IOCTL_STORAGE_QUERY_PROPERTY,
+            (&mut query as *mut StoragePropertyQuery).cast(),
+            mem::size_of::<StoragePropertyQuery>() as u32,
+            (&mut descriptor as *mut T).cast(),
+            mem::size_of::<T>() as u32,
+            &mut bytes_returned,
+            ptr::null_mut(),
+        )
+    };
+    if result == 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(descriptor)
+}
@@ -0,0 +1,179 @@
+//! Version headers of the farm stored at fixed offsets of metadata and plot files and checked on
+//! open before anything else is read from or written to farm files, such that farm created by
+//! incompatible farmer is rejected with a clear error instead of failing somewhere deep inside
+//! decoding or being corrupted, see [`check_version()`].
+//!
+//! Metadata file has its header in the last disk sector of reserved space, while every plot file
+//! (each segment of the plot) starts with a header that occupies one disk sector, such that sectors
+//! stay aligned to disk sectors (see [`plot_data_offset()`]).
+//!
+//! Farms created before version headers were introduced have zeroes in place of metadata file
+//! header and no header in plot files. Such farm is [`LEGACY_FARM_VERSION`] if it has plotted
+//! sectors (which can't be moved to make space for plot file headers) and [`FARM_VERSION`]
+//! otherwise, see [`resolve_farm_version()`]. Headers are written into such farms on the next open
+//! that is not read-only, the same happens for farms creation of which was interrupted (creation is
+//! resumed on the next open and headers are written as the last step of it).
+
+#[cfg(test)]
+mod tests;
+
+use crate::single_disk_farm::direct_io_file::DISK_SECTOR_SIZE;
+use crate::single_disk_farm::RESERVED_PLOT_METADATA;
+use std::io;
+use subspace_core_primitives::SectorIndex;
+use subspace_farmer_components::file_ext::FileExt;
+use thiserror::Error;
+
+/// Version of the farm created before version headers were introduced, plot files of such farm
+/// don't have headers
+pub(super) const LEGACY_FARM_VERSION: u32 = 0;
+/// Version of the farm created by this farmer, versions up to this one are supported
+pub(super) const FARM_VERSION: u32 = 1;
+/// Offset of version header in metadata file: the last disk sector of reserved space, such that it
+/// is written and read with a single aligned request
+pub(super) const METADATA_VERSION_HEADER_OFFSET: u64 =
+    RESERVED_PLOT_METADATA - DISK_SECTOR_SIZE as u64;
+/// Size of version header at the beginning of every plot file, plotted sectors follow it
+pub(super) const PLOT_VERSION_HEADER_SIZE: u64 = DISK_SECTOR_SIZE as u64;
+
+/// Farm file with version header
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(super) enum VersionedFile {
+    /// Metadata file
+    Metadata,
+    /// Plot file (any segment of it)
+    Plot,
+}
+
+impl VersionedFile {
+    /// Magic bytes at the beginning of version header, different for different files, such that
+    /// one file can't be mistaken for another
+    fn magic(self) -> [u8; 8] {
+        match self {
+            Self::Metadata => *b"SUBSPFRM",
+            Self::Plot => *b"SUBSPPLT",
+        }
+    }
+
+    /// Offset of version header in the file
+    pub(super) fn header_offset(self) -> u64 {
+        match self {
+            Self::Metadata => METADATA_VERSION_HEADER_OFFSET,
+            Self::Plot => 0,
+        }
+    }
+}
+
+/// Errors happening when checking farm version
+#[derive(Debug, Error)]
+pub(super) enum FarmVersionError {
+    /// Header doesn't start with magic bytes, file was not created by the farmer or is corrupted
+    #[error("Unexpected farm version header magic {}", hex::encode(magic))]
+    UnexpectedMagic {
+        /// Bytes found in place of magic
+        magic: [u8; 8],
+    },
+    /// Farm version is not supported by this farmer
+    #[error("Farm version {found} is not supported, supported versions are up to {supported}")]
+    IncompatibleVersion {
+        /// Version found in the header
+        found: u32,
+        /// The latest version supported by this farmer
+        supported: u32,
+    },
+    /// I/O error occurred
+    #[error("Farm version header I/O error: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// Encode header of `file` with `version`, padded to a whole disk sector
+fn encode_header(file: VersionedFile, version: u32) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(DISK_SECTOR_SIZE);
+    bytes.extend_from_slice(&file.magic());
+    bytes.extend_from_slice(&version.to_le_bytes());
+    bytes.resize(DISK_SECTOR_SIZE, 0);
+    bytes
+}
+
+/// Read version header of `file`, returns `None` if file has no header (farm was created before
+/// headers were introduced or creation of the farm was interrupted)
+pub(super) fn read_version<F>(
+    file: &F,
+    kind: VersionedFile,
+) -> Result<Option<u32>, FarmVersionError>
+where
+    F: FileExt,
+{
+    let header_offset = kind.header_offset();
+    if file.size()? < header_offset + DISK_SECTOR_SIZE as u64 {
+        return Ok(None);
+    }
+
+    let mut header = vec![0; DISK_SECTOR_SIZE];
+    file.read_exact_at(&mut header, header_offset)?;
+    if header.iter().all(|&byte| byte == 0) {
+        return Ok(None);
+    }
+
+    let (magic, header) = header
+        .split_first_chunk::<8>()
+        .expect("Header is larger than magic; qed");
+    if *magic != kind.magic() {
+        return Err(FarmVersionError::UnexpectedMagic { magic: *magic });
+    }
+    let (version, _padding) = header
+        .split_first_chunk::<4>()
+        .expect("Header is larger than magic and version; qed");
+
+    Ok(Some(u32::from_le_bytes(*version)))
+}
+
+/// Check that `file` belongs to farm compatible with this farmer, returns version from the header
+/// or `None` if file has no header (which is compatible, see module documentation)
+pub(super) fn check_version<F>(
+    file: &F,
+    kind: VersionedFile,
+) -> Result<Option<u32>, FarmVersionError>
+where
+    F: FileExt,
+{
+    match read_version(file, kind)? {
+        Some(found) if found > FARM_VERSION => Err(FarmVersionError::IncompatibleVersion {
+            found,
+            supported: FARM_VERSION,
+        }),
+        maybe_version => Ok(maybe_version),
+    }
+}
+
+/// Write header of `file` with `version` and sync it
+pub(super) fn write_version<F>(file: &F, kind: VersionedFile, version: u32) -> io::Result<()>
+where
+    F: FileExt,
+{
+    file.write_all_at(&encode_header(kind, version), kind.header_offset())?;
+    file.sync_data()
+}
+
+/// Version of the farm whose metadata file has `metadata_version` header (`None` if it doesn't
+/// have one) and `plotted_sector_count` plotted sectors
+pub(super) fn resolve_farm_version(
+    metadata_version: Option<u32>,
+    plotted_sector_count: SectorIndex,
+) -> u32 {
+    match metadata_version {
+        Some(version) => version,
+        // Nothing is plotted yet, so plot files can start with headers
+        None if plotted_sector_count == 0 => FARM_VERSION,
+        None => LEGACY_FARM_VERSION,
+    }
+}
+
+/// Offset of plotted sectors in every plot file of the farm with `farm_version`
+pub(super) fn plot_data_offset(farm_version: u32) -> u64 {
+    if farm_version == LEGACY_FARM_VERSION {
+        0
+    } else {
+        PLOT_VERSION_HEADER_SIZE
+    }
+}
@@ -0,0 +1,170 @@
+use crate::single_disk_farm::direct_io_file::{DirectIoFile, DISK_SECTOR_SIZE};
+use crate::single_disk_farm::farm_version::{
+    check_version, encode_header, plot_data_offset, read_version, resolve_farm_version,
+    write_version, FarmVersionError, VersionedFile, FARM_VERSION, LEGACY_FARM_VERSION,
+    METADATA_VERSION_HEADER_OFFSET, PLOT_VERSION_HEADER_SIZE,
+};
+use crate::single_disk_farm::RESERVED_PLOT_METADATA;
+use std::assert_matches::assert_matches;
+use std::fs::File;
+use std::path::Path;
+use subspace_farmer_components::file_ext::FileExt;
+use tempfile::tempdir;
+
+/// Create metadata file with reserved space only, header is not written
+fn create_metadata_file(path: &Path) -> DirectIoFile {
+    File::create_new(path)
+        .unwrap()
+        .set_len(RESERVED_PLOT_METADATA)
+        .unwrap();
+
+    DirectIoFile::open(path).unwrap()
+}
+
+#[test]
+fn farm_version_roundtrip() {
+    let tempdir = tempdir().unwrap();
+    let metadata_file = create_metadata_file(&tempdir.path().join("metadata.bin"));
+    let plot_file = DirectIoFile::open(&tempdir.path().join("plot.bin")).unwrap();
+
+    for (file, kind) in [
+        (&metadata_file, VersionedFile::Metadata),
+        (&plot_file, VersionedFile::Plot),
+    ] {
+        write_version(file, kind, FARM_VERSION).unwrap();
+        assert_eq!(read_version(file, kind).unwrap(), Some(FARM_VERSION));
+        assert_eq!(check_version(file, kind).unwrap(), Some(FARM_VERSION));
+    }
+
+    // Plot file header occupies exactly the space before plotted sectors
+    assert_eq!(plot_file.size().unwrap(), PLOT_VERSION_HEADER_SIZE);
+    assert_eq!(metadata_file.size().unwrap(), RESERVED_PLOT_METADATA);
+}
+
+#[test]
+fn missing_header() {
+    let tempdir = tempdir().unwrap();
+
+    // Farm created before version header was introduced is compatible
+    let metadata_file = create_metadata_file(&tempdir.path().join("metadata.bin"));
+    assert_eq!(
+        read_version(&metadata_file, VersionedFile::Metadata).unwrap(),
+        None
+    );
+    assert_eq!(
+        check_version(&metadata_file, VersionedFile::Metadata).unwrap(),
+        None
+    );
+
+    // File that is too small to contain the header doesn't have it either
+    metadata_file
+        .set_len(METADATA_VERSION_HEADER_OFFSET)
+        .unwrap();
+    assert_eq!(
+        check_version(&metadata_file, VersionedFile::Metadata).unwrap(),
+        None
+    );
+    metadata_file.set_len(0).unwrap();
+    assert_eq!(
+        check_version(&metadata_file, VersionedFile::Metadata).unwrap(),
+        None
+    );
+
+    let plot_file = DirectIoFile::open(&tempdir.path().join("plot.bin")).unwrap();
+    assert_eq!(
+        check_version(&plot_file, VersionedFile::Plot).unwrap(),
+        None
+    );
+
+    // Farm without header keeps sectors at the beginning of plot files once something is plotted
+    assert_eq!(resolve_farm_version(None, 1), LEGACY_FARM_VERSION);
+    assert_eq!(plot_data_offset(LEGACY_FARM_VERSION), 0);
+    assert_eq!(resolve_farm_version(None, 0), FARM_VERSION);
+    assert_eq!(plot_data_offset(FARM_VERSION), PLOT_VERSION_HEADER_SIZE);
+    assert_eq!(
+        resolve_farm_version(Some(LEGACY_FARM_VERSION), 0),
+        LEGACY_FARM_VERSION
+    );
+    assert_eq!(resolve_farm_version(Some(FARM_VERSION), 1), FARM_VERSION);
+}
+
+#[test]
+fn wrong_magic() {
+    let tempdir = tempdir().unwrap();
+    let metadata_file = create_metadata_file(&tempdir.path().join("metadata.bin"));
+
+    let mut header = encode_header(VersionedFile::Metadata, FARM_VERSION);
+    header[0] ^= 0xff;
+    metadata_file
+        .write_all_at(&header, METADATA_VERSION_HEADER_OFFSET)
+        .unwrap();
+    assert_matches!(
+        check_version(&metadata_file, VersionedFile::Metadata),
+        Err(FarmVersionError::UnexpectedMagic { magic }) if magic.as_slice() == &header[..8]
+    );
+
+    // Anything other than zeroes in place of the header is not recognized
+    let mut header = vec![0; DISK_SECTOR_SIZE];
+    header[DISK_SECTOR_SIZE - 1] = 1;
+    metadata_file
+        .write_all_at(&header, METADATA_VERSION_HEADER_OFFSET)
+        .unwrap();
+    assert_matches!(
+        check_version(&metadata_file, VersionedFile::Metadata),
+        Err(FarmVersionError::UnexpectedMagic { magic: [0; 8] })
+    );
+
+    // Header of one file is not recognized in another file
+    let plot_file = DirectIoFile::open(&tempdir.path().join("plot.bin")).unwrap();
+    plot_file
+        .write_all_at(&encode_header(VersionedFile::Metadata, FARM_VERSION), 0)
+        .unwrap();
+    assert_matches!(
+        check_version(&plot_file, VersionedFile::Plot),
+        Err(FarmVersionError::UnexpectedMagic { .. })
+    );
+
+    // Headers can be replaced, such that farm is recognized again
+    write_version(&metadata_file, VersionedFile::Metadata, FARM_VERSION).unwrap();
+    assert_eq!(
+        check_version(&metadata_file, VersionedFile::Metadata).unwrap(),
+        Some(FARM_VERSION)
+    );
+    write_version(&plot_file, VersionedFile::Plot, FARM_VERSION).unwrap();
+    assert_eq!(
+        check_version(&plot_file, VersionedFile::Plot).unwrap(),
+        Some(FARM_VERSION)
+    );
+}
+
+#[test]
+fn incompatible_version() {
+    let tempdir = tempdir().unwrap();
+    let metadata_file = create_metadata_file(&tempdir.path().join("metadata.bin"));
+    let plot_file = DirectIoFile::open(&tempdir.path().join("plot.bin")).unwrap();
+
+    for (file, kind) in [
+        (&metadata_file, VersionedFile::Metadata),
+        (&plot_file, VersionedFile::Plot),
+    ] {
+        for version in [FARM_VERSION + 1, u32::MAX] {
+            file.write_all_at(&encode_header(kind, version), kind.header_offset())
+                .unwrap();
+            assert_eq!(read_version(file, kind).unwrap(), Some(version));
+            assert_matches!(
+                check_version(file, kind),
+                Err(FarmVersionError::IncompatibleVersion {
+                    found,
+                    supported: FARM_VERSION,
+                }) if found == version
+            );
+        }
+
+        // Older versions are supported
+        write_version(file, kind, LEGACY_FARM_VERSION).unwrap();
+        assert_eq!(
+            check_version(file, kind).unwrap(),
+            Some(LEGACY_FARM_VERSION)
+        );
+    }
+}
@@ -4,12 +4,16 @@ use crate::farm::{
     AuditingDetails, FarmingError, FarmingNotification, ProvingDetails, ProvingResult,
 };
 use crate::node_client::NodeClient;
-use crate::single_disk_farm::Handlers;
+use crate::single_disk_farm::direct_io_file::MAX_SMALL_READ_SIZE;
+use crate::single_disk_farm::sector_reader::SectorReader;
+use crate::single_disk_farm::{Handlers, PlotFile};
 use async_lock::{Mutex as AsyncMutex, RwLock as AsyncRwLock};
 use futures::channel::mpsc;
 use futures::StreamExt;
 use parking_lot::Mutex;
 use rayon::ThreadPool;
+use std::io;
+use std::mem::MaybeUninit;
 use std::sync::Arc;
 use std::time::Instant;
 use subspace_core_primitives::crypto::kzg::Kzg;
@@ -17,6 +21,7 @@ use subspace_core_primitives::{PosSeed, PublicKey, SectorIndex, Solution, Soluti
 use subspace_erasure_coding::ErasureCoding;
 use subspace_farmer_components::auditing::{audit_plot_sync, AuditingError};
 use subspace_farmer_components::proving::{ProvableSolutions, ProvingError};
+use subspace_farmer_components::read_plan::ReadAlignment;
 use subspace_farmer_components::reading::ReadSectorRecordChunksMode;
 use subspace_farmer_components::sector::SectorMetadataChecksummed;
 use subspace_farmer_components::ReadAtSync;
@@ -56,6 +61,38 @@ where
     Err(FarmingError::SlotNotificationStreamEnded)
 }
 
+/// Plot file used for auditing and proving, reads of at most [`MAX_SMALL_READ_SIZE`] bytes (like
+/// s-buckets of small sectors and record chunks) are done without locking, see
+/// [`DirectIoFile::read_small_at()`](super::DirectIoFile::read_small_at)
+#[derive(Debug)]
+pub(crate) struct FarmingPlotFile(pub(crate) PlotFile);
+
+impl ReadAtSync for FarmingPlotFile {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        if buf.len() <= MAX_SMALL_READ_SIZE {
+            self.0.read_small(buf, offset)
+        } else {
+            self.0.read_at(buf, offset)
+        }
+    }
+
+    fn read_at_uninit<'a>(
+        &self,
+        buf: &'a mut [MaybeUninit<u8>],
+        offset: u64,
+    ) -> io::Result<&'a mut [u8]> {
+        if buf.len() <= MAX_SMALL_READ_SIZE {
+            self.0.read_small_uninit(buf, offset)
+        } else {
+            self.0.read_at_uninit(buf, offset)
+        }
+    }
+
+    fn read_alignment(&self) -> Option<ReadAlignment> {
+        self.0.read_alignment()
+    }
+}
+
 /// Plot audit options
 #[derive(Debug)]
 pub struct PlotAuditOptions<'a, PosTable>
@@ -68,6 +105,8 @@ where
     pub reward_address: &'a PublicKey,
     /// Slot info for the audit
     pub slot_info: SlotInfo,
+    /// Number of sectors the plot has space for, sectors with larger index are not audited
+    pub sector_count: SectorIndex,
     /// Metadata of all sectors plotted so far
     pub sectors_metadata: &'a [SectorMetadataChecksummed],
     /// Kzg instance
@@ -121,68 +160,88 @@ where
     where
         PosTable: Table,
     {
-        let PlotAuditOptions {
-            public_key,
-            reward_address,
-            slot_info,
-            sectors_metadata,
-            kzg,
-            erasure_coding,
-            maybe_sector_being_modified,
-            read_sector_record_chunks_mode: mode,
-            table_generator,
-        } = options;
-
-        let audit_results = audit_plot_sync(
-            public_key,
-            &slot_info.global_challenge,
-            slot_info.voting_solution_range,
-            &self.0,
-            sectors_metadata,
-            maybe_sector_being_modified,
-        )?;
-
-        Ok(audit_results
-            .into_iter()
-            .filter_map(|audit_results| {
-                let sector_index = audit_results.sector_index;
-
-                let sector_solutions = audit_results.solution_candidates.into_solutions(
-                    reward_address,
-                    kzg,
-                    erasure_coding,
-                    mode,
-                    |seed: &PosSeed| table_generator.lock().generate_parallel(seed),
-                );
-
-                let sector_solutions = match sector_solutions {
-                    Ok(solutions) => solutions,
-                    Err(error) => {
-                        warn!(
-                            %error,
-                            %sector_index,
-                            "Failed to turn solution candidates into solutions",
-                        );
+        audit_plot(&self.0, options)
+    }
+}
 
-                        return None;
-                    }
-                };
+/// Same as [`PlotAudit::audit()`], but for borrowed plot
+pub(super) fn audit_plot<'a, PosTable, Plot>(
+    plot: &'a Plot,
+    options: PlotAuditOptions<'a, PosTable>,
+) -> Result<
+    Vec<(
+        SectorIndex,
+        impl ProvableSolutions<Item = Result<Solution<PublicKey, PublicKey>, ProvingError>> + 'a,
+    )>,
+    AuditingError,
+>
+where
+    PosTable: Table,
+    Plot: ReadAtSync + 'a,
+{
+    let PlotAuditOptions {
+        public_key,
+        reward_address,
+        slot_info,
+        sector_count,
+        sectors_metadata,
+        kzg,
+        erasure_coding,
+        maybe_sector_being_modified,
+        read_sector_record_chunks_mode: mode,
+        table_generator,
+    } = options;
+
+    let audit_results = audit_plot_sync(
+        public_key,
+        &slot_info.global_challenge,
+        slot_info.voting_solution_range,
+        plot,
+        sector_count,
+        sectors_metadata,
+        maybe_sector_being_modified,
+    )?;
+
+    Ok(audit_results
+        .into_iter()
+        .filter_map(|audit_results| {
+            let sector_index = audit_results.sector_index;
+
+            let sector_solutions = audit_results.solution_candidates.into_solutions(
+                reward_address,
+                kzg,
+                erasure_coding,
+                mode,
+                |seed: &PosSeed| table_generator.lock().generate_parallel(seed),
+            );
+
+            let sector_solutions = match sector_solutions {
+                Ok(solutions) => solutions,
+                Err(error) => {
+                    warn!(
+                        %error,
+                        %sector_index,
+                        "Failed to turn solution candidates into solutions",
+                    );
 
-                if sector_solutions.len() == 0 {
                     return None;
                 }
+            };
 
-                Some((sector_index, sector_solutions))
-            })
-            .collect())
-    }
+            if sector_solutions.len() == 0 {
+                return None;
+            }
+
+            Some((sector_index, sector_solutions))
+        })
+        .collect())
 }
 
-pub(super) struct FarmingOptions<NC, PlotAudit> {
+pub(super) struct FarmingOptions<NC, PF, MF> {
     pub(super) public_key: PublicKey,
     pub(super) reward_address: PublicKey,
     pub(super) node_client: NC,
-    pub(super) plot_audit: PlotAudit,
+    pub(super) sector_reader: SectorReader<PF, MF>,
     pub(super) sectors_metadata: Arc<AsyncRwLock<Vec<SectorMetadataChecksummed>>>,
     pub(super) kzg: Kzg,
     pub(super) erasure_coding: ErasureCoding,
@@ -198,19 +257,20 @@ pub(super) struct FarmingOptions<NC, PlotAudit> {
 ///
 /// NOTE: Returned future is async, but does blocking operations and should be running in dedicated
 /// thread.
-pub(super) async fn farming<'a, PosTable, NC, Plot>(
-    farming_options: FarmingOptions<NC, PlotAudit<Plot>>,
+pub(super) async fn farming<PosTable, NC, PF, MF>(
+    farming_options: FarmingOptions<NC, PF, MF>,
 ) -> Result<(), FarmingError>
 where
     PosTable: Table,
     NC: NodeClient,
-    Plot: ReadAtSync + 'a,
+    PF: ReadAtSync,
+    MF: Sync,
 {
     let FarmingOptions {
         public_key,
         reward_address,
         node_client,
-        plot_audit,
+        sector_reader,
         sectors_metadata,
         kzg,
         erasure_coding,
@@ -254,17 +314,21 @@ where
                 thread_pool.install(|| {
                     let _span_guard = span.enter();
 
-                    plot_audit.audit(PlotAuditOptions::<PosTable> {
-                        public_key: &public_key,
-                        reward_address: &reward_address,
-                        slot_info,
-                        sectors_metadata: &sectors_metadata,
-                        kzg: &kzg,
-                        erasure_coding: &erasure_coding,
-                        maybe_sector_being_modified,
-                        read_sector_record_chunks_mode,
-                        table_generator: &table_generator,
-                    })
+                    audit_plot(
+                        sector_reader.plot_file(),
+                        PlotAuditOptions::<PosTable> {
+                            public_key: &public_key,
+                            reward_address: &reward_address,
+                            slot_info,
+                            sector_count: sector_reader.sector_count(),
+                            sectors_metadata: &sectors_metadata,
+                            kzg: &kzg,
+                            erasure_coding: &erasure_coding,
+                            maybe_sector_being_modified,
+                            read_sector_record_chunks_mode,
+                            table_generator: &table_generator,
+                        },
+                    )
                 })?
             };
 
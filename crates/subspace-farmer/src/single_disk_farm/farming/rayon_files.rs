@@ -1,7 +1,9 @@
 use std::fs::{File, OpenOptions};
 use std::io;
+use std::mem::MaybeUninit;
 use std::path::Path;
 use subspace_farmer_components::file_ext::{FileExt, OpenOptionsExt};
+use subspace_farmer_components::read_plan::ReadAlignment;
 use subspace_farmer_components::ReadAtSync;
 
 /// Wrapper data structure for multiple files to be used with [`rayon`] thread pool, where the same
@@ -15,12 +17,30 @@ where
     File: ReadAtSync,
 {
     fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
-        let thread_index = rayon::current_thread_index().unwrap_or_default();
-        let file = self.files.get(thread_index).ok_or_else(|| {
-            io::Error::new(io::ErrorKind::Other, "No files entry for this rayon thread")
-        })?;
+        self.current_thread_file()?.read_at(buf, offset)
+    }
+
+    fn read_at_uninit<'a>(
+        &self,
+        buf: &'a mut [MaybeUninit<u8>],
+        offset: u64,
+    ) -> io::Result<&'a mut [u8]> {
+        self.current_thread_file()?.read_at_uninit(buf, offset)
+    }
 
-        file.read_at(buf, offset)
+    fn read_regions(
+        &self,
+        base_offset: u64,
+        regions: &[(u32, u32)],
+        out: &mut [u8],
+    ) -> io::Result<()> {
+        self.current_thread_file()?
+            .read_regions(base_offset, regions, out)
+    }
+
+    /// All files are the same file, so alignment of any of them applies
+    fn read_alignment(&self) -> Option<ReadAlignment> {
+        self.files.first()?.read_alignment()
     }
 }
 
@@ -31,6 +51,36 @@ where
     fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
         (*self).read_at(buf, offset)
     }
+
+    fn read_at_uninit<'a>(
+        &self,
+        buf: &'a mut [MaybeUninit<u8>],
+        offset: u64,
+    ) -> io::Result<&'a mut [u8]> {
+        (*self).read_at_uninit(buf, offset)
+    }
+
+    fn read_regions(
+        &self,
+        base_offset: u64,
+        regions: &[(u32, u32)],
+        out: &mut [u8],
+    ) -> io::Result<()> {
+        (*self).read_regions(base_offset, regions, out)
+    }
+
+    fn read_alignment(&self) -> Option<ReadAlignment> {
+        (*self).read_alignment()
+    }
+}
+
+impl<File> RayonFiles<File> {
+    fn current_thread_file(&self) -> io::Result<&File> {
+        let thread_index = rayon::current_thread_index().unwrap_or_default();
+        self.files.get(thread_index).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::Other, "No files entry for this rayon thread")
+        })
+    }
 }
 
 impl RayonFiles<File> {
@@ -59,7 +109,10 @@ where
 {
     /// Open file at specified path as many times as there is number of threads in current [`rayon`]
     /// thread pool with a provided function
-    pub fn open_with(path: &Path, open: fn(&Path) -> io::Result<File>) -> io::Result<Self> {
+    pub fn open_with<Open>(path: &Path, open: Open) -> io::Result<Self>
+    where
+        Open: Fn(&Path) -> io::Result<File>,
+    {
         let files = (0..rayon::current_num_threads())
             .map(|_| open(path))
             .collect::<Result<Vec<_>, _>>()?;
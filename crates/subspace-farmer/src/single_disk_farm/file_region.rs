@@ -0,0 +1,253 @@
+//! View over a region of a file, such that subsystems can work with offsets relative to their own
+//! region instead of doing base offset math at every call site
+
+#[cfg(test)]
+mod tests;
+
+use std::io;
+use std::mem::MaybeUninit;
+use std::ops::Range;
+use subspace_farmer_components::file_ext::{AccessPattern, FileExt, IoGeometry};
+use subspace_farmer_components::read_plan::ReadAlignment;
+use subspace_farmer_components::{region_buffers, ReadAtSync, WriteAtSync};
+
+/// View over a region of a file that translates offsets relative to the start of the region into
+/// file offsets and rejects access outside of the region with [`io::ErrorKind::UnexpectedEof`]
+#[derive(Debug)]
+pub struct FileRegion<'a, F> {
+    file: &'a F,
+    start: u64,
+    len: u64,
+}
+
+impl<F> Clone for FileRegion<'_, F> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<F> Copy for FileRegion<'_, F> {}
+
+impl<F> ReadAtSync for FileRegion<'_, F>
+where
+    F: ReadAtSync,
+{
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        self.file
+            .read_at(buf, self.file_offset(offset, buf.len() as u64)?)
+    }
+
+    fn read_at_uninit<'a>(
+        &self,
+        buf: &'a mut [MaybeUninit<u8>],
+        offset: u64,
+    ) -> io::Result<&'a mut [u8]> {
+        self.file
+            .read_at_uninit(buf, self.file_offset(offset, buf.len() as u64)?)
+    }
+
+    fn read_regions(
+        &self,
+        base_offset: u64,
+        regions: &[(u32, u32)],
+        out: &mut [u8],
+    ) -> io::Result<()> {
+        let end = regions
+            .iter()
+            .map(|&(offset, len)| u64::from(offset) + u64::from(len))
+            .max()
+            .unwrap_or_default();
+        // Validate output buffer length before reading anything
+        region_buffers(regions, out)?;
+
+        self.file
+            .read_regions(self.file_offset(base_offset, end)?, regions, out)
+    }
+
+    /// Alignment of the file, such that sector boundaries are relative to the start of the file
+    /// rather than the region
+    fn read_alignment(&self) -> Option<ReadAlignment> {
+        self.file
+            .read_alignment()
+            .map(|alignment| alignment.with_offset(self.start))
+    }
+}
+
+impl<F> ReadAtSync for &FileRegion<'_, F>
+where
+    F: ReadAtSync,
+{
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        (**self).read_at(buf, offset)
+    }
+
+    fn read_at_uninit<'a>(
+        &self,
+        buf: &'a mut [MaybeUninit<u8>],
+        offset: u64,
+    ) -> io::Result<&'a mut [u8]> {
+        (**self).read_at_uninit(buf, offset)
+    }
+
+    fn read_regions(
+        &self,
+        base_offset: u64,
+        regions: &[(u32, u32)],
+        out: &mut [u8],
+    ) -> io::Result<()> {
+        (**self).read_regions(base_offset, regions, out)
+    }
+
+    fn read_alignment(&self) -> Option<ReadAlignment> {
+        (**self).read_alignment()
+    }
+}
+
+impl<F> WriteAtSync for FileRegion<'_, F>
+where
+    F: WriteAtSync,
+{
+    fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        self.file
+            .write_all_at(buf, self.file_offset(offset, buf.len() as u64)?)
+    }
+
+    fn sync_data(&self) -> io::Result<()> {
+        self.file.sync_data()
+    }
+}
+
+impl<F> FileExt for FileRegion<'_, F>
+where
+    F: FileExt,
+{
+    /// Length of the region
+    fn size(&self) -> io::Result<u64> {
+        Ok(self.len)
+    }
+
+    /// Make sure file has first `len` bytes of the region allocated, fails if `len` exceeds
+    /// region length
+    fn preallocate(&self, len: u64) -> io::Result<()> {
+        self.file.preallocate(self.file_offset(0, len)? + len)
+    }
+
+    fn geometry(&self) -> IoGeometry {
+        self.file.geometry()
+    }
+
+    fn advise_random_access(&self) -> io::Result<()> {
+        self.file.advise_random_access()
+    }
+
+    fn advise_sequential_access(&self) -> io::Result<()> {
+        self.file.advise_sequential_access()
+    }
+
+    fn set_access_pattern(&self, access_pattern: AccessPattern) -> io::Result<()> {
+        self.file.set_access_pattern(access_pattern)
+    }
+
+    /// Locks the whole file
+    fn try_lock_exclusive(&self) -> io::Result<()> {
+        self.file.try_lock_exclusive()
+    }
+
+    /// Locks the whole file
+    fn lock_shared(&self) -> io::Result<()> {
+        self.file.lock_shared()
+    }
+
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        self.file
+            .read_exact_at(buf, self.file_offset(offset, buf.len() as u64)?)
+    }
+
+    fn read_exact_at_vectored(&self, requests: &mut [(u64, &mut [u8])]) -> io::Result<()> {
+        // Translate all offsets before reading anything, such that out of range request doesn't
+        // result in partial read
+        for (offset, buf) in requests.iter_mut() {
+            *offset = self.file_offset(*offset, buf.len() as u64)?;
+        }
+
+        self.file.read_exact_at_vectored(requests)
+    }
+
+    fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        self.file
+            .write_all_at(buf, self.file_offset(offset, buf.len() as u64)?)
+    }
+
+    fn punch_hole(&self, offset: u64, len: u64) -> io::Result<()> {
+        self.file.punch_hole(self.file_offset(offset, len)?, len)
+    }
+
+    fn sync_all(&self) -> io::Result<()> {
+        self.file.sync_all()
+    }
+
+    fn sync_data(&self) -> io::Result<()> {
+        self.file.sync_data()
+    }
+}
+
+impl<'a, F> FileRegion<'a, F> {
+    /// Create view over `len` bytes of `file` starting at `start`
+    pub fn new(file: &'a F, start: u64, len: u64) -> Self {
+        Self { file, start, len }
+    }
+
+    /// Offset of the region in the file
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    /// Length of the region
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Whether region is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Create view over `range` relative to the start of this region, fails if `range` is not
+    /// within this region
+    pub fn region(&self, range: Range<u64>) -> io::Result<Self> {
+        if range.start > range.end {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid range {range:?}"),
+            ));
+        }
+
+        Ok(Self {
+            file: self.file,
+            start: self.file_offset(range.start, range.end - range.start)?,
+            len: range.end - range.start,
+        })
+    }
+
+    /// Split region into two at `mid` bytes from the start of this region, fails if `mid` is
+    /// outside of this region
+    pub fn split_at(&self, mid: u64) -> io::Result<(Self, Self)> {
+        Ok((self.region(0..mid)?, self.region(mid..self.len)?))
+    }
+
+    /// Translate `offset` relative to the region into file offset, fails if `len` bytes at `offset`
+    /// do not fit into the region
+    fn file_offset(&self, offset: u64, len: u64) -> io::Result<u64> {
+        match offset.checked_add(len) {
+            Some(end) if end <= self.len => Ok(self.start + offset),
+            _ => Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!(
+                    "Access of {len} bytes at offset {offset} is outside of region of {} bytes at \
+                    offset {}",
+                    self.len, self.start
+                ),
+            )),
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use crate::single_disk_farm::direct_io_file::DirectIoFile;
+use crate::single_disk_farm::file_region::FileRegion;
+use rand::prelude::*;
+use std::{fs, io};
+use subspace_farmer_components::file_ext::FileExt;
+use subspace_farmer_components::ReadAtSync;
+use tempfile::tempdir;
+
+const FILE_SIZE: usize = 10_000;
+const REGION_START: u64 = 1_000;
+const REGION_LEN: u64 = 5_000;
+
+#[test]
+fn boundaries() {
+    let tempdir = tempdir().unwrap();
+    let file_path = tempdir.as_ref().join("file.bin");
+    let mut data = vec![0u8; FILE_SIZE];
+    thread_rng().fill(data.as_mut_slice());
+    fs::write(&file_path, &data).unwrap();
+
+    let file = DirectIoFile::open(&file_path).unwrap();
+    let region = FileRegion::new(&file, REGION_START, REGION_LEN);
+    let region_data = &data[REGION_START as usize..][..REGION_LEN as usize];
+    assert_eq!(region.size().unwrap(), REGION_LEN);
+
+    // First and last byte
+    let mut byte = [0u8; 1];
+    region.read_exact_at(&mut byte, 0).unwrap();
+    assert_eq!(byte[0], region_data[0]);
+    region.read_at(&mut byte, REGION_LEN - 1).unwrap();
+    assert_eq!(byte[0], region_data[REGION_LEN as usize - 1]);
+
+    // The whole region
+    let mut buffer = vec![0u8; REGION_LEN as usize];
+    region.read_exact_at(&mut buffer, 0).unwrap();
+    assert_eq!(buffer, region_data);
+
+    // Writes at the first and last byte end up at the right place in the file
+    region.write_all_at(&[1], 0).unwrap();
+    region.write_all_at(&[2], REGION_LEN - 1).unwrap();
+    data[REGION_START as usize] = 1;
+    data[(REGION_START + REGION_LEN) as usize - 1] = 2;
+    let mut file_contents = vec![0u8; FILE_SIZE];
+    file.read_exact_at(&mut file_contents, 0).unwrap();
+    assert_eq!(file_contents, data);
+
+    // Regions
+    let mut out = [0u8; 3];
+    region
+        .read_regions(REGION_LEN - 10, &[(0, 1), (8, 2)], &mut out)
+        .unwrap();
+    let region_data = &data[REGION_START as usize..][..REGION_LEN as usize];
+    assert_eq!(out[0], region_data[REGION_LEN as usize - 10]);
+    assert_eq!(out[1..], region_data[REGION_LEN as usize - 2..]);
+
+    // Vectored
+    let mut first = [0u8; 2];
+    let mut last = [0u8; 2];
+    region
+        .read_exact_at_vectored(&mut [(0, &mut first[..]), (REGION_LEN - 2, &mut last[..])])
+        .unwrap();
+    assert_eq!(first, region_data[..2]);
+    assert_eq!(last, region_data[REGION_LEN as usize - 2..]);
+}
+
+#[test]
+fn out_of_range() {
+    let tempdir = tempdir().unwrap();
+    let file_path = tempdir.as_ref().join("file.bin");
+    fs::write(&file_path, vec![0u8; FILE_SIZE]).unwrap();
+
+    let file = DirectIoFile::open(&file_path).unwrap();
+    let region = FileRegion::new(&file, REGION_START, REGION_LEN);
+
+    let mut buffer = [0u8; 2];
+    for offset in [REGION_LEN - 1, REGION_LEN, u64::MAX] {
+        assert_eq!(
+            region
+                .read_exact_at(&mut buffer, offset)
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+        assert_eq!(
+            region.read_at(&mut buffer, offset).unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+        assert_eq!(
+            region.write_all_at(&buffer, offset).unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+    }
+    assert_eq!(
+        region
+            .read_regions(REGION_LEN - 10, &[(0, 1), (9, 2)], &mut [0u8; 3])
+            .unwrap_err()
+            .kind(),
+        io::ErrorKind::UnexpectedEof
+    );
+    assert_eq!(
+        region
+            .read_exact_at_vectored(&mut [(0, &mut [0u8; 1][..]), (REGION_LEN, &mut [0u8; 1][..])])
+            .unwrap_err()
+            .kind(),
+        io::ErrorKind::UnexpectedEof
+    );
+    assert_eq!(
+        region.preallocate(REGION_LEN + 1).unwrap_err().kind(),
+        io::ErrorKind::UnexpectedEof
+    );
+
+    // Nothing was written outside of the region
+    assert_eq!(fs::read(&file_path).unwrap(), vec![0u8; FILE_SIZE]);
+}
+
+#[test]
+fn sub_regions() {
+    let tempdir = tempdir().unwrap();
+    let file_path = tempdir.as_ref().join("file.bin");
+    let mut data = vec![0u8; FILE_SIZE];
+    thread_rng().fill(data.as_mut_slice());
+    fs::write(&file_path, &data).unwrap();
+
+    let file = DirectIoFile::open(&file_path).unwrap();
+    let region = FileRegion::new(&file, REGION_START, REGION_LEN);
+
+    let sub_region = region.region(100..200).unwrap();
+    assert_eq!(sub_region.start(), REGION_START + 100);
+    assert_eq!(sub_region.len(), 100);
+    let mut byte = [0u8; 1];
+    sub_region.read_exact_at(&mut byte, 99).unwrap();
+    assert_eq!(byte[0], data[REGION_START as usize + 199]);
+    assert!(sub_region.read_exact_at(&mut byte, 100).is_err());
+
+    let (left, right) = region.split_at(REGION_LEN - 1).unwrap();
+    assert_eq!((left.start(), left.len()), (REGION_START, REGION_LEN - 1));
+    assert_eq!(
+        (right.start(), right.len()),
+        (REGION_START + REGION_LEN - 1, 1)
+    );
+    right.read_exact_at(&mut byte, 0).unwrap();
+    assert_eq!(byte[0], data[(REGION_START + REGION_LEN) as usize - 1]);
+
+    let (left, right) = region.split_at(REGION_LEN).unwrap();
+    assert_eq!(left.len(), REGION_LEN);
+    assert!(right.is_empty());
+
+    assert!(region.region(0..REGION_LEN + 1).is_err());
+    assert!(region.region(REGION_LEN + 1..REGION_LEN + 1).is_err());
+    #[allow(clippy::reversed_empty_ranges)]
+    let invalid_range = 200..100;
+    assert!(region.region(invalid_range).is_err());
+    assert!(region.split_at(REGION_LEN + 1).is_err());
+}
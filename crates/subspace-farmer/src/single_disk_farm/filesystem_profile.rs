@@ -0,0 +1,289 @@
+//! Detection of copy-on-write and compressed file systems plot file is stored on, such that farm
+//! can adapt to them where possible and warn about them otherwise (copy-on-write fragments plot
+//! file with every sector written and compression wastes CPU on incompressible plot), see
+//! [`FilesystemProfile`]
+
+#[cfg(target_os = "linux")]
+mod linux;
+#[cfg(test)]
+mod tests;
+#[cfg(windows)]
+mod windows;
+
+#[cfg(target_os = "linux")]
+use self::linux as backend;
+#[cfg(windows)]
+use self::windows as backend;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::{fmt, io};
+use tracing::{debug, info, warn};
+
+/// Plot file with more extents than this per GiB of its size is considered to be fragmented
+/// (preallocated plot file on a file system without copy-on-write typically has a few extents per
+/// GiB)
+pub const FRAGMENTED_EXTENTS_PER_GIB: u64 = 256;
+
+/// Attributes of the file and file system it is stored on reported by OS
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct FilesystemAttributes {
+    /// Name of the file system (like `btrfs` or `NTFS`), `None` if unknown
+    pub filesystem: Option<String>,
+    /// Whether writes to the file are copy-on-write
+    pub copy_on_write: bool,
+    /// Whether contents of the file are compressed
+    pub compressed: bool,
+    /// Number of extents the file consists of, `None` if unknown
+    pub extents: Option<u64>,
+}
+
+/// Detection and adjustment of file attributes, abstracted such that OS interaction can be mocked
+pub(super) trait FilesystemBackend {
+    /// Detect attributes of the file at `path` and the file system it is stored on
+    fn detect(&self, path: &Path) -> io::Result<FilesystemAttributes>;
+
+    /// Disable copy-on-write for the file at `path`, only effective for empty files
+    fn disable_copy_on_write(&self, path: &Path) -> io::Result<()>;
+
+    /// Disable compression of the file at `path`
+    fn disable_compression(&self, path: &Path) -> io::Result<()>;
+}
+
+/// [`FilesystemBackend`] of the current OS (Linux: `statfs`, `FS_IOC_GETFLAGS` and `FS_IOC_FIEMAP`,
+/// Windows: `FILE_ATTRIBUTE_COMPRESSED` and `FSCTL_GET_EXTERNAL_BACKING`), detection is not
+/// supported on other platforms
+#[derive(Debug, Default, Copy, Clone)]
+pub(super) struct OsFilesystem;
+
+#[cfg(any(target_os = "linux", windows))]
+impl FilesystemBackend for OsFilesystem {
+    fn detect(&self, path: &Path) -> io::Result<FilesystemAttributes> {
+        backend::detect(path)
+    }
+
+    fn disable_copy_on_write(&self, path: &Path) -> io::Result<()> {
+        backend::disable_copy_on_write(path)
+    }
+
+    fn disable_compression(&self, path: &Path) -> io::Result<()> {
+        backend::disable_compression(path)
+    }
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+impl FilesystemBackend for OsFilesystem {
+    fn detect(&self, _path: &Path) -> io::Result<FilesystemAttributes> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Detecting file system attributes is not supported on this platform",
+        ))
+    }
+
+    fn disable_copy_on_write(&self, _path: &Path) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Disabling copy-on-write is not supported on this platform",
+        ))
+    }
+
+    fn disable_compression(&self, _path: &Path) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Disabling compression is not supported on this platform",
+        ))
+    }
+}
+
+/// File system profile of the plot file stored in farm info, see
+/// [`SingleDiskFarmInfo::filesystem_profile()`](super::SingleDiskFarmInfo::filesystem_profile)
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilesystemProfile {
+    /// Name of the file system (like `btrfs` or `NTFS`), `None` if unknown
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filesystem: Option<String>,
+    /// Whether writes to the plot file are copy-on-write
+    pub copy_on_write: bool,
+    /// Whether contents of the plot file are compressed
+    pub compressed: bool,
+    /// Whether copy-on-write was disabled for the plot file by the farmer when it was created
+    #[serde(default)]
+    pub copy_on_write_disabled: bool,
+    /// Whether compression was disabled for the plot file by the farmer when it was created
+    #[serde(default)]
+    pub compression_disabled: bool,
+}
+
+/// Problem with the file system plot file is stored on that farmer was not able to adapt to
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum FilesystemWarning {
+    /// Writes to the plot file are copy-on-write
+    CopyOnWrite,
+    /// Contents of the plot file are compressed
+    Compressed,
+    /// Plot file is fragmented
+    Fragmented {
+        /// Number of extents the plot file consists of
+        extents: u64,
+        /// Size of the plot file in bytes
+        size: u64,
+    },
+}
+
+impl fmt::Display for FilesystemWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CopyOnWrite => write!(
+                f,
+                "Plot file is stored on copy-on-write file system, which fragments it with every \
+                sector written and amplifies writes"
+            ),
+            Self::Compressed => write!(
+                f,
+                "Plot file is compressed, plot is incompressible and compression only wastes CPU \
+                and slows down reads"
+            ),
+            Self::Fragmented { extents, size } => write!(
+                f,
+                "Plot file of {} is fragmented into {extents} extents, which slows down proving",
+                bytesize::to_string(*size, true)
+            ),
+        }
+    }
+}
+
+impl FilesystemWarning {
+    /// Advice how to get rid of the problem
+    pub fn remediation(&self) -> &'static str {
+        match self {
+            Self::CopyOnWrite => {
+                "Create farm on a file system without copy-on-write (like ext4 or XFS) or in a \
+                directory with copy-on-write disabled (like `chattr +C <directory>` on Btrfs) \
+                before farm files are created"
+            }
+            Self::Compressed => {
+                "Disable compression of the farm directory (like `compact /U /S:<directory>` on \
+                NTFS or `btrfs property set <directory> compression none` on Btrfs) and recreate \
+                the farm"
+            }
+            Self::Fragmented { .. } => {
+                "Recreate the farm on a file system without copy-on-write or copy plot file to a \
+                freshly preallocated file while farmer is stopped"
+            }
+        }
+    }
+}
+
+/// Result of [`check_plot_file()`]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(super) struct FilesystemCheck {
+    /// Profile to be stored in farm info
+    pub(super) profile: FilesystemProfile,
+    /// Problems farmer was not able to adapt to, all of them are logged already
+    pub(super) warnings: Vec<FilesystemWarning>,
+}
+
+/// Detect attributes of the plot file at `path` with `filesystem_backend`, disable copy-on-write
+/// and compression for the plot file if it was just created (`new_plot_file`) and warn about
+/// problems that remain.
+///
+/// Adjustments made previously are carried over from `previous_profile`. Check is best-effort,
+/// `None` is returned if detection is not possible, errors of adjustments are only logged.
+pub(super) fn check_plot_file<B>(
+    filesystem_backend: &B,
+    path: &Path,
+    plot_file_size: u64,
+    new_plot_file: bool,
+    previous_profile: Option<&FilesystemProfile>,
+) -> Option<FilesystemCheck>
+where
+    B: FilesystemBackend,
+{
+    let mut attributes = match filesystem_backend.detect(path) {
+        Ok(attributes) => attributes,
+        Err(error) => {
+            debug!(
+                path = %path.display(),
+                %error,
+                "Failed to detect file system attributes of plot file"
+            );
+
+            return None;
+        }
+    };
+    debug!(path = %path.display(), ?attributes, "Detected file system attributes of plot file");
+
+    let mut profile = FilesystemProfile {
+        filesystem: attributes.filesystem.clone(),
+        copy_on_write: attributes.copy_on_write,
+        compressed: attributes.compressed,
+        copy_on_write_disabled: previous_profile
+            .is_some_and(|profile| profile.copy_on_write_disabled),
+        compression_disabled: previous_profile.is_some_and(|profile| profile.compression_disabled),
+    };
+
+    if new_plot_file && attributes.copy_on_write {
+        match filesystem_backend.disable_copy_on_write(path) {
+            Ok(()) => {
+                info!(path = %path.display(), "Disabled copy-on-write for plot file");
+                profile.copy_on_write = false;
+                profile.copy_on_write_disabled = true;
+            }
+            Err(error) => {
+                debug!(
+                    path = %path.display(),
+                    %error,
+                    "Failed to disable copy-on-write for plot file"
+                );
+            }
+        }
+    }
+    if new_plot_file && attributes.compressed {
+        match filesystem_backend.disable_compression(path) {
+            Ok(()) => {
+                info!(path = %path.display(), "Disabled compression for plot file");
+                profile.compressed = false;
+                profile.compression_disabled = true;
+            }
+            Err(error) => {
+                debug!(
+                    path = %path.display(),
+                    %error,
+                    "Failed to disable compression for plot file"
+                );
+            }
+        }
+    }
+    // Extents of a new file are meaningless
+    if new_plot_file {
+        attributes.extents = None;
+    }
+
+    let mut warnings = Vec::new();
+    if profile.copy_on_write {
+        warnings.push(FilesystemWarning::CopyOnWrite);
+    }
+    if profile.compressed {
+        warnings.push(FilesystemWarning::Compressed);
+    }
+    if let Some(extents) = attributes.extents {
+        let gib = plot_file_size.div_ceil(1024 * 1024 * 1024).max(1);
+        if extents > FRAGMENTED_EXTENTS_PER_GIB * gib {
+            warnings.push(FilesystemWarning::Fragmented {
+                extents,
+                size: plot_file_size,
+            });
+        }
+    }
+
+    for warning in &warnings {
+        warn!(
+            path = %path.display(),
+            filesystem = profile.filesystem.as_deref().unwrap_or("unknown"),
+            remediation = warning.remediation(),
+            "{warning}"
+        );
+    }
+
+    Some(FilesystemCheck { profile, warnings })
+}
@@ -0,0 +1,145 @@
+//! Linux backend of [`OsFilesystem`](super::OsFilesystem)
+
+use crate::single_disk_farm::filesystem_profile::FilesystemAttributes;
+use std::ffi::CString;
+use std::fs::File;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+use std::{io, mem};
+
+/// `FS_IOC_GETFLAGS` ioctl (with `int` argument the kernel actually uses)
+const FS_IOC_GETFLAGS: libc::c_ulong = 0x8008_6601;
+/// `FS_IOC_SETFLAGS` ioctl (with `int` argument the kernel actually uses)
+const FS_IOC_SETFLAGS: libc::c_ulong = 0x4008_6602;
+/// `FS_IOC_FIEMAP` ioctl
+const FS_IOC_FIEMAP: libc::c_ulong = 0xC020_660B;
+/// `FS_COMPR_FL` inode flag, file is compressed
+const FS_COMPR_FL: libc::c_int = 0x0000_0004;
+/// `FS_NOCOMP_FL` inode flag, file must not be compressed
+const FS_NOCOMP_FL: libc::c_int = 0x0000_0400;
+/// `FS_NOCOW_FL` inode flag, file is not copy-on-write
+const FS_NOCOW_FL: libc::c_int = 0x0080_0000;
+
+/// File systems identified by `f_type` of `statfs`: magic, name and whether files are
+/// copy-on-write unless `FS_NOCOW_FL` is set
+const KNOWN_FILESYSTEMS: &[(u32, &str, bool)] = &[
+    (0x9123_683E, "btrfs", true),
+    (0xCA45_1A4E, "bcachefs", true),
+    (0x2FC1_2FC1, "zfs", true),
+    (0x0000_EF53, "ext4", false),
+    (0x5846_5342, "xfs", false),
+    (0xF2F5_2010, "f2fs", false),
+    (0x7366_746E, "ntfs3", false),
+    (0x0102_1994, "tmpfs", false),
+];
+
+/// `struct fiemap` without extents array, such that only the number of extents is returned
+#[derive(Debug, Default)]
+#[repr(C)]
+struct Fiemap {
+    start: u64,
+    length: u64,
+    flags: u32,
+    mapped_extents: u32,
+    extent_count: u32,
+    reserved: u32,
+}
+
+/// Detect file system with `statfs`, copy-on-write and compression of the file from its inode
+/// flags and number of its extents with `FS_IOC_FIEMAP`
+pub(super) fn detect(path: &Path) -> io::Result<FilesystemAttributes> {
+    let c_path = CString::new(path.as_os_str().as_bytes())?;
+    // SAFETY: `statfs` is a plain C struct for which all zeroes is a valid value
+    let mut statfs = unsafe { mem::zeroed::<libc::statfs>() };
+    // SAFETY: Path is null-terminated, `statfs` is a valid pointer
+    if unsafe { libc::statfs(c_path.as_ptr(), &mut statfs) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let known_filesystem = KNOWN_FILESYSTEMS
+        .iter()
+        .find(|(magic, _name, _copy_on_write)| *magic == statfs.f_type as u32);
+
+    let file = File::open(path)?;
+    // Not all file systems support inode flags
+    let flags = get_flags(&file).unwrap_or_default();
+    let copy_on_write = known_filesystem
+        .is_some_and(|(_magic, _name, copy_on_write)| *copy_on_write)
+        && flags & FS_NOCOW_FL == 0;
+
+    Ok(FilesystemAttributes {
+        filesystem: known_filesystem.map(|(_magic, name, _copy_on_write)| name.to_string()),
+        copy_on_write,
+        compressed: flags & FS_COMPR_FL != 0,
+        // Not all file systems support FIEMAP
+        extents: extents(&file).ok(),
+    })
+}
+
+/// Set `FS_NOCOW_FL` inode flag, which is only effective for empty files on Btrfs
+pub(super) fn disable_copy_on_write(path: &Path) -> io::Result<()> {
+    let file = File::open(path)?;
+    let flags = get_flags(&file)?;
+    set_flags(&file, flags | FS_NOCOW_FL)?;
+
+    // Some file systems silently ignore unsupported flags
+    if get_flags(&file)? & FS_NOCOW_FL == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "File system ignored request to disable copy-on-write",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Replace `FS_COMPR_FL` inode flag with `FS_NOCOMP_FL`
+pub(super) fn disable_compression(path: &Path) -> io::Result<()> {
+    let file = File::open(path)?;
+    let flags = get_flags(&file)?;
+    set_flags(&file, (flags & !FS_COMPR_FL) | FS_NOCOMP_FL)?;
+
+    if get_flags(&file)? & FS_COMPR_FL != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "File system ignored request to disable compression",
+        ));
+    }
+
+    Ok(())
+}
+
+fn get_flags(file: &File) -> io::Result<libc::c_int> {
+    let mut flags: libc::c_int = 0;
+    // SAFETY: File descriptor is valid, `flags` is a valid pointer to `int` argument of the ioctl
+    if unsafe { libc::ioctl(file.as_raw_fd(), FS_IOC_GETFLAGS as _, &mut flags) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(flags)
+}
+
+fn set_flags(file: &File, flags: libc::c_int) -> io::Result<()> {
+    // SAFETY: File descriptor is valid, `flags` is a valid pointer to `int` argument of the ioctl
+    if unsafe { libc::ioctl(file.as_raw_fd(), FS_IOC_SETFLAGS as _, &flags) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+/// Number of extents of the whole file, with zero `extent_count` kernel only counts extents
+/// without returning them
+fn extents(file: &File) -> io::Result<u64> {
+    let mut fiemap = Fiemap {
+        length: u64::MAX,
+        ..Fiemap::default()
+    };
+    // SAFETY: File descriptor is valid, `fiemap` is a valid pointer to `struct fiemap` with no
+    // space for extents, which matches `extent_count`
+    if unsafe { libc::ioctl(file.as_raw_fd(), FS_IOC_FIEMAP as _, &mut fiemap) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(u64::from(fiemap.mapped_extents))
+}
@@ -0,0 +1,236 @@
+use crate::single_disk_farm::filesystem_profile::{
+    check_plot_file, FilesystemAttributes, FilesystemBackend, FilesystemProfile, FilesystemWarning,
+    FRAGMENTED_EXTENTS_PER_GIB,
+};
+use parking_lot::Mutex;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const GIB: u64 = 1024 * 1024 * 1024;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+enum Call {
+    DisableCopyOnWrite(PathBuf),
+    DisableCompression(PathBuf),
+}
+
+/// Backend that reports `attributes` and records adjustments, which only succeed if supported
+#[derive(Debug, Default)]
+struct MockFilesystem {
+    /// `None` if detection fails
+    attributes: Option<FilesystemAttributes>,
+    copy_on_write_can_be_disabled: bool,
+    compression_can_be_disabled: bool,
+    calls: Mutex<Vec<Call>>,
+}
+
+impl FilesystemBackend for MockFilesystem {
+    fn detect(&self, _path: &Path) -> io::Result<FilesystemAttributes> {
+        self.attributes
+            .clone()
+            .ok_or_else(|| io::Error::from(io::ErrorKind::Unsupported))
+    }
+
+    fn disable_copy_on_write(&self, path: &Path) -> io::Result<()> {
+        self.calls
+            .lock()
+            .push(Call::DisableCopyOnWrite(path.to_path_buf()));
+        if self.copy_on_write_can_be_disabled {
+            Ok(())
+        } else {
+            Err(io::Error::from(io::ErrorKind::Unsupported))
+        }
+    }
+
+    fn disable_compression(&self, path: &Path) -> io::Result<()> {
+        self.calls
+            .lock()
+            .push(Call::DisableCompression(path.to_path_buf()));
+        if self.compression_can_be_disabled {
+            Ok(())
+        } else {
+            Err(io::Error::from(io::ErrorKind::Unsupported))
+        }
+    }
+}
+
+fn attributes(filesystem: &str, copy_on_write: bool, compressed: bool) -> FilesystemAttributes {
+    FilesystemAttributes {
+        filesystem: Some(filesystem.to_string()),
+        copy_on_write,
+        compressed,
+        extents: Some(1),
+    }
+}
+
+#[test]
+fn regular_filesystem() {
+    let path = Path::new("plot.bin");
+    let backend = MockFilesystem {
+        attributes: Some(attributes("ext4", false, false)),
+        ..MockFilesystem::default()
+    };
+
+    for new_plot_file in [true, false] {
+        let check = check_plot_file(&backend, path, GIB, new_plot_file, None).unwrap();
+        assert_eq!(
+            check.profile,
+            FilesystemProfile {
+                filesystem: Some("ext4".to_string()),
+                ..FilesystemProfile::default()
+            }
+        );
+        assert!(check.warnings.is_empty());
+    }
+    assert!(backend.calls.lock().is_empty());
+}
+
+#[test]
+fn copy_on_write_disabled_for_new_plot_file() {
+    let path = Path::new("plot.bin");
+    let backend = MockFilesystem {
+        attributes: Some(attributes("btrfs", true, false)),
+        copy_on_write_can_be_disabled: true,
+        ..MockFilesystem::default()
+    };
+
+    let check = check_plot_file(&backend, path, 0, true, None).unwrap();
+    assert_eq!(
+        *backend.calls.lock(),
+        vec![Call::DisableCopyOnWrite(path.to_path_buf())]
+    );
+    assert!(!check.profile.copy_on_write);
+    assert!(check.profile.copy_on_write_disabled);
+    assert!(check.warnings.is_empty());
+
+    // Once plot file is written copy-on-write can't be disabled anymore, but previous adjustment is
+    // remembered
+    backend.calls.lock().clear();
+    let backend = MockFilesystem {
+        attributes: Some(attributes("btrfs", false, false)),
+        ..backend
+    };
+    let previous_profile = check.profile;
+    let check = check_plot_file(&backend, path, GIB, false, Some(&previous_profile)).unwrap();
+    assert!(backend.calls.lock().is_empty());
+    assert_eq!(check.profile, previous_profile);
+    assert!(check.warnings.is_empty());
+}
+
+#[test]
+fn copy_on_write_warning() {
+    let path = Path::new("plot.bin");
+
+    // Existing plot file is not touched
+    let backend = MockFilesystem {
+        attributes: Some(attributes("btrfs", true, false)),
+        copy_on_write_can_be_disabled: true,
+        ..MockFilesystem::default()
+    };
+    let check = check_plot_file(&backend, path, GIB, false, None).unwrap();
+    assert!(backend.calls.lock().is_empty());
+    assert!(check.profile.copy_on_write);
+    assert!(!check.profile.copy_on_write_disabled);
+    assert_eq!(check.warnings, vec![FilesystemWarning::CopyOnWrite]);
+
+    // Copy-on-write of ZFS can't be disabled
+    let backend = MockFilesystem {
+        attributes: Some(attributes("zfs", true, false)),
+        ..MockFilesystem::default()
+    };
+    let check = check_plot_file(&backend, path, 0, true, None).unwrap();
+    assert_eq!(
+        *backend.calls.lock(),
+        vec![Call::DisableCopyOnWrite(path.to_path_buf())]
+    );
+    assert!(check.profile.copy_on_write);
+    assert!(!check.profile.copy_on_write_disabled);
+    assert_eq!(check.warnings, vec![FilesystemWarning::CopyOnWrite]);
+}
+
+#[test]
+fn compression() {
+    let path = Path::new("plot.bin");
+
+    let backend = MockFilesystem {
+        attributes: Some(attributes("NTFS", false, true)),
+        compression_can_be_disabled: true,
+        ..MockFilesystem::default()
+    };
+    let check = check_plot_file(&backend, path, 0, true, None).unwrap();
+    assert_eq!(
+        *backend.calls.lock(),
+        vec![Call::DisableCompression(path.to_path_buf())]
+    );
+    assert!(!check.profile.compressed);
+    assert!(check.profile.compression_disabled);
+    assert!(check.warnings.is_empty());
+
+    // Both copy-on-write and compression of Btrfs, but only compression can be disabled
+    let backend = MockFilesystem {
+        attributes: Some(attributes("btrfs", true, true)),
+        compression_can_be_disabled: true,
+        ..MockFilesystem::default()
+    };
+    let check = check_plot_file(&backend, path, 0, true, None).unwrap();
+    assert_eq!(
+        *backend.calls.lock(),
+        vec![
+            Call::DisableCopyOnWrite(path.to_path_buf()),
+            Call::DisableCompression(path.to_path_buf())
+        ]
+    );
+    assert!(check.profile.copy_on_write);
+    assert!(!check.profile.compressed);
+    assert_eq!(check.warnings, vec![FilesystemWarning::CopyOnWrite]);
+
+    // Existing compressed plot file is only warned about
+    let backend = MockFilesystem {
+        attributes: Some(attributes("NTFS", false, true)),
+        compression_can_be_disabled: true,
+        ..MockFilesystem::default()
+    };
+    let check = check_plot_file(&backend, path, GIB, false, None).unwrap();
+    assert!(backend.calls.lock().is_empty());
+    assert!(check.profile.compressed);
+    assert_eq!(check.warnings, vec![FilesystemWarning::Compressed]);
+}
+
+#[test]
+fn fragmentation() {
+    let path = Path::new("plot.bin");
+    let plot_file_size = 4 * GIB;
+    let backend = |extents| MockFilesystem {
+        attributes: Some(FilesystemAttributes {
+            extents: Some(extents),
+            ..attributes("xfs", false, false)
+        }),
+        ..MockFilesystem::default()
+    };
+
+    let extents = FRAGMENTED_EXTENTS_PER_GIB * 4;
+    let check = check_plot_file(&backend(extents), path, plot_file_size, false, None).unwrap();
+    assert!(check.warnings.is_empty());
+
+    let extents = FRAGMENTED_EXTENTS_PER_GIB * 4 + 1;
+    let check = check_plot_file(&backend(extents), path, plot_file_size, false, None).unwrap();
+    assert_eq!(
+        check.warnings,
+        vec![FilesystemWarning::Fragmented {
+            extents,
+            size: plot_file_size
+        }]
+    );
+
+    // Extents of new plot file are ignored
+    let check = check_plot_file(&backend(extents), path, plot_file_size, true, None).unwrap();
+    assert!(check.warnings.is_empty());
+}
+
+#[test]
+fn detection_failure() {
+    let backend = MockFilesystem::default();
+
+    assert!(check_plot_file(&backend, Path::new("plot.bin"), 0, true, None).is_none());
+    assert!(backend.calls.lock().is_empty());
+}
@@ -0,0 +1,143 @@
+//! Windows backend of [`OsFilesystem`](super::OsFilesystem)
+
+use crate::single_disk_farm::filesystem_profile::FilesystemAttributes;
+use std::ffi::OsString;
+use std::fs::{File, OpenOptions};
+use std::os::windows::ffi::OsStringExt;
+use std::os::windows::io::AsRawHandle;
+use std::path::Path;
+use std::{io, mem, ptr};
+use winapi::um::fileapi::{GetFileInformationByHandle, GetVolumeInformationByHandleW};
+use winapi::um::ioapiset::DeviceIoControl;
+use winapi::um::winioctl::FSCTL_SET_COMPRESSION;
+use winapi::um::winnt::{FILE_ATTRIBUTE_COMPRESSED, HANDLE};
+
+/// `FSCTL_GET_EXTERNAL_BACKING` control code, file is compressed by Windows Overlay Filter (like
+/// with `compact /EXE`) if it is externally backed
+const FSCTL_GET_EXTERNAL_BACKING: u32 = 0x0009_0310;
+/// `FSCTL_DELETE_EXTERNAL_BACKING` control code
+const FSCTL_DELETE_EXTERNAL_BACKING: u32 = 0x0009_0314;
+/// `ERROR_OBJECT_NOT_EXTERNALLY_BACKED` error code
+const ERROR_OBJECT_NOT_EXTERNALLY_BACKED: i32 = 342;
+/// `ERROR_INSUFFICIENT_BUFFER` error code
+const ERROR_INSUFFICIENT_BUFFER: i32 = 122;
+/// `ERROR_MORE_DATA` error code
+const ERROR_MORE_DATA: i32 = 234;
+/// `COMPRESSION_FORMAT_NONE` compression format
+const COMPRESSION_FORMAT_NONE: u16 = 0;
+/// Max length of file system name in UTF-16 code units
+const MAX_FILESYSTEM_NAME_LENGTH: usize = 261;
+
+/// Detect file system with `GetVolumeInformationByHandleW()` and compression of the file from
+/// `FILE_ATTRIBUTE_COMPRESSED` (NTFS compression) and `FSCTL_GET_EXTERNAL_BACKING` (Windows
+/// Overlay Filter compression), copy-on-write and number of extents are not reported
+pub(super) fn detect(path: &Path) -> io::Result<FilesystemAttributes> {
+    let file = File::open(path)?;
+    let handle = file.as_raw_handle() as HANDLE;
+
+    let mut filesystem_name = vec![0u16; MAX_FILESYSTEM_NAME_LENGTH];
+    // SAFETY: Handle is valid, buffer length is passed correctly, other outputs are optional
+    let filesystem = if unsafe {
+        GetVolumeInformationByHandleW(
+            handle,
+            ptr::null_mut(),
+            0,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            ptr::null_mut(),
+            filesystem_name.as_mut_ptr(),
+            filesystem_name.len() as u32,
+        )
+    } != 0
+    {
+        let length = filesystem_name
+            .iter()
+            .position(|&c| c == 0)
+            .unwrap_or(filesystem_name.len());
+        OsString::from_wide(&filesystem_name[..length])
+            .into_string()
+            .ok()
+    } else {
+        None
+    };
+
+    // SAFETY: Structure is plain data for which all zeroes is a valid value
+    let mut file_information = unsafe { mem::zeroed() };
+    // SAFETY: Handle is valid, `file_information` is a valid pointer
+    if unsafe { GetFileInformationByHandle(handle, &mut file_information) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let compressed = file_information.dwFileAttributes & FILE_ATTRIBUTE_COMPRESSED != 0
+        || externally_backed(&file)?;
+
+    Ok(FilesystemAttributes {
+        filesystem,
+        copy_on_write: false,
+        compressed,
+        extents: None,
+    })
+}
+
+/// Copy-on-write of regular files can't be controlled on Windows
+pub(super) fn disable_copy_on_write(_path: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Disabling copy-on-write is not supported on Windows",
+    ))
+}
+
+/// Remove external backing of the file (if any) and set `COMPRESSION_FORMAT_NONE` with
+/// `FSCTL_SET_COMPRESSION`
+pub(super) fn disable_compression(path: &Path) -> io::Result<()> {
+    let file = OpenOptions::new().read(true).write(true).open(path)?;
+
+    if externally_backed(&file)? {
+        device_io_control(&file, FSCTL_DELETE_EXTERNAL_BACKING, &[])?;
+    }
+    device_io_control(
+        &file,
+        FSCTL_SET_COMPRESSION,
+        &COMPRESSION_FORMAT_NONE.to_le_bytes(),
+    )
+}
+
+/// Whether file is externally backed, backing information itself is not needed, hence no output
+/// buffer is provided
+fn externally_backed(file: &File) -> io::Result<bool> {
+    match device_io_control(file, FSCTL_GET_EXTERNAL_BACKING, &[]) {
+        Ok(()) => Ok(true),
+        Err(error) => match error.raw_os_error() {
+            Some(ERROR_INSUFFICIENT_BUFFER | ERROR_MORE_DATA) => Ok(true),
+            Some(ERROR_OBJECT_NOT_EXTERNALLY_BACKED) => Ok(false),
+            _ => Err(error),
+        },
+    }
+}
+
+/// Issue file system control `control_code` for `file` with `input` and no output
+fn device_io_control(file: &File, control_code: u32, input: &[u8]) -> io::Result<()> {
+    let mut bytes_returned = 0u32;
+    // SAFETY: Handle is valid, input buffer and its length are passed correctly, no output is
+    // expected and operation is synchronous
+    let result = unsafe {
+        DeviceIoControl(
+            file.as_raw_handle() as HANDLE,
+            control_code,
+            if input.is_empty() {
+                ptr::null_mut()
+            } else {
+                input.as_ptr() as *mut _
+            },
+            input.len() as u32,
+            ptr::null_mut(),
+            0,
+            &mut bytes_returned,
+            ptr::null_mut(),
+        )
+    };
+    if result == 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
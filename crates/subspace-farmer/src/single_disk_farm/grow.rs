@@ -0,0 +1,386 @@
+//! Growing of the farm in place while it is running, such that farm can be extended when disk
+//! space is freed without replotting existing sectors or creating another farm on the same disk,
+//! see [`FarmGrowHandle`].
+//!
+//! The same handle shrinks the farm while it is running, see [`FarmGrowHandle::shrink()`].
+
+#[cfg(test)]
+mod tests;
+
+use crate::farm::HandlerFn;
+use crate::single_disk_farm::direct_io_file::{DirectIoFile, DISK_SECTOR_SIZE};
+use crate::single_disk_farm::metadata_journal::MetadataJournal;
+use crate::single_disk_farm::metadata_layout::MetadataLayout;
+use crate::single_disk_farm::plot_cache::DiskPlotCache;
+use crate::single_disk_farm::preallocation::{
+    preallocate_in_chunks, CancelOnDrop, PreallocatedSize, PreallocationFrontier,
+    PreallocationProgress,
+};
+use crate::single_disk_farm::shrink::{shrink, FarmShrinkError, FarmShrinkSummary};
+use crate::single_disk_farm::{
+    target_sector_count, PlotFile, SingleDiskFarm, SingleDiskFarmError, SingleDiskFarmInfo,
+};
+use crate::utils::AsyncJoinOnDrop;
+use async_lock::{Mutex as AsyncMutex, RwLock as AsyncRwLock};
+use futures::channel::mpsc;
+use parking_lot::Mutex;
+use std::io;
+use std::num::{NonZeroU64, NonZeroU8};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU16, Ordering};
+use std::sync::Arc;
+use subspace_core_primitives::SectorIndex;
+use subspace_farmer_components::file_ext::FileExt;
+use subspace_farmer_components::sector::{sector_size, SectorMetadataChecksummed};
+use thiserror::Error;
+use tracing::{debug, info, Span};
+
+/// Summary of farm growing, see [`FarmGrowHandle::grow()`]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct FarmGrowSummary {
+    /// Number of sectors farm had space for before growing
+    pub sector_count: SectorIndex,
+    /// Number of sectors farm has space for after growing, sectors in between are queued for
+    /// plotting
+    pub new_sector_count: SectorIndex,
+}
+
+/// Errors happening during farm growing
+#[derive(Debug, Error)]
+pub enum FarmGrowError {
+    /// Read-only farm can't be grown
+    #[error("Read-only farm can't be grown")]
+    ReadOnly,
+    /// Plot file is still being preallocated after farm was opened
+    #[error(
+        "Plot file is still being preallocated, farm can be grown once preallocation finishes"
+    )]
+    PreallocationInProgress,
+    /// Farm is too large
+    #[error(
+        "Farm can't grow to {sector_count} sectors, max supported is {max_sectors}. Consider \
+        creating another farm instead."
+    )]
+    FarmTooLarge {
+        /// Number of sectors that would fit into new allocated space
+        sector_count: u64,
+        /// Max supported number of sectors
+        max_sectors: SectorIndex,
+    },
+    /// Farm info on disk doesn't belong to this farm anymore
+    #[error("Farm info in {} doesn't match farm that is running", directory.display())]
+    FarmInfoMismatch {
+        /// Farm directory
+        directory: PathBuf,
+    },
+    /// Can't preallocate metadata file, probably not enough space on disk
+    #[error("Can't preallocate metadata file, probably not enough space on disk: {0}")]
+    CantPreallocateMetadataFile(io::Error),
+    /// Can't preallocate plot file, probably not enough space on disk
+    #[error("Can't preallocate plot file, probably not enough space on disk: {0}")]
+    CantPreallocatePlotFile(io::Error),
+    /// Plot file preallocation was cancelled, farm remains of the same size
+    #[error("Plot file preallocation was cancelled")]
+    PreallocationCancelled,
+    /// Farm error
+    #[error(transparent)]
+    Farm(#[from] SingleDiskFarmError),
+    /// I/O error occurred
+    #[error("Farm grow I/O error: {0}")]
+    Io(#[from] io::Error),
+    /// Failed to spawn task for blocking thread
+    #[error("Failed to spawn task for blocking thread: {0}")]
+    TokioJoinError(#[from] tokio::task::JoinError),
+}
+
+pub(super) struct Inner {
+    pub(super) directory: PathBuf,
+    /// Copy of farm info that is stored on disk, locked for the whole duration of growing or
+    /// shrinking, such that concurrent requests are processed one by one
+    pub(super) info: Mutex<SingleDiskFarmInfo>,
+    pub(super) plot_file: Arc<PlotFile>,
+    pub(super) metadata_file: Arc<DirectIoFile>,
+    /// Journal of metadata file updates shared with plotting, `None` for read-only farm
+    pub(super) metadata_journal: Option<Arc<MetadataJournal<Arc<DirectIoFile>>>>,
+    pub(super) sectors_metadata: Arc<AsyncRwLock<Vec<SectorMetadataChecksummed>>>,
+    /// Shared with plotting, held while sectors are dropped by shrinking
+    pub(super) sector_modification_mutex: Arc<AsyncMutex<()>>,
+    pub(super) plot_cache: DiskPlotCache,
+    pub(super) total_sectors_count: Arc<AtomicU16>,
+    /// `None` for read-only farm
+    new_sectors_sender: Option<mpsc::UnboundedSender<Range<SectorIndex>>>,
+    /// Frontier of plot file preallocation that started when farm was opened
+    preallocation_frontier: PreallocationFrontier,
+    preallocation_chunk_size: NonZeroU64,
+    on_preallocation_progress: Option<HandlerFn<PreallocationProgress>>,
+    span: Span,
+}
+
+/// Handle that grows or shrinks the farm while it is running, see
+/// [`SingleDiskFarm::grow_handle()`]
+#[derive(Clone)]
+pub struct FarmGrowHandle {
+    pub(super) inner: Arc<Inner>,
+}
+
+impl FarmGrowHandle {
+    #[allow(clippy::too_many_arguments)]
+    pub(super) fn new(
+        directory: PathBuf,
+        info: SingleDiskFarmInfo,
+        plot_file: Arc<PlotFile>,
+        metadata_file: Arc<DirectIoFile>,
+        metadata_journal: Option<Arc<MetadataJournal<Arc<DirectIoFile>>>>,
+        sectors_metadata: Arc<AsyncRwLock<Vec<SectorMetadataChecksummed>>>,
+        sector_modification_mutex: Arc<AsyncMutex<()>>,
+        plot_cache: DiskPlotCache,
+        total_sectors_count: Arc<AtomicU16>,
+        new_sectors_sender: Option<mpsc::UnboundedSender<Range<SectorIndex>>>,
+        preallocation_frontier: PreallocationFrontier,
+        preallocation_chunk_size: NonZeroU64,
+        on_preallocation_progress: Option<HandlerFn<PreallocationProgress>>,
+        span: Span,
+    ) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                directory,
+                info: Mutex::new(info),
+                plot_file,
+                metadata_file,
+                metadata_journal,
+                sectors_metadata,
+                sector_modification_mutex,
+                plot_cache,
+                total_sectors_count,
+                new_sectors_sender,
+                preallocation_frontier,
+                preallocation_chunk_size,
+                on_preallocation_progress,
+                span,
+            }),
+        }
+    }
+
+    /// Grow the farm to fit into `new_allocated_space` with `cache_percentage` of it used for
+    /// piece cache, nothing is done if farm already has space for as many sectors.
+    ///
+    /// Additional space of plot and metadata files is preallocated in chunks with progress
+    /// reported the same way as when farm is opened (see
+    /// [`SingleDiskFarmOptions`](super::SingleDiskFarmOptions)), then new sectors are queued for
+    /// plotting once already queued sectors are plotted, while existing sectors continue to be
+    /// farmed. Preallocation is cancelled if returned future is dropped, in which case farm remains
+    /// of the same size.
+    ///
+    /// Identity, sector size and layout of the farm remain the same, farm must be opened with
+    /// `new_allocated_space` afterwards, piece cache is resized on the next open. Farms with
+    /// additional plot segments can't be grown, neither can farms whose plot file is still being
+    /// preallocated after open.
+    pub async fn grow(
+        &self,
+        new_allocated_space: u64,
+        cache_percentage: NonZeroU8,
+    ) -> Result<FarmGrowSummary, FarmGrowError> {
+        let cancel_preallocation = CancelOnDrop::default();
+        let grow_fut = tokio::task::spawn_blocking({
+            let inner = Arc::clone(&self.inner);
+            let preallocation_cancelled = cancel_preallocation.cancelled();
+
+            move || {
+                let _span_guard = inner.span.enter();
+                grow(
+                    &inner,
+                    new_allocated_space,
+                    cache_percentage,
+                    &preallocation_cancelled,
+                )
+            }
+        });
+
+        AsyncJoinOnDrop::new(grow_fut, false).await?
+    }
+
+    /// Shrink the farm to fit into `new_allocated_space` with `cache_percentage` of it used for
+    /// piece cache by dropping trailing sectors, nothing is done if farm already fits.
+    ///
+    /// Plotting stops writing dropped sectors right away, then sectors being written are waited
+    /// for, dropped sectors are marked as expired in metadata and removed from farming before plot
+    /// and metadata files are truncated, such that farm remains consistent if interrupted at any
+    /// point. Pieces cached in the plot (see [`SingleDiskFarm::plot_cache()`]) are evicted and
+    /// plot cache is not used until the next open.
+    ///
+    /// Identity, sector size and layout of the farm remain the same, farm must be opened with
+    /// `new_allocated_space` afterwards, piece cache is resized on the next open. Farms with
+    /// additional plot segments can't be shrunk.
+    pub async fn shrink(
+        &self,
+        new_allocated_space: u64,
+        cache_percentage: NonZeroU8,
+    ) -> Result<FarmShrinkSummary, FarmShrinkError> {
+        let shrink_fut = tokio::task::spawn_blocking({
+            let inner = Arc::clone(&self.inner);
+
+            move || {
+                let _span_guard = inner.span.enter();
+                shrink(&inner, new_allocated_space, cache_percentage)
+            }
+        });
+
+        AsyncJoinOnDrop::new(shrink_fut, false).await?
+    }
+}
+
+fn grow(
+    inner: &Inner,
+    new_allocated_space: u64,
+    cache_percentage: NonZeroU8,
+    preallocation_cancelled: &AtomicBool,
+) -> Result<FarmGrowSummary, FarmGrowError> {
+    let Inner {
+        directory,
+        info,
+        plot_file,
+        metadata_file,
+        metadata_journal: _,
+        sectors_metadata: _,
+        sector_modification_mutex: _,
+        plot_cache: _,
+        total_sectors_count,
+        new_sectors_sender,
+        preallocation_frontier,
+        preallocation_chunk_size,
+        on_preallocation_progress,
+        span: _,
+    } = inner;
+
+    let Some(new_sectors_sender) = new_sectors_sender else {
+        return Err(FarmGrowError::ReadOnly);
+    };
+
+    // Both would preallocate the same plot file and share preallocation record
+    if !preallocation_frontier.is_complete() {
+        return Err(FarmGrowError::PreallocationInProgress);
+    }
+
+    let mut info = info.lock();
+    let sector_size = sector_size(info.pieces_in_sector()) as u64;
+    let sector_count = total_sectors_count.load(Ordering::Acquire);
+    let new_sector_count = target_sector_count(new_allocated_space, cache_percentage, sector_size)?;
+
+    if new_sector_count <= u64::from(sector_count) {
+        info!(
+            %sector_count,
+            %new_sector_count,
+            "Farm already has space for requested number of sectors, nothing to grow"
+        );
+
+        return Ok(FarmGrowSummary {
+            sector_count,
+            new_sector_count: sector_count,
+        });
+    }
+
+    let new_sector_count = match SectorIndex::try_from(new_sector_count) {
+        Ok(new_sector_count) if new_sector_count < SectorIndex::MAX => new_sector_count,
+        _ => {
+            // We use this for both count and index, hence index must not reach actual `MAX`
+            return Err(FarmGrowError::FarmTooLarge {
+                sector_count: new_sector_count,
+                max_sectors: SectorIndex::MAX - 1,
+            });
+        }
+    };
+
+    // Only the last segment can be resized, which is the farm directory only if there are no
+    // additional segments
+    if let Some(plot_segment) = info.plot_segments().first() {
+        return Err(SingleDiskFarmError::PlotSegmentResized {
+            id: *info.id(),
+            directory: directory.clone(),
+            sector_count: plot_segment.sector_count,
+            new_sector_count,
+        }
+        .into());
+    }
+
+    if !farm_info_matches(directory, &info)? {
+        return Err(FarmGrowError::FarmInfoMismatch {
+            directory: directory.clone(),
+        });
+    }
+
+    info!(%sector_count, %new_sector_count, "Growing farm");
+
+    // Existing sector metadata and header stay where they are, the table is only extended
+    let metadata_size = MetadataLayout::CURRENT
+        .metadata_size(new_sector_count)
+        .next_multiple_of(DISK_SECTOR_SIZE as u64);
+    metadata_file
+        .preallocate(metadata_size)
+        .map_err(FarmGrowError::CantPreallocateMetadataFile)?;
+
+    // Align plot file size for disk sector size
+    let plot_file_size = plot_file.last_segment_offset()
+        + (u64::from(new_sector_count) * sector_size).next_multiple_of(DISK_SECTOR_SIZE as u64);
+    let preallocated_size =
+        PreallocatedSize::new(directory.join(SingleDiskFarm::PLOT_PREALLOCATION_FILE));
+    let preallocated = preallocate_in_chunks(
+        plot_file.as_ref(),
+        plot_file_size,
+        *preallocation_chunk_size,
+        &preallocated_size,
+        |progress| {
+            debug!(
+                allocated = %progress.allocated,
+                total = %progress.total,
+                "Preallocating plot file"
+            );
+
+            if let Some(on_preallocation_progress) = on_preallocation_progress {
+                on_preallocation_progress(&progress);
+            }
+        },
+        preallocation_cancelled,
+    )
+    .map_err(FarmGrowError::CantPreallocatePlotFile)?;
+
+    if !preallocated {
+        return Err(FarmGrowError::PreallocationCancelled);
+    }
+
+    // Farm info is only updated once all space is allocated, if farm is interrupted before that,
+    // files are truncated back to the previous size on the next open
+    {
+        let SingleDiskFarmInfo::V0 {
+            allocated_space, ..
+        } = &mut *info;
+        *allocated_space = new_allocated_space;
+    }
+    info.store_to(directory)?;
+
+    total_sectors_count.store(new_sector_count, Ordering::Release);
+    // Plotting is not running anymore if receiver is dropped, in which case new sectors are
+    // plotted after restart
+    let _ = new_sectors_sender.unbounded_send(sector_count..new_sector_count);
+
+    let summary = FarmGrowSummary {
+        sector_count,
+        new_sector_count,
+    };
+
+    info!(?summary, "Farm grown");
+
+    Ok(summary)
+}
+
+/// Check that farm info on disk still belongs to the farm that is running, such that identity and
+/// sector size are not changed by growing or shrinking
+pub(super) fn farm_info_matches(directory: &Path, info: &SingleDiskFarmInfo) -> io::Result<bool> {
+    Ok(matches!(
+        SingleDiskFarmInfo::load_from(directory)?,
+        Some(stored_info)
+            if stored_info.id() == info.id()
+                && stored_info.public_key() == info.public_key()
+                && stored_info.pieces_in_sector() == info.pieces_in_sector()
+    ))
+}
@@ -0,0 +1,240 @@
+use crate::farm::{FarmId, HandlerFn};
+use crate::single_disk_farm::direct_io_file::DISK_SECTOR_SIZE;
+use crate::single_disk_farm::grow::{FarmGrowError, FarmGrowSummary};
+use crate::single_disk_farm::metadata_layout::MetadataLayout;
+use crate::single_disk_farm::preallocation::{
+    BackgroundPreallocation, PreallocatedSize, PreallocationProgress,
+};
+use crate::single_disk_farm::test_farm::{GrowHandleOptions, TestFarm};
+use crate::single_disk_farm::{test_farm, SingleDiskFarm, SingleDiskFarmInfo};
+use futures::channel::mpsc;
+use futures::StreamExt;
+use parking_lot::Mutex;
+use std::assert_matches::assert_matches;
+use std::fs;
+use std::num::{NonZeroU64, NonZeroU8};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use subspace_core_primitives::SectorIndex;
+use subspace_farmer_components::sector::sector_size;
+use tempfile::tempdir;
+
+const PIECES_IN_SECTOR: u16 = 1;
+const SECTOR_COUNT: SectorIndex = 2;
+const CACHE_PERCENTAGE: NonZeroU8 = NonZeroU8::new(1).expect("Not zero; qed");
+/// Several chunks per sector
+const PREALLOCATION_CHUNK_SIZE: NonZeroU64 =
+    NonZeroU64::new(sector_size(PIECES_IN_SECTOR) as u64 / 3).expect("Not zero; qed");
+
+/// Allocated space that fits exactly `sector_count` sectors with [`CACHE_PERCENTAGE`]
+fn allocated_space(sector_count: SectorIndex) -> u64 {
+    test_farm::allocated_space(PIECES_IN_SECTOR, sector_count, CACHE_PERCENTAGE)
+}
+
+/// Handler that records preallocation progress into `progress`
+fn progress_recorder(
+    progress: &Arc<Mutex<Vec<PreallocationProgress>>>,
+) -> HandlerFn<PreallocationProgress> {
+    let progress = Arc::clone(progress);
+    Arc::new(move |preallocation_progress: &PreallocationProgress| {
+        progress.lock().push(*preallocation_progress);
+    })
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn grow_two_sectors_to_four() {
+    let tempdir = tempdir().unwrap();
+    let directory = tempdir.as_ref();
+    let farm =
+        TestFarm::new(PIECES_IN_SECTOR, SECTOR_COUNT).with_cache_percentage(CACHE_PERCENTAGE);
+    let info = farm.create(directory);
+    let plot_before = fs::read(directory.join(SingleDiskFarm::PLOT_FILE)).unwrap();
+    let metadata_before = fs::read(directory.join(SingleDiskFarm::METADATA_FILE)).unwrap();
+
+    let (new_sectors_sender, mut new_sectors_receiver) = mpsc::unbounded();
+    let progress = Arc::default();
+    let grow_handle = farm.grow_handle(
+        directory,
+        info,
+        GrowHandleOptions {
+            new_sectors_sender: Some(new_sectors_sender),
+            preallocation_chunk_size: PREALLOCATION_CHUNK_SIZE,
+            on_preallocation_progress: Some(progress_recorder(&progress)),
+            ..GrowHandleOptions::default()
+        },
+    );
+
+    let new_sector_count = SECTOR_COUNT * 2;
+    let new_allocated_space = allocated_space(new_sector_count);
+    let summary = grow_handle
+        .grow(new_allocated_space, CACHE_PERCENTAGE)
+        .await
+        .unwrap();
+    assert_eq!(
+        summary,
+        FarmGrowSummary {
+            sector_count: SECTOR_COUNT,
+            new_sector_count,
+        }
+    );
+    assert_eq!(
+        grow_handle
+            .inner
+            .total_sectors_count
+            .load(Ordering::Acquire),
+        new_sector_count
+    );
+
+    // New sectors are queued for plotting
+    assert_eq!(
+        new_sectors_receiver.next().await,
+        Some(SECTOR_COUNT..new_sector_count)
+    );
+
+    // Files are extended without touching existing sectors
+    let sector_size = sector_size(PIECES_IN_SECTOR) as u64;
+    let plot = fs::read(directory.join(SingleDiskFarm::PLOT_FILE)).unwrap();
+    assert_eq!(
+        plot.len() as u64,
+        (u64::from(new_sector_count) * sector_size).next_multiple_of(DISK_SECTOR_SIZE as u64)
+    );
+    assert_eq!(plot[..plot_before.len()], plot_before);
+    let metadata = fs::read(directory.join(SingleDiskFarm::METADATA_FILE)).unwrap();
+    assert_eq!(
+        metadata.len() as u64,
+        MetadataLayout::CURRENT
+            .metadata_size(new_sector_count)
+            .next_multiple_of(DISK_SECTOR_SIZE as u64)
+    );
+    assert_eq!(metadata[..metadata_before.len()], metadata_before);
+    assert_eq!(
+        SingleDiskFarmInfo::load_from(directory)
+            .unwrap()
+            .unwrap()
+            .allocated_space(),
+        new_allocated_space
+    );
+
+    // Preallocation is done in chunks with progress
+    {
+        let progress = progress.lock();
+        assert!(progress.len() > 2);
+        assert_eq!(
+            progress.last().copied(),
+            Some(PreallocationProgress {
+                allocated: plot.len() as u64,
+                total: plot.len() as u64,
+            })
+        );
+    }
+    assert!(!directory
+        .join(SingleDiskFarm::PLOT_PREALLOCATION_FILE)
+        .exists());
+
+    // Farm already has space for as many sectors, nothing else is done
+    let summary = grow_handle
+        .grow(new_allocated_space, CACHE_PERCENTAGE)
+        .await
+        .unwrap();
+    assert_eq!(summary.new_sector_count, new_sector_count);
+    drop(grow_handle);
+    assert_eq!(new_sectors_receiver.next().await, None);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn grow_rejected() {
+    let tempdir = tempdir().unwrap();
+    let directory = tempdir.as_ref();
+    let farm =
+        TestFarm::new(PIECES_IN_SECTOR, SECTOR_COUNT).with_cache_percentage(CACHE_PERCENTAGE);
+    let info = farm.create(directory);
+    let progress = Arc::default();
+
+    // Read-only farm doesn't plot
+    assert_matches!(
+        farm.grow_handle(
+            directory,
+            info.clone(),
+            GrowHandleOptions {
+                read_only: true,
+                preallocation_chunk_size: PREALLOCATION_CHUNK_SIZE,
+                on_preallocation_progress: Some(progress_recorder(&progress)),
+                ..GrowHandleOptions::default()
+            }
+        )
+        .grow(allocated_space(SECTOR_COUNT * 2), CACHE_PERCENTAGE)
+        .await,
+        Err(FarmGrowError::ReadOnly)
+    );
+
+    // Plot file is still being preallocated after farm was opened
+    let plot_file = fs::File::open(directory.join(SingleDiskFarm::PLOT_FILE)).unwrap();
+    let (_plot_preallocation, preallocation_frontier) = BackgroundPreallocation::new(
+        &plot_file,
+        u64::MAX,
+        PreallocatedSize::new(directory.join(SingleDiskFarm::PLOT_PREALLOCATION_FILE)),
+    )
+    .unwrap();
+    let (new_sectors_sender, _new_sectors_receiver) = mpsc::unbounded();
+    assert_matches!(
+        farm.grow_handle(
+            directory,
+            info.clone(),
+            GrowHandleOptions {
+                new_sectors_sender: Some(new_sectors_sender),
+                preallocation_frontier,
+                preallocation_chunk_size: PREALLOCATION_CHUNK_SIZE,
+                on_preallocation_progress: Some(progress_recorder(&progress)),
+                ..GrowHandleOptions::default()
+            }
+        )
+        .grow(allocated_space(SECTOR_COUNT * 2), CACHE_PERCENTAGE)
+        .await,
+        Err(FarmGrowError::PreallocationInProgress)
+    );
+
+    let (new_sectors_sender, _new_sectors_receiver) = mpsc::unbounded();
+    let grow_handle = farm.grow_handle(
+        directory,
+        info,
+        GrowHandleOptions {
+            new_sectors_sender: Some(new_sectors_sender),
+            preallocation_chunk_size: PREALLOCATION_CHUNK_SIZE,
+            on_preallocation_progress: Some(progress_recorder(&progress)),
+            ..GrowHandleOptions::default()
+        },
+    );
+
+    // More sectors than can be addressed
+    assert_matches!(
+        grow_handle.grow(u64::MAX / 2, CACHE_PERCENTAGE).await,
+        Err(FarmGrowError::FarmTooLarge { max_sectors, .. }) if max_sectors == SectorIndex::MAX - 1
+    );
+
+    // Farm info on disk belongs to a different farm
+    SingleDiskFarmInfo::new(
+        FarmId::new(),
+        [0; 32],
+        Default::default(),
+        PIECES_IN_SECTOR,
+        allocated_space(SECTOR_COUNT),
+    )
+    .store_to(directory)
+    .unwrap();
+    assert_matches!(
+        grow_handle
+            .grow(allocated_space(SECTOR_COUNT * 2), CACHE_PERCENTAGE)
+            .await,
+        Err(FarmGrowError::FarmInfoMismatch { .. })
+    );
+
+    // Nothing was changed
+    assert_eq!(
+        grow_handle
+            .inner
+            .total_sectors_count
+            .load(Ordering::Acquire),
+        SECTOR_COUNT
+    );
+    assert!(progress.lock().is_empty());
+}
@@ -0,0 +1,368 @@
+//! Verification of plotted sectors against checksums stored at the end of each sector and
+//! background integrity scrub that goes over plotted sectors at a limited rate
+
+#[cfg(test)]
+mod tests;
+
+use crate::farm::{SectorCorruptionDetails, SectorUpdate};
+use crate::single_disk_farm::file_region::FileRegion;
+use crate::single_disk_farm::sector_reader::SectorReader;
+use crate::single_disk_farm::Handlers;
+use async_lock::RwLock as AsyncRwLock;
+use futures::channel::mpsc;
+use parity_scale_codec::{Decode, Encode};
+use parking_lot::{Condvar, Mutex};
+use std::num::NonZeroU64;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use std::{fs, io, mem};
+use subspace_core_primitives::{Blake3Hash, Record, SectorIndex};
+use subspace_farmer_components::file_ext::FileExt;
+use subspace_farmer_components::sector::SectorMetadataChecksummed;
+use subspace_farmer_components::ReadAtSync;
+use tokio::task::yield_now;
+use tracing::{debug, trace, warn};
+
+/// How long to wait before checking again when there are no plotted sectors yet
+const NO_PLOTTED_SECTORS_INTERVAL: Duration = Duration::from_secs(60);
+/// How long to wait before moving on after skipping sector that is being plotted
+const SECTOR_BEING_PLOTTED_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Verify `sector` (see [`SectorReader::read_sector()`]) against checksum stored in its last
+/// bytes, which is written together with the rest of the sector during plotting.
+///
+/// `scratch_buffer` is used for reads, returns `None` if sector is intact.
+pub(super) fn verify_sector_checksum<F>(
+    sector: &FileRegion<'_, F>,
+    scratch_buffer: &mut [u8],
+) -> io::Result<Option<SectorCorruptionDetails>>
+where
+    F: ReadAtSync + FileExt,
+{
+    verify_sector_checksum_with(sector, scratch_buffer, |_| Ok(()))
+}
+
+/// Same as [`verify_sector_checksum()`], but calls `on_read` with the number of bytes after every
+/// read, which allows to throttle reads, error returned by `on_read` stops verification
+pub(super) fn verify_sector_checksum_with<F, R>(
+    sector: &FileRegion<'_, F>,
+    scratch_buffer: &mut [u8],
+    mut on_read: R,
+) -> io::Result<Option<SectorCorruptionDetails>>
+where
+    F: ReadAtSync + FileExt,
+    R: FnMut(usize) -> io::Result<()>,
+{
+    let checksum_offset = sector
+        .len()
+        .checked_sub(mem::size_of::<Blake3Hash>() as u64)
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Sector of {} bytes can't contain checksum", sector.len()),
+            )
+        })?;
+    let (sector_bytes, checksum) = sector.split_at(checksum_offset)?;
+    let sector_bytes_len = sector_bytes.len();
+
+    let mut hasher = blake3::Hasher::new();
+    for offset_in_sector in (0..sector_bytes_len).step_by(scratch_buffer.len()) {
+        let bytes_to_read =
+            (sector_bytes_len - offset_in_sector).min(scratch_buffer.len() as u64) as usize;
+        let bytes = &mut scratch_buffer[..bytes_to_read];
+
+        sector_bytes.read_exact_at(bytes, offset_in_sector)?;
+        on_read(bytes.len())?;
+
+        hasher.update(bytes);
+    }
+
+    let actual_checksum = *hasher.finalize().as_bytes();
+    let mut expected_checksum = Blake3Hash::default();
+    checksum.read_exact_at(&mut expected_checksum, 0)?;
+
+    Ok(
+        (actual_checksum != expected_checksum).then_some(SectorCorruptionDetails {
+            actual_checksum,
+            expected_checksum,
+        }),
+    )
+}
+
+/// Read the plotted sector at `sector_index` with `sector_reader` and verify it with
+/// [`verify_sector_checksum_with()`], shared by background integrity scrub and readability scrub
+pub(super) fn verify_plotted_sector<PF, MF, R>(
+    sector_reader: &SectorReader<PF, MF>,
+    sector_index: SectorIndex,
+    scratch_buffer: &mut [u8],
+    on_read: R,
+) -> io::Result<Option<SectorCorruptionDetails>>
+where
+    PF: ReadAtSync + FileExt,
+    R: FnMut(usize) -> io::Result<()>,
+{
+    let sector = sector_reader.read_sector(sector_index)?;
+    verify_sector_checksum_with(&sector, scratch_buffer, on_read)
+}
+
+/// Position of the background integrity scrub, stored on disk such that scrub continues where it
+/// left off after restart
+#[derive(Debug)]
+pub(super) struct IntegrityScrubPosition {
+    /// Path to the file with position, `None` means position is only kept in memory
+    path: Option<PathBuf>,
+    next_sector_index: SectorIndex,
+}
+
+impl IntegrityScrubPosition {
+    /// Load position from `path`, scrub starts from the first sector if file doesn't exist or
+    /// can't be decoded. `None` path means position is only kept in memory (for read-only farms).
+    pub(super) fn load(path: Option<PathBuf>) -> io::Result<Self> {
+        let next_sector_index = match &path {
+            Some(path) => match fs::read(path) {
+                Ok(bytes) => SectorIndex::decode(&mut bytes.as_slice()).unwrap_or_else(|error| {
+                    warn!(
+                        path = %path.display(),
+                        %error,
+                        "Failed to decode integrity scrub position, starting from the beginning"
+                    );
+
+                    0
+                }),
+                Err(error) if error.kind() == io::ErrorKind::NotFound => 0,
+                Err(error) => {
+                    return Err(error);
+                }
+            },
+            None => 0,
+        };
+
+        Ok(Self {
+            path,
+            next_sector_index,
+        })
+    }
+
+    /// Sector index that should be checked next, wraps around to the first sector after the last
+    /// plotted sector was checked
+    pub(super) fn next_sector_index(&self, plotted_sectors_count: SectorIndex) -> SectorIndex {
+        if self.next_sector_index < plotted_sectors_count {
+            self.next_sector_index
+        } else {
+            0
+        }
+    }
+
+    /// Record that `sector_index` was checked
+    pub(super) fn advance(&mut self, sector_index: SectorIndex) -> io::Result<()> {
+        self.next_sector_index = sector_index.saturating_add(1);
+
+        if let Some(path) = &self.path {
+            fs::write(path, self.next_sector_index.encode())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Interrupts waits of [`RateLimiter`], such that rate limited reads can be stopped without waiting
+/// for the current wait to finish
+#[derive(Debug, Default)]
+pub(super) struct RateLimiterInterrupt {
+    interrupted: Mutex<bool>,
+    condvar: Condvar,
+}
+
+impl RateLimiterInterrupt {
+    /// Interrupt current and all future waits
+    pub(super) fn interrupt(&self) {
+        *self.interrupted.lock() = true;
+        self.condvar.notify_all();
+    }
+
+    pub(super) fn is_interrupted(&self) -> bool {
+        *self.interrupted.lock()
+    }
+}
+
+/// Interrupts [`RateLimiterInterrupt`] when dropped
+pub(super) struct InterruptOnDrop(pub(super) Arc<RateLimiterInterrupt>);
+
+impl Drop for InterruptOnDrop {
+    fn drop(&mut self) {
+        self.0.interrupt();
+    }
+}
+
+/// Limits average read rate by blocking the current thread
+pub(super) struct RateLimiter {
+    bytes_per_second: NonZeroU64,
+    started_at: Instant,
+    bytes_read: u64,
+    interrupt: Arc<RateLimiterInterrupt>,
+}
+
+impl RateLimiter {
+    pub(super) fn new(bytes_per_second: NonZeroU64) -> Self {
+        Self::with_interrupt(bytes_per_second, Arc::default())
+    }
+
+    pub(super) fn with_interrupt(
+        bytes_per_second: NonZeroU64,
+        interrupt: Arc<RateLimiterInterrupt>,
+    ) -> Self {
+        Self {
+            bytes_per_second,
+            started_at: Instant::now(),
+            bytes_read: 0,
+            interrupt,
+        }
+    }
+
+    /// Wait until `bytes` more can be read without exceeding the rate limit, returns
+    /// [`io::ErrorKind::Interrupted`] error if rate limiter was interrupted
+    pub(super) fn on_read(&mut self, bytes: usize) -> io::Result<()> {
+        self.bytes_read += bytes as u64;
+
+        let expected_elapsed =
+            Duration::from_secs_f64(self.bytes_read as f64 / self.bytes_per_second.get() as f64);
+        let mut interrupted = self.interrupt.interrupted.lock();
+        if let Some(remaining) = expected_elapsed.checked_sub(self.started_at.elapsed()) {
+            self.interrupt.condvar.wait_while_for(
+                &mut interrupted,
+                |interrupted| !*interrupted,
+                remaining,
+            );
+        }
+
+        if *interrupted {
+            return Err(io::Error::new(
+                io::ErrorKind::Interrupted,
+                "Rate limited reads were interrupted",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+pub(super) struct IntegrityScrubOptions<PF, MF> {
+    pub(super) sector_reader: SectorReader<PF, MF>,
+    pub(super) position: IntegrityScrubPosition,
+    /// Max average read rate in bytes per second
+    pub(super) rate_limit: NonZeroU64,
+    pub(super) sectors_metadata: Arc<AsyncRwLock<Vec<SectorMetadataChecksummed>>>,
+    pub(super) modifying_sector_index: Arc<AsyncRwLock<Option<SectorIndex>>>,
+    pub(super) handlers: Arc<Handlers>,
+    /// Corrupted sectors are sent here to be replotted, `None` for read-only farm
+    pub(super) corrupted_sectors_sender: Option<mpsc::UnboundedSender<SectorIndex>>,
+    /// Scrub exits once interrupted
+    pub(super) interrupt: Arc<RateLimiterInterrupt>,
+}
+
+/// Goes over plotted sectors in a loop, verifies their checksums and reports corrupted sectors
+/// with [`SectorUpdate::Corrupted`] and sends them to `corrupted_sectors_sender` to be replotted.
+///
+/// NOTE: Returned future is async, but does blocking operations and should be running in dedicated
+/// thread.
+pub(super) async fn integrity_scrub<PF, MF>(options: IntegrityScrubOptions<PF, MF>)
+where
+    PF: ReadAtSync + FileExt,
+{
+    let IntegrityScrubOptions {
+        sector_reader,
+        mut position,
+        rate_limit,
+        sectors_metadata,
+        modifying_sector_index,
+        handlers,
+        corrupted_sectors_sender,
+        interrupt,
+    } = options;
+
+    let mut scratch_buffer = vec![0; Record::SIZE];
+    let mut rate_limiter = RateLimiter::with_interrupt(rate_limit, Arc::clone(&interrupt));
+
+    while !interrupt.is_interrupted() {
+        let plotted_sectors_count = sectors_metadata.read().await.len() as SectorIndex;
+        if plotted_sectors_count == 0 {
+            tokio::time::sleep(NO_PLOTTED_SECTORS_INTERVAL).await;
+            // Don't accumulate reads allowance while there is nothing to read
+            rate_limiter = RateLimiter::with_interrupt(rate_limit, Arc::clone(&interrupt));
+            continue;
+        }
+
+        let sector_index = position.next_sector_index(plotted_sectors_count);
+        if sector_index == 0 {
+            debug!(%plotted_sectors_count, "Starting integrity scrub of plotted sectors");
+        }
+
+        let sector_metadata_before = encoded_sector_metadata(&sectors_metadata, sector_index).await;
+        if *modifying_sector_index.read().await == Some(sector_index) {
+            trace!(%sector_index, "Sector is being plotted, skipping integrity check");
+            tokio::time::sleep(SECTOR_BEING_PLOTTED_INTERVAL).await;
+        } else {
+            let result =
+                verify_plotted_sector(&sector_reader, sector_index, &mut scratch_buffer, |bytes| {
+                    rate_limiter.on_read(bytes)
+                });
+            if interrupt.is_interrupted() {
+                break;
+            }
+
+            // Lock is not held during the check to not block plotting for a long time, instead
+            // result is ignored if sector was replotted in the meantime
+            let replotted = *modifying_sector_index.read().await == Some(sector_index)
+                || encoded_sector_metadata(&sectors_metadata, sector_index).await
+                    != sector_metadata_before;
+
+            match result {
+                _ if replotted => {
+                    trace!(%sector_index, "Sector was replotted during integrity check");
+                }
+                Ok(None) => {
+                    trace!(%sector_index, "Sector is intact");
+                }
+                Ok(Some(corruption_details)) => {
+                    warn!(
+                        %sector_index,
+                        actual_checksum = %hex::encode(corruption_details.actual_checksum),
+                        expected_checksum = %hex::encode(corruption_details.expected_checksum),
+                        "Plotted sector checksum mismatch, sector is corrupted"
+                    );
+
+                    handlers
+                        .sector_update
+                        .call_simple(&(sector_index, SectorUpdate::Corrupted(corruption_details)));
+                    if let Some(corrupted_sectors_sender) = &corrupted_sectors_sender {
+                        if let Err(error) = corrupted_sectors_sender.unbounded_send(sector_index) {
+                            warn!(%error, %sector_index, "Failed to queue corrupted sector");
+                        }
+                    }
+                }
+                Err(error) => {
+                    warn!(%error, %sector_index, "Failed to read sector for integrity check");
+                }
+            }
+        }
+
+        if let Err(error) = position.advance(sector_index) {
+            warn!(%error, %sector_index, "Failed to store integrity scrub position");
+        }
+
+        // Allow other futures (like stop signal) to make progress between sectors
+        yield_now().await;
+    }
+}
+
+async fn encoded_sector_metadata(
+    sectors_metadata: &AsyncRwLock<Vec<SectorMetadataChecksummed>>,
+    sector_index: SectorIndex,
+) -> Option<Vec<u8>> {
+    sectors_metadata
+        .read()
+        .await
+        .get(usize::from(sector_index))
+        .map(Encode::encode)
+}
@@ -0,0 +1,248 @@
+use crate::farm::SectorUpdate;
+use crate::single_disk_farm::direct_io_file::DirectIoFile;
+use crate::single_disk_farm::integrity::{
+    integrity_scrub, verify_sector_checksum, IntegrityScrubOptions, IntegrityScrubPosition,
+    RateLimiter, RateLimiterInterrupt,
+};
+use crate::single_disk_farm::metadata_layout::MetadataLayout;
+use crate::single_disk_farm::sector_reader::SectorReader;
+use crate::single_disk_farm::test_farm::TestFarm;
+use crate::single_disk_farm::{test_farm, Handlers, SingleDiskFarm};
+use async_lock::RwLock as AsyncRwLock;
+use futures::channel::mpsc;
+use futures::StreamExt;
+use std::num::NonZeroU64;
+use std::sync::atomic::AtomicU16;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use std::{fs, io, mem, thread};
+use subspace_core_primitives::{Blake3Hash, SectorIndex};
+use subspace_farmer_components::faulty_file::{Fault, FaultRule, FaultyFile, Operation};
+use subspace_farmer_components::sector::{sector_size, SectorMetadataChecksummed};
+use tempfile::tempdir;
+
+const PIECES_IN_SECTOR: u16 = 1;
+/// Not a multiple of scratch buffer size, such that partial reads are exercised
+const SECTOR_SIZE: u64 = sector_size(PIECES_IN_SECTOR) as u64;
+const SECTOR_COUNT: SectorIndex = 3;
+
+/// Sector reader of `plot_file`, metadata file is not used by integrity checks
+fn sector_reader<PF>(plot_file: PF) -> SectorReader<PF, ()> {
+    SectorReader::new(
+        plot_file,
+        (),
+        MetadataLayout::CURRENT,
+        PIECES_IN_SECTOR,
+        Arc::new(AtomicU16::new(SECTOR_COUNT)),
+    )
+}
+
+/// Metadata of all plotted sectors of farm with `SECTOR_COUNT` sectors
+fn sectors_metadata() -> Vec<SectorMetadataChecksummed> {
+    (0..SECTOR_COUNT)
+        .map(|sector_index| test_farm::sector_metadata(sector_index, PIECES_IN_SECTOR))
+        .collect()
+}
+
+#[test]
+fn flipped_byte() {
+    let tempdir = tempdir().unwrap();
+    TestFarm::new(PIECES_IN_SECTOR, SECTOR_COUNT).create(tempdir.as_ref());
+    let plot_path = tempdir.as_ref().join(SingleDiskFarm::PLOT_FILE);
+    let mut scratch_buffer = vec![0; 4096];
+
+    {
+        let sector_reader = sector_reader(DirectIoFile::open(&plot_path).unwrap());
+        for sector_index in 0..SECTOR_COUNT {
+            let sector = sector_reader.read_sector(sector_index).unwrap();
+            assert_eq!(
+                verify_sector_checksum(&sector, &mut scratch_buffer).unwrap(),
+                None
+            );
+        }
+    }
+
+    test_farm::flip_byte(tempdir.as_ref(), PIECES_IN_SECTOR, 1);
+    let plot = fs::read(&plot_path).unwrap();
+
+    let sector_reader = sector_reader(DirectIoFile::open(&plot_path).unwrap());
+    for sector_index in 0..SECTOR_COUNT {
+        let sector = sector_reader.read_sector(sector_index).unwrap();
+        let maybe_corruption_details =
+            verify_sector_checksum(&sector, &mut scratch_buffer).unwrap();
+
+        if sector_index == 1 {
+            let corruption_details = maybe_corruption_details.unwrap();
+            let sector_end = 2 * SECTOR_SIZE as usize;
+            assert_eq!(
+                corruption_details.expected_checksum,
+                plot[sector_end - mem::size_of::<Blake3Hash>()..sector_end]
+            );
+            assert_ne!(
+                corruption_details.actual_checksum,
+                corruption_details.expected_checksum
+            );
+        } else {
+            assert_eq!(maybe_corruption_details, None);
+        }
+    }
+}
+
+#[test]
+fn position() {
+    let tempdir = tempdir().unwrap();
+    let position_path = tempdir.as_ref().join("position.bin");
+
+    {
+        let mut position = IntegrityScrubPosition::load(Some(position_path.clone())).unwrap();
+        assert_eq!(position.next_sector_index(SECTOR_COUNT), 0);
+        position.advance(0).unwrap();
+        position.advance(1).unwrap();
+        assert_eq!(position.next_sector_index(SECTOR_COUNT), 2);
+    }
+
+    {
+        let mut position = IntegrityScrubPosition::load(Some(position_path.clone())).unwrap();
+        // Resumes from stored position
+        assert_eq!(position.next_sector_index(SECTOR_COUNT), 2);
+        // Wraps around when fewer sectors are plotted
+        assert_eq!(position.next_sector_index(2), 0);
+        position.advance(2).unwrap();
+        assert_eq!(position.next_sector_index(SECTOR_COUNT), 0);
+    }
+
+    // Position is not stored without path
+    let mut position = IntegrityScrubPosition::load(None).unwrap();
+    position.advance(1).unwrap();
+    assert_eq!(position.next_sector_index(SECTOR_COUNT), 2);
+    assert_eq!(
+        IntegrityScrubPosition::load(None)
+            .unwrap()
+            .next_sector_index(SECTOR_COUNT),
+        0
+    );
+}
+
+#[tokio::test]
+async fn scrub_resumes_from_saved_position() {
+    let tempdir = tempdir().unwrap();
+    TestFarm::new(PIECES_IN_SECTOR, SECTOR_COUNT).create(tempdir.as_ref());
+    let plot_path = tempdir.as_ref().join(SingleDiskFarm::PLOT_FILE);
+    let position_path = tempdir.as_ref().join("position.bin");
+    test_farm::flip_byte(tempdir.as_ref(), PIECES_IN_SECTOR, 0);
+    test_farm::flip_byte(tempdir.as_ref(), PIECES_IN_SECTOR, 2);
+
+    // Sector 0 was already checked before restart
+    IntegrityScrubPosition::load(Some(position_path.clone()))
+        .unwrap()
+        .advance(0)
+        .unwrap();
+
+    let handlers = Arc::<Handlers>::default();
+    let (corrupted_sender, mut corrupted_receiver) = mpsc::unbounded();
+    let _handler_id = handlers
+        .sector_update
+        .add(Arc::new(move |(sector_index, sector_update)| {
+            if let SectorUpdate::Corrupted(_) = sector_update {
+                let _ = corrupted_sender.unbounded_send(*sector_index);
+            }
+        }));
+
+    let (queued_sender, mut queued_receiver) = mpsc::unbounded();
+    let scrub_handle = tokio::spawn(integrity_scrub(IntegrityScrubOptions {
+        sector_reader: sector_reader(DirectIoFile::open(&plot_path).unwrap()),
+        position: IntegrityScrubPosition::load(Some(position_path.clone())).unwrap(),
+        rate_limit: NonZeroU64::new(u64::MAX).unwrap(),
+        sectors_metadata: Arc::new(AsyncRwLock::new(sectors_metadata())),
+        modifying_sector_index: Arc::default(),
+        handlers: Arc::clone(&handlers),
+        corrupted_sectors_sender: Some(queued_sender),
+        interrupt: Arc::default(),
+    }));
+
+    // Sector 2 is found first since scrub resumed after sector 0, then it wraps around
+    assert_eq!(corrupted_receiver.next().await, Some(2));
+    assert_eq!(corrupted_receiver.next().await, Some(0));
+    // Corrupted sectors are queued for replotting in the same order
+    assert_eq!(queued_receiver.next().await, Some(2));
+    assert_eq!(queued_receiver.next().await, Some(0));
+
+    scrub_handle.abort();
+    let _ = scrub_handle.await;
+}
+
+#[tokio::test]
+async fn scrub_read_errors_and_corruption() {
+    let tempdir = tempdir().unwrap();
+    TestFarm::new(PIECES_IN_SECTOR, SECTOR_COUNT).create(tempdir.as_ref());
+    let plot_path = tempdir.as_ref().join(SingleDiskFarm::PLOT_FILE);
+
+    let handlers = Arc::<Handlers>::default();
+    let (corrupted_sender, mut corrupted_receiver) = mpsc::unbounded();
+    let _handler_id = handlers
+        .sector_update
+        .add(Arc::new(move |(sector_index, sector_update)| {
+            if let SectorUpdate::Corrupted(_) = sector_update {
+                let _ = corrupted_sender.unbounded_send(*sector_index);
+            }
+        }));
+
+    // Sector 0 can't be read once, sector 2 is read corrupted once, sector 1 is intact
+    let plot_file = Arc::new(FaultyFile::new(DirectIoFile::open(&plot_path).unwrap()));
+    plot_file.inject(
+        FaultRule::new(Operation::Read, Fault::Fail(io::ErrorKind::Other)).offsets(0..SECTOR_SIZE),
+    );
+    plot_file.inject(
+        FaultRule::new(Operation::Read, Fault::Corrupt).offsets(SECTOR_SIZE * 2..SECTOR_SIZE * 3),
+    );
+
+    let scrub_handle = tokio::spawn(integrity_scrub(IntegrityScrubOptions {
+        sector_reader: sector_reader(Arc::clone(&plot_file)),
+        position: IntegrityScrubPosition::load(None).unwrap(),
+        rate_limit: NonZeroU64::new(u64::MAX).unwrap(),
+        sectors_metadata: Arc::new(AsyncRwLock::new(sectors_metadata())),
+        modifying_sector_index: Arc::default(),
+        handlers: Arc::clone(&handlers),
+        corrupted_sectors_sender: None,
+        interrupt: Arc::default(),
+    }));
+
+    // Read error is not reported as corruption, scrub continues with the next sector
+    assert_eq!(corrupted_receiver.next().await, Some(2));
+
+    scrub_handle.abort();
+    let _ = scrub_handle.await;
+
+    let history = plot_file.history();
+    assert_eq!(
+        history[0].fault,
+        Some(Fault::Fail(io::ErrorKind::Other)),
+        "{history:?}"
+    );
+}
+
+#[test]
+fn rate_limiter_interrupt() {
+    let interrupt = Arc::<RateLimiterInterrupt>::default();
+    let mut rate_limiter =
+        RateLimiter::with_interrupt(NonZeroU64::new(1).unwrap(), Arc::clone(&interrupt));
+
+    let start = Instant::now();
+    thread::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(Duration::from_millis(100));
+            interrupt.interrupt();
+        });
+
+        // Would wait for an hour without interruption
+        let error = rate_limiter.on_read(3600).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::Interrupted);
+    });
+    assert!(start.elapsed() < Duration::from_secs(60));
+
+    // Stays interrupted
+    assert_eq!(
+        rate_limiter.on_read(1).unwrap_err().kind(),
+        io::ErrorKind::Interrupted
+    );
+}
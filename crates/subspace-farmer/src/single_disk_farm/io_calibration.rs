@@ -0,0 +1,294 @@
+//! One-time calibration of max request size of farm files on farm open, such that request size
+//! is chosen by measuring the actual disk rather than guessed from its kind (optimal unbuffered
+//! request size varies from 256 KiB on some SATA SSDs to 8 MiB on fast NVMe disks), see
+//! [`SingleDiskFarmOptions::auto_tune_io`](super::SingleDiskFarmOptions::auto_tune_io)
+
+#[cfg(test)]
+mod tests;
+
+use crate::single_disk_farm::direct_io_file::{DirectIoFile, DirectIoOptions, DISK_SECTOR_SIZE};
+use crate::single_disk_farm::read_benchmark::ReadLatencyStats;
+use crate::single_disk_farm::{SingleDiskFarm, SingleDiskFarmInfo};
+use rand::prelude::*;
+use std::ops::Range;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use std::{fmt, fs, io};
+use subspace_farmer_components::file_ext::FileExt;
+use subspace_farmer_components::ReadAtSync;
+use tracing::{debug, info, warn};
+
+/// Request sizes calibration chooses from
+pub const CALIBRATION_REQUEST_SIZES: [usize; 6] = [
+    256 * 1024,
+    512 * 1024,
+    1024 * 1024,
+    2 * 1024 * 1024,
+    4 * 1024 * 1024,
+    8 * 1024 * 1024,
+];
+/// Largest of [`CALIBRATION_REQUEST_SIZES`]
+const MAX_CALIBRATION_REQUEST_SIZE: usize =
+    CALIBRATION_REQUEST_SIZES[CALIBRATION_REQUEST_SIZES.len() - 1];
+/// Default time budget of calibration reads, shared by all request sizes
+pub const DEFAULT_CALIBRATION_TIME_BUDGET: Duration = Duration::from_millis(1500);
+/// Size of the region calibration reads are done from, the beginning of the plot file of existing
+/// farm or a temporary file of a new farm
+const CALIBRATION_REGION_SIZE: u64 = 32 * 1024 * 1024;
+/// Min number of reads of every request size, done even if time budget is exceeded
+const MIN_CALIBRATION_READS: usize = 2;
+/// Max number of reads of every request size
+const MAX_CALIBRATION_READS: usize = 16;
+/// Smaller request size is preferred as long as its throughput is within this fraction of the
+/// best one, smaller requests delay concurrent time-critical reads (like proving) less
+const THROUGHPUT_TOLERANCE: f64 = 0.9;
+
+/// Results of reads of a single request size during calibration
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct CalibratedRequestSize {
+    /// Size of every read
+    pub request_size: usize,
+    /// Latency of reads
+    pub latency: ReadLatencyStats,
+    /// Throughput of reads in bytes per second
+    pub throughput: u64,
+}
+
+/// Report of request size calibration
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RequestSizeCalibration {
+    /// Results of every request size that was tried, in increasing order of request size
+    pub request_sizes: Vec<CalibratedRequestSize>,
+    /// Chosen max request size
+    pub max_request_size: usize,
+    /// How long calibration took
+    pub elapsed: Duration,
+}
+
+impl fmt::Display for RequestSizeCalibration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "max request size {} chosen in {:?}:",
+            bytesize::to_string(self.max_request_size as u64, true),
+            self.elapsed
+        )?;
+        for calibrated in &self.request_sizes {
+            write!(
+                f,
+                " {} at {}/s (p95 {:?})",
+                bytesize::to_string(calibrated.request_size as u64, true),
+                bytesize::to_string(calibrated.throughput, true),
+                calibrated.latency.p95
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Time aligned reads of every one of [`CALIBRATION_REQUEST_SIZES`] that fits into `region` of
+/// `file` at random offsets and choose the smallest request size whose throughput is close to the
+/// best one.
+///
+/// `time_budget` is split evenly between request sizes, but at least [`MIN_CALIBRATION_READS`]
+/// reads of every size are done regardless.
+pub(super) fn calibrate_request_size<F>(
+    file: &F,
+    region: Range<u64>,
+    time_budget: Duration,
+) -> io::Result<RequestSizeCalibration>
+where
+    F: ReadAtSync,
+{
+    let alignment = DISK_SECTOR_SIZE as u64;
+    let region_start = region.start.next_multiple_of(alignment);
+    let region_size = region.end.saturating_sub(region_start);
+    let request_size_time_budget = time_budget / CALIBRATION_REQUEST_SIZES.len() as u32;
+
+    let mut rng = thread_rng();
+    let mut request_sizes = Vec::with_capacity(CALIBRATION_REQUEST_SIZES.len());
+
+    let started_at = Instant::now();
+    for request_size in CALIBRATION_REQUEST_SIZES {
+        if request_size as u64 > region_size {
+            break;
+        }
+
+        let max_offset = region_start + (region_size - request_size as u64) / alignment * alignment;
+        let mut buffer = vec![0; request_size];
+        let mut latencies = Vec::with_capacity(MAX_CALIBRATION_READS);
+        let request_size_started_at = Instant::now();
+
+        while latencies.len() < MIN_CALIBRATION_READS
+            || (latencies.len() < MAX_CALIBRATION_READS
+                && request_size_started_at.elapsed() < request_size_time_budget)
+        {
+            let offset = rng.gen_range(region_start..=max_offset) / alignment * alignment;
+
+            let start = Instant::now();
+            file.read_at(&mut buffer, offset)?;
+            latencies.push(start.elapsed());
+        }
+
+        let total_latency = latencies.iter().sum::<Duration>();
+        let bytes_read = (request_size * latencies.len()) as u64;
+        let throughput = (bytes_read as f64 / total_latency.as_secs_f64().max(f64::EPSILON)) as u64;
+
+        debug!(
+            %request_size,
+            %throughput,
+            reads = %latencies.len(),
+            "Calibrated request size"
+        );
+
+        request_sizes.push(CalibratedRequestSize {
+            request_size,
+            latency: ReadLatencyStats::new(latencies, bytes_read),
+            throughput,
+        });
+    }
+
+    let Some(best_throughput) = request_sizes
+        .iter()
+        .map(|calibrated| calibrated.throughput)
+        .max()
+    else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Calibration region of {region_size} bytes is smaller than the smallest request \
+                size {}",
+                CALIBRATION_REQUEST_SIZES[0]
+            ),
+        ));
+    };
+    let max_request_size = request_sizes
+        .iter()
+        .find(|calibrated| {
+            calibrated.throughput as f64 >= best_throughput as f64 * THROUGHPUT_TOLERANCE
+        })
+        .map(|calibrated| calibrated.request_size)
+        .expect("Request size with the best throughput is always within tolerance; qed");
+
+    Ok(RequestSizeCalibration {
+        request_sizes,
+        max_request_size,
+        elapsed: started_at.elapsed(),
+    })
+}
+
+/// Max request size previously calibrated for the farm in `directory` or a newly calibrated one,
+/// which is recorded in farm info unless farm is read-only. Farm info is not stored, caller stores
+/// it such that subsequent opens skip calibration.
+///
+/// Existing farms are calibrated with reads from the beginning of the plot file, new farms (and
+/// farms whose plot file is too small) with reads from a temporary file that is removed
+/// afterwards. `wrap_file` is applied to the file calibration reads are done from. `None` is
+/// returned if calibration is not possible, in which case max request size of the device profile
+/// is supposed to be used.
+pub(super) fn tune_max_request_size<F, WF>(
+    directory: &Path,
+    single_disk_farm_info: &mut SingleDiskFarmInfo,
+    read_only: bool,
+    time_budget: Duration,
+    wrap_file: WF,
+) -> io::Result<Option<usize>>
+where
+    F: ReadAtSync,
+    WF: FnOnce(DirectIoFile) -> F,
+{
+    if let Some(max_request_size) = single_disk_farm_info.calibrated_max_request_size() {
+        if CALIBRATION_REQUEST_SIZES.contains(&max_request_size) {
+            debug!(%max_request_size, "Using previously calibrated max request size");
+
+            return Ok(Some(max_request_size));
+        }
+
+        warn!(
+            %max_request_size,
+            "Previously calibrated max request size is not supported, calibrating again"
+        );
+    }
+
+    let direct_io_options = DirectIoOptions {
+        max_request_size: Some(MAX_CALIBRATION_REQUEST_SIZE),
+        scratch_buffers: Some(1),
+        read_only,
+        ..DirectIoOptions::default()
+    };
+
+    let plot_file_path = directory.join(SingleDiskFarm::PLOT_FILE);
+    let existing_plot_file_size = match fs::metadata(&plot_file_path) {
+        Ok(metadata) => metadata.len(),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => 0,
+        Err(error) => {
+            return Err(error);
+        }
+    };
+
+    let calibration = if existing_plot_file_size >= CALIBRATION_REGION_SIZE {
+        let file = DirectIoFile::open_with_options(&plot_file_path, direct_io_options)?;
+
+        calibrate_request_size(&wrap_file(file), 0..CALIBRATION_REGION_SIZE, time_budget)?
+    } else if read_only {
+        debug!("Plot file is too small to calibrate max request size of read-only farm");
+
+        return Ok(None);
+    } else {
+        let calibration_file_path = directory.join(SingleDiskFarm::IO_CALIBRATION_FILE);
+        let calibration = calibrate_with_temporary_file(
+            &calibration_file_path,
+            direct_io_options,
+            time_budget,
+            wrap_file,
+        );
+        // File may have been left behind even if calibration failed
+        if let Err(error) = fs::remove_file(&calibration_file_path) {
+            if error.kind() != io::ErrorKind::NotFound {
+                warn!(
+                    path = %calibration_file_path.display(),
+                    %error,
+                    "Failed to remove temporary calibration file"
+                );
+            }
+        }
+
+        calibration?
+    };
+
+    info!(%calibration, "Calibrated max request size");
+
+    if !read_only {
+        let SingleDiskFarmInfo::V0 {
+            calibrated_max_request_size,
+            ..
+        } = single_disk_farm_info;
+        calibrated_max_request_size.replace(calibration.max_request_size);
+    }
+
+    Ok(Some(calibration.max_request_size))
+}
+
+fn calibrate_with_temporary_file<F, WF>(
+    calibration_file_path: &Path,
+    direct_io_options: DirectIoOptions,
+    time_budget: Duration,
+    wrap_file: WF,
+) -> io::Result<RequestSizeCalibration>
+where
+    F: ReadAtSync,
+    WF: FnOnce(DirectIoFile) -> F,
+{
+    let file = DirectIoFile::open_with_options(calibration_file_path, direct_io_options)?;
+    file.preallocate(CALIBRATION_REGION_SIZE)?;
+    // Region is written rather than only preallocated, many file systems return zeroes for
+    // unwritten extents without reaching the disk at all
+    let chunk = vec![0; MAX_CALIBRATION_REQUEST_SIZE];
+    for offset in (0..CALIBRATION_REGION_SIZE).step_by(chunk.len()) {
+        file.write_all_at(&chunk, offset)?;
+    }
+    file.sync_data()?;
+
+    calibrate_request_size(&wrap_file(file), 0..CALIBRATION_REGION_SIZE, time_budget)
+}
@@ -0,0 +1,140 @@
+use crate::farm::FarmId;
+use crate::single_disk_farm::direct_io_file::DirectIoFile;
+use crate::single_disk_farm::io_calibration::{
+    calibrate_request_size, tune_max_request_size, CALIBRATION_REGION_SIZE,
+    CALIBRATION_REQUEST_SIZES, MIN_CALIBRATION_READS,
+};
+use crate::single_disk_farm::{SingleDiskFarm, SingleDiskFarmInfo};
+use rand::prelude::*;
+use std::path::Path;
+use std::time::Duration;
+use std::{fs, io, thread};
+use subspace_farmer_components::ReadAtSync;
+use tempfile::tempdir;
+
+/// Delay of reads of every request size other than the fastest one
+const SLOW_READ_DELAY: Duration = Duration::from_millis(50);
+const TIME_BUDGET: Duration = Duration::from_millis(60);
+
+/// File whose reads of `fastest_request_size` are done right away, while reads of any other size
+/// are delayed by [`SLOW_READ_DELAY`]
+struct SizeDependentFile {
+    file: DirectIoFile,
+    fastest_request_size: usize,
+}
+
+impl ReadAtSync for SizeDependentFile {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        if buf.len() != self.fastest_request_size {
+            thread::sleep(SLOW_READ_DELAY);
+        }
+        self.file.read_at(buf, offset)
+    }
+}
+
+fn create_farm_info(directory: &Path) -> SingleDiskFarmInfo {
+    let info = SingleDiskFarmInfo::new(FarmId::new(), [0; 32], Default::default(), 1, 0);
+    info.store_to(directory).unwrap();
+    info
+}
+
+fn stored_calibrated_max_request_size(directory: &Path) -> Option<usize> {
+    SingleDiskFarmInfo::load_from(directory)
+        .unwrap()
+        .unwrap()
+        .calibrated_max_request_size()
+}
+
+#[test]
+fn new_farm_calibrated_once() {
+    let tempdir = tempdir().unwrap();
+    let directory = tempdir.as_ref();
+    let mut info = create_farm_info(directory);
+
+    let fastest_request_size = 1024 * 1024;
+    let max_request_size =
+        tune_max_request_size(directory, &mut info, false, TIME_BUDGET, |file| {
+            SizeDependentFile {
+                file,
+                fastest_request_size,
+            }
+        })
+        .unwrap();
+    assert_eq!(max_request_size, Some(fastest_request_size));
+    assert_eq!(
+        info.calibrated_max_request_size(),
+        Some(fastest_request_size)
+    );
+    // Farm info is stored by the caller once farm files are created
+    assert_eq!(stored_calibrated_max_request_size(directory), None);
+    // Temporary file is removed after calibration
+    assert!(!directory.join(SingleDiskFarm::IO_CALIBRATION_FILE).exists());
+
+    // Stored choice is reused without calibrating again, even though the disk changed
+    let max_request_size =
+        tune_max_request_size(directory, &mut info, false, TIME_BUDGET, |file| {
+            SizeDependentFile {
+                file,
+                fastest_request_size: CALIBRATION_REQUEST_SIZES[0],
+            }
+        })
+        .unwrap();
+    assert_eq!(max_request_size, Some(fastest_request_size));
+    assert!(!directory.join(SingleDiskFarm::IO_CALIBRATION_FILE).exists());
+}
+
+#[test]
+fn read_only_farm_not_persisted() {
+    let tempdir = tempdir().unwrap();
+    let directory = tempdir.as_ref();
+    let mut info = create_farm_info(directory);
+    let plot_file_path = directory.join(SingleDiskFarm::PLOT_FILE);
+
+    // Plot file is too small and read-only farm can't create temporary file
+    fs::write(&plot_file_path, vec![0; CALIBRATION_REQUEST_SIZES[0]]).unwrap();
+    assert_eq!(
+        tune_max_request_size(directory, &mut info, true, TIME_BUDGET, |file| file).unwrap(),
+        None
+    );
+
+    let mut plot = vec![0; CALIBRATION_REGION_SIZE as usize];
+    thread_rng().fill(plot.as_mut_slice());
+    fs::write(&plot_file_path, plot).unwrap();
+
+    let fastest_request_size = 4 * 1024 * 1024;
+    let max_request_size = tune_max_request_size(directory, &mut info, true, TIME_BUDGET, |file| {
+        SizeDependentFile {
+            file,
+            fastest_request_size,
+        }
+    })
+    .unwrap();
+    assert_eq!(max_request_size, Some(fastest_request_size));
+    assert_eq!(info.calibrated_max_request_size(), None);
+    assert_eq!(stored_calibrated_max_request_size(directory), None);
+}
+
+#[test]
+fn only_request_sizes_that_fit_are_tried() {
+    let tempdir = tempdir().unwrap();
+    let file_path = tempdir.as_ref().join("file.bin");
+    fs::write(&file_path, vec![0; 2 * 1024 * 1024]).unwrap();
+    let file = DirectIoFile::open(&file_path).unwrap();
+
+    let calibration = calibrate_request_size(&file, 0..1024 * 1024, TIME_BUDGET).unwrap();
+    assert_eq!(
+        calibration
+            .request_sizes
+            .iter()
+            .map(|calibrated| calibrated.request_size)
+            .collect::<Vec<_>>(),
+        CALIBRATION_REQUEST_SIZES[..3]
+    );
+    for calibrated in &calibration.request_sizes {
+        assert!(calibrated.latency.reads >= MIN_CALIBRATION_READS as u64);
+    }
+    assert!(CALIBRATION_REQUEST_SIZES[..3].contains(&calibration.max_request_size));
+
+    let error = calibrate_request_size(&file, 4096..128 * 1024, TIME_BUDGET).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+}
@@ -0,0 +1,214 @@
+//! Write-ahead journal of metadata file updates stored in reserved space of the metadata file,
+//! such that update torn by power loss (which with read-modify-write of unbuffered I/O may affect
+//! more than the bytes being updated) is repaired on the next start, see [`MetadataJournal`]
+
+#[cfg(test)]
+mod tests;
+
+use crate::single_disk_farm::direct_io_file::DISK_SECTOR_SIZE;
+use crate::single_disk_farm::farm_version::METADATA_VERSION_HEADER_OFFSET;
+use parking_lot::Mutex;
+use std::ops::Range;
+use std::{io, mem};
+use subspace_core_primitives::{Blake3Hash, BLAKE3_HASH_SIZE};
+use subspace_farmer_components::file_ext::FileExt;
+use subspace_farmer_components::WriteAtSync;
+use tracing::{debug, info};
+
+/// Region of the metadata file occupied by the journal, reserved space between the header (which is
+/// padded to a whole disk sector) and farm version header
+pub(super) const JOURNAL_REGION: Range<u64> =
+    DISK_SECTOR_SIZE as u64..METADATA_VERSION_HEADER_OFFSET;
+/// Size of the header of every entry: sequence number, offset and length of the update followed by
+/// checksum of all of them and updated bytes
+const ENTRY_HEADER_SIZE: usize =
+    mem::size_of::<u64>() * 2 + mem::size_of::<u32>() + BLAKE3_HASH_SIZE;
+
+/// Size of entry with `len` bytes of update, entries are padded to whole disk sectors, such that
+/// every entry is written with a single aligned write
+fn entry_size(len: usize) -> usize {
+    (ENTRY_HEADER_SIZE + len).next_multiple_of(DISK_SECTOR_SIZE)
+}
+
+fn entry_checksum(sequence: u64, offset: u64, bytes: &[u8]) -> Blake3Hash {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&sequence.to_le_bytes());
+    hasher.update(&offset.to_le_bytes());
+    hasher.update(&(bytes.len() as u32).to_le_bytes());
+    hasher.update(bytes);
+    hasher.finalize().into()
+}
+
+fn encode_entry(sequence: u64, offset: u64, bytes: &[u8]) -> Vec<u8> {
+    let mut entry = Vec::with_capacity(entry_size(bytes.len()));
+    entry.extend_from_slice(&sequence.to_le_bytes());
+    entry.extend_from_slice(&offset.to_le_bytes());
+    entry.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    entry.extend_from_slice(&entry_checksum(sequence, offset, bytes));
+    entry.extend_from_slice(bytes);
+    entry.resize(entry_size(bytes.len()), 0);
+    entry
+}
+
+/// Decode entry at the beginning of `bytes`, returns sequence number, offset and updated bytes,
+/// `None` if there is no entry or it is torn
+fn decode_entry(bytes: &[u8]) -> Option<(u64, u64, &[u8])> {
+    let (sequence, bytes) = bytes.split_first_chunk::<8>()?;
+    let (offset, bytes) = bytes.split_first_chunk::<8>()?;
+    let (len, bytes) = bytes.split_first_chunk::<4>()?;
+    let (checksum, bytes) = bytes.split_first_chunk::<BLAKE3_HASH_SIZE>()?;
+
+    let sequence = u64::from_le_bytes(*sequence);
+    let offset = u64::from_le_bytes(*offset);
+    let updated_bytes = bytes.get(..u32::from_le_bytes(*len) as usize)?;
+
+    (entry_checksum(sequence, offset, updated_bytes) == *checksum).then_some((
+        sequence,
+        offset,
+        updated_bytes,
+    ))
+}
+
+/// Whether update of `len` bytes at `offset` overlaps with the journal itself
+fn overlaps_journal(offset: u64, len: usize) -> bool {
+    offset < JOURNAL_REGION.end && offset.saturating_add(len as u64) > JOURNAL_REGION.start
+}
+
+#[derive(Debug)]
+struct Cursor {
+    /// Offset at which the next entry is written
+    position: u64,
+    /// Sequence number of the last written entry
+    sequence: u64,
+}
+
+/// Write-ahead journal of metadata file updates.
+///
+/// Every update is appended to the journal with a sequence number and checksum, synced and only
+/// then written to its home location, such that update torn by power loss is either ignored (if
+/// journal entry is torn, home location is not written yet) or repaired by
+/// [`Self::recover()`] on the next start. Updates are serialized and home location of every update
+/// is synced together with the journal entry of the following update, such that only the latest
+/// update may need to be repaired. Journal wraps around once its end is reached, older entries
+/// that remain in the journal are never replayed.
+#[derive(Debug)]
+pub(super) struct MetadataJournal<F> {
+    file: F,
+    cursor: Mutex<Cursor>,
+}
+
+impl<F> WriteAtSync for MetadataJournal<F>
+where
+    F: FileExt + Send + Sync,
+{
+    fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        let entry_size = entry_size(buf.len()) as u64;
+        if entry_size > JOURNAL_REGION.end - JOURNAL_REGION.start
+            || overlaps_journal(offset, buf.len())
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Update of {} bytes at offset {offset} can't be journaled",
+                    buf.len()
+                ),
+            ));
+        }
+
+        let mut cursor = self.cursor.lock();
+        if cursor.position + entry_size > JOURNAL_REGION.end {
+            cursor.position = JOURNAL_REGION.start;
+        }
+        cursor.sequence += 1;
+        self.file
+            .write_all_at(&encode_entry(cursor.sequence, offset, buf), cursor.position)?;
+        cursor.position += entry_size;
+
+        // Home location is only written once update is persisted in the journal
+        self.file.sync_data()?;
+        self.file.write_all_at(buf, offset)
+    }
+
+    fn sync_data(&self) -> io::Result<()> {
+        self.file.sync_data()
+    }
+}
+
+impl<F> MetadataJournal<F>
+where
+    F: FileExt,
+{
+    /// Journal of updates of `file`, journal region of which must be empty, see
+    /// [`Self::recover()`]
+    pub(super) fn new(file: F) -> Self {
+        Self {
+            file,
+            cursor: Mutex::new(Cursor {
+                position: JOURNAL_REGION.start,
+                sequence: 0,
+            }),
+        }
+    }
+
+    /// Replay journal of `file` if `replay` is `true` (journal is supposed to be replayed after
+    /// unclean shutdown) and clear it, returns number of replayed entries.
+    ///
+    /// Only the entry with the highest sequence number among entries that are not torn is applied,
+    /// since home locations of all earlier updates were synced before it was appended, while
+    /// earlier entries that were not overwritten yet may be older than updates that are already
+    /// applied. Entry past the end of the file (that was shrunk since) is skipped. Replay is
+    /// idempotent, such that crash during replay is handled by replaying again. Journal is cleared
+    /// afterwards, such that entries are never replayed over updates done without journal later
+    /// (like during farm resizing).
+    pub(super) fn recover(file: &F, replay: bool) -> io::Result<usize> {
+        let file_size = file.size()?;
+        if file_size < JOURNAL_REGION.end {
+            debug!(%file_size, "Metadata file is too small to contain journal");
+            return Ok(0);
+        }
+
+        let mut journal = vec![0; (JOURNAL_REGION.end - JOURNAL_REGION.start) as usize];
+        file.read_exact_at(&mut journal, JOURNAL_REGION.start)?;
+
+        let mut replayed = 0;
+        if replay {
+            let mut latest_entry = None::<(u64, u64, &[u8])>;
+            let mut position = 0;
+            while position < journal.len() {
+                match decode_entry(&journal[position..]) {
+                    Some(entry) => {
+                        position += entry_size(entry.2.len());
+                        if latest_entry.map_or(true, |(sequence, _, _)| sequence < entry.0) {
+                            latest_entry.replace(entry);
+                        }
+                    }
+                    None => {
+                        position += DISK_SECTOR_SIZE;
+                    }
+                }
+            }
+
+            if let Some((sequence, offset, bytes)) = latest_entry {
+                if overlaps_journal(offset, bytes.len())
+                    || offset.saturating_add(bytes.len() as u64) > file_size
+                {
+                    debug!(%sequence, %offset, "Skipping journal entry outside of metadata file");
+                } else {
+                    file.write_all_at(bytes, offset)?;
+                    file.sync_data()?;
+                    replayed += 1;
+
+                    info!(%sequence, "Replayed metadata journal after unclean shutdown");
+                }
+            }
+        }
+
+        if journal.iter().any(|&byte| byte != 0) {
+            journal.fill(0);
+            file.write_all_at(&journal, JOURNAL_REGION.start)?;
+            file.sync_data()?;
+        }
+
+        Ok(replayed)
+    }
+}
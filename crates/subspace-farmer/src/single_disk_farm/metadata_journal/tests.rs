@@ -0,0 +1,167 @@
+use crate::single_disk_farm::direct_io_file::{DirectIoFile, DISK_SECTOR_SIZE};
+use crate::single_disk_farm::metadata_journal::{MetadataJournal, JOURNAL_REGION};
+use crate::single_disk_farm::RESERVED_PLOT_METADATA;
+use rand::prelude::*;
+use std::path::Path;
+use std::sync::Arc;
+use std::{fs, io};
+use subspace_farmer_components::faulty_file::{Fault, FaultRule, FaultyFile, Operation};
+use subspace_farmer_components::file_ext::FileExt;
+use subspace_farmer_components::WriteAtSync;
+use tempfile::tempdir;
+
+/// Number of sector metadata slots after reserved space
+const SLOTS: u64 = 4;
+const SLOT_SIZE: usize = DISK_SECTOR_SIZE;
+
+fn slot_offset(slot: u64) -> u64 {
+    RESERVED_PLOT_METADATA + slot * SLOT_SIZE as u64
+}
+
+fn random_update() -> Vec<u8> {
+    let mut update = vec![0; SLOT_SIZE];
+    thread_rng().fill(update.as_mut_slice());
+    update
+}
+
+fn open_metadata_file(directory: &Path) -> Arc<FaultyFile<DirectIoFile>> {
+    let path = directory.join("metadata.bin");
+    fs::write(&path, vec![0; slot_offset(SLOTS) as usize]).unwrap();
+    Arc::new(FaultyFile::new(DirectIoFile::open(&path).unwrap()))
+}
+
+fn read_slot(file: &FaultyFile<DirectIoFile>, slot: u64) -> Vec<u8> {
+    let mut bytes = vec![0; SLOT_SIZE];
+    FileExt::read_exact_at(file, &mut bytes, slot_offset(slot)).unwrap();
+    bytes
+}
+
+fn read_journal(file: &FaultyFile<DirectIoFile>) -> Vec<u8> {
+    let mut bytes = vec![0; (JOURNAL_REGION.end - JOURNAL_REGION.start) as usize];
+    FileExt::read_exact_at(file, &mut bytes, JOURNAL_REGION.start).unwrap();
+    bytes
+}
+
+#[test]
+fn crash_between_journal_append_and_home_write() {
+    let tempdir = tempdir().unwrap();
+    let file = open_metadata_file(tempdir.as_ref());
+    let journal = MetadataJournal::new(Arc::clone(&file));
+
+    let first_update = random_update();
+    journal.write_all_at(&first_update, slot_offset(0)).unwrap();
+    assert_eq!(read_slot(&file, 0), first_update);
+
+    // Crash after update is appended to the journal, but before it reaches home location
+    file.inject(
+        FaultRule::new(Operation::Write, Fault::Fail(io::ErrorKind::Other))
+            .offsets(slot_offset(1)..slot_offset(2)),
+    );
+    let second_update = random_update();
+    journal
+        .write_all_at(&second_update, slot_offset(1))
+        .unwrap_err();
+    drop(journal);
+    assert_eq!(read_slot(&file, 1), vec![0; SLOT_SIZE]);
+
+    // Journal is not replayed after clean shutdown, but it is still cleared
+    let snapshot = read_journal(&file);
+    assert_eq!(MetadataJournal::recover(&file, false).unwrap(), 0);
+    assert_eq!(read_slot(&file, 1), vec![0; SLOT_SIZE]);
+    assert!(read_journal(&file).iter().all(|&byte| byte == 0));
+
+    // Replay after unclean shutdown restores the latest update, the first one was synced already
+    FileExt::write_all_at(file.as_ref(), &snapshot, JOURNAL_REGION.start).unwrap();
+    assert_eq!(MetadataJournal::recover(&file, true).unwrap(), 1);
+    assert_eq!(read_slot(&file, 0), first_update);
+    assert_eq!(read_slot(&file, 1), second_update);
+    assert!(read_journal(&file).iter().all(|&byte| byte == 0));
+
+    // Nothing is left to replay
+    assert_eq!(MetadataJournal::recover(&file, true).unwrap(), 0);
+    assert_eq!(read_slot(&file, 1), second_update);
+}
+
+#[test]
+fn torn_journal_entry_ignored() {
+    let tempdir = tempdir().unwrap();
+    let file = open_metadata_file(tempdir.as_ref());
+    let journal = MetadataJournal::new(Arc::clone(&file));
+
+    let first_update = random_update();
+    journal.write_all_at(&first_update, slot_offset(0)).unwrap();
+
+    // Journal entry of the second update is torn and crash happens before home location is written
+    file.inject(FaultRule::new(Operation::Write, Fault::Corrupt).offsets(JOURNAL_REGION));
+    file.inject(
+        FaultRule::new(Operation::Write, Fault::Fail(io::ErrorKind::Other))
+            .offsets(slot_offset(0)..slot_offset(1)),
+    );
+    journal
+        .write_all_at(&random_update(), slot_offset(0))
+        .unwrap_err();
+    drop(journal);
+
+    assert_eq!(MetadataJournal::recover(&file, true).unwrap(), 1);
+    assert_eq!(read_slot(&file, 0), first_update);
+}
+
+#[test]
+fn wrap_around_replays_only_latest_update() {
+    let tempdir = tempdir().unwrap();
+    let file = open_metadata_file(tempdir.as_ref());
+    let journal = MetadataJournal::new(Arc::clone(&file));
+
+    // Enough updates of one and two slots for journal to wrap around multiple times at different
+    // positions, leaving older entries behind at the end of the journal
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut latest_contents = vec![0; SLOT_SIZE * SLOTS as usize];
+    let journal_entries = (JOURNAL_REGION.end - JOURNAL_REGION.start) / (SLOT_SIZE as u64 * 2);
+    let mut last_update = (0, 0);
+    for _ in 0..journal_entries * 3 {
+        let slots = rng.gen_range(1..=2);
+        let slot = rng.gen_range(0..=SLOTS - slots);
+        let mut update = vec![0; SLOT_SIZE * slots as usize];
+        rng.fill(update.as_mut_slice());
+        journal.write_all_at(&update, slot_offset(slot)).unwrap();
+        latest_contents[SLOT_SIZE * slot as usize..][..update.len()].copy_from_slice(&update);
+        last_update = (slot, slots);
+    }
+    drop(journal);
+
+    // Home location of the latest update is reverted to simulate update that was not applied
+    let (slot, slots) = last_update;
+    FileExt::write_all_at(
+        file.as_ref(),
+        &vec![0; SLOT_SIZE * slots as usize],
+        slot_offset(slot),
+    )
+    .unwrap();
+
+    // Replay is idempotent and results in the latest contents, older entries that are still in
+    // the journal are not replayed over updates that followed them
+    let snapshot = read_journal(&file);
+    for _ in 0..2 {
+        FileExt::write_all_at(file.as_ref(), &snapshot, JOURNAL_REGION.start).unwrap();
+        assert_eq!(MetadataJournal::recover(&file, true).unwrap(), 1);
+        let contents = (0..SLOTS)
+            .flat_map(|slot| read_slot(&file, slot))
+            .collect::<Vec<_>>();
+        assert!(contents == latest_contents);
+    }
+}
+
+#[test]
+fn update_of_journal_rejected() {
+    let tempdir = tempdir().unwrap();
+    let file = open_metadata_file(tempdir.as_ref());
+    let journal = MetadataJournal::new(Arc::clone(&file));
+
+    journal
+        .write_all_at(&random_update(), JOURNAL_REGION.start)
+        .unwrap_err();
+    journal
+        .write_all_at(&vec![0; RESERVED_PLOT_METADATA as usize], slot_offset(0))
+        .unwrap_err();
+    assert!(read_journal(&file).iter().all(|&byte| byte == 0));
+}
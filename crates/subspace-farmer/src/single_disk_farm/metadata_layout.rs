@@ -0,0 +1,227 @@
+//! Layout of the metadata file: header at the beginning of reserved space followed by metadata
+//! records of all sectors at fixed offsets, see [`MetadataLayout`]
+
+#[cfg(test)]
+mod tests;
+
+use crate::single_disk_farm::direct_io_file::DISK_SECTOR_SIZE;
+use crate::single_disk_farm::{PlotMetadataHeader, RESERVED_PLOT_METADATA};
+use parity_scale_codec::{Decode, Encode};
+use std::ffi::OsString;
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+use subspace_core_primitives::SectorIndex;
+use subspace_farmer_components::file_ext::FileExt;
+use subspace_farmer_components::sector::SectorMetadataChecksummed;
+use tracing::info;
+
+/// Suffix of the file metadata file is migrated into before it replaces the original
+const MIGRATION_FILE_SUFFIX: &str = ".migration";
+
+/// Layout of the metadata file, identified by the version in [`PlotMetadataHeader`]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(super) enum MetadataLayout {
+    /// Sector metadata records are packed back to back, such that every update of the header or of
+    /// a record covers disk sectors partially and needs read-modify-write with direct I/O
+    Unaligned,
+    /// Header and every sector metadata record are padded to whole disk sectors, such that every
+    /// update is a single aligned write
+    Aligned,
+}
+
+impl MetadataLayout {
+    /// Layout new metadata files are created with and older ones are migrated to
+    pub(super) const CURRENT: Self = Self::Aligned;
+
+    /// Layout of metadata file with specified version, `None` if version is not supported
+    pub(super) fn from_version(version: u8) -> Option<Self> {
+        match version {
+            0 => Some(Self::Unaligned),
+            1 => Some(Self::Aligned),
+            _ => None,
+        }
+    }
+
+    /// Version of the layout stored in [`PlotMetadataHeader`]
+    pub(super) fn version(self) -> u8 {
+        match self {
+            Self::Unaligned => 0,
+            Self::Aligned => 1,
+        }
+    }
+
+    /// Space occupied by metadata of one sector
+    pub(super) fn sector_metadata_slot_size(self) -> u64 {
+        let sector_metadata_size = SectorMetadataChecksummed::encoded_size() as u64;
+
+        match self {
+            Self::Unaligned => sector_metadata_size,
+            Self::Aligned => sector_metadata_size.next_multiple_of(DISK_SECTOR_SIZE as u64),
+        }
+    }
+
+    /// Offset of sector metadata in metadata file
+    pub(super) fn sector_metadata_offset(self, sector_index: SectorIndex) -> u64 {
+        RESERVED_PLOT_METADATA + self.sector_metadata_slot_size() * u64::from(sector_index)
+    }
+
+    /// Size of metadata file with space for metadata of `sector_count` sectors, not aligned to
+    /// disk sectors for [`Self::Unaligned`]
+    pub(super) fn metadata_size(self, sector_count: SectorIndex) -> u64 {
+        self.sector_metadata_offset(sector_count)
+    }
+
+    /// Number of sectors metadata file of `metadata_size` bytes has space for
+    pub(super) fn sector_count(self, metadata_size: u64) -> u64 {
+        metadata_size.saturating_sub(RESERVED_PLOT_METADATA) / self.sector_metadata_slot_size()
+    }
+
+    /// Encode header, padded to disk sector for [`Self::Aligned`]
+    pub(super) fn encode_header(self, metadata_header: &PlotMetadataHeader) -> Vec<u8> {
+        let mut bytes = metadata_header.encode();
+        if self == Self::Aligned {
+            bytes.resize(DISK_SECTOR_SIZE, 0);
+        }
+
+        bytes
+    }
+
+    /// Pad encoded sector metadata to the size of the slot
+    pub(super) fn pad_sector_metadata(self, mut sector_metadata: Vec<u8>) -> Vec<u8> {
+        sector_metadata.resize(self.sector_metadata_slot_size() as usize, 0);
+        sector_metadata
+    }
+
+    /// Encode sector metadata, padded to the size of the slot
+    pub(super) fn encode_sector_metadata(
+        self,
+        sector_metadata: &SectorMetadataChecksummed,
+    ) -> Vec<u8> {
+        self.pad_sector_metadata(sector_metadata.encode())
+    }
+}
+
+/// Migrate metadata file at `path` to [`MetadataLayout::CURRENT`] if it uses older layout, returns
+/// `true` if file was migrated.
+///
+/// Migrated file is written next to the original, synced and renamed over it, such that crash at
+/// any point leaves either the original or fully migrated file behind. Leftover of interrupted
+/// migration is removed and migration starts over. Files that don't exist, can't be decoded or
+/// have unsupported version are left as is for the caller to handle.
+pub(super) fn migrate_metadata_file(path: &Path) -> io::Result<bool> {
+    let migration_path = migration_file_path(path);
+    if !write_migrated_metadata_file(path, &migration_path)? {
+        return Ok(false);
+    }
+
+    fs::rename(&migration_path, path)?;
+    sync_parent_directory(path)?;
+
+    info!(path = %path.display(), "Metadata file migrated to aligned layout");
+
+    Ok(true)
+}
+
+/// Path of the file metadata file at `path` is migrated into
+fn migration_file_path(path: &Path) -> PathBuf {
+    let mut migration_path = OsString::from(path.as_os_str());
+    migration_path.push(MIGRATION_FILE_SUFFIX);
+    PathBuf::from(migration_path)
+}
+
+/// Write contents of metadata file at `path` in [`MetadataLayout::CURRENT`] into a synced file at
+/// `migration_path`, returns `false` if migration is not necessary
+fn write_migrated_metadata_file(path: &Path, migration_path: &Path) -> io::Result<bool> {
+    // Original file is only replaced after migrated file is fully written, so leftover of
+    // interrupted migration is incomplete and useless
+    match fs::remove_file(migration_path) {
+        Ok(()) => {
+            info!(
+                path = %migration_path.display(),
+                "Removed leftover of interrupted metadata file migration"
+            );
+        }
+        Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+        Err(error) => {
+            return Err(error);
+        }
+    }
+
+    let metadata_file = match File::open(path) {
+        Ok(metadata_file) => metadata_file,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => {
+            return Ok(false);
+        }
+        Err(error) => {
+            return Err(error);
+        }
+    };
+    let metadata_size = metadata_file.size()?;
+    if metadata_size < PlotMetadataHeader::encoded_size() as u64 {
+        return Ok(false);
+    }
+
+    let mut metadata_header_bytes = vec![0; PlotMetadataHeader::encoded_size()];
+    metadata_file.read_exact_at(&mut metadata_header_bytes, 0)?;
+    let Ok(mut metadata_header) = PlotMetadataHeader::decode(&mut metadata_header_bytes.as_ref())
+    else {
+        return Ok(false);
+    };
+    let Some(layout) = MetadataLayout::from_version(metadata_header.version) else {
+        return Ok(false);
+    };
+    if layout == MetadataLayout::CURRENT {
+        return Ok(false);
+    }
+
+    info!(
+        path = %path.display(),
+        version = %metadata_header.version,
+        "Migrating metadata file to aligned layout"
+    );
+
+    let sector_count =
+        SectorIndex::try_from(layout.sector_count(metadata_size)).unwrap_or(SectorIndex::MAX - 1);
+    metadata_header.version = MetadataLayout::CURRENT.version();
+    metadata_header.plotted_sector_count = metadata_header.plotted_sector_count.min(sector_count);
+
+    let migrated_file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(migration_path)?;
+    migrated_file.preallocate(MetadataLayout::CURRENT.metadata_size(sector_count))?;
+    migrated_file.write_all_at(&MetadataLayout::CURRENT.encode_header(&metadata_header), 0)?;
+
+    let mut sector_metadata_bytes = vec![0; SectorMetadataChecksummed::encoded_size()];
+    for sector_index in 0..metadata_header.plotted_sector_count {
+        metadata_file.read_exact_at(
+            &mut sector_metadata_bytes,
+            layout.sector_metadata_offset(sector_index),
+        )?;
+        // Records are copied as is, those that fail to decode are handled on open like before
+        migrated_file.write_all_at(
+            &sector_metadata_bytes,
+            MetadataLayout::CURRENT.sector_metadata_offset(sector_index),
+        )?;
+    }
+
+    migrated_file.sync_all()?;
+
+    Ok(true)
+}
+
+/// Sync directory containing `path`, such that rename of the file is persisted
+fn sync_parent_directory(path: &Path) -> io::Result<()> {
+    // Directories can't be opened as files on Windows, rename is persisted with the file there
+    if cfg!(unix) {
+        if let Some(directory) = path
+            .parent()
+            .filter(|directory| !directory.as_os_str().is_empty())
+        {
+            File::open(directory)?.sync_all()?;
+        }
+    }
+
+    Ok(())
+}
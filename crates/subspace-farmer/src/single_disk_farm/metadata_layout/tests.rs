@@ -0,0 +1,213 @@
+use crate::single_disk_farm::direct_io_file::{DirectIoFile, DISK_SECTOR_SIZE};
+use crate::single_disk_farm::metadata_layout::{
+    migrate_metadata_file, migration_file_path, write_migrated_metadata_file, MetadataLayout,
+};
+use crate::single_disk_farm::{PlotMetadataHeader, RESERVED_PLOT_METADATA};
+use parity_scale_codec::{Decode, Encode};
+use std::fs;
+use std::num::NonZeroU64;
+use std::path::Path;
+use subspace_core_primitives::{HistorySize, Record, SectorIndex};
+use subspace_farmer_components::file_ext::FileExt;
+use subspace_farmer_components::sector::{SectorMetadata, SectorMetadataChecksummed};
+use tempfile::tempdir;
+
+const SECTOR_COUNT: SectorIndex = 4;
+const PLOTTED_SECTOR_COUNT: SectorIndex = 3;
+
+fn sector_metadata(sector_index: SectorIndex) -> SectorMetadataChecksummed {
+    let mut s_bucket_sizes = Box::new([0; Record::NUM_S_BUCKETS]);
+    s_bucket_sizes[usize::from(sector_index)] = 1;
+
+    SectorMetadataChecksummed::from(SectorMetadata {
+        sector_index,
+        pieces_in_sector: 1,
+        s_bucket_sizes,
+        history_size: HistorySize::new(NonZeroU64::new(u64::from(sector_index) + 1).unwrap()),
+    })
+}
+
+/// Write metadata file in unaligned layout with space for `SECTOR_COUNT` sectors, of which
+/// `PLOTTED_SECTOR_COUNT` are plotted
+fn write_unaligned_metadata_file(path: &Path) -> Vec<u8> {
+    let layout = MetadataLayout::Unaligned;
+    let mut metadata = layout.encode_header(&PlotMetadataHeader {
+        version: layout.version(),
+        plotted_sector_count: PLOTTED_SECTOR_COUNT,
+    });
+    metadata.resize(RESERVED_PLOT_METADATA as usize, 0);
+    for sector_index in 0..PLOTTED_SECTOR_COUNT {
+        metadata.extend_from_slice(&layout.encode_sector_metadata(&sector_metadata(sector_index)));
+    }
+    // Like metadata file of the farm, aligned to disk sector size
+    metadata.resize(
+        (layout.metadata_size(SECTOR_COUNT) as usize).next_multiple_of(DISK_SECTOR_SIZE),
+        0,
+    );
+
+    fs::write(path, &metadata).unwrap();
+    metadata
+}
+
+fn assert_migrated(path: &Path) {
+    let layout = MetadataLayout::CURRENT;
+    let metadata = fs::read(path).unwrap();
+    assert_eq!(metadata.len() as u64, layout.metadata_size(SECTOR_COUNT));
+
+    let metadata_header = PlotMetadataHeader::decode(&mut metadata.as_slice()).unwrap();
+    assert_eq!(metadata_header.version, layout.version());
+    assert_eq!(metadata_header.plotted_sector_count, PLOTTED_SECTOR_COUNT);
+
+    for sector_index in 0..PLOTTED_SECTOR_COUNT {
+        let offset = layout.sector_metadata_offset(sector_index) as usize;
+        let sector_metadata_bytes = sector_metadata(sector_index).encode();
+        assert_eq!(
+            metadata[offset..][..sector_metadata_bytes.len()],
+            sector_metadata_bytes
+        );
+    }
+}
+
+#[test]
+fn layout() {
+    let sector_metadata_size = SectorMetadataChecksummed::encoded_size() as u64;
+
+    for layout in [MetadataLayout::Unaligned, MetadataLayout::Aligned] {
+        assert_eq!(MetadataLayout::from_version(layout.version()), Some(layout));
+        assert!(layout.sector_metadata_slot_size() >= sector_metadata_size);
+        assert_eq!(layout.sector_metadata_offset(0), RESERVED_PLOT_METADATA);
+        assert_eq!(
+            layout.sector_count(layout.metadata_size(SECTOR_COUNT)),
+            u64::from(SECTOR_COUNT)
+        );
+        assert_eq!(
+            layout.encode_sector_metadata(&sector_metadata(1)).len() as u64,
+            layout.sector_metadata_slot_size()
+        );
+    }
+    assert_eq!(MetadataLayout::from_version(u8::MAX), None);
+    assert_eq!(MetadataLayout::CURRENT, MetadataLayout::Aligned);
+
+    let aligned = MetadataLayout::Aligned;
+    assert_eq!(
+        aligned.sector_metadata_slot_size() % DISK_SECTOR_SIZE as u64,
+        0
+    );
+    assert_eq!(
+        aligned
+            .encode_header(&PlotMetadataHeader {
+                version: aligned.version(),
+                plotted_sector_count: 1,
+            })
+            .len(),
+        DISK_SECTOR_SIZE
+    );
+}
+
+#[test]
+fn migration_from_unaligned_layout() {
+    let tempdir = tempdir().unwrap();
+    let path = tempdir.as_ref().join("metadata.bin");
+    write_unaligned_metadata_file(&path);
+
+    assert!(migrate_metadata_file(&path).unwrap());
+    assert_migrated(&path);
+    assert!(!migration_file_path(&path).exists());
+
+    // Already migrated file is left as is
+    let migrated = fs::read(&path).unwrap();
+    assert!(!migrate_metadata_file(&path).unwrap());
+    assert_eq!(fs::read(&path).unwrap(), migrated);
+
+    // Missing file is left for the caller to handle
+    assert!(!migrate_metadata_file(&tempdir.as_ref().join("missing.bin")).unwrap());
+}
+
+#[test]
+fn crash_during_migration() {
+    let tempdir = tempdir().unwrap();
+    let path = tempdir.as_ref().join("metadata.bin");
+    let migration_path = migration_file_path(&path);
+    let original = write_unaligned_metadata_file(&path);
+
+    // Crash before rename leaves original file intact next to fully written migrated file
+    assert!(write_migrated_metadata_file(&path, &migration_path).unwrap());
+    assert_eq!(fs::read(&path).unwrap(), original);
+    assert!(migration_path.exists());
+
+    // Crash while migrated file was being written leaves it incomplete
+    let migrated = fs::read(&migration_path).unwrap();
+    fs::write(&migration_path, &migrated[..migrated.len() / 2]).unwrap();
+
+    // Leftover is discarded and migration starts over
+    assert!(migrate_metadata_file(&path).unwrap());
+    assert_migrated(&path);
+    assert!(!migration_path.exists());
+}
+
+#[test]
+fn aligned_updates_without_read_modify_write() {
+    let tempdir = tempdir().unwrap();
+
+    for layout in [MetadataLayout::Unaligned, MetadataLayout::Aligned] {
+        let path = tempdir
+            .as_ref()
+            .join(format!("metadata-{}.bin", layout.version()));
+        fs::write(
+            &path,
+            vec![
+                0u8;
+                (layout.metadata_size(SECTOR_COUNT) as usize).next_multiple_of(DISK_SECTOR_SIZE)
+            ],
+        )
+        .unwrap();
+        let metadata_file = DirectIoFile::open(&path).unwrap();
+
+        // The same updates plotting does: sector metadata followed by header
+        for sector_index in 0..SECTOR_COUNT {
+            metadata_file
+                .write_all_at(
+                    &layout.encode_sector_metadata(&sector_metadata(sector_index)),
+                    layout.sector_metadata_offset(sector_index),
+                )
+                .unwrap();
+            metadata_file
+                .write_all_at(
+                    &layout.encode_header(&PlotMetadataHeader {
+                        version: layout.version(),
+                        plotted_sector_count: sector_index + 1,
+                    }),
+                    0,
+                )
+                .unwrap();
+        }
+
+        let io_stats = metadata_file.io_stats();
+        assert_eq!(io_stats.aligned_writes + io_stats.read_modify_writes, 8);
+        match layout {
+            MetadataLayout::Unaligned => {
+                assert!(io_stats.read_modify_writes > 0);
+            }
+            MetadataLayout::Aligned => {
+                assert_eq!(io_stats.read_modify_writes, 0);
+                assert_eq!(io_stats.amplified_bytes, 0);
+            }
+        }
+
+        let metadata = fs::read(&path).unwrap();
+        for sector_index in 0..SECTOR_COUNT {
+            let offset = layout.sector_metadata_offset(sector_index) as usize;
+            let sector_metadata_bytes = sector_metadata(sector_index).encode();
+            assert_eq!(
+                metadata[offset..][..sector_metadata_bytes.len()],
+                sector_metadata_bytes
+            );
+        }
+        assert_eq!(
+            PlotMetadataHeader::decode(&mut metadata.as_slice())
+                .unwrap()
+                .plotted_sector_count,
+            SECTOR_COUNT
+        );
+    }
+}
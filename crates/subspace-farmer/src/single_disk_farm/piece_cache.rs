@@ -2,16 +2,16 @@
 mod tests;
 
 use crate::farm::{FarmError, PieceCache, PieceCacheOffset};
-#[cfg(windows)]
-use crate::single_disk_farm::unbuffered_io_file_windows::UnbufferedIoFileWindows;
-use crate::single_disk_farm::unbuffered_io_file_windows::DISK_SECTOR_SIZE;
+use crate::single_disk_farm::direct_io_file::metrics::FileMetrics;
+use crate::single_disk_farm::direct_io_file::{
+    DirectIoFile, DirectIoOptions, IoRequestLimiter, IoStats, RequestStats,
+    DEFAULT_MAX_REQUEST_SIZE, DISK_SECTOR_SIZE,
+};
 use crate::utils::AsyncJoinOnDrop;
 use async_trait::async_trait;
 use futures::channel::mpsc;
 use futures::{stream, SinkExt, Stream, StreamExt};
 use parking_lot::Mutex;
-#[cfg(not(windows))]
-use std::fs::{File, OpenOptions};
 use std::path::Path;
 use std::sync::Arc;
 use std::task::Poll;
@@ -19,8 +19,6 @@ use std::{fs, io, mem};
 use subspace_core_primitives::crypto::blake3_hash_list;
 use subspace_core_primitives::{Blake3Hash, Piece, PieceIndex};
 use subspace_farmer_components::file_ext::FileExt;
-#[cfg(not(windows))]
-use subspace_farmer_components::file_ext::OpenOptionsExt;
 use thiserror::Error;
 use tokio::runtime::Handle;
 use tokio::task;
@@ -29,6 +27,13 @@ use tracing::{debug, info, warn};
 /// How many pieces should be skipped before stopping to check the rest of contents, this allows to
 /// not miss most of the pieces after one or two corrupted pieces
 const CONTENTS_READ_SKIP_LIMIT: usize = 3;
+/// Max size of a single request to cache file. Cache elements are ~1 MiB and are accessed one at a
+/// time, smaller requests keep scratch buffers small and don't delay concurrent requests to the
+/// same disk (like proving reads of the plot) for long.
+pub const PIECE_CACHE_MAX_REQUEST_SIZE: usize = 256 * 1024;
+/// Max number of scratch buffers of cache file, cache is accessed by a few concurrent piece reads
+/// and writes, so there is no need to keep as many buffers as for the plot
+pub const PIECE_CACHE_SCRATCH_BUFFERS: usize = 2;
 
 /// Disk piece cache open error
 #[derive(Debug, Error)]
@@ -57,10 +62,7 @@ pub enum DiskPieceCacheError {
 
 #[derive(Debug)]
 struct Inner {
-    #[cfg(not(windows))]
-    file: File,
-    #[cfg(windows)]
-    file: UnbufferedIoFileWindows,
+    file: DirectIoFile,
     num_elements: u32,
 }
 
@@ -132,30 +134,48 @@ impl PieceCache for DiskPieceCache {
 impl DiskPieceCache {
     pub(crate) const FILE_NAME: &'static str = "piece_cache.bin";
 
-    pub(crate) fn open(directory: &Path, capacity: u32) -> Result<Self, DiskPieceCacheError> {
+    /// Open cache with `capacity` elements in `directory`, creating it if necessary.
+    ///
+    /// Cache file uses direct/unbuffered I/O (falling back to buffered I/O where direct I/O is not
+    /// supported) with `direct_io_options` tuned for cache access pattern, see
+    /// [`Self::direct_io_options()`].
+    pub(crate) fn open(
+        directory: &Path,
+        capacity: u32,
+        direct_io_options: DirectIoOptions,
+        metrics: Option<Arc<FileMetrics>>,
+        io_request_limiter: Option<IoRequestLimiter>,
+    ) -> Result<Self, DiskPieceCacheError> {
         if capacity == 0 {
             return Err(DiskPieceCacheError::ZeroCapacity);
         }
 
-        #[cfg(not(windows))]
-        let file = OpenOptions::new()
-            .read(true)
-            .write(true)
-            .create(true)
-            .advise_random_access()
-            .open(directory.join(Self::FILE_NAME))?;
-
-        #[cfg(not(windows))]
-        file.advise_random_access()?;
-
-        #[cfg(windows)]
-        let file = UnbufferedIoFileWindows::open(&directory.join(Self::FILE_NAME))?;
+        let direct_io_options = Self::direct_io_options(direct_io_options);
+        let file_path = directory.join(Self::FILE_NAME);
+        let file = DirectIoFile::open_with_limiter(
+            &file_path,
+            direct_io_options,
+            metrics,
+            io_request_limiter,
+        )?;
+        debug!(
+            path = %file_path.display(),
+            unbuffered = %file.is_unbuffered(),
+            "Opened piece cache file"
+        );
 
         let expected_size = u64::from(Self::element_size()) * u64::from(capacity);
         // Align plot file size for disk sector size
         let expected_size =
             expected_size.div_ceil(DISK_SECTOR_SIZE as u64) * DISK_SECTOR_SIZE as u64;
-        if file.size()? != expected_size {
+        let mut capacity = capacity;
+        if direct_io_options.read_only {
+            // Read-only file can't be resized, use only elements that fit into existing file
+            capacity = capacity.min((file.size()? / u64::from(Self::element_size())) as u32);
+            if capacity == 0 {
+                return Err(DiskPieceCacheError::ZeroCapacity);
+            }
+        } else if file.size()? != expected_size {
             // Allocating the whole file (`set_len` below can create a sparse file, which will cause
             // writes to fail later)
             file.preallocate(expected_size)
@@ -172,6 +192,47 @@ impl DiskPieceCache {
         })
     }
 
+    /// Options of plot file adjusted for cache file: max request size is limited to
+    /// [`PIECE_CACHE_MAX_REQUEST_SIZE`] and number of scratch buffers to
+    /// [`PIECE_CACHE_SCRATCH_BUFFERS`], other options are used as is
+    pub fn direct_io_options(direct_io_options: DirectIoOptions) -> DirectIoOptions {
+        DirectIoOptions {
+            max_request_size: Some(
+                direct_io_options
+                    .max_request_size
+                    .unwrap_or(DEFAULT_MAX_REQUEST_SIZE)
+                    .min(PIECE_CACHE_MAX_REQUEST_SIZE),
+            ),
+            scratch_buffers: Some(
+                direct_io_options
+                    .scratch_buffers
+                    .unwrap_or_else(DirectIoOptions::default_scratch_buffers)
+                    .min(PIECE_CACHE_SCRATCH_BUFFERS),
+            ),
+            ..direct_io_options
+        }
+    }
+
+    /// Whether cache file uses direct/unbuffered I/O
+    pub fn is_unbuffered(&self) -> bool {
+        self.inner.file.is_unbuffered()
+    }
+
+    /// Write alignment stats of cache file, see [`DirectIoFile::io_stats()`]
+    pub fn io_stats(&self) -> IoStats {
+        self.inner.file.io_stats()
+    }
+
+    /// Request stats of cache file, see [`DirectIoFile::request_stats()`]
+    pub fn request_stats(&self) -> RequestStats {
+        self.inner.file.request_stats()
+    }
+
+    /// Reset I/O stats of cache file, see [`DirectIoFile::reset_io_stats()`]
+    pub fn reset_io_stats(&self) {
+        self.inner.file.reset_io_stats();
+    }
+
     pub(crate) const fn element_size() -> u32 {
         (PieceIndex::SIZE + Piece::SIZE + mem::size_of::<Blake3Hash>()) as u32
     }
@@ -233,17 +294,14 @@ impl DiskPieceCache {
 
         let element_offset = u64::from(offset) * u64::from(Self::element_size());
 
+        // Whole element is written at once, such that unaligned edges of the element need
+        // read-modify-write only once with direct I/O
         let piece_index_bytes = piece_index.to_bytes();
-        self.inner
-            .file
-            .write_all_at(&piece_index_bytes, element_offset)?;
-        self.inner
-            .file
-            .write_all_at(piece.as_ref(), element_offset + PieceIndex::SIZE as u64)?;
-        self.inner.file.write_all_at(
-            &blake3_hash_list(&[&piece_index_bytes, piece.as_ref()]),
-            element_offset + PieceIndex::SIZE as u64 + Piece::SIZE as u64,
-        )?;
+        let mut element = Vec::with_capacity(Self::element_size() as usize);
+        element.extend_from_slice(&piece_index_bytes);
+        element.extend_from_slice(piece.as_ref());
+        element.extend_from_slice(&blake3_hash_list(&[&piece_index_bytes, piece.as_ref()]));
+        self.inner.file.write_all_at(&element, element_offset)?;
 
         Ok(())
     }
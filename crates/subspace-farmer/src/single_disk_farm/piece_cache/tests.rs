@@ -1,6 +1,8 @@
+use crate::single_disk_farm::direct_io_file::DirectIoOptions;
 use crate::single_disk_farm::piece_cache::{DiskPieceCache, DiskPieceCacheError, PieceCacheOffset};
 use rand::prelude::*;
 use std::assert_matches::assert_matches;
+use std::fs;
 use subspace_core_primitives::{Piece, PieceIndex};
 use tempfile::tempdir;
 
@@ -8,7 +10,8 @@ use tempfile::tempdir;
 fn basic() {
     let path = tempdir().unwrap();
     {
-        let disk_piece_cache = DiskPieceCache::open(path.as_ref(), 2).unwrap();
+        let disk_piece_cache =
+            DiskPieceCache::open(path.as_ref(), 2, DirectIoOptions::default(), None, None).unwrap();
 
         // Initially empty
         assert_eq!(
@@ -115,7 +118,8 @@ fn basic() {
 
     // Reopening works
     {
-        let disk_piece_cache = DiskPieceCache::open(path.as_ref(), 2).unwrap();
+        let disk_piece_cache =
+            DiskPieceCache::open(path.as_ref(), 2, DirectIoOptions::default(), None, None).unwrap();
         // Two pieces stored
         assert_eq!(
             disk_piece_cache
@@ -130,7 +134,8 @@ fn basic() {
     {
         DiskPieceCache::wipe(path.as_ref()).unwrap();
 
-        let disk_piece_cache = DiskPieceCache::open(path.as_ref(), 2).unwrap();
+        let disk_piece_cache =
+            DiskPieceCache::open(path.as_ref(), 2, DirectIoOptions::default(), None, None).unwrap();
         // Wiped successfully
         assert_eq!(
             disk_piece_cache
@@ -141,3 +146,69 @@ fn basic() {
         );
     }
 }
+
+#[test]
+fn buffered_io() {
+    let direct_path = tempdir().unwrap();
+    let buffered_path = tempdir().unwrap();
+
+    let direct_cache = DiskPieceCache::open(
+        direct_path.as_ref(),
+        3,
+        DirectIoOptions::default(),
+        None,
+        None,
+    )
+    .unwrap();
+    let buffered_cache = DiskPieceCache::open(
+        buffered_path.as_ref(),
+        3,
+        DirectIoOptions {
+            buffered_io: true,
+            ..DirectIoOptions::default()
+        },
+        None,
+        None,
+    )
+    .unwrap();
+    assert!(!buffered_cache.is_unbuffered());
+
+    // Elements are not aligned to disk sectors, such that writes need read-modify-write
+    for (offset, piece_index) in [(1, 5), (0, 3), (2, 100), (1, 7)] {
+        let piece = {
+            let mut piece = Piece::default();
+            thread_rng().fill(piece.as_mut());
+            piece
+        };
+
+        for disk_piece_cache in [&direct_cache, &buffered_cache] {
+            disk_piece_cache
+                .write_piece(
+                    PieceCacheOffset(offset),
+                    PieceIndex::from(piece_index),
+                    &piece,
+                )
+                .unwrap();
+            assert_eq!(
+                disk_piece_cache
+                    .read_piece_index(PieceCacheOffset(offset))
+                    .unwrap(),
+                Some(PieceIndex::from(piece_index))
+            );
+            assert_eq!(
+                disk_piece_cache
+                    .read_piece(PieceCacheOffset(offset))
+                    .unwrap(),
+                Some(piece.clone())
+            );
+        }
+    }
+
+    // On-disk format is the same regardless of I/O mode
+    drop(direct_cache);
+    drop(buffered_cache);
+    assert_eq!(
+        fs::read(direct_path.as_ref().join(DiskPieceCache::FILE_NAME)).unwrap(),
+        fs::read(buffered_path.as_ref().join(DiskPieceCache::FILE_NAME)).unwrap()
+    );
+}
@@ -1,18 +1,18 @@
 use crate::farm::{FarmError, PieceReader};
-#[cfg(windows)]
-use crate::single_disk_farm::unbuffered_io_file_windows::UnbufferedIoFileWindows;
+use crate::single_disk_farm::direct_io_file::async_file::{AsyncDirectIoFile, IoThreadPool};
+use crate::single_disk_farm::direct_io_file::DirectIoFile;
+use crate::single_disk_farm::sector_reader::SectorReader;
+use crate::single_disk_farm::PlotFile;
 use async_lock::{Mutex as AsyncMutex, RwLock as AsyncRwLock};
 use async_trait::async_trait;
 use futures::channel::{mpsc, oneshot};
 use futures::{SinkExt, StreamExt};
-#[cfg(not(windows))]
-use std::fs::File;
 use std::future::Future;
 use std::sync::Arc;
 use subspace_core_primitives::{Piece, PieceOffset, PublicKey, SectorId, SectorIndex};
 use subspace_erasure_coding::ErasureCoding;
 use subspace_farmer_components::reading::ReadSectorRecordChunksMode;
-use subspace_farmer_components::sector::{sector_size, SectorMetadataChecksummed};
+use subspace_farmer_components::sector::SectorMetadataChecksummed;
 use subspace_farmer_components::{reading, ReadAt, ReadAtAsync, ReadAtSync};
 use subspace_proof_of_space::Table;
 use tracing::{error, warn};
@@ -46,12 +46,14 @@ impl DiskPieceReader {
     ///
     /// NOTE: Background future is async, but does blocking operations and should be running in
     /// dedicated thread.
+    ///
+    /// If `io_thread_pool` is provided, sectors are read asynchronously on it, such that reads of
+    /// record chunks overlap with each other.
     #[allow(clippy::too_many_arguments)]
     pub(super) fn new<PosTable>(
         public_key: PublicKey,
-        pieces_in_sector: u16,
-        #[cfg(not(windows))] plot_file: Arc<File>,
-        #[cfg(windows)] plot_file: Arc<UnbufferedIoFileWindows>,
+        sector_reader: SectorReader<Arc<PlotFile>, Arc<DirectIoFile>>,
+        io_thread_pool: Option<IoThreadPool>,
         sectors_metadata: Arc<AsyncRwLock<Vec<SectorMetadataChecksummed>>>,
         erasure_coding: ErasureCoding,
         modifying_sector_index: Arc<AsyncRwLock<Option<SectorIndex>>>,
@@ -63,11 +65,23 @@ impl DiskPieceReader {
     {
         let (read_piece_sender, read_piece_receiver) = mpsc::channel(10);
 
+        let async_plot_file = io_thread_pool.and_then(|io_thread_pool| {
+            match sector_reader.plot_file().single_file() {
+                Some(file) => Some(AsyncDirectIoFile::new(Arc::clone(file), io_thread_pool)),
+                None => {
+                    warn!(
+                        "Asynchronous reads are not supported for plot file spanning multiple \
+                        directories, using synchronous reads instead"
+                    );
+                    None
+                }
+            }
+        });
         let reading_fut = async move {
-            read_pieces::<PosTable, _>(
+            read_pieces::<PosTable, _, _>(
                 public_key,
-                pieces_in_sector,
-                &*plot_file,
+                sector_reader,
+                async_plot_file,
                 sectors_metadata,
                 erasure_coding,
                 modifying_sector_index,
@@ -107,10 +121,10 @@ impl DiskPieceReader {
 }
 
 #[allow(clippy::too_many_arguments)]
-async fn read_pieces<PosTable, S>(
+async fn read_pieces<PosTable, PF, MF>(
     public_key: PublicKey,
-    pieces_in_sector: u16,
-    plot_file: S,
+    sector_reader: SectorReader<PF, MF>,
+    async_plot_file: Option<AsyncDirectIoFile>,
     sectors_metadata: Arc<AsyncRwLock<Vec<SectorMetadataChecksummed>>>,
     erasure_coding: ErasureCoding,
     modifying_sector_index: Arc<AsyncRwLock<Option<SectorIndex>>>,
@@ -119,8 +133,9 @@ async fn read_pieces<PosTable, S>(
     global_mutex: Arc<AsyncMutex<()>>,
 ) where
     PosTable: Table,
-    S: ReadAtSync,
+    PF: ReadAtSync,
 {
+    let pieces_in_sector = sector_reader.pieces_in_sector();
     let mut table_generator = PosTable::generator();
 
     while let Some(read_piece_request) = read_piece_receiver.next().await {
@@ -186,23 +201,42 @@ async fn read_pieces<PosTable, S>(
             continue;
         }
 
-        let sector_size = sector_size(pieces_in_sector);
-        let sector = plot_file.offset(u64::from(sector_index) * sector_size as u64);
+        let sector = match sector_reader.read_sector(sector_index) {
+            Ok(sector) => sector,
+            Err(error) => {
+                warn!(%error, %piece_offset, "Sector can't be read");
+                // Doesn't matter if receiver still cares about it
+                let _ = response_sender.send(None);
+                continue;
+            }
+        };
 
         // Take mutex briefly to make sure piece reading is allowed right now
         global_mutex.lock().await;
 
-        let maybe_piece = read_piece::<PosTable, _, _>(
-            &public_key,
-            piece_offset,
-            &sector_metadata,
-            // TODO: Async
-            &ReadAt::from_sync(&sector),
-            &erasure_coding,
-            mode,
-            &mut table_generator,
-        )
-        .await;
+        let maybe_piece = if let Some(async_plot_file) = &async_plot_file {
+            read_piece::<PosTable, _, _>(
+                &public_key,
+                piece_offset,
+                &sector_metadata,
+                &ReadAt::from_async(async_plot_file.offset(sector.start())),
+                &erasure_coding,
+                mode,
+                &mut table_generator,
+            )
+            .await
+        } else {
+            read_piece::<PosTable, _, _>(
+                &public_key,
+                piece_offset,
+                &sector_metadata,
+                &ReadAt::from_sync(sector),
+                &erasure_coding,
+                mode,
+                &mut table_generator,
+            )
+            .await
+        };
 
         // Doesn't matter if receiver still cares about it
         let _ = response_sender.send(maybe_piece);
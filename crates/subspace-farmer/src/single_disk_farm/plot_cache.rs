@@ -2,15 +2,14 @@
 mod tests;
 
 use crate::farm::{FarmError, MaybePieceStoredResult, PlotCache};
-#[cfg(windows)]
-use crate::single_disk_farm::unbuffered_io_file_windows::UnbufferedIoFileWindows;
+use crate::single_disk_farm::direct_io_file::IoPriority;
+use crate::single_disk_farm::file_region::FileRegion;
+use crate::single_disk_farm::PlotFile;
 use crate::utils::AsyncJoinOnDrop;
 use async_lock::RwLock as AsyncRwLock;
 use async_trait::async_trait;
 use parking_lot::RwLock;
 use std::collections::HashMap;
-#[cfg(not(windows))]
-use std::fs::File;
 use std::sync::{Arc, Weak};
 use std::{io, mem};
 use subspace_core_primitives::crypto::blake3_hash_list;
@@ -43,15 +42,21 @@ struct CachedPieces {
     next_offset: Option<u32>,
 }
 
-/// Additional piece cache that exploit part of the plot that does not contain sectors yet
+/// Additional piece cache that exploit part of the plot that does not contain sectors yet.
+///
+/// Pieces are stored starting from the end of the plot file, such that pieces nearest to the
+/// plotting frontier are the ones evicted first as plotting consumes the space, see
+/// [`DiskPlotCache::reserve_sector()`].
 #[derive(Debug, Clone)]
 pub struct DiskPlotCache {
-    #[cfg(not(windows))]
-    file: Weak<File>,
-    #[cfg(windows)]
-    file: Weak<UnbufferedIoFileWindows>,
+    file: Weak<PlotFile>,
     sectors_metadata: Weak<AsyncRwLock<Vec<SectorMetadataChecksummed>>>,
     cached_pieces: Arc<RwLock<CachedPieces>>,
+    /// Offset in the plot file up to which space belongs to plotting, pieces are never written
+    /// below it
+    plotting_frontier: Arc<RwLock<u64>>,
+    /// Offset in the plot file up to which space can be used for caching
+    cache_end: u64,
     sector_size: u64,
 }
 
@@ -79,14 +84,26 @@ impl PlotCache for DiskPlotCache {
 
 impl DiskPlotCache {
     pub(crate) fn new(
-        #[cfg(not(windows))] file: &Arc<File>,
-        #[cfg(windows)] file: &Arc<UnbufferedIoFileWindows>,
+        file: &Arc<PlotFile>,
         sectors_metadata: &Arc<AsyncRwLock<Vec<SectorMetadataChecksummed>>>,
         target_sector_count: SectorIndex,
         sector_size: usize,
     ) -> Self {
         info!("Checking plot cache contents, this can take a while");
         let sector_size = sector_size as u64;
+        // Separate file instance with background priority, such that the check doesn't compete
+        // with time-critical reads
+        let background_file = file.try_clone().map(|file| {
+            file.set_io_priority(IoPriority::Background);
+            file
+        });
+        let check_file: &PlotFile = match &background_file {
+            Ok(background_file) => background_file,
+            Err(error) => {
+                debug!(%error, "Failed to clone plot file, checking with foreground priority");
+                file
+            }
+        };
         let cached_pieces = {
             let sectors_metadata = sectors_metadata.read_blocking();
             let mut element = vec![0; Self::element_size() as usize];
@@ -103,7 +120,7 @@ impl DiskPlotCache {
             let to_offset = (file_size / Self::element_size() as u64) as u32;
             // TODO: Parallelize or read in larger batches
             for offset in (from_offset..to_offset).rev() {
-                match Self::read_piece_internal(file, offset, &mut element) {
+                match Self::read_piece_internal(check_file, offset, &mut element) {
                     Ok(maybe_piece_index) => match maybe_piece_index {
                         Some(piece_index) => {
                             map.insert(RecordKey::from(piece_index.to_multihash()), offset);
@@ -129,10 +146,14 @@ impl DiskPlotCache {
 
         info!("Finished checking plot cache contents");
 
+        let plotted_size = sector_size * sectors_metadata.read_blocking().len() as u64;
+
         Self {
             file: Arc::downgrade(file),
             sectors_metadata: Arc::downgrade(sectors_metadata),
             cached_pieces: Arc::new(RwLock::new(cached_pieces)),
+            plotting_frontier: Arc::new(RwLock::new(plotted_size)),
+            cache_end: sector_size * u64::from(target_sector_count),
             sector_size,
         }
     }
@@ -210,6 +231,9 @@ impl DiskPlotCache {
             return Ok(false);
         }
 
+        // Sectors that are being written right now are protected by the plotting frontier instead
+        drop(sectors_metadata);
+
         let Some(file) = self.file.upgrade() else {
             return Ok(false);
         };
@@ -217,28 +241,90 @@ impl DiskPlotCache {
         let piece_index_bytes = piece_index.to_bytes();
         let write_fut = tokio::task::spawn_blocking({
             let piece = piece.clone();
+            let cached_pieces = Arc::clone(&self.cached_pieces);
+            let plotting_frontier = Arc::clone(&self.plotting_frontier);
+            let cache_end = self.cache_end;
 
             move || {
-                file.write_all_at(&piece_index_bytes, element_offset)?;
-                file.write_all_at(piece.as_ref(), element_offset + PieceIndex::SIZE as u64)?;
-                file.write_all_at(
+                // Held until the piece is in the map, such that plotting waits for the write to
+                // finish before taking the space over and the piece can't be missed by eviction
+                let plotting_frontier = plotting_frontier.read();
+                if element_offset < *plotting_frontier {
+                    // No space to store more pieces anymore
+                    cached_pieces.write().next_offset.take();
+                    return Ok(false);
+                }
+
+                // Writes are confined to the space plotting doesn't use
+                let region = FileRegion::new(
+                    &*file,
+                    *plotting_frontier,
+                    cache_end.saturating_sub(*plotting_frontier),
+                );
+                let region_offset = element_offset - *plotting_frontier;
+                region.write_all_at(&piece_index_bytes, region_offset)?;
+                region.write_all_at(piece.as_ref(), region_offset + PieceIndex::SIZE as u64)?;
+                region.write_all_at(
                     &blake3_hash_list(&[&piece_index_bytes, piece.as_ref()]),
-                    element_offset + PieceIndex::SIZE as u64 + Piece::SIZE as u64,
-                )
+                    region_offset + PieceIndex::SIZE as u64 + Piece::SIZE as u64,
+                )?;
+
+                // Store newly written piece in the map
+                cached_pieces
+                    .write()
+                    .map
+                    .insert(RecordKey::from(piece_index.to_multihash()), offset);
+
+                Ok::<_, io::Error>(true)
             }
         });
 
-        AsyncJoinOnDrop::new(write_fut, false).await??;
+        Ok(AsyncJoinOnDrop::new(write_fut, false).await??)
+    }
+
+    /// Reserve space of the sector for plotting before it is written, plotted data always takes
+    /// precedence over cached pieces.
+    ///
+    /// Waits for in-flight piece writes to finish and evicts all pieces stored in the space of the
+    /// sector (and before it) at once, no pieces are written into this space afterwards.
+    pub(crate) async fn reserve_sector(
+        &self,
+        sector_index: SectorIndex,
+    ) -> Result<(), DiskPlotCacheError> {
+        // Waiting for in-flight piece writes blocks, so it is done on a blocking thread
+        let reserve_fut = tokio::task::spawn_blocking({
+            let disk_plot_cache = self.clone();
+
+            move || disk_plot_cache.reserve_sector_blocking(sector_index)
+        });
+
+        Ok(AsyncJoinOnDrop::new(reserve_fut, false).await?)
+    }
 
-        // Just to be safe, avoid any overlap of write locks
-        drop(sectors_metadata);
-        // Store newly written piece in the map
-        self.cached_pieces
-            .write()
+    /// Blocking version of [`Self::reserve_sector()`], for use outside of async context.
+    pub(crate) fn reserve_sector_blocking(&self, sector_index: SectorIndex) {
+        let sector_end = (u64::from(sector_index) + 1) * self.sector_size;
+        let mut plotting_frontier = self.plotting_frontier.write();
+        if sector_end <= *plotting_frontier {
+            return;
+        }
+        *plotting_frontier = sector_end;
+
+        // The first element that is fully past the plotting frontier
+        let first_offset = sector_end
+            .div_ceil(u64::from(Self::element_size()))
+            .try_into()
+            .unwrap_or(u32::MAX);
+        let mut cached_pieces = self.cached_pieces.write();
+        cached_pieces
             .map
-            .insert(RecordKey::from(piece_index.to_multihash()), offset);
-
-        Ok(true)
+            .retain(|_key, offset| *offset >= first_offset);
+        if cached_pieces
+            .next_offset
+            .is_some_and(|next_offset| next_offset < first_offset)
+        {
+            cached_pieces.next_offset.take();
+        }
     }
 
     /// Read piece from cache.
@@ -273,8 +359,7 @@ impl DiskPlotCache {
     }
 
     fn read_piece_internal(
-        #[cfg(not(windows))] file: &File,
-        #[cfg(windows)] file: &UnbufferedIoFileWindows,
+        file: &PlotFile,
         offset: u32,
         element: &mut [u8],
     ) -> Result<Option<PieceIndex>, DiskPlotCacheError> {
@@ -1,18 +1,12 @@
 use crate::farm::MaybePieceStoredResult;
+use crate::single_disk_farm::direct_io_file::{DirectIoFile, DISK_SECTOR_SIZE};
 use crate::single_disk_farm::plot_cache::DiskPlotCache;
-#[cfg(windows)]
-use crate::single_disk_farm::unbuffered_io_file_windows::UnbufferedIoFileWindows;
-use crate::single_disk_farm::unbuffered_io_file_windows::DISK_SECTOR_SIZE;
 use rand::prelude::*;
 use std::assert_matches::assert_matches;
-#[cfg(not(windows))]
-use std::fs::OpenOptions;
 use std::num::NonZeroU64;
 use std::sync::Arc;
 use subspace_core_primitives::{HistorySize, Piece, PieceIndex, Record, SectorIndex};
 use subspace_farmer_components::file_ext::FileExt;
-#[cfg(not(windows))]
-use subspace_farmer_components::file_ext::OpenOptionsExt;
 use subspace_farmer_components::sector::{SectorMetadata, SectorMetadataChecksummed};
 use subspace_networking::libp2p::kad::RecordKey;
 use subspace_networking::utils::multihash::ToMultihash;
@@ -31,17 +25,7 @@ async fn basic() {
     });
 
     let tempdir = tempdir().unwrap();
-    #[cfg(not(windows))]
-    let file = OpenOptions::new()
-        .read(true)
-        .write(true)
-        .create(true)
-        .advise_random_access()
-        .open(tempdir.path().join("plot.bin"))
-        .unwrap();
-
-    #[cfg(windows)]
-    let file = UnbufferedIoFileWindows::open(&tempdir.path().join("plot.bin")).unwrap();
+    let file = DirectIoFile::open(&tempdir.path().join("plot.bin")).unwrap();
 
     // Align plot file size for disk sector size
     file.preallocate(
@@ -189,3 +173,169 @@ async fn basic() {
     drop(file);
     assert_matches!(disk_plot_cache.read_piece(&record_key_0).await, None);
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn plotting_frontier() {
+    let tempdir = tempdir().unwrap();
+    let file = DirectIoFile::open(&tempdir.path().join("plot.bin")).unwrap();
+
+    // Align plot file size for disk sector size
+    file.preallocate(
+        (FAKE_SECTOR_SIZE as u64 * u64::from(TARGET_SECTOR_COUNT))
+            .div_ceil(DISK_SECTOR_SIZE as u64)
+            * DISK_SECTOR_SIZE as u64,
+    )
+    .unwrap();
+
+    let file = Arc::new(file);
+    let sectors_metadata = Arc::default();
+
+    let disk_plot_cache = DiskPlotCache::new(
+        &file,
+        &sectors_metadata,
+        TARGET_SECTOR_COUNT,
+        FAKE_SECTOR_SIZE,
+    );
+
+    let pieces = (0..)
+        .map(|piece_index| {
+            let mut piece = Piece::default();
+            thread_rng().fill(piece.as_mut());
+            (PieceIndex::from(piece_index), piece)
+        })
+        .take(20)
+        .collect::<Vec<_>>();
+
+    // Cache is filled concurrently with plotting, plotting writes sectors before updating sectors
+    // metadata, cache must not overwrite them in the meantime
+    let store_pieces_fut = tokio::spawn({
+        let disk_plot_cache = disk_plot_cache.clone();
+        let pieces = pieces.clone();
+
+        async move {
+            let mut stored_pieces = Vec::new();
+            for (piece_index, piece) in pieces {
+                if disk_plot_cache
+                    .try_store_piece(piece_index, &piece)
+                    .await
+                    .unwrap()
+                {
+                    stored_pieces.push(piece_index);
+                }
+            }
+            stored_pieces
+        }
+    });
+
+    for sector_index in 0..TARGET_SECTOR_COUNT {
+        disk_plot_cache.reserve_sector(sector_index).await.unwrap();
+        file.write_all_at(
+            &vec![sector_index as u8 + 1; FAKE_SECTOR_SIZE],
+            u64::from(sector_index) * FAKE_SECTOR_SIZE as u64,
+        )
+        .unwrap();
+
+        // Pieces that are still cached are intact
+        for (piece_index, piece) in &pieces {
+            let record_key = RecordKey::from(piece_index.to_multihash());
+            if let MaybePieceStoredResult::Yes = disk_plot_cache.is_piece_maybe_stored(&record_key)
+            {
+                assert!(disk_plot_cache.read_piece(&record_key).await.unwrap() == *piece);
+            }
+        }
+    }
+
+    let stored_pieces = store_pieces_fut.await.unwrap();
+    assert!(!stored_pieces.is_empty());
+
+    // The whole file is plotted, all pieces are evicted and no new pieces can be stored
+    for (piece_index, piece) in &pieces {
+        let record_key = RecordKey::from(piece_index.to_multihash());
+        assert_matches!(
+            disk_plot_cache.is_piece_maybe_stored(&record_key),
+            MaybePieceStoredResult::No
+        );
+        assert!(!disk_plot_cache
+            .try_store_piece(*piece_index, piece)
+            .await
+            .unwrap());
+    }
+
+    // No plotted sector was overwritten by cache writes
+    let mut sector = vec![0; FAKE_SECTOR_SIZE];
+    for sector_index in 0..TARGET_SECTOR_COUNT {
+        file.read_exact_at(
+            &mut sector,
+            u64::from(sector_index) * FAKE_SECTOR_SIZE as u64,
+        )
+        .unwrap();
+        assert!(sector.iter().all(|&byte| byte == sector_index as u8 + 1));
+    }
+}
+
+#[tokio::test]
+async fn plotting_frontier_eviction() {
+    let tempdir = tempdir().unwrap();
+    let file = DirectIoFile::open(&tempdir.path().join("plot.bin")).unwrap();
+
+    // Align plot file size for disk sector size
+    file.preallocate(
+        (FAKE_SECTOR_SIZE as u64 * u64::from(TARGET_SECTOR_COUNT))
+            .div_ceil(DISK_SECTOR_SIZE as u64)
+            * DISK_SECTOR_SIZE as u64,
+    )
+    .unwrap();
+
+    let file = Arc::new(file);
+    let sectors_metadata = Arc::default();
+
+    let disk_plot_cache = DiskPlotCache::new(
+        &file,
+        &sectors_metadata,
+        TARGET_SECTOR_COUNT,
+        FAKE_SECTOR_SIZE,
+    );
+
+    // Fill the cache, pieces are stored from the end of the file
+    let mut stored_pieces = Vec::new();
+    for piece_index in 0.. {
+        let piece_index = PieceIndex::from(piece_index);
+        let mut piece = Piece::default();
+        thread_rng().fill(piece.as_mut());
+        if !disk_plot_cache
+            .try_store_piece(piece_index, &piece)
+            .await
+            .unwrap()
+        {
+            break;
+        }
+        stored_pieces.push((RecordKey::from(piece_index.to_multihash()), piece));
+    }
+    assert!(stored_pieces.len() > 2);
+
+    // Pieces nearest to the frontier are evicted first as soon as space is reserved for plotting,
+    // before sectors metadata is updated
+    let element_size = u64::from(DiskPlotCache::element_size());
+    let elements_count = FAKE_SECTOR_SIZE as u64 * u64::from(TARGET_SECTOR_COUNT) / element_size;
+    for sector_index in 0..TARGET_SECTOR_COUNT {
+        disk_plot_cache.reserve_sector(sector_index).await.unwrap();
+        let frontier = u64::from(sector_index + 1) * FAKE_SECTOR_SIZE as u64;
+
+        for (position, (record_key, piece)) in stored_pieces.iter().enumerate() {
+            let element_offset = (elements_count - 1 - position as u64) * element_size;
+            if element_offset < frontier {
+                assert_matches!(
+                    disk_plot_cache.is_piece_maybe_stored(record_key),
+                    MaybePieceStoredResult::No
+                );
+                assert_matches!(disk_plot_cache.read_piece(record_key).await, None);
+            } else {
+                assert_matches!(
+                    disk_plot_cache.is_piece_maybe_stored(record_key),
+                    MaybePieceStoredResult::Yes
+                );
+                assert!(disk_plot_cache.read_piece(record_key).await.unwrap() == *piece);
+            }
+        }
+    }
+}
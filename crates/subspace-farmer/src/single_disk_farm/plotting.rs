@@ -1,29 +1,36 @@
+#[cfg(test)]
+mod tests;
+
 use crate::farm::{SectorExpirationDetails, SectorPlottingDetails, SectorUpdate};
-#[cfg(windows)]
-use crate::single_disk_farm::unbuffered_io_file_windows::UnbufferedIoFileWindows;
+use crate::single_disk_farm::direct_io_file::metrics::FileMetrics;
+use crate::single_disk_farm::direct_io_file::{is_disk_full, DirectIoFile};
+use crate::single_disk_farm::metadata_journal::MetadataJournal;
+use crate::single_disk_farm::metadata_layout::MetadataLayout;
+use crate::single_disk_farm::plot_cache::{DiskPlotCache, DiskPlotCacheError};
+use crate::single_disk_farm::preallocation::PreallocationFrontier;
+use crate::single_disk_farm::shutdown::ShutdownMarker;
 use crate::single_disk_farm::{
-    BackgroundTaskError, Handlers, PlotMetadataHeader, RESERVED_PLOT_METADATA,
+    BackgroundTaskError, Durability, Handlers, PlotFile, PlotMetadataHeader,
 };
 use crate::thread_pool_manager::PlottingThreadPoolManager;
 use crate::utils::AsyncJoinOnDrop;
 use crate::{node_client, NodeClient};
 use async_lock::{Mutex as AsyncMutex, RwLock as AsyncRwLock};
 use futures::channel::{mpsc, oneshot};
+use futures::future::pending;
 use futures::{select, FutureExt, SinkExt, StreamExt};
 use lru::LruCache;
-use parity_scale_codec::Encode;
 use std::collections::HashMap;
-#[cfg(not(windows))]
-use std::fs::File;
 use std::io;
 use std::num::NonZeroUsize;
 use std::ops::Range;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU16, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use subspace_core_primitives::crypto::kzg::Kzg;
 use subspace_core_primitives::{
-    Blake3Hash, HistorySize, PieceOffset, PublicKey, SectorId, SectorIndex, SegmentHeader,
+    Blake3Hash, HistorySize, PieceOffset, PublicKey, Record, SectorId, SectorIndex, SegmentHeader,
     SegmentIndex,
 };
 use subspace_erasure_coding::ErasureCoding;
@@ -32,17 +39,27 @@ use subspace_farmer_components::plotting::{
     download_sector, encode_sector, DownloadSectorOptions, DownloadedSector, EncodeSectorOptions,
     PlottedSector,
 };
-use subspace_farmer_components::sector::SectorMetadataChecksummed;
-use subspace_farmer_components::{plotting, PieceGetter};
+use subspace_farmer_components::sector::{SectorMetadata, SectorMetadataChecksummed};
+use subspace_farmer_components::{plotting, PieceGetter, ReadAtSync, WriteAtSync};
 use subspace_proof_of_space::Table;
 use thiserror::Error;
 use tokio::sync::{broadcast, watch, OwnedSemaphorePermit, Semaphore};
 use tokio::task::yield_now;
-use tracing::{debug, info, trace, warn, Instrument};
+use tracing::{debug, error, info, trace, warn, Instrument};
 
 const FARMER_APP_INFO_RETRY_INTERVAL: Duration = Duration::from_millis(500);
 /// Size of the cache of archived segments for the purposes of faster sector expiration checks.
 const ARCHIVED_SEGMENTS_CACHE_SIZE: NonZeroUsize = NonZeroUsize::new(1000).expect("Not zero; qed");
+/// How often to check available space while plotting is paused due to disk being full
+const DISK_FULL_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+/// How many times sector is written again when it doesn't match its contents when read back
+const WRITE_VERIFICATION_RETRIES: u32 = 1;
+/// Written sector is read back and compared in chunks of this size, such that verification doesn't
+/// need a second sector-sized buffer
+const WRITE_VERIFICATION_CHUNK_SIZE: usize = Record::SIZE;
+/// Updates of metadata header that happen within this window while more sectors are queued are
+/// coalesced into a single write
+const METADATA_HEADER_WRITE_WINDOW: Duration = Duration::from_secs(5);
 
 pub(super) struct SectorToPlot {
     sector_index: SectorIndex,
@@ -50,6 +67,8 @@ pub(super) struct SectorToPlot {
     progress: f32,
     /// Whether this is the last sector queued so far
     last_queued: bool,
+    /// Whether sector has already expired when it was queued for replotting
+    expired: bool,
     acknowledgement_sender: oneshot::Sender<()>,
     next_segment_index_hint: Option<SectorIndex>,
 }
@@ -90,6 +109,22 @@ pub enum PlottingError {
     /// Background downloading panicked
     #[error("Background downloading panicked")]
     BackgroundDownloadingPanicked,
+    /// Sector didn't match its contents when read back after writing, even after writing it again
+    #[error("Sector {sector_index} didn't match its contents when read back after writing")]
+    WriteVerificationFailed {
+        /// Sector index that failed verification
+        sector_index: SectorIndex,
+    },
+    /// Plot file preallocation in the background failed, sectors can't be plotted past the part
+    /// that is preallocated
+    #[error("Can't preallocate plot file, probably not enough space on disk: {error}")]
+    PlotFilePreallocation {
+        /// Lower-level error
+        error: io::Error,
+    },
+    /// Plot cache error
+    #[error("Plot cache error: {0}")]
+    PlotCache(#[from] DiskPlotCacheError),
 }
 
 pub(super) struct PlottingOptions<'a, NC, PG> {
@@ -97,22 +132,28 @@ pub(super) struct PlottingOptions<'a, NC, PG> {
     pub(super) node_client: &'a NC,
     pub(super) pieces_in_sector: u16,
     pub(super) sector_size: usize,
-    pub(super) sector_metadata_size: usize,
     pub(super) metadata_header: PlotMetadataHeader,
-    #[cfg(not(windows))]
-    pub(super) plot_file: Arc<File>,
-    #[cfg(windows)]
-    pub(super) plot_file: Arc<UnbufferedIoFileWindows>,
-    #[cfg(not(windows))]
-    pub(super) metadata_file: File,
-    #[cfg(windows)]
-    pub(super) metadata_file: UnbufferedIoFileWindows,
+    /// Farm directory, used to check free space when disk is full
+    pub(super) directory: PathBuf,
+    pub(super) plot_file: Arc<PlotFile>,
+    /// All metadata updates go through the journal, which was recovered when farm was opened
+    pub(super) metadata_file: Arc<MetadataJournal<Arc<DirectIoFile>>>,
     pub(super) sectors_metadata: Arc<AsyncRwLock<Vec<SectorMetadataChecksummed>>>,
+    /// Sectors past this count were dropped by shrinking the farm and are not written anymore,
+    /// see [`FarmGrowHandle::shrink()`](super::grow::FarmGrowHandle::shrink)
+    pub(super) total_sectors_count: Arc<AtomicU16>,
+    /// Plot cache that uses space of the plot file that is not plotted yet
+    pub(super) plot_cache: DiskPlotCache,
     pub(super) piece_getter: &'a PG,
     pub(super) kzg: &'a Kzg,
     pub(super) erasure_coding: &'a ErasureCoding,
     pub(super) handlers: Arc<Handlers>,
     pub(super) modifying_sector_index: Arc<AsyncRwLock<Option<SectorIndex>>>,
+    /// Records sectors modified since files were last synced
+    pub(super) shutdown_marker: Arc<ShutdownMarker>,
+    /// Held for as long as sector is being modified, such that it is not rewritten concurrently
+    /// (see [`SingleDiskFarm::rewrite_sector()`](super::SingleDiskFarm::rewrite_sector))
+    pub(super) sector_modification_mutex: Arc<AsyncMutex<()>>,
     pub(super) sectors_to_plot_receiver: mpsc::Receiver<SectorToPlot>,
     /// Semaphore for part of the plotting when farmer downloads new sector, allows to limit memory
     /// usage of the plotting process, permit will be held until the end of the plotting process
@@ -121,6 +162,11 @@ pub(super) struct PlottingOptions<'a, NC, PG> {
     pub(super) plotting_thread_pool_manager: PlottingThreadPoolManager,
     pub(super) stop_receiver: broadcast::Receiver<()>,
     pub(super) global_mutex: &'a AsyncMutex<()>,
+    pub(super) durability: Durability,
+    /// Read sectors back after writing and compare with what was written
+    pub(super) verify_writes: bool,
+    /// Metrics of plot file, used to record write verification failures
+    pub(super) plot_file_metrics: Option<Arc<FileMetrics>>,
 }
 
 /// Starts plotting process.
@@ -140,24 +186,39 @@ where
         node_client,
         pieces_in_sector,
         sector_size,
-        sector_metadata_size,
-        mut metadata_header,
+        metadata_header,
+        directory,
         plot_file,
         metadata_file,
         sectors_metadata,
+        total_sectors_count,
+        plot_cache,
         piece_getter,
         kzg,
         erasure_coding,
         handlers,
         modifying_sector_index,
+        shutdown_marker,
+        sector_modification_mutex,
         mut sectors_to_plot_receiver,
         downloading_semaphore,
         record_encoding_concurrency,
         plotting_thread_pool_manager,
         mut stop_receiver,
         global_mutex,
+        durability,
+        verify_writes,
+        plot_file_metrics,
     } = plotting_options;
 
+    let metadata_layout = MetadataLayout::CURRENT;
+    let mut metadata_header_writer = MetadataHeaderWriter::new(
+        &metadata_file,
+        metadata_header,
+        &total_sectors_count,
+        METADATA_HEADER_WRITE_WINDOW,
+    );
+
     let abort_early = Arc::new(AtomicBool::new(false));
 
     let _abort_early_task = AsyncJoinOnDrop::new(
@@ -178,6 +239,7 @@ where
         .map(|_| PosTable::generator())
         .collect::<Vec<_>>();
 
+    let mut sectors_written = 0_u64;
     let mut maybe_next_downloaded_sector_fut = None::<
         AsyncJoinOnDrop<Result<(OwnedSemaphorePermit, DownloadedSector), plotting::PlottingError>>,
     >;
@@ -186,6 +248,7 @@ where
             sector_index,
             progress,
             last_queued,
+            expired,
             acknowledgement_sender: _acknowledgement_sender,
             // TODO: Remove this hint once we have
             //  https://github.com/rust-lang/futures-rs/issues/2793 and can
@@ -216,6 +279,9 @@ where
             .sector_update
             .call_simple(&(sector_index, sector_state));
 
+        let sector_offset = (sector_index as usize * sector_size) as u64;
+        let sector_metadata_offset = metadata_layout.sector_metadata_offset(sector_index);
+
         let start = Instant::now();
 
         // This `loop` is a workaround for edge-case in local setup if expiration is configured to
@@ -394,9 +460,41 @@ where
 
             plotting_result?
         };
-
-        // Inform others that this sector is being modified
+        // Padded to the whole slot, such that sector metadata is written without read-modify-write
+        let sector_metadata = metadata_layout.pad_sector_metadata(sector_metadata);
+
+        // Plotted sector takes precedence over pieces cached in space that is not plotted yet
+        plot_cache.reserve_sector(sector_index).await?;
+
+        // Inform others that this sector is being modified, sector stays online while new one is
+        // being plotted and only goes offline for the duration of the write
+        let sector_modification_guard = sector_modification_mutex.lock().await;
+        // Farm was shrunk since sector was queued, checked while holding the mutex such that
+        // shrinking doesn't miss the sector
+        if sector_index >= total_sectors_count.load(Ordering::Acquire) {
+            debug!(%sector_index, "Sector was dropped by shrinking the farm, skipping");
+            continue;
+        }
         modifying_sector_index.write().await.replace(sector_index);
+        // Recorded before anything is written, such that sector is checked after unclean shutdown
+        shutdown_marker.note_modified_sector(sector_index)?;
+
+        if expired {
+            // Contents of expired sector are useless, so its space is reclaimed before the new
+            // sector is written, which leaves room for it on thin-provisioned and compressed
+            // volumes
+            if let Err(error) = invalidate_sector(
+                plot_file.as_ref(),
+                &metadata_file,
+                sector_index,
+                pieces_in_sector,
+                sector_offset,
+                sector_size as u64,
+                sector_metadata_offset,
+            ) {
+                warn!(%error, %sector_index, "Failed to invalidate expired sector");
+            }
+        }
 
         {
             // Take mutex briefly to make sure writing is allowed right now
@@ -409,11 +507,49 @@ where
 
             let start = Instant::now();
 
-            plot_file.write_all_at(&sector, (sector_index as usize * sector_size) as u64)?;
-            metadata_file.write_all_at(
-                &sector_metadata,
-                RESERVED_PLOT_METADATA + (u64::from(sector_index) * sector_metadata_size as u64),
-            )?;
+            sectors_written += 1;
+            let written = write_sector_or_wait_for_space(
+                sector_index,
+                sector_size as u64 + metadata_layout.sector_metadata_slot_size(),
+                || {
+                    write_sector(
+                        &plot_file,
+                        &metadata_file,
+                        sector_index,
+                        &sector,
+                        sector_offset,
+                        &sector_metadata,
+                        sector_metadata_offset,
+                        durability.should_sync(sectors_written),
+                        verify_writes,
+                        plot_file_metrics.as_deref(),
+                    )
+                },
+                || {
+                    write_dummy_sector_metadata(
+                        &metadata_file,
+                        metadata_layout,
+                        sector_index,
+                        pieces_in_sector,
+                        sector_metadata_offset,
+                    )
+                },
+                || fs4::available_space(&directory),
+                DISK_FULL_CHECK_INTERVAL,
+                &handlers,
+                &abort_early,
+            )
+            .await?;
+
+            if !written {
+                return Ok(());
+            }
+            if durability.should_sync(sectors_written) {
+                // Files were synced after writing the sector
+                if let Err(error) = shutdown_marker.note_synced() {
+                    warn!(%error, "Failed to record synced files in shutdown marker");
+                }
+            }
 
             handlers.sector_update.call_simple(&(
                 sector_index,
@@ -421,9 +557,13 @@ where
             ));
         }
 
-        if sector_index + 1 > metadata_header.plotted_sector_count {
-            metadata_header.plotted_sector_count = sector_index + 1;
-            metadata_file.write_all_at(&metadata_header.encode(), 0)?;
+        if sector_index + 1 > metadata_header_writer.plotted_sector_count() {
+            // Header is only written right away if nothing else is queued or sync is due
+            metadata_header_writer.set_plotted_sector_count(
+                sector_index + 1,
+                last_queued,
+                durability.should_sync(sectors_written),
+            )?;
         }
         {
             let mut sectors_metadata = sectors_metadata.write().await;
@@ -436,6 +576,10 @@ where
             }
         }
 
+        // Inform others that this sector is no longer being modified
+        modifying_sector_index.write().await.take();
+        drop(sector_modification_guard);
+
         let maybe_old_plotted_sector = maybe_old_sector_metadata.map(|old_sector_metadata| {
             let old_history_size = old_sector_metadata.history_size;
 
@@ -462,9 +606,6 @@ where
             }
         });
 
-        // Inform others that this sector is no longer being modified
-        modifying_sector_index.write().await.take();
-
         if replotting {
             debug!(%sector_index, "Sector replotted successfully");
             if last_queued {
@@ -494,18 +635,350 @@ pub(super) struct PlottingSchedulerOptions<NC> {
     pub(super) public_key_hash: Blake3Hash,
     pub(super) sectors_indices_left_to_plot: Range<SectorIndex>,
     pub(super) target_sector_count: SectorIndex,
+    pub(super) sector_size: u64,
+    /// Sectors are only queued for plotting once they are within preallocated part of plot file
+    pub(super) preallocation_frontier: PreallocationFrontier,
     pub(super) last_archived_segment_index: SegmentIndex,
     pub(super) min_sector_lifetime: HistorySize,
     pub(super) node_client: NC,
     pub(super) handlers: Arc<Handlers>,
     pub(super) sectors_metadata: Arc<AsyncRwLock<Vec<SectorMetadataChecksummed>>>,
     pub(super) sectors_to_plot_sender: mpsc::Sender<SectorToPlot>,
+    /// Ranges of sector indices added to the farm by growing it, see
+    /// [`FarmGrowHandle::grow()`](super::grow::FarmGrowHandle::grow)
+    pub(super) new_sectors_receiver: mpsc::UnboundedReceiver<Range<SectorIndex>>,
+    /// Sectors found to be corrupted by integrity scrub, they are replotted right away
+    pub(super) corrupted_sectors_receiver: mpsc::UnboundedReceiver<SectorIndex>,
     pub(super) initial_plotting_finished: Option<oneshot::Sender<()>>,
     // Delay between segment header being acknowledged by farmer and potentially triggering
     // replotting
     pub(super) new_segment_processing_delay: Duration,
 }
 
+/// Write plotted sector and its metadata, if `sync` is `true` plot file is synced before metadata
+/// is written and metadata file is synced afterwards.
+///
+/// If `verify` is `true`, sector is read back right after writing and compared with `sector`, it is
+/// written again once on mismatch and [`PlottingError::WriteVerificationFailed`] is returned
+/// without writing metadata if mismatch persists, every mismatch is recorded in `metrics`.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn write_sector<PF, MF>(
+    plot_file: &PF,
+    metadata_file: &MF,
+    sector_index: SectorIndex,
+    sector: &[u8],
+    sector_offset: u64,
+    sector_metadata: &[u8],
+    sector_metadata_offset: u64,
+    sync: bool,
+    verify: bool,
+    metrics: Option<&FileMetrics>,
+) -> Result<(), PlottingError>
+where
+    PF: ReadAtSync + WriteAtSync,
+    MF: WriteAtSync,
+{
+    if verify {
+        let mut written_chunk = vec![0; sector.len().min(WRITE_VERIFICATION_CHUNK_SIZE)];
+        let mut failures = 0;
+        loop {
+            plot_file.write_all_at(sector, sector_offset)?;
+            if written_sector_matches(plot_file, sector, sector_offset, &mut written_chunk)? {
+                break;
+            }
+
+            failures += 1;
+            if let Some(metrics) = metrics {
+                metrics.observe_write_verification_failure();
+            }
+            if failures > WRITE_VERIFICATION_RETRIES {
+                return Err(PlottingError::WriteVerificationFailed { sector_index });
+            }
+            warn!(%sector_index, "Sector didn't match when read back after writing, retrying");
+        }
+    } else {
+        plot_file.write_all_at(sector, sector_offset)?;
+    }
+
+    if sync {
+        // Sector must be durable before metadata referencing it is written, otherwise metadata may
+        // survive a crash while sector contents do not
+        plot_file.sync_data()?;
+    }
+    metadata_file.write_all_at(sector_metadata, sector_metadata_offset)?;
+    if sync {
+        metadata_file.sync_data()?;
+    }
+
+    Ok(())
+}
+
+/// Read sector written at `sector_offset` back in chunks of `scratch_buffer` size and compare it
+/// with `sector`, stops at the first chunk that doesn't match
+fn written_sector_matches<PF>(
+    plot_file: &PF,
+    sector: &[u8],
+    sector_offset: u64,
+    scratch_buffer: &mut [u8],
+) -> io::Result<bool>
+where
+    PF: ReadAtSync,
+{
+    let mut offset = sector_offset;
+    for chunk in sector.chunks(scratch_buffer.len()) {
+        let written_chunk = &mut scratch_buffer[..chunk.len()];
+        plot_file.read_at(written_chunk, offset)?;
+        if written_chunk != chunk {
+            return Ok(false);
+        }
+        offset += chunk.len() as u64;
+    }
+
+    Ok(true)
+}
+
+/// Calls `write` until it succeeds.
+///
+/// If disk is full, `roll_back` is called such that partially written sector is not trusted,
+/// [`SectorPlottingDetails::DiskFull`] is sent and writing is paused until `available_space`
+/// reports at least `required_space` bytes, checked every `check_interval`.
+///
+/// Returns `Ok(false)` if plotting was aborted while paused.
+#[allow(clippy::too_many_arguments)]
+async fn write_sector_or_wait_for_space<W, R, AS>(
+    sector_index: SectorIndex,
+    required_space: u64,
+    mut write: W,
+    mut roll_back: R,
+    mut available_space: AS,
+    check_interval: Duration,
+    handlers: &Handlers,
+    abort_early: &AtomicBool,
+) -> Result<bool, PlottingError>
+where
+    W: FnMut() -> Result<(), PlottingError>,
+    R: FnMut() -> io::Result<()>,
+    AS: FnMut() -> io::Result<u64>,
+{
+    loop {
+        let error = match write() {
+            Ok(()) => {
+                return Ok(true);
+            }
+            Err(PlottingError::Io(error)) if is_disk_full(&error) => error,
+            Err(error) => {
+                return Err(error);
+            }
+        };
+
+        warn!(
+            %error,
+            %sector_index,
+            %required_space,
+            "Disk is full, pausing plotting until enough free space is available"
+        );
+
+        if let Err(error) = roll_back() {
+            // Header was not updated yet, so this only matters when replotting
+            error!(%error, %sector_index, "Failed to roll back sector metadata");
+        }
+
+        handlers.sector_update.call_simple(&(
+            sector_index,
+            SectorUpdate::Plotting(SectorPlottingDetails::DiskFull { required_space }),
+        ));
+
+        loop {
+            if abort_early.load(Ordering::Acquire) {
+                return Ok(false);
+            }
+
+            tokio::time::sleep(check_interval).await;
+
+            match available_space() {
+                Ok(available_space) if available_space >= required_space => {
+                    break;
+                }
+                Ok(available_space) => {
+                    trace!(%available_space, %required_space, "Not enough free space yet");
+                }
+                Err(error) => {
+                    // Try to write anyway, if disk is still full then writing will be paused again
+                    warn!(%error, "Failed to check available space");
+                    break;
+                }
+            }
+        }
+
+        info!(%sector_index, "Enough free space is available, resuming plotting");
+
+        handlers.sector_update.call_simple(&(
+            sector_index,
+            SectorUpdate::Plotting(SectorPlottingDetails::Writing),
+        ));
+    }
+}
+
+/// Replace metadata of the sector with dummy metadata of expired sector and sync metadata file,
+/// such that sector that was not fully written is replotted rather than trusted after restart
+pub(super) fn write_dummy_sector_metadata<MF>(
+    metadata_file: &MF,
+    metadata_layout: MetadataLayout,
+    sector_index: SectorIndex,
+    pieces_in_sector: u16,
+    sector_metadata_offset: u64,
+) -> io::Result<()>
+where
+    MF: WriteAtSync,
+{
+    let dummy_sector_metadata = SectorMetadataChecksummed::from(SectorMetadata {
+        sector_index,
+        pieces_in_sector,
+        s_bucket_sizes: Box::new([0; Record::NUM_S_BUCKETS]),
+        history_size: HistorySize::from(SegmentIndex::ZERO),
+    });
+
+    metadata_file.write_all_at(
+        &metadata_layout.encode_sector_metadata(&dummy_sector_metadata),
+        sector_metadata_offset,
+    )?;
+    metadata_file.sync_data()
+}
+
+/// Invalidate sector by replacing its metadata with dummy metadata of expired sector and
+/// deallocating its space in the plot file, such that disk space is returned to the file system
+/// (matters for thin-provisioned and compressed volumes) before replotted sector is written
+#[allow(clippy::too_many_arguments)]
+fn invalidate_sector<PF, MF>(
+    plot_file: &PF,
+    metadata_file: &MF,
+    sector_index: SectorIndex,
+    pieces_in_sector: u16,
+    sector_offset: u64,
+    sector_size: u64,
+    sector_metadata_offset: u64,
+) -> io::Result<()>
+where
+    PF: FileExt,
+    MF: WriteAtSync,
+{
+    // Metadata is replaced first, such that sector is not trusted after restart even if its
+    // contents were deallocated partially
+    write_dummy_sector_metadata(
+        metadata_file,
+        MetadataLayout::CURRENT,
+        sector_index,
+        pieces_in_sector,
+        sector_metadata_offset,
+    )?;
+    plot_file.punch_hole(sector_offset, sector_size)
+}
+
+/// Write metadata header padded to disk sector, syncing metadata file afterwards if `sync` is
+/// `true`
+fn write_metadata_header<MF>(
+    metadata_file: &MF,
+    metadata_header: &PlotMetadataHeader,
+    sync: bool,
+) -> io::Result<()>
+where
+    MF: WriteAtSync,
+{
+    metadata_file.write_all_at(&MetadataLayout::CURRENT.encode_header(metadata_header), 0)?;
+
+    if sync {
+        metadata_file.sync_data()?;
+    }
+
+    Ok(())
+}
+
+/// Coalesces updates of metadata header that happen within a window into a single write.
+///
+/// Header that is not written yet only undercounts plotted sectors, such that sectors plotted since
+/// the last write are plotted again after a crash. Pending update is written on drop.
+///
+/// Number of plotted sectors never exceeds total number of sectors, which decreases when farm is
+/// shrunk, such that pending update doesn't refer to dropped sectors.
+struct MetadataHeaderWriter<'a, MF>
+where
+    MF: WriteAtSync,
+{
+    metadata_file: &'a MF,
+    metadata_header: PlotMetadataHeader,
+    total_sectors_count: &'a AtomicU16,
+    pending_since: Option<Instant>,
+    window: Duration,
+}
+
+impl<MF> Drop for MetadataHeaderWriter<'_, MF>
+where
+    MF: WriteAtSync,
+{
+    fn drop(&mut self) {
+        if let Err(error) = self.flush(false) {
+            warn!(%error, "Failed to write pending metadata header update");
+        }
+    }
+}
+
+impl<'a, MF> MetadataHeaderWriter<'a, MF>
+where
+    MF: WriteAtSync,
+{
+    fn new(
+        metadata_file: &'a MF,
+        metadata_header: PlotMetadataHeader,
+        total_sectors_count: &'a AtomicU16,
+        window: Duration,
+    ) -> Self {
+        Self {
+            metadata_file,
+            metadata_header,
+            total_sectors_count,
+            pending_since: None,
+            window,
+        }
+    }
+
+    /// Number of plotted sectors including pending update
+    fn plotted_sector_count(&self) -> SectorIndex {
+        self.metadata_header
+            .plotted_sector_count
+            .min(self.total_sectors_count.load(Ordering::Acquire))
+    }
+
+    /// Update number of plotted sectors, header is written right away if `flush` or `sync` is
+    /// `true` or if the oldest pending update is older than the window, see
+    /// [`write_metadata_header()`]
+    fn set_plotted_sector_count(
+        &mut self,
+        plotted_sector_count: SectorIndex,
+        flush: bool,
+        sync: bool,
+    ) -> io::Result<()> {
+        self.metadata_header.plotted_sector_count = plotted_sector_count;
+        let pending_since = *self.pending_since.get_or_insert_with(Instant::now);
+
+        if flush || sync || pending_since.elapsed() >= self.window {
+            self.flush(sync)?;
+        }
+
+        Ok(())
+    }
+
+    /// Write pending update, if any
+    fn flush(&mut self, sync: bool) -> io::Result<()> {
+        if self.pending_since.is_some() {
+            self.metadata_header.plotted_sector_count = self.plotted_sector_count();
+            write_metadata_header(self.metadata_file, &self.metadata_header, sync)?;
+            self.pending_since = None;
+        }
+
+        Ok(())
+    }
+}
+
 pub(super) async fn plotting_scheduler<NC>(
     plotting_scheduler_options: PlottingSchedulerOptions<NC>,
 ) -> Result<(), BackgroundTaskError>
@@ -516,12 +989,16 @@ where
         public_key_hash,
         sectors_indices_left_to_plot,
         target_sector_count,
+        sector_size,
+        preallocation_frontier,
         last_archived_segment_index,
         min_sector_lifetime,
         node_client,
         handlers,
         sectors_metadata,
         sectors_to_plot_sender,
+        new_sectors_receiver,
+        corrupted_sectors_receiver,
         initial_plotting_finished,
         new_segment_processing_delay,
     } = plotting_scheduler_options;
@@ -549,16 +1026,29 @@ where
         new_segment_processing_delay,
     );
 
+    // Sectors of grown farm are queued independently, such that replotting is not blocked while
+    // they are plotted
+    let queue_grown_sectors_fut = queue_grown_sectors(
+        new_sectors_receiver,
+        target_sector_count,
+        sector_size,
+        preallocation_frontier.clone(),
+        sectors_to_plot_sender.clone(),
+    );
+
     let send_plotting_notifications_fut = send_plotting_notifications(
         public_key_hash,
         sectors_indices_left_to_plot,
         target_sector_count,
+        sector_size,
+        preallocation_frontier,
         min_sector_lifetime,
         &node_client,
         &handlers,
         sectors_metadata,
         archived_segments_receiver,
         sectors_to_plot_sender,
+        corrupted_sectors_receiver,
         initial_plotting_finished,
     );
 
@@ -569,6 +1059,9 @@ where
         result = send_plotting_notifications_fut.fuse() => {
             result
         }
+        () = queue_grown_sectors_fut.fuse() => {
+            Ok(())
+        }
     }
 }
 
@@ -618,37 +1111,31 @@ async fn send_plotting_notifications<NC>(
     public_key_hash: Blake3Hash,
     sectors_indices_left_to_plot: Range<SectorIndex>,
     target_sector_count: SectorIndex,
+    sector_size: u64,
+    mut preallocation_frontier: PreallocationFrontier,
     min_sector_lifetime: HistorySize,
     node_client: &NC,
     handlers: &Handlers,
     sectors_metadata: Arc<AsyncRwLock<Vec<SectorMetadataChecksummed>>>,
     mut archived_segments_receiver: watch::Receiver<SegmentHeader>,
     mut sectors_to_plot_sender: mpsc::Sender<SectorToPlot>,
+    mut corrupted_sectors_receiver: mpsc::UnboundedReceiver<SectorIndex>,
     initial_plotting_finished: Option<oneshot::Sender<()>>,
 ) -> Result<(), BackgroundTaskError>
 where
     NC: NodeClient,
 {
     // Finish initial plotting if some sectors were not plotted fully yet
-    let mut sectors_indices_left_to_plot = sectors_indices_left_to_plot.into_iter().peekable();
-    while let Some(sector_index) = sectors_indices_left_to_plot.next() {
-        let (acknowledgement_sender, acknowledgement_receiver) = oneshot::channel();
-        if let Err(error) = sectors_to_plot_sender
-            .send(SectorToPlot {
-                sector_index,
-                progress: sector_index as f32 / target_sector_count as f32 * 100.0,
-                last_queued: sector_index + 1 == target_sector_count,
-                acknowledgement_sender,
-                next_segment_index_hint: sectors_indices_left_to_plot.peek().copied(),
-            })
-            .await
-        {
-            warn!(%error, "Failed to send sector index for initial plotting");
-            return Ok(());
-        }
-
-        // We do not care if message was sent back or sender was just dropped
-        let _ = acknowledgement_receiver.await;
+    if !queue_new_sectors(
+        sectors_indices_left_to_plot,
+        target_sector_count,
+        sector_size,
+        &mut preallocation_frontier,
+        &mut sectors_to_plot_sender,
+    )
+    .await
+    {
+        return Ok(());
     }
 
     if let Some(initial_plotting_finished) = initial_plotting_finished {
@@ -823,8 +1310,13 @@ where
         let sectors_queued = sectors_to_replot.len();
         sectors_to_replot.sort_by_key(|sector_to_replot| sector_to_replot.expires_at);
         let mut sector_indices_to_replot = sectors_to_replot.drain(..).enumerate().peekable();
-        while let Some((index, SectorToReplot { sector_index, .. })) =
-            sector_indices_to_replot.next()
+        while let Some((
+            index,
+            SectorToReplot {
+                sector_index,
+                expires_at,
+            },
+        )) = sector_indices_to_replot.next()
         {
             let (acknowledgement_sender, acknowledgement_receiver) = oneshot::channel();
             if let Err(error) = sectors_to_plot_sender
@@ -832,6 +1324,7 @@ where
                     sector_index,
                     progress: index as f32 / sectors_queued as f32 * 100.0,
                     last_queued: index + 1 == sectors_queued,
+                    expired: expires_at <= archived_segment_header.segment_index(),
                     acknowledgement_sender,
                     next_segment_index_hint: sector_indices_to_replot
                         .peek()
@@ -849,10 +1342,114 @@ where
             sectors_expire_at.remove(&sector_index);
         }
 
-        if archived_segments_receiver.changed().await.is_err() {
-            break;
+        select! {
+            result = archived_segments_receiver.changed().fuse() => {
+                if result.is_err() {
+                    break;
+                }
+            }
+            sector_index = corrupted_sectors_receiver.select_next_some() => {
+                // Contents of corrupted sector are useless, so it is replotted like expired sector
+                let (acknowledgement_sender, acknowledgement_receiver) = oneshot::channel();
+                if let Err(error) = sectors_to_plot_sender
+                    .send(SectorToPlot {
+                        sector_index,
+                        progress: 0.0,
+                        last_queued: true,
+                        expired: true,
+                        acknowledgement_sender,
+                        next_segment_index_hint: None,
+                    })
+                    .await
+                {
+                    warn!(%error, "Failed to send corrupted sector index for replotting");
+                    return Ok(());
+                }
+
+                // We do not care if message was sent back or sender was just dropped
+                let _ = acknowledgement_receiver.await;
+
+                sectors_expire_at.remove(&sector_index);
+            }
         }
     }
 
     Ok(())
 }
+
+/// Queue sectors added to the farm by growing it (see
+/// [`FarmGrowHandle::grow()`](super::grow::FarmGrowHandle::grow)) for plotting as they arrive, see
+/// [`queue_new_sectors()`].
+///
+/// Returns once plotting is no longer running, never returns if farm is not grown anymore.
+async fn queue_grown_sectors(
+    mut new_sectors_receiver: mpsc::UnboundedReceiver<Range<SectorIndex>>,
+    mut target_sector_count: SectorIndex,
+    sector_size: u64,
+    mut preallocation_frontier: PreallocationFrontier,
+    mut sectors_to_plot_sender: mpsc::Sender<SectorToPlot>,
+) {
+    while let Some(new_sectors) = new_sectors_receiver.next().await {
+        target_sector_count = target_sector_count.max(new_sectors.end);
+        if !queue_new_sectors(
+            new_sectors,
+            target_sector_count,
+            sector_size,
+            &mut preallocation_frontier,
+            &mut sectors_to_plot_sender,
+        )
+        .await
+        {
+            return;
+        }
+    }
+
+    // Grow handle is dropped together with the farm, plotting stops on its own
+    pending().await
+}
+
+/// Queue sectors that were never plotted for plotting one by one, waiting for each to be plotted
+/// before queueing the next one.
+///
+/// Sector is only queued once plot file is preallocated up to its end according to
+/// `preallocation_frontier`. Returns `false` if plotting is no longer running or plot file
+/// preallocation stopped before reaching the sector.
+async fn queue_new_sectors(
+    sector_indices: Range<SectorIndex>,
+    target_sector_count: SectorIndex,
+    sector_size: u64,
+    preallocation_frontier: &mut PreallocationFrontier,
+    sectors_to_plot_sender: &mut mpsc::Sender<SectorToPlot>,
+) -> bool {
+    let mut sector_indices = sector_indices.peekable();
+    while let Some(sector_index) = sector_indices.next() {
+        if !preallocation_frontier
+            .wait_for((u64::from(sector_index) + 1) * sector_size)
+            .await
+        {
+            warn!(%sector_index, "Plot file preallocation stopped before reaching sector");
+            return false;
+        }
+
+        let (acknowledgement_sender, acknowledgement_receiver) = oneshot::channel();
+        if let Err(error) = sectors_to_plot_sender
+            .send(SectorToPlot {
+                sector_index,
+                progress: sector_index as f32 / target_sector_count as f32 * 100.0,
+                last_queued: sector_index + 1 == target_sector_count,
+                expired: false,
+                acknowledgement_sender,
+                next_segment_index_hint: sector_indices.peek().copied(),
+            })
+            .await
+        {
+            warn!(%error, "Failed to send sector index for initial plotting");
+            return false;
+        }
+
+        // We do not care if message was sent back or sender was just dropped
+        let _ = acknowledgement_receiver.await;
+    }
+
+    true
+}
@@ -0,0 +1,738 @@
+use crate::farm::{FarmId, SectorPlottingDetails, SectorUpdate};
+use crate::single_disk_farm::direct_io_file::metrics::{DiskFileKind, DiskMetrics};
+use crate::single_disk_farm::direct_io_file::{is_disk_full, DirectIoFile, DISK_SECTOR_SIZE};
+use crate::single_disk_farm::metadata_layout::MetadataLayout;
+use crate::single_disk_farm::plotting::{
+    invalidate_sector, queue_grown_sectors, queue_new_sectors, write_dummy_sector_metadata,
+    write_metadata_header, write_sector, write_sector_or_wait_for_space, written_sector_matches,
+    MetadataHeaderWriter, PlottingError, SectorToPlot,
+};
+use crate::single_disk_farm::preallocation::{
+    BackgroundPreallocation, PreallocatedSize, PreallocationFrontier,
+};
+use crate::single_disk_farm::{Durability, Handlers, PlotMetadataHeader};
+use futures::channel::{mpsc, oneshot};
+use futures::{SinkExt, StreamExt};
+use parity_scale_codec::Decode;
+use parking_lot::Mutex;
+use prometheus_client::registry::Registry;
+use rand::prelude::*;
+use std::assert_matches::assert_matches;
+use std::collections::VecDeque;
+use std::fs::OpenOptions;
+use std::num::{NonZeroU32, NonZeroU64};
+use std::sync::atomic::{AtomicBool, AtomicU16, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use std::{fs, io, thread};
+use subspace_core_primitives::{HistorySize, SectorIndex, SegmentIndex};
+use subspace_farmer_components::faulty_file::{Fault, FaultRule, FaultyFile, Operation};
+use subspace_farmer_components::file_ext::FileExt;
+use subspace_farmer_components::in_memory_file::InMemoryFile;
+use subspace_farmer_components::sector::SectorMetadataChecksummed;
+use subspace_farmer_components::{ReadAtSync, WriteAtSync};
+use tempfile::tempdir;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Call {
+    Write { offset: u64, len: usize },
+    SyncData,
+}
+
+/// Mock file that records writes and syncs
+#[derive(Debug, Default)]
+struct MockFile {
+    calls: Mutex<Vec<Call>>,
+}
+
+impl MockFile {
+    fn take_calls(&self) -> Vec<Call> {
+        std::mem::take(&mut *self.calls.lock())
+    }
+}
+
+impl ReadAtSync for MockFile {
+    fn read_at(&self, _buf: &mut [u8], _offset: u64) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl WriteAtSync for MockFile {
+    fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        self.calls.lock().push(Call::Write {
+            offset,
+            len: buf.len(),
+        });
+        Ok(())
+    }
+
+    fn sync_data(&self) -> io::Result<()> {
+        self.calls.lock().push(Call::SyncData);
+        Ok(())
+    }
+}
+
+#[test]
+fn sync_after_writes() {
+    let plot_file = MockFile::default();
+    let metadata_file = MockFile::default();
+    let metadata_header = PlotMetadataHeader {
+        version: 0,
+        plotted_sector_count: 1,
+    };
+
+    write_sector(
+        &plot_file,
+        &metadata_file,
+        3,
+        &[0; 10],
+        100,
+        &[0; 5],
+        50,
+        true,
+        false,
+        None,
+    )
+    .unwrap();
+    assert_eq!(
+        plot_file.take_calls(),
+        vec![
+            Call::Write {
+                offset: 100,
+                len: 10
+            },
+            Call::SyncData
+        ]
+    );
+    assert_eq!(
+        metadata_file.take_calls(),
+        vec![Call::Write { offset: 50, len: 5 }, Call::SyncData]
+    );
+
+    write_metadata_header(&metadata_file, &metadata_header, true).unwrap();
+    assert_eq!(
+        metadata_file.take_calls(),
+        vec![
+            Call::Write {
+                offset: 0,
+                len: DISK_SECTOR_SIZE
+            },
+            Call::SyncData
+        ]
+    );
+
+    write_sector(
+        &plot_file,
+        &metadata_file,
+        3,
+        &[0; 10],
+        100,
+        &[0; 5],
+        50,
+        false,
+        false,
+        None,
+    )
+    .unwrap();
+    write_metadata_header(&metadata_file, &metadata_header, false).unwrap();
+    assert!(!plot_file.take_calls().contains(&Call::SyncData));
+    assert!(!metadata_file.take_calls().contains(&Call::SyncData));
+}
+
+#[test]
+fn durability() {
+    for sectors_written in 1..=10 {
+        assert!(Durability::Always.should_sync(sectors_written));
+        assert!(!Durability::Never.should_sync(sectors_written));
+        assert_eq!(
+            Durability::Periodic(NonZeroU32::new(3).unwrap()).should_sync(sectors_written),
+            sectors_written % 3 == 0
+        );
+    }
+}
+
+#[test]
+fn coalesced_metadata_header_writes() {
+    let metadata_file = MockFile::default();
+    let metadata_header = PlotMetadataHeader {
+        version: MetadataLayout::CURRENT.version(),
+        plotted_sector_count: 0,
+    };
+    let header_write = Call::Write {
+        offset: 0,
+        len: DISK_SECTOR_SIZE,
+    };
+
+    let total_sectors_count = AtomicU16::new(SectorIndex::MAX - 1);
+
+    {
+        let mut writer = MetadataHeaderWriter::new(
+            &metadata_file,
+            metadata_header,
+            &total_sectors_count,
+            Duration::from_secs(3600),
+        );
+
+        // Updates within the window are coalesced while more sectors are queued
+        for plotted_sector_count in 1..=3 {
+            writer
+                .set_plotted_sector_count(plotted_sector_count, false, false)
+                .unwrap();
+        }
+        assert!(metadata_file.take_calls().is_empty());
+        assert_eq!(writer.plotted_sector_count(), 3);
+
+        // Written right away when nothing else is queued
+        writer.set_plotted_sector_count(4, true, false).unwrap();
+        assert_eq!(metadata_file.take_calls(), vec![header_write]);
+
+        // Written and synced right away when sync is due
+        writer.set_plotted_sector_count(5, false, true).unwrap();
+        assert_eq!(
+            metadata_file.take_calls(),
+            vec![header_write, Call::SyncData]
+        );
+
+        // Pending update is written on drop
+        writer.set_plotted_sector_count(6, false, false).unwrap();
+        assert!(metadata_file.take_calls().is_empty());
+
+        // Sectors dropped by shrinking are not counted as plotted anymore
+        total_sectors_count.store(4, Ordering::Release);
+        assert_eq!(writer.plotted_sector_count(), 4);
+    }
+    assert_eq!(metadata_file.take_calls(), vec![header_write]);
+
+    // Every update is written once window has passed
+    let mut writer = MetadataHeaderWriter::new(
+        &metadata_file,
+        PlotMetadataHeader {
+            version: MetadataLayout::CURRENT.version(),
+            plotted_sector_count: 0,
+        },
+        &total_sectors_count,
+        Duration::ZERO,
+    );
+    writer.set_plotted_sector_count(1, false, false).unwrap();
+    writer.set_plotted_sector_count(2, false, false).unwrap();
+    drop(writer);
+    assert_eq!(metadata_file.take_calls(), vec![header_write, header_write]);
+}
+
+fn disk_full_error() -> io::Error {
+    #[cfg(target_os = "linux")]
+    {
+        // `ENOSPC`
+        io::Error::from_raw_os_error(28)
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        io::Error::from(io::ErrorKind::StorageFull)
+    }
+}
+
+#[tokio::test]
+async fn disk_full() {
+    assert!(is_disk_full(&disk_full_error()));
+    assert!(!is_disk_full(&io::Error::from(io::ErrorKind::Other)));
+
+    let plot_file = FaultyFile::new(InMemoryFile::default());
+    let metadata_file = InMemoryFile::default();
+    let handlers = Handlers::default();
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let _handler_id = handlers.sector_update.add(Arc::new({
+        let events = Arc::clone(&events);
+
+        move |(_sector_index, sector_update)| {
+            if let SectorUpdate::Plotting(sector_plotting_details) = sector_update {
+                events.lock().push(sector_plotting_details.clone());
+            }
+        }
+    }));
+
+    plot_file.inject(FaultRule::new(
+        Operation::Write,
+        Fault::Fail(io::ErrorKind::StorageFull),
+    ));
+    // Not enough space on the first check
+    let mut available_space = VecDeque::from([10, 100]);
+
+    let written = write_sector_or_wait_for_space(
+        3,
+        100,
+        || {
+            write_sector(
+                &plot_file,
+                &metadata_file,
+                3,
+                &[0; 10],
+                100,
+                &[0; 5],
+                50,
+                false,
+                false,
+                None,
+            )
+        },
+        || write_dummy_sector_metadata(&metadata_file, MetadataLayout::CURRENT, 3, 1, 50),
+        || Ok(available_space.pop_front().unwrap()),
+        Duration::ZERO,
+        &handlers,
+        &AtomicBool::new(false),
+    )
+    .await
+    .unwrap();
+
+    assert!(written);
+    assert!(available_space.is_empty());
+    // Sector was written once enough space became available
+    assert_eq!(plot_file.inner().bytes().len(), 110);
+    // Sector metadata was rolled back and synced before pausing
+    assert_eq!(metadata_file.syncs(), 1);
+    assert_eq!(
+        metadata_file.bytes().len() as u64,
+        50 + MetadataLayout::CURRENT.sector_metadata_slot_size()
+    );
+    {
+        let events = events.lock();
+        assert_eq!(events.len(), 2);
+        assert_matches!(
+            events[0],
+            SectorPlottingDetails::DiskFull {
+                required_space: 100
+            }
+        );
+        assert_matches!(events[1], SectorPlottingDetails::Writing);
+    }
+
+    // Plotting is aborted while paused, sector doesn't fit into the disk
+    plot_file.set_capacity(Some(105));
+    let written = write_sector_or_wait_for_space(
+        3,
+        100,
+        || {
+            write_sector(
+                &plot_file,
+                &metadata_file,
+                3,
+                &[0; 10],
+                100,
+                &[0; 5],
+                50,
+                false,
+                false,
+                None,
+            )
+        },
+        || write_dummy_sector_metadata(&metadata_file, MetadataLayout::CURRENT, 3, 1, 50),
+        || Ok(0),
+        Duration::ZERO,
+        &handlers,
+        &AtomicBool::new(true),
+    )
+    .await
+    .unwrap();
+    assert!(!written);
+    assert_eq!(metadata_file.syncs(), 2);
+    // Sector metadata is rolled back and will be replotted after restart
+    let rolled_back_metadata =
+        SectorMetadataChecksummed::decode(&mut &metadata_file.bytes()[50..]).unwrap();
+    assert_eq!(rolled_back_metadata.sector_index, 3);
+    assert_eq!(
+        rolled_back_metadata.history_size,
+        HistorySize::from(SegmentIndex::ZERO)
+    );
+
+    // Other errors are returned as is
+    plot_file.set_capacity(None);
+    plot_file.inject(FaultRule::new(
+        Operation::Write,
+        Fault::Fail(io::ErrorKind::PermissionDenied),
+    ));
+    let result = write_sector_or_wait_for_space(
+        3,
+        100,
+        || {
+            write_sector(
+                &plot_file,
+                &metadata_file,
+                3,
+                &[0; 10],
+                100,
+                &[0; 5],
+                50,
+                false,
+                false,
+                None,
+            )
+        },
+        || write_dummy_sector_metadata(&metadata_file, MetadataLayout::CURRENT, 3, 1, 50),
+        || Ok(0),
+        Duration::ZERO,
+        &handlers,
+        &AtomicBool::new(false),
+    )
+    .await;
+    assert_matches!(result, Err(PlottingError::Io(_)));
+}
+
+#[test]
+fn invalidate_expired_sector() {
+    const SECTOR_SIZE: usize = DISK_SECTOR_SIZE * 2;
+
+    let tempdir = tempdir().unwrap();
+    let plot_path = tempdir.as_ref().join("plot.bin");
+    let mut plot = vec![0u8; SECTOR_SIZE * 3];
+    thread_rng().fill(plot.as_mut_slice());
+    fs::write(&plot_path, &plot).unwrap();
+
+    let plot_file = DirectIoFile::open(&plot_path).unwrap();
+    let metadata_file = InMemoryFile::default();
+
+    invalidate_sector(
+        &plot_file,
+        &metadata_file,
+        1,
+        1,
+        SECTOR_SIZE as u64,
+        SECTOR_SIZE as u64,
+        50,
+    )
+    .unwrap();
+
+    // Only contents of invalidated sector are gone
+    plot[SECTOR_SIZE..][..SECTOR_SIZE].fill(0);
+    assert_eq!(fs::read(&plot_path).unwrap(), plot);
+
+    // Sector metadata is replaced and synced, such that sector will be replotted after restart
+    assert_eq!(metadata_file.syncs(), 1);
+    let invalidated_metadata =
+        SectorMetadataChecksummed::decode(&mut &metadata_file.bytes()[50..]).unwrap();
+    assert_eq!(invalidated_metadata.sector_index, 1);
+    assert_eq!(
+        invalidated_metadata.history_size,
+        HistorySize::from(SegmentIndex::ZERO)
+    );
+}
+
+#[test]
+fn verify_writes() {
+    let disk_metrics = DiskMetrics::new(&mut Registry::default());
+    let metrics = disk_metrics.file_metrics(&FarmId::new(), DiskFileKind::Plot);
+    let sector = (0..100).collect::<Vec<u8>>();
+    let write = |plot_file: &FaultyFile<InMemoryFile>, metadata_file: &InMemoryFile| {
+        write_sector(
+            plot_file,
+            metadata_file,
+            3,
+            &sector,
+            100,
+            &[1; 5],
+            50,
+            false,
+            true,
+            Some(&*metrics),
+        )
+    };
+
+    // Corrupted write is detected and sector is written again
+    let plot_file = FaultyFile::new(InMemoryFile::default());
+    plot_file.inject(FaultRule::new(Operation::Write, Fault::Corrupt));
+    let metadata_file = InMemoryFile::default();
+    write(&plot_file, &metadata_file).unwrap();
+    assert_eq!(plot_file.inner().bytes()[100..], sector);
+    assert_eq!(metadata_file.bytes()[50..], [1; 5]);
+    assert_eq!(metrics.write_verification_failures.get(), 1);
+    assert_eq!(
+        plot_file
+            .history()
+            .into_iter()
+            .map(|record| (record.operation, record.fault))
+            .collect::<Vec<_>>(),
+        vec![
+            (Operation::Write, Some(Fault::Corrupt)),
+            (Operation::Read, None),
+            (Operation::Write, None),
+            (Operation::Read, None),
+        ]
+    );
+
+    // Sector fails if corruption persists, metadata is not written
+    let plot_file = FaultyFile::new(InMemoryFile::default());
+    plot_file.inject(FaultRule::new(Operation::Write, Fault::Corrupt).times(usize::MAX));
+    let metadata_file = InMemoryFile::default();
+    assert_matches!(
+        write(&plot_file, &metadata_file),
+        Err(PlottingError::WriteVerificationFailed { sector_index: 3 })
+    );
+    assert!(metadata_file.bytes().is_empty());
+    assert_eq!(metrics.write_verification_failures.get(), 3);
+
+    // Failure to read sector back is not a verification failure
+    let plot_file = FaultyFile::new(InMemoryFile::default());
+    plot_file.inject(FaultRule::new(
+        Operation::Read,
+        Fault::Fail(io::ErrorKind::Other),
+    ));
+    let metadata_file = InMemoryFile::default();
+    assert_matches!(write(&plot_file, &metadata_file), Err(PlottingError::Io(_)));
+    assert!(metadata_file.bytes().is_empty());
+    assert_eq!(metrics.write_verification_failures.get(), 3);
+
+    // Nothing is read back without verification
+    let plot_file = FaultyFile::new(InMemoryFile::default());
+    plot_file.inject(FaultRule::new(Operation::Write, Fault::Corrupt));
+    write_sector(
+        &plot_file,
+        &metadata_file,
+        3,
+        &sector,
+        100,
+        &[1; 5],
+        50,
+        false,
+        false,
+        Some(&*metrics),
+    )
+    .unwrap();
+    assert_ne!(plot_file.inner().bytes()[100..], sector);
+    assert!(plot_file
+        .history()
+        .iter()
+        .all(|record| record.operation == Operation::Write));
+    assert_eq!(metrics.write_verification_failures.get(), 3);
+}
+
+#[test]
+fn verify_written_sector_in_chunks() {
+    let sector = (0..100).collect::<Vec<u8>>();
+    let plot_file = FaultyFile::new(InMemoryFile::default());
+    WriteAtSync::write_all_at(&plot_file, &sector, 100).unwrap();
+    let mut scratch_buffer = vec![0; 30];
+
+    assert!(written_sector_matches(&plot_file, &sector, 100, &mut scratch_buffer).unwrap());
+    // Sector is read in chunks of scratch buffer size, the last one is shorter
+    assert_eq!(
+        plot_file
+            .history()
+            .into_iter()
+            .filter(|record| record.operation == Operation::Read)
+            .map(|record| (record.offset, record.len))
+            .collect::<Vec<_>>(),
+        vec![(100, 30), (130, 30), (160, 30), (190, 10)]
+    );
+
+    // Reading stops at the first chunk that doesn't match
+    WriteAtSync::write_all_at(&plot_file, &[0], 145).unwrap();
+    let reads_before = plot_file.history().len();
+    assert!(!written_sector_matches(&plot_file, &sector, 100, &mut scratch_buffer).unwrap());
+    assert_eq!(plot_file.history().len() - reads_before, 2);
+}
+
+#[tokio::test]
+async fn queue_sectors_of_grown_farm() {
+    let (mut sectors_to_plot_sender, mut sectors_to_plot_receiver) = mpsc::channel(1);
+    let queued_fut = tokio::spawn(async move {
+        let mut queued = Vec::new();
+        // Sector is acknowledged when it is dropped
+        while let Some(sector_to_plot) = sectors_to_plot_receiver.next().await {
+            assert!(!sector_to_plot.expired);
+            queued.push((
+                sector_to_plot.sector_index,
+                sector_to_plot.last_queued,
+                sector_to_plot.next_segment_index_hint,
+            ));
+        }
+        queued
+    });
+
+    // Initial plotting of two sectors followed by two sectors of grown farm
+    let mut preallocation_frontier = PreallocationFrontier::complete();
+    assert!(
+        queue_new_sectors(
+            0..2,
+            2,
+            100,
+            &mut preallocation_frontier,
+            &mut sectors_to_plot_sender
+        )
+        .await
+    );
+    assert!(
+        queue_new_sectors(
+            2..4,
+            4,
+            100,
+            &mut preallocation_frontier,
+            &mut sectors_to_plot_sender
+        )
+        .await
+    );
+    drop(sectors_to_plot_sender);
+
+    assert_eq!(
+        queued_fut.await.unwrap(),
+        vec![
+            (0, false, Some(1)),
+            (1, true, None),
+            (2, false, Some(3)),
+            (3, true, None)
+        ]
+    );
+
+    // Plotting is no longer running
+    let (mut sectors_to_plot_sender, sectors_to_plot_receiver) = mpsc::channel(1);
+    drop(sectors_to_plot_receiver);
+    assert!(
+        !queue_new_sectors(
+            0..1,
+            1,
+            100,
+            &mut preallocation_frontier,
+            &mut sectors_to_plot_sender
+        )
+        .await
+    );
+}
+
+#[tokio::test]
+async fn grown_sectors_do_not_block_replotting() {
+    let (sectors_to_plot_sender, mut sectors_to_plot_receiver) = mpsc::channel(1);
+    let (new_sectors_sender, new_sectors_receiver) = mpsc::unbounded();
+    let _queue_grown_sectors_task = tokio::spawn(queue_grown_sectors(
+        new_sectors_receiver,
+        2,
+        100,
+        PreallocationFrontier::complete(),
+        sectors_to_plot_sender.clone(),
+    ));
+
+    new_sectors_sender.unbounded_send(2..4).unwrap();
+    let grown_sector = sectors_to_plot_receiver.next().await.unwrap();
+    assert_eq!(grown_sector.sector_index, 2);
+
+    // Sector is queued for replotting while grown sector is still being plotted
+    let (acknowledgement_sender, _acknowledgement_receiver) = oneshot::channel();
+    let mut replotting_sender = sectors_to_plot_sender;
+    replotting_sender
+        .send(SectorToPlot {
+            sector_index: 0,
+            progress: 0.0,
+            last_queued: true,
+            expired: true,
+            acknowledgement_sender,
+            next_segment_index_hint: None,
+        })
+        .await
+        .unwrap();
+    let replotted_sector = sectors_to_plot_receiver.next().await.unwrap();
+    assert_eq!(replotted_sector.sector_index, 0);
+
+    // The next grown sector is queued once the previous one is plotted
+    drop(grown_sector);
+    assert_eq!(
+        sectors_to_plot_receiver.next().await.unwrap().sector_index,
+        3
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn queue_sectors_during_preallocation() {
+    const SECTOR_SIZE: u64 = 4096;
+    const SECTOR_COUNT: SectorIndex = 4;
+
+    let tempdir = tempdir().unwrap();
+    let plot_file = Arc::new(FaultyFile::new(
+        OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(tempdir.as_ref().join("plot.bin"))
+            .unwrap(),
+    ));
+    // Slow preallocator, one sector per chunk
+    plot_file.inject(
+        FaultRule::new(
+            Operation::Preallocate,
+            Fault::Delay(Duration::from_millis(100)),
+        )
+        .times(usize::MAX),
+    );
+    let (plot_preallocation, mut preallocation_frontier) = BackgroundPreallocation::new(
+        &plot_file,
+        SECTOR_SIZE * u64::from(SECTOR_COUNT),
+        PreallocatedSize::new(tempdir.as_ref().join("preallocation.bin")),
+    )
+    .unwrap();
+    assert_eq!(preallocation_frontier.allocated(), 0);
+
+    let preallocation_finished = Arc::new(AtomicBool::new(false));
+    let preallocation_thread = thread::spawn({
+        let plot_file = Arc::clone(&plot_file);
+        let preallocation_finished = Arc::clone(&preallocation_finished);
+
+        move || {
+            let preallocated = plot_preallocation
+                .run(
+                    &plot_file,
+                    NonZeroU64::new(SECTOR_SIZE).unwrap(),
+                    |_progress| {},
+                    |len| plot_file.inner().set_len(len),
+                    &AtomicBool::new(false),
+                )
+                .unwrap();
+            preallocation_finished.store(true, Ordering::Release);
+            preallocated
+        }
+    });
+
+    let (mut sectors_to_plot_sender, mut sectors_to_plot_receiver) = mpsc::channel(1);
+    let queued_fut = tokio::spawn({
+        let plot_file = Arc::clone(&plot_file);
+
+        async move {
+            let mut queued = Vec::new();
+            // Sector is acknowledged when it is dropped
+            while let Some(sector_to_plot) = sectors_to_plot_receiver.next().await {
+                // Sector is never handed out past preallocated part of the file
+                let sector_end = (u64::from(sector_to_plot.sector_index) + 1) * SECTOR_SIZE;
+                assert!(plot_file.size().unwrap() >= sector_end);
+                queued.push((
+                    sector_to_plot.sector_index,
+                    preallocation_finished.load(Ordering::Acquire),
+                ));
+            }
+            queued
+        }
+    });
+
+    assert!(
+        queue_new_sectors(
+            0..SECTOR_COUNT,
+            SECTOR_COUNT,
+            SECTOR_SIZE,
+            &mut preallocation_frontier,
+            &mut sectors_to_plot_sender
+        )
+        .await
+    );
+    drop(sectors_to_plot_sender);
+
+    let queued = queued_fut.await.unwrap();
+    assert_eq!(
+        queued
+            .iter()
+            .map(|&(sector_index, _)| sector_index)
+            .collect::<Vec<_>>(),
+        (0..SECTOR_COUNT).collect::<Vec<_>>()
+    );
+    // Plotting started before preallocation finished
+    assert_eq!(queued[0], (0, false));
+
+    assert!(preallocation_thread.join().unwrap());
+    assert!(preallocation_frontier.is_complete());
+    assert_eq!(
+        plot_file.size().unwrap(),
+        SECTOR_SIZE * u64::from(SECTOR_COUNT)
+    );
+}
@@ -0,0 +1,302 @@
+//! Preallocation of plot file in chunks with progress reporting and cancellation, such that
+//! preallocation of large farms on file systems that zero-fill allocated space doesn't look like a
+//! hang and can be interrupted.
+//!
+//! When farm is opened, plot file is preallocated in the background (see
+//! [`BackgroundPreallocation`]) and plotting writes sectors into already preallocated space in the
+//! meantime, see [`PreallocationFrontier`].
+
+#[cfg(test)]
+mod tests;
+
+use parity_scale_codec::{Decode, Encode};
+use std::num::NonZeroU64;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::{fs, io};
+use subspace_farmer_components::file_ext::FileExt;
+use tokio::sync::watch;
+use tracing::{info, warn};
+
+/// Default size of a single preallocation chunk
+pub const DEFAULT_PREALLOCATION_CHUNK_SIZE: NonZeroU64 =
+    NonZeroU64::new(16 * 1024 * 1024 * 1024).expect("Not zero; qed");
+
+/// Progress of plot file preallocation
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct PreallocationProgress {
+    /// Bytes allocated so far
+    pub allocated: u64,
+    /// Total size of the file
+    pub total: u64,
+}
+
+/// Set on drop, used to cancel preallocation when farm creation is cancelled
+#[derive(Debug, Default)]
+pub(super) struct CancelOnDrop(Arc<AtomicBool>);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Release);
+    }
+}
+
+impl CancelOnDrop {
+    pub(super) fn cancelled(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.0)
+    }
+}
+
+/// Size of the file that was completely preallocated, stored on disk such that interrupted
+/// preallocation continues from the last completed chunk rather than trusting file size, which
+/// may have been changed by incomplete allocation
+#[derive(Debug)]
+pub(super) struct PreallocatedSize {
+    path: PathBuf,
+}
+
+impl PreallocatedSize {
+    pub(super) fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// Whether previous preallocation was interrupted before completion
+    pub(super) fn is_interrupted(&self) -> bool {
+        self.path.exists()
+    }
+
+    /// Size of `file` that is preallocated already, up to `len`: the last completed chunk if
+    /// preallocation was interrupted before and file size otherwise
+    pub(super) fn allocated<F>(&self, file: &F, len: u64) -> io::Result<u64>
+    where
+        F: FileExt,
+    {
+        let allocated = match self.load()? {
+            Some(allocated) => allocated,
+            None => file.size()?,
+        };
+
+        Ok(allocated.min(len))
+    }
+
+    /// Returns `None` if preallocation was not interrupted before
+    fn load(&self) -> io::Result<Option<u64>> {
+        match fs::read(&self.path) {
+            Ok(bytes) => Ok(Some(u64::decode(&mut bytes.as_slice()).unwrap_or_else(
+                |error| {
+                    warn!(
+                        path = %self.path.display(),
+                        %error,
+                        "Failed to decode preallocated size, starting from the beginning"
+                    );
+
+                    0
+                },
+            ))),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
+    fn store(&self, allocated: u64) -> io::Result<()> {
+        fs::write(&self.path, allocated.encode())
+    }
+
+    fn remove(&self) -> io::Result<()> {
+        match fs::remove_file(&self.path) {
+            Ok(()) => Ok(()),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(error) => Err(error),
+        }
+    }
+}
+
+/// Preallocate `file` to be at least `len` bytes in chunks of `chunk_size` bytes, calling
+/// `on_progress` before the first and after every chunk.
+///
+/// Completed size is stored in `preallocated_size` after every chunk, such that interrupted
+/// preallocation continues from the last completed chunk next time. `cancelled` is checked between
+/// chunks, returns `Ok(false)` if preallocation was cancelled. File is not truncated if it is
+/// already larger than `len`.
+pub(super) fn preallocate_in_chunks<F, P>(
+    file: &F,
+    len: u64,
+    chunk_size: NonZeroU64,
+    preallocated_size: &PreallocatedSize,
+    mut on_progress: P,
+    cancelled: &AtomicBool,
+) -> io::Result<bool>
+where
+    F: FileExt,
+    P: FnMut(PreallocationProgress),
+{
+    let mut allocated = preallocated_size.allocated(file, len)?;
+
+    on_progress(PreallocationProgress {
+        allocated,
+        total: len,
+    });
+
+    if allocated < len {
+        preallocated_size.store(allocated)?;
+    }
+
+    while allocated < len {
+        if cancelled.load(Ordering::Acquire) {
+            return Ok(false);
+        }
+
+        allocated = allocated.saturating_add(chunk_size.get()).min(len);
+        file.preallocate(allocated)?;
+        preallocated_size.store(allocated)?;
+
+        on_progress(PreallocationProgress {
+            allocated,
+            total: len,
+        });
+    }
+
+    preallocated_size.remove()?;
+
+    Ok(true)
+}
+
+/// Size of the plot file that is preallocated so far, such that sectors are only written into
+/// preallocated space while preallocation continues in the background, see
+/// [`BackgroundPreallocation`].
+///
+/// Frontier only moves forward and is unbounded once preallocation is complete.
+#[derive(Debug, Clone)]
+pub(super) struct PreallocationFrontier {
+    allocated: watch::Receiver<u64>,
+}
+
+impl PreallocationFrontier {
+    /// Frontier of the file that doesn't need preallocation
+    pub(super) fn complete() -> Self {
+        let (_allocated_sender, allocated) = watch::channel(u64::MAX);
+
+        Self { allocated }
+    }
+
+    /// Size of the file that is preallocated so far
+    pub(super) fn allocated(&self) -> u64 {
+        *self.allocated.borrow()
+    }
+
+    /// Whether the whole file is preallocated
+    pub(super) fn is_complete(&self) -> bool {
+        self.allocated() == u64::MAX
+    }
+
+    /// Wait for the file to be preallocated up to `len` bytes, logging progress while waiting.
+    ///
+    /// Returns `false` if preallocation stopped before reaching `len` (failed or was cancelled).
+    pub(super) async fn wait_for(&mut self, len: u64) -> bool {
+        let allocated = self.allocated();
+        if allocated >= len {
+            return true;
+        }
+
+        info!(
+            %allocated,
+            %len,
+            "Plotting caught up with plot file preallocation, waiting for it"
+        );
+
+        self.allocated
+            .wait_for(|&allocated| {
+                if allocated < len {
+                    info!(%allocated, %len, "Waiting for plot file preallocation");
+                }
+
+                allocated >= len
+            })
+            .await
+            .is_ok()
+    }
+}
+
+/// Preallocation of the plot file that continues in the background after farm is opened, advancing
+/// [`PreallocationFrontier`] after every chunk.
+///
+/// Frontier is advanced only after completed size is stored in [`PreallocatedSize`], such that
+/// sectors written before a crash are always within space that is considered preallocated on the
+/// next open.
+#[derive(Debug)]
+pub(super) struct BackgroundPreallocation {
+    preallocated_size: PreallocatedSize,
+    len: u64,
+    allocated_sender: watch::Sender<u64>,
+}
+
+impl BackgroundPreallocation {
+    /// Create preallocation of `file` to `len` bytes together with its frontier, which starts at
+    /// the size that is already preallocated
+    pub(super) fn new<F>(
+        file: &F,
+        len: u64,
+        preallocated_size: PreallocatedSize,
+    ) -> io::Result<(Self, PreallocationFrontier)>
+    where
+        F: FileExt,
+    {
+        let (allocated_sender, allocated) = watch::channel(preallocated_size.allocated(file, len)?);
+
+        Ok((
+            Self {
+                preallocated_size,
+                len,
+                allocated_sender,
+            },
+            PreallocationFrontier { allocated },
+        ))
+    }
+
+    /// Preallocate `file` with [`preallocate_in_chunks()`] and call `truncate` with expected size
+    /// afterwards (file may be larger than expected), frontier is complete once this is done.
+    ///
+    /// Returns `Ok(false)` if preallocation was cancelled, frontier stops where it was in this case
+    /// as well as on error.
+    pub(super) fn run<F, P, T>(
+        self,
+        file: &F,
+        chunk_size: NonZeroU64,
+        mut on_progress: P,
+        truncate: T,
+        cancelled: &AtomicBool,
+    ) -> io::Result<bool>
+    where
+        F: FileExt,
+        P: FnMut(PreallocationProgress),
+        T: FnOnce(u64) -> io::Result<()>,
+    {
+        let Self {
+            preallocated_size,
+            len,
+            allocated_sender,
+        } = self;
+
+        let preallocated = preallocate_in_chunks(
+            file,
+            len,
+            chunk_size,
+            &preallocated_size,
+            |progress| {
+                allocated_sender.send_replace(progress.allocated);
+                on_progress(progress);
+            },
+            cancelled,
+        )?;
+
+        if !preallocated {
+            return Ok(false);
+        }
+
+        truncate(len)?;
+        allocated_sender.send_replace(u64::MAX);
+
+        Ok(true)
+    }
+}
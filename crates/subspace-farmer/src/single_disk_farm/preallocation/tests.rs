@@ -0,0 +1,199 @@
+use crate::single_disk_farm::preallocation::{
+    preallocate_in_chunks, BackgroundPreallocation, PreallocatedSize, PreallocationProgress,
+};
+use std::fs::OpenOptions;
+use std::num::NonZeroU64;
+use std::sync::atomic::{AtomicBool, Ordering};
+use subspace_farmer_components::file_ext::FileExt;
+use tempfile::tempdir;
+
+const CHUNK_SIZE: NonZeroU64 = NonZeroU64::new(4096).expect("Not zero; qed");
+/// Not a multiple of chunk size, such that the last chunk is smaller
+const FILE_SIZE: u64 = 10_000;
+
+fn progress(allocated: u64) -> PreallocationProgress {
+    PreallocationProgress {
+        allocated,
+        total: FILE_SIZE,
+    }
+}
+
+#[test]
+fn chunks() {
+    let tempdir = tempdir().unwrap();
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(tempdir.as_ref().join("plot.bin"))
+        .unwrap();
+    let preallocated_size = PreallocatedSize::new(tempdir.as_ref().join("preallocation.bin"));
+
+    let mut progress_sequence = Vec::new();
+    let preallocated = preallocate_in_chunks(
+        &file,
+        FILE_SIZE,
+        CHUNK_SIZE,
+        &preallocated_size,
+        |progress| progress_sequence.push(progress),
+        &AtomicBool::new(false),
+    )
+    .unwrap();
+
+    assert!(preallocated);
+    assert_eq!(
+        progress_sequence,
+        vec![
+            progress(0),
+            progress(4096),
+            progress(8192),
+            progress(FILE_SIZE)
+        ]
+    );
+    assert_eq!(file.size().unwrap(), FILE_SIZE);
+    assert!(!preallocated_size.is_interrupted());
+
+    // Nothing to do for already allocated file
+    let mut progress_sequence = Vec::new();
+    let preallocated = preallocate_in_chunks(
+        &file,
+        FILE_SIZE,
+        CHUNK_SIZE,
+        &preallocated_size,
+        |progress| progress_sequence.push(progress),
+        &AtomicBool::new(false),
+    )
+    .unwrap();
+
+    assert!(preallocated);
+    assert_eq!(progress_sequence, vec![progress(FILE_SIZE)]);
+}
+
+#[test]
+fn cancellation() {
+    let tempdir = tempdir().unwrap();
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(tempdir.as_ref().join("plot.bin"))
+        .unwrap();
+    let preallocated_size = PreallocatedSize::new(tempdir.as_ref().join("preallocation.bin"));
+
+    // Cancel after the first chunk
+    let cancelled = AtomicBool::new(false);
+    let mut progress_sequence = Vec::new();
+    let preallocated = preallocate_in_chunks(
+        &file,
+        FILE_SIZE,
+        CHUNK_SIZE,
+        &preallocated_size,
+        |progress| {
+            progress_sequence.push(progress);
+            if progress.allocated > 0 {
+                cancelled.store(true, Ordering::Release);
+            }
+        },
+        &cancelled,
+    )
+    .unwrap();
+
+    assert!(!preallocated);
+    assert_eq!(progress_sequence, vec![progress(0), progress(4096)]);
+    assert_eq!(file.size().unwrap(), 4096);
+    assert!(preallocated_size.is_interrupted());
+
+    // File size is not trusted after interruption, preallocation continues from the last completed
+    // chunk instead
+    file.set_len(FILE_SIZE).unwrap();
+
+    let mut progress_sequence = Vec::new();
+    let preallocated = preallocate_in_chunks(
+        &file,
+        FILE_SIZE,
+        CHUNK_SIZE,
+        &preallocated_size,
+        |progress| progress_sequence.push(progress),
+        &AtomicBool::new(false),
+    )
+    .unwrap();
+
+    assert!(preallocated);
+    assert_eq!(
+        progress_sequence,
+        vec![progress(4096), progress(8192), progress(FILE_SIZE)]
+    );
+    assert!(!preallocated_size.is_interrupted());
+}
+
+#[tokio::test]
+async fn background_preallocation_frontier() {
+    let tempdir = tempdir().unwrap();
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(tempdir.as_ref().join("plot.bin"))
+        .unwrap();
+    let preallocated_size_path = tempdir.as_ref().join("preallocation.bin");
+
+    let (preallocation, mut frontier) = BackgroundPreallocation::new(
+        &file,
+        FILE_SIZE,
+        PreallocatedSize::new(preallocated_size_path.clone()),
+    )
+    .unwrap();
+    assert_eq!(frontier.allocated(), 0);
+    assert!(!frontier.is_complete());
+
+    // Cancel after the first chunk
+    let cancelled = AtomicBool::new(false);
+    let preallocated = preallocation
+        .run(
+            &file,
+            CHUNK_SIZE,
+            |progress| {
+                if progress.allocated > 0 {
+                    cancelled.store(true, Ordering::Release);
+                }
+            },
+            |_len| unreachable!("Preallocation was cancelled"),
+            &cancelled,
+        )
+        .unwrap();
+
+    assert!(!preallocated);
+    assert_eq!(frontier.allocated(), 4096);
+    assert!(frontier.wait_for(4096).await);
+    // Preallocation stopped before reaching requested size
+    assert!(!frontier.wait_for(4097).await);
+
+    // Frontier continues from the last completed chunk, file is truncated before frontier is
+    // complete
+    file.set_len(FILE_SIZE * 2).unwrap();
+    let (preallocation, mut frontier) = BackgroundPreallocation::new(
+        &file,
+        FILE_SIZE,
+        PreallocatedSize::new(preallocated_size_path),
+    )
+    .unwrap();
+    assert_eq!(frontier.allocated(), 4096);
+
+    let preallocated = preallocation
+        .run(
+            &file,
+            CHUNK_SIZE,
+            |_progress| {},
+            |len| file.set_len(len),
+            &AtomicBool::new(false),
+        )
+        .unwrap();
+
+    assert!(preallocated);
+    assert!(frontier.is_complete());
+    assert!(frontier.wait_for(u64::MAX).await);
+    assert_eq!(file.size().unwrap(), FILE_SIZE);
+}
@@ -0,0 +1,341 @@
+//! Benchmark of plot reads that replicates read pattern of auditing and proving against the actual
+//! plot, such that it is possible to check whether disk is fast enough to prove in time (generic
+//! disk benchmarks do not use unbuffered scattered small reads the way farmer does)
+
+#[cfg(test)]
+mod tests;
+
+use crate::single_disk_farm::metadata_layout::MetadataLayout;
+use crate::single_disk_farm::{
+    PlotMetadataHeader, SingleDiskFarm, INTERNAL_BENCHMARK_READ_TIMEOUT,
+};
+use parity_scale_codec::Decode;
+use rand::prelude::*;
+use rand::seq::index;
+use rayon::prelude::*;
+use std::io;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use subspace_core_primitives::crypto::Scalar;
+use subspace_core_primitives::{Record, SectorIndex};
+use subspace_farmer_components::file_ext::FileExt;
+use subspace_farmer_components::sector::{
+    sector_size, SectorContentsMap, SectorMetadataChecksummed,
+};
+use subspace_farmer_components::ReadAtSync;
+use thiserror::Error;
+
+/// Default for [`ReadBenchmarkOptions::deadline_budget`]
+pub const DEFAULT_DEADLINE_BUDGET: Duration = INTERNAL_BENCHMARK_READ_TIMEOUT;
+/// Max number of sectors whose metadata is read before benchmarking, metadata of every sector is
+/// large and reads are spread across the plot well enough with this many sectors
+const MAX_SAMPLED_SECTORS: usize = 64;
+
+/// Options for [`SingleDiskFarm::benchmark_reads()`]
+#[derive(Debug, Copy, Clone)]
+pub struct ReadBenchmarkOptions<'a> {
+    /// For how long to run benchmark, at least one audit and one proving read is done regardless
+    pub duration: Duration,
+    /// Time budget for reads of a single proof, see [`ReadBenchmarkReport::fits_deadline`]
+    pub deadline_budget: Duration,
+    /// Checked between reads, benchmark stops early with [`ReadBenchmarkReport::cancelled`] set
+    /// once this is `true`
+    pub cancelled: &'a AtomicBool,
+}
+
+/// Latency statistics of one kind of reads
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct ReadLatencyStats {
+    /// Number of reads done
+    pub reads: u64,
+    /// Total number of bytes read
+    pub bytes_read: u64,
+    /// Median latency
+    pub p50: Duration,
+    /// 95th percentile latency
+    pub p95: Duration,
+    /// 99th percentile latency
+    pub p99: Duration,
+    /// Max latency
+    pub max: Duration,
+}
+
+impl ReadLatencyStats {
+    pub(super) fn new(mut latencies: Vec<Duration>, bytes_read: u64) -> Self {
+        latencies.sort_unstable();
+
+        // Nearest-rank percentile
+        let percentile = |percent: usize| {
+            let rank = (latencies.len() * percent).div_ceil(100).max(1);
+            latencies.get(rank - 1).copied().unwrap_or_default()
+        };
+
+        Self {
+            reads: latencies.len() as u64,
+            bytes_read,
+            p50: percentile(50),
+            p95: percentile(95),
+            p99: percentile(99),
+            max: latencies.last().copied().unwrap_or_default(),
+        }
+    }
+}
+
+/// Report of plot reads benchmark, see [`SingleDiskFarm::benchmark_reads()`]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ReadBenchmarkReport {
+    /// Number of plotted sectors reads were done from
+    pub plotted_sectors: SectorIndex,
+    /// Number of randomly sampled plotted sectors with valid metadata that reads were done from
+    pub sampled_sectors: usize,
+    /// Reads of a single s-bucket of a sector, one such read is done for every sector on audit
+    pub audit: ReadLatencyStats,
+    /// Concurrent reads of individual chunks of one record in a sector, done on proving, each
+    /// sample is the latency of reading the whole record
+    pub prove: ReadLatencyStats,
+    /// Aggregate read throughput in bytes per second
+    pub throughput: u64,
+    /// How long benchmark took
+    pub elapsed: Duration,
+    /// Time budget for reads of a single proof benchmark was run with
+    pub deadline_budget: Duration,
+    /// Whether 99th percentile latency of proving reads fits within deadline budget
+    pub fits_deadline: bool,
+    /// Whether benchmark was cancelled before configured duration has passed
+    pub cancelled: bool,
+}
+
+/// Errors happening during plot reads benchmark
+#[derive(Debug, Error)]
+pub enum ReadBenchmarkError {
+    /// Farm info file does not exist
+    #[error("Farm info file does not exist in {}", directory.display())]
+    FarmInfoFileDoesNotExist {
+        /// Farm directory
+        directory: PathBuf,
+    },
+    /// Failed to decode metadata header
+    #[error("Failed to decode metadata header: {0}")]
+    FailedToDecodeMetadataHeader(parity_scale_codec::Error),
+    /// Unexpected metadata version
+    #[error("Unexpected metadata version {0}")]
+    UnexpectedMetadataVersion(u8),
+    /// There are no plotted sectors with valid metadata to read from
+    #[error("There are no plotted sectors with valid metadata to read from")]
+    NoPlottedSectors,
+    /// I/O error occurred
+    #[error("Read benchmark I/O error: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// Plotted sector reads are done from
+struct SampledSector {
+    sector_index: SectorIndex,
+    /// Offset (in chunks) and size of every non-empty s-bucket
+    s_buckets: Vec<(u64, u16)>,
+}
+
+/// Benchmark reads from `plot_file` of `plot_size` bytes with pattern of auditing and proving.
+///
+/// Only sectors that are plotted according to `metadata_file` and fit into `plot_size` are read
+/// from, nothing is written to either file.
+pub(super) fn read_benchmark<PF, MF>(
+    plot_file: &PF,
+    plot_size: u64,
+    metadata_file: &MF,
+    pieces_in_sector: u16,
+    options: ReadBenchmarkOptions<'_>,
+) -> Result<ReadBenchmarkReport, ReadBenchmarkError>
+where
+    PF: ReadAtSync,
+    MF: FileExt,
+{
+    let ReadBenchmarkOptions {
+        duration,
+        deadline_budget,
+        cancelled,
+    } = options;
+
+    let metadata_header = {
+        let mut metadata_header_bytes = vec![0; PlotMetadataHeader::encoded_size()];
+        metadata_file.read_exact_at(&mut metadata_header_bytes, 0)?;
+
+        PlotMetadataHeader::decode(&mut metadata_header_bytes.as_slice())
+            .map_err(ReadBenchmarkError::FailedToDecodeMetadataHeader)?
+    };
+    let Some(metadata_layout) = MetadataLayout::from_version(metadata_header.version) else {
+        return Err(ReadBenchmarkError::UnexpectedMetadataVersion(
+            metadata_header.version,
+        ));
+    };
+
+    let sector_size = sector_size(pieces_in_sector) as u64;
+    // Sector that is being plotted right now may already be counted in metadata of a live farm
+    // before plot file is extended to fit it
+    let plotted_sectors = metadata_header
+        .plotted_sector_count
+        .min(SectorIndex::try_from(plot_size / sector_size).unwrap_or(SectorIndex::MAX));
+
+    let sampled_sectors = sample_sectors(
+        metadata_file,
+        metadata_layout,
+        plotted_sectors,
+        pieces_in_sector,
+    )?;
+    if sampled_sectors.is_empty() {
+        return Err(ReadBenchmarkError::NoPlottedSectors);
+    }
+
+    let sector_contents_map_size = SectorContentsMap::encoded_size(pieces_in_sector) as u64;
+    let mut rng = thread_rng();
+    let mut audit_latencies = Vec::new();
+    let mut audit_bytes_read = 0;
+    let mut prove_latencies = Vec::new();
+    let mut prove_bytes_read = 0;
+    let mut audit_buffer = Vec::new();
+    let mut report_cancelled = false;
+
+    let started_at = Instant::now();
+    loop {
+        let sampled_sector = sampled_sectors
+            .choose(&mut rng)
+            .expect("Not empty, checked above; qed");
+        let record_chunks_offset =
+            u64::from(sampled_sector.sector_index) * sector_size + sector_contents_map_size;
+
+        // Audit reads the whole s-bucket of every sector
+        {
+            let &(s_bucket_offset, s_bucket_size) = sampled_sector
+                .s_buckets
+                .choose(&mut rng)
+                .expect("Sectors without non-empty s-buckets are not sampled; qed");
+            audit_buffer.resize(usize::from(s_bucket_size) * Scalar::FULL_BYTES, 0);
+
+            let start = Instant::now();
+            plot_file.read_at(
+                &mut audit_buffer,
+                record_chunks_offset + s_bucket_offset * Scalar::FULL_BYTES as u64,
+            )?;
+            audit_latencies.push(start.elapsed());
+            audit_bytes_read += audit_buffer.len() as u64;
+        }
+
+        // Proving reads chunks of one record concurrently, s-bucket contains a chunk of the
+        // record with probability proportional to its size
+        {
+            let chunk_locations = sampled_sector
+                .s_buckets
+                .iter()
+                .filter_map(|&(s_bucket_offset, s_bucket_size)| {
+                    (rng.gen_range(0..pieces_in_sector) < s_bucket_size)
+                        .then(|| s_bucket_offset + u64::from(rng.gen_range(0..s_bucket_size)))
+                })
+                .collect::<Vec<_>>();
+
+            let start = Instant::now();
+            chunk_locations.par_iter().try_for_each(|&chunk_location| {
+                let mut record_chunk = [0; Scalar::FULL_BYTES];
+                plot_file.read_at(
+                    &mut record_chunk,
+                    record_chunks_offset + chunk_location * Scalar::FULL_BYTES as u64,
+                )
+            })?;
+            prove_latencies.push(start.elapsed());
+            prove_bytes_read += (chunk_locations.len() * Scalar::FULL_BYTES) as u64;
+        }
+
+        if started_at.elapsed() >= duration {
+            break;
+        }
+        if cancelled.load(Ordering::Acquire) {
+            report_cancelled = true;
+            break;
+        }
+    }
+    let elapsed = started_at.elapsed();
+
+    let audit = ReadLatencyStats::new(audit_latencies, audit_bytes_read);
+    let prove = ReadLatencyStats::new(prove_latencies, prove_bytes_read);
+    let throughput = ((audit.bytes_read + prove.bytes_read) as f64 / elapsed.as_secs_f64()) as u64;
+
+    Ok(ReadBenchmarkReport {
+        plotted_sectors,
+        sampled_sectors: sampled_sectors.len(),
+        audit,
+        prove,
+        throughput,
+        elapsed,
+        deadline_budget,
+        fits_deadline: prove.p99 <= deadline_budget,
+        cancelled: report_cancelled,
+    })
+}
+
+/// Read metadata of up to [`MAX_SAMPLED_SECTORS`] random plotted sectors, skipping sectors with
+/// invalid metadata or without any chunks
+fn sample_sectors<MF>(
+    metadata_file: &MF,
+    metadata_layout: MetadataLayout,
+    plotted_sectors: SectorIndex,
+    pieces_in_sector: u16,
+) -> io::Result<Vec<SampledSector>>
+where
+    MF: FileExt,
+{
+    let sector_metadata_size = SectorMetadataChecksummed::encoded_size();
+    let max_chunks = u64::from(pieces_in_sector) * Record::NUM_CHUNKS as u64;
+    let mut sector_metadata_bytes = vec![0; sector_metadata_size];
+
+    let mut sector_indices = index::sample(
+        &mut thread_rng(),
+        usize::from(plotted_sectors),
+        usize::from(plotted_sectors).min(MAX_SAMPLED_SECTORS),
+    )
+    .into_vec();
+    // Read metadata in order
+    sector_indices.sort_unstable();
+
+    let mut sampled_sectors = Vec::with_capacity(sector_indices.len());
+    for sector_index in sector_indices {
+        let sector_index =
+            SectorIndex::try_from(sector_index).expect("Sampled below `plotted_sectors`; qed");
+
+        metadata_file.read_exact_at(
+            &mut sector_metadata_bytes,
+            metadata_layout.sector_metadata_offset(sector_index),
+        )?;
+
+        let Ok(sector_metadata) =
+            SectorMetadataChecksummed::decode(&mut sector_metadata_bytes.as_slice())
+        else {
+            continue;
+        };
+        if sector_metadata.sector_index != sector_index
+            || sector_metadata.pieces_in_sector != pieces_in_sector
+        {
+            continue;
+        }
+
+        let mut s_bucket_offset = 0;
+        let mut s_buckets = Vec::new();
+        for &s_bucket_size in sector_metadata.s_bucket_sizes.iter() {
+            if s_bucket_size > 0 {
+                s_buckets.push((s_bucket_offset, s_bucket_size));
+            }
+            s_bucket_offset += u64::from(s_bucket_size);
+        }
+
+        // Reads must not go beyond the sector
+        if s_buckets.is_empty() || s_bucket_offset > max_chunks {
+            continue;
+        }
+
+        sampled_sectors.push(SampledSector {
+            sector_index,
+            s_buckets,
+        });
+    }
+
+    Ok(sampled_sectors)
+}
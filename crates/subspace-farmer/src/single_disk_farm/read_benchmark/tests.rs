@@ -0,0 +1,212 @@
+use crate::single_disk_farm::direct_io_file::{DirectIoFile, DirectIoOptions};
+use crate::single_disk_farm::read_benchmark::{
+    read_benchmark, ReadBenchmarkError, ReadBenchmarkOptions, ReadBenchmarkReport,
+    DEFAULT_DEADLINE_BUDGET,
+};
+use crate::single_disk_farm::test_farm::TestFarm;
+use crate::single_disk_farm::{test_farm, SingleDiskFarm};
+use std::assert_matches::assert_matches;
+use std::fs::OpenOptions;
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
+use std::{io, thread};
+use subspace_core_primitives::crypto::Scalar;
+use subspace_core_primitives::SectorIndex;
+use subspace_farmer_components::file_ext::FileExt;
+use subspace_farmer_components::sector::sector_size;
+use subspace_farmer_components::ReadAtSync;
+use tempfile::tempdir;
+
+const PIECES_IN_SECTOR: u16 = 1;
+const SECTOR_COUNT: SectorIndex = 2;
+/// Number of non-empty s-buckets in every sector, kept small such that proving reads are fast
+const NON_EMPTY_S_BUCKETS: usize = 16;
+
+/// Plot file that sleeps for `delay` before every read
+struct SlowFile {
+    file: DirectIoFile,
+    delay: Duration,
+}
+
+impl ReadAtSync for SlowFile {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        thread::sleep(self.delay);
+        self.file.read_at(buf, offset)
+    }
+}
+
+/// Overwrite metadata of the first `plotted_sector_count` sectors such that every sector has
+/// `NON_EMPTY_S_BUCKETS` non-empty s-buckets, metadata of sector `invalid_sector_index` is invalid
+fn write_sectors_metadata(
+    directory: &Path,
+    plotted_sector_count: SectorIndex,
+    invalid_sector_index: Option<SectorIndex>,
+) {
+    for sector_index in 0..plotted_sector_count {
+        let pieces_in_sector = if invalid_sector_index == Some(sector_index) {
+            PIECES_IN_SECTOR + 1
+        } else {
+            PIECES_IN_SECTOR
+        };
+        let mut sector_metadata = test_farm::sector_metadata(sector_index, pieces_in_sector);
+        for s_bucket_size in sector_metadata
+            .s_bucket_sizes
+            .iter_mut()
+            .step_by(7)
+            .take(NON_EMPTY_S_BUCKETS)
+        {
+            *s_bucket_size = PIECES_IN_SECTOR;
+        }
+
+        test_farm::write_sector_metadata(directory, sector_index, &sector_metadata);
+    }
+}
+
+fn benchmark_with_delay(directory: &Path, delay: Duration) -> ReadBenchmarkReport {
+    let open = |name: &str| {
+        DirectIoFile::open_with_options(
+            &directory.join(name),
+            DirectIoOptions {
+                read_only: true,
+                ..DirectIoOptions::default()
+            },
+        )
+        .unwrap()
+    };
+    let plot_file = SlowFile {
+        file: open(SingleDiskFarm::PLOT_FILE),
+        delay,
+    };
+    let plot_size = plot_file.file.size().unwrap();
+
+    let cancelled = AtomicBool::new(false);
+    read_benchmark(
+        &plot_file,
+        plot_size,
+        &open(SingleDiskFarm::METADATA_FILE),
+        PIECES_IN_SECTOR,
+        ReadBenchmarkOptions {
+            duration: Duration::from_millis(200),
+            deadline_budget: Duration::from_millis(5),
+            cancelled: &cancelled,
+        },
+    )
+    .unwrap()
+}
+
+#[test]
+fn report() {
+    let tempdir = tempdir().unwrap();
+    TestFarm::new(PIECES_IN_SECTOR, SECTOR_COUNT + 1).create(tempdir.as_ref());
+    write_sectors_metadata(tempdir.as_ref(), SECTOR_COUNT + 1, None);
+    // One more sector in metadata than in plot, like when sector is being plotted
+    OpenOptions::new()
+        .write(true)
+        .open(tempdir.as_ref().join(SingleDiskFarm::PLOT_FILE))
+        .unwrap()
+        .set_len(sector_size(PIECES_IN_SECTOR) as u64 * u64::from(SECTOR_COUNT))
+        .unwrap();
+
+    let cancelled = AtomicBool::new(false);
+    let report = SingleDiskFarm::benchmark_reads(
+        tempdir.as_ref(),
+        ReadBenchmarkOptions {
+            duration: Duration::from_millis(200),
+            deadline_budget: DEFAULT_DEADLINE_BUDGET,
+            cancelled: &cancelled,
+        },
+    )
+    .unwrap();
+
+    assert_eq!(report.plotted_sectors, SECTOR_COUNT);
+    assert_eq!(report.sampled_sectors, usize::from(SECTOR_COUNT));
+    assert!(report.elapsed >= Duration::from_millis(200));
+    assert!(!report.cancelled);
+    assert!(report.throughput > 0);
+    assert_eq!(report.deadline_budget, DEFAULT_DEADLINE_BUDGET);
+    assert!(report.fits_deadline);
+
+    for stats in [report.audit, report.prove] {
+        assert!(stats.reads > 0);
+        assert!(stats.bytes_read > 0);
+        assert!(stats.p50 <= stats.p95);
+        assert!(stats.p95 <= stats.p99);
+        assert!(stats.p99 <= stats.max);
+    }
+    // Every audit read is a whole s-bucket, every s-bucket contains a chunk of every record
+    assert_eq!(
+        report.audit.bytes_read,
+        report.audit.reads * Scalar::FULL_BYTES as u64
+    );
+    assert_eq!(
+        report.prove.bytes_read,
+        report.prove.reads * NON_EMPTY_S_BUCKETS as u64 * Scalar::FULL_BYTES as u64
+    );
+}
+
+#[test]
+fn injected_delay() {
+    let tempdir = tempdir().unwrap();
+    TestFarm::new(PIECES_IN_SECTOR, SECTOR_COUNT).create(tempdir.as_ref());
+    write_sectors_metadata(tempdir.as_ref(), SECTOR_COUNT, None);
+
+    let fast = benchmark_with_delay(tempdir.as_ref(), Duration::ZERO);
+    let delay = Duration::from_millis(10);
+    let slow = benchmark_with_delay(tempdir.as_ref(), delay);
+
+    for (fast_stats, slow_stats) in [(fast.audit, slow.audit), (fast.prove, slow.prove)] {
+        assert!(slow_stats.p50 >= delay);
+        assert!(slow_stats.p50 <= slow_stats.p95);
+        assert!(slow_stats.p95 <= slow_stats.p99);
+        assert!(slow_stats.p99 <= slow_stats.max);
+        assert!(slow_stats.reads < fast_stats.reads);
+    }
+    assert!(slow.throughput < fast.throughput);
+    assert!(!slow.fits_deadline);
+}
+
+#[test]
+fn invalid_metadata_skipped() {
+    let tempdir = tempdir().unwrap();
+    TestFarm::new(PIECES_IN_SECTOR, SECTOR_COUNT).create(tempdir.as_ref());
+    write_sectors_metadata(tempdir.as_ref(), SECTOR_COUNT, Some(0));
+
+    let cancelled = AtomicBool::new(true);
+    let report = SingleDiskFarm::benchmark_reads(
+        tempdir.as_ref(),
+        ReadBenchmarkOptions {
+            duration: Duration::from_secs(10),
+            deadline_budget: DEFAULT_DEADLINE_BUDGET,
+            cancelled: &cancelled,
+        },
+    )
+    .unwrap();
+
+    assert_eq!(report.plotted_sectors, SECTOR_COUNT);
+    assert_eq!(report.sampled_sectors, 1);
+    // Cancelled benchmark still does one round of reads
+    assert!(report.cancelled);
+    assert_eq!(report.audit.reads, 1);
+    assert_eq!(report.prove.reads, 1);
+}
+
+#[test]
+fn no_plotted_sectors() {
+    let tempdir = tempdir().unwrap();
+    TestFarm::new(PIECES_IN_SECTOR, SECTOR_COUNT)
+        .with_plotted_sector_count(0)
+        .create(tempdir.as_ref());
+
+    let cancelled = AtomicBool::new(false);
+    let result = SingleDiskFarm::benchmark_reads(
+        tempdir.as_ref(),
+        ReadBenchmarkOptions {
+            duration: Duration::from_millis(200),
+            deadline_budget: DEFAULT_DEADLINE_BUDGET,
+            cancelled: &cancelled,
+        },
+    );
+
+    assert_matches!(result, Err(ReadBenchmarkError::NoPlottedSectors));
+}
@@ -0,0 +1,219 @@
+//! Quick self-test of plot reads done on farm startup, such that disk that develops bad blocks or
+//! became too slow to prove in time is noticed right away rather than through missed rewards, see
+//! [`ReadSelfTestOptions`]
+
+#[cfg(test)]
+mod tests;
+
+use crate::single_disk_farm::direct_io_file::metrics::FileMetrics;
+use crate::single_disk_farm::direct_io_file::DISK_SECTOR_SIZE;
+use crate::single_disk_farm::read_benchmark::ReadLatencyStats;
+use rand::prelude::*;
+use std::fmt;
+use std::time::{Duration, Instant};
+use subspace_core_primitives::SectorIndex;
+use subspace_farmer_components::sector::SectorMetadataChecksummed;
+use subspace_farmer_components::ReadAtSync;
+use thiserror::Error;
+use tracing::{debug, warn};
+
+/// Default for [`ReadSelfTestOptions::regions`]
+pub const DEFAULT_SELF_TEST_REGIONS: usize = 64;
+/// Default for [`ReadSelfTestOptions::region_size`]
+pub const DEFAULT_SELF_TEST_REGION_SIZE: usize = 1024 * 1024;
+/// Default for [`ReadSelfTestOptions::max_p95_latency`], 1 MiB reads taking longer than this
+/// indicate disk that is unlikely to read everything necessary for a proof in time
+pub const DEFAULT_SELF_TEST_MAX_P95_LATENCY: Duration = Duration::from_millis(500);
+
+/// What to do when read self-test fails
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum ReadSelfTestAction {
+    /// Log a warning and continue farm startup
+    #[default]
+    Warn,
+    /// Fail farm startup with [`ReadSelfTestError::Failed`]
+    Fail,
+}
+
+/// Options of read self-test done on farm startup, see
+/// [`SingleDiskFarmOptions::read_self_test`](super::SingleDiskFarmOptions::read_self_test)
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ReadSelfTestOptions {
+    /// Number of randomly sampled regions of plotted sectors to read
+    pub regions: usize,
+    /// Size of every region, rounded down to disk sector size and to sector size if larger
+    pub region_size: usize,
+    /// Self-test fails if 95th percentile latency of region reads exceeds this
+    pub max_p95_latency: Duration,
+    /// What to do when read self-test fails
+    pub on_failure: ReadSelfTestAction,
+}
+
+impl Default for ReadSelfTestOptions {
+    fn default() -> Self {
+        Self {
+            regions: DEFAULT_SELF_TEST_REGIONS,
+            region_size: DEFAULT_SELF_TEST_REGION_SIZE,
+            max_p95_latency: DEFAULT_SELF_TEST_MAX_P95_LATENCY,
+            on_failure: ReadSelfTestAction::default(),
+        }
+    }
+}
+
+/// Report of read self-test, see [`ReadSelfTestOptions`]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ReadSelfTestReport {
+    /// Number of plotted sectors regions were sampled from
+    pub plotted_sectors: SectorIndex,
+    /// Size of every region read
+    pub region_size: usize,
+    /// Latency of successful region reads
+    pub latency: ReadLatencyStats,
+    /// Number of region reads that failed
+    pub read_errors: usize,
+    /// Offset in plot file and error of the first failed region read
+    pub first_read_error: Option<(u64, String)>,
+    /// Max allowed 95th percentile latency self-test was run with
+    pub max_p95_latency: Duration,
+    /// How long self-test took
+    pub elapsed: Duration,
+}
+
+impl fmt::Display for ReadSelfTestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} reads of {} from {} plotted sectors, p95 latency {:?} (max {:?}), {} read errors",
+            self.latency.reads,
+            bytesize::to_string(self.region_size as u64, true),
+            self.plotted_sectors,
+            self.latency.p95,
+            self.max_p95_latency,
+            self.read_errors
+        )?;
+        if let Some((offset, error)) = &self.first_read_error {
+            write!(f, " (first at offset {offset}: {error})")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl ReadSelfTestReport {
+    /// Whether all reads succeeded and 95th percentile latency is within allowed limit
+    pub fn passed(&self) -> bool {
+        self.read_errors == 0 && self.latency.p95 <= self.max_p95_latency
+    }
+}
+
+/// Errors happening during read self-test
+#[derive(Debug, Error)]
+pub enum ReadSelfTestError {
+    /// Self-test failed and [`ReadSelfTestAction::Fail`] was requested
+    #[error("Plot read self-test failed: {0}")]
+    Failed(ReadSelfTestReport),
+}
+
+/// Read [`ReadSelfTestOptions::regions`] randomly sampled regions of plotted sectors of
+/// `plot_file` and time them, every read is recorded in `metrics`.
+///
+/// Only sectors with non-dummy metadata in `sectors_metadata` are read from, such that space that
+/// is not plotted yet or was deallocated is never read. Returns error only if self-test failed
+/// and [`ReadSelfTestAction::Fail`] was requested, otherwise failure is logged as a warning.
+pub(super) fn read_self_test<PF>(
+    plot_file: &PF,
+    sectors_metadata: &[SectorMetadataChecksummed],
+    sector_size: u64,
+    options: &ReadSelfTestOptions,
+    metrics: Option<&FileMetrics>,
+) -> Result<ReadSelfTestReport, ReadSelfTestError>
+where
+    PF: ReadAtSync,
+{
+    let ReadSelfTestOptions {
+        regions,
+        region_size,
+        max_p95_latency,
+        on_failure,
+    } = *options;
+
+    let plotted_sectors = sectors_metadata
+        .iter()
+        .zip(0..)
+        .filter_map(|(sector_metadata, sector_index)| {
+            // Dummy metadata of expired or invalidated sectors has all s-buckets empty
+            (sector_metadata.s_bucket_sizes.iter().any(|&size| size > 0)).then_some(sector_index)
+        })
+        .collect::<Vec<SectorIndex>>();
+    let alignment = DISK_SECTOR_SIZE as u64;
+    let region_size = (region_size as u64).min(sector_size) / alignment * alignment;
+
+    let mut rng = thread_rng();
+    let mut buffer = vec![0; region_size as usize];
+    let mut latencies = Vec::with_capacity(regions);
+    let mut bytes_read = 0;
+    let mut read_errors = 0;
+    let mut first_read_error = None;
+
+    let started_at = Instant::now();
+    if region_size > 0 {
+        for _ in 0..regions {
+            let Some(&sector_index) = plotted_sectors.choose(&mut rng) else {
+                break;
+            };
+            let sector_start = u64::from(sector_index) * sector_size;
+            // Region must be aligned and fit into the sector
+            let first_offset = sector_start.next_multiple_of(alignment);
+            let last_offset = (sector_start + sector_size - region_size) / alignment * alignment;
+            let offset = if first_offset <= last_offset {
+                rng.gen_range(first_offset..=last_offset) / alignment * alignment
+            } else {
+                // Aligned region doesn't fit into the sector
+                sector_start
+            };
+
+            let start = Instant::now();
+            match plot_file.read_at(&mut buffer, offset) {
+                Ok(()) => {
+                    let latency = start.elapsed();
+                    if let Some(metrics) = metrics {
+                        metrics.observe_self_test_read(latency);
+                    }
+                    latencies.push(latency);
+                    bytes_read += region_size;
+                }
+                Err(error) => {
+                    debug!(%error, %offset, "Self-test read failed");
+                    if let Some(metrics) = metrics {
+                        metrics.observe_self_test_read_error();
+                    }
+                    read_errors += 1;
+                    first_read_error.get_or_insert_with(|| (offset, error.to_string()));
+                }
+            }
+        }
+    }
+
+    let report = ReadSelfTestReport {
+        plotted_sectors: SectorIndex::try_from(plotted_sectors.len()).unwrap_or(SectorIndex::MAX),
+        region_size: region_size as usize,
+        latency: ReadLatencyStats::new(latencies, bytes_read),
+        read_errors,
+        first_read_error,
+        max_p95_latency,
+        elapsed: started_at.elapsed(),
+    };
+
+    if report.passed() {
+        debug!(%report, "Plot read self-test passed");
+    } else if on_failure == ReadSelfTestAction::Fail {
+        return Err(ReadSelfTestError::Failed(report));
+    } else {
+        warn!(
+            %report,
+            "Plot read self-test failed, disk may be failing or too slow to prove in time"
+        );
+    }
+
+    Ok(report)
+}
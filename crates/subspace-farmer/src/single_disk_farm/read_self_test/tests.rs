@@ -0,0 +1,151 @@
+use crate::single_disk_farm::read_self_test::{
+    read_self_test, ReadSelfTestAction, ReadSelfTestError, ReadSelfTestOptions,
+};
+use std::io;
+use std::num::NonZeroU64;
+use std::time::Duration;
+use subspace_core_primitives::{HistorySize, Record};
+use subspace_farmer_components::faulty_file::{Fault, FaultRule, FaultyFile, Operation};
+use subspace_farmer_components::sector::{SectorMetadata, SectorMetadataChecksummed};
+
+const SECTOR_SIZE: u64 = 64 * 1024;
+const REGION_SIZE: usize = 4096;
+
+/// Sectors `0..count`, sectors in `unplotted` have dummy metadata
+fn sectors_metadata(count: u16, unplotted: &[u16]) -> Vec<SectorMetadataChecksummed> {
+    (0..count)
+        .map(|sector_index| {
+            let s_bucket_size = if unplotted.contains(&sector_index) {
+                0
+            } else {
+                1
+            };
+            SectorMetadataChecksummed::from(SectorMetadata {
+                sector_index,
+                pieces_in_sector: 1,
+                s_bucket_sizes: Box::new([s_bucket_size; Record::NUM_S_BUCKETS]),
+                history_size: HistorySize::new(NonZeroU64::MIN),
+            })
+        })
+        .collect()
+}
+
+fn options(on_failure: ReadSelfTestAction) -> ReadSelfTestOptions {
+    ReadSelfTestOptions {
+        regions: 32,
+        region_size: REGION_SIZE,
+        on_failure,
+        ..ReadSelfTestOptions::default()
+    }
+}
+
+#[test]
+fn reads_plotted_sectors_only() {
+    let sectors_metadata = sectors_metadata(4, &[1, 3]);
+    let plot_file = FaultyFile::new(vec![0u8; SECTOR_SIZE as usize * 4]);
+
+    let report = read_self_test(
+        &plot_file,
+        &sectors_metadata,
+        SECTOR_SIZE,
+        &options(ReadSelfTestAction::Fail),
+        None,
+    )
+    .unwrap();
+
+    assert!(report.passed());
+    assert_eq!(report.plotted_sectors, 2);
+    assert_eq!(report.latency.reads, 32);
+    assert_eq!(report.read_errors, 0);
+
+    let history = plot_file.history();
+    assert_eq!(history.len(), 32);
+    for record in history {
+        assert_eq!(record.len, REGION_SIZE as u64);
+        assert_eq!(record.offset % REGION_SIZE as u64, 0);
+        let sector_index = record.offset / SECTOR_SIZE;
+        assert!(sector_index == 0 || sector_index == 2, "{record:?}");
+        assert_eq!((record.offset + record.len - 1) / SECTOR_SIZE, sector_index);
+    }
+}
+
+#[test]
+fn nothing_plotted() {
+    let sectors_metadata = sectors_metadata(2, &[0, 1]);
+    let plot_file = FaultyFile::new(vec![0u8; SECTOR_SIZE as usize * 2]);
+
+    let report = read_self_test(
+        &plot_file,
+        &sectors_metadata,
+        SECTOR_SIZE,
+        &options(ReadSelfTestAction::Fail),
+        None,
+    )
+    .unwrap();
+
+    assert!(report.passed());
+    assert_eq!(report.latency.reads, 0);
+    assert!(plot_file.history().is_empty());
+}
+
+#[test]
+fn bad_region() {
+    let sectors_metadata = sectors_metadata(2, &[]);
+
+    for on_failure in [ReadSelfTestAction::Warn, ReadSelfTestAction::Fail] {
+        let plot_file = FaultyFile::new(vec![0u8; SECTOR_SIZE as usize * 2]);
+        // Whole second sector is unreadable
+        plot_file.inject(
+            FaultRule::new(Operation::Read, Fault::Fail(io::ErrorKind::Other))
+                .offsets(SECTOR_SIZE..SECTOR_SIZE * 2)
+                .times(usize::MAX),
+        );
+
+        let result = read_self_test(
+            &plot_file,
+            &sectors_metadata,
+            SECTOR_SIZE,
+            &options(on_failure),
+            None,
+        );
+        let report = match (on_failure, result) {
+            (ReadSelfTestAction::Warn, Ok(report)) => report,
+            (ReadSelfTestAction::Fail, Err(ReadSelfTestError::Failed(report))) => report,
+            (on_failure, result) => {
+                panic!("Unexpected result with {on_failure:?}: {result:?}");
+            }
+        };
+
+        assert!(!report.passed());
+        assert!(report.read_errors > 0);
+        assert_eq!(report.latency.reads as usize + report.read_errors, 32);
+        let (offset, _error) = report.first_read_error.unwrap();
+        assert_eq!(offset / SECTOR_SIZE, 1);
+    }
+}
+
+#[test]
+fn slow_reads() {
+    let sectors_metadata = sectors_metadata(2, &[]);
+    let plot_file = FaultyFile::new(vec![0u8; SECTOR_SIZE as usize * 2]);
+    plot_file.inject(
+        FaultRule::new(Operation::Read, Fault::Delay(Duration::from_millis(5))).times(usize::MAX),
+    );
+
+    let result = read_self_test(
+        &plot_file,
+        &sectors_metadata,
+        SECTOR_SIZE,
+        &ReadSelfTestOptions {
+            max_p95_latency: Duration::from_millis(1),
+            ..options(ReadSelfTestAction::Fail)
+        },
+        None,
+    );
+
+    let Err(ReadSelfTestError::Failed(report)) = result else {
+        panic!("Self-test must fail on latency: {result:?}");
+    };
+    assert_eq!(report.read_errors, 0);
+    assert!(report.latency.p95 > Duration::from_millis(1));
+}
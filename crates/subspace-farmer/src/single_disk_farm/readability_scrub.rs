@@ -0,0 +1,338 @@
+//! Readability scrub that sequentially reads the whole plot and metadata files and verifies them,
+//! such that it is possible to check whether farm is physically readable end to end without
+//! farming it for days (after migrating to a different disk or when cable issues are suspected)
+
+#[cfg(test)]
+mod tests;
+
+use crate::farm::SectorCorruptionDetails;
+use crate::single_disk_farm::direct_io_file::DEFAULT_MAX_REQUEST_SIZE;
+use crate::single_disk_farm::integrity::{verify_plotted_sector, RateLimiter};
+use crate::single_disk_farm::metadata_layout::MetadataLayout;
+use crate::single_disk_farm::sector_reader::SectorReader;
+use crate::single_disk_farm::{PlotMetadataHeader, SingleDiskFarm};
+use parity_scale_codec::Decode;
+use std::io;
+use std::num::NonZeroU64;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU16, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use subspace_core_primitives::SectorIndex;
+use subspace_farmer_components::file_ext::{AccessPattern, FileExt};
+use subspace_farmer_components::sector::{sector_size, SectorMetadataChecksummed};
+use subspace_farmer_components::ReadAtSync;
+use thiserror::Error;
+
+/// Size of individual reads, large sequential reads are the most efficient with direct I/O
+const READ_SIZE: usize = DEFAULT_MAX_REQUEST_SIZE;
+
+/// Options for [`SingleDiskFarm::readability_scrub()`]
+#[derive(Debug, Copy, Clone)]
+pub struct ReadabilityScrubOptions<'a> {
+    /// Max average read rate in bytes per second, such that scrub can run while farming without
+    /// affecting proving, reads are not limited when `None`
+    pub rate_limit: Option<NonZeroU64>,
+    /// Checked between sectors, scrub stops early with [`ReadabilityScrubSummary::cancelled`] set
+    /// once this is `true`
+    pub cancelled: &'a AtomicBool,
+}
+
+/// Status of a plotted sector checked by readability scrub
+#[derive(Debug)]
+pub enum SectorReadability {
+    /// Sector was read successfully and matches its checksum
+    Intact,
+    /// Sector was read successfully, but doesn't match its checksum
+    Corrupted(SectorCorruptionDetails),
+    /// Sector metadata can't be decoded or doesn't match the farm
+    InvalidMetadata {
+        /// Reason why metadata is invalid
+        reason: String,
+    },
+    /// Sector or its metadata can't be read from disk
+    Unreadable(io::Error),
+}
+
+/// Summary of readability scrub, see [`SingleDiskFarm::readability_scrub()`]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct ReadabilityScrubSummary {
+    /// Number of plotted sectors according to metadata
+    pub plotted_sectors: SectorIndex,
+    /// Number of sectors that were checked, smaller than the number of plotted sectors if scrub
+    /// was cancelled
+    pub checked_sectors: SectorIndex,
+    /// Number of sectors with [`SectorReadability::Intact`] status
+    pub intact_sectors: SectorIndex,
+    /// Number of sectors with [`SectorReadability::Corrupted`] status
+    pub corrupted_sectors: SectorIndex,
+    /// Number of sectors with [`SectorReadability::InvalidMetadata`] status
+    pub invalid_metadata_sectors: SectorIndex,
+    /// Number of sectors with [`SectorReadability::Unreadable`] status
+    pub unreadable_sectors: SectorIndex,
+    /// Total number of bytes read from plot and metadata files
+    pub bytes_read: u64,
+    /// How long scrub took
+    pub elapsed: Duration,
+    /// Whether scrub was cancelled before all plotted sectors were checked
+    pub cancelled: bool,
+}
+
+impl ReadabilityScrubSummary {
+    /// Whether all checked sectors are intact
+    pub fn is_intact(&self) -> bool {
+        self.intact_sectors == self.checked_sectors
+    }
+}
+
+/// Errors happening during readability scrub that prevent checking individual sectors
+#[derive(Debug, Error)]
+pub enum ReadabilityScrubError {
+    /// Farm info file does not exist
+    #[error("Farm info file does not exist in {}", directory.display())]
+    FarmInfoFileDoesNotExist {
+        /// Farm directory
+        directory: PathBuf,
+    },
+    /// Failed to decode metadata header
+    #[error("Failed to decode metadata header: {0}")]
+    FailedToDecodeMetadataHeader(parity_scale_codec::Error),
+    /// Unexpected metadata version
+    #[error("Unexpected metadata version {0}")]
+    UnexpectedMetadataVersion(u8),
+    /// Metadata file is too small for the number of plotted sectors
+    #[error(
+        "Metadata file is too small for {plotted_sectors} plotted sectors: expected at least \
+        {expected_size} bytes, file size is {size}"
+    )]
+    MetadataFileTooSmall {
+        /// Number of plotted sectors according to metadata
+        plotted_sectors: SectorIndex,
+        /// Expected min size
+        expected_size: u64,
+        /// File size
+        size: u64,
+    },
+    /// Plot file is too small for the number of plotted sectors
+    #[error(
+        "Plot file is too small for {plotted_sectors} plotted sectors: expected at least \
+        {expected_size} bytes, file size is {size}"
+    )]
+    PlotFileTooSmall {
+        /// Number of plotted sectors according to metadata
+        plotted_sectors: SectorIndex,
+        /// Expected min size
+        expected_size: u64,
+        /// File size
+        size: u64,
+    },
+    /// I/O error occurred
+    #[error("Readability scrub I/O error: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// Reads plotted sectors of `plot_file` and their metadata from `metadata_file` sequentially,
+/// verifies metadata invariants and sector checksums, calling `on_sector` with status of every
+/// sector in order.
+///
+/// Sectors replotted while scrub is running may be reported as corrupted.
+pub(super) fn readability_scrub<PF, MF, S>(
+    plot_file: PF,
+    metadata_file: MF,
+    pieces_in_sector: u16,
+    options: ReadabilityScrubOptions<'_>,
+    mut on_sector: S,
+) -> Result<ReadabilityScrubSummary, ReadabilityScrubError>
+where
+    PF: ReadAtSync + FileExt,
+    MF: FileExt,
+    S: FnMut(SectorIndex, &SectorReadability),
+{
+    let ReadabilityScrubOptions {
+        rate_limit,
+        cancelled,
+    } = options;
+
+    let started_at = Instant::now();
+    let mut rate_limiter = rate_limit.map(RateLimiter::new);
+    let mut summary = ReadabilityScrubSummary::default();
+    let mut bytes_read = 0;
+    let mut on_read = |bytes: usize| {
+        bytes_read += bytes as u64;
+        match &mut rate_limiter {
+            Some(rate_limiter) => rate_limiter.on_read(bytes),
+            None => Ok(()),
+        }
+    };
+
+    // Both files are read sequentially, error doesn't matter here
+    let _ = plot_file.set_access_pattern(AccessPattern::Sequential);
+    let _ = metadata_file.set_access_pattern(AccessPattern::Sequential);
+
+    let metadata_header = {
+        let mut metadata_header_bytes = vec![0; PlotMetadataHeader::encoded_size()];
+        metadata_file.read_exact_at(&mut metadata_header_bytes, 0)?;
+        on_read(metadata_header_bytes.len())?;
+
+        PlotMetadataHeader::decode(&mut metadata_header_bytes.as_slice())
+            .map_err(ReadabilityScrubError::FailedToDecodeMetadataHeader)?
+    };
+    let Some(metadata_layout) = MetadataLayout::from_version(metadata_header.version) else {
+        return Err(ReadabilityScrubError::UnexpectedMetadataVersion(
+            metadata_header.version,
+        ));
+    };
+
+    let plotted_sectors = metadata_header.plotted_sector_count;
+    let sector_metadata_size = SectorMetadataChecksummed::encoded_size();
+    let sector_metadata_slot_size = metadata_layout.sector_metadata_slot_size() as usize;
+
+    let metadata_size = metadata_file.size()?;
+    let expected_metadata_size = metadata_layout.metadata_size(plotted_sectors);
+    if metadata_size < expected_metadata_size {
+        return Err(ReadabilityScrubError::MetadataFileTooSmall {
+            plotted_sectors,
+            expected_size: expected_metadata_size,
+            size: metadata_size,
+        });
+    }
+
+    let plot_size = plot_file.size()?;
+    let expected_plot_size = sector_size(pieces_in_sector) as u64 * u64::from(plotted_sectors);
+    if plot_size < expected_plot_size {
+        return Err(ReadabilityScrubError::PlotFileTooSmall {
+            plotted_sectors,
+            expected_size: expected_plot_size,
+            size: plot_size,
+        });
+    }
+
+    let metadata_file = Arc::new(metadata_file);
+    let sector_reader = SectorReader::new(
+        plot_file,
+        Arc::clone(&metadata_file),
+        metadata_layout,
+        pieces_in_sector,
+        Arc::new(AtomicU16::new(plotted_sectors)),
+    );
+
+    let mut plot_scratch_buffer = vec![0; READ_SIZE];
+    // Metadata of multiple sectors is read at once
+    let sectors_per_metadata_chunk =
+        SectorIndex::try_from((READ_SIZE / sector_metadata_slot_size).max(1))
+            .unwrap_or(SectorIndex::MAX);
+    let mut metadata_chunk = Vec::new();
+    // Sectors whose metadata is in `metadata_chunk`
+    let mut metadata_chunk_sectors = 0..0;
+
+    for sector_index in 0..plotted_sectors {
+        if cancelled.load(Ordering::Acquire) {
+            summary.cancelled = true;
+            break;
+        }
+
+        let metadata_result = if metadata_chunk_sectors.contains(&sector_index) {
+            Ok(())
+        } else {
+            let chunk_end = sector_index
+                .saturating_add(sectors_per_metadata_chunk)
+                .min(plotted_sectors);
+            metadata_chunk.resize(
+                usize::from(chunk_end - sector_index) * sector_metadata_slot_size,
+                0,
+            );
+
+            let result = metadata_file.read_exact_at(
+                &mut metadata_chunk,
+                metadata_layout.sector_metadata_offset(sector_index),
+            );
+            metadata_chunk_sectors = match result {
+                Ok(()) => {
+                    on_read(metadata_chunk.len())?;
+                    sector_index..chunk_end
+                }
+                // Only this sector is reported, chunk is read again starting with the next sector
+                Err(_) => 0..0,
+            };
+
+            result
+        };
+
+        let sector_readability = match metadata_result {
+            Ok(()) => {
+                let offset_in_chunk = usize::from(sector_index - metadata_chunk_sectors.start);
+                let metadata_bytes = &metadata_chunk[offset_in_chunk * sector_metadata_slot_size..]
+                    [..sector_metadata_size];
+                let invalid_metadata_reason =
+                    check_sector_metadata(metadata_bytes, sector_index, pieces_in_sector).err();
+
+                let checksum_result = verify_plotted_sector(
+                    &sector_reader,
+                    sector_index,
+                    &mut plot_scratch_buffer,
+                    &mut on_read,
+                );
+
+                match (checksum_result, invalid_metadata_reason) {
+                    (Err(error), _) => SectorReadability::Unreadable(error),
+                    (Ok(_), Some(reason)) => SectorReadability::InvalidMetadata { reason },
+                    (Ok(Some(corruption_details)), None) => {
+                        SectorReadability::Corrupted(corruption_details)
+                    }
+                    (Ok(None), None) => SectorReadability::Intact,
+                }
+            }
+            Err(error) => SectorReadability::Unreadable(error),
+        };
+
+        summary.checked_sectors += 1;
+        match &sector_readability {
+            SectorReadability::Intact => {
+                summary.intact_sectors += 1;
+            }
+            SectorReadability::Corrupted(_) => {
+                summary.corrupted_sectors += 1;
+            }
+            SectorReadability::InvalidMetadata { .. } => {
+                summary.invalid_metadata_sectors += 1;
+            }
+            SectorReadability::Unreadable(_) => {
+                summary.unreadable_sectors += 1;
+            }
+        }
+
+        on_sector(sector_index, &sector_readability);
+    }
+
+    summary.plotted_sectors = plotted_sectors;
+    summary.bytes_read = bytes_read;
+    summary.elapsed = started_at.elapsed();
+
+    Ok(summary)
+}
+
+/// Check that sector metadata can be decoded (which includes checksum verification) and belongs to
+/// the sector at `sector_index`
+fn check_sector_metadata(
+    mut metadata_bytes: &[u8],
+    sector_index: SectorIndex,
+    pieces_in_sector: u16,
+) -> Result<(), String> {
+    let sector_metadata = SectorMetadataChecksummed::decode(&mut metadata_bytes)
+        .map_err(|error| format!("Failed to decode sector metadata: {error}"))?;
+
+    if sector_metadata.sector_index != sector_index {
+        return Err(format!(
+            "Sector index mismatch: expected {sector_index}, found {}",
+            sector_metadata.sector_index
+        ));
+    }
+    if sector_metadata.pieces_in_sector != pieces_in_sector {
+        return Err(format!(
+            "Pieces in sector mismatch: expected {pieces_in_sector}, found {}",
+            sector_metadata.pieces_in_sector
+        ));
+    }
+
+    Ok(())
+}
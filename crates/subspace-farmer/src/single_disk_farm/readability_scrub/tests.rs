@@ -0,0 +1,201 @@
+use crate::single_disk_farm::direct_io_file::DirectIoFile;
+use crate::single_disk_farm::readability_scrub::{
+    readability_scrub, ReadabilityScrubError, ReadabilityScrubOptions, SectorReadability,
+};
+use crate::single_disk_farm::test_farm::TestFarm;
+use crate::single_disk_farm::{test_farm, SingleDiskFarm};
+use std::assert_matches::assert_matches;
+use std::num::NonZeroU64;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::{fs, io};
+use subspace_core_primitives::SectorIndex;
+use subspace_farmer_components::faulty_file::{Fault, FaultRule, FaultyFile, Operation};
+use subspace_farmer_components::sector::{sector_size, SectorMetadataChecksummed};
+use tempfile::tempdir;
+
+const PIECES_IN_SECTOR: u16 = 1;
+const SECTOR_COUNT: SectorIndex = 2;
+
+#[test]
+fn good_and_corrupted_sector() {
+    let tempdir = tempdir().unwrap();
+    TestFarm::new(PIECES_IN_SECTOR, SECTOR_COUNT).create(tempdir.as_ref());
+    test_farm::flip_byte(tempdir.as_ref(), PIECES_IN_SECTOR, 1);
+
+    let cancelled = AtomicBool::new(false);
+    let mut statuses = Vec::new();
+    let summary = SingleDiskFarm::readability_scrub(
+        tempdir.as_ref(),
+        ReadabilityScrubOptions {
+            rate_limit: NonZeroU64::new(u64::MAX),
+            cancelled: &cancelled,
+        },
+        |sector_index, sector_readability| {
+            statuses.push((sector_index, format!("{sector_readability:?}")));
+        },
+    )
+    .unwrap();
+
+    assert_eq!(statuses.len(), usize::from(SECTOR_COUNT));
+    assert_eq!(statuses[0], (0, format!("{:?}", SectorReadability::Intact)));
+    assert_eq!(statuses[1].0, 1);
+    assert!(statuses[1].1.starts_with("Corrupted"), "{}", statuses[1].1);
+
+    assert_eq!(summary.plotted_sectors, SECTOR_COUNT);
+    assert_eq!(summary.checked_sectors, SECTOR_COUNT);
+    assert_eq!(summary.intact_sectors, 1);
+    assert_eq!(summary.corrupted_sectors, 1);
+    assert_eq!(summary.invalid_metadata_sectors, 0);
+    assert_eq!(summary.unreadable_sectors, 0);
+    assert!(!summary.cancelled);
+    assert!(!summary.is_intact());
+    // Whole plot and metadata of all sectors is read
+    assert!(
+        summary.bytes_read
+            >= (sector_size(PIECES_IN_SECTOR) + SectorMetadataChecksummed::encoded_size()) as u64
+                * u64::from(SECTOR_COUNT)
+    );
+}
+
+#[test]
+fn invalid_metadata() {
+    let tempdir = tempdir().unwrap();
+    TestFarm::new(PIECES_IN_SECTOR, SECTOR_COUNT).create(tempdir.as_ref());
+    // Metadata of sector 1 claims to be sector 0
+    test_farm::write_sector_metadata(
+        tempdir.as_ref(),
+        1,
+        &test_farm::sector_metadata(0, PIECES_IN_SECTOR),
+    );
+
+    let cancelled = AtomicBool::new(false);
+    let mut invalid_metadata_sectors = Vec::new();
+    let summary = SingleDiskFarm::readability_scrub(
+        tempdir.as_ref(),
+        ReadabilityScrubOptions {
+            rate_limit: None,
+            cancelled: &cancelled,
+        },
+        |sector_index, sector_readability| {
+            if let SectorReadability::InvalidMetadata { reason } = sector_readability {
+                invalid_metadata_sectors.push((sector_index, reason.clone()));
+            }
+        },
+    )
+    .unwrap();
+
+    assert_eq!(invalid_metadata_sectors.len(), 1);
+    assert_eq!(invalid_metadata_sectors[0].0, 1);
+    assert!(
+        invalid_metadata_sectors[0]
+            .1
+            .contains("Sector index mismatch"),
+        "{}",
+        invalid_metadata_sectors[0].1
+    );
+    assert_eq!(summary.intact_sectors, 1);
+    assert_eq!(summary.invalid_metadata_sectors, 1);
+}
+
+#[test]
+fn cancellation() {
+    let tempdir = tempdir().unwrap();
+    TestFarm::new(PIECES_IN_SECTOR, SECTOR_COUNT).create(tempdir.as_ref());
+
+    // Cancelled after the first sector
+    let cancelled = AtomicBool::new(false);
+    let summary = SingleDiskFarm::readability_scrub(
+        tempdir.as_ref(),
+        ReadabilityScrubOptions {
+            rate_limit: None,
+            cancelled: &cancelled,
+        },
+        |_sector_index, _sector_readability| {
+            cancelled.store(true, Ordering::Release);
+        },
+    )
+    .unwrap();
+
+    assert!(summary.cancelled);
+    assert_eq!(summary.plotted_sectors, SECTOR_COUNT);
+    assert_eq!(summary.checked_sectors, 1);
+    assert!(summary.is_intact());
+}
+
+#[test]
+fn plot_file_too_small() {
+    let tempdir = tempdir().unwrap();
+    TestFarm::new(PIECES_IN_SECTOR, SECTOR_COUNT).create(tempdir.as_ref());
+
+    let plot_path = tempdir.as_ref().join(SingleDiskFarm::PLOT_FILE);
+    let mut plot = fs::read(&plot_path).unwrap();
+    plot.truncate(sector_size(PIECES_IN_SECTOR));
+    fs::write(&plot_path, plot).unwrap();
+
+    let cancelled = AtomicBool::new(false);
+    let result = SingleDiskFarm::readability_scrub(
+        tempdir.as_ref(),
+        ReadabilityScrubOptions {
+            rate_limit: None,
+            cancelled: &cancelled,
+        },
+        |_sector_index, _sector_readability| {
+            panic!("No sectors must be checked");
+        },
+    );
+
+    assert_matches!(
+        result,
+        Err(ReadabilityScrubError::PlotFileTooSmall {
+            plotted_sectors: SECTOR_COUNT,
+            ..
+        })
+    );
+}
+
+#[test]
+fn read_error() {
+    let tempdir = tempdir().unwrap();
+    TestFarm::new(PIECES_IN_SECTOR, SECTOR_COUNT).create(tempdir.as_ref());
+    let sector_size = sector_size(PIECES_IN_SECTOR) as u64;
+
+    let plot_file = FaultyFile::new(
+        DirectIoFile::open(&tempdir.as_ref().join(SingleDiskFarm::PLOT_FILE)).unwrap(),
+    );
+    let metadata_file =
+        DirectIoFile::open(&tempdir.as_ref().join(SingleDiskFarm::METADATA_FILE)).unwrap();
+    // Sector 0 can't be read, sector 1 is corrupted on the way from disk
+    plot_file.inject(
+        FaultRule::new(Operation::Read, Fault::Fail(io::ErrorKind::Other)).offsets(0..sector_size),
+    );
+    plot_file.inject(
+        FaultRule::new(Operation::Read, Fault::Corrupt).offsets(sector_size..sector_size * 2),
+    );
+
+    let cancelled = AtomicBool::new(false);
+    let mut statuses = Vec::new();
+    let summary = readability_scrub(
+        plot_file,
+        metadata_file,
+        PIECES_IN_SECTOR,
+        ReadabilityScrubOptions {
+            rate_limit: None,
+            cancelled: &cancelled,
+        },
+        |sector_index, sector_readability| {
+            statuses.push((sector_index, format!("{sector_readability:?}")));
+        },
+    )
+    .unwrap();
+
+    // Scrub continues after read error
+    assert_eq!(statuses.len(), usize::from(SECTOR_COUNT));
+    assert!(statuses[0].1.starts_with("Unreadable"), "{}", statuses[0].1);
+    assert!(statuses[1].1.starts_with("Corrupted"), "{}", statuses[1].1);
+
+    assert_eq!(summary.checked_sectors, SECTOR_COUNT);
+    assert_eq!(summary.intact_sectors, 0);
+    assert_eq!(summary.corrupted_sectors, 1);
+    assert_eq!(summary.unreadable_sectors, 1);
+    assert!(!summary.is_intact());
+}
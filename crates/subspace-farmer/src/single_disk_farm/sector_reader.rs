@@ -0,0 +1,205 @@
+//! Access to plotted sectors and their metadata by sector index, such that sector offsets and
+//! sizes are derived from farm layout in one place instead of at every call site, see
+//! [`SectorReader`]
+
+#[cfg(test)]
+mod tests;
+
+use crate::single_disk_farm::file_region::FileRegion;
+use crate::single_disk_farm::metadata_layout::MetadataLayout;
+use parity_scale_codec::Decode;
+use std::io;
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::sync::Arc;
+use subspace_core_primitives::SectorIndex;
+use subspace_farmer_components::file_ext::FileExt;
+use subspace_farmer_components::sector::{sector_size, SectorMetadataChecksummed};
+use thiserror::Error;
+
+/// Errors happening when reading sectors with [`SectorReader`]
+#[derive(Debug, Error)]
+pub enum SectorReaderError {
+    /// Sector index is outside of the farm
+    #[error("Sector index {sector_index} is out of range, farm has {sector_count} sectors")]
+    SectorIndexOutOfRange {
+        /// Sector index
+        sector_index: SectorIndex,
+        /// Number of sectors in the farm
+        sector_count: SectorIndex,
+    },
+    /// Failed to read sector metadata
+    #[error("Failed to read metadata of sector {sector_index} at offset {offset}: {error}")]
+    MetadataRead {
+        /// Sector index
+        sector_index: SectorIndex,
+        /// Offset of sector metadata in metadata file
+        offset: u64,
+        /// Low-level error
+        error: io::Error,
+    },
+    /// Failed to decode sector metadata
+    #[error("Failed to decode metadata of sector {sector_index}: {error}")]
+    MetadataDecode {
+        /// Sector index
+        sector_index: SectorIndex,
+        /// Low-level error
+        error: parity_scale_codec::Error,
+    },
+    /// Sector metadata belongs to a different sector
+    #[error("Metadata of sector {sector_index} belongs to sector {found_sector_index}")]
+    SectorIndexMismatch {
+        /// Sector index
+        sector_index: SectorIndex,
+        /// Sector index found in metadata
+        found_sector_index: SectorIndex,
+    },
+}
+
+impl From<SectorReaderError> for io::Error {
+    fn from(error: SectorReaderError) -> Self {
+        let kind = match &error {
+            SectorReaderError::SectorIndexOutOfRange { .. } => io::ErrorKind::InvalidInput,
+            SectorReaderError::MetadataRead { error, .. } => error.kind(),
+            SectorReaderError::MetadataDecode { .. }
+            | SectorReaderError::SectorIndexMismatch { .. } => io::ErrorKind::InvalidData,
+        };
+
+        io::Error::new(kind, error)
+    }
+}
+
+/// Reader of plotted sectors that owns handles of plot and metadata files together with the
+/// layout of the farm.
+///
+/// Sectors are exposed as bounds-checked [`FileRegion`]s, such that reads past the end of the
+/// sector fail instead of silently reading the neighbouring sector. Number of sectors is shared
+/// with the farm, such that reader remains valid when farm grows.
+#[derive(Debug, Clone)]
+pub struct SectorReader<PF, MF> {
+    plot_file: PF,
+    metadata_file: MF,
+    metadata_layout: MetadataLayout,
+    pieces_in_sector: u16,
+    sector_count: Arc<AtomicU16>,
+}
+
+impl<PF, MF> SectorReader<PF, MF> {
+    /// Create new instance for farm with `pieces_in_sector` pieces in every sector, whose plot file
+    /// has space for `sector_count` sectors
+    pub(super) fn new(
+        plot_file: PF,
+        metadata_file: MF,
+        metadata_layout: MetadataLayout,
+        pieces_in_sector: u16,
+        sector_count: Arc<AtomicU16>,
+    ) -> Self {
+        Self {
+            plot_file,
+            metadata_file,
+            metadata_layout,
+            pieces_in_sector,
+            sector_count,
+        }
+    }
+
+    /// Same reader, but with a different handle of the same plot file (like the one optimized for
+    /// a specific access pattern)
+    pub(super) fn with_plot_file<PF2>(&self, plot_file: PF2) -> SectorReader<PF2, MF>
+    where
+        MF: Clone,
+    {
+        SectorReader {
+            plot_file,
+            metadata_file: self.metadata_file.clone(),
+            metadata_layout: self.metadata_layout,
+            pieces_in_sector: self.pieces_in_sector,
+            sector_count: Arc::clone(&self.sector_count),
+        }
+    }
+
+    /// Plot file sectors are read from
+    pub(super) fn plot_file(&self) -> &PF {
+        &self.plot_file
+    }
+
+    /// Number of pieces in every sector
+    pub fn pieces_in_sector(&self) -> u16 {
+        self.pieces_in_sector
+    }
+
+    /// Size of every sector in bytes
+    pub fn sector_size(&self) -> u64 {
+        sector_size(self.pieces_in_sector) as u64
+    }
+
+    /// Number of sectors in the farm, plotted or not
+    pub fn sector_count(&self) -> SectorIndex {
+        self.sector_count.load(Ordering::Acquire)
+    }
+
+    /// View over the sector at `sector_index`, offsets of reads are relative to the start of the
+    /// sector
+    pub fn read_sector(
+        &self,
+        sector_index: SectorIndex,
+    ) -> Result<FileRegion<'_, PF>, SectorReaderError> {
+        self.check_sector_index(sector_index)?;
+
+        let sector_size = self.sector_size();
+        Ok(FileRegion::new(
+            &self.plot_file,
+            u64::from(sector_index) * sector_size,
+            sector_size,
+        ))
+    }
+
+    /// Read metadata of the sector at `sector_index` from metadata file, metadata that doesn't
+    /// belong to the sector results in [`SectorReaderError::SectorIndexMismatch`]
+    pub fn read_sector_metadata(
+        &self,
+        sector_index: SectorIndex,
+    ) -> Result<SectorMetadataChecksummed, SectorReaderError>
+    where
+        MF: FileExt,
+    {
+        self.check_sector_index(sector_index)?;
+
+        let offset = self.metadata_layout.sector_metadata_offset(sector_index);
+        let mut sector_metadata_bytes = vec![0; SectorMetadataChecksummed::encoded_size()];
+        self.metadata_file
+            .read_exact_at(&mut sector_metadata_bytes, offset)
+            .map_err(|error| SectorReaderError::MetadataRead {
+                sector_index,
+                offset,
+                error,
+            })?;
+
+        let sector_metadata = SectorMetadataChecksummed::decode(
+            &mut sector_metadata_bytes.as_slice(),
+        )
+        .map_err(|error| SectorReaderError::MetadataDecode {
+            sector_index,
+            error,
+        })?;
+        if sector_metadata.sector_index != sector_index {
+            return Err(SectorReaderError::SectorIndexMismatch {
+                sector_index,
+                found_sector_index: sector_metadata.sector_index,
+            });
+        }
+
+        Ok(sector_metadata)
+    }
+
+    fn check_sector_index(&self, sector_index: SectorIndex) -> Result<(), SectorReaderError> {
+        let sector_count = self.sector_count();
+        if sector_index >= sector_count {
+            return Err(SectorReaderError::SectorIndexOutOfRange {
+                sector_index,
+                sector_count,
+            });
+        }
+
+        Ok(())
+    }
+}
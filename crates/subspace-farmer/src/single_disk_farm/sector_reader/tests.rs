@@ -0,0 +1,167 @@
+use crate::single_disk_farm::metadata_layout::MetadataLayout;
+use crate::single_disk_farm::sector_reader::{SectorReader, SectorReaderError};
+use crate::single_disk_farm::test_farm::TestFarm;
+use crate::single_disk_farm::{test_farm, SingleDiskFarm, RESERVED_PLOT_METADATA};
+use parity_scale_codec::Encode;
+use std::assert_matches::assert_matches;
+use std::fs::{File, OpenOptions};
+use std::path::Path;
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::sync::Arc;
+use std::{fs, io};
+use subspace_core_primitives::SectorIndex;
+use subspace_farmer_components::file_ext::FileExt;
+use subspace_farmer_components::sector::sector_size;
+use subspace_farmer_components::ReadAtSync;
+use tempfile::tempdir;
+
+const PIECES_IN_SECTOR: u16 = 1;
+const SECTOR_COUNT: SectorIndex = 2;
+
+/// Sector reader of farm with `SECTOR_COUNT` sectors in `directory`
+fn open_sector_reader(directory: &Path) -> SectorReader<File, File> {
+    let open = |file_name| {
+        OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(directory.join(file_name))
+            .unwrap()
+    };
+
+    SectorReader::new(
+        open(SingleDiskFarm::PLOT_FILE),
+        open(SingleDiskFarm::METADATA_FILE),
+        MetadataLayout::CURRENT,
+        PIECES_IN_SECTOR,
+        Arc::new(AtomicU16::new(SECTOR_COUNT)),
+    )
+}
+
+#[test]
+fn sector_boundaries() {
+    let tempdir = tempdir().unwrap();
+    TestFarm::new(PIECES_IN_SECTOR, SECTOR_COUNT).create(tempdir.as_ref());
+    let sector_reader = open_sector_reader(tempdir.as_ref());
+    let sector_size = sector_size(PIECES_IN_SECTOR) as u64;
+    let plot = fs::read(tempdir.as_ref().join(SingleDiskFarm::PLOT_FILE)).unwrap();
+
+    assert_eq!(sector_reader.sector_count(), SECTOR_COUNT);
+    assert_eq!(sector_reader.sector_size(), sector_size);
+
+    for sector_index in 0..SECTOR_COUNT {
+        let sector = sector_reader.read_sector(sector_index).unwrap();
+        assert_eq!(sector.start(), u64::from(sector_index) * sector_size);
+        assert_eq!(sector.len(), sector_size);
+
+        let expected_sector_bytes = &plot[sector.start() as usize..][..sector_size as usize];
+        let mut sector_bytes = vec![0xff; sector_size as usize];
+        sector.read_at(&mut sector_bytes, 0).unwrap();
+        assert_eq!(sector_bytes, expected_sector_bytes);
+
+        // Reads past the end of the sector don't reach the neighbouring sector
+        let mut bytes = [0xff; 2];
+        assert_eq!(
+            sector
+                .read_at(&mut bytes, sector_size - 1)
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+        assert_eq!(bytes, [0xff; 2]);
+        sector.read_at(&mut bytes[..1], sector_size - 1).unwrap();
+        assert_eq!(bytes[0], expected_sector_bytes[sector_size as usize - 1]);
+    }
+}
+
+#[test]
+fn sector_index_out_of_range() {
+    let tempdir = tempdir().unwrap();
+    TestFarm::new(PIECES_IN_SECTOR, SECTOR_COUNT).create(tempdir.as_ref());
+    let sector_reader = open_sector_reader(tempdir.as_ref());
+
+    assert_matches!(
+        sector_reader.read_sector(SECTOR_COUNT),
+        Err(SectorReaderError::SectorIndexOutOfRange {
+            sector_index: SECTOR_COUNT,
+            sector_count: SECTOR_COUNT,
+        })
+    );
+    assert_matches!(
+        sector_reader.read_sector_metadata(SectorIndex::MAX),
+        Err(SectorReaderError::SectorIndexOutOfRange {
+            sector_index: SectorIndex::MAX,
+            sector_count: SECTOR_COUNT,
+        })
+    );
+    let error = io::Error::from(sector_reader.read_sector(SECTOR_COUNT).unwrap_err());
+    assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+
+    // Sector count is shared with the farm, such that grown farm can be read from right away
+    sector_reader
+        .sector_count
+        .store(SECTOR_COUNT + 1, Ordering::Release);
+    let sector = sector_reader.read_sector(SECTOR_COUNT).unwrap();
+    assert_eq!(
+        sector.start(),
+        u64::from(SECTOR_COUNT) * sector_reader.sector_size()
+    );
+}
+
+#[test]
+fn sector_metadata_errors() {
+    let tempdir = tempdir().unwrap();
+    TestFarm::new(PIECES_IN_SECTOR, SECTOR_COUNT).create(tempdir.as_ref());
+    let sector_reader = open_sector_reader(tempdir.as_ref());
+    let metadata_layout = MetadataLayout::CURRENT;
+
+    for sector_index in 0..SECTOR_COUNT {
+        assert_eq!(
+            sector_reader
+                .read_sector_metadata(sector_index)
+                .unwrap()
+                .encode(),
+            test_farm::sector_metadata(sector_index, PIECES_IN_SECTOR).encode()
+        );
+    }
+
+    // Metadata of the first sector is stored in the slot of the second sector
+    sector_reader
+        .metadata_file
+        .write_all_at(
+            &metadata_layout
+                .encode_sector_metadata(&test_farm::sector_metadata(0, PIECES_IN_SECTOR)),
+            metadata_layout.sector_metadata_offset(1),
+        )
+        .unwrap();
+    assert_matches!(
+        sector_reader.read_sector_metadata(1),
+        Err(SectorReaderError::SectorIndexMismatch {
+            sector_index: 1,
+            found_sector_index: 0,
+        })
+    );
+
+    // Checksum of the first sector doesn't match anymore
+    sector_reader
+        .metadata_file
+        .write_all_at(&[1], metadata_layout.sector_metadata_offset(0) + 10)
+        .unwrap();
+    assert_matches!(
+        sector_reader.read_sector_metadata(0),
+        Err(SectorReaderError::MetadataDecode {
+            sector_index: 0,
+            ..
+        })
+    );
+
+    // Metadata file is too small
+    sector_reader
+        .metadata_file
+        .set_len(RESERVED_PLOT_METADATA)
+        .unwrap();
+    assert_matches!(
+        sector_reader.read_sector_metadata(0),
+        Err(SectorReaderError::MetadataRead { sector_index: 0, offset, .. })
+            if offset == metadata_layout.sector_metadata_offset(0)
+    );
+}
@@ -0,0 +1,255 @@
+//! Rewriting of a single plotted sector in place with externally provided contents, which is a
+//! low-level tool for repairing sectors that were found to be corrupted, see
+//! [`SingleDiskFarm::rewrite_sector()`](super::SingleDiskFarm::rewrite_sector)
+
+#[cfg(test)]
+mod tests;
+
+use crate::single_disk_farm::direct_io_file::metrics::FileMetrics;
+use crate::single_disk_farm::metadata_layout::MetadataLayout;
+use crate::single_disk_farm::plotting::{write_sector, PlottingError};
+use crate::single_disk_farm::shutdown::ShutdownMarker;
+use crate::utils::AsyncJoinOnDrop;
+use async_lock::{Mutex as AsyncMutex, RwLock as AsyncRwLock};
+use parity_scale_codec::Decode;
+use std::sync::Arc;
+use std::{io, mem};
+use subspace_core_primitives::crypto::blake3_hash;
+use subspace_core_primitives::{Blake3Hash, SectorIndex};
+use subspace_farmer_components::sector::{sector_size, SectorMetadataChecksummed};
+use subspace_farmer_components::{ReadAtSync, WriteAtSync};
+use thiserror::Error;
+use tracing::{info, warn, Span};
+
+/// Errors happening during sector rewrite
+#[derive(Debug, Error)]
+pub enum SectorRewriteError {
+    /// Farm is opened in read-only mode
+    #[error("Farm is opened in read-only mode, sectors can't be rewritten")]
+    ReadOnly,
+    /// Sector is not plotted
+    #[error("Sector {sector_index} is not plotted (plotted sectors: {plotted_sectors_count})")]
+    NotPlotted {
+        /// Sector index
+        sector_index: SectorIndex,
+        /// Number of sectors plotted so far
+        plotted_sectors_count: SectorIndex,
+    },
+    /// Size of sector bytes doesn't match farm layout
+    #[error("Invalid sector size: expected {expected} bytes, got {actual} bytes")]
+    InvalidSectorSize {
+        /// Expected size
+        expected: usize,
+        /// Actual size
+        actual: usize,
+    },
+    /// Size of sector metadata bytes doesn't match farm layout
+    #[error("Invalid sector metadata size: expected {expected} bytes, got {actual} bytes")]
+    InvalidSectorMetadataSize {
+        /// Expected size
+        expected: usize,
+        /// Actual size
+        actual: usize,
+    },
+    /// Failed to decode sector metadata (including checksum mismatch)
+    #[error("Failed to decode sector metadata: {0}")]
+    InvalidSectorMetadata(#[from] parity_scale_codec::Error),
+    /// Sector metadata belongs to a different sector or farm layout
+    #[error(
+        "Sector metadata doesn't match sector {sector_index} with {pieces_in_sector} pieces in \
+        sector"
+    )]
+    SectorMetadataMismatch {
+        /// Sector index
+        sector_index: SectorIndex,
+        /// Number of pieces in sector of the farm
+        pieces_in_sector: u16,
+    },
+    /// Sector bytes don't match checksum stored in their last bytes
+    #[error("Sector bytes don't match checksum stored in them")]
+    SectorChecksumMismatch {
+        /// Checksum of sector bytes
+        actual_checksum: Blake3Hash,
+        /// Checksum stored in the last bytes of the sector
+        expected_checksum: Blake3Hash,
+    },
+    /// Sector didn't match its contents when read back after writing, even after writing it again
+    #[error("Sector {sector_index} didn't match its contents when read back after writing")]
+    WriteVerificationFailed {
+        /// Sector index
+        sector_index: SectorIndex,
+    },
+    /// I/O error occurred
+    #[error("Sector rewrite I/O error: {0}")]
+    Io(#[from] io::Error),
+    /// Tokio join error
+    #[error("Tokio join error: {0}")]
+    TokioJoinError(#[from] tokio::task::JoinError),
+}
+
+pub(super) struct SectorRewriteOptions<PF, MF> {
+    pub(super) plot_file: PF,
+    /// Metadata file updates are supposed to go through the same journal as plotting uses
+    pub(super) metadata_file: MF,
+    pub(super) pieces_in_sector: u16,
+    pub(super) sectors_metadata: Arc<AsyncRwLock<Vec<SectorMetadataChecksummed>>>,
+    pub(super) modifying_sector_index: Arc<AsyncRwLock<Option<SectorIndex>>>,
+    /// Records sector being rewritten until files are synced
+    pub(super) shutdown_marker: Arc<ShutdownMarker>,
+    /// Held by plotting for as long as sector is being modified
+    pub(super) sector_modification_mutex: Arc<AsyncMutex<()>>,
+    /// Metrics of plot file, used to record write verification failures
+    pub(super) plot_file_metrics: Option<Arc<FileMetrics>>,
+}
+
+/// Validate `sector_bytes` and `sector_metadata_bytes` against farm layout and checksums, returns
+/// decoded sector metadata
+fn validate_sector(
+    sector_index: SectorIndex,
+    pieces_in_sector: u16,
+    sector_bytes: &[u8],
+    sector_metadata_bytes: &[u8],
+) -> Result<SectorMetadataChecksummed, SectorRewriteError> {
+    let sector_size = sector_size(pieces_in_sector);
+    if sector_bytes.len() != sector_size {
+        return Err(SectorRewriteError::InvalidSectorSize {
+            expected: sector_size,
+            actual: sector_bytes.len(),
+        });
+    }
+    let sector_metadata_size = SectorMetadataChecksummed::encoded_size();
+    if sector_metadata_bytes.len() != sector_metadata_size {
+        return Err(SectorRewriteError::InvalidSectorMetadataSize {
+            expected: sector_metadata_size,
+            actual: sector_metadata_bytes.len(),
+        });
+    }
+
+    let sector_metadata = SectorMetadataChecksummed::decode(&mut &*sector_metadata_bytes)?;
+    if sector_metadata.sector_index != sector_index
+        || sector_metadata.pieces_in_sector != pieces_in_sector
+    {
+        return Err(SectorRewriteError::SectorMetadataMismatch {
+            sector_index,
+            pieces_in_sector,
+        });
+    }
+
+    let (sector_contents, expected_checksum) =
+        sector_bytes.split_at(sector_size - mem::size_of::<Blake3Hash>());
+    let actual_checksum = blake3_hash(sector_contents);
+    if actual_checksum != expected_checksum {
+        return Err(SectorRewriteError::SectorChecksumMismatch {
+            actual_checksum,
+            expected_checksum: expected_checksum
+                .try_into()
+                .expect("Split at the size of the checksum above; qed"),
+        });
+    }
+
+    Ok(sector_metadata)
+}
+
+/// Rewrite plotted sector with `sector_bytes` and `sector_metadata_bytes` (SCALE-encoded
+/// [`SectorMetadataChecksummed`]).
+///
+/// Nothing is written unless both are valid. Sector is taken offline (see
+/// `modifying_sector_index`) while it is being rewritten, sector is read back after writing and
+/// both files are synced before it is brought back online.
+pub(super) async fn rewrite_sector<PF, MF>(
+    options: SectorRewriteOptions<PF, MF>,
+    sector_index: SectorIndex,
+    sector_bytes: Vec<u8>,
+    sector_metadata_bytes: Vec<u8>,
+) -> Result<(), SectorRewriteError>
+where
+    PF: ReadAtSync + WriteAtSync + 'static,
+    MF: WriteAtSync + 'static,
+{
+    let SectorRewriteOptions {
+        plot_file,
+        metadata_file,
+        pieces_in_sector,
+        sectors_metadata,
+        modifying_sector_index,
+        shutdown_marker,
+        sector_modification_mutex,
+        plot_file_metrics,
+    } = options;
+
+    let sector_metadata = validate_sector(
+        sector_index,
+        pieces_in_sector,
+        &sector_bytes,
+        &sector_metadata_bytes,
+    )?;
+
+    // Plotting holds this mutex for as long as it modifies a sector, such that sector being
+    // plotted right now is not rewritten concurrently and plotting doesn't start modifying sector
+    // until it is rewritten
+    let _sector_modification_guard = sector_modification_mutex.lock().await;
+
+    let plotted_sectors_count = sectors_metadata.read().await.len() as SectorIndex;
+    if sector_index >= plotted_sectors_count {
+        return Err(SectorRewriteError::NotPlotted {
+            sector_index,
+            plotted_sectors_count,
+        });
+    }
+
+    // Take sector offline, such that it is not audited or read from while being rewritten
+    modifying_sector_index.write().await.replace(sector_index);
+    if let Err(error) = shutdown_marker.note_modified_sector(sector_index) {
+        modifying_sector_index.write().await.take();
+        return Err(SectorRewriteError::Io(error));
+    }
+
+    let span = Span::current();
+    let write_fut = tokio::task::spawn_blocking(move || {
+        let _span_guard = span.enter();
+
+        let metadata_layout = MetadataLayout::CURRENT;
+        write_sector(
+            &plot_file,
+            &metadata_file,
+            sector_index,
+            &sector_bytes,
+            u64::from(sector_index) * sector_bytes.len() as u64,
+            &metadata_layout.pad_sector_metadata(sector_metadata_bytes),
+            metadata_layout.sector_metadata_offset(sector_index),
+            true,
+            true,
+            plot_file_metrics.as_deref(),
+        )
+    });
+    let result = match AsyncJoinOnDrop::new(write_fut, false).await {
+        Ok(Ok(())) => Ok(()),
+        Ok(Err(PlottingError::WriteVerificationFailed { sector_index })) => {
+            Err(SectorRewriteError::WriteVerificationFailed { sector_index })
+        }
+        Ok(Err(PlottingError::Io(error))) => Err(SectorRewriteError::Io(error)),
+        Ok(Err(error)) => Err(SectorRewriteError::Io(io::Error::other(error))),
+        Err(error) => Err(SectorRewriteError::TokioJoinError(error)),
+    };
+
+    match &result {
+        Ok(()) => {
+            info!(%sector_index, "Sector rewritten successfully");
+            sectors_metadata.write().await[usize::from(sector_index)] = sector_metadata;
+            // Both files were synced after writing
+            if let Err(error) = shutdown_marker.note_synced() {
+                warn!(%error, "Failed to record synced files in shutdown marker");
+            }
+        }
+        Err(error) => {
+            // Metadata is only written once sector matches its contents when read back, previous
+            // metadata stays in use and sector will be reported by integrity scrub if damaged
+            warn!(%error, %sector_index, "Failed to rewrite sector");
+        }
+    }
+
+    // Bring sector back online
+    modifying_sector_index.write().await.take();
+
+    result
+}
@@ -0,0 +1,288 @@
+use crate::single_disk_farm::direct_io_file::DirectIoFile;
+use crate::single_disk_farm::integrity::verify_sector_checksum;
+use crate::single_disk_farm::metadata_layout::MetadataLayout;
+use crate::single_disk_farm::sector_reader::SectorReader;
+use crate::single_disk_farm::sector_rewrite::{rewrite_sector, SectorRewriteError};
+use crate::single_disk_farm::shutdown::ShutdownMarker;
+use crate::single_disk_farm::test_farm::TestFarm;
+use crate::single_disk_farm::{test_farm, SingleDiskFarm};
+use parity_scale_codec::Encode;
+use std::assert_matches::assert_matches;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::AtomicU16;
+use std::sync::Arc;
+use subspace_core_primitives::{HistorySize, Record, SectorIndex, SegmentIndex};
+use subspace_farmer_components::faulty_file::{Fault, FaultRule, FaultyFile, Operation};
+use subspace_farmer_components::sector::{sector_size, SectorMetadata, SectorMetadataChecksummed};
+use tempfile::tempdir;
+
+const PIECES_IN_SECTOR: u16 = 1;
+const SECTOR_SIZE: usize = sector_size(PIECES_IN_SECTOR);
+const SECTOR_COUNT: SectorIndex = 3;
+const SHUTDOWN_MARKER_FILE: &str = "shutdown_marker.bin";
+/// Sector that is corrupted in every test
+const CORRUPTED_SECTOR_INDEX: SectorIndex = 1;
+
+/// Contents of all sectors in the plot of farm in `directory`
+fn read_sectors(directory: &Path) -> Vec<Vec<u8>> {
+    fs::read(directory.join(SingleDiskFarm::PLOT_FILE))
+        .unwrap()
+        .chunks_exact(SECTOR_SIZE)
+        .take(usize::from(SECTOR_COUNT))
+        .map(<[u8]>::to_vec)
+        .collect()
+}
+
+fn create_shutdown_marker(directory: &Path) -> Arc<ShutdownMarker> {
+    Arc::new(ShutdownMarker::create(directory.join(SHUTDOWN_MARKER_FILE)).unwrap())
+}
+
+/// Returns `true` if sector is intact
+fn verify_sector(plot_file: &Arc<FaultyFile<DirectIoFile>>, sector_index: SectorIndex) -> bool {
+    let sector_reader = SectorReader::new(
+        Arc::clone(plot_file),
+        (),
+        MetadataLayout::CURRENT,
+        PIECES_IN_SECTOR,
+        Arc::new(AtomicU16::new(SECTOR_COUNT)),
+    );
+    let sector = sector_reader.read_sector(sector_index).unwrap();
+    verify_sector_checksum(&sector, &mut vec![0; Record::SIZE])
+        .unwrap()
+        .is_none()
+}
+
+fn read_sector_metadata(directory: &Path, sector_index: SectorIndex) -> Vec<u8> {
+    fs::read(directory.join(SingleDiskFarm::METADATA_FILE)).unwrap()
+        [MetadataLayout::CURRENT.sector_metadata_offset(sector_index) as usize..]
+        [..SectorMetadataChecksummed::encoded_size()]
+        .to_vec()
+}
+
+#[tokio::test]
+async fn rewrite_corrupted_sector() {
+    let tempdir = tempdir().unwrap();
+    let farm = TestFarm::new(PIECES_IN_SECTOR, SECTOR_COUNT);
+    farm.create(tempdir.as_ref());
+    let sectors = read_sectors(tempdir.as_ref());
+    test_farm::flip_byte(tempdir.as_ref(), PIECES_IN_SECTOR, CORRUPTED_SECTOR_INDEX);
+    let plot_file = Arc::new(FaultyFile::new(
+        DirectIoFile::open(&tempdir.as_ref().join(SingleDiskFarm::PLOT_FILE)).unwrap(),
+    ));
+    let shutdown_marker = create_shutdown_marker(tempdir.as_ref());
+
+    assert!(verify_sector(&plot_file, 0));
+    assert!(!verify_sector(&plot_file, CORRUPTED_SECTOR_INDEX));
+    assert!(verify_sector(&plot_file, 2));
+
+    let options = farm.sector_rewrite_options(
+        tempdir.as_ref(),
+        Arc::clone(&plot_file),
+        Arc::clone(&shutdown_marker),
+    );
+    let sectors_metadata = Arc::clone(&options.sectors_metadata);
+    let modifying_sector_index = Arc::clone(&options.modifying_sector_index);
+    let sector_metadata = SectorMetadataChecksummed::from(SectorMetadata {
+        history_size: HistorySize::from(SegmentIndex::ONE),
+        ..SectorMetadata::clone(&test_farm::sector_metadata(
+            CORRUPTED_SECTOR_INDEX,
+            PIECES_IN_SECTOR,
+        ))
+    });
+    rewrite_sector(
+        options,
+        CORRUPTED_SECTOR_INDEX,
+        sectors[usize::from(CORRUPTED_SECTOR_INDEX)].clone(),
+        sector_metadata.encode(),
+    )
+    .await
+    .unwrap();
+
+    // Sector passes integrity check again and is back online with updated metadata
+    for sector_index in 0..SECTOR_COUNT {
+        assert!(verify_sector(&plot_file, sector_index));
+    }
+    assert_eq!(*modifying_sector_index.read().await, None);
+    assert_eq!(
+        sectors_metadata.read().await[usize::from(CORRUPTED_SECTOR_INDEX)].encode(),
+        sector_metadata.encode()
+    );
+    // Files were synced, so sector doesn't need to be checked after unclean shutdown
+    assert_eq!(
+        ShutdownMarker::modified_sectors(&tempdir.as_ref().join(SHUTDOWN_MARKER_FILE)).unwrap(),
+        Some(Vec::new())
+    );
+    assert_eq!(
+        read_sector_metadata(tempdir.as_ref(), CORRUPTED_SECTOR_INDEX),
+        sector_metadata.encode()
+    );
+    assert_eq!(
+        read_sector_metadata(tempdir.as_ref(), 0),
+        test_farm::sector_metadata(0, PIECES_IN_SECTOR).encode()
+    );
+}
+
+#[tokio::test]
+async fn invalid_input() {
+    let tempdir = tempdir().unwrap();
+    let farm = TestFarm::new(PIECES_IN_SECTOR, SECTOR_COUNT);
+    farm.create(tempdir.as_ref());
+    let sectors = read_sectors(tempdir.as_ref());
+    test_farm::flip_byte(tempdir.as_ref(), PIECES_IN_SECTOR, CORRUPTED_SECTOR_INDEX);
+    let plot_file = Arc::new(FaultyFile::new(
+        DirectIoFile::open(&tempdir.as_ref().join(SingleDiskFarm::PLOT_FILE)).unwrap(),
+    ));
+    let shutdown_marker = create_shutdown_marker(tempdir.as_ref());
+    let options = || {
+        farm.sector_rewrite_options(
+            tempdir.as_ref(),
+            Arc::clone(&plot_file),
+            Arc::clone(&shutdown_marker),
+        )
+    };
+
+    let sector = &sectors[usize::from(CORRUPTED_SECTOR_INDEX)];
+    let sector_metadata =
+        test_farm::sector_metadata(CORRUPTED_SECTOR_INDEX, PIECES_IN_SECTOR).encode();
+
+    assert_matches!(
+        rewrite_sector(
+            options(),
+            CORRUPTED_SECTOR_INDEX,
+            sector[1..].to_vec(),
+            sector_metadata.clone()
+        )
+        .await,
+        Err(SectorRewriteError::InvalidSectorSize { .. })
+    );
+    assert_matches!(
+        rewrite_sector(
+            options(),
+            CORRUPTED_SECTOR_INDEX,
+            sector.clone(),
+            sector_metadata[1..].to_vec()
+        )
+        .await,
+        Err(SectorRewriteError::InvalidSectorMetadataSize { .. })
+    );
+
+    let mut corrupted_sector_metadata = sector_metadata.clone();
+    corrupted_sector_metadata[0] ^= 1;
+    assert_matches!(
+        rewrite_sector(
+            options(),
+            CORRUPTED_SECTOR_INDEX,
+            sector.clone(),
+            corrupted_sector_metadata
+        )
+        .await,
+        Err(SectorRewriteError::InvalidSectorMetadata(_))
+    );
+
+    // Metadata of a different sector
+    assert_matches!(
+        rewrite_sector(
+            options(),
+            CORRUPTED_SECTOR_INDEX,
+            sector.clone(),
+            test_farm::sector_metadata(2, PIECES_IN_SECTOR).encode()
+        )
+        .await,
+        Err(SectorRewriteError::SectorMetadataMismatch { .. })
+    );
+
+    let mut corrupted_sector = sector.clone();
+    corrupted_sector[123] ^= 1;
+    assert_matches!(
+        rewrite_sector(
+            options(),
+            CORRUPTED_SECTOR_INDEX,
+            corrupted_sector,
+            sector_metadata.clone()
+        )
+        .await,
+        Err(SectorRewriteError::SectorChecksumMismatch { .. })
+    );
+
+    // Only plotted sectors can be rewritten
+    let options = options();
+    options.sectors_metadata.write().await.truncate(1);
+    assert_matches!(
+        rewrite_sector(
+            options,
+            CORRUPTED_SECTOR_INDEX,
+            sector.clone(),
+            sector_metadata.clone()
+        )
+        .await,
+        Err(SectorRewriteError::NotPlotted {
+            sector_index: CORRUPTED_SECTOR_INDEX,
+            plotted_sectors_count: 1
+        })
+    );
+
+    // Nothing was written
+    assert!(!verify_sector(&plot_file, CORRUPTED_SECTOR_INDEX));
+    assert!(plot_file
+        .history()
+        .iter()
+        .all(|record| record.operation != Operation::Write));
+}
+
+#[tokio::test]
+async fn write_verification_failure() {
+    let tempdir = tempdir().unwrap();
+    let farm = TestFarm::new(PIECES_IN_SECTOR, SECTOR_COUNT);
+    farm.create(tempdir.as_ref());
+    let sectors = read_sectors(tempdir.as_ref());
+    test_farm::flip_byte(tempdir.as_ref(), PIECES_IN_SECTOR, CORRUPTED_SECTOR_INDEX);
+    let plot_file = Arc::new(FaultyFile::new(
+        DirectIoFile::open(&tempdir.as_ref().join(SingleDiskFarm::PLOT_FILE)).unwrap(),
+    ));
+    let shutdown_marker = create_shutdown_marker(tempdir.as_ref());
+
+    // Sector never matches its contents when read back
+    let sector_offset = u64::from(CORRUPTED_SECTOR_INDEX) * SECTOR_SIZE as u64;
+    plot_file.inject(
+        FaultRule::new(Operation::Read, Fault::Corrupt)
+            .offsets(sector_offset..sector_offset + SECTOR_SIZE as u64)
+            .times(usize::MAX),
+    );
+
+    let options = farm.sector_rewrite_options(
+        tempdir.as_ref(),
+        Arc::clone(&plot_file),
+        Arc::clone(&shutdown_marker),
+    );
+    let modifying_sector_index = Arc::clone(&options.modifying_sector_index);
+    let sector_metadata = test_farm::sector_metadata(CORRUPTED_SECTOR_INDEX, PIECES_IN_SECTOR);
+    let new_sector_metadata = SectorMetadataChecksummed::from(SectorMetadata {
+        history_size: HistorySize::from(SegmentIndex::ONE),
+        ..SectorMetadata::clone(&sector_metadata)
+    });
+    assert_matches!(
+        rewrite_sector(
+            options,
+            CORRUPTED_SECTOR_INDEX,
+            sectors[usize::from(CORRUPTED_SECTOR_INDEX)].clone(),
+            new_sector_metadata.encode()
+        )
+        .await,
+        Err(SectorRewriteError::WriteVerificationFailed {
+            sector_index: CORRUPTED_SECTOR_INDEX
+        })
+    );
+
+    // Sector is back online, but metadata was not updated
+    assert_eq!(*modifying_sector_index.read().await, None);
+    // Sector was possibly written partially, it is checked after unclean shutdown
+    assert_eq!(
+        ShutdownMarker::modified_sectors(&tempdir.as_ref().join(SHUTDOWN_MARKER_FILE)).unwrap(),
+        Some(vec![CORRUPTED_SECTOR_INDEX])
+    );
+    assert_eq!(
+        read_sector_metadata(tempdir.as_ref(), CORRUPTED_SECTOR_INDEX),
+        sector_metadata.encode()
+    );
+}
@@ -0,0 +1,205 @@
+//! Shrinking of the farm in place while it is running by dropping trailing sectors, such that
+//! allocated space can be reduced without wiping and replotting the whole farm, see
+//! [`FarmGrowHandle::shrink()`](super::grow::FarmGrowHandle::shrink)
+
+#[cfg(test)]
+mod tests;
+
+use crate::single_disk_farm::direct_io_file::DISK_SECTOR_SIZE;
+use crate::single_disk_farm::grow::{farm_info_matches, Inner};
+use crate::single_disk_farm::metadata_layout::MetadataLayout;
+use crate::single_disk_farm::{
+    target_sector_count, PlotMetadataHeader, SingleDiskFarmError, SingleDiskFarmInfo,
+};
+use std::io;
+use std::num::NonZeroU8;
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use subspace_core_primitives::{HistorySize, Record, SectorIndex, SegmentIndex};
+use subspace_farmer_components::file_ext::FileExt;
+use subspace_farmer_components::sector::{sector_size, SectorMetadata, SectorMetadataChecksummed};
+use subspace_farmer_components::WriteAtSync;
+use thiserror::Error;
+use tracing::info;
+
+/// Summary of farm shrinking, see
+/// [`FarmGrowHandle::shrink()`](super::grow::FarmGrowHandle::shrink)
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct FarmShrinkSummary {
+    /// Number of sectors farm had space for before shrinking
+    pub sector_count: SectorIndex,
+    /// Number of sectors farm has space for after shrinking
+    pub new_sector_count: SectorIndex,
+    /// Number of plotted sectors that were dropped
+    pub dropped_plotted_sectors: SectorIndex,
+}
+
+/// Errors happening during farm shrinking
+#[derive(Debug, Error)]
+pub enum FarmShrinkError {
+    /// Read-only farm can't be shrunk
+    #[error("Read-only farm can't be shrunk")]
+    ReadOnly,
+    /// Farm info on disk doesn't belong to this farm anymore
+    #[error("Farm info in {} doesn't match farm that is running", directory.display())]
+    FarmInfoMismatch {
+        /// Farm directory
+        directory: PathBuf,
+    },
+    /// Farm error
+    #[error(transparent)]
+    Farm(#[from] SingleDiskFarmError),
+    /// I/O error occurred
+    #[error("Farm shrink I/O error: {0}")]
+    Io(#[from] io::Error),
+    /// Failed to spawn task for blocking thread
+    #[error("Failed to spawn task for blocking thread: {0}")]
+    TokioJoinError(#[from] tokio::task::JoinError),
+}
+
+pub(super) fn shrink(
+    inner: &Inner,
+    new_allocated_space: u64,
+    cache_percentage: NonZeroU8,
+) -> Result<FarmShrinkSummary, FarmShrinkError> {
+    let Inner {
+        directory,
+        info,
+        plot_file,
+        metadata_file,
+        metadata_journal,
+        sectors_metadata,
+        sector_modification_mutex,
+        plot_cache,
+        total_sectors_count,
+        ..
+    } = inner;
+
+    let Some(metadata_journal) = metadata_journal else {
+        return Err(FarmShrinkError::ReadOnly);
+    };
+
+    let mut info = info.lock();
+    let pieces_in_sector = info.pieces_in_sector();
+    let sector_size = sector_size(pieces_in_sector) as u64;
+    let sector_count = total_sectors_count.load(Ordering::Acquire);
+    let new_sector_count = target_sector_count(new_allocated_space, cache_percentage, sector_size)?;
+
+    if new_sector_count >= u64::from(sector_count) {
+        info!(
+            %sector_count,
+            %new_sector_count,
+            "Farm already fits into new allocated space, nothing to shrink"
+        );
+
+        return Ok(FarmShrinkSummary {
+            sector_count,
+            new_sector_count: sector_count,
+            dropped_plotted_sectors: 0,
+        });
+    }
+    // Less than current sector count, hence fits
+    let new_sector_count = new_sector_count as SectorIndex;
+
+    // Only the farm directory can be shrunk, additional segments follow it in the plot
+    if let Some(plot_segment) = info.plot_segments().first() {
+        return Err(SingleDiskFarmError::PlotSegmentResized {
+            id: *info.id(),
+            directory: directory.clone(),
+            sector_count: plot_segment.sector_count,
+            new_sector_count,
+        }
+        .into());
+    }
+
+    if !farm_info_matches(directory, &info)? {
+        return Err(FarmShrinkError::FarmInfoMismatch {
+            directory: directory.clone(),
+        });
+    }
+
+    // Plotting checks this before writing a sector, such that dropped sectors that are queued for
+    // plotting already are not written anymore
+    total_sectors_count.store(new_sector_count, Ordering::Release);
+
+    // Waits for sector that is being written right now, if any, no sectors are modified afterwards
+    // until shrinking is done
+    let _sector_modification_guard = sector_modification_mutex.lock_blocking();
+    let plotted_sector_count = {
+        let mut sectors_metadata = sectors_metadata.write_blocking();
+        let plotted_sector_count = SectorIndex::try_from(sectors_metadata.len())
+            .expect("Number of sectors never exceeds `SectorIndex` type; qed");
+
+        info!(
+            %sector_count,
+            %new_sector_count,
+            %plotted_sector_count,
+            "Shrinking farm"
+        );
+
+        let metadata_layout = MetadataLayout::CURRENT;
+        // Dropped sectors are marked as expired and excluded from the header before any file is
+        // truncated, such that crash at any point leaves metadata that doesn't refer to missing
+        // data
+        for sector_index in new_sector_count..plotted_sector_count {
+            let dummy_sector = SectorMetadataChecksummed::from(SectorMetadata {
+                sector_index,
+                pieces_in_sector,
+                s_bucket_sizes: Box::new([0; Record::NUM_S_BUCKETS]),
+                history_size: HistorySize::from(SegmentIndex::ZERO),
+            });
+            metadata_journal.write_all_at(
+                &metadata_layout.encode_sector_metadata(&dummy_sector),
+                metadata_layout.sector_metadata_offset(sector_index),
+            )?;
+        }
+        metadata_journal.write_all_at(
+            &metadata_layout.encode_header(&PlotMetadataHeader {
+                version: metadata_layout.version(),
+                plotted_sector_count: plotted_sector_count.min(new_sector_count),
+            }),
+            0,
+        )?;
+        metadata_journal.sync_data()?;
+
+        // Dropped sectors are no longer farmed or replotted
+        sectors_metadata.truncate(usize::from(new_sector_count));
+
+        plotted_sector_count
+    };
+
+    {
+        let SingleDiskFarmInfo::V0 {
+            allocated_space, ..
+        } = &mut *info;
+        *allocated_space = new_allocated_space;
+    }
+    info.store_to(directory)?;
+
+    // Pieces are cached in the space past plotted sectors, all of it is reserved such that nothing
+    // is cached in space that is about to be truncated
+    plot_cache.reserve_sector_blocking(sector_count - 1);
+
+    // Align plot file size for disk sector size
+    let plot_file_size = (u64::from(new_sector_count) * sector_size)
+        .next_multiple_of(DISK_SECTOR_SIZE as u64)
+        .min(plot_file.size()?);
+    plot_file.set_len(plot_file_size)?;
+    plot_file.sync_all()?;
+
+    let metadata_size = MetadataLayout::CURRENT
+        .metadata_size(new_sector_count)
+        .next_multiple_of(DISK_SECTOR_SIZE as u64);
+    metadata_file.set_len(metadata_size)?;
+    metadata_file.sync_all()?;
+
+    let summary = FarmShrinkSummary {
+        sector_count,
+        new_sector_count,
+        dropped_plotted_sectors: plotted_sector_count.saturating_sub(new_sector_count),
+    };
+
+    info!(?summary, "Farm shrunk");
+
+    Ok(summary)
+}
@@ -0,0 +1,245 @@
+use crate::farm::FarmId;
+use crate::single_disk_farm::direct_io_file::DISK_SECTOR_SIZE;
+use crate::single_disk_farm::metadata_layout::MetadataLayout;
+use crate::single_disk_farm::readability_scrub::{ReadabilityScrubOptions, SectorReadability};
+use crate::single_disk_farm::shrink::{FarmShrinkError, FarmShrinkSummary};
+use crate::single_disk_farm::test_farm::{GrowHandleOptions, TestFarm};
+use crate::single_disk_farm::{
+    fixed_space_usage, test_farm, PlotMetadataHeader, SingleDiskFarm, SingleDiskFarmError,
+    SingleDiskFarmInfo,
+};
+use parity_scale_codec::Decode;
+use std::assert_matches::assert_matches;
+use std::fs;
+use std::num::NonZeroU8;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use subspace_core_primitives::SectorIndex;
+use subspace_farmer_components::sector::sector_size;
+use tempfile::tempdir;
+
+const PIECES_IN_SECTOR: u16 = 1;
+const SECTOR_COUNT: SectorIndex = 4;
+const CACHE_PERCENTAGE: NonZeroU8 = NonZeroU8::new(1).expect("Not zero; qed");
+
+/// Allocated space that fits exactly `sector_count` sectors with [`CACHE_PERCENTAGE`]
+fn allocated_space(sector_count: SectorIndex) -> u64 {
+    test_farm::allocated_space(PIECES_IN_SECTOR, sector_count, CACHE_PERCENTAGE)
+}
+
+fn plotted_sector_count(directory: &Path) -> SectorIndex {
+    let metadata = fs::read(directory.join(SingleDiskFarm::METADATA_FILE)).unwrap();
+    PlotMetadataHeader::decode(&mut metadata.as_slice())
+        .unwrap()
+        .plotted_sector_count
+}
+
+/// Sectors visited by readability scrub, all of which must be intact
+fn scrubbed_sectors(directory: &Path) -> Vec<SectorIndex> {
+    let cancelled = AtomicBool::new(false);
+    let mut sectors = Vec::new();
+    let summary = SingleDiskFarm::readability_scrub(
+        directory,
+        ReadabilityScrubOptions {
+            rate_limit: None,
+            cancelled: &cancelled,
+        },
+        |sector_index, sector_readability| {
+            assert_matches!(sector_readability, SectorReadability::Intact);
+            sectors.push(sector_index);
+        },
+    )
+    .unwrap();
+    assert!(summary.is_intact());
+
+    sectors
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn shrink_by_two_sectors() {
+    let tempdir = tempdir().unwrap();
+    let directory = tempdir.as_ref();
+    let farm =
+        TestFarm::new(PIECES_IN_SECTOR, SECTOR_COUNT).with_cache_percentage(CACHE_PERCENTAGE);
+    let info = farm.create(directory);
+    assert_eq!(scrubbed_sectors(directory), vec![0, 1, 2, 3]);
+    let grow_handle = farm.grow_handle(directory, info, GrowHandleOptions::default());
+
+    let new_sector_count = SECTOR_COUNT - 2;
+    let new_allocated_space = allocated_space(new_sector_count);
+    let summary = grow_handle
+        .shrink(new_allocated_space, CACHE_PERCENTAGE)
+        .await
+        .unwrap();
+    assert_eq!(
+        summary,
+        FarmShrinkSummary {
+            sector_count: SECTOR_COUNT,
+            new_sector_count,
+            dropped_plotted_sectors: 2,
+        }
+    );
+
+    // Dropped sectors are no longer plotted or farmed by running farm
+    assert_eq!(
+        grow_handle
+            .inner
+            .total_sectors_count
+            .load(Ordering::Acquire),
+        new_sector_count
+    );
+    assert_eq!(
+        grow_handle
+            .inner
+            .sectors_metadata
+            .read()
+            .await
+            .iter()
+            .map(|sector_metadata| sector_metadata.sector_index)
+            .collect::<Vec<_>>(),
+        vec![0, 1]
+    );
+
+    // Files are truncated to the new number of sectors
+    let sector_size = sector_size(PIECES_IN_SECTOR) as u64;
+    assert_eq!(
+        fs::metadata(directory.join(SingleDiskFarm::PLOT_FILE))
+            .unwrap()
+            .len(),
+        (u64::from(new_sector_count) * sector_size).next_multiple_of(DISK_SECTOR_SIZE as u64)
+    );
+    assert_eq!(
+        fs::metadata(directory.join(SingleDiskFarm::METADATA_FILE))
+            .unwrap()
+            .len(),
+        MetadataLayout::CURRENT
+            .metadata_size(new_sector_count)
+            .next_multiple_of(DISK_SECTOR_SIZE as u64)
+    );
+    assert_eq!(plotted_sector_count(directory), new_sector_count);
+    assert_eq!(
+        SingleDiskFarmInfo::load_from(directory)
+            .unwrap()
+            .unwrap()
+            .allocated_space(),
+        new_allocated_space
+    );
+
+    // Farm already fits, nothing else is done
+    let summary = grow_handle
+        .shrink(new_allocated_space, CACHE_PERCENTAGE)
+        .await
+        .unwrap();
+    assert_eq!(summary.new_sector_count, new_sector_count);
+    assert_eq!(summary.dropped_plotted_sectors, 0);
+    assert_eq!(plotted_sector_count(directory), new_sector_count);
+    drop(grow_handle);
+
+    // Reopened farm only audits and scrubs remaining sectors
+    let sectors_metadata = SingleDiskFarm::read_all_sectors_metadata(directory).unwrap();
+    assert_eq!(
+        sectors_metadata
+            .iter()
+            .map(|sector_metadata| sector_metadata.sector_index)
+            .collect::<Vec<_>>(),
+        vec![0, 1]
+    );
+    assert_eq!(scrubbed_sectors(directory), vec![0, 1]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn shrink_partially_plotted_farm() {
+    let tempdir = tempdir().unwrap();
+    let directory = tempdir.as_ref();
+    let farm = TestFarm::new(PIECES_IN_SECTOR, SECTOR_COUNT)
+        .with_plotted_sector_count(1)
+        .with_cache_percentage(CACHE_PERCENTAGE);
+    let info = farm.create(directory);
+
+    let summary = farm
+        .grow_handle(directory, info, GrowHandleOptions::default())
+        .shrink(allocated_space(2), CACHE_PERCENTAGE)
+        .await
+        .unwrap();
+    assert_eq!(summary.new_sector_count, 2);
+    // Only space that was not plotted yet is dropped
+    assert_eq!(summary.dropped_plotted_sectors, 0);
+    assert_eq!(plotted_sector_count(directory), 1);
+    assert_eq!(scrubbed_sectors(directory), vec![0]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn shrink_rejected() {
+    let tempdir = tempdir().unwrap();
+    let directory = tempdir.as_ref();
+    let farm =
+        TestFarm::new(PIECES_IN_SECTOR, SECTOR_COUNT).with_cache_percentage(CACHE_PERCENTAGE);
+    let info = farm.create(directory);
+    let plot = fs::read(directory.join(SingleDiskFarm::PLOT_FILE)).unwrap();
+    let metadata = fs::read(directory.join(SingleDiskFarm::METADATA_FILE)).unwrap();
+
+    // Read-only farm doesn't modify files
+    assert_matches!(
+        farm.grow_handle(
+            directory,
+            info.clone(),
+            GrowHandleOptions {
+                read_only: true,
+                ..GrowHandleOptions::default()
+            }
+        )
+        .shrink(allocated_space(2), CACHE_PERCENTAGE)
+        .await,
+        Err(FarmShrinkError::ReadOnly)
+    );
+
+    let grow_handle = farm.grow_handle(directory, info, GrowHandleOptions::default());
+
+    // Not even one sector fits
+    assert_matches!(
+        grow_handle
+            .shrink(fixed_space_usage(), CACHE_PERCENTAGE)
+            .await,
+        Err(FarmShrinkError::Farm(
+            SingleDiskFarmError::InsufficientAllocatedSpace { .. }
+        ))
+    );
+
+    // Farm info on disk belongs to a different farm
+    let info_bytes = fs::read(directory.join(SingleDiskFarmInfo::FILE_NAME)).unwrap();
+    SingleDiskFarmInfo::new(
+        FarmId::new(),
+        [0; 32],
+        Default::default(),
+        PIECES_IN_SECTOR,
+        allocated_space(SECTOR_COUNT),
+    )
+    .store_to(directory)
+    .unwrap();
+    assert_matches!(
+        grow_handle
+            .shrink(allocated_space(2), CACHE_PERCENTAGE)
+            .await,
+        Err(FarmShrinkError::FarmInfoMismatch { .. })
+    );
+    fs::write(directory.join(SingleDiskFarmInfo::FILE_NAME), info_bytes).unwrap();
+
+    // Farm is left intact
+    assert_eq!(
+        grow_handle
+            .inner
+            .total_sectors_count
+            .load(Ordering::Acquire),
+        SECTOR_COUNT
+    );
+    drop(grow_handle);
+    assert_eq!(
+        fs::read(directory.join(SingleDiskFarm::PLOT_FILE)).unwrap(),
+        plot
+    );
+    assert_eq!(
+        fs::read(directory.join(SingleDiskFarm::METADATA_FILE)).unwrap(),
+        metadata
+    );
+    assert_eq!(scrubbed_sectors(directory), vec![0, 1, 2, 3]);
+}
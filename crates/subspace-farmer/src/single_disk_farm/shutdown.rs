@@ -0,0 +1,139 @@
+//! Graceful shutdown of the farm and detection of unclean shutdown (crash, power failure or files
+//! that couldn't be closed in time), after which sectors that were modified, but not synced yet are
+//! checked on the next start
+
+#[cfg(test)]
+mod tests;
+
+use crate::single_disk_farm::direct_io_file::DirectIoFile;
+use crate::single_disk_farm::PlotFile;
+use async_lock::RwLock as AsyncRwLock;
+use parking_lot::Mutex;
+use std::fs::{File, OpenOptions};
+use std::io::{Seek, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use std::{fs, io, mem};
+use subspace_core_primitives::SectorIndex;
+use tracing::warn;
+
+/// Marker file that exists while farm is open for writing and is removed on clean shutdown, such
+/// that unclean shutdown is detected on the next start.
+///
+/// Marker contains indices of sectors that were modified since plot and metadata files were last
+/// synced (including sector that is being modified right now), those are the only sectors that may
+/// be damaged by unclean shutdown.
+#[derive(Debug)]
+pub(super) struct ShutdownMarker {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+impl ShutdownMarker {
+    /// Sectors recorded in marker at `path` left behind by unclean shutdown, in the order they were
+    /// modified and without duplicates, `None` if there is no marker
+    pub(super) fn modified_sectors(path: &Path) -> io::Result<Option<Vec<SectorIndex>>> {
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                return Ok(None);
+            }
+            Err(error) => {
+                return Err(error);
+            }
+        };
+
+        let mut sectors = Vec::new();
+        // Incomplete trailing index may be left by unclean shutdown during append, sector write
+        // didn't start yet in that case
+        for sector_index in bytes.chunks_exact(mem::size_of::<SectorIndex>()) {
+            let sector_index = SectorIndex::from_le_bytes(
+                sector_index
+                    .try_into()
+                    .expect("Chunk has exactly the size of sector index; qed"),
+            );
+            if !sectors.contains(&sector_index) {
+                sectors.push(sector_index);
+            }
+        }
+
+        Ok(Some(sectors))
+    }
+
+    /// Create empty marker at `path` (replacing existing one) and sync it to disk
+    pub(super) fn create(path: PathBuf) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(true)
+            .write(true)
+            .open(&path)?;
+        file.sync_all()?;
+
+        Ok(Self {
+            path,
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Record that sector at `sector_index` is about to be modified, must be called before anything
+    /// is written to plot or metadata file for this sector
+    pub(super) fn note_modified_sector(&self, sector_index: SectorIndex) -> io::Result<()> {
+        let mut file = self.file.lock();
+        // Position is always at the end of the file, it is only rewound when file is truncated
+        file.write_all(&sector_index.to_le_bytes())?;
+        file.sync_data()
+    }
+
+    /// Record that plot and metadata files were synced, such that sectors modified so far are
+    /// durable and no longer need to be checked after unclean shutdown
+    pub(super) fn note_synced(&self) -> io::Result<()> {
+        let mut file = self.file.lock();
+        file.set_len(0)?;
+        file.rewind()?;
+        file.sync_data()
+    }
+
+    /// Remove marker on clean shutdown
+    pub(super) fn remove(&self) -> io::Result<()> {
+        fs::remove_file(&self.path)
+    }
+}
+
+/// Close plot and metadata files gracefully within `timeout` (see [`DirectIoFile::close()`]),
+/// returns `true` if shutdown is clean.
+///
+/// Shutdown is clean if both files were closed in time and no sector was being modified, such that
+/// plot and metadata files are consistent with each other. Otherwise sector that was being modified
+/// may have been written partially or without its metadata.
+pub(super) fn close_files(
+    plot_file: &PlotFile,
+    metadata_file: &DirectIoFile,
+    modifying_sector_index: &AsyncRwLock<Option<SectorIndex>>,
+    timeout: Duration,
+) -> bool {
+    let deadline = Instant::now() + timeout;
+    let mut clean = true;
+
+    if let Err(error) = plot_file.close(timeout) {
+        warn!(%error, "Failed to close plot file cleanly");
+        clean = false;
+    }
+    if let Err(error) = metadata_file.close(deadline.saturating_duration_since(Instant::now())) {
+        warn!(%error, "Failed to close metadata file cleanly");
+        clean = false;
+    }
+
+    // Write lock is held while sector index is being updated, which is treated the same way as
+    // sector being modified
+    let sector_being_modified = modifying_sector_index
+        .try_read()
+        .map_or(true, |modifying_sector_index| {
+            modifying_sector_index.is_some()
+        });
+    if sector_being_modified {
+        warn!("Sector was being modified during shutdown");
+        clean = false;
+    }
+
+    clean
+}
@@ -0,0 +1,129 @@
+use crate::single_disk_farm::concatenated_files::ConcatenatedFiles;
+use crate::single_disk_farm::direct_io_file::{DirectIoFile, DISK_SECTOR_SIZE};
+use crate::single_disk_farm::shutdown::{close_files, ShutdownMarker};
+use async_lock::RwLock as AsyncRwLock;
+use rand::prelude::*;
+use std::sync::Arc;
+use std::time::Duration;
+use std::{fs, thread};
+use subspace_farmer_components::faulty_file::{Fault, FaultRule, FaultyFile, Operation};
+use subspace_farmer_components::WriteAtSync;
+use tempfile::tempdir;
+
+const SECTOR_SIZE: usize = 64 * 1024;
+const SECTOR_METADATA_SIZE: usize = 512;
+const WRITE_DELAY: Duration = Duration::from_millis(200);
+
+#[test]
+fn marker() {
+    let tempdir = tempdir().unwrap();
+    let path = tempdir.as_ref().join("shutdown_marker.bin");
+
+    assert_eq!(ShutdownMarker::modified_sectors(&path).unwrap(), None);
+    let marker = ShutdownMarker::create(path.clone()).unwrap();
+    assert_eq!(
+        ShutdownMarker::modified_sectors(&path).unwrap(),
+        Some(Vec::new())
+    );
+
+    // Replotted sectors are recorded regardless of their history size, once each
+    marker.note_modified_sector(5).unwrap();
+    marker.note_modified_sector(2).unwrap();
+    marker.note_modified_sector(5).unwrap();
+    assert_eq!(
+        ShutdownMarker::modified_sectors(&path).unwrap(),
+        Some(vec![5, 2])
+    );
+
+    marker.note_synced().unwrap();
+    marker.note_modified_sector(7).unwrap();
+    assert_eq!(
+        ShutdownMarker::modified_sectors(&path).unwrap(),
+        Some(vec![7])
+    );
+
+    // Incomplete index written during unclean shutdown is ignored
+    let mut bytes = fs::read(&path).unwrap();
+    bytes.push(1);
+    fs::write(&path, bytes).unwrap();
+    assert_eq!(
+        ShutdownMarker::modified_sectors(&path).unwrap(),
+        Some(vec![7])
+    );
+
+    // Marker left by unclean shutdown is replaced on the next start
+    let marker = ShutdownMarker::create(path.clone()).unwrap();
+    assert_eq!(
+        ShutdownMarker::modified_sectors(&path).unwrap(),
+        Some(Vec::new())
+    );
+    marker.remove().unwrap();
+    assert_eq!(ShutdownMarker::modified_sectors(&path).unwrap(), None);
+}
+
+#[test]
+fn shutdown_racing_write() {
+    // Shutdown happens before, during and after sector write
+    for close_after in [Duration::ZERO, WRITE_DELAY / 2, WRITE_DELAY * 3] {
+        let tempdir = tempdir().unwrap();
+        let plot_file_path = tempdir.as_ref().join("plot.bin");
+        let metadata_file_path = tempdir.as_ref().join("metadata.bin");
+        fs::write(&plot_file_path, vec![0u8; SECTOR_SIZE]).unwrap();
+        fs::write(&metadata_file_path, vec![0u8; DISK_SECTOR_SIZE]).unwrap();
+
+        let plot_file = ConcatenatedFiles::new(
+            Vec::new(),
+            Arc::new(DirectIoFile::open(&plot_file_path).unwrap()),
+        );
+        let metadata_file = DirectIoFile::open(&metadata_file_path).unwrap();
+        let modifying_sector_index = AsyncRwLock::new(None);
+
+        let mut sector = vec![0u8; SECTOR_SIZE];
+        thread_rng().fill(sector.as_mut_slice());
+        let mut sector_metadata = vec![0u8; SECTOR_METADATA_SIZE];
+        thread_rng().fill(sector_metadata.as_mut_slice());
+
+        // Plotting writes through its own handles, metadata write is delayed, such that shutdown
+        // can happen between sector and its metadata being written
+        let writer_plot_file = FaultyFile::new(plot_file.try_clone().unwrap());
+        let writer_metadata_file = FaultyFile::new(metadata_file.try_clone().unwrap());
+        writer_metadata_file.inject(FaultRule::new(Operation::Write, Fault::Delay(WRITE_DELAY)));
+
+        let clean = thread::scope(|scope| {
+            scope.spawn(|| {
+                modifying_sector_index.write_blocking().replace(0);
+                let result = writer_plot_file
+                    .write_all_at(&sector, 0)
+                    .and_then(|()| writer_metadata_file.write_all_at(&sector_metadata, 0));
+                // Like in plotting, sector index is left behind if writing fails
+                if result.is_ok() {
+                    modifying_sector_index.write_blocking().take();
+                }
+            });
+
+            thread::sleep(close_after);
+            close_files(
+                &plot_file,
+                &metadata_file,
+                &modifying_sector_index,
+                WRITE_DELAY * 10,
+            )
+        });
+
+        let plot = fs::read(&plot_file_path).unwrap();
+        let metadata = fs::read(&metadata_file_path).unwrap();
+        let fully_written = plot == sector && metadata[..SECTOR_METADATA_SIZE] == sector_metadata;
+        let untouched =
+            plot.iter().all(|&byte| byte == 0) && metadata.iter().all(|&byte| byte == 0);
+
+        // Either plot and metadata are consistent or shutdown must not be considered clean
+        assert!(
+            !clean || fully_written || untouched,
+            "close_after={close_after:?}"
+        );
+        if close_after > WRITE_DELAY {
+            assert!(clean);
+            assert!(fully_written);
+        }
+    }
+}
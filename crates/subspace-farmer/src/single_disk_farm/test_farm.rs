@@ -0,0 +1,280 @@
+//! Farm fixture for tests, farm files are written directly, bypassing plotting
+
+use crate::farm::{FarmId, HandlerFn};
+use crate::single_disk_farm::direct_io_file::{DirectIoFile, DISK_SECTOR_SIZE};
+use crate::single_disk_farm::grow::FarmGrowHandle;
+use crate::single_disk_farm::metadata_journal::MetadataJournal;
+use crate::single_disk_farm::metadata_layout::MetadataLayout;
+use crate::single_disk_farm::plot_cache::DiskPlotCache;
+use crate::single_disk_farm::preallocation::{PreallocationFrontier, PreallocationProgress};
+use crate::single_disk_farm::sector_rewrite::SectorRewriteOptions;
+use crate::single_disk_farm::shutdown::ShutdownMarker;
+use crate::single_disk_farm::{
+    fixed_space_usage, open_plot_file, PlotMetadataHeader, PlotSegmentInfo, SingleDiskFarm,
+    SingleDiskFarmInfo, RESERVED_PLOT_METADATA,
+};
+use async_lock::RwLock as AsyncRwLock;
+use futures::channel::mpsc;
+use rand::prelude::*;
+use std::num::{NonZeroU64, NonZeroU8};
+use std::ops::Range;
+use std::path::Path;
+use std::sync::atomic::AtomicU16;
+use std::sync::Arc;
+use std::{fs, mem};
+use subspace_core_primitives::{Blake3Hash, HistorySize, Record, SectorIndex, SegmentIndex};
+use subspace_farmer_components::sector::{sector_size, SectorMetadata, SectorMetadataChecksummed};
+use tracing::Span;
+
+/// Allocated space that fits exactly `sector_count` sectors with `pieces_in_sector` pieces each
+/// with `cache_percentage`
+pub(super) fn allocated_space(
+    pieces_in_sector: u16,
+    sector_count: SectorIndex,
+    cache_percentage: NonZeroU8,
+) -> u64 {
+    let single_sector_overhead =
+        sector_size(pieces_in_sector) as u64 + MetadataLayout::CURRENT.sector_metadata_slot_size();
+    let plottable_space =
+        u64::from(sector_count) * single_sector_overhead + DISK_SECTOR_SIZE as u64;
+    let cache_percentage = u64::from(cache_percentage.get());
+
+    fixed_space_usage() + (plottable_space.div_ceil(100 - cache_percentage) + 1) * 100
+}
+
+/// Metadata of the sector at `sector_index` as written by [`TestFarm::create()`]
+pub(super) fn sector_metadata(
+    sector_index: SectorIndex,
+    pieces_in_sector: u16,
+) -> SectorMetadataChecksummed {
+    SectorMetadataChecksummed::from(SectorMetadata {
+        sector_index,
+        pieces_in_sector,
+        s_bucket_sizes: Box::new([0; Record::NUM_S_BUCKETS]),
+        history_size: HistorySize::from(SegmentIndex::ZERO),
+    })
+}
+
+/// Options of [`TestFarm::grow_handle()`]
+pub(super) struct GrowHandleOptions {
+    /// Farm doesn't have metadata journal, like a farm opened read-only
+    pub(super) read_only: bool,
+    /// Sender of new sectors to plot, farm doesn't plot when `None`
+    pub(super) new_sectors_sender: Option<mpsc::UnboundedSender<Range<SectorIndex>>>,
+    pub(super) preallocation_frontier: PreallocationFrontier,
+    pub(super) preallocation_chunk_size: NonZeroU64,
+    pub(super) on_preallocation_progress: Option<HandlerFn<PreallocationProgress>>,
+}
+
+impl Default for GrowHandleOptions {
+    fn default() -> Self {
+        Self {
+            read_only: false,
+            new_sectors_sender: None,
+            preallocation_frontier: PreallocationFrontier::complete(),
+            preallocation_chunk_size: NonZeroU64::new(DISK_SECTOR_SIZE as u64)
+                .expect("Not zero; qed"),
+            on_preallocation_progress: None,
+        }
+    }
+}
+
+/// Farm that is created in a directory with [`Self::create()`], farm files are written directly
+#[derive(Debug, Copy, Clone)]
+pub(super) struct TestFarm {
+    pieces_in_sector: u16,
+    sector_count: SectorIndex,
+    plotted_sector_count: SectorIndex,
+    allocated_space: u64,
+}
+
+impl TestFarm {
+    /// Farm with space for `sector_count` sectors of `pieces_in_sector` pieces, all of which are
+    /// plotted, allocated space is exactly the size of all sectors
+    pub(super) fn new(pieces_in_sector: u16, sector_count: SectorIndex) -> Self {
+        Self {
+            pieces_in_sector,
+            sector_count,
+            plotted_sector_count: sector_count,
+            allocated_space: sector_size(pieces_in_sector) as u64 * u64::from(sector_count),
+        }
+    }
+
+    /// Plot only the first `plotted_sector_count` sectors, the rest are zeroed
+    pub(super) fn with_plotted_sector_count(mut self, plotted_sector_count: SectorIndex) -> Self {
+        self.plotted_sector_count = plotted_sector_count;
+        self
+    }
+
+    /// Store `allocated_space` in farm info
+    pub(super) fn with_allocated_space(mut self, allocated_space: u64) -> Self {
+        self.allocated_space = allocated_space;
+        self
+    }
+
+    /// Store allocated space that fits exactly all sectors with `cache_percentage`, see
+    /// [`allocated_space()`]
+    pub(super) fn with_cache_percentage(self, cache_percentage: NonZeroU8) -> Self {
+        self.with_allocated_space(allocated_space(
+            self.pieces_in_sector,
+            self.sector_count,
+            cache_percentage,
+        ))
+    }
+
+    /// Create farm in `directory`, plotted sectors have random contents, each followed by its
+    /// checksum.
+    ///
+    /// Both plot and metadata files are sized for all sectors.
+    pub(super) fn create(&self, directory: &Path) -> SingleDiskFarmInfo {
+        let Self {
+            pieces_in_sector,
+            sector_count,
+            plotted_sector_count,
+            allocated_space,
+        } = *self;
+        let sector_size = sector_size(pieces_in_sector);
+        let info = SingleDiskFarmInfo::new(
+            FarmId::new(),
+            [0; 32],
+            Default::default(),
+            pieces_in_sector,
+            allocated_space,
+        );
+        info.store_to(directory).unwrap();
+
+        let layout = MetadataLayout::CURRENT;
+        let mut plot = Vec::with_capacity(sector_size * usize::from(sector_count));
+        let mut metadata = layout.encode_header(&PlotMetadataHeader {
+            version: layout.version(),
+            plotted_sector_count,
+        });
+        metadata.resize(RESERVED_PLOT_METADATA as usize, 0);
+
+        for sector_index in 0..plotted_sector_count {
+            let mut sector_bytes = vec![0u8; sector_size - mem::size_of::<Blake3Hash>()];
+            thread_rng().fill(sector_bytes.as_mut_slice());
+            plot.extend_from_slice(&sector_bytes);
+            plot.extend_from_slice(blake3::hash(&sector_bytes).as_bytes());
+
+            metadata.extend_from_slice(
+                &layout.encode_sector_metadata(&sector_metadata(sector_index, pieces_in_sector)),
+            );
+        }
+        plot.resize(
+            (sector_size * usize::from(sector_count)).next_multiple_of(DISK_SECTOR_SIZE),
+            0,
+        );
+        metadata.resize(
+            (layout.metadata_size(sector_count) as usize).next_multiple_of(DISK_SECTOR_SIZE),
+            0,
+        );
+
+        fs::write(directory.join(SingleDiskFarm::PLOT_FILE), plot).unwrap();
+        fs::write(directory.join(SingleDiskFarm::METADATA_FILE), metadata).unwrap();
+
+        info
+    }
+
+    /// Handle of farm created with [`Self::create()`] in `directory` as if it was running
+    pub(super) fn grow_handle(
+        &self,
+        directory: &Path,
+        info: SingleDiskFarmInfo,
+        options: GrowHandleOptions,
+    ) -> FarmGrowHandle {
+        let GrowHandleOptions {
+            read_only,
+            new_sectors_sender,
+            preallocation_frontier,
+            preallocation_chunk_size,
+            on_preallocation_progress,
+        } = options;
+        let sector_count = self.sector_count;
+        let sector_size = sector_size(self.pieces_in_sector);
+        let plot_file = Arc::new(
+            open_plot_file(
+                &[PlotSegmentInfo {
+                    directory: directory.to_path_buf(),
+                    sector_count,
+                }],
+                sector_size as u64,
+                0,
+                |_segment_index, plot_file_path| DirectIoFile::open(plot_file_path),
+            )
+            .unwrap(),
+        );
+        let metadata_file =
+            Arc::new(DirectIoFile::open(&directory.join(SingleDiskFarm::METADATA_FILE)).unwrap());
+        let sectors_metadata = Arc::new(AsyncRwLock::new(
+            SingleDiskFarm::read_all_sectors_metadata(directory).unwrap(),
+        ));
+        let plot_cache =
+            DiskPlotCache::new(&plot_file, &sectors_metadata, sector_count, sector_size);
+
+        FarmGrowHandle::new(
+            directory.to_path_buf(),
+            info,
+            plot_file,
+            Arc::clone(&metadata_file),
+            (!read_only).then(|| Arc::new(MetadataJournal::new(metadata_file))),
+            sectors_metadata,
+            Arc::default(),
+            plot_cache,
+            Arc::new(AtomicU16::new(sector_count)),
+            new_sectors_sender,
+            preallocation_frontier,
+            preallocation_chunk_size,
+            on_preallocation_progress,
+            Span::current(),
+        )
+    }
+
+    /// Options for rewriting sectors of farm created with [`Self::create()`] in `directory`
+    pub(super) fn sector_rewrite_options<PF>(
+        &self,
+        directory: &Path,
+        plot_file: PF,
+        shutdown_marker: Arc<ShutdownMarker>,
+    ) -> SectorRewriteOptions<PF, Arc<MetadataJournal<Arc<DirectIoFile>>>> {
+        let metadata_file =
+            DirectIoFile::open(&directory.join(SingleDiskFarm::METADATA_FILE)).unwrap();
+
+        SectorRewriteOptions {
+            plot_file,
+            metadata_file: Arc::new(MetadataJournal::new(Arc::new(metadata_file))),
+            pieces_in_sector: self.pieces_in_sector,
+            sectors_metadata: Arc::new(AsyncRwLock::new(
+                SingleDiskFarm::read_all_sectors_metadata(directory).unwrap(),
+            )),
+            modifying_sector_index: Arc::default(),
+            shutdown_marker,
+            sector_modification_mutex: Arc::default(),
+            plot_file_metrics: None,
+        }
+    }
+}
+
+/// Overwrite metadata of the sector at `sector_index` in farm created with [`TestFarm::create()`]
+pub(super) fn write_sector_metadata(
+    directory: &Path,
+    sector_index: SectorIndex,
+    sector_metadata: &SectorMetadataChecksummed,
+) {
+    let layout = MetadataLayout::CURRENT;
+    let metadata_path = directory.join(SingleDiskFarm::METADATA_FILE);
+    let mut metadata = fs::read(&metadata_path).unwrap();
+    let sector_metadata_bytes = layout.encode_sector_metadata(sector_metadata);
+    let offset = layout.sector_metadata_offset(sector_index) as usize;
+    metadata[offset..][..sector_metadata_bytes.len()].copy_from_slice(&sector_metadata_bytes);
+    fs::write(&metadata_path, metadata).unwrap();
+}
+
+/// Flip a single byte in the plotted sector at `sector_index` such that it no longer matches its
+/// checksum
+pub(super) fn flip_byte(directory: &Path, pieces_in_sector: u16, sector_index: SectorIndex) {
+    let plot_path = directory.join(SingleDiskFarm::PLOT_FILE);
+    let mut plot = fs::read(&plot_path).unwrap();
+    plot[usize::from(sector_index) * sector_size(pieces_in_sector) + 123] ^= 1;
+    fs::write(&plot_path, plot).unwrap();
+}
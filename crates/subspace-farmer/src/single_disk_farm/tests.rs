@@ -0,0 +1,401 @@
+use crate::farm::FarmId;
+use crate::single_disk_farm::concatenated_files::ConcatenatedFiles;
+use crate::single_disk_farm::direct_io_file::{
+    DirectIoFile, DirectIoOptions, IoRequestLimiter, IoStats, OpenMode, RequestStats,
+    DISK_SECTOR_SIZE,
+};
+use crate::single_disk_farm::piece_cache::{DiskPieceCache, PieceCacheOffset};
+use crate::single_disk_farm::{
+    check_plot_file_size, check_plot_layout, farm_io_stats, lock_farm_directory, open_farm_file,
+    open_plot_file, reset_farm_io_stats, FarmIoStats, FileIoStats, PlotSegmentInfo, SingleDiskFarm,
+    SingleDiskFarmError,
+};
+use std::assert_matches::assert_matches;
+use std::num::NonZeroUsize;
+use std::path::Path;
+use std::sync::Arc;
+use std::{fs, thread};
+use subspace_core_primitives::{Piece, PieceIndex};
+use subspace_farmer_components::file_ext::FileExt;
+use subspace_farmer_components::{ReadAtSync, WriteAtSync};
+use tempfile::tempdir;
+
+#[test]
+fn farm_locking() {
+    let tempdir = tempdir().unwrap();
+    let directory = tempdir.as_ref();
+    let lock_file_path = directory.join(SingleDiskFarm::LOCK_FILE);
+    let plot_file_path = directory.join(SingleDiskFarm::PLOT_FILE);
+
+    let plot_file = DirectIoFile::open(&plot_file_path).unwrap();
+    plot_file.write_all_at(&[1; DISK_SECTOR_SIZE], 0).unwrap();
+    let lock = lock_farm_directory(directory).unwrap();
+
+    // Second lock of the same farm, like another farmer process would do, must fail with typed
+    // error
+    thread::scope(|scope| {
+        scope
+            .spawn(|| {
+                assert_matches!(
+                    lock_farm_directory(directory),
+                    Err(SingleDiskFarmError::FarmAlreadyInUse { path }) if path == lock_file_path
+                );
+                // Readers fail instead of waiting for farm to be closed
+                assert_eq!(
+                    SingleDiskFarm::try_lock_shared(directory)
+                        .unwrap_err()
+                        .kind(),
+                    std::io::ErrorKind::WouldBlock
+                );
+                // Farm files themselves are not locked and can be read through other handles
+                let mut buffer = [0; DISK_SECTOR_SIZE];
+                DirectIoFile::open(&plot_file_path)
+                    .unwrap()
+                    .read_exact_at(&mut buffer, 0)
+                    .unwrap();
+                assert_eq!(buffer, [1; DISK_SECTOR_SIZE]);
+            })
+            .join()
+            .unwrap();
+    });
+
+    // Lock is released when dropped
+    drop(lock);
+    drop(lock_farm_directory(directory).unwrap());
+
+    // Shared locks do not conflict with each other
+    let _first_reader = SingleDiskFarm::try_lock_shared(directory).unwrap();
+    let _second_reader = SingleDiskFarm::try_lock_shared(directory).unwrap();
+    assert_matches!(
+        lock_farm_directory(directory),
+        Err(SingleDiskFarmError::FarmAlreadyInUse { .. })
+    );
+}
+
+#[test]
+fn plot_layout() {
+    let tempdir = tempdir().unwrap();
+    let id = FarmId::new();
+    let plot_segment = |directory: &Path, sector_count| PlotSegmentInfo {
+        directory: directory.to_path_buf(),
+        sector_count,
+    };
+    let farm_directory = tempdir.as_ref().join("farm");
+    let directories = [tempdir.as_ref().join("a"), tempdir.as_ref().join("b")];
+    for directory in &directories {
+        fs::create_dir_all(directory).unwrap();
+        fs::write(directory.join(SingleDiskFarm::PLOT_FILE), b"").unwrap();
+    }
+
+    let stored_plot_layout = [
+        plot_segment(&farm_directory, 2),
+        plot_segment(&directories[0], 3),
+        plot_segment(&directories[1], 4),
+    ];
+
+    // Farm stored in farm directory only can be resized and extended
+    check_plot_layout(&id, &[], &stored_plot_layout).unwrap();
+    // The last segment can be resized and farm directory can be moved
+    check_plot_layout(
+        &id,
+        &stored_plot_layout,
+        &[
+            plot_segment(&tempdir.as_ref().join("moved"), 2),
+            plot_segment(&directories[0], 3),
+            plot_segment(&directories[1], 1),
+        ],
+    )
+    .unwrap();
+
+    // Other segments can't be resized, including farm directory
+    assert_matches!(
+        check_plot_layout(
+            &id,
+            &stored_plot_layout,
+            &[
+                plot_segment(&farm_directory, 2),
+                plot_segment(&directories[0], 5),
+                plot_segment(&directories[1], 4),
+            ],
+        ),
+        Err(SingleDiskFarmError::PlotSegmentResized {
+            directory,
+            sector_count: 3,
+            new_sector_count: 5,
+            ..
+        }) if directory == directories[0]
+    );
+    assert_matches!(
+        check_plot_layout(
+            &id,
+            &stored_plot_layout,
+            &[
+                plot_segment(&farm_directory, 1),
+                plot_segment(&directories[0], 3),
+                plot_segment(&directories[1], 4),
+            ],
+        ),
+        Err(SingleDiskFarmError::PlotSegmentResized { directory, .. })
+            if directory == farm_directory
+    );
+
+    // Segments must be the same and in the same order
+    for plot_layout in [
+        vec![plot_segment(&farm_directory, 2)],
+        vec![
+            plot_segment(&farm_directory, 2),
+            plot_segment(&directories[1], 4),
+            plot_segment(&directories[0], 3),
+        ],
+    ] {
+        assert_matches!(
+            check_plot_layout(&id, &stored_plot_layout, &plot_layout),
+            Err(SingleDiskFarmError::PlotSegmentsMismatch { expected, .. })
+                if expected == directories
+        );
+    }
+
+    // Reopening with a missing segment fails with clear error
+    fs::remove_file(directories[1].join(SingleDiskFarm::PLOT_FILE)).unwrap();
+    let result = check_plot_layout(&id, &stored_plot_layout, &stored_plot_layout);
+    assert_matches!(
+        &result,
+        Err(SingleDiskFarmError::PlotSegmentMissing { directory, .. })
+            if *directory == directories[1]
+    );
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("make sure disk is mounted"));
+}
+
+#[test]
+fn farm_file_open_mode() {
+    let tempdir = tempdir().unwrap();
+    let file_path = tempdir.as_ref().join(SingleDiskFarm::METADATA_FILE);
+    let io_request_limiter = IoRequestLimiter::new(NonZeroUsize::MIN);
+    let open = |open_mode| {
+        open_farm_file(
+            &file_path,
+            DirectIoOptions {
+                open_mode,
+                ..DirectIoOptions::default()
+            },
+            None,
+            &io_request_limiter,
+        )
+    };
+
+    // Resuming farm with missing file
+    assert_matches!(
+        open(OpenMode::OpenExisting),
+        Err(SingleDiskFarmError::FarmFileMissing { path }) if path == file_path
+    );
+    assert!(!file_path.exists());
+
+    open(OpenMode::CreateNew).unwrap();
+
+    // Creating farm over leftovers of the previous attempt
+    let result = open(OpenMode::CreateNew);
+    assert_matches!(
+        &result,
+        Err(SingleDiskFarmError::FarmFileAlreadyExists { path }) if *path == file_path
+    );
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("did you mean to resume"));
+
+    open(OpenMode::OpenExisting).unwrap();
+}
+
+#[test]
+fn read_all_sectors_metadata_missing_farm() {
+    let tempdir = tempdir().unwrap();
+    let metadata_file_path = tempdir.as_ref().join(SingleDiskFarm::METADATA_FILE);
+
+    let error = SingleDiskFarm::read_all_sectors_metadata(tempdir.as_ref()).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+    assert!(!metadata_file_path.exists());
+}
+
+#[test]
+fn plot_file_size() {
+    let tempdir = tempdir().unwrap();
+    let sector_size = 4096;
+    let plot_layout = [tempdir.as_ref().join("a"), tempdir.as_ref().join("b")]
+        .into_iter()
+        .map(|directory| {
+            fs::create_dir_all(&directory).unwrap();
+            fs::write(
+                directory.join(SingleDiskFarm::PLOT_FILE),
+                vec![0; sector_size * 2],
+            )
+            .unwrap();
+
+            PlotSegmentInfo {
+                directory,
+                sector_count: 3,
+            }
+        })
+        .collect::<Vec<_>>();
+    let open = || {
+        open_plot_file(
+            &plot_layout,
+            sector_size as u64,
+            0,
+            |_segment_index, path| DirectIoFile::open(path),
+        )
+        .unwrap()
+    };
+
+    // Plotted sectors fit into files
+    check_plot_file_size(&open(), &plot_layout, sector_size as u64, 0).unwrap();
+    check_plot_file_size(&open(), &plot_layout, sector_size as u64, 2).unwrap();
+
+    // Resuming farm with the first segment that is too small for plotted sectors
+    assert_matches!(
+        check_plot_file_size(&open(), &plot_layout, sector_size as u64, 3),
+        Err(SingleDiskFarmError::FarmFileSizeMismatch {
+            path,
+            plotted_sector_count: 3,
+            expected_size,
+            actual_size,
+        }) if path == plot_layout[0].directory.join(SingleDiskFarm::PLOT_FILE)
+            && expected_size == sector_size as u64 * 3
+            && actual_size == sector_size as u64 * 2
+    );
+
+    // Sectors 3 and 4 are plotted in the second segment
+    fs::write(
+        plot_layout[0].directory.join(SingleDiskFarm::PLOT_FILE),
+        vec![0; sector_size * 3],
+    )
+    .unwrap();
+    check_plot_file_size(&open(), &plot_layout, sector_size as u64, 5).unwrap();
+    assert_matches!(
+        check_plot_file_size(&open(), &plot_layout, sector_size as u64, 6),
+        Err(SingleDiskFarmError::FarmFileSizeMismatch { path, .. })
+            if path == plot_layout[1].directory.join(SingleDiskFarm::PLOT_FILE)
+    );
+}
+
+#[test]
+fn farm_io_stats_aggregation() {
+    let tempdir = tempdir().unwrap();
+    let directory = tempdir.as_ref();
+
+    let plot_file = ConcatenatedFiles::new(
+        vec![(
+            Arc::new(DirectIoFile::open(&directory.join("plot-0.bin")).unwrap()),
+            DISK_SECTOR_SIZE as u64,
+        )],
+        Arc::new(DirectIoFile::open(&directory.join("plot-1.bin")).unwrap()),
+    );
+    let metadata_file = DirectIoFile::open(&directory.join("metadata.bin")).unwrap();
+    let piece_cache =
+        DiskPieceCache::open(directory, 2, DirectIoOptions::default(), None, None).unwrap();
+
+    // Whatever was done on open is not part of the workload below
+    reset_farm_io_stats(&plot_file, &metadata_file, &piece_cache);
+    assert_eq!(
+        farm_io_stats(&plot_file, &metadata_file, &piece_cache),
+        FarmIoStats::default()
+    );
+
+    // Write crosses segment boundary and results in one request to each segment
+    let plot_bytes = vec![1; DISK_SECTOR_SIZE * 2];
+    WriteAtSync::write_all_at(&plot_file, &plot_bytes, 0).unwrap();
+    let mut buffer = vec![0; DISK_SECTOR_SIZE * 2];
+    ReadAtSync::read_at(&plot_file, &mut buffer, 0).unwrap();
+    assert_eq!(buffer, plot_bytes);
+
+    metadata_file
+        .write_all_at(&[2; DISK_SECTOR_SIZE], 0)
+        .unwrap();
+    metadata_file
+        .read_exact_at(&mut buffer[..DISK_SECTOR_SIZE], 0)
+        .unwrap();
+
+    let piece = Piece::default();
+    piece_cache
+        .write_piece(PieceCacheOffset(1), PieceIndex::ONE, &piece)
+        .unwrap();
+    assert!(piece_cache
+        .read_piece(PieceCacheOffset(1))
+        .unwrap()
+        .is_some());
+
+    let io_stats = farm_io_stats(&plot_file, &metadata_file, &piece_cache);
+    assert_eq!(
+        io_stats,
+        FarmIoStats {
+            plot: FileIoStats {
+                requests: plot_file.request_stats(),
+                write_alignment: plot_file.io_stats(),
+            },
+            metadata: FileIoStats::new(&metadata_file),
+            piece_cache: FileIoStats {
+                requests: piece_cache.request_stats(),
+                write_alignment: piece_cache.io_stats(),
+            },
+        }
+    );
+    assert_eq!(
+        io_stats.total(),
+        io_stats.plot + io_stats.metadata + io_stats.piece_cache
+    );
+
+    let RequestStats {
+        reads,
+        bytes_read,
+        read_errors,
+        writes,
+        bytes_written,
+        write_errors,
+        ..
+    } = io_stats.plot.requests;
+    assert_eq!(
+        (reads, bytes_read, read_errors),
+        (2, plot_bytes.len() as u64, 0)
+    );
+    assert_eq!(
+        (writes, bytes_written, write_errors),
+        (2, plot_bytes.len() as u64, 0)
+    );
+    assert_eq!(
+        io_stats.plot.write_alignment,
+        IoStats {
+            aligned_writes: 2,
+            read_modify_writes: 0,
+            amplified_bytes: 0,
+        }
+    );
+
+    let metadata_requests = io_stats.metadata.requests;
+    assert_eq!(metadata_requests.reads, 1);
+    assert_eq!(metadata_requests.bytes_read, DISK_SECTOR_SIZE as u64);
+    assert_eq!(metadata_requests.writes, 1);
+    assert_eq!(metadata_requests.bytes_written, DISK_SECTOR_SIZE as u64);
+
+    let piece_cache_requests = io_stats.piece_cache.requests;
+    assert!(piece_cache_requests.writes > 0);
+    assert!(piece_cache_requests.bytes_written >= DiskPieceCache::element_size() as u64);
+    assert!(piece_cache_requests.reads > 0);
+
+    let total_requests = io_stats.total().requests;
+    assert_eq!(
+        total_requests.bytes_written,
+        plot_bytes.len() as u64 + DISK_SECTOR_SIZE as u64 + piece_cache_requests.bytes_written
+    );
+    assert_eq!(
+        total_requests.average_write_latency(),
+        total_requests.write_time / total_requests.writes as u32
+    );
+
+    reset_farm_io_stats(&plot_file, &metadata_file, &piece_cache);
+    assert_eq!(
+        farm_io_stats(&plot_file, &metadata_file, &piece_cache),
+        FarmIoStats::default()
+    );
+}
@@ -49,9 +49,10 @@ pub(crate) fn extract_fraud_proofs(
     extrinsics
         .into_iter()
         .filter_map(|uxt| match uxt.function {
-            RuntimeCall::Domains(pallet_domains::Call::submit_fraud_proof { fraud_proof })
-                if fraud_proof.domain_id() == domain_id
-                    && successful_fraud_proofs.contains(&fraud_proof.hash()) =>
+            RuntimeCall::Domains(pallet_domains::Call::submit_fraud_proof {
+                fraud_proof, ..
+            }) if fraud_proof.domain_id() == domain_id
+                && successful_fraud_proofs.contains(&fraud_proof.hash()) =>
             {
                 Some(*fraud_proof)
             }
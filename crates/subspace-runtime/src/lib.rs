@@ -36,7 +36,7 @@ use core::mem;
 use core::num::NonZeroU64;
 use domain_runtime_primitives::opaque::Header as DomainHeader;
 use domain_runtime_primitives::{
-    AccountIdConverter, BlockNumber as DomainNumber, Hash as DomainHash,
+    AccountIdConverter, BlockNumber as DomainNumber, EthereumAccountId, Hash as DomainHash,
 };
 use frame_support::inherent::ProvideInherent;
 use frame_support::traits::{
@@ -60,16 +60,19 @@ use sp_consensus_subspace::{
 };
 use sp_core::crypto::{ByteArray, KeyTypeId};
 use sp_core::{OpaqueMetadata, H256};
-use sp_domains::bundle_producer_election::BundleProducerElectionParams;
+use sp_domains::bundle_producer_election::{BundleProducerElectionParams, EpochElectionParams};
+use sp_domains::inherents::RejectedBundleRecord;
 use sp_domains::{
-    ChannelId, DomainAllowlistUpdates, DomainId, DomainInstanceData, DomainsHoldIdentifier,
-    ExecutionReceiptFor, MessengerHoldIdentifier, OpaqueBundle, OperatorId, OperatorPublicKey,
-    StakingHoldIdentifier,
+    BundleDigest, ChannelId, DomainAllowlistUpdates, DomainFees, DomainId, DomainInstanceData,
+    DomainStateRoot, DomainsHoldIdentifier, EpochIndex, ExecutionReceiptFor,
+    MessengerHoldIdentifier, OpaqueBundle, OperatorId, OperatorMetadata, OperatorPublicKey,
+    PermissionedActionAllowedBy, RuntimeId, RuntimeSummary, SlashEvidence, StakingHoldIdentifier,
 };
 use sp_domains_fraud_proof::fraud_proof::FraudProof;
 use sp_messenger::endpoint::{Endpoint, EndpointHandler as EndpointHandlerT, EndpointId};
 use sp_messenger::messages::{
-    BlockMessagesWithStorageKey, ChainId, CrossDomainMessage, MessageId, MessageKey,
+    BlockMessagesWithStorageKey, ChainId, ConsensusChainMmrLeafProof, CrossDomainMessage,
+    MessageId, MessageKey,
 };
 use sp_messenger_host_functions::{get_storage_key, StorageKeyRequest};
 use sp_mmr_primitives::{EncodableOpaqueLeaf, Proof};
@@ -79,6 +82,7 @@ use sp_runtime::traits::{
 use sp_runtime::transaction_validity::{TransactionSource, TransactionValidity};
 use sp_runtime::{
     create_runtime_str, generic, AccountId32, ApplyExtrinsicResult, BoundedVec, Perbill,
+    Perquintill,
 };
 use sp_std::collections::btree_map::BTreeMap;
 use sp_std::marker::PhantomData;
@@ -118,7 +122,7 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
     spec_version: 4,
     impl_version: 0,
     apis: RUNTIME_API_VERSIONS,
-    transaction_version: 0,
+    transaction_version: 1,
     state_version: 0,
     extrinsic_state_version: 0,
 };
@@ -399,6 +403,10 @@ impl pallet_domains::HoldIdentifier<Runtime> for HoldIdentifier {
     fn storage_fund_withdrawal(operator_id: OperatorId) -> Self {
         Self::Domains(DomainsHoldIdentifier::StorageFund(operator_id))
     }
+
+    fn operator_metadata_deposit(operator_id: OperatorId) -> Self {
+        Self::Domains(DomainsHoldIdentifier::OperatorMetadata(operator_id))
+    }
 }
 
 impl pallet_messenger::HoldIdentifier<Runtime> for HoldIdentifier {
@@ -507,6 +515,52 @@ impl sp_messenger::MmrProofVerifier<mmr::Hash, Hash> for MmrProofVerifier {
     }
 }
 
+impl sp_domains_fraud_proof::ConsensusBlockMmrProofVerifier<BlockNumber, Hash, mmr::Hash>
+    for MmrProofVerifier
+{
+    fn verify_proof_and_extract_consensus_block(
+        mmr_leaf_proof: ConsensusChainMmrLeafProof<Hash, mmr::Hash>,
+    ) -> Option<(BlockNumber, Hash)> {
+        let ConsensusChainMmrLeafProof {
+            opaque_mmr_leaf,
+            proof,
+            ..
+        } = mmr_leaf_proof;
+        let leaf: mmr::Leaf = opaque_mmr_leaf.into_opaque_leaf().try_decode()?;
+        let consensus_block = (leaf.block_number(), leaf.block_hash());
+        Mmr::verify_leaves(vec![leaf], proof).ok()?;
+        Some(consensus_block)
+    }
+
+    #[cfg(feature = "runtime-benchmarks")]
+    fn consensus_block_mmr_proof_for_benchmarks(
+    ) -> Option<(BlockNumber, ConsensusChainMmrLeafProof<Hash, mmr::Hash>)> {
+        use frame_support::traits::Hooks;
+        use sp_mmr_primitives::LeafDataProvider;
+
+        // Append the leaf of the parent block to the empty MMR, such that the proof of the only
+        // leaf can be constructed without the MMR nodes, which are only kept offchain
+        if pallet_mmr::NumberOfLeaves::<Runtime>::get() != 0 {
+            return None;
+        }
+        let leaf = <Runtime as pallet_mmr::Config>::LeafData::leaf_data();
+        <Mmr as Hooks<BlockNumber>>::on_initialize(System::block_number());
+
+        Some((
+            leaf.block_number(),
+            ConsensusChainMmrLeafProof {
+                consensus_block_hash: System::parent_hash(),
+                opaque_mmr_leaf: EncodableOpaqueLeaf::from_leaf(&leaf),
+                proof: Proof {
+                    leaf_indices: vec![0],
+                    leaf_count: 1,
+                    items: vec![],
+                },
+            },
+        ))
+    }
+}
+
 pub struct StorageKeys;
 
 impl sp_messenger::StorageKeys for StorageKeys {
@@ -591,20 +645,31 @@ parameter_types! {
     pub const DomainTxRangeAdjustmentInterval: u64 = TX_RANGE_ADJUSTMENT_INTERVAL_BLOCKS;
     /// Runtime upgrade is delayed for 1 day at 6 sec block time.
     pub const DomainRuntimeUpgradeDelay: BlockNumber = 14_400;
+    /// Governance may shorten a runtime upgrade delay down to 1 hour at 6 sec block time.
+    pub const MinRuntimeUpgradeDelay: BlockNumber = 600;
+    /// Governance may extend a runtime upgrade delay up to 1 week at 6 sec block time.
+    pub const MaxRuntimeUpgradeDelay: BlockNumber = 100_800;
     /// Minimum operator stake to become an operator.
     // TODO: this value should be properly updated before mainnet
     pub const MinOperatorStake: Balance = 100 * SSC;
     /// Minimum nominator stake to nominate and operator.
     // TODO: this value should be properly updated before mainnet
     pub const MinNominatorStake: Balance = SSC;
+    pub const NominationKeepAlive: Balance = SSC;
     /// Use the consensus chain's `Normal` extrinsics block size limit as the domain block size limit
     pub MaxDomainBlockSize: u32 = NORMAL_DISPATCH_RATIO * MAX_BLOCK_LENGTH;
     /// Use the consensus chain's `Normal` extrinsics block weight limit as the domain block weight limit
     pub MaxDomainBlockWeight: Weight = NORMAL_DISPATCH_RATIO * BLOCK_WEIGHT_FOR_2_SEC;
     pub const MaxBundlesPerBlock: u32 = 10;
+    pub const MaxBundleExtrinsics: u32 = 10_000;
+    /// The bundle limit of the domain must be able to fit at least a few average extrinsics
+    pub const MinBundleSize: u32 = 1024;
+    pub MinBundleWeight: Weight = Weight::from_parts(WEIGHT_REF_TIME_PER_SECOND / 100, 0);
     pub const DomainInstantiationDeposit: Balance = 100 * SSC;
     pub const MaxDomainNameLength: u32 = 32;
+    pub const MaxDomainsPerOwner: u32 = 16;
     pub const BlockTreePruningDepth: u32 = 14_400;
+    pub const MaxReceiptGap: u32 = 100;
     pub const StakeWithdrawalLockingPeriod: DomainNumber = 14_400;
     // TODO: revisit these. For now epoch every 10 mins for a 6 second block and only 100 number of staking
     // operations allowed within each epoch.
@@ -612,9 +677,22 @@ parameter_types! {
     pub TreasuryAccount: AccountId = PalletId(*b"treasury").into_account_truncating();
     pub const MaxPendingStakingOperation: u32 = 512;
     pub const MaxNominators: u32 = 256;
+    pub const OperatorMetadataDeposit: Balance = SSC;
+    pub const MaxSlashEvidence: u32 = 16;
+    // 14 days with 6 second blocks
+    pub const SlashEvidenceRetentionPeriod: BlockNumber = 201_600;
+    pub const MaxRejectedBundleRecords: u32 = 16;
+    // Give governance a day (with 10 mins epoch) to cancel the slash caused by a bad fraud proof.
+    pub const SlashDeferDuration: EpochIndex = 144;
+    pub const DomainFeeHistoryDepth: u32 = 1_008;
+    pub const OperatorEpochSummaryHistoryDepth: u32 = 1_008;
+    pub const DomainStallPeriod: BlockNumber = 14_400;
+    // 30 days with 6 second blocks
+    pub const DomainFreezePeriod: BlockNumber = 432_000;
     pub const DomainsPalletId: PalletId = PalletId(*b"domains_");
     pub const MaxInitialDomainAccounts: u32 = 10;
     pub const MinInitialDomainAccountBalance: Balance = SSC;
+    pub const MaxContractCreationAllowListLength: u32 = 100;
     pub const BundleLongevity: u32 = 5;
 }
 
@@ -657,8 +735,12 @@ impl pallet_domains::Config for Runtime {
     type RuntimeEvent = RuntimeEvent;
     type DomainHash = DomainHash;
     type DomainHeader = sp_runtime::generic::Header<DomainNumber, BlakeTwo256>;
+    type MmrHash = mmr::Hash;
+    type MmrProofVerifier = MmrProofVerifier;
     type ConfirmationDepthK = ConfirmationDepthK;
     type DomainRuntimeUpgradeDelay = DomainRuntimeUpgradeDelay;
+    type MinRuntimeUpgradeDelay = MinRuntimeUpgradeDelay;
+    type MaxRuntimeUpgradeDelay = MaxRuntimeUpgradeDelay;
     type Currency = Balances;
     type HoldIdentifier = HoldIdentifier;
     type WeightInfo = pallet_domains::weights::SubstrateWeight<Runtime>;
@@ -666,19 +748,34 @@ impl pallet_domains::Config for Runtime {
     type DomainTxRangeAdjustmentInterval = DomainTxRangeAdjustmentInterval;
     type MinOperatorStake = MinOperatorStake;
     type MinNominatorStake = MinNominatorStake;
+    type NominationKeepAlive = NominationKeepAlive;
     type MaxDomainBlockSize = MaxDomainBlockSize;
     type MaxDomainBlockWeight = MaxDomainBlockWeight;
     type MaxBundlesPerBlock = MaxBundlesPerBlock;
+    type MaxBundleExtrinsics = MaxBundleExtrinsics;
+    type MinBundleSize = MinBundleSize;
+    type MinBundleWeight = MinBundleWeight;
     type DomainInstantiationDeposit = DomainInstantiationDeposit;
     type MaxDomainNameLength = MaxDomainNameLength;
+    type MaxDomainsPerOwner = MaxDomainsPerOwner;
     type Share = Balance;
     type BlockTreePruningDepth = BlockTreePruningDepth;
+    type MaxReceiptGap = MaxReceiptGap;
     type ConsensusSlotProbability = SlotProbability;
     type StakeWithdrawalLockingPeriod = StakeWithdrawalLockingPeriod;
     type StakeEpochDuration = StakeEpochDuration;
     type TreasuryAccount = TreasuryAccount;
     type MaxPendingStakingOperation = MaxPendingStakingOperation;
     type MaxNominators = MaxNominators;
+    type OperatorMetadataDeposit = OperatorMetadataDeposit;
+    type MaxSlashEvidence = MaxSlashEvidence;
+    type SlashEvidenceRetentionPeriod = SlashEvidenceRetentionPeriod;
+    type MaxRejectedBundleRecords = MaxRejectedBundleRecords;
+    type SlashDeferDuration = SlashDeferDuration;
+    type DomainFeeHistoryDepth = DomainFeeHistoryDepth;
+    type OperatorEpochSummaryHistoryDepth = OperatorEpochSummaryHistoryDepth;
+    type DomainStallPeriod = DomainStallPeriod;
+    type DomainFreezePeriod = DomainFreezePeriod;
     type Randomness = Subspace;
     type PalletId = DomainsPalletId;
     type StorageFee = TransactionFees;
@@ -687,7 +784,9 @@ impl pallet_domains::Config for Runtime {
     type DomainsTransfersTracker = Transporter;
     type MaxInitialDomainAccounts = MaxInitialDomainAccounts;
     type MinInitialDomainAccountBalance = MinInitialDomainAccountBalance;
+    type MaxContractCreationAllowListLength = MaxContractCreationAllowListLength;
     type DomainBundleSubmitted = Messenger;
+    type BundleSignatureVerifier = pallet_domains::DefaultBundleSignatureVerifier;
 }
 
 parameter_types! {
@@ -885,7 +984,15 @@ pub type Executive = frame_executive::Executive<
     frame_system::ChainContext<Runtime>,
     Runtime,
     AllPalletsWithSystem,
-    InitializeDynamicIssuance,
+    (
+        InitializeDynamicIssuance,
+        pallet_domains::migrations::MigrateToV1<Runtime>,
+        pallet_domains::migrations::MigrateToV2<Runtime>,
+        pallet_domains::migrations::MigrateToV3<Runtime>,
+        pallet_domains::migrations::MigrateToV4<Runtime>,
+        pallet_domains::migrations::MigrateToV5<Runtime>,
+        pallet_domains::migrations::MigrateToV6<Runtime>,
+    ),
 >;
 
 fn extract_segment_headers(ext: &UncheckedExtrinsic) -> Option<Vec<SegmentHeader>> {
@@ -1183,6 +1290,10 @@ impl_runtime_apis! {
             Domains::genesis_state_root(domain_id)
         }
 
+        fn genesis_execution_receipt(domain_id: DomainId) -> Option<ExecutionReceiptFor<DomainHeader, Block, Balance>> {
+            Domains::genesis_execution_receipt(domain_id)
+        }
+
         fn head_receipt_number(domain_id: DomainId) -> DomainNumber {
             Domains::head_receipt_number(domain_id)
         }
@@ -1199,6 +1310,10 @@ impl_runtime_apis! {
             Domains::domain_bundle_limit(domain_id).ok().flatten()
         }
 
+        fn effective_domain_params(domain_id: DomainId) -> Option<sp_domains::EffectiveDomainParams<DomainNumber>> {
+            Domains::effective_domain_params(domain_id)
+        }
+
         fn non_empty_er_exists(domain_id: DomainId) -> bool {
             Domains::non_empty_er_exists(domain_id)
         }
@@ -1244,6 +1359,70 @@ impl_runtime_apis! {
         fn storage_fund_account_balance(operator_id: OperatorId) -> Balance {
             Domains::storage_fund_account_balance(operator_id)
         }
+
+        fn storage_fund_account(operator_id: OperatorId) -> AccountId {
+            Domains::storage_fund_account(operator_id)
+        }
+
+        fn slash_evidence(operator_id: OperatorId) -> Vec<SlashEvidence<DomainNumber, BlockNumber, DomainHash>> {
+            Domains::slash_evidence(operator_id)
+        }
+
+        fn evm_domain_contract_creation_allowed_by(domain_id: DomainId) -> Option<PermissionedActionAllowedBy<EthereumAccountId>> {
+            Domains::evm_domain_contract_creation_allowed_by(domain_id)
+        }
+
+        fn evm_domain_contract_creation_allow_list_update(domain_id: DomainId) -> Option<PermissionedActionAllowedBy<EthereumAccountId>> {
+            Domains::evm_domain_contract_creation_allow_list_update(domain_id)
+        }
+
+        fn domain_state_root(domain_id: DomainId, domain_block_number: DomainNumber, domain_block_hash: DomainHash) -> Option<DomainStateRoot> {
+            Domains::domain_state_root(domain_id, domain_block_number, domain_block_hash)
+        }
+
+        fn execution_inbox_digests(domain_id: DomainId, domain_block_number: DomainNumber, consensus_block_number: BlockNumber) -> Vec<BundleDigest<DomainHash>> {
+            Domains::execution_inbox_digests(domain_id, domain_block_number, consensus_block_number)
+        }
+
+        fn current_inbox(domain_id: DomainId) -> Vec<BundleDigest<DomainHash>> {
+            Domains::current_inbox(domain_id)
+        }
+
+        fn revert_depth(domain_id: DomainId) -> DomainNumber {
+            Domains::revert_depth(domain_id)
+        }
+
+        fn rejected_bundles(operator_id: OperatorId) -> (u32, Vec<RejectedBundleRecord<BlockNumber>>) {
+            Domains::rejected_bundles(operator_id)
+        }
+
+        fn domain_fee_totals(domain_id: DomainId) -> DomainFees<Balance> {
+            Domains::domain_fee_totals(domain_id)
+        }
+
+        fn domain_epoch_fees(domain_id: DomainId, epoch_index: EpochIndex) -> Option<DomainFees<Balance>> {
+            Domains::domain_epoch_fees(domain_id, epoch_index)
+        }
+
+        fn runtime_summary(runtime_id: RuntimeId) -> Option<RuntimeSummary<BlockNumber, Hash>> {
+            Domains::runtime_summary(runtime_id)
+        }
+
+        fn runtime_summaries() -> Vec<RuntimeSummary<BlockNumber, Hash>> {
+            Domains::runtime_summaries()
+        }
+
+        fn operator_metadata(operator_id: OperatorId) -> Option<OperatorMetadata> {
+            Domains::operator_metadata(operator_id)
+        }
+
+        fn domains_owned_by(owner: AccountId) -> Vec<DomainId> {
+            Domains::domains_owned_by(owner)
+        }
+
+        fn nominator_tax_paid(operator_id: OperatorId, nominator: AccountId) -> Balance {
+            Domains::nominator_tax_paid(operator_id, nominator)
+        }
     }
 
     impl sp_domains::BundleProducerElectionApi<Block, Balance> for Runtime {
@@ -1254,6 +1433,18 @@ impl_runtime_apis! {
         fn operator(operator_id: OperatorId) -> Option<(OperatorPublicKey, Balance)> {
             Domains::operator(operator_id)
         }
+
+        fn operator_election_threshold(domain_id: DomainId, operator_id: OperatorId) -> Option<U256> {
+            Domains::operator_election_threshold(domain_id, operator_id)
+        }
+
+        fn operator_expected_bundles_per_slot(domain_id: DomainId, operator_id: OperatorId) -> Option<Perquintill> {
+            Domains::operator_expected_bundles_per_slot(domain_id, operator_id)
+        }
+
+        fn bundle_producer_election_params_at(domain_id: DomainId, epoch_index: EpochIndex) -> Option<EpochElectionParams<Balance>> {
+            Domains::bundle_producer_election_params_at(domain_id, epoch_index)
+        }
     }
 
     impl sp_session::SessionKeys<Block> for Runtime {
@@ -1343,7 +1534,14 @@ impl_runtime_apis! {
 
     impl sp_domains_fraud_proof::FraudProofApi<Block, DomainHeader> for Runtime {
         fn submit_fraud_proof_unsigned(fraud_proof: FraudProof<NumberFor<Block>, <Block as BlockT>::Hash, DomainHeader>) {
-            Domains::submit_fraud_proof_unsigned(fraud_proof)
+            Domains::submit_fraud_proof_unsigned(fraud_proof, None)
+        }
+
+        fn submit_fraud_proof_with_consensus_block_proof_unsigned(
+            fraud_proof: FraudProof<NumberFor<Block>, <Block as BlockT>::Hash, DomainHeader>,
+            consensus_block_proof: ConsensusChainMmrLeafProof<<Block as BlockT>::Hash, mmr::Hash>,
+        ) {
+            Domains::submit_fraud_proof_unsigned(fraud_proof, Some(consensus_block_proof))
         }
 
         fn extract_fraud_proofs(
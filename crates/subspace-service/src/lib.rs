@@ -1033,10 +1033,12 @@ where
         let create_inherent_data_providers = {
             let client = client.clone();
             let subspace_link = subspace_link.clone();
+            let rejected_bundles_tracker = transaction_pool.rejected_bundles_tracker().clone();
 
             move |parent_hash, ()| {
                 let client = client.clone();
                 let subspace_link = subspace_link.clone();
+                let rejected_bundles_tracker = rejected_bundles_tracker.clone();
 
                 async move {
                     let timestamp = sp_timestamp::InherentDataProvider::from_system_time();
@@ -1052,7 +1054,12 @@ where
                             subspace_link.segment_headers_for_block(parent_block_number + 1),
                         );
 
-                    Ok((timestamp, subspace_inherents))
+                    // The rejected bundles are only taken when the inherent data is created after
+                    // the slot is claimed
+                    let domains_inherents =
+                        rejected_bundles_tracker.inherent_data_provider(parent_hash);
+
+                    Ok((timestamp, subspace_inherents, domains_inherents))
                 }
             }
         };
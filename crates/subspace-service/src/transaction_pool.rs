@@ -1,7 +1,10 @@
 use async_trait::async_trait;
 use futures::future::{Future, FutureExt, Ready};
+use futures::StreamExt;
+use parity_scale_codec::Encode;
+use parking_lot::Mutex;
 use sc_client_api::blockchain::HeaderBackend;
-use sc_client_api::{AuxStore, BlockBackend, ExecutorProvider, UsageProvider};
+use sc_client_api::{AuxStore, BlockBackend, BlockchainEvents, ExecutorProvider, UsageProvider};
 use sc_service::{TaskManager, TransactionPoolOptions};
 use sc_transaction_pool::error::{Error as TxPoolError, Result as TxPoolResult};
 use sc_transaction_pool::{
@@ -14,19 +17,24 @@ use sc_transaction_pool_api::{
 };
 use sp_api::{ApiExt, ProvideRuntimeApi};
 use sp_blockchain::{HeaderMetadata, TreeRoute};
+use sp_consensus::BlockOrigin;
 use sp_consensus_slots::Slot;
 use sp_consensus_subspace::{ChainConstants, FarmerPublicKey, SubspaceApi};
 use sp_core::traits::SpawnEssentialNamed;
+use sp_domains::inherents::{RejectedBundle, RejectedBundles, MAX_REJECTED_BUNDLES_PER_BLOCK};
 use sp_domains::DomainsApi;
 use sp_domains_fraud_proof::bundle_equivocation::check_equivocation;
 use sp_domains_fraud_proof::fraud_proof::FraudProof;
 use sp_domains_fraud_proof::{FraudProofApi, InvalidTransactionCode};
+use sp_inherents::{InherentData, InherentIdentifier};
 use sp_runtime::generic::BlockId;
 use sp_runtime::traits::{Block as BlockT, BlockIdTo, Header as HeaderT, NumberFor};
-use sp_runtime::transaction_validity::{TransactionValidity, TransactionValidityError};
+use sp_runtime::transaction_validity::{
+    InvalidTransaction, TransactionValidity, TransactionValidityError,
+};
 use sp_runtime::SaturatedConversion;
 use sp_transaction_pool::runtime_api::TaggedTransactionQueue;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::marker::PhantomData;
 use std::pin::Pin;
 use std::sync::atomic::{AtomicU32, Ordering};
@@ -58,6 +66,129 @@ type PolledIterator<PoolApi> = Pin<Box<dyn Future<Output = ReadyIteratorFor<Pool
 
 pub type BlockExtrinsicOf<Block> = <Block as BlockT>::Extrinsic;
 
+/// The maximum number of rejected bundles kept before they are reported, the oldest are dropped
+/// when exceeded.
+const MAX_PENDING_REJECTED_BUNDLES: usize = 256;
+
+struct RejectedBundlesTrackerInner<Hash> {
+    /// Rejected bundles that are not reported yet, oldest first.
+    pending: VecDeque<RejectedBundle>,
+    /// The rejected bundles taken for the block built on top of the given parent block, they are
+    /// put back if this node does not import a block on top of the parent.
+    in_flight: Option<(Hash, Vec<RejectedBundle>)>,
+}
+
+/// Bundles dropped by the block author while building blocks, they are reported to the runtime
+/// through the domains inherent of the following blocks built by this node.
+#[derive(Clone)]
+pub struct RejectedBundlesTracker<Block: BlockT> {
+    inner: Arc<Mutex<RejectedBundlesTrackerInner<Block::Hash>>>,
+}
+
+impl<Block: BlockT> Default for RejectedBundlesTracker<Block> {
+    fn default() -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(RejectedBundlesTrackerInner {
+                pending: VecDeque::new(),
+                in_flight: None,
+            })),
+        }
+    }
+}
+
+impl<Block: BlockT> RejectedBundlesTracker<Block> {
+    fn note_rejected_bundle(&self, rejected_bundle: RejectedBundle) {
+        let pending = &mut self.inner.lock().pending;
+        pending.push_back(rejected_bundle);
+        if pending.len() > MAX_PENDING_REJECTED_BUNDLES {
+            let to_drop = pending.len() - MAX_PENDING_REJECTED_BUNDLES;
+            pending.drain(..to_drop);
+        }
+    }
+
+    /// Returns the parent of the block that is being built by this node.
+    fn building_on(&self) -> Option<Block::Hash> {
+        self.inner
+            .lock()
+            .in_flight
+            .as_ref()
+            .map(|(parent_hash, _)| *parent_hash)
+    }
+
+    /// Takes at most `MAX_REJECTED_BUNDLES_PER_BLOCK` of the oldest rejected bundles for the
+    /// block built on top of `parent_hash`, the rest are kept for the following blocks.
+    ///
+    /// The rejected bundles taken for a previous block that is never imported are put back first.
+    fn take_for_block(&self, parent_hash: Block::Hash) -> RejectedBundles {
+        let mut inner = self.inner.lock();
+        if let Some((_, rejected_bundles)) = inner.in_flight.take() {
+            for rejected_bundle in rejected_bundles.into_iter().rev() {
+                inner.pending.push_front(rejected_bundle);
+            }
+        }
+
+        let count = inner
+            .pending
+            .len()
+            .min(MAX_REJECTED_BUNDLES_PER_BLOCK as usize);
+        let rejected_bundles: Vec<_> = inner.pending.drain(..count).collect();
+        inner.in_flight = Some((parent_hash, rejected_bundles.clone()));
+
+        RejectedBundles::truncate_from(rejected_bundles)
+    }
+
+    /// Notes that this node imported a block on top of `parent_hash`, the rejected bundles taken
+    /// for it are reported.
+    fn on_own_block_imported(&self, parent_hash: Block::Hash) {
+        let mut inner = self.inner.lock();
+        if matches!(inner.in_flight, Some((hash, _)) if hash == parent_hash) {
+            inner.in_flight = None;
+        }
+    }
+
+    /// Returns the domains inherent data provider of the block built on top of `parent_hash`.
+    pub fn inherent_data_provider(
+        &self,
+        parent_hash: Block::Hash,
+    ) -> RejectedBundlesInherentDataProvider<Block> {
+        RejectedBundlesInherentDataProvider {
+            tracker: self.clone(),
+            parent_hash,
+        }
+    }
+}
+
+/// Provides the rejected bundles as the domains inherent data, the rejected bundles are only
+/// taken from the tracker when the inherent data is created, i.e. after the slot is claimed.
+pub struct RejectedBundlesInherentDataProvider<Block: BlockT> {
+    tracker: RejectedBundlesTracker<Block>,
+    parent_hash: Block::Hash,
+}
+
+#[async_trait]
+impl<Block: BlockT> sp_inherents::InherentDataProvider
+    for RejectedBundlesInherentDataProvider<Block>
+{
+    async fn provide_inherent_data(
+        &self,
+        inherent_data: &mut InherentData,
+    ) -> Result<(), sp_inherents::Error> {
+        sp_domains::inherents::InherentDataProvider::new(
+            self.tracker.take_for_block(self.parent_hash),
+        )
+        .provide_inherent_data(inherent_data)
+        .await
+    }
+
+    async fn try_handle_error(
+        &self,
+        _identifier: &InherentIdentifier,
+        _error: &[u8],
+    ) -> Option<Result<(), sp_inherents::Error>> {
+        None
+    }
+}
+
 #[derive(Clone)]
 pub struct FullChainApiWrapper<Client, Block: BlockT, DomainHeader: HeaderT> {
     inner: Arc<FullChainApi<Client, Block>>,
@@ -263,6 +394,8 @@ where
     PoolApi: ChainApi<Block = Block>,
 {
     inner: BasicPool<PoolApi, Block>,
+    removed_invalid_sink: UnboundedSender<(Block::Hash, BlockExtrinsicOf<Block>)>,
+    rejected_bundles_tracker: RejectedBundlesTracker<Block>,
 }
 
 impl<Block, PoolApi> BasicPoolWrapper<Block, PoolApi>
@@ -277,6 +410,8 @@ where
         prometheus: Option<&PrometheusRegistry>,
         spawner: Spawn,
         client: Arc<Client>,
+        removed_invalid_sink: UnboundedSender<(Block::Hash, BlockExtrinsicOf<Block>)>,
+        rejected_bundles_tracker: RejectedBundlesTracker<Block>,
     ) -> Self
     where
         Client: UsageProvider<Block>,
@@ -294,7 +429,11 @@ where
             client.usage_info().chain.finalized_hash,
         );
 
-        Self { inner: basic_pool }
+        Self {
+            inner: basic_pool,
+            removed_invalid_sink,
+            rejected_bundles_tracker,
+        }
     }
 
     /// Gets shared reference to the underlying pool.
@@ -305,6 +444,11 @@ where
     pub fn api(&self) -> &PoolApi {
         self.inner.api()
     }
+
+    /// Gets the bundles dropped by the block author that are pending to be reported.
+    pub fn rejected_bundles_tracker(&self) -> &RejectedBundlesTracker<Block> {
+        &self.rejected_bundles_tracker
+    }
 }
 
 impl<Block, Client, DomainHeader> LocalTransactionPool
@@ -415,7 +559,17 @@ where
     }
 
     fn remove_invalid(&self, hashes: &[TxHash<Self>]) -> Vec<Arc<Self::InPoolTransaction>> {
-        self.inner.remove_invalid(hashes)
+        let removed = self.inner.remove_invalid(hashes);
+        // The block author drops the transactions that fail to apply while building the block on
+        // top of the parent block, forward them to find out the rejected bundles among them.
+        if let Some(parent_hash) = self.rejected_bundles_tracker.building_on() {
+            for transaction in removed.iter() {
+                let _ = self
+                    .removed_invalid_sink
+                    .send((parent_hash, transaction.data.clone()));
+            }
+        }
+        removed
     }
 
     fn status(&self) -> PoolStatus {
@@ -454,6 +608,55 @@ where
     }
 }
 
+/// Returns the reason the bundle carried by `extrinsic` is rejected at block `at`, `None` if the
+/// extrinsic is not a bundle or the bundle is not rejected by the domains pallet.
+fn rejected_bundle_of<Client, Block, DomainHeader>(
+    client: &Client,
+    pool_api: &FullChainApiWrapper<Client, Block, DomainHeader>,
+    at: Block::Hash,
+    extrinsic: BlockExtrinsicOf<Block>,
+) -> Option<RejectedBundle>
+where
+    Block: BlockT,
+    Client: ProvideRuntimeApi<Block>
+        + BlockBackend<Block>
+        + BlockIdTo<Block>
+        + HeaderBackend<Block>
+        + HeaderMetadata<Block, Error = sp_blockchain::Error>
+        + Send
+        + Sync
+        + 'static,
+    DomainHeader: HeaderT,
+    Client::Api: TaggedTransactionQueue<Block>
+        + SubspaceApi<Block, FarmerPublicKey>
+        + DomainsApi<Block, DomainHeader>,
+{
+    let opaque_bundle = match client.runtime_api().extract_bundle(at, extrinsic.clone()) {
+        Ok(maybe_opaque_bundle) => maybe_opaque_bundle?,
+        Err(err) => {
+            error!(
+                target: "consensus-rejected-bundles-tracker",
+                "failed to extract bundle: {err:?}"
+            );
+            return None;
+        }
+    };
+
+    let Ok(Err(TransactionValidityError::Invalid(InvalidTransaction::Custom(code)))) =
+        pool_api.validate_transaction_blocking(at, TransactionSource::InBlock, extrinsic)
+    else {
+        return None;
+    };
+    let reason = InvalidTransactionCode::rejected_bundle_reason(code)?;
+
+    // The runtime verifies the operator signature of the bundle header instead of trusting the
+    // block author, so the signed header is reported along with the reason.
+    Some(RejectedBundle {
+        encoded_sealed_header: opaque_bundle.sealed_header.encode(),
+        reason,
+    })
+}
+
 pub fn new_full<Client, Block, DomainHeader>(
     transaction_pool_options: TransactionPoolOptions,
     is_authoring_blocks: bool,
@@ -473,6 +676,7 @@ where
         + ExecutorProvider<Block>
         + UsageProvider<Block>
         + BlockIdTo<Block>
+        + BlockchainEvents<Block>
         + Send
         + Sync
         + 'static,
@@ -483,6 +687,8 @@ where
         + DomainsApi<Block, DomainHeader>,
 {
     let (fraud_proof_submit_sink, mut fraud_proof_submit_stream) = mpsc::unbounded_channel();
+    let (removed_invalid_sink, mut removed_invalid_stream) = mpsc::unbounded_channel();
+    let rejected_bundles_tracker = RejectedBundlesTracker::default();
     let pool_api = Arc::new(FullChainApiWrapper::new(
         client.clone(),
         prometheus_registry,
@@ -494,12 +700,73 @@ where
     let basic_pool = Arc::new(BasicPoolWrapper::with_revalidation_type(
         transaction_pool_options,
         is_authoring_blocks,
-        pool_api,
+        pool_api.clone(),
         prometheus_registry,
         task_manager.spawn_essential_handle(),
         client.clone(),
+        removed_invalid_sink,
+        rejected_bundles_tracker.clone(),
     ));
 
+    // Run a separate task to find out the reason of the bundles dropped by the block author, the
+    // bundles are re-validated against the block they are dropped from once this node imports it
+    // and the `InvalidTransaction::Custom` code is reported as the reason.
+    task_manager
+        .spawn_essential_handle()
+        .spawn_essential_blocking("consensus-rejected-bundles-tracker", None, {
+            let client = client.clone();
+            Box::pin(async move {
+                let mut import_notification_stream = client.import_notification_stream();
+                // The transactions dropped while building the block on top of the parent block
+                let mut dropped_extrinsics: Option<(Block::Hash, Vec<BlockExtrinsicOf<Block>>)> =
+                    None;
+                loop {
+                    // The transactions are dropped before the block is imported, poll them first
+                    // so they are all received when the block import is noticed.
+                    futures::select_biased! {
+                        maybe_dropped = removed_invalid_stream.recv().fuse() => {
+                            let Some((parent_hash, extrinsic)) = maybe_dropped else {
+                                break;
+                            };
+                            match &mut dropped_extrinsics {
+                                Some((hash, extrinsics)) if *hash == parent_hash => {
+                                    extrinsics.push(extrinsic);
+                                }
+                                _ => dropped_extrinsics = Some((parent_hash, vec![extrinsic])),
+                            }
+                        }
+                        maybe_notification = import_notification_stream.next() => {
+                            let Some(notification) = maybe_notification else {
+                                break;
+                            };
+                            if notification.origin != BlockOrigin::Own {
+                                continue;
+                            }
+                            let parent_hash = *notification.header.parent_hash();
+                            rejected_bundles_tracker.on_own_block_imported(parent_hash);
+                            let extrinsics = match dropped_extrinsics.take() {
+                                Some((hash, extrinsics)) if hash == parent_hash => extrinsics,
+                                maybe_dropped => {
+                                    dropped_extrinsics = maybe_dropped;
+                                    continue;
+                                }
+                            };
+                            for extrinsic in extrinsics {
+                                if let Some(rejected_bundle) = rejected_bundle_of(
+                                    &*client,
+                                    &*pool_api,
+                                    notification.hash,
+                                    extrinsic,
+                                ) {
+                                    rejected_bundles_tracker.note_rejected_bundle(rejected_bundle);
+                                }
+                            }
+                        }
+                    }
+                }
+            })
+        });
+
     let offchain_tx_pool_factory = OffchainTransactionPoolFactory::new(basic_pool.clone());
 
     // run a separate task to submit fraud proof since chain api cannot depend on Basic pool since
@@ -179,6 +179,7 @@ where
         sp_block_fees::InherentDataProvider,
         sp_executive::InherentDataProvider,
         sp_messenger::InherentDataProvider,
+        sp_evm_tracker::InherentDataProvider,
     );
 
     async fn create_inherent_data_providers(
@@ -229,11 +230,31 @@ where
                 maybe_updates: domain_chains_allowlist_update,
             });
 
+        // TODO: remove version check before next network
+        let domains_api_version = runtime_api
+            .api_version::<dyn DomainsApi<CBlock, Block::Header>>(consensus_block_hash)?
+            // safe to return default version as 1 since there will always be version 1.
+            .unwrap_or(1);
+
+        let maybe_contract_creation_allow_list_update = if domains_api_version >= 4 {
+            runtime_api.evm_domain_contract_creation_allow_list_update(
+                consensus_block_hash,
+                self.domain_id,
+            )?
+        } else {
+            None
+        };
+        let evm_tracker_inherent_provider =
+            sp_evm_tracker::InherentDataProvider::new(sp_evm_tracker::InherentType {
+                maybe_contract_creation_allow_list_update,
+            });
+
         Ok((
             timestamp_provider,
             storage_price_provider,
             runtime_upgrade_provider,
             messenger_inherent_provider,
+            evm_tracker_inherent_provider,
         ))
     }
 }
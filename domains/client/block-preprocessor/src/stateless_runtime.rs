@@ -1,13 +1,15 @@
 use codec::{Codec, Encode};
 use domain_runtime_primitives::opaque::AccountId;
-use domain_runtime_primitives::{Balance, CheckExtrinsicsValidityError, DecodeExtrinsicError};
+use domain_runtime_primitives::{
+    Balance, CheckExtrinsicsValidityError, DecodeExtrinsicError, EthereumAccountId,
+};
 use sc_client_api::execution_extensions::ExtensionsFactory;
 use sc_executor::RuntimeVersionOf;
 use sp_api::{ApiError, Core};
 use sp_core::traits::{CallContext, CodeExecutor, FetchRuntimeCode, RuntimeCode};
 use sp_core::Hasher;
 use sp_domains::core_api::DomainCoreApi;
-use sp_domains::DomainAllowlistUpdates;
+use sp_domains::{DomainAllowlistUpdates, PermissionedActionAllowedBy};
 use sp_messenger::messages::MessageKey;
 use sp_messenger::MessengerApi;
 use sp_runtime::traits::{Block as BlockT, NumberFor};
@@ -230,6 +232,17 @@ where
         )
     }
 
+    pub fn construct_evm_contract_creation_allowed_by_extrinsic(
+        &self,
+        contract_creation_allowed_by: PermissionedActionAllowedBy<EthereumAccountId>,
+    ) -> Result<Option<Block::Extrinsic>, ApiError> {
+        <Self as DomainCoreApi<Block>>::construct_evm_contract_creation_allowed_by_extrinsic(
+            self,
+            Default::default(),
+            contract_creation_allowed_by,
+        )
+    }
+
     pub fn is_inherent_extrinsic(
         &self,
         extrinsic: &<Block as BlockT>::Extrinsic,
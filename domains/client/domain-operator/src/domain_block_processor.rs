@@ -12,7 +12,7 @@ use sc_consensus::{
     StorageChanges,
 };
 use sc_transaction_pool_api::OffchainTransactionPoolFactory;
-use sp_api::{ApiExt, ProvideRuntimeApi};
+use sp_api::{ApiError, ApiExt, ProvideRuntimeApi};
 use sp_blockchain::{HashAndNumber, HeaderBackend, HeaderMetadata};
 use sp_consensus::{BlockOrigin, SyncOracle};
 use sp_core::traits::CodeExecutor;
@@ -23,6 +23,7 @@ use sp_domains::{BundleValidity, DomainId, DomainsApi, ExecutionReceipt, HeaderH
 use sp_domains_fraud_proof::fraud_proof::{FraudProof, ValidBundleProof};
 use sp_domains_fraud_proof::FraudProofApi;
 use sp_messenger::MessengerApi;
+use sp_mmr_primitives::MmrApi;
 use sp_runtime::traits::{Block as BlockT, Header as HeaderT, NumberFor, One, Zero};
 use sp_runtime::{Digest, Saturating};
 use std::cmp::Ordering;
@@ -742,7 +743,9 @@ where
         + ProofProvider<CBlock>
         + ProvideRuntimeApi<CBlock>
         + 'static,
-    CClient::Api: DomainsApi<CBlock, Block::Header> + FraudProofApi<CBlock, Block::Header>,
+    CClient::Api: DomainsApi<CBlock, Block::Header>
+        + FraudProofApi<CBlock, Block::Header>
+        + MmrApi<CBlock, H256, NumberFor<CBlock>>,
     Backend: sc_client_api::Backend<Block> + 'static,
     E: CodeExecutor,
 {
@@ -758,6 +761,8 @@ where
         }
 
         if let Some(mismatched_receipts) = self.find_mismatch_receipt(consensus_block_hash)? {
+            let bad_receipt_consensus_number =
+                mismatched_receipts.bad_receipt.consensus_block_number;
             let fraud_proof = self.generate_fraud_proof(mismatched_receipts)?;
 
             tracing::info!("Submit fraud proof: {fraud_proof:?}");
@@ -767,6 +772,42 @@ where
                 self.consensus_offchain_tx_pool_factory
                     .offchain_transaction_pool(consensus_best_hash),
             );
+            let fraud_proof_api_version = runtime_api
+                .api_version::<dyn FraudProofApi<CBlock, Block::Header>>(consensus_best_hash)
+                .map_err(sp_blockchain::Error::RuntimeApiError)?
+                .ok_or_else(|| {
+                    sp_blockchain::Error::RuntimeApiError(ApiError::Application(
+                        format!("FraudProofApi not found at: {:?}", consensus_best_hash).into(),
+                    ))
+                })?;
+
+            // TODO: This is used to keep compatible with gemini-3h, remove before next network
+            if fraud_proof_api_version >= 2 {
+                // The consensus block hash of the bad receipt may be pruned from the consensus
+                // state before the fraud proof is verified, submit the MMR proof of the consensus
+                // block along with the fraud proof in case it is.
+                match self
+                    .fraud_proof_generator
+                    .generate_consensus_block_mmr_proof(
+                        bad_receipt_consensus_number,
+                        consensus_best_hash,
+                    ) {
+                    Ok(consensus_block_proof) => {
+                        runtime_api.submit_fraud_proof_with_consensus_block_proof_unsigned(
+                            consensus_best_hash,
+                            fraud_proof,
+                            consensus_block_proof,
+                        )?;
+                        return Ok(());
+                    }
+                    Err(err) => {
+                        tracing::warn!(
+                            ?err,
+                            "Failed to generate consensus block MMR proof for fraud proof"
+                        );
+                    }
+                }
+            }
             runtime_api.submit_fraud_proof_unsigned(consensus_best_hash, fraud_proof)?;
         }
 
@@ -33,6 +33,7 @@ use sp_domains::core_api::DomainCoreApi;
 use sp_domains::{BundleProducerElectionApi, DomainsApi, OpaqueBundle, OperatorId};
 use sp_domains_fraud_proof::FraudProofApi;
 use sp_messenger::MessengerApi;
+use sp_mmr_primitives::MmrApi;
 use sp_runtime::traits::{Block as BlockT, Header as HeaderT, NumberFor};
 use sp_transaction_pool::runtime_api::TaggedTransactionQueue;
 use std::pin::pin;
@@ -92,7 +93,8 @@ pub(super) async fn start_worker<
     CClient::Api: DomainsApi<CBlock, Block::Header>
         + MessengerApi<CBlock>
         + BundleProducerElectionApi<CBlock, Balance>
-        + FraudProofApi<CBlock, Block::Header>,
+        + FraudProofApi<CBlock, Block::Header>
+        + MmrApi<CBlock, H256, NumberFor<CBlock>>,
     TransactionPool: sc_transaction_pool_api::TransactionPool<Block = Block, Hash = <Block as BlockT>::Hash>
         + 'static,
     Backend: sc_client_api::Backend<Block> + 'static,
@@ -20,6 +20,8 @@ use sp_domains_fraud_proof::fraud_proof::{
     InvalidExtrinsicsRootProof, InvalidStateTransitionProof, InvalidTransfersProof,
     ValidBundleDigest,
 };
+use sp_messenger::messages::ConsensusChainMmrLeafProof;
+use sp_mmr_primitives::MmrApi;
 use sp_runtime::generic::BlockId;
 use sp_runtime::traits::{Block as BlockT, Header as HeaderT, NumberFor};
 use sp_runtime::{Digest, DigestItem};
@@ -68,6 +70,10 @@ pub enum FraudProofError {
         is_true_invalid: bool,
         extrinsics_validity_response: Result<(), CheckExtrinsicsValidityError>,
     },
+    #[error("Failed to generate the MMR proof of the consensus block: {0:?}")]
+    MmrProof(sp_mmr_primitives::Error),
+    #[error("Missing MMR leaf of the consensus block")]
+    MissingMmrLeaf,
 }
 
 pub struct FraudProofGenerator<Block, CBlock, Client, CClient, Backend, E> {
@@ -95,6 +101,38 @@ impl<Block, CBlock, Client, CClient, Backend, E> Clone
 type FraudProofFor<CBlock, DomainHeader> =
     FraudProof<NumberFor<CBlock>, <CBlock as BlockT>::Hash, DomainHeader>;
 
+impl<Block, CBlock, Client, CClient, Backend, E>
+    FraudProofGenerator<Block, CBlock, Client, CClient, Backend, E>
+where
+    Block: BlockT,
+    CBlock: BlockT,
+    CClient: ProvideRuntimeApi<CBlock> + 'static,
+    CClient::Api: MmrApi<CBlock, H256, NumberFor<CBlock>>,
+{
+    /// Generates the MMR proof of the consensus block `consensus_block_number` against the MMR at
+    /// the consensus block `at`, it is submitted along with the fraud proof such that the
+    /// consensus block of the bad receipt can be verified even if its hash is pruned from the
+    /// consensus state.
+    pub(crate) fn generate_consensus_block_mmr_proof(
+        &self,
+        consensus_block_number: NumberFor<CBlock>,
+        at: CBlock::Hash,
+    ) -> Result<ConsensusChainMmrLeafProof<CBlock::Hash, H256>, FraudProofError> {
+        let (mut leaves, proof) = self
+            .consensus_client
+            .runtime_api()
+            .generate_proof(at, vec![consensus_block_number], None)?
+            .map_err(FraudProofError::MmrProof)?;
+        let opaque_mmr_leaf = leaves.pop().ok_or(FraudProofError::MissingMmrLeaf)?;
+
+        Ok(ConsensusChainMmrLeafProof {
+            consensus_block_hash: at,
+            opaque_mmr_leaf,
+            proof,
+        })
+    }
+}
+
 impl<Block, CBlock, Client, CClient, Backend, E>
     FraudProofGenerator<Block, CBlock, Client, CClient, Backend, E>
 where
@@ -935,6 +935,7 @@ async fn test_bad_invalid_state_transition_proof_is_rejected() {
                 subspace_test_runtime::UncheckedExtrinsic::new_unsigned(
                     pallet_domains::Call::submit_fraud_proof {
                         fraud_proof: Box::new(fraud_proof.clone()),
+                        maybe_consensus_block_proof: None,
                     }
                     .into(),
                 )
@@ -996,6 +997,7 @@ async fn test_bad_invalid_state_transition_proof_is_rejected() {
                 subspace_test_runtime::UncheckedExtrinsic::new_unsigned(
                     pallet_domains::Call::submit_fraud_proof {
                         fraud_proof: Box::new(fraud_proof),
+                        maybe_consensus_block_proof: None,
                     }
                     .into(),
                 )
@@ -2685,6 +2687,7 @@ async fn test_valid_bundle_proof_generation_and_verification() {
         subspace_test_runtime::UncheckedExtrinsic::new_unsigned(
             pallet_domains::Call::submit_fraud_proof {
                 fraud_proof: Box::new(FraudProof::ValidBundle(proof)),
+                maybe_consensus_block_proof: None,
             }
             .into(),
         )
@@ -2737,7 +2740,7 @@ async fn test_valid_bundle_proof_generation_and_verification() {
         )
         .unwrap();
         if let subspace_test_runtime::RuntimeCall::Domains(
-            pallet_domains::Call::submit_fraud_proof { fraud_proof },
+            pallet_domains::Call::submit_fraud_proof { fraud_proof, .. },
         ) = ext.function
         {
             if let FraudProof::ValidBundle(proof) = *fraud_proof {
@@ -2997,7 +3000,7 @@ async fn duplicated_bundle_should_be_rejected() {
         .unwrap_err()
     {
         sc_transaction_pool::error::Error::Pool(TxPoolError::InvalidTransaction(invalid_tx)) => {
-            assert_eq!(invalid_tx, InvalidTransactionCode::Bundle.into())
+            assert_eq!(invalid_tx, InvalidTransactionCode::DuplicatedBundle.into())
         }
         e => panic!("Unexpected error: {e}"),
     }
@@ -3083,7 +3086,10 @@ async fn stale_and_in_future_bundle_should_be_rejected() {
         .unwrap_err()
     {
         sc_transaction_pool::error::Error::Pool(TxPoolError::InvalidTransaction(invalid_tx)) => {
-            assert_eq!(invalid_tx, InvalidTransactionCode::Bundle.into())
+            assert_eq!(
+                invalid_tx,
+                InvalidTransactionCode::BundleSlotInThePast.into()
+            )
         }
         e => panic!("Unexpected error: {e}"),
     }
@@ -3139,7 +3145,7 @@ async fn stale_and_in_future_bundle_should_be_rejected() {
             sc_transaction_pool::error::Error::Pool(TxPoolError::InvalidTransaction(
                 invalid_tx,
             )) => {
-                assert_eq!(invalid_tx, InvalidTransactionCode::Bundle.into())
+                assert_eq!(invalid_tx, InvalidTransactionCode::BundleProofOfTime.into())
             }
             e => panic!("Unexpected error: {e}"),
         }
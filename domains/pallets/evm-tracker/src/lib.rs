@@ -0,0 +1,143 @@
+// Copyright (C) 2023 Subspace Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Pallet EVM Tracker
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(test)]
+mod tests;
+
+pub use pallet::*;
+
+#[frame_support::pallet]
+mod pallet {
+    use domain_runtime_primitives::EthereumAccountId;
+    use frame_support::pallet_prelude::*;
+    use frame_system::pallet_prelude::*;
+    use sp_domains::PermissionedActionAllowedBy;
+    use sp_evm_tracker::{InherentError, InherentType, INHERENT_IDENTIFIER};
+
+    #[pallet::config]
+    pub trait Config: frame_system::Config {}
+
+    /// The accounts allowed to create contracts, `None` means anyone can create contracts.
+    ///
+    /// The initial value is set during domain instantiation and the later updates are delivered
+    /// from the consensus chain through inherent.
+    #[pallet::storage]
+    pub(super) type ContractCreationAllowedBy<T> =
+        StorageValue<_, PermissionedActionAllowedBy<EthereumAccountId>, OptionQuery>;
+
+    /// Pallet evm-tracker to store the contract creation allow list of the EVM domain.
+    #[pallet::pallet]
+    #[pallet::without_storage_info]
+    pub struct Pallet<T>(_);
+
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        #[pallet::call_index(0)]
+        #[pallet::weight((T::DbWeight::get().writes(1), DispatchClass::Mandatory))]
+        pub fn set_contract_creation_allowed_by(
+            origin: OriginFor<T>,
+            contract_creation_allowed_by: PermissionedActionAllowedBy<EthereumAccountId>,
+        ) -> DispatchResult {
+            ensure_none(origin)?;
+            ContractCreationAllowedBy::<T>::put(contract_creation_allowed_by);
+            Ok(())
+        }
+    }
+
+    #[pallet::inherent]
+    impl<T: Config> ProvideInherent for Pallet<T> {
+        type Call = Call<T>;
+        type Error = InherentError;
+        const INHERENT_IDENTIFIER: InherentIdentifier = INHERENT_IDENTIFIER;
+
+        fn create_inherent(data: &InherentData) -> Option<Self::Call> {
+            let inherent_data = data
+                .get_data::<InherentType>(&INHERENT_IDENTIFIER)
+                .expect("EVM tracker inherent data not correctly encoded")
+                .expect("EVM tracker inherent data must be provided");
+
+            inherent_data.maybe_contract_creation_allow_list_update.map(
+                |contract_creation_allowed_by| Call::set_contract_creation_allowed_by {
+                    contract_creation_allowed_by,
+                },
+            )
+        }
+
+        fn is_inherent_required(data: &InherentData) -> Result<Option<Self::Error>, Self::Error> {
+            let inherent_data = data
+                .get_data::<InherentType>(&INHERENT_IDENTIFIER)
+                .expect("EVM tracker inherent data not correctly encoded")
+                .expect("EVM tracker inherent data must be provided");
+
+            Ok(
+                if inherent_data
+                    .maybe_contract_creation_allow_list_update
+                    .is_none()
+                {
+                    None
+                } else {
+                    Some(InherentError::MissingContractCreationAllowListUpdate)
+                },
+            )
+        }
+
+        fn check_inherent(call: &Self::Call, data: &InherentData) -> Result<(), Self::Error> {
+            let inherent_data = data
+                .get_data::<InherentType>(&INHERENT_IDENTIFIER)
+                .expect("EVM tracker inherent data not correctly encoded")
+                .expect("EVM tracker inherent data must be provided");
+
+            if let Some(provided_allowed_by) =
+                inherent_data.maybe_contract_creation_allow_list_update
+            {
+                if let Call::set_contract_creation_allowed_by {
+                    contract_creation_allowed_by,
+                } = call
+                {
+                    if contract_creation_allowed_by != &provided_allowed_by {
+                        return Err(InherentError::IncorrectContractCreationAllowListUpdate);
+                    }
+                }
+            } else {
+                return Err(InherentError::MissingContractCreationAllowListUpdate);
+            }
+
+            Ok(())
+        }
+
+        fn is_inherent(call: &Self::Call) -> bool {
+            matches!(call, Call::set_contract_creation_allowed_by { .. })
+        }
+    }
+
+    impl<T: Config> Pallet<T> {
+        /// Returns the current contract creation allow list, `None` means anyone can create
+        /// contracts.
+        pub fn contract_creation_allowed_by(
+        ) -> Option<PermissionedActionAllowedBy<EthereumAccountId>> {
+            ContractCreationAllowedBy::<T>::get()
+        }
+
+        /// Returns true if the given account is allowed to create contracts.
+        pub fn is_allowed_to_create_contracts(signer: &EthereumAccountId) -> bool {
+            ContractCreationAllowedBy::<T>::get()
+                .map_or(true, |allowed_by| allowed_by.is_allowed(signer))
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use crate::{self as pallet_evm_tracker};
+use domain_runtime_primitives::EthereumAccountId;
+use frame_support::derive_impl;
+use sp_domains::PermissionedActionAllowedBy;
+use sp_runtime::BuildStorage;
+
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+    pub struct Test {
+        System: frame_system = 0,
+        EVMTracker: pallet_evm_tracker = 1,
+    }
+);
+
+impl pallet_evm_tracker::Config for Test {}
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig as frame_system::DefaultConfig)]
+impl frame_system::Config for Test {
+    type Block = Block;
+}
+
+pub(crate) fn new_test_ext() -> sp_io::TestExternalities {
+    let t = frame_system::GenesisConfig::<Test>::default()
+        .build_storage()
+        .unwrap();
+
+    t.into()
+}
+
+#[test]
+fn test_contract_creation_allowed_by_storage_key() {
+    new_test_ext().execute_with(|| {
+        assert_eq!(
+            pallet_evm_tracker::ContractCreationAllowedBy::<Test>::hashed_key().to_vec(),
+            sp_domains::evm_contract_creation_allowed_by_storage_key().0
+        );
+    });
+}
+
+#[test]
+fn test_contract_creation_allow_list() {
+    new_test_ext().execute_with(|| {
+        let allowed = EthereumAccountId::from([1u8; 20]);
+        let not_allowed = EthereumAccountId::from([2u8; 20]);
+
+        // Anyone is allowed before the allow list is set
+        assert!(EVMTracker::is_allowed_to_create_contracts(&not_allowed));
+
+        EVMTracker::set_contract_creation_allowed_by(
+            RuntimeOrigin::none(),
+            PermissionedActionAllowedBy::Accounts(vec![allowed]),
+        )
+        .unwrap();
+        assert!(EVMTracker::is_allowed_to_create_contracts(&allowed));
+        assert!(!EVMTracker::is_allowed_to_create_contracts(&not_allowed));
+
+        EVMTracker::set_contract_creation_allowed_by(
+            RuntimeOrigin::none(),
+            PermissionedActionAllowedBy::Anyone,
+        )
+        .unwrap();
+        assert!(EVMTracker::is_allowed_to_create_contracts(&not_allowed));
+    });
+}
@@ -0,0 +1,76 @@
+//! Inherents for evm-tracker pallet
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode};
+use domain_runtime_primitives::EthereumAccountId;
+use sp_domains::PermissionedActionAllowedBy;
+use sp_inherents::{Error, InherentData, InherentIdentifier, IsFatalError};
+
+/// EVM tracker inherent identifier.
+pub const INHERENT_IDENTIFIER: InherentIdentifier = *b"evmtrack";
+
+#[derive(Debug, Encode)]
+#[cfg_attr(feature = "std", derive(Decode))]
+pub enum InherentError {
+    MissingContractCreationAllowListUpdate,
+    IncorrectContractCreationAllowListUpdate,
+}
+
+impl IsFatalError for InherentError {
+    fn is_fatal_error(&self) -> bool {
+        true
+    }
+}
+
+/// The type of the inherent.
+#[derive(Debug, Encode, Decode)]
+pub struct InherentType {
+    /// The contract creation allow list updated on the consensus chain since the last domain
+    /// block, if any.
+    pub maybe_contract_creation_allow_list_update:
+        Option<PermissionedActionAllowedBy<EthereumAccountId>>,
+}
+
+/// Provides the contract creation allow list inherent data.
+#[cfg(feature = "std")]
+pub struct InherentDataProvider {
+    data: InherentType,
+}
+
+#[cfg(feature = "std")]
+impl InherentDataProvider {
+    /// Create new inherent data provider from the given `data`.
+    pub fn new(data: InherentType) -> Self {
+        Self { data }
+    }
+
+    /// Returns the `data` of this inherent data provider.
+    pub fn data(&self) -> &InherentType {
+        &self.data
+    }
+}
+
+#[cfg(feature = "std")]
+#[async_trait::async_trait]
+impl sp_inherents::InherentDataProvider for InherentDataProvider {
+    async fn provide_inherent_data(
+        &self,
+        inherent_data: &mut InherentData,
+    ) -> Result<(), sp_inherents::Error> {
+        inherent_data.put_data(INHERENT_IDENTIFIER, &self.data)
+    }
+
+    async fn try_handle_error(
+        &self,
+        identifier: &InherentIdentifier,
+        error: &[u8],
+    ) -> Option<Result<(), sp_inherents::Error>> {
+        if *identifier != INHERENT_IDENTIFIER {
+            return None;
+        }
+
+        let error = InherentError::decode(&mut &*error).ok()?;
+
+        Some(Err(Error::Application(Box::from(format!("{error:?}")))))
+    }
+}
@@ -66,6 +66,13 @@ pub const SLOT_DURATION: u64 = 1000;
 /// The EVM chain Id type
 pub type EVMChainId = u64;
 
+/// Account id type used by EVM domains.
+pub type EthereumAccountId = AccountId20;
+
+/// Custom error code returned when the signer of an EVM domain transaction is not allowed to
+/// create contracts.
+pub const ERR_CONTRACT_CREATION_NOT_ALLOWED: u8 = 1;
+
 /// Maximum block length for mandatory dispatch.
 pub const MAXIMUM_MANDATORY_BLOCK_LENGTH: u32 = 5 * 1024 * 1024;
 
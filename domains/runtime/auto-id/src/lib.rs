@@ -20,8 +20,8 @@ pub use domain_runtime_primitives::{
     EXISTENTIAL_DEPOSIT, MAXIMUM_BLOCK_WEIGHT,
 };
 use domain_runtime_primitives::{
-    AccountId, Address, CheckExtrinsicsValidityError, DecodeExtrinsicError, Signature,
-    SLOT_DURATION,
+    AccountId, Address, CheckExtrinsicsValidityError, DecodeExtrinsicError, EthereumAccountId,
+    Signature, SLOT_DURATION,
 };
 use frame_support::dispatch::{DispatchClass, DispatchInfo, GetDispatchInfo};
 use frame_support::inherent::ProvideInherent;
@@ -39,7 +39,10 @@ use pallet_transporter::EndpointHandler;
 use sp_api::impl_runtime_apis;
 use sp_core::crypto::KeyTypeId;
 use sp_core::{Get, OpaqueMetadata};
-use sp_domains::{ChannelId, DomainAllowlistUpdates, DomainId, MessengerHoldIdentifier, Transfers};
+use sp_domains::{
+    ChannelId, DomainAllowlistUpdates, DomainId, MessengerHoldIdentifier,
+    PermissionedActionAllowedBy, Transfers,
+};
 use sp_messenger::endpoint::{Endpoint, EndpointHandler as EndpointHandlerT, EndpointId};
 use sp_messenger::messages::{
     BlockMessagesWithStorageKey, ChainId, CrossDomainMessage, MessageId, MessageKey,
@@ -780,6 +783,10 @@ impl_runtime_apis! {
             )
         }
 
+        fn construct_evm_contract_creation_allowed_by_extrinsic(_contract_creation_allowed_by: PermissionedActionAllowedBy<EthereumAccountId>) -> Option<<Block as BlockT>::Extrinsic> {
+            None
+        }
+
         fn transfers() -> Transfers<Balance> {
             Transporter::chain_transfers()
         }
@@ -22,7 +22,8 @@ pub use domain_runtime_primitives::{
     EXISTENTIAL_DEPOSIT, MAXIMUM_BLOCK_WEIGHT,
 };
 use domain_runtime_primitives::{
-    CheckExtrinsicsValidityError, DecodeExtrinsicError, SLOT_DURATION,
+    CheckExtrinsicsValidityError, DecodeExtrinsicError, EthereumAccountId,
+    ERR_CONTRACT_CREATION_NOT_ALLOWED, SLOT_DURATION,
 };
 use fp_account::EthereumSignature;
 use fp_self_contained::{CheckedSignature, SelfContainedCall};
@@ -40,7 +41,9 @@ use frame_support::{construct_runtime, parameter_types};
 use frame_system::limits::{BlockLength, BlockWeights};
 use pallet_block_fees::fees::OnChargeDomainTransaction;
 use pallet_ethereum::Call::transact;
-use pallet_ethereum::{PostLogContent, Transaction as EthereumTransaction, TransactionStatus};
+use pallet_ethereum::{
+    PostLogContent, Transaction as EthereumTransaction, TransactionAction, TransactionStatus,
+};
 use pallet_evm::{
     Account as EVMAccount, EnsureAddressNever, EnsureAddressRoot, FeeCalculator,
     IdentityAddressMapping, Runner,
@@ -49,7 +52,10 @@ use pallet_transporter::EndpointHandler;
 use sp_api::impl_runtime_apis;
 use sp_core::crypto::KeyTypeId;
 use sp_core::{Get, OpaqueMetadata, H160, H256, U256};
-use sp_domains::{ChannelId, DomainAllowlistUpdates, DomainId, MessengerHoldIdentifier, Transfers};
+use sp_domains::{
+    ChannelId, DomainAllowlistUpdates, DomainId, MessengerHoldIdentifier,
+    PermissionedActionAllowedBy, Transfers,
+};
 use sp_messenger::endpoint::{Endpoint, EndpointHandler as EndpointHandlerT, EndpointId};
 use sp_messenger::messages::{
     BlockMessagesWithStorageKey, ChainId, CrossDomainMessage, MessageId, MessageKey,
@@ -129,6 +135,21 @@ pub type Executive = domain_pallet_executive::Executive<
     AllPalletsWithSystem,
 >;
 
+/// Returns `true` if the given ethereum call creates a contract while the signer is not allowed
+/// to create contracts.
+fn is_contract_creation_disallowed(call: &pallet_ethereum::Call<Runtime>, signer: &H160) -> bool {
+    let pallet_ethereum::Call::transact { transaction } = call else {
+        return false;
+    };
+    let action = match transaction {
+        EthereumTransaction::Legacy(transaction) => &transaction.action,
+        EthereumTransaction::EIP2930(transaction) => &transaction.action,
+        EthereumTransaction::EIP1559(transaction) => &transaction.action,
+    };
+    matches!(action, TransactionAction::Create)
+        && !EVMTracker::is_allowed_to_create_contracts(&EthereumAccountId::from(*signer))
+}
+
 impl fp_self_contained::SelfContainedCall for RuntimeCall {
     type SignedInfo = H160;
 
@@ -154,6 +175,13 @@ impl fp_self_contained::SelfContainedCall for RuntimeCall {
     ) -> Option<TransactionValidity> {
         match self {
             RuntimeCall::Ethereum(call) => {
+                if is_contract_creation_disallowed(call, info) {
+                    return Some(Err(InvalidTransaction::Custom(
+                        ERR_CONTRACT_CREATION_NOT_ALLOWED,
+                    )
+                    .into()));
+                }
+
                 // Ensure the caller can pay for the consensus chain storage fee
                 let consensus_storage_fee =
                     BlockFees::consensus_chain_byte_fee() * Balance::from(len as u32);
@@ -178,6 +206,13 @@ impl fp_self_contained::SelfContainedCall for RuntimeCall {
     ) -> Option<Result<(), TransactionValidityError>> {
         match self {
             RuntimeCall::Ethereum(call) => {
+                if is_contract_creation_disallowed(call, info) {
+                    return Some(Err(InvalidTransaction::Custom(
+                        ERR_CONTRACT_CREATION_NOT_ALLOWED,
+                    )
+                    .into()));
+                }
+
                 // Withdraw the consensus chain storage fee from the caller and record
                 // it in the `BlockFees`
                 let consensus_storage_fee =
@@ -672,6 +707,8 @@ impl pallet_base_fee::Config for Runtime {
 
 impl pallet_domain_id::Config for Runtime {}
 
+impl pallet_evm_tracker::Config for Runtime {}
+
 // Create the runtime by composing the FRAME pallets that were previously configured.
 //
 // NOTE: Currently domain runtime does not naturally support the pallets with inherent extrinsics.
@@ -703,6 +740,7 @@ construct_runtime!(
         // domain instance stuff
         SelfDomainId: pallet_domain_id = 90,
         BlockFees: pallet_block_fees = 91,
+        EVMTracker: pallet_evm_tracker = 92,
 
         // Sudo account
         Sudo: pallet_sudo = 100,
@@ -1012,6 +1050,7 @@ impl_runtime_apis! {
                 RuntimeCall::Timestamp(call) => Timestamp::is_inherent(call),
                 RuntimeCall::ExecutivePallet(call) => ExecutivePallet::is_inherent(call),
                 RuntimeCall::Messenger(call) => Messenger::is_inherent(call),
+                RuntimeCall::EVMTracker(call) => EVMTracker::is_inherent(call),
                 _ => false,
             }
         }
@@ -1079,6 +1118,12 @@ impl_runtime_apis! {
             )
         }
 
+        fn construct_evm_contract_creation_allowed_by_extrinsic(contract_creation_allowed_by: PermissionedActionAllowedBy<EthereumAccountId>) -> Option<<Block as BlockT>::Extrinsic> {
+            Some(UncheckedExtrinsic::new_unsigned(
+                pallet_evm_tracker::Call::set_contract_creation_allowed_by{ contract_creation_allowed_by }.into()
+            ))
+        }
+
         fn transfers() -> Transfers<Balance> {
             Transporter::chain_transfers()
         }
@@ -18,7 +18,8 @@ use codec::{Decode, Encode, MaxEncodedLen};
 use core::mem;
 pub use domain_runtime_primitives::opaque::Header;
 use domain_runtime_primitives::{
-    block_weights, maximum_block_length, EXISTENTIAL_DEPOSIT, MAXIMUM_BLOCK_WEIGHT, SLOT_DURATION,
+    block_weights, maximum_block_length, EthereumAccountId, ERR_CONTRACT_CREATION_NOT_ALLOWED,
+    EXISTENTIAL_DEPOSIT, MAXIMUM_BLOCK_WEIGHT, SLOT_DURATION,
 };
 pub use domain_runtime_primitives::{
     opaque, Balance, BlockNumber, CheckExtrinsicsValidityError, DecodeExtrinsicError, Hash, Nonce,
@@ -38,7 +39,9 @@ use frame_support::{construct_runtime, parameter_types};
 use frame_system::limits::{BlockLength, BlockWeights};
 use pallet_block_fees::fees::OnChargeDomainTransaction;
 use pallet_ethereum::Call::transact;
-use pallet_ethereum::{PostLogContent, Transaction as EthereumTransaction, TransactionStatus};
+use pallet_ethereum::{
+    PostLogContent, Transaction as EthereumTransaction, TransactionAction, TransactionStatus,
+};
 use pallet_evm::{
     Account as EVMAccount, EnsureAddressNever, EnsureAddressRoot, FeeCalculator,
     IdentityAddressMapping, Runner,
@@ -47,7 +50,10 @@ use pallet_transporter::EndpointHandler;
 use sp_api::impl_runtime_apis;
 use sp_core::crypto::KeyTypeId;
 use sp_core::{Get, OpaqueMetadata, H160, H256, U256};
-use sp_domains::{DomainAllowlistUpdates, DomainId, MessengerHoldIdentifier, Transfers};
+use sp_domains::{
+    DomainAllowlistUpdates, DomainId, MessengerHoldIdentifier, PermissionedActionAllowedBy,
+    Transfers,
+};
 use sp_messenger::endpoint::{Endpoint, EndpointHandler as EndpointHandlerT, EndpointId};
 use sp_messenger::messages::{
     BlockMessagesWithStorageKey, ChainId, ChannelId, CrossDomainMessage, MessageId, MessageKey,
@@ -126,6 +132,21 @@ pub type Executive = domain_pallet_executive::Executive<
     AllPalletsWithSystem,
 >;
 
+/// Returns `true` if the given ethereum call creates a contract while the signer is not allowed
+/// to create contracts.
+fn is_contract_creation_disallowed(call: &pallet_ethereum::Call<Runtime>, signer: &H160) -> bool {
+    let pallet_ethereum::Call::transact { transaction } = call else {
+        return false;
+    };
+    let action = match transaction {
+        EthereumTransaction::Legacy(transaction) => &transaction.action,
+        EthereumTransaction::EIP2930(transaction) => &transaction.action,
+        EthereumTransaction::EIP1559(transaction) => &transaction.action,
+    };
+    matches!(action, TransactionAction::Create)
+        && !EVMTracker::is_allowed_to_create_contracts(&EthereumAccountId::from(*signer))
+}
+
 impl fp_self_contained::SelfContainedCall for RuntimeCall {
     type SignedInfo = H160;
 
@@ -151,6 +172,13 @@ impl fp_self_contained::SelfContainedCall for RuntimeCall {
     ) -> Option<TransactionValidity> {
         match self {
             RuntimeCall::Ethereum(call) => {
+                if is_contract_creation_disallowed(call, info) {
+                    return Some(Err(InvalidTransaction::Custom(
+                        ERR_CONTRACT_CREATION_NOT_ALLOWED,
+                    )
+                    .into()));
+                }
+
                 // Ensure the caller can pay the consensus chain storage fee
                 let consensus_storage_fee =
                     BlockFees::consensus_chain_byte_fee() * Balance::from(len as u32);
@@ -175,6 +203,13 @@ impl fp_self_contained::SelfContainedCall for RuntimeCall {
     ) -> Option<Result<(), TransactionValidityError>> {
         match self {
             RuntimeCall::Ethereum(call) => {
+                if is_contract_creation_disallowed(call, info) {
+                    return Some(Err(InvalidTransaction::Custom(
+                        ERR_CONTRACT_CREATION_NOT_ALLOWED,
+                    )
+                    .into()));
+                }
+
                 // Withdraw the consensus chain storage fee from the caller and record
                 // it in the `BlockFees`
                 let consensus_storage_fee =
@@ -656,6 +691,8 @@ impl pallet_base_fee::Config for Runtime {
 
 impl pallet_domain_id::Config for Runtime {}
 
+impl pallet_evm_tracker::Config for Runtime {}
+
 // Create the runtime by composing the FRAME pallets that were previously configured.
 //
 // NOTE: Currently domain runtime does not naturally support the pallets with inherent extrinsics.
@@ -684,6 +721,7 @@ construct_runtime!(
         // domain instance stuff
         SelfDomainId: pallet_domain_id = 90,
         BlockFees: pallet_block_fees = 91,
+        EVMTracker: pallet_evm_tracker = 92,
 
         // Sudo account
         Sudo: pallet_sudo = 100,
@@ -977,11 +1015,18 @@ impl_runtime_apis! {
             )
         }
 
+        fn construct_evm_contract_creation_allowed_by_extrinsic(contract_creation_allowed_by: PermissionedActionAllowedBy<EthereumAccountId>) -> Option<<Block as BlockT>::Extrinsic> {
+            Some(UncheckedExtrinsic::new_unsigned(
+                pallet_evm_tracker::Call::set_contract_creation_allowed_by{ contract_creation_allowed_by }.into()
+            ))
+        }
+
         fn is_inherent_extrinsic(extrinsic: &<Block as BlockT>::Extrinsic) -> bool {
             match &extrinsic.0.function {
                 RuntimeCall::Timestamp(call) => Timestamp::is_inherent(call),
                 RuntimeCall::ExecutivePallet(call) => ExecutivePallet::is_inherent(call),
                 RuntimeCall::Messenger(call) => Messenger::is_inherent(call),
+                RuntimeCall::EVMTracker(call) => EVMTracker::is_inherent(call),
                 _ => false,
             }
         }
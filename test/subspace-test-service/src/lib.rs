@@ -804,7 +804,7 @@ impl MockConsensusNode {
                 )
                 .expect("Decode tx must success");
                 if let subspace_test_runtime::RuntimeCall::Domains(
-                    pallet_domains::Call::submit_fraud_proof { fraud_proof },
+                    pallet_domains::Call::submit_fraud_proof { fraud_proof, .. },
                 ) = ext.function
                 {
                     if fraud_proof_predict(&fraud_proof) {